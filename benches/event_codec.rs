@@ -0,0 +1,70 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use PerpInfra::event_log::codec::EventCodec;
+use PerpInfra::events::base::{BaseEvent, EventPayload, EventType};
+use PerpInfra::events::order::Side;
+use PerpInfra::events::trade::{Fee, TradeEvent};
+use PerpInfra::types::balance::Balance;
+use PerpInfra::types::ids::{MarketId, OrderId, TradeId, UserId};
+use PerpInfra::types::position::PositionSide;
+use PerpInfra::types::price::Price;
+use PerpInfra::types::quantity::Quantity;
+use PerpInfra::types::ratio::Ratio;
+
+/// Trade events dominate the consumer under load, so benchmark those
+fn trade_event() -> BaseEvent {
+    let market_id = MarketId::new();
+    let trade = TradeEvent {
+        base: BaseEvent::new(EventType::Trade, market_id),
+        trade_id: TradeId::new(),
+        maker_order_id: OrderId::new(),
+        taker_order_id: OrderId::new(),
+        maker_user_id: UserId::new(),
+        taker_user_id: UserId::new(),
+        price: Price::from_f64(50_000.0),
+        quantity: Quantity::from_f64(0.25),
+        maker_side: Side::Buy,
+        maker_fee: Fee { amount: Balance::from_f64(2.5), rate: Ratio::from_f64(0.0002) },
+        taker_fee: Fee { amount: Balance::from_f64(6.25), rate: Ratio::from_f64(0.0005) },
+        liquidation: false,
+        maker_position_side: PositionSide::Both,
+        taker_position_side: PositionSide::Both,
+    };
+
+    let mut event = BaseEvent::with_payload(
+        EventType::Trade,
+        market_id,
+        EventPayload::Trade(Box::new(trade)),
+    );
+    event.sequence = 42;
+    event
+}
+
+fn decode_benchmarks(c: &mut Criterion) {
+    let event = trade_event();
+    let bincode_bytes = bincode::serialize(&event).unwrap();
+    let binary_frame = EventCodec::Binary.encode(&event).unwrap();
+    let json_frame = EventCodec::Json.encode(&event).unwrap();
+
+    let mut group = c.benchmark_group("trade_event_decode");
+
+    group.bench_function("bincode", |b| {
+        b.iter(|| bincode::deserialize::<BaseEvent>(black_box(&bincode_bytes)).unwrap())
+    });
+
+    group.bench_function("binary_full_decode", |b| {
+        b.iter(|| EventCodec::decode(black_box(&binary_frame)).unwrap())
+    });
+
+    group.bench_function("binary_view_sequence", |b| {
+        b.iter(|| EventCodec::with_view(black_box(&binary_frame), |e| e.sequence).unwrap())
+    });
+
+    group.bench_function("json", |b| {
+        b.iter(|| EventCodec::decode(black_box(&json_frame)).unwrap())
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, decode_benchmarks);
+criterion_main!(benches);
@@ -0,0 +1,126 @@
+//! Order book hot-path latency: add, cancel and match
+//!
+//! Compare against the previous book layout with criterion baselines:
+//! `cargo bench --bench order_book -- --save-baseline before` on the old
+//! revision, then `cargo bench --bench order_book -- --baseline before`.
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use PerpInfra::config::fees::FeeConfig;
+use PerpInfra::events::order::{OrderType, Side, TimeInForce};
+use PerpInfra::matching::matcher::Matcher;
+use PerpInfra::matching::order_book::{Order, OrderBook};
+use PerpInfra::settlement::position_manager::PositionManager;
+use PerpInfra::types::ids::{MarketId, OrderId, UserId};
+use PerpInfra::types::position::PositionSide;
+use PerpInfra::types::price::Price;
+use PerpInfra::types::quantity::Quantity;
+use PerpInfra::types::timestamp::Timestamp;
+
+/// Resting orders per side, spread over LEVELS price levels
+const RESTING: usize = 10_000;
+const LEVELS: usize = 100;
+
+fn order(side: Side, price: f64, quantity: f64, time_in_force: TimeInForce) -> Order {
+    Order {
+        order_id: OrderId::new(),
+        user_id: UserId::new(),
+        side,
+        order_type: OrderType::Limit,
+        price: Price::from_f64(price),
+        quantity: Quantity::from_f64(quantity),
+        filled: Quantity::zero(),
+        timestamp: Timestamp::now(),
+        time_in_force,
+        reduce_only: false,
+        post_only: false,
+        slippage_limit: None,
+        self_trade_prevention: None,
+        position_side: PositionSide::Both,
+        min_fill_quantity: None,
+    }
+}
+
+/// Asks from 50_001 upwards, RESTING / LEVELS orders per level
+fn resting_asks() -> Vec<Order> {
+    (0..RESTING)
+        .map(|i| order(Side::Sell, 50_001.0 + (i % LEVELS) as f64, 1.0, TimeInForce::GTC))
+        .collect()
+}
+
+fn book_with(orders: &[Order]) -> OrderBook {
+    let mut book = OrderBook::new();
+    for order in orders {
+        book.add_order(order.clone()).unwrap();
+    }
+    book
+}
+
+fn add_benchmarks(c: &mut Criterion) {
+    let orders = resting_asks();
+    let mut group = c.benchmark_group("order_book_add");
+
+    group.bench_function("add_10k", |b| {
+        b.iter_batched(
+            || orders.clone(),
+            |orders| {
+                let mut book = OrderBook::new();
+                for order in orders {
+                    book.add_order(order).unwrap();
+                }
+                black_box(book)
+            },
+            BatchSize::LargeInput,
+        )
+    });
+
+    group.finish();
+}
+
+fn cancel_benchmarks(c: &mut Criterion) {
+    let orders = resting_asks();
+    let mut group = c.benchmark_group("order_book_cancel");
+
+    // Cancels from the middle of deep levels: the old layout scanned the queue
+    let cancelled: Vec<OrderId> = orders.iter().skip(RESTING / 2).take(1_000).map(|o| o.order_id).collect();
+    group.bench_function("cancel_1k_mid_queue", |b| {
+        b.iter_batched(
+            || book_with(&orders),
+            |mut book| {
+                for order_id in &cancelled {
+                    black_box(book.remove_order(order_id).unwrap());
+                }
+                book
+            },
+            BatchSize::LargeInput,
+        )
+    });
+
+    group.finish();
+}
+
+fn match_benchmarks(c: &mut Criterion) {
+    let orders = resting_asks();
+    let market_id = MarketId::new();
+    let positions = PositionManager::new_with_market(market_id);
+    let mut group = c.benchmark_group("order_book_match");
+
+    // IOC taker sweeping 500 resting orders over 5 levels
+    let taker = order(Side::Buy, 50_005.0, 500.0, TimeInForce::IOC);
+    group.bench_function("sweep_500_makers", |b| {
+        b.iter_batched(
+            || Matcher::new(book_with(&orders), FeeConfig::default(), market_id),
+            |mut matcher| {
+                let trades = matcher
+                    .match_order(black_box(&taker), &positions, Price::zero())
+                    .unwrap();
+                black_box(trades);
+                matcher
+            },
+            BatchSize::LargeInput,
+        )
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, add_benchmarks, cancel_benchmarks, match_benchmarks);
+criterion_main!(benches);
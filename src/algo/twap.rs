@@ -0,0 +1,237 @@
+use std::collections::HashMap;
+use std::time::Duration;
+use serde::Serialize;
+use crate::error::{Error, Result};
+use crate::events::base::{BaseEvent, CorrelationId, EventType};
+use crate::events::order::{OrderSubmit, OrderType, Side, TimeInForce};
+use crate::observability::metrics::TWAP_SLICES_SUBMITTED;
+use crate::types::ids::{MarketId, OrderId, UserId};
+use crate::types::position::PositionSide;
+use crate::types::price::Price;
+use crate::types::quantity::Quantity;
+use crate::types::ratio::Ratio;
+use crate::types::timestamp::Timestamp;
+
+/// Shortest slice interval accepted
+pub const MIN_SLICE_INTERVAL: Duration = Duration::from_secs(1);
+/// Longest schedule accepted
+pub const MAX_DURATION: Duration = Duration::from_secs(86400);
+
+/// Parent TWAP order as submitted by the client
+#[derive(Clone, Debug)]
+pub struct TwapRequest {
+    pub user_id: UserId,
+    pub side: Side,
+    pub total_quantity: Quantity,
+    pub duration: Duration,
+    pub slice_interval: Duration,
+    pub limit_price: Option<Price>,  // None: market slices; Some: limit IOC slices
+    pub slippage_limit: Option<Ratio>,  // Max fraction from mark per market slice; required without a limit price
+    pub reduce_only: bool,
+    pub position_side: PositionSide,
+}
+
+/// Progress of one parent order
+#[derive(Clone, Debug, Serialize)]
+pub struct TwapState {
+    pub parent_id: CorrelationId,
+    pub user_id: UserId,
+    pub side: Side,
+    pub total_quantity: Quantity,
+    pub slice_quantity: Quantity,
+    pub slices_total: u32,
+    pub slices_sent: u32,
+    pub quantity_sent: Quantity,
+    pub started_at: Timestamp,
+    pub next_slice_at: Timestamp,
+    pub child_order_ids: Vec<OrderId>,
+    #[serde(skip)]
+    request: TwapRequest,
+}
+
+impl TwapState {
+    pub fn remaining(&self) -> Quantity {
+        self.total_quantity - self.quantity_sent
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.slices_sent >= self.slices_total
+    }
+}
+
+/// TWAP slicing engine
+///
+/// ## Slicing
+/// - The schedule has `ceil(duration / slice_interval)` slices, the first
+///   released immediately and one per interval after that
+/// - Each slice is `total / slices` rounded down to the lot size; the last
+///   slice carries the remainder
+/// - Slices are IOC child `OrderSubmit`s (market, or limit at `limit_price`)
+///   that go through the event log like any client order
+///
+/// ## Linkage
+/// - Every child carries the parent's id as its `correlation_id`, so fills
+///   and rejects can be attributed to the parent downstream
+/// - `cancel()` stops further slices; children already sent are unaffected
+pub struct TwapEngine {
+    market_id: MarketId,
+    lot_size: Quantity,
+    parents: HashMap<CorrelationId, TwapState>,
+}
+
+impl TwapEngine {
+    pub fn new(market_id: MarketId, lot_size: Quantity) -> Self {
+        TwapEngine {
+            market_id,
+            lot_size,
+            parents: HashMap::new(),
+        }
+    }
+
+    /// Accept a parent order; its first slice is due at `now`
+    pub fn submit(&mut self, request: TwapRequest, now: Timestamp) -> Result<TwapState> {
+        if request.slice_interval < MIN_SLICE_INTERVAL
+            || request.duration < request.slice_interval
+            || request.duration > MAX_DURATION
+        {
+            return Err(Error::ConfigError(format!(
+                "TWAP needs slice_interval >= {}s and slice_interval <= duration <= {}s",
+                MIN_SLICE_INTERVAL.as_secs(), MAX_DURATION.as_secs()
+            )));
+        }
+        if request.total_quantity.to_i64() <= 0 {
+            return Err(Error::InvalidQuantity);
+        }
+        // Market slices are refused by the validator without a slippage limit
+        if request.limit_price.is_none() && request.slippage_limit.is_none() {
+            return Err(Error::MarketOrderRequiresSlippageLimit);
+        }
+
+        let interval_ms = request.slice_interval.as_millis() as u64;
+        let slices_total = (request.duration.as_millis() as u64).div_ceil(interval_ms) as u32;
+
+        let lot = self.lot_size.to_i64().max(1);
+        let slice_quantity = (request.total_quantity.to_i64() / slices_total as i64) / lot * lot;
+        if slice_quantity == 0 {
+            return Err(Error::InvalidLotSize);
+        }
+
+        let state = TwapState {
+            parent_id: CorrelationId::new(),
+            user_id: request.user_id,
+            side: request.side,
+            total_quantity: request.total_quantity,
+            slice_quantity: Quantity::from_i64(slice_quantity),
+            slices_total,
+            slices_sent: 0,
+            quantity_sent: Quantity::zero(),
+            started_at: now,
+            next_slice_at: now,
+            child_order_ids: Vec::new(),
+            request,
+        };
+
+        tracing::info!(
+            "TWAP {} accepted: {:?} {} in {} slices",
+            state.parent_id.0, state.side, state.total_quantity.to_f64(), slices_total
+        );
+
+        self.parents.insert(state.parent_id, state.clone());
+        Ok(state)
+    }
+
+    /// Stop a parent's remaining slices
+    pub fn cancel(&mut self, parent_id: &CorrelationId) -> Option<TwapState> {
+        self.parents.remove(parent_id)
+    }
+
+    pub fn get(&self, parent_id: &CorrelationId) -> Option<&TwapState> {
+        self.parents.get(parent_id)
+    }
+
+    pub fn active_for(&self, user_id: &UserId) -> Vec<&TwapState> {
+        self.parents.values().filter(|state| state.user_id == *user_id).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.parents.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.parents.is_empty()
+    }
+
+    /// Child orders whose slice time has come, oldest first
+    ///
+    /// A parent is dropped once its last slice is released.
+    pub fn due_slices(&mut self, now: Timestamp) -> Vec<OrderSubmit> {
+        let mut due: Vec<CorrelationId> = self.parents.values()
+            .filter(|state| state.next_slice_at <= now)
+            .map(|state| state.parent_id)
+            .collect();
+        // HashMap order is not deterministic: release oldest first
+        due.sort_by_key(|parent_id| (self.parents[parent_id].next_slice_at, parent_id.0));
+
+        let mut children = Vec::with_capacity(due.len());
+        for parent_id in due {
+            let state = match self.parents.get_mut(&parent_id) {
+                Some(state) => state,
+                None => continue,
+            };
+
+            let last_slice = state.slices_sent + 1 == state.slices_total;
+            let quantity = if last_slice { state.remaining() } else { state.slice_quantity };
+
+            let child = Self::child_order(self.market_id, state, quantity, now);
+            state.slices_sent += 1;
+            state.quantity_sent = state.quantity_sent + quantity;
+            state.child_order_ids.push(child.order_id);
+            state.next_slice_at = state.started_at + state.request.slice_interval * state.slices_sent;
+
+            TWAP_SLICES_SUBMITTED.inc();
+            tracing::debug!(
+                "TWAP {} slice {}/{}: {} ({})",
+                parent_id.0, state.slices_sent, state.slices_total, quantity.to_f64(), child.order_id
+            );
+
+            if state.is_complete() {
+                tracing::info!("TWAP {} completed", parent_id.0);
+                self.parents.remove(&parent_id);
+            }
+            children.push(child);
+        }
+
+        children
+    }
+
+    fn child_order(market_id: MarketId, state: &TwapState, quantity: Quantity, now: Timestamp) -> OrderSubmit {
+        let mut base = BaseEvent::new(EventType::OrderSubmit, market_id);
+        base.timestamp = now;
+        base.correlation_id = state.parent_id;
+        base.checksum = base.calculate_checksum();
+
+        let (order_type, slippage_limit) = match state.request.limit_price {
+            Some(_) => (OrderType::Limit, None),
+            None => (OrderType::Market, state.request.slippage_limit),
+        };
+
+        OrderSubmit {
+            base,
+            order_id: OrderId::new(),
+            user_id: state.user_id,
+            side: state.side,
+            order_type,
+            price: state.request.limit_price,
+            quantity,
+            time_in_force: TimeInForce::IOC,
+            reduce_only: state.request.reduce_only,
+            post_only: false,
+            slippage_limit,
+            trigger_price: None,
+            trailing_offset: None,
+            self_trade_prevention: None,
+            position_side: state.request.position_side,
+            min_fill_quantity: None,
+        }
+    }
+}
@@ -1,21 +1,28 @@
 use axum::{
-    extract::Request,
+    extract::{MatchedPath, Request, State},
     middleware::Next,
     response::Response,
-    http::StatusCode,
+    http::{Method, StatusCode},
 };
 use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
 use serde::{Deserialize, Serialize};
 use crate::error::{Error, Result};
+use crate::events::order::SelfTradePrevention;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
-use crate::types::ids::UserId;
+use tokio::sync::RwLock;
+use crate::types::ids::{OperatorId, TenantId, UserId};
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Claims {
     pub sub: String,  // User ID
     pub exp: u64,     // Expiration time
     pub iat: u64,     // Issued at
     pub role: String, // User role (user, admin, operator)
+    #[serde(default)]
+    pub master: Option<String>,  // Master account ID when `sub` is a sub-account
 }
 
 pub struct JwtAuth {
@@ -32,6 +39,15 @@ impl JwtAuth {
     }
 
     pub fn generate_token(&self, user_id: UserId, role: &str, duration_secs: u64) -> Result<String> {
+        self.encode_claims(user_id, None, role, duration_secs)
+    }
+
+    /// Token for a sub-account, naming the master it belongs to
+    pub fn generate_sub_account_token(&self, sub_account_id: UserId, master_id: UserId, role: &str, duration_secs: u64) -> Result<String> {
+        self.encode_claims(sub_account_id, Some(master_id), role, duration_secs)
+    }
+
+    fn encode_claims(&self, user_id: UserId, master_id: Option<UserId>, role: &str, duration_secs: u64) -> Result<String> {
         let now = SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .unwrap()
@@ -42,6 +58,7 @@ impl JwtAuth {
             exp: now + duration_secs,
             iat: now,
             role: role.to_string(),
+            master: master_id.map(|master_id| master_id.to_string()),
         };
 
         encode(&Header::default(), &claims, &self.encoding_key)
@@ -106,23 +123,411 @@ pub async fn admin_auth_middleware(
     Ok(response)
 }
 
+/// What an API key may do
+///
+/// Any scope includes read access; `Admin` includes everything. Keys deployed
+/// on trading servers get `Trade` only, so a leaked key can never move funds.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ApiKeyScope {
+    ReadOnly,
+    Trade,     // Submit, amend and cancel orders; account trading settings
+    Transfer,  // Withdrawals, transfers and issuing sub-account keys
+    Admin,     // Operator routes
+}
+
+impl ApiKeyScope {
+    pub fn grants(&self, required: ApiKeyScope) -> bool {
+        *self == required || *self == ApiKeyScope::Admin || required == ApiKeyScope::ReadOnly
+    }
+
+    /// Scope a route needs; None for public routes
+    pub fn required_for(method: &Method, path: &str) -> Option<ApiKeyScope> {
+        if PUBLIC_ROUTES.contains(&path) {
+            None
+        } else if path.starts_with("/admin/") {
+            Some(ApiKeyScope::Admin)
+        } else if path.starts_with("/transfers") || path.starts_with("/withdrawals") || path.ends_with("/api-keys") {
+            Some(ApiKeyScope::Transfer)
+        } else if method == Method::GET {
+            Some(ApiKeyScope::ReadOnly)
+        } else {
+            Some(ApiKeyScope::Trade)
+        }
+    }
+}
+
+/// Market data anyone may read, keyed by the router's path pattern
+const PUBLIC_ROUTES: &[&str] = &["/health", "/orderbook/:market", "/market/volatility", "/funding/predicted"];
+
+/// Scopes carried by a JWT: operators get everything, users all but `Admin`
+pub fn scopes_for_role(role: &str) -> HashSet<ApiKeyScope> {
+    match role {
+        "admin" | "operator" => HashSet::from([ApiKeyScope::ReadOnly, ApiKeyScope::Trade, ApiKeyScope::Transfer, ApiKeyScope::Admin]),
+        _ => HashSet::from([ApiKeyScope::ReadOnly, ApiKeyScope::Trade, ApiKeyScope::Transfer]),
+    }
+}
+
+/// The caller of an authenticated request, from its API key or JWT
+///
+/// Inserted into the request extensions by `api_key_scope_middleware`;
+/// handlers act for `user_id` only.
+#[derive(Clone, Debug)]
+pub struct Principal {
+    pub user_id: UserId,
+    pub master_id: Option<UserId>,  // Set when the caller is a sub-account
+    pub scopes: HashSet<ApiKeyScope>,
+    pub api_key: Option<String>,    // None for JWT callers
+}
+
+impl Principal {
+    pub fn grants(&self, required: ApiKeyScope) -> bool {
+        self.scopes.iter().any(|scope| scope.grants(required))
+    }
+
+    /// Parse a user ID named in a request, refusing anyone but the caller
+    ///
+    /// 400 for a malformed ID, 403 for someone else's account.
+    pub fn authorize_user(&self, user_id: &str) -> std::result::Result<UserId, StatusCode> {
+        let user_id = UserId::from_string(user_id).map_err(|_| StatusCode::BAD_REQUEST)?;
+        if user_id != self.user_id {
+            tracing::warn!("{:?} refused acting for {:?}", self.user_id, user_id);
+            return Err(StatusCode::FORBIDDEN);
+        }
+        Ok(user_id)
+    }
+
+    /// The caller as an operator, for admin actions
+    ///
+    /// 403 unless the caller holds the Admin scope.
+    pub fn operator_id(&self) -> std::result::Result<OperatorId, StatusCode> {
+        if !self.grants(ApiKeyScope::Admin) {
+            tracing::warn!("{:?} refused an operator action", self.user_id);
+            return Err(StatusCode::FORBIDDEN);
+        }
+        Ok(OperatorId(self.user_id.0))
+    }
+
+    fn from_key(key: &str, metadata: &ApiKeyMetadata) -> Self {
+        Principal {
+            user_id: metadata.user_id,
+            master_id: metadata.master_id,
+            scopes: metadata.scopes.clone(),
+            api_key: Some(key.to_string()),
+        }
+    }
+
+    fn from_claims(claims: &Claims) -> Result<Self> {
+        let parse = |id: &str| UserId::from_string(id).map_err(|_| Error::AuthenticationError(format!("bad subject {:?}", id)));
+        Ok(Principal {
+            user_id: parse(&claims.sub)?,
+            master_id: claims.master.as_deref().map(parse).transpose()?,
+            scopes: scopes_for_role(&claims.role),
+            api_key: None,
+        })
+    }
+}
+
+/// What is stored for each issued key
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ApiKeyMetadata {
+    pub user_id: UserId,
+    pub tenant_id: Option<TenantId>,
+    pub scopes: HashSet<ApiKeyScope>,
+    pub self_trade_prevention: Option<SelfTradePrevention>,  // Overrides the account and market modes
+    #[serde(default)]
+    pub master_id: Option<UserId>,  // Set on keys a master issued for one of its sub-accounts
+}
+
+impl ApiKeyMetadata {
+    pub fn grants(&self, required: ApiKeyScope) -> bool {
+        self.scopes.iter().any(|scope| scope.grants(required))
+    }
+}
+
+/// Scopes for keys added without explicit ones: trading, never funds or admin
+pub fn default_scopes() -> HashSet<ApiKeyScope> {
+    HashSet::from([ApiKeyScope::ReadOnly, ApiKeyScope::Trade])
+}
+
 // API Key authentication (alternative to JWT)
+//
+// With a `path`, issued keys and their settings are written through to a
+// JSON file that is loaded again on start.
 pub struct ApiKeyAuth {
-    valid_keys: std::collections::HashMap<String, UserId>,
+    keys: HashMap<String, ApiKeyMetadata>,
+    path: Option<PathBuf>,
 }
 
 impl ApiKeyAuth {
     pub fn new() -> Self {
         ApiKeyAuth {
-            valid_keys: std::collections::HashMap::new(),
+            keys: HashMap::new(),
+            path: None,
         }
     }
 
+    /// Load issued keys; a missing file starts empty
+    pub fn load(path: &str) -> Result<Self> {
+        let path = PathBuf::from(path);
+        let keys = match std::fs::read(&path) {
+            Ok(data) => serde_json::from_slice(&data)
+                .map_err(|e| Error::DeserializationError(e.to_string()))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => HashMap::new(),
+            Err(e) => return Err(Error::IoError(e)),
+        };
+
+        Ok(ApiKeyAuth { keys, path: Some(path) })
+    }
+
+    /// Write the keys through to the file they were loaded from
+    pub fn save(&self) -> Result<()> {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(Error::IoError)?;
+        }
+
+        let data = serde_json::to_vec_pretty(&self.keys)
+            .map_err(|e| Error::SerializationError(e.to_string()))?;
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, data).map_err(Error::IoError)?;
+        std::fs::rename(&tmp, path).map_err(Error::IoError)?;
+        Ok(())
+    }
+
     pub fn add_key(&mut self, key: String, user_id: UserId) {
-        self.valid_keys.insert(key, user_id);
+        self.add_key_with_scopes(key, user_id, default_scopes());
+    }
+
+    pub fn add_key_with_scopes(&mut self, key: String, user_id: UserId, scopes: HashSet<ApiKeyScope>) {
+        self.keys.insert(key, ApiKeyMetadata {
+            user_id,
+            tenant_id: None,
+            scopes,
+            self_trade_prevention: None,
+            master_id: None,
+        });
+    }
+
+    /// Register a key issued to a tenant (organization) on behalf of one of its users
+    pub fn add_tenant_key(&mut self, key: String, user_id: UserId, tenant_id: TenantId) {
+        self.keys.insert(key, ApiKeyMetadata {
+            user_id,
+            tenant_id: Some(tenant_id),
+            scopes: default_scopes(),
+            self_trade_prevention: None,
+            master_id: None,
+        });
+    }
+
+    /// Register a key a master issued for one of its sub-accounts; the key
+    /// acts as the sub-account only
+    pub fn add_sub_account_key(&mut self, key: String, sub_account_id: UserId, master_id: UserId, scopes: HashSet<ApiKeyScope>) {
+        self.keys.insert(key, ApiKeyMetadata {
+            user_id: sub_account_id,
+            tenant_id: None,
+            scopes,
+            self_trade_prevention: None,
+            master_id: Some(master_id),
+        });
     }
 
     pub fn verify_key(&self, key: &str) -> Option<UserId> {
-        self.valid_keys.get(key).copied()
+        self.keys.get(key).map(|metadata| metadata.user_id)
+    }
+
+    pub fn metadata(&self, key: &str) -> Option<&ApiKeyMetadata> {
+        self.keys.get(key)
+    }
+
+    pub fn tenant_for_key(&self, key: &str) -> Option<TenantId> {
+        self.keys.get(key).and_then(|metadata| metadata.tenant_id)
+    }
+
+    /// Users holding a key issued to a tenant, with the tenant
+    pub fn tenant_members(&self) -> Vec<(TenantId, UserId)> {
+        self.keys.values()
+            .filter_map(|metadata| metadata.tenant_id.map(|tenant_id| (tenant_id, metadata.user_id)))
+            .collect()
+    }
+
+    /// Replace a key's scopes
+    pub fn set_scopes(&mut self, key: &str, scopes: HashSet<ApiKeyScope>) -> Result<()> {
+        let metadata = self.keys.get_mut(key).ok_or(Error::Unauthorized)?;
+        metadata.scopes = scopes;
+        Ok(())
+    }
+
+    /// Owner of the key if it grants `required`
+    pub fn authorize(&self, key: &str, required: ApiKeyScope) -> Result<UserId> {
+        self.authorize_principal(key, required).map(|principal| principal.user_id)
+    }
+
+    /// The key's principal if it grants `required`
+    pub fn authorize_principal(&self, key: &str, required: ApiKeyScope) -> Result<Principal> {
+        let metadata = self.keys.get(key).ok_or(Error::Unauthorized)?;
+        if !metadata.grants(required) {
+            return Err(Error::ApiKeyScopeDenied(required));
+        }
+        Ok(Principal::from_key(key, metadata))
+    }
+
+    /// Self-trade prevention mode for orders sent with this key, overriding
+    /// the account and market modes; None clears the override
+    pub fn set_self_trade_prevention(&mut self, key: &str, mode: Option<SelfTradePrevention>) -> Result<()> {
+        let metadata = self.keys.get_mut(key).ok_or(Error::Unauthorized)?;
+        let previous = std::mem::replace(&mut metadata.self_trade_prevention, mode);
+        if let Err(e) = self.save() {
+            // Keep memory and file in step
+            if let Some(metadata) = self.keys.get_mut(key) {
+                metadata.self_trade_prevention = previous;
+            }
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    pub fn self_trade_prevention_for_key(&self, key: &str) -> Option<SelfTradePrevention> {
+        self.keys.get(key).and_then(|metadata| metadata.self_trade_prevention)
     }
-}
\ No newline at end of file
+}
+
+/// Authenticate every non-public route and enforce its scope
+///
+/// The caller is identified by `X-API-Key`, or else by a Bearer JWT; the
+/// resulting `Principal` is added to the request extensions. No credential
+/// or an unknown one gets 401, a credential without the scope 403.
+pub async fn api_key_scope_middleware(
+    State(api_keys): State<Arc<RwLock<ApiKeyAuth>>>,
+    matched_path: Option<MatchedPath>,
+    mut request: Request,
+    next: Next,
+) -> std::result::Result<Response, StatusCode> {
+    let path = matched_path.as_ref().map_or(request.uri().path(), |path| path.as_str()).to_string();
+    let required = match ApiKeyScope::required_for(request.method(), &path) {
+        Some(required) => required,
+        None => return Ok(next.run(request).await),
+    };
+
+    let api_key = request.headers().get("X-API-Key").and_then(|h| h.to_str().ok());
+    let bearer = request.headers().get("Authorization")
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.strip_prefix("Bearer "));
+
+    let principal = match (api_key, bearer) {
+        (Some(key), _) => match api_keys.read().await.authorize_principal(key, required) {
+            Ok(principal) => principal,
+            Err(Error::ApiKeyScopeDenied(_)) => {
+                tracing::warn!("API key denied {:?} scope for {} {}", required, request.method(), path);
+                return Err(StatusCode::FORBIDDEN);
+            }
+            Err(_) => return Err(StatusCode::UNAUTHORIZED),
+        },
+        (None, Some(token)) => {
+            let principal = JWT_AUTH.verify_token(token)
+                .and_then(|claims| Principal::from_claims(&claims))
+                .map_err(|_| StatusCode::UNAUTHORIZED)?;
+            if !principal.grants(required) {
+                tracing::warn!("Token of {:?} denied {:?} scope for {} {}", principal.user_id, required, request.method(), path);
+                return Err(StatusCode::FORBIDDEN);
+            }
+            principal
+        }
+        (None, None) => return Err(StatusCode::UNAUTHORIZED),
+    };
+
+    request.extensions_mut().insert(principal);
+    Ok(next.run(request).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, extract::Extension, middleware, routing::{get, post}, Router};
+    use tower::ServiceExt;
+
+    async fn whoami(Extension(principal): Extension<Principal>) -> String {
+        principal.user_id.to_string()
+    }
+
+    fn router(api_keys: ApiKeyAuth) -> Router {
+        let api_keys = Arc::new(RwLock::new(api_keys));
+        Router::new()
+            .route("/health", get(|| async { "OK" }))
+            .route("/orders", post(whoami))
+            .route("/transfers", post(whoami))
+            .route_layer(middleware::from_fn_with_state(api_keys.clone(), api_key_scope_middleware))
+            .with_state(api_keys)
+    }
+
+    fn request(path: &str, header: Option<(&str, String)>) -> Request {
+        let mut builder = Request::builder().method(if path == "/health" { Method::GET } else { Method::POST }).uri(path);
+        if let Some((name, value)) = header {
+            builder = builder.header(name, value);
+        }
+        builder.body(Body::empty()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn scoped_routes_need_a_credential() {
+        let app = router(ApiKeyAuth::new());
+        assert_eq!(app.clone().oneshot(request("/health", None)).await.unwrap().status(), StatusCode::OK);
+        assert_eq!(app.oneshot(request("/orders", None)).await.unwrap().status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn api_key_acts_as_its_owner_within_its_scopes() {
+        let owner = UserId::new();
+        let mut api_keys = ApiKeyAuth::new();
+        api_keys.add_key("trading".to_string(), owner);
+        let app = router(api_keys);
+
+        let response = app.clone().oneshot(request("/orders", Some(("X-API-Key", "trading".to_string())))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(body, owner.to_string().as_bytes());
+
+        let response = app.clone().oneshot(request("/transfers", Some(("X-API-Key", "trading".to_string())))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let response = app.oneshot(request("/orders", Some(("X-API-Key", "unknown".to_string())))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn bearer_token_acts_as_its_subject() {
+        let user_id = UserId::new();
+        let token = JWT_AUTH.generate_token(user_id, "user", 60).unwrap();
+        let app = router(ApiKeyAuth::new());
+
+        let response = app.clone().oneshot(request("/transfers", Some(("Authorization", format!("Bearer {}", token))))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = app.oneshot(request("/transfers", Some(("Authorization", "Bearer forged".to_string())))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[test]
+    fn principal_acts_for_itself_only() {
+        let principal = Principal {
+            user_id: UserId::new(),
+            master_id: None,
+            scopes: default_scopes(),
+            api_key: None,
+        };
+        assert_eq!(principal.authorize_user(&principal.user_id.to_string()), Ok(principal.user_id));
+        assert_eq!(principal.authorize_user(&UserId::new().to_string()), Err(StatusCode::FORBIDDEN));
+        assert_eq!(principal.authorize_user("not-a-user"), Err(StatusCode::BAD_REQUEST));
+    }
+
+    #[test]
+    fn only_admins_act_as_operators() {
+        let user_id = UserId::new();
+        let trader = Principal { user_id, master_id: None, scopes: default_scopes(), api_key: None };
+        assert_eq!(trader.operator_id(), Err(StatusCode::FORBIDDEN));
+
+        let admin = Principal { scopes: scopes_for_role("admin"), ..trader };
+        assert_eq!(admin.operator_id(), Ok(OperatorId(user_id.0)));
+    }
+}
@@ -0,0 +1,302 @@
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use axum::extract::Request;
+use axum::middleware::Next;
+use axum::response::Response;
+use tokio::sync::{mpsc, oneshot};
+use crate::config::IngressConfig;
+use crate::error::{Error, Result};
+use crate::events::base::{BaseEvent, EventPayload, EventType};
+use crate::events::order::OrderSubmit;
+use crate::interfaces::event_producer::EventProducer;
+use crate::observability::metrics::{INGRESS_OUT_OF_ORDER, INGRESS_QUEUE_DELAY, INGRESS_QUEUE_DEPTH};
+use crate::types::timestamp::Timestamp;
+
+/// Receive time of a request, taken by `stamp_ingress` before extraction,
+/// authentication or any other async work
+#[derive(Clone, Copy, Debug)]
+pub struct IngressStamp {
+    pub received_at: Timestamp,  // HLC: unique and monotonic, defines the fair order
+    instant: Instant,            // For the reorder window and queue delay
+}
+
+impl IngressStamp {
+    pub fn now() -> Self {
+        IngressStamp {
+            received_at: Timestamp::now(),
+            instant: Instant::now(),
+        }
+    }
+
+    /// Stamps for the `len` orders of a batch received with this one: this
+    /// stamp first, then later ones in batch order, all sharing its reorder window
+    pub fn batch(&self, len: usize) -> Vec<IngressStamp> {
+        (0..len)
+            .map(|i| match i {
+                0 => *self,
+                _ => IngressStamp { received_at: Timestamp::now(), instant: self.instant },
+            })
+            .collect()
+    }
+}
+
+/// Outermost API middleware: stamps every request on receipt and adds the
+/// `IngressStamp` to its extensions
+pub async fn stamp_ingress(mut request: Request, next: Next) -> Response {
+    request.extensions_mut().insert(IngressStamp::now());
+    next.run(request).await
+}
+
+struct Pending {
+    stamp: IngressStamp,
+    order: OrderSubmit,
+    produced: oneshot::Sender<Result<()>>,  // Outcome of producing the order, for the handler
+}
+
+impl PartialEq for Pending {
+    fn eq(&self, other: &Self) -> bool {
+        self.stamp.received_at == other.stamp.received_at
+    }
+}
+
+impl Eq for Pending {}
+
+impl PartialOrd for Pending {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Pending {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.stamp.received_at.cmp(&other.stamp.received_at)
+    }
+}
+
+/// Handle the API handlers use to stamp and enqueue order requests
+#[derive(Clone)]
+pub struct IngressHandle {
+    tx: mpsc::Sender<Pending>,
+}
+
+impl IngressHandle {
+    /// Queue an order for sequencing and wait until it is in the event log;
+    /// its event timestamp becomes the receive time
+    /// Refused while OrderSubmit is disabled for the order's market; a failed
+    /// produce is returned, never only logged
+    pub async fn submit(&self, stamp: IngressStamp, mut order: OrderSubmit) -> Result<()> {
+        crate::controls::check_event_type_enabled(order.base.market_id, EventType::OrderSubmit)?;
+        order.base.timestamp = stamp.received_at;
+        order.base.checksum = order.base.calculate_checksum();
+
+        let (produced, outcome) = oneshot::channel();
+        self.tx.try_send(Pending { stamp, order, produced })
+            .map_err(|_| Error::IngressQueueFull)?;
+        INGRESS_QUEUE_DEPTH.inc();
+        outcome.await.unwrap_or(Err(Error::IngressClosed))
+    }
+}
+
+/// Latency-fair ingress sequencing
+///
+/// ## Ordering
+/// - Every request is stamped on receipt, before parsing, lookups or any
+///   other await point in the handler
+/// - Requests are held for `reorder_window` and released in stamp order, so a
+///   handler that was slower to reach the queue cannot overtake one received
+///   earlier (reordering is bounded by the window)
+/// - A request that arrives after a later-stamped one was already released
+///   is produced immediately, never dropped
+///
+/// ## Backpressure
+/// - The queue holds at most `queue_capacity` requests; `submit()` fails with
+///   `IngressQueueFull` instead of blocking the handler
+///
+/// ## Delivery
+/// - `submit()` resolves once the order is produced, with the producer's
+///   error if it could not be, so the client never gets an acceptance for an
+///   order that is not in the log
+pub struct IngressSequencer {
+    rx: mpsc::Receiver<Pending>,
+    reorder_window: Duration,
+    buffer: BinaryHeap<Reverse<Pending>>,
+    last_released: Option<Timestamp>,
+}
+
+impl IngressSequencer {
+    pub fn new(config: &IngressConfig) -> (IngressHandle, IngressSequencer) {
+        let (tx, rx) = mpsc::channel(config.queue_capacity.max(1));
+
+        let sequencer = IngressSequencer {
+            rx,
+            reorder_window: Duration::from_micros(config.reorder_window_us),
+            buffer: BinaryHeap::new(),
+            last_released: None,
+        };
+        (IngressHandle { tx }, sequencer)
+    }
+
+    /// Release requests into the event log until every handle is dropped
+    pub async fn run<P: EventProducer + ?Sized>(mut self, producer: Arc<P>) {
+        loop {
+            let deadline = self.buffer.peek()
+                .map(|Reverse(pending)| pending.stamp.instant + self.reorder_window);
+
+            tokio::select! {
+                received = self.rx.recv() => match received {
+                    Some(pending) => self.buffer.push(Reverse(pending)),
+                    None => break,
+                },
+                _ = Self::sleep_until(deadline) => {}
+            }
+
+            self.release_due(&*producer, Instant::now()).await;
+        }
+
+        // Shutdown: flush whatever is still held
+        self.reorder_window = Duration::ZERO;
+        self.release_due(&*producer, Instant::now()).await;
+    }
+
+    async fn sleep_until(deadline: Option<Instant>) {
+        match deadline {
+            Some(deadline) => tokio::time::sleep_until(deadline.into()).await,
+            None => std::future::pending().await,
+        }
+    }
+
+    async fn release_due<P: EventProducer + ?Sized>(&mut self, producer: &P, now: Instant) {
+        while let Some(Reverse(pending)) = self.buffer.peek() {
+            if pending.stamp.instant + self.reorder_window > now {
+                break;
+            }
+            let Reverse(pending) = self.buffer.pop().expect("peeked");
+
+            if self.last_released.is_some_and(|last| pending.stamp.received_at < last) {
+                INGRESS_OUT_OF_ORDER.inc();
+            } else {
+                self.last_released = Some(pending.stamp.received_at);
+            }
+
+            INGRESS_QUEUE_DEPTH.dec();
+            INGRESS_QUEUE_DELAY
+                .with_label_values(&["order_submit"])
+                .observe(now.duration_since(pending.stamp.instant).as_secs_f64());
+
+            let order_id = pending.order.order_id;
+            let base = pending.order.base.clone();
+            let outcome = producer.produce(BaseEvent {
+                payload: EventPayload::OrderSubmit(Box::new(pending.order)),
+                ..base
+            }).await.map(|_| ());
+            if let Err(e) = &outcome {
+                tracing::error!("Failed to produce order {} from ingress: {:?}", order_id, e);
+            }
+            let _ = pending.produced.send(outcome);  // The handler may have gone away
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::order::{OrderType, Side, TimeInForce};
+    use crate::interfaces::memory::InMemoryEventProducer;
+    use crate::types::ids::{MarketId, OrderId, UserId};
+    use crate::types::position::PositionSide;
+    use crate::types::quantity::Quantity;
+
+    fn order(market_id: MarketId) -> OrderSubmit {
+        OrderSubmit {
+            base: BaseEvent::new(EventType::OrderSubmit, market_id),
+            order_id: OrderId::new(),
+            user_id: UserId::new(),
+            side: Side::Buy,
+            order_type: OrderType::Market,
+            price: None,
+            quantity: Quantity::from_i64(1),
+            time_in_force: TimeInForce::IOC,
+            reduce_only: false,
+            post_only: false,
+            slippage_limit: None,
+            trigger_price: None,
+            trailing_offset: None,
+            self_trade_prevention: None,
+            position_side: PositionSide::Both,
+            min_fill_quantity: None,
+        }
+    }
+
+    /// A log that refuses every event
+    struct UnavailableLog;
+
+    #[async_trait::async_trait]
+    impl EventProducer for UnavailableLog {
+        async fn produce(&self, _event: BaseEvent) -> Result<u64> {
+            Err(Error::KafkaError("broker unavailable".to_string()))
+        }
+    }
+
+    fn sequencer() -> (IngressHandle, IngressSequencer) {
+        IngressSequencer::new(&IngressConfig { reorder_window_us: 0, queue_capacity: 8 })
+    }
+
+    #[tokio::test]
+    async fn disabled_orders_are_refused_before_the_log() {
+        let market_id = MarketId::new();  // Toggles are process-wide; keep this test's to itself
+        let (handle, sequencer) = sequencer();
+        let producer = Arc::new(InMemoryEventProducer::new());
+        let running = tokio::spawn(sequencer.run(producer.clone()));
+
+        crate::controls::disable_event_type(market_id, EventType::OrderSubmit);
+        let refused = handle.submit(IngressStamp::now(), order(market_id)).await;
+        assert!(matches!(refused, Err(Error::EventTypeDisabled(EventType::OrderSubmit))));
+
+        crate::controls::enable_event_type(market_id, EventType::OrderSubmit);
+        handle.submit(IngressStamp::now(), order(market_id)).await.unwrap();
+        drop(handle);
+
+        running.await.unwrap();
+        assert_eq!(producer.drain().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn a_failed_produce_is_returned_to_the_submitter() {
+        let (handle, sequencer) = sequencer();
+        tokio::spawn(sequencer.run(Arc::new(UnavailableLog)));
+
+        let submitted = handle.submit(IngressStamp::now(), order(MarketId::new())).await;
+        assert!(matches!(submitted, Err(Error::KafkaError(_))));
+    }
+
+    #[tokio::test]
+    async fn a_batch_is_logged_in_batch_order() {
+        let market_id = MarketId::new();
+        let (handle, sequencer) = IngressSequencer::new(&IngressConfig { reorder_window_us: 20_000, queue_capacity: 8 });
+        let producer = Arc::new(InMemoryEventProducer::new());
+        let running = tokio::spawn(sequencer.run(producer.clone()));
+
+        let orders: Vec<OrderSubmit> = (0..3).map(|_| order(market_id)).collect();
+        let order_ids: Vec<OrderId> = orders.iter().map(|order| order.order_id).collect();
+        // Queued last order first
+        let submissions = IngressStamp::now().batch(orders.len()).into_iter()
+            .zip(orders)
+            .rev()
+            .map(|(stamp, order)| handle.submit(stamp, order));
+        for outcome in futures::future::join_all(submissions).await {
+            outcome.unwrap();
+        }
+        drop(handle);
+        running.await.unwrap();
+
+        let logged: Vec<OrderId> = producer.drain().into_iter()
+            .map(|event| match event.payload {
+                EventPayload::OrderSubmit(order) => order.order_id,
+                _ => panic!("not an order"),
+            })
+            .collect();
+        assert_eq!(logged, order_ids);
+    }
+}
@@ -1,4 +1,7 @@
-mod rest;
-mod websocket;
-mod auth;
-mod rate_limit;
\ No newline at end of file
+pub mod rest;
+pub mod ingress;
+pub mod websocket;
+pub mod auth;
+mod rate_limit;
+pub mod tenant;
+pub mod read_model;
\ No newline at end of file
@@ -1,11 +1,13 @@
 use std::collections::HashMap;
+use std::hash::Hash;
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 use crate::error::{Error, Result};
 use crate::types::ids::UserId;
 
-pub struct RateLimiter {
-    limits: Arc<Mutex<HashMap<UserId, RateLimit>>>,
+/// Fixed-window rate limiter keyed per user by default, or per tenant
+pub struct RateLimiter<K = UserId> {
+    limits: Arc<Mutex<HashMap<K, RateLimit>>>,
     max_requests: usize,
     window: Duration,
 }
@@ -15,7 +17,7 @@ struct RateLimit {
     window_start: Instant,
 }
 
-impl RateLimiter {
+impl<K: Eq + Hash + Copy> RateLimiter<K> {
     pub fn new(max_requests: usize, window: Duration) -> Self {
         RateLimiter {
             limits: Arc::new(Mutex::new(HashMap::new())),
@@ -24,11 +26,16 @@ impl RateLimiter {
         }
     }
 
-    pub fn check(&self, user_id: UserId) -> Result<()> {
+    pub fn check(&self, key: K) -> Result<()> {
+        self.check_with_limit(key, self.max_requests)
+    }
+
+    /// Check against a caller-supplied limit (e.g. a per-tenant quota override)
+    pub fn check_with_limit(&self, key: K, max_requests: usize) -> Result<()> {
         let mut limits = self.limits.lock().unwrap();
         let now = Instant::now();
 
-        let limit = limits.entry(user_id).or_insert(RateLimit {
+        let limit = limits.entry(key).or_insert(RateLimit {
             count: 0,
             window_start: now,
         });
@@ -40,7 +47,7 @@ impl RateLimiter {
         }
 
         // Check limit
-        if limit.count >= self.max_requests {
+        if limit.count >= max_requests {
             return Err(Error::RateLimitExceeded);
         }
 
@@ -0,0 +1,361 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::{watch, RwLock};
+use tokio::time::{interval, Duration};
+use crate::config::risk::UserLimits;
+use crate::funding::index::CumulativeFunding;
+use crate::core::matching_core::MatchingCoreHandle;
+use crate::matching::matcher::Matcher;
+use crate::matching::order_book::{DepthSnapshot, Order, QueuePosition};
+use crate::observability::metrics::READ_MODEL_PUBLISH_SKIPPED;
+use crate::settlement::balance_manager::BalanceManager;
+use crate::settlement::position_manager::PositionManager;
+use crate::types::account::Account;
+use crate::types::ids::{OrderId, UserId};
+use crate::types::position::{Position, PositionSide};
+use crate::types::timestamp::Timestamp;
+
+/// Number of account/position shards served to the API
+pub const READ_MODEL_SHARDS: usize = 16;
+/// Price levels per side kept in the published depth
+pub const READ_MODEL_DEPTH_LEVELS: usize = 1000;
+
+/// Immutable view of the accounts and positions in one shard
+///
+/// Entries are shared with the previous publish of the shard unless they
+/// changed, so a publish copies only what moved.
+#[derive(Default)]
+pub struct AccountShard {
+    pub published_at: Option<Timestamp>,
+    pub accounts: HashMap<UserId, Arc<Account>>,
+    pub positions: HashMap<(UserId, PositionSide), Arc<Position>>,  // Every leg, hedge mode included
+    pub leverage: HashMap<UserId, f64>,          // Chosen leverage (absent: tier cap)
+    pub limits: HashMap<UserId, UserLimits>,     // Operator overrides of the exposure caps
+    pub cumulative_funding: CumulativeFunding,    // Market-wide, the same in every shard
+}
+
+/// Immutable view of the resting orders with their queue positions
+#[derive(Default)]
+pub struct BookView {
+    pub published_at: Option<Timestamp>,
+    pub orders: HashMap<OrderId, (Order, QueuePosition)>,
+    pub user_orders: HashMap<UserId, Vec<OrderId>>,  // From the book's user index
+    pub depth: DepthSnapshot,
+}
+
+impl BookView {
+    /// Resting orders of `user_id`, oldest first
+    pub fn orders_of(&self, user_id: &UserId) -> Vec<&Order> {
+        let mut orders: Vec<&Order> = self.user_orders.get(user_id)
+            .map(|order_ids| order_ids.iter()
+                .filter_map(|order_id| self.orders.get(order_id).map(|(order, _)| order))
+                .collect())
+            .unwrap_or_default();
+        orders.sort_by_key(|order| (order.timestamp, order.order_id.0));
+        orders
+    }
+}
+
+fn shard_of(user_id: &UserId) -> usize {
+    (user_id.0.as_u128() % READ_MODEL_SHARDS as u128) as usize
+}
+
+/// Eventually consistent read models for the REST API
+///
+/// ## Consistency
+/// - Views lag engine state by at most one publish interval
+/// - Each shard is internally consistent; different shards may come from
+///   different publishes
+///
+/// ## Contention
+/// - Handlers only borrow `watch` channels, never the engine's `RwLock`s
+/// - The publisher uses `try_read` and skips a round while the engine is writing
+/// - Only shards with a changed entry are rebuilt, and a rebuilt shard copies
+///   only the changed entries (the rest are shared with the previous view)
+/// - The book is copied off the matching core in one command (only when its
+///   epoch moved) and the view is built from the copy, off the core
+#[derive(Clone)]
+pub struct ReadModels {
+    shards: Vec<watch::Receiver<Arc<AccountShard>>>,
+    book: watch::Receiver<Arc<BookView>>,
+}
+
+impl ReadModels {
+    fn shard(&self, user_id: &UserId) -> Arc<AccountShard> {
+        self.shards[shard_of(user_id)].borrow().clone()
+    }
+
+    pub fn account(&self, user_id: &UserId) -> Option<Account> {
+        self.shard(user_id).accounts.get(user_id).map(|account| (**account).clone())
+    }
+
+    /// The one-way (`Both`) leg
+    pub fn position(&self, user_id: &UserId) -> Option<Position> {
+        self.shard(user_id).positions.get(&(*user_id, PositionSide::Both)).map(|position| (**position).clone())
+    }
+
+    /// Every leg of `user_id`, in `PositionManager::positions_for` order
+    pub fn positions_for(&self, user_id: &UserId) -> Vec<Position> {
+        let shard = self.shard(user_id);
+        [PositionSide::Both, PositionSide::Long, PositionSide::Short].iter()
+            .filter_map(|leg| shard.positions.get(&(*user_id, *leg)).map(|position| (**position).clone()))
+            .collect()
+    }
+
+    pub fn leverage(&self, user_id: &UserId) -> Option<f64> {
+        self.shard(user_id).leverage.get(user_id).copied()
+    }
+
+    pub fn user_limits(&self, user_id: &UserId) -> Option<UserLimits> {
+        self.shard(user_id).limits.get(user_id).copied()
+    }
+
+    pub fn cumulative_funding(&self) -> CumulativeFunding {
+        self.shards[0].borrow().cumulative_funding
+    }
+
+    pub fn all_accounts(&self) -> Vec<Account> {
+        self.shards.iter()
+            .flat_map(|shard| shard.borrow().accounts.values().map(|account| (**account).clone()).collect::<Vec<_>>())
+            .collect()
+    }
+
+    pub fn all_positions(&self) -> Vec<Position> {
+        self.shards.iter()
+            .flat_map(|shard| shard.borrow().positions.values().map(|position| (**position).clone()).collect::<Vec<_>>())
+            .collect()
+    }
+
+    pub fn book(&self) -> Arc<BookView> {
+        self.book.borrow().clone()
+    }
+}
+
+/// Copies engine state into the read models on a fixed interval
+pub struct ReadModelPublisher {
+    balance_manager: Arc<RwLock<BalanceManager>>,
+    position_manager: Arc<RwLock<PositionManager>>,
+    matching: MatchingCoreHandle<Matcher>,
+    book_epoch: Option<u64>,  // Core epoch of the last published book
+    shards: Vec<watch::Sender<Arc<AccountShard>>>,
+    book: watch::Sender<Arc<BookView>>,
+}
+
+impl ReadModelPublisher {
+    pub fn new(
+        balance_manager: Arc<RwLock<BalanceManager>>,
+        position_manager: Arc<RwLock<PositionManager>>,
+        matching: MatchingCoreHandle<Matcher>,
+    ) -> (Self, ReadModels) {
+        let (shard_txs, shard_rxs): (Vec<_>, Vec<_>) = (0..READ_MODEL_SHARDS)
+            .map(|_| watch::channel(Arc::new(AccountShard::default())))
+            .unzip();
+        let (book_tx, book_rx) = watch::channel(Arc::new(BookView::default()));
+
+        let publisher = ReadModelPublisher {
+            balance_manager,
+            position_manager,
+            matching,
+            book_epoch: None,
+            shards: shard_txs,
+            book: book_tx,
+        };
+
+        (publisher, ReadModels { shards: shard_rxs, book: book_rx })
+    }
+
+    /// Publish accounts/positions and the book; false if either was skipped
+    pub async fn publish(&mut self) -> bool {
+        let accounts_published = self.publish_accounts();
+        let book_published = self.publish_book().await;
+        accounts_published && book_published
+    }
+
+    fn publish_accounts(&self) -> bool {
+        // Never queue behind the engine: a skipped round is picked up next tick
+        let (balance_mgr, position_mgr) = match (
+            self.balance_manager.try_read(),
+            self.position_manager.try_read(),
+        ) {
+            (Ok(balance_mgr), Ok(position_mgr)) => (balance_mgr, position_mgr),
+            _ => {
+                READ_MODEL_PUBLISH_SKIPPED.with_label_values(&["accounts"]).inc();
+                return false;
+            }
+        };
+
+        let now = Timestamp::now();
+        let cumulative_funding = position_mgr.cumulative_funding();
+        let published: Vec<Arc<AccountShard>> = self.shards.iter().map(|tx| tx.borrow().clone()).collect();
+
+        // First pass compares against the published views without copying anything
+        let mut dirty = [false; READ_MODEL_SHARDS];
+        let mut counts = [(0usize, 0usize, 0usize, 0usize); READ_MODEL_SHARDS];
+        for (user_id, account) in &balance_mgr.accounts {
+            let shard = shard_of(user_id);
+            counts[shard].0 += 1;
+            dirty[shard] |= published[shard].accounts.get(user_id).is_none_or(|seen| **seen != *account);
+        }
+        for position in position_mgr.get_all_positions() {
+            let shard = shard_of(&position.user_id);
+            counts[shard].1 += 1;
+            dirty[shard] |= published[shard].positions.get(&(position.user_id, position.position_side)).is_none_or(|seen| **seen != *position);
+        }
+        let leverage_settings = position_mgr.leverage_settings();
+        for (user_id, leverage) in &leverage_settings {
+            let shard = shard_of(user_id);
+            counts[shard].2 += 1;
+            dirty[shard] |= published[shard].leverage.get(user_id) != Some(leverage);
+        }
+        let limit_overrides = position_mgr.limit_overrides();
+        for (user_id, limits) in &limit_overrides {
+            let shard = shard_of(user_id);
+            counts[shard].3 += 1;
+            dirty[shard] |= published[shard].limits.get(user_id) != Some(limits);
+        }
+        for (shard, view) in published.iter().enumerate() {
+            // Removed entries, or a market-wide funding move
+            dirty[shard] |= counts[shard] != (view.accounts.len(), view.positions.len(), view.leverage.len(), view.limits.len())
+                || view.cumulative_funding != cumulative_funding
+                || view.published_at.is_none();
+        }
+        if !dirty.contains(&true) {
+            return true;
+        }
+
+        // Second pass rebuilds the changed shards, sharing unchanged entries
+        let mut shards: Vec<Option<AccountShard>> = dirty.iter()
+            .map(|dirty| dirty.then(|| AccountShard { published_at: Some(now), cumulative_funding, ..Default::default() }))
+            .collect();
+        for (user_id, account) in &balance_mgr.accounts {
+            let shard = shard_of(user_id);
+            if let Some(view) = shards[shard].as_mut() {
+                let entry = match published[shard].accounts.get(user_id) {
+                    Some(seen) if **seen == *account => seen.clone(),
+                    _ => Arc::new(account.clone()),
+                };
+                view.accounts.insert(*user_id, entry);
+            }
+        }
+        for position in position_mgr.get_all_positions() {
+            let shard = shard_of(&position.user_id);
+            if let Some(view) = shards[shard].as_mut() {
+                let entry = match published[shard].positions.get(&(position.user_id, position.position_side)) {
+                    Some(seen) if **seen == *position => seen.clone(),
+                    _ => Arc::new(position.clone()),
+                };
+                view.positions.insert((position.user_id, position.position_side), entry);
+            }
+        }
+        drop(position_mgr);
+        drop(balance_mgr);
+        for (user_id, leverage) in leverage_settings {
+            if let Some(view) = shards[shard_of(&user_id)].as_mut() {
+                view.leverage.insert(user_id, leverage);
+            }
+        }
+        for (user_id, limits) in limit_overrides {
+            if let Some(view) = shards[shard_of(&user_id)].as_mut() {
+                view.limits.insert(user_id, limits);
+            }
+        }
+
+        for (tx, shard) in self.shards.iter().zip(shards) {
+            if let Some(shard) = shard {
+                tx.send_replace(Arc::new(shard));
+            }
+        }
+
+        true
+    }
+
+    async fn publish_book(&mut self) -> bool {
+        let epoch = self.matching.book().epoch;
+        if self.book_epoch == Some(epoch) {
+            return true;  // Nothing applied on the core since the last publish
+        }
+
+        let order_book = match self.matching.copy_book().await {
+            Ok(order_book) => order_book,
+            Err(e) => {
+                tracing::debug!("Book view not published: {}", e);
+                READ_MODEL_PUBLISH_SKIPPED.with_label_values(&["book"]).inc();
+                return false;
+            }
+        };
+
+        let levels = order_book.bids.values().chain(order_book.asks.values());
+        let orders = levels
+            .flat_map(|level| {
+                let side = order_book.level_orders(level).next().map(|o| o.side);
+                side.map(|side| order_book.level_queue_positions(side, level.price))
+                    .unwrap_or_default()
+            })
+            .map(|(order, position)| (order.order_id, (order.clone(), position)))
+            .collect();
+        let user_orders = order_book.users()
+            .map(|user_id| {
+                let order_ids = order_book.user_orders(user_id).iter().map(|order| order.order_id).collect();
+                (*user_id, order_ids)
+            })
+            .collect();
+        let depth = order_book.depth(READ_MODEL_DEPTH_LEVELS);
+
+        self.book.send_replace(Arc::new(BookView {
+            published_at: Some(Timestamp::now()),
+            orders,
+            user_orders,
+            depth,
+        }));
+        self.book_epoch = Some(epoch);
+
+        true
+    }
+
+    pub async fn run(mut self, publish_interval: Duration) {
+        let mut ticker = interval(publish_interval);
+        loop {
+            ticker.tick().await;
+            self.publish().await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::fees::FeeConfig;
+    use crate::core::matching_core::MatchingCore;
+    use crate::matching::order_book::OrderBook;
+    use crate::types::balance::Balance;
+    use crate::types::ids::MarketId;
+
+    #[tokio::test]
+    async fn a_publish_rebuilds_only_the_shards_that_changed() {
+        let balance_manager = Arc::new(RwLock::new(BalanceManager::new()));
+        let market_id = MarketId::new();
+        let position_manager = Arc::new(RwLock::new(PositionManager::new_with_market(market_id)));
+        let matching = MatchingCore::spawn(Matcher::new(OrderBook::new(), FeeConfig::default(), market_id), 16).unwrap();
+        let (mut publisher, read_models) = ReadModelPublisher::new(balance_manager.clone(), position_manager, matching);
+
+        let changed = UserId::new();
+        let untouched = std::iter::repeat_with(UserId::new)
+            .find(|user_id| shard_of(user_id) != shard_of(&changed))
+            .unwrap();
+        {
+            let mut balance_mgr = balance_manager.write().await;
+            balance_mgr.create_account(changed).unwrap();
+            balance_mgr.create_account(untouched).unwrap();
+        }
+        assert!(publisher.publish().await);
+        let untouched_view = read_models.shard(&untouched);
+        let changed_view = read_models.shard(&changed);
+
+        balance_manager.write().await.accounts.get_mut(&changed).unwrap().balance = Balance::from_i64(5);
+        assert!(publisher.publish().await);
+
+        assert!(Arc::ptr_eq(&untouched_view, &read_models.shard(&untouched)));
+        assert!(!Arc::ptr_eq(&changed_view, &read_models.shard(&changed)));
+        assert_eq!(read_models.account(&changed).unwrap().balance, Balance::from_i64(5));
+    }
+}
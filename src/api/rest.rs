@@ -1,31 +1,129 @@
 use axum::{
     Router,
-    routing::{get, post, delete},
-    extract::{Path, State, Json},
+    middleware,
+    routing::{get, post},
+    extract::{Extension, Path, Query, State, Json},
     http::StatusCode,
 };
 use crate::events::order::*;
+use crate::funding::payment_history::FundingPaymentHistory;
+use std::collections::HashSet;
 use std::sync::Arc;
-use tokio::sync::RwLock;
-use crate::interfaces::balance_provider::BalanceProvider;
-use crate::types::ids::{MarketId, OrderId, UserId};
+use tokio::sync::{mpsc, oneshot, RwLock};
+use crate::algo::twap::{TwapEngine, TwapRequest, TwapState};
+use crate::api::auth::{api_key_scope_middleware, default_scopes, ApiKeyAuth, ApiKeyScope, Principal};
+use crate::api::ingress::{stamp_ingress, IngressHandle, IngressStamp};
+use crate::api::read_model::{ReadModels, READ_MODEL_DEPTH_LEVELS};
+use crate::controls::{ProcessorAction, ProcessorCommand, ProcessorHaltState};
+use crate::controls::deadmans_switch::{DeadMansSwitch, SwitchState};
+use crate::controls::recovery::{RecoveryCommand, RecoveryReport};
+use crate::settlement::migration::{AccountExport, MigrationCommand};
+use crate::error::Error;
+use crate::event_log::producer::KafkaEventProducer;
+use crate::event_log::snapshot_manager::SnapshotManager;
+use crate::events::base::{BaseEvent, CorrelationId, EventPayload};
+use crate::events::balance::{IsolatedMarginTransfer, SubAccountCreated, SubAccountTransfer, Transfer};
+use crate::config::risk::UserLimits;
+use crate::events::control::UserLimitsSet;
+use crate::funding::predicted::{PredictedFunding, PredictedFundingTracker};
+use crate::interfaces::event_producer::EventProducer;
+use crate::liquidation::stress::{LiquidationStress, StressReport, StressScenario};
+use crate::api::tenant::{TenantPositionSummary, TenantRegistry};
+use crate::matching::order_archive::{OrderArchive, TerminalStatus};
+use crate::matching::order_book::{DepthLevel, Order};
+use crate::notifications::preferences::{NotificationPreferences, UserNotificationPreferences};
+use crate::price_infra::history::VolatilityStats;
+use crate::risk::daily_report::{load_report, DailyRiskReport};
+use crate::risk::liquidation_price::{leg_liquidation_prices, LiquidationPrices};
+use crate::risk::margin::MarginCalculator;
+use crate::risk::pnl::FundingAccrual;
+use crate::risk::pre_trade_check::{OpenExposure, PreTradeRiskCheck, PreviewContext, RiskPreview};
+use crate::risk::withdrawal_check::WithdrawalRiskCheck;
+use crate::settlement::repair::{plan_account_repair, RepairPlan, RepairScope};
+use crate::settlement::sub_accounts;
+use crate::types::account::Account;
+use crate::types::balance::Balance;
+use crate::types::ids::{MarketId, OperatorId, OrderId, TenantId, UserId};
+use crate::types::position::{MarginMode, Position, PositionMode, PositionSide};
+use crate::types::timestamp::Timestamp;
 use crate::types::price::Price;
 use crate::types::quantity::Quantity;
+use crate::types::ratio::Ratio;
 
 pub struct ApiState {
-    // Shared state with engine components
-    pub balance_manager: Arc<RwLock<crate::settlement::balance_manager::BalanceManager>>,
-    pub position_manager: Arc<RwLock<crate::settlement::position_manager::PositionManager>>,
+    // Engine state is served from read models, never the engine's locks
+    pub read_models: ReadModels,
+    pub order_archive: Arc<RwLock<OrderArchive>>,
+    pub funding_history: Arc<RwLock<FundingPaymentHistory>>,
+    pub tenant_registry: Arc<RwLock<TenantRegistry>>,
+    pub withdrawal_check: Arc<WithdrawalRiskCheck>,
+    pub margin_calculator: Arc<MarginCalculator>,  // Liquidation prices shown with positions
+    pub pre_trade_check: Arc<PreTradeRiskCheck>,   // What-if previews; the engine runs its own
+    pub mark_price: Arc<RwLock<Price>>,  // Latest mark price from the price feed
+    pub funding_accrual: Arc<RwLock<Option<FundingAccrual>>>,  // Unsettled funding at the latest prices (perpetuals only)
+    pub predicted_funding: Arc<PredictedFundingTracker>,  // Fed by the price feed (perpetuals only)
+    pub market_id: MarketId,
+    pub event_producer: Arc<KafkaEventProducer>,  // Records admin actions in the event log
+    pub snapshot_manager: Arc<SnapshotManager>,
+    pub recovery_tx: mpsc::Sender<RecoveryCommand>,  // Runs on the event loop that owns the consumer
+    pub processor_tx: mpsc::Sender<ProcessorCommand>,  // Halt controls, run on the event loop that owns the processor
+    pub migration_tx: mpsc::Sender<MigrationCommand>,  // Account export/import, run on the same loop
+    pub api_keys: Arc<RwLock<ApiKeyAuth>>,
+    pub deadmans_switch: Arc<RwLock<DeadMansSwitch>>,  // Expiries are turned into CancelAllOrders by the engine
+    pub latest_risk_report: Arc<RwLock<Option<DailyRiskReport>>>,
+    pub risk_reports_dir: String,
+    pub twap_engine: Arc<RwLock<TwapEngine>>,  // Slices are released into the event log by the engine
+    pub ingress: IngressHandle,  // Orders reach the event log in receive order
+    pub notification_preferences: Arc<RwLock<NotificationPreferences>>,
+    pub volatility: Arc<RwLock<Option<VolatilityStats>>>,  // Realized index volatility from the price feed
+    pub liquidation_stress: Arc<LiquidationStress>,  // Dry runs against the read models
 }
 
 pub fn create_router(state: Arc<ApiState>) -> Router {
     Router::new()
         .route("/health", get(health_check))
         .route("/orders", post(submit_order))
-        .route("/orders/:id", delete(cancel_order))
+        .route("/orders/batch", post(submit_order_batch))
+        .route("/orders/cancel-all", post(cancel_all_orders))
+        .route("/deadmans-switch", post(arm_deadmans_switch))
+        .route("/algo/twap", post(submit_twap))
+        .route("/algo/twap/:id", get(get_twap).delete(cancel_twap))
+        .route("/orders/:id", get(get_order).delete(cancel_order).patch(amend_order))
+        .route("/orders/:id/queue", get(get_queue_position))
+        .route("/risk/preview", post(preview_order))
         .route("/orders", get(list_orders))
+        .route("/orderbook/:market", get(get_order_book_depth))
         .route("/positions", get(get_positions))
+        .route("/positions/:market/margin", post(transfer_isolated_margin))
         .route("/balances", get(get_balances))
+        .route("/account/withdrawable", get(get_withdrawable))
+        .route("/sub-accounts", get(list_sub_accounts).post(create_sub_account))
+        .route("/sub-accounts/:id/api-keys", post(issue_sub_account_key))
+        .route("/transfers", post(submit_transfer))
+        .route("/transfers/sub-accounts", post(transfer_sub_account))
+        .route("/funding/payments", get(get_funding_payments))
+        .route("/account/position-mode", post(set_position_mode))
+        .route("/account/margin-mode", post(set_margin_mode))
+        .route("/account/leverage", post(set_leverage))
+        .route("/account/api-key/self-trade-prevention", post(set_key_self_trade_prevention))
+        .route("/account/notifications", get(get_notification_preferences).put(set_notification_preferences))
+        .route("/market/volatility", get(get_volatility))
+        .route("/funding/predicted", get(get_predicted_funding))
+        .route("/tenants/:id/positions", get(get_tenant_positions))
+        .route("/admin/processor", get(get_processor_status))
+        .route("/admin/processor/halt", post(halt_processor))
+        .route("/admin/processor/acknowledge", post(acknowledge_processor_halt))
+        .route("/admin/processor/resume", post(resume_processor))
+        .route("/admin/repair/account", post(repair_account))
+        .route("/admin/recovery", post(run_recovery))
+        .route("/admin/accounts/export", get(export_accounts))
+        .route("/admin/accounts/import", post(import_accounts))
+        .route("/admin/limits/:user_id", post(set_user_limits))
+        .route("/admin/risk-report", get(get_latest_risk_report))
+        .route("/admin/risk-report/:date", get(get_risk_report))
+        .route("/admin/liquidation/stress", post(run_liquidation_stress))
+        .route_layer(middleware::from_fn_with_state(state.api_keys.clone(), api_key_scope_middleware))
+        .layer(middleware::from_fn(stamp_ingress))  // Outermost: runs before authentication
         .with_state(state)
 }
 
@@ -33,6 +131,21 @@ async fn health_check() -> &'static str {
     "OK"
 }
 
+/// Log an account holder's request, unless its event type is disabled at ingress
+async fn produce_user_event(state: &ApiState, event: BaseEvent) -> Result<(), StatusCode> {
+    crate::controls::check_event_type_enabled(event.market_id, event.event_type)
+        .map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
+    state.event_producer.produce(event).await
+        .map(|_| ())
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+/// Charge an order request to the caller's tenant quota (429 once spent)
+async fn charge_tenant(state: &ApiState, user_id: &UserId, endpoint: &str) -> Result<(), StatusCode> {
+    state.tenant_registry.read().await.charge(user_id, endpoint)
+        .map_err(|_| StatusCode::TOO_MANY_REQUESTS)
+}
+
 #[derive(serde::Deserialize)]
 struct OrderRequest {
     user_id: String,
@@ -44,44 +157,170 @@ struct OrderRequest {
     time_in_force: TimeInForce,
     reduce_only: bool,
     post_only: bool,
+    #[serde(default)]
+    trigger_price: Option<i64>,  // Required for StopMarket / StopLimit
+    #[serde(default)]
+    trailing_offset: Option<TrailingOffset>,  // Required for TrailingStop
+    #[serde(default)]
+    position_side: PositionSide,  // Long or Short leg in hedge mode
+    #[serde(default)]
+    min_fill_quantity: Option<i64>,  // IOC only: reject rather than fill less than this
+    #[serde(default)]
+    slippage_limit: Option<f64>,  // Required for market orders: max fraction from mark, e.g. 0.01
 }
 
 async fn submit_order(
     State(state): State<Arc<ApiState>>,
+    Extension(principal): Extension<Principal>,
+    Extension(stamp): Extension<IngressStamp>,  // Taken on receipt, so handler scheduling cannot reorder competing orders
     Json(req): Json<OrderRequest>,
 ) -> Result<Json<OrderAccepted>, StatusCode> {
-    let order_id = OrderId::new();
+    let market_id = validate_order_request(&state, &req)?;
+
+    // Check user balance; keys and tokens only place orders for their owner
+    let user_id = principal.authorize_user(&req.user_id)?;
+    charge_tenant(&state, &user_id, "orders").await?;
+    let account = state.read_models.account(&user_id)
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    // Basic margin check (simplified)
+    let required_margin = req.quantity / 20; // Assuming 20x leverage
+    if account.available_balance().to_i64() < required_margin {
+        return Err(StatusCode::PAYMENT_REQUIRED);
+    }
+
+    let self_trade_prevention = key_self_trade_prevention(&state, &principal).await;
+    let order_submit = order_submit(req, user_id, market_id, self_trade_prevention);
+    let order_id = order_submit.order_id;
+
+    // Publish to event log through the ingress sequencer; accepted only once logged
+    submit_to_ingress(&state, stamp, order_submit).await?;
+    tracing::info!("Order submitted: {:?}", order_id);
+
+    Ok(Json(OrderAccepted {
+        base: crate::events::base::BaseEvent::new(
+            crate::events::base::EventType::OrderAccepted,
+            market_id,
+        ),
+        order_id,
+        user_id,
+    }))
+}
+
+/// Most orders one `/orders/batch` request may carry
+const MAX_BATCH_ORDERS: usize = 20;
+
+#[derive(serde::Deserialize)]
+struct BatchOrderRequest {
+    orders: Vec<OrderRequest>,
+}
+
+/// Place a quote set (one user's orders) in one request
+///
+/// The set is risk-checked as a whole in a single pass against its worst
+/// case (`PreTradeRiskCheck::check_batch`): one failing check refuses every
+/// order. Accepted orders reach the event log in batch order; the engine
+/// still runs its own checks on each.
+async fn submit_order_batch(
+    State(state): State<Arc<ApiState>>,
+    Extension(principal): Extension<Principal>,
+    Extension(stamp): Extension<IngressStamp>,
+    Json(req): Json<BatchOrderRequest>,
+) -> Result<Json<Vec<OrderAccepted>>, StatusCode> {
+    if req.orders.is_empty() || req.orders.len() > MAX_BATCH_ORDERS {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let self_trade_prevention = key_self_trade_prevention(&state, &principal).await;
+    let mut orders = Vec::with_capacity(req.orders.len());
+    for order in req.orders {
+        let market_id = validate_order_request(&state, &order)?;
+        let user_id = principal.authorize_user(&order.user_id)?;
+        orders.push(order_submit(order, user_id, market_id, self_trade_prevention));
+    }
+    let user_id = principal.user_id;
+    charge_tenant(&state, &user_id, "orders_batch").await?;
 
-    // Validate request
+    let account = state.read_models.account(&user_id)
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let position = state.read_models.position(&user_id)
+        .unwrap_or_else(|| Position::new(user_id, state.market_id));
+    let mark_price = *state.mark_price.read().await;
+    if mark_price == Price::zero() {
+        return Err(StatusCode::SERVICE_UNAVAILABLE);  // No price yet to value the quotes at
+    }
+    state.pre_trade_check.check_batch_account(&orders, &position, &account, mark_price)
+        .map_err(|e| match e {
+            Error::InsufficientMargin { .. } | Error::InsufficientBalance => StatusCode::PAYMENT_REQUIRED,
+            _ => StatusCode::UNPROCESSABLE_ENTITY,
+        })?;
+
+    // One stamp per order, in batch order, all released by the same reorder window
+    let accepted: Vec<OrderAccepted> = orders.iter()
+        .map(|order| OrderAccepted {
+            base: BaseEvent::new(crate::events::base::EventType::OrderAccepted, state.market_id),
+            order_id: order.order_id,
+            user_id,
+        })
+        .collect();
+    let submissions = stamp.batch(orders.len()).into_iter()
+        .zip(orders)
+        .map(|(stamp, order)| submit_to_ingress(&state, stamp, order));
+    for outcome in futures::future::join_all(submissions).await {
+        outcome?;
+    }
+    tracing::info!("Order batch submitted: {} orders for {:?}", accepted.len(), user_id);
+
+    Ok(Json(accepted))
+}
+
+/// Shape checks of an order request; the market it names
+fn validate_order_request(state: &ApiState, req: &OrderRequest) -> Result<MarketId, StatusCode> {
     if req.quantity <= 0 {
         return Err(StatusCode::BAD_REQUEST);
     }
 
-    if req.order_type == OrderType::Limit && req.price.is_none() {
+    if matches!(req.order_type, OrderType::Limit | OrderType::StopLimit) && req.price.is_none() {
         return Err(StatusCode::BAD_REQUEST);
     }
 
-    // Check user balance
-    let balance_manager = state.balance_manager.read().await;
-    let account = balance_manager.get_account(UserId::from_string(&req.user_id))
-        .map_err(|_| StatusCode::NOT_FOUND)?;
+    if req.order_type.is_stop() && req.trigger_price.is_none() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
 
-    // Basic margin check (simplified)
-    let required_margin = req.quantity / 20; // Assuming 20x leverage
-    if account.available_balance().to_i64() < required_margin {
-        return Err(StatusCode::PAYMENT_REQUIRED);
+    if req.order_type == OrderType::TrailingStop && req.trailing_offset.is_none() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let market_id = MarketId::from_string(&req.market_id)
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+    if market_id != state.market_id {
+        return Err(StatusCode::NOT_FOUND);
     }
+    Ok(market_id)
+}
 
-    drop(balance_manager);
+/// Per-key self-trade prevention override
+async fn key_self_trade_prevention(state: &ApiState, principal: &Principal) -> Option<SelfTradePrevention> {
+    match &principal.api_key {
+        Some(key) => state.api_keys.read().await.self_trade_prevention_for_key(key),
+        None => None,
+    }
+}
 
-    // Create OrderSubmit event
-    let order_submit = OrderSubmit {
+fn order_submit(
+    req: OrderRequest,
+    user_id: UserId,
+    market_id: MarketId,
+    self_trade_prevention: Option<SelfTradePrevention>,
+) -> OrderSubmit {
+    OrderSubmit {
         base: crate::events::base::BaseEvent::new(
             crate::events::base::EventType::OrderSubmit,
-            MarketId::from_string(&req.market_id),
+            market_id,
         ),
-        order_id,
-        user_id: UserId::from_string(&req.user_id),
+        order_id: OrderId::new(),
+        user_id,
         side: req.side,
         order_type: req.order_type,
         price: req.price.map(Price::from_i64),
@@ -89,43 +328,195 @@ async fn submit_order(
         time_in_force: req.time_in_force,
         reduce_only: req.reduce_only,
         post_only: req.post_only,
+        slippage_limit: req.slippage_limit.map(Ratio::from_f64),
+        trigger_price: req.trigger_price.map(Price::from_i64),
+        trailing_offset: req.trailing_offset,
+        self_trade_prevention,
+        position_side: req.position_side,
+        min_fill_quantity: req.min_fill_quantity.map(Quantity::from_i64),
+    }
+}
+
+/// Hand an order to the ingress sequencer and wait until it is logged
+async fn submit_to_ingress(state: &ApiState, stamp: IngressStamp, order: OrderSubmit) -> Result<(), StatusCode> {
+    state.ingress.submit(stamp, order).await
+        .map_err(|e| match e {
+            Error::IngressQueueFull | Error::IngressClosed | Error::EventTypeDisabled(_) => StatusCode::SERVICE_UNAVAILABLE,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        })
+}
+
+/// Hypothetical order for `/risk/preview`; never submitted
+#[derive(serde::Deserialize)]
+struct PreviewRequest {
+    user_id: String,
+    side: Side,
+    order_type: OrderType,
+    price: Option<i64>,  // Limit price; market orders are projected at mark
+    quantity: i64,
+    #[serde(default)]
+    reduce_only: bool,
+    #[serde(default)]
+    position_side: PositionSide,
+}
+
+/// Run the pre-trade checks against a hypothetical order
+///
+/// Read-only: evaluated on the read models, nothing reaches the event log,
+/// so the answer can differ from the engine's if state moves in between.
+async fn preview_order(
+    State(state): State<Arc<ApiState>>,
+    Extension(principal): Extension<Principal>,
+    Json(req): Json<PreviewRequest>,
+) -> Result<Json<RiskPreview>, StatusCode> {
+    if req.quantity <= 0 {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    if req.order_type == OrderType::Limit && req.price.is_none() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let user_id = principal.authorize_user(&req.user_id)?;
+    let account = state.read_models.account(&user_id)
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let position = state.read_models.position(&user_id)
+        .unwrap_or_else(|| Position::new_leg(user_id, state.market_id, req.position_side));
+    let mark_price = *state.mark_price.read().await;
+    let funding = *state.funding_accrual.read().await;
+
+    let order = OrderSubmit {
+        base: BaseEvent::new(crate::events::base::EventType::OrderSubmit, state.market_id),
+        order_id: OrderId::new(),
+        user_id,
+        side: req.side,
+        order_type: req.order_type,
+        price: req.price.map(Price::from_i64),
+        quantity: Quantity::from_i64(req.quantity),
+        time_in_force: TimeInForce::GTC,
+        reduce_only: req.reduce_only,
+        post_only: false,
         slippage_limit: None,
+        trigger_price: None,
+        trailing_offset: None,
+        self_trade_prevention: None,
+        position_side: req.position_side,
+        min_fill_quantity: None,
     };
 
-    // Publish to event log (would integrate with EventProducer)
-    tracing::info!("Order submitted: {:?}", order_id);
+    // Resting orders and open interest as the engine would count them
+    let book = state.read_models.book();
+    let resting = book.orders_of(&user_id);
+    let (resting_bids, resting_asks) = resting.iter()
+        .fold((Quantity::zero(), Quantity::zero()), |(bids, asks), order| {
+            let unfilled = order.quantity - order.filled;
+            match order.side {
+                Side::Buy => (bids + unfilled, asks),
+                Side::Sell => (bids, asks + unfilled),
+            }
+        });
+    let exposure = OpenExposure {
+        position_size: position.size,
+        resting_bids,
+        resting_asks,
+        open_orders: resting.len(),
+    };
+    let open_interest = open_interest(&state.read_models.all_positions());
+    let overrides = state.read_models.user_limits(&user_id);
 
-    Ok(Json(OrderAccepted {
-        base: crate::events::base::BaseEvent::new(
-            crate::events::base::EventType::OrderAccepted,
-            MarketId::from_string(&req.market_id),
-        ),
-        order_id,
-        user_id: UserId::from_string(&req.user_id),
-    }))
+    let context = PreviewContext {
+        account: &account,
+        position: &position,
+        exposure: &exposure,
+        overrides: overrides.as_ref(),
+        leverage: state.read_models.leverage(&user_id),
+        open_interest,
+        funding: funding.as_ref(),
+    };
+
+    // Only fails if the projected position overflows
+    state.pre_trade_check.preview(&order, &context, mark_price)
+        .map(Json)
+        .map_err(|_| StatusCode::UNPROCESSABLE_ENTITY)
 }
 
 async fn cancel_order(
     State(state): State<Arc<ApiState>>,
+    Extension(principal): Extension<Principal>,
     Path(order_id): Path<String>,
 ) -> Result<StatusCode, StatusCode> {
-    // Parse order_id
-    let order_id = OrderId::from_string(&order_id);
+    let order_id = OrderId::from_string(&order_id)
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
 
-    // Create OrderCancel event
-    let cancel_event = OrderCancel {
-        base: crate::events::base::BaseEvent::new(
-            crate::events::base::EventType::OrderCancel,
-            MarketId::new(),
-        ),
+    // Untriggered stops are not in the book: the EventProcessor checks their owner
+    if let Some((order, _)) = state.read_models.book().orders.get(&order_id)
+        && order.user_id != principal.user_id
+    {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    let cancel = OrderCancel {
+        base: BaseEvent::new(crate::events::base::EventType::OrderCancel, state.market_id),
         order_id,
-        user_id: UserId::new(), // Would get from auth context
+        user_id: principal.user_id,
     };
 
-    // Publish to event log
-    tracing::info!("Order cancelled: {:?}", order_id);
+    let base = cancel.base.clone();
+    produce_user_event(&state, BaseEvent {
+        payload: EventPayload::OrderCancel(Box::new(cancel)),
+        ..base
+    }).await?;
 
-    Ok(StatusCode::OK)
+    Ok(StatusCode::ACCEPTED)
+}
+
+#[derive(serde::Deserialize)]
+struct AmendRequest {
+    user_id: String,
+    price: Option<i64>,
+    quantity: Option<i64>,  // New total quantity, including any filled part
+}
+
+async fn amend_order(
+    State(state): State<Arc<ApiState>>,
+    Extension(principal): Extension<Principal>,
+    Path(order_id): Path<String>,
+    Json(req): Json<AmendRequest>,
+) -> Result<StatusCode, StatusCode> {
+    let order_id = OrderId::from_string(&order_id)
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+    let user_id = principal.authorize_user(&req.user_id)?;
+    charge_tenant(&state, &user_id, "amend_order").await?;
+
+    if req.price.is_none() && req.quantity.is_none() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    if req.price.is_some_and(|p| p <= 0) || req.quantity.is_some_and(|q| q <= 0) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    // Only resting orders can be amended
+    match state.read_models.book().orders.get(&order_id) {
+        Some((order, _)) if order.user_id == user_id => {}
+        Some(_) => return Err(StatusCode::FORBIDDEN),
+        None => return Err(StatusCode::NOT_FOUND),
+    }
+
+    let amend = OrderAmend {
+        base: BaseEvent::new(crate::events::base::EventType::OrderAmend, state.market_id),
+        order_id,
+        user_id,
+        new_price: req.price.map(Price::from_i64),
+        new_quantity: req.quantity.map(Quantity::from_i64),
+    };
+
+    // Validation, margin and priority are decided by the EventProcessor
+    let base = amend.base.clone();
+    produce_user_event(&state, BaseEvent {
+        payload: EventPayload::OrderAmend(Box::new(amend)),
+        ..base
+    }).await?;
+
+    Ok(StatusCode::ACCEPTED)
 }
 
 #[derive(serde::Serialize)]
@@ -137,14 +528,167 @@ struct OrderResponse {
     quantity: i64,
     filled: i64,
     status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reject_reason: Option<RejectReason>,
+}
+
+impl OrderResponse {
+    fn from_order(order: &Order, status: &str) -> Self {
+        OrderResponse {
+            order_id: order.order_id.to_string(),
+            user_id: order.user_id.to_string(),
+            side: format!("{:?}", order.side),
+            price: match order.order_type {
+                OrderType::Limit | OrderType::StopLimit => Some(order.price.to_i64()),
+                OrderType::Market | OrderType::StopMarket | OrderType::TrailingStop => None,
+            },
+            quantity: order.quantity.to_i64(),
+            filled: order.filled.to_i64(),
+            status: status.to_string(),
+            reject_reason: None,
+        }
+    }
+}
+
+async fn get_order(
+    State(state): State<Arc<ApiState>>,
+    Extension(principal): Extension<Principal>,
+    Path(order_id): Path<String>,
+) -> Result<Json<OrderResponse>, StatusCode> {
+    let order_id = OrderId::from_string(&order_id)
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    // Live orders first
+    if let Some((order, _)) = state.read_models.book().orders.get(&order_id) {
+        if order.user_id != principal.user_id {
+            return Err(StatusCode::NOT_FOUND);
+        }
+        return Ok(Json(OrderResponse::from_order(order, "open")));
+    }
+
+    // Recently completed orders from the terminal-order archive
+    let order_archive = state.order_archive.read().await;
+    let archived = order_archive.get(&order_id)
+        .filter(|archived| archived.order.user_id == principal.user_id)
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let status = match archived.status {
+        TerminalStatus::Filled => "filled",
+        TerminalStatus::Cancelled => "cancelled",
+        TerminalStatus::Rejected => "rejected",
+        TerminalStatus::Expired => "expired",
+    };
+
+    let mut response = OrderResponse::from_order(&archived.order, status);
+    response.reject_reason = archived.reject_reason;
+
+    Ok(Json(response))
+}
+
+#[derive(serde::Serialize)]
+struct QueuePositionResponse {
+    order_id: String,
+    price: i64,
+    orders_ahead: usize,
+    quantity_ahead: i64,
+    level_quantity: i64,
+}
+
+/// Estimated queue position of a resting order (404 once it leaves the book)
+async fn get_queue_position(
+    State(state): State<Arc<ApiState>>,
+    Extension(principal): Extension<Principal>,
+    Path(order_id): Path<String>,
+) -> Result<Json<QueuePositionResponse>, StatusCode> {
+    let order_id = OrderId::from_string(&order_id)
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    // Other users' orders are not found, as for `get_order`
+    let book = state.read_models.book();
+    let (_, position) = book.orders.get(&order_id)
+        .filter(|(order, _)| order.user_id == principal.user_id)
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(QueuePositionResponse {
+        order_id: order_id.to_string(),
+        price: position.price.to_i64(),
+        orders_ahead: position.orders_ahead,
+        quantity_ahead: position.quantity_ahead.to_i64(),
+        level_quantity: position.level_quantity.to_i64(),
+    }))
+}
+
+/// Levels per side returned when `depth` is not given
+const DEFAULT_DEPTH_LEVELS: usize = 20;
+
+#[derive(serde::Deserialize)]
+struct DepthQuery {
+    depth: Option<usize>,
+}
+
+#[derive(serde::Serialize)]
+struct DepthLevelResponse {
+    price: i64,
+    quantity: i64,
+    orders: usize,
+}
+
+#[derive(serde::Serialize)]
+struct DepthResponse {
+    market_id: String,
+    published_at: Option<u64>,  // Read model publish time (ms)
+    bids: Vec<DepthLevelResponse>,
+    asks: Vec<DepthLevelResponse>,
+}
+
+/// Aggregated book levels, best first (`?depth=N` levels per side)
+async fn get_order_book_depth(
+    State(state): State<Arc<ApiState>>,
+    Path(market): Path<String>,
+    Query(query): Query<DepthQuery>,
+) -> Result<Json<DepthResponse>, StatusCode> {
+    let market_id = MarketId::from_string(&market)
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+    if market_id != state.market_id {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    let levels = query.depth.unwrap_or(DEFAULT_DEPTH_LEVELS).min(READ_MODEL_DEPTH_LEVELS);
+    let book = state.read_models.book();
+    let depth = book.depth.truncated(levels);
+
+    let to_response = |levels: Vec<DepthLevel>| levels.into_iter()
+        .map(|level| DepthLevelResponse {
+            price: level.price.to_i64(),
+            quantity: level.quantity.to_i64(),
+            orders: level.orders,
+        })
+        .collect();
+
+    Ok(Json(DepthResponse {
+        market_id: market_id.to_string(),
+        published_at: book.published_at.map(|at| at.physical),
+        bids: to_response(depth.bids),
+        asks: to_response(depth.asks),
+    }))
+}
+
+#[derive(serde::Deserialize)]
+struct ListOrdersQuery {
+    user_id: String,
 }
 
+/// Open orders of one user, oldest first
 async fn list_orders(
     State(state): State<Arc<ApiState>>,
+    Extension(principal): Extension<Principal>,
+    Query(query): Query<ListOrdersQuery>,
 ) -> Result<Json<Vec<OrderResponse>>, StatusCode> {
-    // In production, would query order book or database
-    // For now, return empty list
-    let orders = vec![];
+    let user_id = principal.authorize_user(&query.user_id)?;
+
+    let book = state.read_models.book();
+    let orders = book.orders_of(&user_id).into_iter()
+        .map(|order| OrderResponse::from_order(order, "open"))
+        .collect();
     Ok(Json(orders))
 }
 
@@ -156,28 +700,68 @@ struct PositionResponse {
     entry_price: i64,
     unrealized_pnl: i64,
     margin_ratio: f64,
+    margin_mode: MarginMode,
+    isolated_margin: i64,
+    liquidation_price: Option<i64>,  // Cross legs share the account's price
+    bankruptcy_price: Option<i64>,
 }
 
 async fn get_positions(
     State(state): State<Arc<ApiState>>,
+    Extension(principal): Extension<Principal>,
 ) -> Result<Json<Vec<PositionResponse>>, StatusCode> {
-    let position_manager = state.position_manager.read().await;
-
-    // Get all positions (in production, filter by user from auth)
-    let positions: Vec<PositionResponse> = position_manager.positions.values()
-        .map(|p| PositionResponse {
-            user_id: format!("{:?}", p.user_id),
-            market_id: format!("{:?}", p.market_id),
-            size: p.size,
-            entry_price: p.entry_price.to_i64(),
-            unrealized_pnl: 0, // Would calculate from current mark price
-            margin_ratio: 0.0, // Would calculate from balance and position
+    let mark_price = *state.mark_price.read().await;
+    let funding = *state.funding_accrual.read().await;
+
+    // The caller's legs; liquidation prices still need the whole market
+    let all_positions = state.read_models.all_positions();
+    let open_interest = open_interest(&all_positions);
+    let positions: Vec<PositionResponse> = all_positions.iter()
+        .filter(|p| p.user_id == principal.user_id)
+        .map(|p| {
+            let prices = position_liquidation_prices(&state, p, &all_positions, mark_price, open_interest, funding.as_ref());
+            PositionResponse {
+                user_id: format!("{:?}", p.user_id),
+                market_id: format!("{:?}", p.market_id),
+                size: p.size,
+                entry_price: p.entry_price.to_i64(),
+                unrealized_pnl: 0, // Would calculate from current mark price
+                margin_ratio: 0.0, // Would calculate from balance and position
+                margin_mode: p.margin_mode,
+                isolated_margin: p.isolated_margin.to_i64(),
+                liquidation_price: prices.liquidation_price.map(|price| price.to_i64()),
+                bankruptcy_price: prices.bankruptcy_price.map(|price| price.to_i64()),
+            }
         })
         .collect();
 
     Ok(Json(positions))
 }
 
+/// Market open interest (sum of long sizes)
+fn open_interest(positions: &[Position]) -> Quantity {
+    positions.iter()
+        .filter(|p| p.size > 0)
+        .fold(Quantity::zero(), |total, p| total + p.abs_size())
+}
+
+/// Liquidation and bankruptcy prices as the liquidation detector sees them
+fn position_liquidation_prices(
+    state: &ApiState,
+    position: &Position,
+    all_positions: &[Position],
+    mark_price: Price,
+    open_interest: Quantity,
+    funding: Option<&FundingAccrual>,
+) -> LiquidationPrices {
+    let balance = state.read_models.account(&position.user_id).map_or(Balance::zero(), |account| account.balance);
+    let account_legs: Vec<&Position> = all_positions.iter()
+        .filter(|p| p.user_id == position.user_id)
+        .collect();
+
+    leg_liquidation_prices(&state.margin_calculator, position, &account_legs, balance, mark_price, open_interest, funding)
+}
+
 #[derive(serde::Serialize)]
 struct BalanceResponse {
     user_id: String,
@@ -189,11 +773,10 @@ struct BalanceResponse {
 
 async fn get_balances(
     State(state): State<Arc<ApiState>>,
+    Extension(principal): Extension<Principal>,
 ) -> Result<Json<Vec<BalanceResponse>>, StatusCode> {
-    let balance_manager = state.balance_manager.read().await;
-
-    // Get all balances (in production, filter by user from auth)
-    let balances: Vec<BalanceResponse> = balance_manager.accounts.values()
+    let balances: Vec<BalanceResponse> = state.read_models.all_accounts().iter()
+        .filter(|a| a.user_id == principal.user_id)
         .map(|a| BalanceResponse {
             user_id: format!("{:?}", a.user_id),
             balance: a.balance.to_i64(),
@@ -204,4 +787,1401 @@ async fn get_balances(
         .collect();
 
     Ok(Json(balances))
-}
\ No newline at end of file
+}
+
+#[derive(serde::Deserialize)]
+struct WithdrawableQuery {
+    user_id: String,
+}
+
+#[derive(serde::Serialize)]
+struct WithdrawableResponse {
+    user_id: String,
+    withdrawable: i64,
+    available_balance: i64,
+    min_margin_ratio: f64,
+}
+
+async fn get_withdrawable(
+    State(state): State<Arc<ApiState>>,
+    Extension(principal): Extension<Principal>,
+    Query(query): Query<WithdrawableQuery>,
+) -> Result<Json<WithdrawableResponse>, StatusCode> {
+    let user_id = principal.authorize_user(&query.user_id)?;
+    let mark_price = *state.mark_price.read().await;
+    if mark_price == Price::zero() {
+        return Err(StatusCode::SERVICE_UNAVAILABLE);  // No price yet to value the position at
+    }
+
+    let account = state.read_models.account(&user_id)
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let positions = state.read_models.positions_for(&user_id);
+
+    // Withdrawals are applied by the processor as they are consumed, so
+    // nothing is held as pending between request and settlement
+    let funding = *state.funding_accrual.read().await;
+    let withdrawable = state.withdrawal_check.max_withdrawable(
+        &account,
+        &positions.iter().collect::<Vec<_>>(),
+        mark_price,
+        funding.as_ref(),
+        Balance::zero(),
+    ).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(WithdrawableResponse {
+        user_id: query.user_id,
+        withdrawable: withdrawable.to_i64(),
+        available_balance: account.available_balance().to_i64(),
+        min_margin_ratio: state.withdrawal_check.min_margin_ratio(),
+    }))
+}
+
+#[derive(serde::Deserialize)]
+struct FundingPaymentsQuery {
+    user_id: String,
+    from: Option<u64>,  // Epoch millis, inclusive
+    to: Option<u64>,    // Epoch millis, exclusive
+}
+
+#[derive(serde::Serialize)]
+struct FundingPaymentResponse {
+    market_id: String,
+    amount: i64,  // Positive = received, negative = paid
+    funding_rate: f64,
+    position_size: i64,
+    mark_price: i64,
+    timestamp: u64,
+}
+
+/// Funding payments applied to a user, oldest first
+async fn get_funding_payments(
+    State(state): State<Arc<ApiState>>,
+    Extension(principal): Extension<Principal>,
+    Query(query): Query<FundingPaymentsQuery>,
+) -> Result<Json<Vec<FundingPaymentResponse>>, StatusCode> {
+    let user_id = principal.authorize_user(&query.user_id)?;
+    let from = Timestamp::from_millis(query.from.unwrap_or(0));
+    let to = Timestamp::from_millis(query.to.unwrap_or(u64::MAX));
+    if from > to {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let history = state.funding_history.read().await;
+    let payments = history.payments(&user_id, from, to).into_iter()
+        .map(|record| FundingPaymentResponse {
+            market_id: record.market_id.to_string(),
+            amount: record.amount.to_i64(),
+            funding_rate: record.funding_rate.to_f64(),
+            position_size: record.position_size.to_i64(),
+            mark_price: record.mark_price.to_i64(),
+            timestamp: record.timestamp.physical,
+        })
+        .collect();
+    Ok(Json(payments))
+}
+
+/// Realized volatility of the index price over the rolling window
+async fn get_volatility(
+    State(state): State<Arc<ApiState>>,
+) -> Result<Json<VolatilityStats>, StatusCode> {
+    // None until the window holds two samples
+    state.volatility.read().await
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Next funding rate at the running premium EMA
+async fn get_predicted_funding(
+    State(state): State<Arc<ApiState>>,
+) -> Result<Json<PredictedFunding>, StatusCode> {
+    // None for dated futures and until the first price with an index
+    state.predicted_funding.latest()
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+async fn get_tenant_positions(
+    State(state): State<Arc<ApiState>>,
+    Path(tenant_id): Path<String>,
+) -> Result<Json<TenantPositionSummary>, StatusCode> {
+    let tenant_id = uuid::Uuid::parse_str(&tenant_id)
+        .map(TenantId)
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    let tenant_registry = state.tenant_registry.read().await;
+    tenant_registry.check_quota(tenant_id)
+        .map_err(|_| StatusCode::TOO_MANY_REQUESTS)?;
+
+    use crate::observability::metrics::API_REQUESTS;
+    API_REQUESTS.with_label_values(&[&tenant_id.to_string(), "tenant_positions"]).inc();
+
+    let summary = tenant_registry.aggregate_positions(tenant_id, |user_id| state.read_models.position(user_id))
+        .map_err(|_| StatusCode::NOT_FOUND)?;
+
+    Ok(Json(summary))
+}
+
+#[derive(serde::Serialize)]
+struct ProcessorStatusResponse {
+    halted: bool,
+    halt: Option<ProcessorHaltState>,
+}
+
+#[derive(serde::Deserialize)]
+struct KeySelfTradePreventionRequest {
+    mode: Option<SelfTradePrevention>,  // None clears the override
+}
+
+/// Self-trade prevention mode for orders sent with the calling API key,
+/// overriding the account and market modes
+async fn set_key_self_trade_prevention(
+    State(state): State<Arc<ApiState>>,
+    Extension(principal): Extension<Principal>,
+    Json(req): Json<KeySelfTradePreventionRequest>,
+) -> Result<StatusCode, StatusCode> {
+    // Tokens have no key to attach the override to
+    let key = principal.api_key.as_deref().ok_or(StatusCode::BAD_REQUEST)?;
+
+    state.api_keys.write().await
+        .set_self_trade_prevention(key, req.mode)
+        .map_err(|e| {
+            tracing::error!("Failed to save API key override: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    Ok(StatusCode::OK)
+}
+
+#[derive(serde::Deserialize)]
+struct NotificationPreferencesQuery {
+    user_id: String,
+}
+
+/// Channels and kinds a user is notified on (defaults if never set)
+async fn get_notification_preferences(
+    State(state): State<Arc<ApiState>>,
+    Extension(principal): Extension<Principal>,
+    Query(query): Query<NotificationPreferencesQuery>,
+) -> Result<Json<UserNotificationPreferences>, StatusCode> {
+    let user_id = principal.authorize_user(&query.user_id)?;
+
+    Ok(Json(state.notification_preferences.read().await.get(&user_id)))
+}
+
+async fn set_notification_preferences(
+    State(state): State<Arc<ApiState>>,
+    Extension(principal): Extension<Principal>,
+    Query(query): Query<NotificationPreferencesQuery>,
+    Json(preferences): Json<UserNotificationPreferences>,
+) -> Result<Json<UserNotificationPreferences>, StatusCode> {
+    let user_id = principal.authorize_user(&query.user_id)?;
+
+    let mut store = state.notification_preferences.write().await;
+    match store.set(user_id, preferences) {
+        Ok(()) => Ok(Json(store.get(&user_id))),
+        Err(Error::ConfigError(_)) => Err(StatusCode::BAD_REQUEST),
+        Err(e) => {
+            tracing::error!("Failed to save notification preferences: {:?}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct PositionModeRequest {
+    user_id: String,
+    mode: PositionMode,
+}
+
+/// Switch between one-way and hedge mode; applied by the engine only while
+/// the account has no open position or resting order
+async fn set_position_mode(
+    State(state): State<Arc<ApiState>>,
+    Extension(principal): Extension<Principal>,
+    Json(req): Json<PositionModeRequest>,
+) -> Result<StatusCode, StatusCode> {
+    let user_id = principal.authorize_user(&req.user_id)?;
+
+    let change = PositionModeChange {
+        base: BaseEvent::new(crate::events::base::EventType::PositionModeChange, state.market_id),
+        user_id,
+        mode: req.mode,
+    };
+
+    let base = change.base.clone();
+    produce_user_event(&state, BaseEvent {
+        payload: EventPayload::PositionModeChange(Box::new(change)),
+        ..base
+    }).await?;
+
+    Ok(StatusCode::ACCEPTED)
+}
+
+#[derive(serde::Deserialize)]
+struct MarginModeRequest {
+    user_id: String,
+    mode: MarginMode,
+}
+
+/// Switch between cross and isolated margin; applied by the engine only
+/// while the account has no open position or resting order
+async fn set_margin_mode(
+    State(state): State<Arc<ApiState>>,
+    Extension(principal): Extension<Principal>,
+    Json(req): Json<MarginModeRequest>,
+) -> Result<StatusCode, StatusCode> {
+    let user_id = principal.authorize_user(&req.user_id)?;
+
+    let change = MarginModeChange {
+        base: BaseEvent::new(crate::events::base::EventType::MarginModeChange, state.market_id),
+        user_id,
+        mode: req.mode,
+    };
+
+    let base = change.base.clone();
+    produce_user_event(&state, BaseEvent {
+        payload: EventPayload::MarginModeChange(Box::new(change)),
+        ..base
+    }).await?;
+
+    Ok(StatusCode::ACCEPTED)
+}
+
+#[derive(serde::Deserialize)]
+struct LeverageRequest {
+    user_id: String,
+    leverage: f64,
+}
+
+/// Choose leverage for this market; the engine refuses it above the risk
+/// tier cap of the account's position or when open orders can't cover the
+/// extra margin
+async fn set_leverage(
+    State(state): State<Arc<ApiState>>,
+    Extension(principal): Extension<Principal>,
+    Json(req): Json<LeverageRequest>,
+) -> Result<StatusCode, StatusCode> {
+    let user_id = principal.authorize_user(&req.user_id)?;
+    if req.leverage.is_nan() || req.leverage < 1.0 {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let change = SetLeverage {
+        base: BaseEvent::new(crate::events::base::EventType::SetLeverage, state.market_id),
+        user_id,
+        leverage: req.leverage,
+    };
+
+    let base = change.base.clone();
+    produce_user_event(&state, BaseEvent {
+        payload: EventPayload::SetLeverage(Box::new(change)),
+        ..base
+    }).await?;
+
+    Ok(StatusCode::ACCEPTED)
+}
+
+#[derive(serde::Deserialize)]
+struct IsolatedMarginRequest {
+    user_id: String,
+    amount: i64,  // Positive adds to the position's bucket, negative removes
+    #[serde(default)]
+    position_side: PositionSide,
+}
+
+/// Add or remove collateral on an isolated position; the engine refuses
+/// removals that would leave the position below initial margin
+async fn transfer_isolated_margin(
+    State(state): State<Arc<ApiState>>,
+    Extension(principal): Extension<Principal>,
+    Path(market): Path<String>,
+    Json(req): Json<IsolatedMarginRequest>,
+) -> Result<StatusCode, StatusCode> {
+    let market_id = MarketId::from_string(&market)
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+    if market_id != state.market_id {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    let user_id = principal.authorize_user(&req.user_id)?;
+    if req.amount == 0 {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let transfer = IsolatedMarginTransfer {
+        base: BaseEvent::new(crate::events::base::EventType::IsolatedMarginTransfer, state.market_id),
+        user_id,
+        position_side: req.position_side,
+        amount: Balance::from_i64(req.amount),
+    };
+
+    let base = transfer.base.clone();
+    produce_user_event(&state, BaseEvent {
+        payload: EventPayload::IsolatedMarginTransfer(Box::new(transfer)),
+        ..base
+    }).await?;
+
+    Ok(StatusCode::ACCEPTED)
+}
+
+#[derive(serde::Deserialize)]
+struct CreateSubAccountRequest {
+    user_id: String,  // Master account
+    label: String,
+}
+
+#[derive(serde::Serialize)]
+struct SubAccountResponse {
+    sub_account_id: String,
+    master_id: String,
+    label: String,
+}
+
+/// Open a sub-account under the caller; the ID is assigned here and the
+/// account exists once the engine has processed the event
+async fn create_sub_account(
+    State(state): State<Arc<ApiState>>,
+    Extension(principal): Extension<Principal>,
+    Json(req): Json<CreateSubAccountRequest>,
+) -> Result<(StatusCode, Json<SubAccountResponse>), StatusCode> {
+    let master_id = principal.authorize_user(&req.user_id)?;
+    let master = state.read_models.account(&master_id)
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let siblings: Vec<Account> = state.read_models.all_accounts().into_iter()
+        .filter(|account| account.master_id == Some(master_id))
+        .collect();
+    sub_accounts::validate_new_sub_account(&master, &siblings.iter().collect::<Vec<_>>(), &req.label)
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    let created = SubAccountCreated {
+        base: BaseEvent::new(crate::events::base::EventType::SubAccountCreated, state.market_id),
+        master_id,
+        sub_account_id: UserId::new(),
+        label: req.label,
+    };
+    let response = SubAccountResponse {
+        sub_account_id: created.sub_account_id.to_string(),
+        master_id: req.user_id,
+        label: created.label.clone(),
+    };
+
+    let base = created.base.clone();
+    produce_user_event(&state, BaseEvent {
+        payload: EventPayload::SubAccountCreated(Box::new(created)),
+        ..base
+    }).await?;
+
+    Ok((StatusCode::ACCEPTED, Json(response)))
+}
+
+#[derive(serde::Deserialize)]
+struct SubAccountsQuery {
+    user_id: String,  // Master account
+}
+
+async fn list_sub_accounts(
+    State(state): State<Arc<ApiState>>,
+    Extension(principal): Extension<Principal>,
+    Query(query): Query<SubAccountsQuery>,
+) -> Result<Json<Vec<SubAccountResponse>>, StatusCode> {
+    let master_id = principal.authorize_user(&query.user_id)?;
+
+    let mut subs: Vec<Account> = state.read_models.all_accounts().into_iter()
+        .filter(|account| account.master_id == Some(master_id))
+        .collect();
+    subs.sort_by_key(|account| (account.created_at, account.user_id.0));
+
+    Ok(Json(subs.into_iter()
+        .map(|account| SubAccountResponse {
+            sub_account_id: account.user_id.to_string(),
+            master_id: query.user_id.clone(),
+            label: account.label.unwrap_or_default(),
+        })
+        .collect()))
+}
+
+#[derive(serde::Deserialize)]
+struct SubAccountKeyRequest {
+    user_id: String,  // Master account
+    #[serde(default)]
+    scopes: Option<HashSet<ApiKeyScope>>,  // Trading only when omitted; never Admin
+}
+
+#[derive(serde::Serialize)]
+struct SubAccountKeyResponse {
+    sub_account_id: String,
+    api_key: String,
+    scopes: HashSet<ApiKeyScope>,
+}
+
+/// Issue an API key acting as one of the caller's sub-accounts
+async fn issue_sub_account_key(
+    State(state): State<Arc<ApiState>>,
+    Extension(principal): Extension<Principal>,
+    Path(id): Path<String>,
+    Json(req): Json<SubAccountKeyRequest>,
+) -> Result<Json<SubAccountKeyResponse>, StatusCode> {
+    let master_id = principal.authorize_user(&req.user_id)?;
+    let sub_account_id = UserId::from_string(&id)
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+    let sub_account = state.read_models.account(&sub_account_id)
+        .ok_or(StatusCode::NOT_FOUND)?;
+    if sub_account.master_id != Some(master_id) {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    let scopes = req.scopes.unwrap_or_else(default_scopes);
+    if scopes.is_empty() || scopes.contains(&ApiKeyScope::Admin) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let api_key = format!("{}{}", uuid::Uuid::new_v4().simple(), uuid::Uuid::new_v4().simple());
+    let mut api_keys = state.api_keys.write().await;
+    api_keys.add_sub_account_key(api_key.clone(), sub_account_id, master_id, scopes.clone());
+    if let Err(e) = api_keys.save() {
+        tracing::error!("Failed to save API keys: {:?}", e);
+        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+    }
+    drop(api_keys);
+    tracing::info!("API key issued for sub-account {:?} by {:?}", sub_account_id, master_id);
+
+    Ok(Json(SubAccountKeyResponse {
+        sub_account_id: id,
+        api_key,
+        scopes,
+    }))
+}
+
+#[derive(serde::Deserialize)]
+struct SubAccountTransferRequest {
+    user_id: String,  // Master account
+    from_user: String,
+    to_user: String,
+    amount: i64,
+}
+
+/// Move funds between the caller and its sub-accounts, or between two of
+/// them; the engine refuses amounts the source cannot withdraw
+async fn transfer_sub_account(
+    State(state): State<Arc<ApiState>>,
+    Extension(principal): Extension<Principal>,
+    Json(req): Json<SubAccountTransferRequest>,
+) -> Result<StatusCode, StatusCode> {
+    let parse = |id: &str| UserId::from_string(id).map_err(|_| StatusCode::BAD_REQUEST);
+    let (master_id, from_user, to_user) = (principal.authorize_user(&req.user_id)?, parse(&req.from_user)?, parse(&req.to_user)?);
+    if req.amount <= 0 || from_user == to_user {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    for user_id in [from_user, to_user] {
+        let account = state.read_models.account(&user_id)
+            .ok_or(StatusCode::NOT_FOUND)?;
+        sub_accounts::check_family(master_id, &account)
+            .map_err(|_| StatusCode::FORBIDDEN)?;
+    }
+
+    let transfer = SubAccountTransfer {
+        base: BaseEvent::new(crate::events::base::EventType::SubAccountTransfer, state.market_id),
+        master_id,
+        from_user,
+        to_user,
+        amount: Balance::from_i64(req.amount),
+    };
+
+    let base = transfer.base.clone();
+    produce_user_event(&state, BaseEvent {
+        payload: EventPayload::SubAccountTransfer(Box::new(transfer)),
+        ..base
+    }).await?;
+
+    Ok(StatusCode::ACCEPTED)
+}
+
+#[derive(serde::Deserialize)]
+struct TransferRequest {
+    #[serde(default)]
+    user_id: Option<String>,  // Sender; the caller, and refused if it names anyone else
+    to_user: String,
+    amount: i64,
+    #[serde(default)]
+    reference_id: Option<String>,
+}
+
+/// Sender and recipient of a transfer; funds only ever leave the caller's
+/// own account
+fn transfer_parties(principal: &Principal, req: &TransferRequest) -> Result<(UserId, UserId), StatusCode> {
+    let from_user = match &req.user_id {
+        Some(user_id) => principal.authorize_user(user_id)?,
+        None => principal.user_id,
+    };
+    let to_user = UserId::from_string(&req.to_user)
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+    if req.amount <= 0 || from_user == to_user {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    Ok((from_user, to_user))
+}
+
+/// Move funds from the caller to another account; the engine refuses
+/// amounts the caller cannot withdraw
+async fn submit_transfer(
+    State(state): State<Arc<ApiState>>,
+    Extension(principal): Extension<Principal>,
+    Json(req): Json<TransferRequest>,
+) -> Result<StatusCode, StatusCode> {
+    let (from_user, to_user) = transfer_parties(&principal, &req)?;
+    if state.read_models.account(&from_user).is_none() || state.read_models.account(&to_user).is_none() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    let transfer = Transfer {
+        base: BaseEvent::new(crate::events::base::EventType::Transfer, state.market_id),
+        from_user,
+        to_user,
+        amount: Balance::from_i64(req.amount),
+        reference_id: req.reference_id,
+    };
+
+    let base = transfer.base.clone();
+    produce_user_event(&state, BaseEvent {
+        payload: EventPayload::Transfer(Box::new(transfer)),
+        ..base
+    }).await?;
+
+    Ok(StatusCode::ACCEPTED)
+}
+
+#[derive(serde::Deserialize)]
+struct DeadMansSwitchRequest {
+    user_id: String,
+    timeout_ms: u64,  // 0 disarms
+}
+
+#[derive(serde::Serialize)]
+struct DeadMansSwitchResponse {
+    armed: bool,
+    switch: Option<SwitchState>,
+}
+
+/// Arm, heartbeat (re-arm before expiry) or disarm (timeout_ms = 0) the
+/// caller's dead man's switch
+async fn arm_deadmans_switch(
+    State(state): State<Arc<ApiState>>,
+    Extension(principal): Extension<Principal>,
+    Json(req): Json<DeadMansSwitchRequest>,
+) -> Result<Json<DeadMansSwitchResponse>, StatusCode> {
+    let user_id = principal.authorize_user(&req.user_id)?;
+
+    let mut switches = state.deadmans_switch.write().await;
+    if req.timeout_ms == 0 {
+        switches.disarm(&user_id);
+        return Ok(Json(DeadMansSwitchResponse { armed: false, switch: None }));
+    }
+
+    let switch = switches.arm(
+        user_id,
+        std::time::Duration::from_millis(req.timeout_ms),
+        crate::types::timestamp::Timestamp::now(),
+    ).map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    Ok(Json(DeadMansSwitchResponse { armed: true, switch: Some(switch) }))
+}
+
+#[derive(serde::Deserialize)]
+struct TwapOrderRequest {
+    user_id: String,
+    side: Side,
+    quantity: i64,
+    duration_secs: u64,
+    slice_interval_secs: u64,
+    #[serde(default)]
+    limit_price: Option<i64>,  // Omitted: market slices
+    #[serde(default)]
+    slippage_limit: Option<f64>,  // Required for market slices: max fraction from mark, e.g. 0.01
+    #[serde(default)]
+    reduce_only: bool,
+    #[serde(default)]
+    position_side: PositionSide,
+}
+
+/// Start a TWAP parent order; child orders carry its id as correlation_id
+async fn submit_twap(
+    State(state): State<Arc<ApiState>>,
+    Extension(principal): Extension<Principal>,
+    Json(req): Json<TwapOrderRequest>,
+) -> Result<Json<TwapState>, StatusCode> {
+    let user_id = principal.authorize_user(&req.user_id)?;
+    charge_tenant(&state, &user_id, "twap").await?;
+
+    let request = TwapRequest {
+        user_id,
+        side: req.side,
+        total_quantity: Quantity::from_i64(req.quantity),
+        duration: std::time::Duration::from_secs(req.duration_secs),
+        slice_interval: std::time::Duration::from_secs(req.slice_interval_secs),
+        limit_price: req.limit_price.map(Price::from_i64),
+        slippage_limit: req.slippage_limit.map(Ratio::from_f64),
+        reduce_only: req.reduce_only,
+        position_side: req.position_side,
+    };
+
+    let twap = state.twap_engine.write().await
+        .submit(request, crate::types::timestamp::Timestamp::now())
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    Ok(Json(twap))
+}
+
+async fn get_twap(
+    State(state): State<Arc<ApiState>>,
+    Extension(principal): Extension<Principal>,
+    Path(parent_id): Path<String>,
+) -> Result<Json<TwapState>, StatusCode> {
+    let parent_id = CorrelationId::from_header(&parent_id)
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    let twaps = state.twap_engine.read().await;
+    let twap = twaps.get(&parent_id).ok_or(StatusCode::NOT_FOUND)?;
+    if twap.user_id != principal.user_id {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    Ok(Json(twap.clone()))
+}
+
+/// Stop a TWAP's remaining slices (children already sent are unaffected)
+async fn cancel_twap(
+    State(state): State<Arc<ApiState>>,
+    Extension(principal): Extension<Principal>,
+    Path(parent_id): Path<String>,
+) -> Result<Json<TwapState>, StatusCode> {
+    let parent_id = CorrelationId::from_header(&parent_id)
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    let mut twaps = state.twap_engine.write().await;
+    match twaps.get(&parent_id) {
+        Some(twap) if twap.user_id == principal.user_id => {}
+        Some(_) => return Err(StatusCode::FORBIDDEN),
+        None => return Err(StatusCode::NOT_FOUND),
+    }
+    twaps.cancel(&parent_id)
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+#[derive(serde::Deserialize)]
+struct CancelAllRequest {
+    #[serde(default)]
+    user_id: Option<String>,      // Omitted: every user in the market (operator only)
+    #[serde(default)]
+    reason: String,
+}
+
+/// Panic button: cancel every resting order of the caller, or of the market
+/// (callers with the Admin scope who are authorized operators)
+async fn cancel_all_orders(
+    State(state): State<Arc<ApiState>>,
+    Extension(principal): Extension<Principal>,
+    Json(req): Json<CancelAllRequest>,
+) -> Result<StatusCode, StatusCode> {
+    let (user_id, operator_id) = match req.user_id.as_deref() {
+        Some(user_id) => (Some(principal.authorize_user(user_id)?), None),
+        None => {
+            let operator_id = principal.operator_id()?;
+            if !crate::utils::helper::is_authorized_operator(operator_id) {
+                return Err(StatusCode::FORBIDDEN);
+            }
+            (None, Some(operator_id))
+        }
+    };
+
+    let cancel_all = CancelAllOrders {
+        base: BaseEvent::new(crate::events::base::EventType::CancelAllOrders, state.market_id),
+        user_id,
+        operator_id,
+        reason: req.reason,
+    };
+
+    let base = cancel_all.base.clone();
+    produce_user_event(&state, BaseEvent {
+        payload: EventPayload::CancelAllOrders(Box::new(cancel_all)),
+        ..base
+    }).await?;
+
+    Ok(StatusCode::ACCEPTED)
+}
+
+#[derive(serde::Deserialize)]
+struct ProcessorControlRequest {
+    #[serde(default)]
+    note: String,
+}
+
+fn control_error_status(error: &Error) -> StatusCode {
+    match error {
+        Error::Unauthorized => StatusCode::FORBIDDEN,
+        Error::ProcessorNotHalted => StatusCode::CONFLICT,
+        Error::HaltNotAcknowledged => StatusCode::PRECONDITION_FAILED,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+fn parse_operator(operator_id: &str) -> Result<OperatorId, StatusCode> {
+    uuid::Uuid::parse_str(operator_id)
+        .map(OperatorId)
+        .map_err(|_| StatusCode::BAD_REQUEST)
+}
+
+/// Run a halt-control action on the event loop that owns the processor
+async fn control_processor(
+    state: &ApiState,
+    action: ProcessorAction,
+) -> Result<Json<ProcessorStatusResponse>, StatusCode> {
+    let (reply, response) = oneshot::channel();
+    state.processor_tx.send(ProcessorCommand { action, reply })
+        .await
+        .map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
+
+    let halt = response.await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .map_err(|e| control_error_status(&e))?;
+    Ok(Json(ProcessorStatusResponse { halted: halt.is_some(), halt }))
+}
+
+async fn get_processor_status(
+    State(state): State<Arc<ApiState>>,
+) -> Result<Json<ProcessorStatusResponse>, StatusCode> {
+    control_processor(&state, ProcessorAction::Status).await
+}
+
+async fn halt_processor(
+    State(state): State<Arc<ApiState>>,
+    Extension(principal): Extension<Principal>,
+    Json(req): Json<ProcessorControlRequest>,
+) -> Result<Json<ProcessorStatusResponse>, StatusCode> {
+    let operator_id = principal.operator_id()?;
+    control_processor(&state, ProcessorAction::Halt { operator_id, note: req.note }).await
+}
+
+async fn acknowledge_processor_halt(
+    State(state): State<Arc<ApiState>>,
+    Extension(principal): Extension<Principal>,
+) -> Result<Json<ProcessorStatusResponse>, StatusCode> {
+    let operator_id = principal.operator_id()?;
+    control_processor(&state, ProcessorAction::Acknowledge { operator_id }).await
+}
+
+async fn resume_processor(
+    State(state): State<Arc<ApiState>>,
+    Extension(principal): Extension<Principal>,
+) -> Result<Json<ProcessorStatusResponse>, StatusCode> {
+    let operator_id = principal.operator_id()?;
+    control_processor(&state, ProcessorAction::Resume { operator_id }).await
+}
+
+#[derive(serde::Deserialize)]
+struct RepairAccountRequest {
+    operator_id: String,
+    user_id: String,
+    snapshot_sequence: u64,
+    scope: RepairScope,
+    reason: String,
+    #[serde(default)]
+    dry_run: bool,
+}
+
+#[derive(serde::Serialize)]
+struct RepairAccountResponse {
+    plan: RepairPlan,
+    balance_delta: Balance,
+    applied: bool,  // false for a dry run
+}
+
+/// Restore one account's balance and/or position from a snapshot
+/// The repair goes through the event log as a StateRepair event; a dry run
+/// only returns the plan for review
+async fn repair_account(
+    State(state): State<Arc<ApiState>>,
+    Json(req): Json<RepairAccountRequest>,
+) -> Result<Json<RepairAccountResponse>, StatusCode> {
+    let operator_id = parse_operator(&req.operator_id)?;
+    let user_id = UserId::from_string(&req.user_id)
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    if !req.scope.balance && !req.scope.position {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let snapshot = state.snapshot_manager
+        .load_snapshot_at_sequence(state.market_id, req.snapshot_sequence)
+        .await
+        .map_err(|_| StatusCode::NOT_FOUND)?;
+
+    let plan = plan_account_repair(
+        &snapshot,
+        user_id,
+        req.scope,
+        state.read_models.account(&user_id).as_ref(),
+        state.read_models.position(&user_id).as_ref(),
+        operator_id,
+        req.reason,
+    ).map_err(|e| match e {
+        Error::AccountNotFound(_) => StatusCode::NOT_FOUND,
+        e => control_error_status(&e),
+    })?;
+
+    let balance_delta = plan.balance_delta();
+
+    if !req.dry_run {
+        state.event_producer.produce(plan.clone().into_event())
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+        tracing::warn!(
+            "State repair submitted: user={:?} snapshot seq={} operator={:?}",
+            user_id, req.snapshot_sequence, operator_id
+        );
+    }
+
+    Ok(Json(RepairAccountResponse { plan, balance_delta, applied: !req.dry_run }))
+}
+
+#[derive(serde::Deserialize)]
+struct UserLimitsRequest {
+    operator_id: String,
+    max_open_notional: Option<i64>,  // Omitted fields fall back to the configured cap
+    max_open_orders: Option<u32>,
+    reason: String,
+}
+
+/// Override one account's exposure caps; omitting both fields clears the
+/// override. Applies to orders submitted after the event is processed.
+async fn set_user_limits(
+    State(state): State<Arc<ApiState>>,
+    Path(user_id): Path<String>,
+    Json(req): Json<UserLimitsRequest>,
+) -> Result<StatusCode, StatusCode> {
+    let operator_id = parse_operator(&req.operator_id)?;
+    if !crate::utils::helper::is_authorized_operator(operator_id) {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    let user_id = UserId::from_string(&user_id)
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+    if req.max_open_notional.is_some_and(|notional| notional <= 0) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let change = UserLimitsSet {
+        base: BaseEvent::new(crate::events::base::EventType::UserLimitsSet, state.market_id),
+        operator_id,
+        user_id,
+        limits: UserLimits {
+            max_open_notional: req.max_open_notional.map(Balance::from_i64),
+            max_open_orders: req.max_open_orders,
+        },
+        reason: req.reason,
+    };
+
+    let base = change.base.clone();
+    state.event_producer.produce(BaseEvent {
+        payload: EventPayload::UserLimitsSet(Box::new(change)),
+        ..base
+    }).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    tracing::warn!("Limit override submitted: user={:?} operator={:?}", user_id, operator_id);
+    Ok(StatusCode::ACCEPTED)
+}
+
+/// Run the guarded recovery procedure; the report is returned even when a
+/// step failed (the processor is then left halted)
+async fn run_recovery(
+    State(state): State<Arc<ApiState>>,
+    Extension(principal): Extension<Principal>,
+    Json(req): Json<ProcessorControlRequest>,
+) -> Result<(StatusCode, Json<RecoveryReport>), StatusCode> {
+    let operator_id = principal.operator_id()?;
+
+    let (reply, response) = oneshot::channel();
+    state.recovery_tx.send(RecoveryCommand { operator_id, note: req.note, reply })
+        .await
+        .map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
+
+    let report = response.await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .map_err(|e| control_error_status(&e))?;
+
+    let status = if report.succeeded() {
+        StatusCode::OK
+    } else {
+        StatusCode::INTERNAL_SERVER_ERROR
+    };
+    Ok((status, Json(report)))
+}
+
+/// Accounts, positions and resting orders of the market, in the portable
+/// export format (venue migrations and disaster recovery drills)
+async fn export_accounts(
+    State(state): State<Arc<ApiState>>,
+    Extension(principal): Extension<Principal>,
+) -> Result<Json<AccountExport>, StatusCode> {
+    let operator_id = principal.operator_id()?;
+
+    let (reply, response) = oneshot::channel();
+    state.migration_tx.send(MigrationCommand::Export { reply })
+        .await
+        .map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
+
+    let export = response.await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    tracing::warn!("Account export taken by {:?} at seq={}", operator_id, export.sequence);
+    Ok(Json(export))
+}
+
+#[derive(serde::Serialize)]
+struct ImportResponse {
+    genesis_events: usize,
+}
+
+/// Seed a fresh deployment from an export: its genesis events are logged
+/// and the processor rebuilds the state from them
+async fn import_accounts(
+    State(state): State<Arc<ApiState>>,
+    Extension(principal): Extension<Principal>,
+    Json(export): Json<AccountExport>,
+) -> Result<(StatusCode, Json<ImportResponse>), StatusCode> {
+    let operator_id = principal.operator_id()?;
+    tracing::warn!("Account import of seq={} requested by {:?}", export.sequence, operator_id);
+
+    let (reply, response) = oneshot::channel();
+    state.migration_tx.send(MigrationCommand::Import { export, reply })
+        .await
+        .map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
+
+    let genesis_events = response.await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .map_err(|e| match e {
+            Error::InvalidChecksum | Error::UnsupportedExportVersion { .. } => StatusCode::UNPROCESSABLE_ENTITY,
+            Error::ExportMarketMismatch { .. } | Error::ImportTargetNotEmpty(_) => StatusCode::CONFLICT,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        })?;
+    Ok((StatusCode::ACCEPTED, Json(ImportResponse { genesis_events })))
+}
+
+async fn get_latest_risk_report(
+    State(state): State<Arc<ApiState>>,
+) -> Result<Json<DailyRiskReport>, StatusCode> {
+    state.latest_risk_report.read().await
+        .clone()
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+#[derive(serde::Deserialize)]
+struct LiquidationStressRequest {
+    operator_id: String,
+    #[serde(flatten)]
+    scenario: StressScenario,
+}
+
+/// Liquidation dry run at the current mark moved by `shock`, against the
+/// read models; nothing is executed
+async fn run_liquidation_stress(
+    State(state): State<Arc<ApiState>>,
+    Json(req): Json<LiquidationStressRequest>,
+) -> Result<Json<StressReport>, StatusCode> {
+    let operator_id = parse_operator(&req.operator_id)?;
+    if !crate::utils::helper::is_authorized_operator(operator_id) {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    let mark_price = *state.mark_price.read().await;
+    if mark_price == Price::zero() {
+        return Err(StatusCode::SERVICE_UNAVAILABLE);  // No price yet
+    }
+    let positions = state.read_models.all_positions();
+    let book = state.read_models.book();
+    let report = state.liquidation_stress
+        .run(req.scenario, mark_price, state.read_models.all_accounts(), &positions, &book.depth)
+        .map_err(|e| match e {
+            Error::ConfigError(_) => StatusCode::BAD_REQUEST,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        })?;
+
+    tracing::info!(
+        "Liquidation stress {:+.1}% by {:?}: {} accounts, shortfall {}",
+        req.scenario.shock * 100.0, operator_id, report.accounts_liquidated, report.shortfall.to_i64()
+    );
+    Ok(Json(report))
+}
+
+/// Daily risk report for a business date (`YYYY-MM-DD`)
+async fn get_risk_report(
+    State(state): State<Arc<ApiState>>,
+    Path(date): Path<String>,
+) -> Result<Json<DailyRiskReport>, StatusCode> {
+    load_report(&state.risk_reports_dir, state.market_id, &date)
+        .map_err(|e| match e {
+            Error::ConfigError(_) => StatusCode::BAD_REQUEST,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        })?
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn principal(user_id: UserId) -> Principal {
+        Principal {
+            user_id,
+            master_id: None,
+            scopes: HashSet::from([ApiKeyScope::ReadOnly, ApiKeyScope::Transfer]),
+            api_key: None,
+        }
+    }
+
+    fn transfer_request(user_id: Option<UserId>, to_user: UserId) -> TransferRequest {
+        TransferRequest {
+            user_id: user_id.map(|user_id| user_id.to_string()),
+            to_user: to_user.to_string(),
+            amount: 100,
+            reference_id: None,
+        }
+    }
+
+    #[test]
+    fn transfer_source_is_the_caller() {
+        let (caller, recipient) = (UserId::new(), UserId::new());
+        assert_eq!(transfer_parties(&principal(caller), &transfer_request(None, recipient)), Ok((caller, recipient)));
+        assert_eq!(transfer_parties(&principal(caller), &transfer_request(Some(caller), recipient)), Ok((caller, recipient)));
+    }
+
+    #[test]
+    fn transfer_from_an_account_the_caller_does_not_own_is_refused() {
+        let (caller, victim) = (UserId::new(), UserId::new());
+        let request = transfer_request(Some(victim), caller);
+        assert_eq!(transfer_parties(&principal(caller), &request), Err(StatusCode::FORBIDDEN));
+    }
+
+    mod router {
+        use super::*;
+        use crate::api::ingress::IngressSequencer;
+        use crate::api::read_model::ReadModelPublisher;
+        use crate::config::{FundingConfig, IngressConfig, LiquidationConfig, OrderArchiveConfig};
+        use crate::config::fees::FeeConfig;
+        use crate::config::loader::KafkaSecurityConfig;
+        use crate::config::risk::RiskConfig;
+        use crate::core::matching_core::MatchingCore;
+        use crate::interfaces::memory::InMemoryEventProducer;
+        use crate::liquidation::detector::LiquidationDetector;
+        use crate::liquidation::insurance_fund::InsuranceFund;
+        use crate::matching::matcher::Matcher;
+        use crate::matching::order_book::OrderBook;
+        use crate::settlement::balance_manager::BalanceManager;
+        use crate::settlement::position_manager::PositionManager;
+        use axum::{body::Body, extract::Request, http::Method};
+        use serde_json::{json, Value};
+        use tower::ServiceExt;
+
+        const TRADER_KEY: &str = "trader-key";
+        const ADMIN_KEY: &str = "admin-key";
+        const MARK: f64 = 50_000.0;
+
+        /// The full router over live read models; orders go through the
+        /// ingress sequencer into an in-memory log
+        struct Api {
+            app: Router,
+            market_id: MarketId,
+            trader: UserId,
+            log: Arc<InMemoryEventProducer>,
+            mark_price: Arc<RwLock<Price>>,
+            processor_rx: mpsc::Receiver<ProcessorCommand>,
+        }
+
+        impl Api {
+            /// The trader holds `balance`, published before the first request
+            async fn new(balance: f64) -> Self {
+                let market_id = MarketId::new();
+                let trader = UserId::new();
+                let balance_manager = Arc::new(RwLock::new(BalanceManager::new()));
+                balance_manager.write().await.create_account(trader).unwrap();
+                balance_manager.write().await.accounts.get_mut(&trader).unwrap().balance = Balance::from_f64(balance);
+                let position_manager = Arc::new(RwLock::new(PositionManager::new_with_market(market_id)));
+                let matching = MatchingCore::spawn(Matcher::new(OrderBook::new(), FeeConfig::default(), market_id), 16).unwrap();
+                let (mut publisher, read_models) = ReadModelPublisher::new(balance_manager, position_manager, matching);
+                assert!(publisher.publish().await);
+
+                let log = Arc::new(InMemoryEventProducer::new());
+                let (ingress, sequencer) = IngressSequencer::new(&IngressConfig { reorder_window_us: 0, queue_capacity: 8 });
+                tokio::spawn(sequencer.run(log.clone()));
+
+                let mut api_keys = ApiKeyAuth::new();
+                api_keys.add_key_with_scopes(TRADER_KEY.to_string(), trader, HashSet::from([ApiKeyScope::ReadOnly, ApiKeyScope::Trade, ApiKeyScope::Transfer]));
+                api_keys.add_key_with_scopes(ADMIN_KEY.to_string(), UserId::new(), HashSet::from([ApiKeyScope::Admin]));
+
+                let mark_price = Arc::new(RwLock::new(Price::from_f64(MARK)));
+                let (processor_tx, processor_rx) = mpsc::channel(1);
+                let detector = Arc::new(LiquidationDetector::new(MarginCalculator::new(RiskConfig::default())));
+                let state = Arc::new(ApiState {
+                    read_models,
+                    order_archive: Arc::new(RwLock::new(OrderArchive::new(OrderArchiveConfig::default()))),
+                    funding_history: Arc::new(RwLock::new(FundingPaymentHistory::open("/nonexistent/funding_payments.jsonl").unwrap())),
+                    tenant_registry: Arc::new(RwLock::new(TenantRegistry::new(100, std::time::Duration::from_secs(60)))),
+                    withdrawal_check: Arc::new(WithdrawalRiskCheck::new(RiskConfig::default())),
+                    margin_calculator: Arc::new(MarginCalculator::new(RiskConfig::default())),
+                    pre_trade_check: Arc::new(PreTradeRiskCheck::new(RiskConfig::default())),
+                    mark_price: mark_price.clone(),
+                    funding_accrual: Arc::new(RwLock::new(None)),
+                    predicted_funding: Arc::new(PredictedFundingTracker::new(FundingConfig::default())),
+                    market_id,
+                    event_producer: Arc::new(KafkaEventProducer::new("localhost:9092", "events", &KafkaSecurityConfig::default()).unwrap()),
+                    snapshot_manager: Arc::new(SnapshotManager::new("/nonexistent/snapshots")),
+                    recovery_tx: mpsc::channel(1).0,
+                    processor_tx,
+                    migration_tx: mpsc::channel(1).0,
+                    api_keys: Arc::new(RwLock::new(api_keys)),
+                    deadmans_switch: Arc::new(RwLock::new(DeadMansSwitch::new())),
+                    latest_risk_report: Arc::new(RwLock::new(None)),
+                    risk_reports_dir: "/nonexistent/risk_reports".to_string(),
+                    twap_engine: Arc::new(RwLock::new(TwapEngine::new(market_id, Quantity::from_i64(1)))),
+                    ingress,
+                    notification_preferences: Arc::new(RwLock::new(NotificationPreferences::new())),
+                    volatility: Arc::new(RwLock::new(None)),
+                    liquidation_stress: Arc::new(LiquidationStress::new(detector, LiquidationConfig::default(), Arc::new(InsuranceFund::new()))),
+                });
+
+                Api { app: create_router(state), market_id, trader, log, mark_price, processor_rx }
+            }
+
+            async fn call(&self, method: Method, uri: &str, key: Option<&str>, body: Option<Value>) -> (StatusCode, Value) {
+                let mut builder = Request::builder().method(method).uri(uri);
+                if let Some(key) = key {
+                    builder = builder.header("X-API-Key", key);
+                }
+                let request = match body {
+                    Some(body) => builder.header("Content-Type", "application/json").body(Body::from(body.to_string())),
+                    None => builder.body(Body::empty()),
+                }.unwrap();
+
+                let response = self.app.clone().oneshot(request).await.unwrap();
+                let status = response.status();
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+            }
+
+            fn order(&self, user_id: UserId) -> Value {
+                json!({
+                    "user_id": user_id.to_string(),
+                    "market_id": self.market_id.to_string(),
+                    "side": "Buy",
+                    "order_type": "Limit",
+                    "price": Price::from_f64(MARK).to_i64(),
+                    "quantity": 1,
+                    "time_in_force": "GTC",
+                    "reduce_only": false,
+                    "post_only": false,
+                })
+            }
+        }
+
+        #[tokio::test]
+        async fn orders_reach_the_log_for_the_key_owner_only() {
+            let api = Api::new(100_000.0).await;
+
+            let (status, accepted) = api.call(Method::POST, "/orders", Some(TRADER_KEY), Some(api.order(api.trader))).await;
+            assert_eq!(status, StatusCode::OK);
+            let logged = api.log.events();
+            assert_eq!(logged.len(), 1);
+            match &logged[0].payload {
+                EventPayload::OrderSubmit(order) => {
+                    assert_eq!(order.user_id, api.trader);
+                    assert_eq!(accepted["order_id"], json!(order.order_id));
+                }
+                other => panic!("expected an order, got {:?}", other),
+            }
+
+            let (status, _) = api.call(Method::POST, "/orders", Some(TRADER_KEY), Some(api.order(UserId::new()))).await;
+            assert_eq!(status, StatusCode::FORBIDDEN);
+            let (status, _) = api.call(Method::POST, "/orders", None, Some(api.order(api.trader))).await;
+            assert_eq!(status, StatusCode::UNAUTHORIZED);
+            assert_eq!(api.log.events().len(), 1);
+
+            // Not resting in the published book yet
+            let queue = format!("/orders/{}/queue", OrderId::new());
+            assert_eq!(api.call(Method::GET, &queue, Some(TRADER_KEY), None).await.0, StatusCode::NOT_FOUND);
+        }
+
+        #[tokio::test]
+        async fn a_twap_is_visible_to_its_owner_only() {
+            let api = Api::new(100_000.0).await;
+            let twap = |slippage_limit: Option<f64>| json!({
+                "user_id": api.trader.to_string(),
+                "side": "Sell",
+                "quantity": 10,
+                "duration_secs": 60,
+                "slice_interval_secs": 10,
+                "slippage_limit": slippage_limit,
+            });
+
+            let (status, _) = api.call(Method::POST, "/algo/twap", Some(TRADER_KEY), Some(twap(None))).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST);  // Market slices need a slippage limit
+
+            let (status, state) = api.call(Method::POST, "/algo/twap", Some(TRADER_KEY), Some(twap(Some(0.01)))).await;
+            assert_eq!(status, StatusCode::OK);
+            let uri = format!("/algo/twap/{}", state["parent_id"].as_str().unwrap());
+            assert_eq!(api.call(Method::GET, &uri, Some(TRADER_KEY), None).await.0, StatusCode::OK);
+            assert_eq!(api.call(Method::GET, &uri, Some(ADMIN_KEY), None).await.0, StatusCode::FORBIDDEN);
+        }
+
+        #[tokio::test]
+        async fn the_deadmans_switch_arms_and_disarms() {
+            let api = Api::new(100_000.0).await;
+            let arm = |timeout_ms: u64| Some(json!({ "user_id": api.trader.to_string(), "timeout_ms": timeout_ms }));
+
+            let (status, armed) = api.call(Method::POST, "/deadmans-switch", Some(TRADER_KEY), arm(60_000)).await;
+            assert_eq!(status, StatusCode::OK);
+            assert_eq!(armed["armed"], json!(true));
+            let (status, disarmed) = api.call(Method::POST, "/deadmans-switch", Some(TRADER_KEY), arm(0)).await;
+            assert_eq!(status, StatusCode::OK);
+            assert_eq!(disarmed["armed"], json!(false));
+        }
+
+        #[tokio::test]
+        async fn a_preview_runs_the_risk_checks_without_logging() {
+            let api = Api::new(100_000.0).await;
+            let preview = json!({
+                "user_id": api.trader.to_string(),
+                "side": "Buy",
+                "order_type": "Market",
+                "quantity": 1,
+            });
+
+            let (status, report) = api.call(Method::POST, "/risk/preview", Some(TRADER_KEY), Some(preview)).await;
+            assert_eq!(status, StatusCode::OK);
+            assert_eq!(report["accepted"], json!(true));
+            assert_eq!(report["resulting_position_size"], json!(1));
+            assert!(api.log.events().is_empty());
+        }
+
+        #[tokio::test]
+        async fn the_book_is_public_for_the_served_market_only() {
+            let api = Api::new(100_000.0).await;
+
+            let (status, depth) = api.call(Method::GET, &format!("/orderbook/{}", api.market_id), None, None).await;
+            assert_eq!(status, StatusCode::OK);
+            assert_eq!(depth["bids"], json!([]));
+            let (status, _) = api.call(Method::GET, &format!("/orderbook/{}", MarketId::new()), None, None).await;
+            assert_eq!(status, StatusCode::NOT_FOUND);
+        }
+
+        #[tokio::test]
+        async fn balances_and_withdrawable_come_from_the_read_models() {
+            let api = Api::new(100_000.0).await;
+
+            let (status, balances) = api.call(Method::GET, "/balances", Some(TRADER_KEY), None).await;
+            assert_eq!(status, StatusCode::OK);
+            assert_eq!(balances[0]["balance"], json!(Balance::from_f64(100_000.0).to_i64()));
+            let (status, positions) = api.call(Method::GET, "/positions", Some(TRADER_KEY), None).await;
+            assert_eq!(status, StatusCode::OK);
+            assert_eq!(positions, json!([]));
+
+            // Flat account: everything can leave
+            let uri = format!("/account/withdrawable?user_id={}", api.trader);
+            let (status, withdrawable) = api.call(Method::GET, &uri, Some(TRADER_KEY), None).await;
+            assert_eq!(status, StatusCode::OK);
+            assert_eq!(withdrawable["withdrawable"], json!(Balance::from_f64(100_000.0).to_i64()));
+
+            *api.mark_price.write().await = Price::zero();
+            assert_eq!(api.call(Method::GET, &uri, Some(TRADER_KEY), None).await.0, StatusCode::SERVICE_UNAVAILABLE);
+        }
+
+        #[tokio::test]
+        async fn transfers_and_sub_accounts_are_refused_before_reaching_the_log() {
+            let api = Api::new(100_000.0).await;
+
+            let uri = format!("/sub-accounts?user_id={}", api.trader);
+            let (status, subs) = api.call(Method::GET, &uri, Some(TRADER_KEY), None).await;
+            assert_eq!(status, StatusCode::OK);
+            assert_eq!(subs, json!([]));
+
+            // Unknown recipient, and funds from someone else's account
+            let transfer = |from: UserId, to: UserId| Some(json!({ "user_id": from.to_string(), "to_user": to.to_string(), "amount": 100 }));
+            let (status, _) = api.call(Method::POST, "/transfers", Some(TRADER_KEY), transfer(api.trader, UserId::new())).await;
+            assert_eq!(status, StatusCode::NOT_FOUND);
+            let (status, _) = api.call(Method::POST, "/transfers", Some(TRADER_KEY), transfer(UserId::new(), api.trader)).await;
+            assert_eq!(status, StatusCode::FORBIDDEN);
+            assert!(api.log.events().is_empty());
+        }
+
+        #[tokio::test]
+        async fn funding_payments_are_the_callers_own() {
+            let api = Api::new(100_000.0).await;
+
+            let uri = format!("/funding/payments?user_id={}", api.trader);
+            let (status, payments) = api.call(Method::GET, &uri, Some(TRADER_KEY), None).await;
+            assert_eq!(status, StatusCode::OK);
+            assert_eq!(payments, json!([]));
+            let (status, _) = api.call(Method::GET, &format!("{}&from=10&to=5", uri), Some(TRADER_KEY), None).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST);
+            let uri = format!("/funding/payments?user_id={}", UserId::new());
+            assert_eq!(api.call(Method::GET, &uri, Some(TRADER_KEY), None).await.0, StatusCode::FORBIDDEN);
+
+            // Public, but nothing to predict before the first index price
+            assert_eq!(api.call(Method::GET, "/funding/predicted", None, None).await.0, StatusCode::NOT_FOUND);
+        }
+
+        #[tokio::test]
+        async fn account_settings_are_validated_for_the_caller() {
+            let api = Api::new(100_000.0).await;
+
+            let uri = format!("/account/notifications?user_id={}", api.trader);
+            assert_eq!(api.call(Method::GET, &uri, Some(TRADER_KEY), None).await.0, StatusCode::OK);
+            let uri = format!("/account/notifications?user_id={}", UserId::new());
+            assert_eq!(api.call(Method::GET, &uri, Some(TRADER_KEY), None).await.0, StatusCode::FORBIDDEN);
+
+            let leverage = json!({ "user_id": api.trader.to_string(), "leverage": 0.5 });
+            let (status, _) = api.call(Method::POST, "/account/leverage", Some(TRADER_KEY), Some(leverage)).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST);
+            let mode = json!({ "user_id": UserId::new().to_string(), "mode": "hedge" });
+            let (status, _) = api.call(Method::POST, "/account/position-mode", Some(TRADER_KEY), Some(mode)).await;
+            assert_eq!(status, StatusCode::FORBIDDEN);
+        }
+
+        #[tokio::test]
+        async fn market_data_is_public() {
+            let api = Api::new(100_000.0).await;
+
+            // No index samples yet
+            assert_eq!(api.call(Method::GET, "/market/volatility", None, None).await.0, StatusCode::NOT_FOUND);
+        }
+
+        #[tokio::test]
+        async fn unknown_tenants_are_not_found() {
+            let api = Api::new(100_000.0).await;
+
+            let uri = format!("/tenants/{}/positions", TenantId::new());
+            assert_eq!(api.call(Method::GET, &uri, Some(TRADER_KEY), None).await.0, StatusCode::NOT_FOUND);
+            assert_eq!(api.call(Method::GET, "/tenants/not-a-tenant/positions", Some(TRADER_KEY), None).await.0, StatusCode::BAD_REQUEST);
+        }
+
+        #[tokio::test]
+        async fn admin_routes_need_the_admin_scope() {
+            let mut api = Api::new(100_000.0).await;
+
+            assert_eq!(api.call(Method::GET, "/admin/processor", Some(TRADER_KEY), None).await.0, StatusCode::FORBIDDEN);
+
+            let mut processor_rx = std::mem::replace(&mut api.processor_rx, mpsc::channel(1).1);
+            tokio::spawn(async move {
+                let command = processor_rx.recv().await.unwrap();
+                assert!(matches!(command.action, ProcessorAction::Status));
+                let _ = command.reply.send(Ok(None));
+            });
+            let (status, processor) = api.call(Method::GET, "/admin/processor", Some(ADMIN_KEY), None).await;
+            assert_eq!(status, StatusCode::OK);
+            assert_eq!(processor["halted"], json!(false));
+
+            // Operators run stress tests only once authorized
+            let stress = json!({ "operator_id": UserId::new().to_string(), "shock": -0.1 });
+            let (status, _) = api.call(Method::POST, "/admin/liquidation/stress", Some(ADMIN_KEY), Some(stress)).await;
+            assert_eq!(status, StatusCode::FORBIDDEN);
+        }
+    }
+}
@@ -0,0 +1,150 @@
+use std::collections::HashMap;
+use std::time::Duration;
+use crate::api::rate_limit::RateLimiter;
+use crate::error::{Error, Result};
+use crate::config::TenantsConfig;
+use crate::observability::metrics::{API_RATE_LIMITED, API_REQUESTS, TENANT_GROSS_POSITION};
+use crate::types::ids::{TenantId, UserId};
+use crate::types::position::Position;
+
+/// Tenant (organization) registry - groups users routed through one integration
+///
+/// API keys belong to a tenant, so brokers with many end clients are
+/// rate limited, aggregated, and labelled in metrics as a single unit.
+pub struct TenantRegistry {
+    members: HashMap<UserId, TenantId>,
+    quotas: HashMap<TenantId, usize>,
+    rate_limiter: RateLimiter<TenantId>,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct TenantPositionSummary {
+    pub tenant_id: TenantId,
+    pub user_count: usize,
+    pub net_size: i64,
+    pub gross_size: i64,
+}
+
+impl TenantRegistry {
+    pub fn new(default_quota: usize, window: Duration) -> Self {
+        TenantRegistry {
+            members: HashMap::new(),
+            quotas: HashMap::new(),
+            rate_limiter: RateLimiter::new(default_quota, window),
+        }
+    }
+
+    /// Registry of the configured tenants, their quotas and listed members
+    pub fn from_config(config: &TenantsConfig) -> Self {
+        let mut registry = TenantRegistry::new(config.default_quota, Duration::from_secs(config.window_secs));
+        for tenant in &config.tenants {
+            if let Some(quota) = tenant.quota {
+                registry.set_quota(tenant.tenant_id, quota);
+            }
+            for user_id in &tenant.members {
+                registry.add_member(tenant.tenant_id, *user_id);
+            }
+        }
+        registry
+    }
+
+    pub fn add_member(&mut self, tenant_id: TenantId, user_id: UserId) {
+        self.members.insert(user_id, tenant_id);
+    }
+
+    pub fn remove_member(&mut self, user_id: &UserId) -> Option<TenantId> {
+        self.members.remove(user_id)
+    }
+
+    pub fn set_quota(&mut self, tenant_id: TenantId, max_requests: usize) {
+        self.quotas.insert(tenant_id, max_requests);
+    }
+
+    pub fn tenant_of(&self, user_id: &UserId) -> Option<TenantId> {
+        self.members.get(user_id).copied()
+    }
+
+    pub fn members_of(&self, tenant_id: TenantId) -> Vec<UserId> {
+        self.members.iter()
+            .filter(|(_, t)| **t == tenant_id)
+            .map(|(u, _)| *u)
+            .collect()
+    }
+
+    /// Charge one request against the tenant's quota
+    pub fn check_quota(&self, tenant_id: TenantId) -> Result<()> {
+        let checked = match self.quotas.get(&tenant_id) {
+            Some(&max_requests) => self.rate_limiter.check_with_limit(tenant_id, max_requests),
+            None => self.rate_limiter.check(tenant_id),
+        };
+
+        checked
+            .inspect_err(|_| {
+                API_RATE_LIMITED.with_label_values(&[&tenant_id.to_string()]).inc();
+            })
+    }
+
+    /// Charge one request of `user_id` to its tenant's quota
+    /// Users outside any tenant are not metered here.
+    pub fn charge(&self, user_id: &UserId, endpoint: &str) -> Result<()> {
+        let tenant_id = match self.tenant_of(user_id) {
+            Some(tenant_id) => tenant_id,
+            None => return Ok(()),
+        };
+        self.check_quota(tenant_id)?;
+        API_REQUESTS.with_label_values(&[&tenant_id.to_string(), endpoint]).inc();
+        Ok(())
+    }
+
+    /// Aggregate positions of all users belonging to a tenant
+    pub fn aggregate_positions(
+        &self,
+        tenant_id: TenantId,
+        position_of: impl Fn(&UserId) -> Option<Position>,
+    ) -> Result<TenantPositionSummary> {
+        let members = self.members_of(tenant_id);
+        if members.is_empty() {
+            return Err(Error::TenantNotFound(tenant_id));
+        }
+
+        let mut net_size = 0i64;
+        let mut gross_size = 0i64;
+        for user_id in &members {
+            if let Some(position) = position_of(user_id) {
+                net_size += position.size;
+                gross_size += position.size.abs();
+            }
+        }
+
+        TENANT_GROSS_POSITION.with_label_values(&[&tenant_id.to_string()]).set(gross_size);
+
+        Ok(TenantPositionSummary {
+            tenant_id,
+            user_count: members.len(),
+            net_size,
+            gross_size,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::TenantConfig;
+
+    #[test]
+    fn configured_members_share_their_tenants_quota() {
+        let tenant_id = TenantId::new();
+        let (client, other_client, outsider) = (UserId::new(), UserId::new(), UserId::new());
+        let registry = TenantRegistry::from_config(&TenantsConfig {
+            default_quota: 100,
+            window_secs: 60,
+            tenants: vec![TenantConfig { tenant_id, quota: Some(2), members: vec![client, other_client] }],
+        });
+
+        registry.charge(&client, "orders").unwrap();
+        registry.charge(&other_client, "orders").unwrap();
+        assert!(matches!(registry.charge(&client, "orders"), Err(Error::RateLimitExceeded)));
+        assert!(registry.charge(&outsider, "orders").is_ok());
+    }
+}
@@ -1,39 +1,101 @@
 use axum::{
     extract::ws::{WebSocket, WebSocketUpgrade, Message},
     response::Response,
-    extract::State,
+    extract::{Extension, State},
+    middleware,
+    routing::get,
+    Router,
 };
 use futures::{sink::SinkExt, stream::StreamExt};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
-use tokio::sync::broadcast;
+use tokio::sync::{broadcast, RwLock};
+use crate::api::auth::{api_key_scope_middleware, ApiKeyAuth, Principal};
+use crate::events::order::RejectReason;
+use crate::events::trade::{ExecutionStatus, Liquidity};
+use crate::notifications::notification::NotificationKind;
 
 pub struct WsState {
     pub event_tx: broadcast::Sender<WsEvent>,
 }
 
+/// Updates pushed to WebSocket clients
+///
+/// Market data (trades, prices) goes to every client; everything naming a
+/// `user_id` goes to that user's sockets only.
 #[derive(Clone, Serialize, Deserialize)]
 pub enum WsEvent {
-    OrderUpdate { order_id: String, status: String },
+    OrderUpdate { order_id: String, user_id: String, status: String },
     TradeUpdate { trade_id: String, price: i64, quantity: i64 },
     PositionUpdate { user_id: String, position: i64 },
     PriceUpdate { symbol: String, price: f64 },
+    OrderRejected { order_id: String, user_id: String, reason: RejectReason },
+    RequestRejected { request_id: String, request_type: String, user_id: String, reason: RejectReason },
+    QueuePosition { order_id: String, user_id: String, price: i64, orders_ahead: usize, quantity_ahead: i64 },
+    ExecutionReport {
+        order_id: String,
+        user_id: String,
+        liquidity: Liquidity,
+        fill_quantity: i64,
+        average_price: i64,
+        fees: i64,
+        filled_quantity: i64,
+        remaining_quantity: i64,
+        status: ExecutionStatus,
+    },
+    Notification { user_id: String, kind: NotificationKind, subject: String, details: serde_json::Value },
+}
+
+impl WsEvent {
+    /// The user a private event belongs to; None for market data
+    pub fn user_id(&self) -> Option<&str> {
+        match self {
+            WsEvent::TradeUpdate { .. } | WsEvent::PriceUpdate { .. } => None,
+            WsEvent::OrderUpdate { user_id, .. }
+            | WsEvent::PositionUpdate { user_id, .. }
+            | WsEvent::OrderRejected { user_id, .. }
+            | WsEvent::RequestRejected { user_id, .. }
+            | WsEvent::QueuePosition { user_id, .. }
+            | WsEvent::ExecutionReport { user_id, .. }
+            | WsEvent::Notification { user_id, .. } => Some(user_id),
+        }
+    }
+
+    /// Market data, or a private event of the authenticated caller
+    pub fn visible_to(&self, principal: &Principal) -> bool {
+        match self.user_id() {
+            Some(user_id) => user_id == principal.user_id.to_string(),
+            None => true,
+        }
+    }
+}
+
+/// `/ws`, behind the same authentication as the REST routes
+pub fn create_ws_router(state: Arc<WsState>, api_keys: Arc<RwLock<ApiKeyAuth>>) -> Router {
+    Router::new()
+        .route("/ws", get(websocket_handler))
+        .route_layer(middleware::from_fn_with_state(api_keys, api_key_scope_middleware))
+        .with_state(state)
 }
 
 pub async fn websocket_handler(
     ws: WebSocketUpgrade,
     State(state): State<Arc<WsState>>,
+    Extension(principal): Extension<Principal>,
 ) -> Response {
-    ws.on_upgrade(|socket| handle_socket(socket, state))
+    ws.on_upgrade(|socket| handle_socket(socket, state, principal))
 }
 
-async fn handle_socket(socket: WebSocket, state: Arc<WsState>) {
+async fn handle_socket(socket: WebSocket, state: Arc<WsState>, principal: Principal) {
     let (mut sender, mut receiver) = socket.split();
     let mut event_rx = state.event_tx.subscribe();
 
-    // Spawn task to send events to client
+    // Spawn task to send the caller's events (and market data) to the client
     let mut send_task = tokio::spawn(async move {
         while let Ok(event) = event_rx.recv().await {
+            if !event.visible_to(&principal) {
+                continue;
+            }
             let msg = serde_json::to_string(&event).unwrap();
             if sender.send(Message::Text(msg)).await.is_err() {
                 break;
@@ -60,4 +122,41 @@ async fn handle_socket(socket: WebSocket, state: Arc<WsState>) {
         _ = (&mut send_task) => recv_task.abort(),
         _ = (&mut recv_task) => send_task.abort(),
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::auth::default_scopes;
+    use crate::types::ids::UserId;
+    use axum::{body::Body, extract::Request, http::StatusCode};
+    use tower::ServiceExt;
+
+    fn principal(user_id: UserId) -> Principal {
+        Principal { user_id, master_id: None, scopes: default_scopes(), api_key: None }
+    }
+
+    #[test]
+    fn private_events_reach_their_owner_only() {
+        let (owner, other) = (UserId::new(), UserId::new());
+        let rejected = WsEvent::OrderRejected {
+            order_id: "o-1".to_string(),
+            user_id: owner.to_string(),
+            reason: RejectReason::InsufficientBalance,
+        };
+        assert!(rejected.visible_to(&principal(owner)));
+        assert!(!rejected.visible_to(&principal(other)));
+
+        let price = WsEvent::PriceUpdate { symbol: "BTC-PERP".to_string(), price: 50_000.0 };
+        assert!(price.visible_to(&principal(other)));
+    }
+
+    #[tokio::test]
+    async fn socket_needs_a_credential() {
+        let (event_tx, _) = broadcast::channel(8);
+        let app = create_ws_router(Arc::new(WsState { event_tx }), Arc::new(RwLock::new(ApiKeyAuth::new())));
+
+        let request = Request::builder().uri("/ws").body(Body::empty()).unwrap();
+        assert_eq!(app.oneshot(request).await.unwrap().status(), StatusCode::UNAUTHORIZED);
+    }
+}
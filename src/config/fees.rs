@@ -1,10 +1,14 @@
 use serde::{Deserialize, Serialize};
+use crate::types::rounding::{RoundingMode, RoundingPolicy};
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
 pub struct FeeConfig {
     pub maker_fee_rate: f64,
     pub taker_fee_rate: f64,
     pub liquidation_fee_rate: f64,
+    pub lp_maker_fee_rate: f64,  // Maker rate for qualified LP program makers (negative = rebate)
+    pub rounding: RoundingPolicy,
 }
 
 impl Default for FeeConfig {
@@ -13,6 +17,30 @@ impl Default for FeeConfig {
             maker_fee_rate: 0.0002,      // 0.02%
             taker_fee_rate: 0.0005,      // 0.05%
             liquidation_fee_rate: 0.005, // 0.5%
+            lp_maker_fee_rate: -0.0001,  // 0.01% rebate
+            rounding: RoundingPolicy::new(RoundingMode::Ceil, 8),  // Round fees in the exchange's favour
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fees_section(toml_text: &str) -> FeeConfig {
+        let config: toml::Value = toml::from_str(toml_text).unwrap();
+        config["fees"].clone().try_into().unwrap()
+    }
+
+    #[test]
+    fn default_config_rounds_fees_up() {
+        let fees = fees_section(include_str!("../../config/default.toml"));
+        assert_eq!(fees.rounding, RoundingPolicy::new(RoundingMode::Ceil, 8));
+    }
+
+    #[test]
+    fn omitted_keys_take_their_defaults() {
+        let fees = fees_section("[fees]\ntaker_fee_rate = 0.001\n");
+        assert_eq!(fees.taker_fee_rate, 0.001);
+        assert_eq!(fees.rounding, FeeConfig::default().rounding);
+    }
+}
@@ -12,15 +12,129 @@ pub struct AppConfig {
     pub risk: RiskConfig,
     pub fees: FeeConfig,
     pub funding: FundingConfig,
+    #[serde(default)]
+    pub statements: StatementConfig,
+    #[serde(default)]
+    pub risk_report: RiskReportConfig,
+    #[serde(default)]
+    pub liquidation: LiquidationConfig,
+    #[serde(default)]
+    pub notifications: NotificationConfig,
+    #[serde(default)]
+    pub archival: ArchivalConfig,
+    #[serde(default)]
+    pub order_archive: OrderArchiveConfig,
+    #[serde(default)]
+    pub event_log_retention: EventLogRetentionConfig,
+    #[serde(default)]
+    pub ingress: IngressConfig,
+    #[serde(default)]
+    pub api: ApiConfig,
+    #[serde(default)]
+    pub tenants: TenantsConfig,
+    #[serde(default)]
+    pub warm_up: WarmUpConfig,
+    #[serde(default)]
+    pub lp_program: LpProgramConfig,
+    #[serde(default)]
+    pub oracle: OracleConfig,
+    #[serde(default)]
+    pub volatility: VolatilityConfig,
+    #[serde(default)]
+    pub price_connectors: PriceConnectorConfig,
+    #[serde(default)]
+    pub symbol_map: SymbolMapConfig,
     pub kafka: KafkaConfig,
     pub price_sources: Vec<crate::price_infra::PriceSourceConfig>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize)]
 pub struct KafkaConfig {
     pub brokers: String,
     pub topic: String,
     pub group_id: String,
+    #[serde(default)]
+    pub codec: crate::event_log::codec::EventCodec,  // "binary" (default) or "json" for debugging
+    #[serde(default)]
+    pub security: KafkaSecurityConfig,
+}
+
+/// librdkafka `security.protocol`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KafkaSecurityProtocol {
+    #[default]
+    Plaintext,
+    Ssl,            // TLS, optionally mutual (client certificate)
+    SaslPlaintext,
+    SaslSsl,        // SASL over TLS (SCRAM in production)
+}
+
+impl KafkaSecurityProtocol {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            KafkaSecurityProtocol::Plaintext => "plaintext",
+            KafkaSecurityProtocol::Ssl => "ssl",
+            KafkaSecurityProtocol::SaslPlaintext => "sasl_plaintext",
+            KafkaSecurityProtocol::SaslSsl => "sasl_ssl",
+        }
+    }
+
+    pub fn uses_sasl(&self) -> bool {
+        matches!(self, KafkaSecurityProtocol::SaslPlaintext | KafkaSecurityProtocol::SaslSsl)
+    }
+
+    pub fn uses_tls(&self) -> bool {
+        matches!(self, KafkaSecurityProtocol::Ssl | KafkaSecurityProtocol::SaslSsl)
+    }
+}
+
+/// TLS / SASL settings shared by every Kafka client (producer, consumer, admin)
+/// Secrets are read from the named environment variables, never from config files
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct KafkaSecurityConfig {
+    #[serde(default)]
+    pub protocol: KafkaSecurityProtocol,
+    pub sasl_mechanism: Option<String>,        // PLAIN, SCRAM-SHA-256, SCRAM-SHA-512
+    pub sasl_username: Option<String>,
+    pub sasl_password_env: Option<String>,
+    pub ca_location: Option<String>,           // CA bundle used to verify the brokers
+    pub certificate_location: Option<String>,  // Client certificate for mTLS
+    pub key_location: Option<String>,          // Client private key for mTLS
+    pub key_password_env: Option<String>,
+}
+
+impl KafkaSecurityConfig {
+    pub fn sasl_password(&self) -> Option<String> {
+        self.sasl_password_env.as_ref().and_then(|var| std::env::var(var).ok())
+    }
+
+    pub fn key_password(&self) -> Option<String> {
+        self.key_password_env.as_ref().and_then(|var| std::env::var(var).ok())
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if self.protocol.uses_sasl() {
+            if self.sasl_mechanism.is_none() || self.sasl_username.is_none() {
+                return Err(Error::ConfigError("Kafka SASL requires sasl_mechanism and sasl_username".to_string()));
+            }
+            if self.sasl_password().is_none() {
+                return Err(Error::ConfigError("Kafka SASL password env var not set".to_string()));
+            }
+        }
+
+        if !self.protocol.uses_tls()
+            && (self.ca_location.is_some() || self.certificate_location.is_some())
+        {
+            return Err(Error::ConfigError("Kafka TLS files configured without an ssl protocol".to_string()));
+        }
+
+        if self.certificate_location.is_some() != self.key_location.is_some() {
+            return Err(Error::ConfigError("Kafka mTLS requires both certificate_location and key_location".to_string()));
+        }
+
+        Ok(())
+    }
 }
 
 impl AppConfig {
@@ -1,7 +1,13 @@
+use std::collections::HashMap;
 use serde::{Deserialize, Serialize};
-use crate::types::ids::MarketId;
+use crate::error::{Error, Result};
+use crate::events::book::CrossedBookAction;
+use crate::events::order::SelfTradePrevention;
+use crate::types::balance::Balance;
+use crate::types::ids::{MarketId, UserId};
 use crate::types::price::Price;
 use crate::types::quantity::Quantity;
+use crate::types::timestamp::Timestamp;
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct MarketConfig {
@@ -12,18 +18,363 @@ pub struct MarketConfig {
     pub min_order_size: Quantity,
     pub max_order_size: Quantity,
     pub max_leverage: f64,
+    #[serde(default)]
+    pub amend_priority: AmendPriorityPolicy,
+    #[serde(default)]
+    pub post_only_mode: PostOnlyMode,
+    #[serde(default)]
+    pub contract: ContractSpec,
+    #[serde(default)]
+    pub self_trade_prevention: SelfTradePrevention,
+    #[serde(default)]
+    pub account_self_trade_prevention: HashMap<UserId, SelfTradePrevention>,  // Per-account overrides
+    #[serde(default)]
+    pub price_bands: PriceBandConfig,
+    #[serde(default)]
+    pub crossed_book_action: CrossedBookAction,
+    #[serde(default)]
+    pub market_type: MarketType,
+    #[serde(default)]
+    pub expiry: Option<ExpirySpec>,  // Required for dated futures
+    #[serde(default)]
+    pub matching_algorithm: MatchingAlgorithmKind,
+    #[serde(default)]
+    pub pro_rata: ProRataConfig,
+}
+
+impl MarketConfig {
+    /// Perpetuals pay funding; dated futures converge through expiry settlement instead
+    pub fn has_funding(&self) -> bool {
+        self.market_type == MarketType::Perpetual
+    }
+
+    /// Expiry of a dated future (None for perpetuals)
+    pub fn expiry(&self) -> Option<&ExpirySpec> {
+        match self.market_type {
+            MarketType::Perpetual => None,
+            MarketType::DatedFuture => self.expiry.as_ref(),
+        }
+    }
+
+    pub fn validate_market_type(&self) -> Result<()> {
+        match (self.market_type, &self.expiry) {
+            (MarketType::DatedFuture, None) => Err(Error::ConfigError(
+                "Dated future market requires an expiry".to_string()
+            )),
+            (MarketType::DatedFuture, Some(expiry)) if expiry.expires_at_ms == 0 => Err(Error::ConfigError(
+                "Dated future expires_at_ms not configured".to_string()
+            )),
+            (MarketType::Perpetual, Some(_)) => Err(Error::ConfigError(
+                "Perpetual market cannot have an expiry".to_string()
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Perpetual swap or expiring future
+/// Both share the contract spec and margin framework; only funding and expiry differ
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MarketType {
+    /// Never expires, anchored to the index by funding payments
+    #[default]
+    Perpetual,
+    /// No funding; every position is cash-settled at expiry
+    DatedFuture,
+}
+
+/// Expiry of a dated future
+///
+/// Trading stops at `expires_at_ms`; open positions are then settled at the
+/// time-weighted index price over the last `settlement_window_secs`
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ExpirySpec {
+    pub expires_at_ms: u64,
+    pub settlement_window_secs: u64,
+}
+
+impl ExpirySpec {
+    pub fn expires_at(&self) -> Timestamp {
+        Timestamp::from_millis(self.expires_at_ms)
+    }
+
+    /// Start of the index TWAP window
+    pub fn window_start(&self) -> Timestamp {
+        Timestamp::from_millis(self.expires_at_ms.saturating_sub(self.settlement_window_secs * 1000))
+    }
+}
+
+impl Default for ExpirySpec {
+    fn default() -> Self {
+        ExpirySpec {
+            expires_at_ms: 0,
+            settlement_window_secs: 1800,  // 30 minutes
+        }
+    }
+}
+
+/// How contract value relates to price
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContractType {
+    /// Value = quantity * multiplier * price, margined and settled in the quote asset
+    #[default]
+    Linear,
+    /// Value = quantity * multiplier / price, margined and settled in the base asset
+    Inverse,
+}
+
+/// Contract specification
+///
+/// ## Value
+/// - Linear: `multiplier` is base units per contract (1.0 = one BTC)
+/// - Inverse: `multiplier` is quote units per contract (100.0 = $100 of BTC)
+/// - Results keep the raw fixed-point product convention of `Quantity * Price`,
+///   so a linear contract with multiplier 1.0 values positions exactly as before
+///
+/// ## PnL
+/// - Linear: size * multiplier * (exit - entry)
+/// - Inverse: size * multiplier * (1/entry - 1/exit), paid in the base asset
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ContractSpec {
+    pub contract_type: ContractType,
+    pub multiplier: f64,
+    pub settlement_currency: String,  // Asset balances, margin and PnL are held in
+}
+
+/// Fixed-point scale shared by Price, Quantity and Balance
+const SCALE: i128 = 100_000_000;
+
+fn overflow(operation: &str) -> Error {
+    Error::Overflow { operation: operation.to_string() }
+}
+
+impl ContractSpec {
+    pub fn is_inverse(&self) -> bool {
+        self.contract_type == ContractType::Inverse
+    }
+
+    fn multiplier_raw(&self) -> i128 {
+        (self.multiplier * SCALE as f64).round() as i128
+    }
+
+    /// Raw fixed-point value in i128, None if even that overflows
+    fn raw_value(&self, size: i64, price: Price) -> Option<i128> {
+        match self.contract_type {
+            ContractType::Linear => {
+                (size as i128 * price.to_i64() as i128).checked_mul(self.multiplier_raw())
+                    .map(|raw| raw / SCALE)
+            }
+            ContractType::Inverse => {
+                if price.to_i64() == 0 {
+                    return Some(0);
+                }
+                (size as i128 * self.multiplier_raw()).checked_mul(SCALE)
+                    .map(|raw| raw / price.to_i64() as i128)
+            }
+        }
+    }
+
+    /// Value of a signed size at `price`, in the settlement currency
+    /// Computed in i128; `Error::Overflow` if the value does not fit a balance
+    pub fn value(&self, size: i64, price: Price) -> Result<Balance> {
+        self.raw_value(size, price)
+            .map_or_else(|| Err(overflow("contract value")), |raw| Balance::try_from_i128(raw, "contract value"))
+    }
+
+    /// Notional of `quantity` contracts at `price`, in the settlement currency
+    pub fn notional(&self, quantity: Quantity, price: Price) -> Result<Balance> {
+        self.value(quantity.to_i64().abs(), price)
+    }
+
+    /// Notional clamped at the largest balance, for margin requirements: a
+    /// position too large to value needs more margin than any account holds
+    pub fn saturating_notional(&self, quantity: Quantity, price: Price) -> Balance {
+        self.raw_value(quantity.to_i64().abs(), price)
+            .map_or(Balance::from_i64(i64::MAX), Balance::saturating_from_i128)
+    }
+
+    /// PnL of a signed size opened at `entry` and valued at `exit`
+    pub fn pnl(&self, size: i64, entry: Price, exit: Price) -> Result<Balance> {
+        match self.contract_type {
+            ContractType::Linear => self.value(size, exit)?.checked_sub(self.value(size, entry)?),
+            // Inverse value falls as price rises: a long gains what the contracts lose in base value
+            ContractType::Inverse => self.value(size, entry)?.checked_sub(self.value(size, exit)?),
+        }
+    }
+
+    /// Entry price after adding `added` contracts at `price` to `size` at `entry`
+    /// Linear averages prices; inverse averages 1/price (harmonic mean)
+    pub fn average_entry(&self, size: i64, entry: Price, added: i64, price: Price) -> Result<Price> {
+        let total = size.abs() as i128 + added.abs() as i128;
+        if total == 0 {
+            return Ok(Price::zero());
+        }
+
+        match self.contract_type {
+            ContractType::Linear => {
+                let notional = size.abs() as i128 * entry.to_i64() as i128
+                    + added.abs() as i128 * price.to_i64() as i128;
+                Price::try_from_i128(notional / total, "average entry")
+            }
+            ContractType::Inverse => {
+                let value = self.value(size.abs(), entry)?.to_i64() as i128
+                    + self.value(added.abs(), price)?.to_i64() as i128;
+                if value == 0 {
+                    return Ok(price);
+                }
+                let raw = (total * self.multiplier_raw()).checked_mul(SCALE)
+                    .ok_or_else(|| overflow("average entry"))?;
+                Price::try_from_i128(raw / value, "average entry")
+            }
+        }
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if self.multiplier.is_nan() || self.multiplier <= 0.0 {
+            return Err(Error::ConfigError("Contract multiplier must be positive".to_string()));
+        }
+        if self.settlement_currency.is_empty() {
+            return Err(Error::ConfigError("Contract settlement currency not configured".to_string()));
+        }
+        Ok(())
+    }
+}
+
+impl Default for ContractSpec {
+    fn default() -> Self {
+        ContractSpec {
+            contract_type: ContractType::Linear,
+            multiplier: 1.0,
+            settlement_currency: "USD".to_string(),
+        }
+    }
+}
+
+/// Fat-finger protection, relative to the mark price
+///
+/// - Limit orders priced more than `limit_band_bps` away from mark are rejected
+/// - Market orders whose estimated worst fill is more than
+///   `market_slippage_band_bps` away from mark are rejected
+/// - 0 disables a band
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct PriceBandConfig {
+    pub limit_band_bps: u32,
+    pub market_slippage_band_bps: u32,
+}
+
+impl PriceBandConfig {
+    /// Allowed distance from `reference`, or None if the band is disabled
+    pub fn band_width(reference: Price, band_bps: u32) -> Option<Price> {
+        (band_bps > 0).then(|| {
+            Price::from_i64((reference.to_i64() as i128 * band_bps as i128 / 10_000) as i64)
+        })
+    }
+}
+
+impl Default for PriceBandConfig {
+    fn default() -> Self {
+        PriceBandConfig {
+            limit_band_bps: 1000,           // 10%
+            market_slippage_band_bps: 500,  // 5%
+        }
+    }
+}
+
+/// What happens to a post-only order that would take liquidity
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PostOnlyMode {
+    /// Reject the order
+    #[default]
+    Reject,
+    /// Move the price one tick behind the best opposite level
+    Reprice,
+}
+
+/// How a taker's quantity is split across the orders of a price level
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchingAlgorithmKind {
+    /// Price-time priority: the front of the queue fills first
+    #[default]
+    Fifo,
+    /// Fills split in proportion to resting size (see `ProRataConfig`)
+    ProRata,
+}
+
+/// Pro-rata allocation parameters
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ProRataConfig {
+    /// The front order of the level is filled in full before the pro-rata split
+    pub top_order_priority: bool,
+    /// Proportional shares below this are dropped; the leftover goes FIFO
+    pub min_allocation: Quantity,
+}
+
+impl Default for ProRataConfig {
+    fn default() -> Self {
+        ProRataConfig {
+            top_order_priority: false,
+            min_allocation: Quantity::from_i64(1),
+        }
+    }
+}
+
+/// Whether an amended order keeps its place in the queue
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AmendPriorityPolicy {
+    /// Size decreases keep priority; price changes and size increases lose it
+    #[default]
+    KeepOnDecrease,
+    /// Every amend moves the order to the back of its level
+    AlwaysReset,
 }
 
 impl Default for MarketConfig {
     fn default() -> Self {
         MarketConfig {
-            market_id: MarketId::from_string("BTC-PERP").expect("REASON"),
+            market_id: MarketId::btc_perp(),
             symbol: "BTC-PERP".to_string(),
             tick_size: Price::from_f64(0.01),        // $0.01
             lot_size: Quantity::from_f64(0.001),     // 0.001 BTC
             min_order_size: Quantity::from_f64(0.001), // 0.001 BTC
             max_order_size: Quantity::from_f64(100.0), // 100 BTC
             max_leverage: 20.0,
+            amend_priority: AmendPriorityPolicy::KeepOnDecrease,
+            post_only_mode: PostOnlyMode::Reject,
+            contract: ContractSpec::default(),
+            self_trade_prevention: SelfTradePrevention::CancelMaker,
+            account_self_trade_prevention: HashMap::new(),
+            price_bands: PriceBandConfig::default(),
+            crossed_book_action: CrossedBookAction::Uncross,
+            market_type: MarketType::Perpetual,
+            expiry: None,
+            matching_algorithm: MatchingAlgorithmKind::Fifo,
+            pro_rata: ProRataConfig::default(),
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn values_beyond_a_balance_overflow_or_saturate_instead_of_wrapping() {
+        let spec = ContractSpec { multiplier: 1_000_000.0, ..ContractSpec::default() };
+        let quantity = Quantity::from_i64(1_000_000);
+        let price = Price::from_f64(50_000.0);
+
+        assert!(matches!(spec.notional(quantity, price), Err(Error::Overflow { .. })));
+        assert_eq!(spec.saturating_notional(quantity, price), Balance::from_i64(i64::MAX));
+        let small = Quantity::from_i64(2);
+        assert_eq!(ContractSpec::default().saturating_notional(small, price), ContractSpec::default().notional(small, price).unwrap());
+    }
+}
@@ -1,5 +1,11 @@
+use std::collections::HashMap;
 use std::time::Duration;
 use serde::{Deserialize, Serialize};
+use crate::events::control::TradingPhase;
+use crate::types::ids::{TenantId, UserId};
+use crate::types::balance::Balance;
+use crate::types::quantity::Quantity;
+use crate::types::rounding::{RoundingMode, RoundingPolicy};
 
 pub mod market;
 pub mod risk;
@@ -7,19 +13,486 @@ pub mod fees;
 pub mod loader;
 pub mod funding;
 
+/// Funding rate per interval: `clamp(P + clamp(I - P, -c, c), min, max)`
+/// with premium index P = premium / index, interest rate I and interest
+/// clamp c (the formula most venues use). P is the time-weighted average of
+/// samples taken every `premium_sample_secs` over the interval.
 #[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
 pub struct FundingConfig {
-    pub funding_interval: Duration,
+    pub funding_interval: FundingInterval,
+    pub settlement: FundingSettlement,
     pub max_funding_rate: f64,
+    pub min_funding_rate: Option<f64>,  // None = -max_funding_rate
+    pub interest_rate: f64,             // Per interval, e.g. 0.0001 = 0.01% per 8h
+    pub interest_clamp: f64,            // Bound on the interest adjustment (I - P)
     pub premium_ema_alpha: f64,
+    pub premium_sample_secs: u64,       // Premium index sampling cadence for the TWAP
+    pub payment_rounding: RoundingPolicy,
+    pub premium_log_path: String,  // Recorded premium samples for downtime catch-up
+    pub payment_history_path: String,  // Journal of applied funding payments, per user
+}
+
+/// How each interval's funding reaches positions
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FundingSettlement {
+    /// Every open position is paid at each interval (prorated if opened mid-interval)
+    #[default]
+    Sweep,
+    /// Each interval only advances the market's cumulative funding index;
+    /// a position settles what it owes when it is next touched (full
+    /// intervals only: no proration for positions opened mid-interval)
+    Lazy,
+}
+
+/// Intervals a market can settle funding on
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum FundingInterval {
+    #[serde(rename = "1h")]
+    OneHour,
+    #[serde(rename = "4h")]
+    FourHours,
+    #[serde(rename = "8h")]
+    EightHours,
+}
+
+impl FundingInterval {
+    pub fn duration(&self) -> Duration {
+        match self {
+            FundingInterval::OneHour => Duration::from_secs(3600),
+            FundingInterval::FourHours => Duration::from_secs(4 * 3600),
+            FundingInterval::EightHours => Duration::from_secs(8 * 3600),
+        }
+    }
+}
+
+impl FundingConfig {
+    /// Lower bound of the funding rate
+    pub fn min_funding_rate(&self) -> f64 {
+        self.min_funding_rate.unwrap_or(-self.max_funding_rate)
+    }
 }
 
 impl Default for FundingConfig {
     fn default() -> Self {
         FundingConfig {
-            funding_interval: Duration::from_secs(28800),  // 8 hours
+            funding_interval: FundingInterval::EightHours,
+            settlement: FundingSettlement::Sweep,
             max_funding_rate: 0.0005,  // 0.05%
+            min_funding_rate: None,
+            interest_rate: 0.0001,     // 0.01% per interval
+            interest_clamp: 0.0005,
             premium_ema_alpha: 0.05,
+            premium_sample_secs: 5,
+            payment_rounding: RoundingPolicy::new(RoundingMode::HalfEven, 8),
+            premium_log_path: "./funding/premium_samples.jsonl".to_string(),
+            payment_history_path: "./funding/payments.jsonl".to_string(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct StatementConfig {
+    pub enabled: bool,
+    pub cutoff_hour_utc: u32,      // Daily cutoff (UTC) for statements of record
+    pub cutoff_minute_utc: u32,
+    pub statements_dir: String,    // Immutable per-day statement files
+}
+
+impl Default for StatementConfig {
+    fn default() -> Self {
+        StatementConfig {
+            enabled: true,
+            cutoff_hour_utc: 0,
+            cutoff_minute_utc: 0,
+            statements_dir: "./statements".to_string(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct RiskReportConfig {
+    pub enabled: bool,
+    pub cutoff_hour_utc: u32,      // Daily cutoff (UTC) the report covers up to
+    pub cutoff_minute_utc: u32,
+    pub reports_dir: String,
+    pub top_positions: usize,      // Largest positions listed
+}
+
+impl Default for RiskReportConfig {
+    fn default() -> Self {
+        RiskReportConfig {
+            enabled: true,
+            cutoff_hour_utc: 0,
+            cutoff_minute_utc: 0,
+            reports_dir: "./risk_reports".to_string(),
+            top_positions: 20,
+        }
+    }
+}
+
+/// REST API settings
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ApiConfig {
+    pub api_keys_path: String,  // Issued API keys with their owners, scopes and overrides
+}
+
+impl Default for ApiConfig {
+    fn default() -> Self {
+        ApiConfig {
+            api_keys_path: "./api_keys.json".to_string(),
+        }
+    }
+}
+
+/// Tenants (organizations) and their request quotas (see `api::tenant`)
+///
+/// Users holding keys issued to a tenant are added to it on start, on top
+/// of the members listed here.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct TenantsConfig {
+    pub default_quota: usize,  // Order requests per window, for tenants without their own quota
+    pub window_secs: u64,
+    pub tenants: Vec<TenantConfig>,
+}
+
+impl Default for TenantsConfig {
+    fn default() -> Self {
+        TenantsConfig {
+            default_quota: 1000,
+            window_secs: 60,
+            tenants: Vec::new(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct TenantConfig {
+    pub tenant_id: TenantId,
+    #[serde(default)]
+    pub quota: Option<usize>,  // Overrides default_quota
+    #[serde(default)]
+    pub members: Vec<UserId>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct IngressConfig {
+    pub reorder_window_us: u64,  // Requests are held this long so slower handlers cannot overtake
+    pub queue_capacity: usize,   // Requests beyond this are refused (503)
+}
+
+impl Default for IngressConfig {
+    fn default() -> Self {
+        IngressConfig {
+            reorder_window_us: 500,
+            queue_capacity: 10_000,
+        }
+    }
+}
+
+/// Restricted trading after a restart while prices and invariants settle
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct WarmUpConfig {
+    pub enabled: bool,
+    pub duration_secs: u64,         // Minimum time in the warm-up phase
+    pub phase: TradingPhase,        // "post_only" or "cancel_only"
+    pub min_price_updates: u32,     // Fresh price snapshots required before opening
+}
+
+impl Default for WarmUpConfig {
+    fn default() -> Self {
+        WarmUpConfig {
+            enabled: true,
+            duration_secs: 30,
+            phase: TradingPhase::PostOnly,
+            min_price_updates: 10,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct NotificationConfig {
+    pub enabled: bool,
+    pub preferences_path: String,     // Saved per-user channel and kind choices
+    pub audit_path: String,           // JSONL record of every delivery attempt
+    pub large_fill_quantity: Quantity,  // Fills at least this large are notified (users may override)
+    pub max_attempts: u32,
+    pub initial_backoff_ms: u64,      // Doubled after every failed attempt
+    pub max_backoff_ms: u64,
+    pub request_timeout_ms: u64,      // Per webhook request
+    pub queue_capacity: usize,        // Notifications beyond this are dropped
+}
+
+impl Default for NotificationConfig {
+    fn default() -> Self {
+        NotificationConfig {
+            enabled: true,
+            preferences_path: "./notifications/preferences.json".to_string(),
+            audit_path: "./notifications/delivery_audit.jsonl".to_string(),
+            large_fill_quantity: Quantity::from_i64(10_00000000),
+            max_attempts: 5,
+            initial_backoff_ms: 500,
+            max_backoff_ms: 60_000,
+            request_timeout_ms: 5_000,
+            queue_capacity: 10_000,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct LiquidationConfig {
+    pub max_participation_rate: f64,  // Max share of reachable book depth per child order (0 = one sweep)
+    pub depth_band_bps: u32,          // Depth counted within this distance of mark
+    pub min_slice_size: Quantity,     // Child orders are never smaller than this
+    pub slice_interval_ms: u64,       // Time between child orders of one liquidation
+    pub ladder_step_bps: u32,         // Each retry of a child order prices this much further from mark (0 = mark only)
+    pub backstop_bps: u32,            // Furthest rung from mark; past it the insurance fund takes the position over
+    pub penalty_bps: u32,             // Charged on liquidated notional and paid into the insurance fund
+    pub margin_call_ratio: f64,       // Warn below this margin ratio (equity / maintenance margin; 0 = off)
+    pub priority: LiquidationPriority,  // Which queued candidate the executor takes first
+    pub trigger_ttl_ms: u64,          // A pending trigger blocks new ones for its leg at most this long
+    pub socialized_loss_period_secs: u64,  // Settlement period whose winners absorb losses beyond the insurance fund
+    pub unwind: UnwindConfig,
+}
+
+/// Working the liquidation engine's inventory (insurance fund takeovers)
+/// back into the market with passive reduce-only orders
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct UnwindConfig {
+    pub enabled: bool,
+    pub interval_ms: u64,                 // Time between quoting passes
+    pub order_size: Quantity,             // Largest unwind order
+    pub max_spread_bps: u32,              // Book counts as recovered when two-sided within this spread
+    pub max_loss_bps: u32,                // Never quote further than this past the engine's entry price...
+    pub max_inventory_notional: Balance,  // ...unless the inventory is worth more than this at mark
+}
+
+impl Default for UnwindConfig {
+    fn default() -> Self {
+        UnwindConfig {
+            enabled: true,
+            interval_ms: 5000,
+            order_size: Quantity::from_i64(10),
+            max_spread_bps: 20,
+            max_loss_bps: 100,
+            max_inventory_notional: Balance::from_f64(1_000_000.0),
+        }
+    }
+}
+
+/// Order in which queued liquidation candidates are executed
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LiquidationPriority {
+    /// Lowest margin ratio first
+    #[default]
+    MostUnderwater,
+    /// Largest position notional at mark first
+    LargestNotional,
+    /// First queued first
+    Oldest,
+}
+
+impl Default for LiquidationConfig {
+    fn default() -> Self {
+        LiquidationConfig {
+            max_participation_rate: 0.25,
+            depth_band_bps: 100,
+            min_slice_size: Quantity::from_i64(1),
+            slice_interval_ms: 1000,
+            ladder_step_bps: 25,
+            backstop_bps: 300,
+            penalty_bps: 50,
+            margin_call_ratio: 1.2,
+            priority: LiquidationPriority::MostUnderwater,
+            trigger_ttl_ms: 30_000,
+            socialized_loss_period_secs: 86_400,
+            unwind: UnwindConfig::default(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ArchivalConfig {
+    pub snapshot_dir: String,
+    pub max_snapshots: usize,              // Kept on disk per market (the newest is never pruned)
+    pub archive_dir: String,               // Compressed exports of pruned snapshots and ledger segments
+    pub archive_snapshots: bool,           // Export snapshots before pruning them
+    pub ledger_segment_max_entries: usize, // Open ledger segment is sealed and exported at this size
+    pub retention_days: u32,               // Archived files older than this are pruned (0 = keep forever)
+    pub check_interval_secs: u64,
+}
+
+impl Default for ArchivalConfig {
+    fn default() -> Self {
+        ArchivalConfig {
+            snapshot_dir: "./snapshots".to_string(),
+            max_snapshots: 100,
+            archive_dir: "./archive".to_string(),
+            archive_snapshots: true,
+            ledger_segment_max_entries: 100_000,
+            retention_days: 365,
+            check_interval_secs: 60,
+        }
+    }
+}
+
+/// Terminal orders kept queryable after they leave the book (see `matching::order_archive`)
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct OrderArchiveConfig {
+    pub max_in_memory: usize,      // Older orders are spilled to spill_dir
+    pub retention_secs: u64,       // Orders archived longer ago are purged
+    pub spill_dir: String,
+    pub purge_interval_secs: u64,
+}
+
+impl Default for OrderArchiveConfig {
+    fn default() -> Self {
+        OrderArchiveConfig {
+            max_in_memory: 100_000,
+            retention_secs: 86_400 * 7,  // 7 days
+            spill_dir: "./order_archive".to_string(),
+            purge_interval_secs: 3600,
+        }
+    }
+}
+
+/// Event-log retention (see `event_log::retention_manager`)
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct EventLogRetentionConfig {
+    pub compliance_window_secs: u64,  // How far back state must be reconstructible
+    pub safety_margin_secs: u64,      // Added on top of the computed broker retention
+    pub archive_dir: Option<String>,  // Unset = no archival, the broker keeps everything needed
+    pub segment_events: usize,        // Events per archived segment
+}
+
+impl Default for EventLogRetentionConfig {
+    fn default() -> Self {
+        EventLogRetentionConfig {
+            compliance_window_secs: 86_400 * 7,  // 7 days
+            safety_margin_secs: 86_400,          // 1 day
+            archive_dir: Some("./event_archive".to_string()),
+            segment_events: 10_000,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct LpProgramConfig {
+    pub enabled: bool,
+    pub makers: Vec<UserId>,                       // Registered liquidity providers
+    pub max_spread_bps: u32,                       // Widest two-sided quote that counts as present
+    pub min_depth: Quantity,                       // Per side, within max_spread_bps of mid
+    pub min_uptime: f64,                           // Fraction of samples meeting obligations to qualify
+    pub sample_interval_secs: u64,
+    pub scorecard_dir: String,
+}
+
+impl Default for LpProgramConfig {
+    fn default() -> Self {
+        LpProgramConfig {
+            enabled: true,
+            makers: Vec::new(),
+            max_spread_bps: 10,                                        // 0.10%
+            min_depth: Quantity::from_f64(1.0),                        // 1 BTC per side
+            min_uptime: 0.90,                                          // 90% of the day
+            sample_interval_secs: 5,
+            scorecard_dir: "./lp_scorecards".to_string(),
+        }
+    }
+}
+
+/// Realized volatility of the index price and volatility-adaptive thresholds
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct VolatilityConfig {
+    pub window_secs: u64,
+    pub sample_interval_ms: u64,
+    pub min_samples: usize,            // Below this the threshold scale stays 1.0
+    pub adaptive_thresholds: bool,     // Scale the outlier and circuit breaker thresholds
+    pub baseline_volatility: f64,      // Annualized volatility at which thresholds are unscaled
+    pub min_threshold_scale: f64,
+    pub max_threshold_scale: f64,
+}
+
+impl Default for VolatilityConfig {
+    fn default() -> Self {
+        VolatilityConfig {
+            window_secs: 3600,             // 1 hour
+            sample_interval_ms: 1000,
+            min_samples: 60,
+            adaptive_thresholds: false,
+            baseline_volatility: 0.6,      // 60% annualized
+            min_threshold_scale: 0.5,
+            max_threshold_scale: 3.0,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct OracleConfig {
+    pub enabled: bool,
+    pub topic: String,                      // Dedicated topic, separate from the event log
+    pub publish_interval_ms: u64,
+    pub signing_key_env: Option<String>,    // Env var holding the HMAC key; unset = unsigned
+    pub key_id: String,                     // Lets consumers pick the key during rotation
+    pub sequence_path: String,              // Last published sequence, kept across restarts
+}
+
+impl Default for OracleConfig {
+    fn default() -> Self {
+        OracleConfig {
+            enabled: false,
+            topic: "prices.oracle".to_string(),
+            publish_interval_ms: 1000,
+            signing_key_env: None,
+            key_id: "default".to_string(),
+            sequence_path: "./oracle_sequence".to_string(),
         }
     }
-}
\ No newline at end of file
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct PriceConnectorConfig {
+    pub initial_backoff_ms: u64,      // Doubled after every failed reconnect
+    pub max_backoff_ms: u64,
+}
+
+impl Default for PriceConnectorConfig {
+    fn default() -> Self {
+        PriceConnectorConfig {
+            initial_backoff_ms: 500,
+            max_backoff_ms: 30_000,
+        }
+    }
+}
+
+/// Exchange symbol for each market and price source
+/// e.g. `[symbol_map."BTC-PERP"]` with `kraken = "XBTUSD"`
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct SymbolMapConfig {
+    pub markets: HashMap<String, HashMap<String, String>>,  // market symbol -> source_id -> exchange symbol
+}
+
+impl SymbolMapConfig {
+    pub fn symbol(&self, market: &str, source_id: &str) -> Option<&str> {
+        self.markets.get(market)?.get(source_id).map(String::as_str)
+    }
+
+    /// (source_id, exchange symbol) pairs configured for a market, sorted by source
+    pub fn sources(&self, market: &str) -> Vec<(&str, &str)> {
+        let mut sources: Vec<(&str, &str)> = self.markets.get(market)
+            .map(|m| m.iter().map(|(source, symbol)| (source.as_str(), symbol.as_str())).collect())
+            .unwrap_or_default();
+        sources.sort();
+        sources
+    }
+}
@@ -1,4 +1,6 @@
 use serde::{Deserialize, Serialize};
+use crate::error::{Error, Result};
+use crate::types::balance::Balance;
 use crate::types::quantity::Quantity;
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -7,6 +9,127 @@ pub struct RiskConfig {
     pub maintenance_margin_rate: f64,
     pub initial_margin_rate: f64,
     pub max_position_size: Quantity,
+    pub withdrawal_margin_buffer: f64,
+    pub max_mark_price_age_ms: u64,       // New orders rejected when the mark price is older
+    pub circuit_breaker_cooldown_ms: u64, // New orders rejected this long after a breaker trip
+    #[serde(default)]
+    pub tiers: Vec<RiskTier>,             // Notional brackets; empty = flat max_leverage / maintenance_margin_rate
+    #[serde(default)]
+    pub limits: PositionLimits,
+    #[serde(default)]
+    pub remargin: RemarginConfig,
+    #[serde(default)]
+    pub concentration: Option<ConcentrationConfig>,  // None = no concentration surcharge
+}
+
+/// Maintenance margin surcharge for positions holding a large share of the
+/// market's open interest, which are hard to liquidate without moving the book
+///
+/// Up to `threshold` of open interest the multiplier is 1; above it, it
+/// rises linearly to `max_multiplier` for a position holding all of it.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ConcentrationConfig {
+    pub threshold: f64,       // Share of open interest, e.g. 0.2 = 20%
+    pub max_multiplier: f64,  // Maintenance margin multiplier at 100% of open interest
+}
+
+/// Re-margining of resting orders as the mark price moves
+///
+/// Order margin is reserved at the mark of the event that placed (or last
+/// touched) the order. Once the mark has moved more than `tolerance` since
+/// the last pass, every user's reservation is brought back to the netted
+/// requirement; smaller moves are left alone to avoid churn.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct RemarginConfig {
+    pub enabled: bool,
+    pub tolerance: f64,  // Fraction of mark movement, e.g. 0.02 = 2%
+}
+
+impl Default for RemarginConfig {
+    fn default() -> Self {
+        RemarginConfig {
+            enabled: true,
+            tolerance: 0.02,
+        }
+    }
+}
+
+/// Exposure caps enforced before an order is admitted (None = no cap)
+///
+/// The per-user caps can be overridden for one account by an operator
+/// (see `UserLimits`).
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct PositionLimits {
+    pub max_open_notional: Option<Balance>,  // Per user: largest position resting orders could leave, at mark
+    pub max_open_orders: Option<u32>,        // Per user: resting orders and pending stops
+    pub max_open_interest: Option<Quantity>, // Per market: sum of long sizes
+}
+
+/// Per-account override of the per-user caps in `PositionLimits`
+/// (None = the configured cap applies)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
+pub struct UserLimits {
+    pub max_open_notional: Option<Balance>,
+    pub max_open_orders: Option<u32>,
+}
+
+impl UserLimits {
+    pub fn is_empty(&self) -> bool {
+        self.max_open_notional.is_none() && self.max_open_orders.is_none()
+    }
+}
+
+/// Margin bracket for positions up to `max_notional`
+///
+/// Larger positions pay higher maintenance rates and may use less leverage
+/// (Binance/Bybit-style risk limits). Tiers are listed smallest first.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct RiskTier {
+    pub max_notional: Balance,         // Inclusive upper bound of the bracket
+    pub maintenance_margin_rate: f64,
+    pub max_leverage: f64,             // Initial margin = notional / max_leverage
+}
+
+impl RiskConfig {
+    /// Brackets must grow, with maintenance rates that never fall and
+    /// leverage that never rises
+    pub fn validate_tiers(&self) -> Result<()> {
+        for (i, tier) in self.tiers.iter().enumerate() {
+            if tier.maintenance_margin_rate <= 0.0 || tier.maintenance_margin_rate >= 1.0 {
+                return Err(Error::ConfigError(format!("Risk tier {}: invalid maintenance_margin_rate", i)));
+            }
+            if tier.max_leverage <= 0.0 || tier.max_leverage > self.max_leverage {
+                return Err(Error::ConfigError(format!("Risk tier {}: max_leverage must be in (0, {}]", i, self.max_leverage)));
+            }
+
+            if let Some(prev) = i.checked_sub(1).map(|p| &self.tiers[p]) {
+                if tier.max_notional <= prev.max_notional {
+                    return Err(Error::ConfigError(format!("Risk tier {}: max_notional must exceed tier {}", i, i - 1)));
+                }
+                if tier.maintenance_margin_rate < prev.maintenance_margin_rate || tier.max_leverage > prev.max_leverage {
+                    return Err(Error::ConfigError(format!(
+                        "Risk tier {}: maintenance rate may not fall nor leverage rise from tier {}", i, i - 1
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn validate_concentration(&self) -> Result<()> {
+        if let Some(concentration) = &self.concentration {
+            if !(concentration.threshold > 0.0 && concentration.threshold < 1.0) {
+                return Err(Error::ConfigError("Concentration threshold must be in (0, 1)".to_string()));
+            }
+            if concentration.max_multiplier.is_nan() || concentration.max_multiplier < 1.0 {
+                return Err(Error::ConfigError("Concentration max_multiplier must be at least 1".to_string()));
+            }
+        }
+        Ok(())
+    }
 }
 
 impl Default for RiskConfig {
@@ -16,6 +139,13 @@ impl Default for RiskConfig {
             maintenance_margin_rate: 0.05,  // 5%
             initial_margin_rate: 0.10,      // 10% (1/max_leverage for 10x effective)
             max_position_size: Quantity::from_i64(1000_00000000), // 1000 BTC
+            withdrawal_margin_buffer: 0.10, // Post-withdrawal margin ratio must stay >= 1.10
+            max_mark_price_age_ms: 10_000,
+            circuit_breaker_cooldown_ms: 60_000,
+            tiers: Vec::new(),
+            limits: PositionLimits::default(),
+            remargin: RemarginConfig::default(),
+            concentration: None,
         }
     }
 }
\ No newline at end of file
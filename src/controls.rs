@@ -1,10 +1,32 @@
+use std::collections::HashSet;
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::RwLock;
 use lazy_static::lazy_static;
+use tokio::sync::oneshot;
+use crate::error::{Error, Result};
+use crate::events::base::{BaseEvent, EventType};
+use crate::events::control::{HaltReason, ProcessorHaltAcknowledged, ProcessorHalted, ProcessorResumed};
+use crate::types::ids::{MarketId, OperatorId};
+use crate::types::timestamp::Timestamp;
+
+pub mod deadmans_switch;
+pub mod recovery;
+pub mod warm_up;
 
 lazy_static! {
     static ref ORDER_PROCESSOR_HALTED: AtomicBool = AtomicBool::new(false);
     static ref LIQUIDATION_ENGINE_HALTED: AtomicBool = AtomicBool::new(false);
     static ref FUNDING_ENGINE_HALTED: AtomicBool = AtomicBool::new(false);
+    static ref DISABLED_EVENT_TYPES: RwLock<HashSet<(MarketId, EventType)>> = RwLock::new(HashSet::new());
+}
+
+/// Current event processor halt, if any
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ProcessorHaltState {
+    pub reason: HaltReason,
+    pub halted_at: Timestamp,
+    pub halted_by: Option<OperatorId>,
+    pub acknowledged_by: Option<OperatorId>,
 }
 
 pub fn halt_order_processor() {
@@ -47,4 +69,224 @@ pub fn is_liquidation_engine_halted() -> bool {
 
 pub fn is_funding_engine_halted() -> bool {
     FUNDING_ENGINE_HALTED.load(Ordering::SeqCst)
-}
\ No newline at end of file
+}
+
+/// Stop accepting one event type on a market at ingress (e.g. pause
+/// OrderSubmit while cancels and liquidations keep flowing)
+pub fn disable_event_type(market_id: MarketId, event_type: EventType) {
+    if let Ok(mut disabled) = DISABLED_EVENT_TYPES.write() {
+        disabled.insert((market_id, event_type));
+        tracing::warn!("Event type {:?} DISABLED for market {}", event_type, market_id);
+    }
+}
+
+pub fn enable_event_type(market_id: MarketId, event_type: EventType) {
+    if let Ok(mut disabled) = DISABLED_EVENT_TYPES.write() {
+        disabled.remove(&(market_id, event_type));
+        tracing::info!("Event type {:?} ENABLED for market {}", event_type, market_id);
+    }
+}
+
+pub fn is_event_type_enabled(market_id: MarketId, event_type: EventType) -> bool {
+    match DISABLED_EVENT_TYPES.read() {
+        Ok(disabled) => !disabled.contains(&(market_id, event_type)),
+        Err(_) => true,
+    }
+}
+
+/// Ingress gate: refuse a request whose event type is disabled, before it
+/// reaches the log. The processor applies whatever is logged.
+pub fn check_event_type_enabled(market_id: MarketId, event_type: EventType) -> Result<()> {
+    if is_event_type_enabled(market_id, event_type) {
+        return Ok(());
+    }
+    crate::observability::metrics::EVENTS_SKIPPED
+        .with_label_values(&[&format!("{:?}", event_type)])
+        .inc();
+    Err(Error::EventTypeDisabled(event_type))
+}
+
+pub fn disabled_event_types(market_id: MarketId) -> Vec<EventType> {
+    match DISABLED_EVENT_TYPES.read() {
+        Ok(disabled) => disabled.iter()
+            .filter(|(m, _)| *m == market_id)
+            .map(|(_, t)| *t)
+            .collect(),
+        Err(_) => Vec::new(),
+    }
+}
+
+/// Event processor halt state, kept by the processor and changed only by
+/// the ProcessorHalted / ProcessorHaltAcknowledged / ProcessorResumed events
+/// it applies from the log, so a replay ends in the same state
+///
+/// ## Rules
+/// - The first halt wins: a second halt keeps the original reason and time
+/// - Automatic halts (sequence gap, invariant violation) must be
+///   acknowledged before resuming
+/// - Acknowledgements and resumes name the halt by `halted_at`; one for an
+///   earlier halt does not clear a later one
+/// - A halt dated at or before the last resumed one is stale (the same
+///   trigger seen again on replay) and is ignored
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct HaltControl {
+    current: Option<ProcessorHaltState>,
+    resumed_through: Option<Timestamp>,  // halted_at of the last resumed halt
+}
+
+impl HaltControl {
+    pub fn state(&self) -> Option<&ProcessorHaltState> {
+        self.current.as_ref()
+    }
+
+    pub fn is_halted(&self) -> bool {
+        self.current.is_some()
+    }
+
+    /// Event recording a new halt; None when already halted or stale
+    pub fn halt(
+        &self,
+        market_id: MarketId,
+        reason: HaltReason,
+        halted_at: Timestamp,
+        halted_by: Option<OperatorId>,
+    ) -> Option<ProcessorHalted> {
+        if self.current.is_some() || self.is_stale(halted_at) {
+            return None;
+        }
+        Some(ProcessorHalted {
+            base: BaseEvent::new(EventType::ProcessorHalted, market_id),
+            reason,
+            halted_at,
+            halted_by,
+        })
+    }
+
+    /// Event recording that an operator has investigated the current halt
+    pub fn acknowledge(&self, market_id: MarketId, operator_id: OperatorId) -> Result<ProcessorHaltAcknowledged> {
+        let state = self.current.as_ref().ok_or(Error::ProcessorNotHalted)?;
+        Ok(ProcessorHaltAcknowledged {
+            base: BaseEvent::new(EventType::ProcessorHaltAcknowledged, market_id),
+            halted_at: state.halted_at,
+            acknowledged_by: operator_id,
+        })
+    }
+
+    /// Event recording the current halt being cleared
+    pub fn resume(&self, market_id: MarketId, operator_id: OperatorId) -> Result<ProcessorResumed> {
+        let state = self.current.as_ref().ok_or(Error::ProcessorNotHalted)?;
+        if state.reason.requires_acknowledgement() && state.acknowledged_by.is_none() {
+            return Err(Error::HaltNotAcknowledged);
+        }
+        Ok(ProcessorResumed {
+            base: BaseEvent::new(EventType::ProcessorResumed, market_id),
+            reason: state.reason.clone(),
+            halted_at: state.halted_at,
+            acknowledged_by: state.acknowledged_by,
+            resumed_by: operator_id,
+        })
+    }
+
+    pub fn apply_halted(&mut self, halted: &ProcessorHalted) {
+        if self.current.is_some() || self.is_stale(halted.halted_at) {
+            return;
+        }
+        tracing::warn!("Event processor HALTED: {:?}", halted.reason);
+        self.current = Some(ProcessorHaltState {
+            reason: halted.reason.clone(),
+            halted_at: halted.halted_at,
+            halted_by: halted.halted_by,
+            acknowledged_by: None,
+        });
+    }
+
+    pub fn apply_acknowledged(&mut self, acknowledged: &ProcessorHaltAcknowledged) {
+        if let Some(state) = self.current.as_mut().filter(|state| state.halted_at == acknowledged.halted_at) {
+            tracing::warn!("Event processor halt {:?} acknowledged by {}", state.reason, acknowledged.acknowledged_by);
+            state.acknowledged_by = Some(acknowledged.acknowledged_by);
+        }
+    }
+
+    pub fn apply_resumed(&mut self, resumed: &ProcessorResumed) {
+        if self.current.as_ref().is_some_and(|state| state.halted_at == resumed.halted_at) {
+            tracing::info!("Event processor RESUMED by {} (was: {:?})", resumed.resumed_by, resumed.reason);
+            self.current = None;
+        }
+        self.resumed_through = self.resumed_through.max(Some(resumed.halted_at));
+    }
+
+    fn is_stale(&self, halted_at: Timestamp) -> bool {
+        self.resumed_through.is_some_and(|through| halted_at <= through)
+    }
+}
+
+/// What the admin API asks of the event processor
+#[derive(Clone, Debug)]
+pub enum ProcessorAction {
+    Status,
+    Halt { operator_id: OperatorId, note: String },
+    Acknowledge { operator_id: OperatorId },
+    Resume { operator_id: OperatorId },
+}
+
+/// Halt-control request from the admin API, run by the event loop that owns
+/// the processor; replies with the halt state afterwards
+pub struct ProcessorCommand {
+    pub action: ProcessorAction,
+    pub reply: oneshot::Sender<Result<Option<ProcessorHaltState>>>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(millis: u64) -> Timestamp {
+        Timestamp::from_millis(millis)
+    }
+
+    #[test]
+    fn automatic_halt_resumes_only_once_acknowledged() {
+        let market_id = MarketId::new();
+        let operator_id = OperatorId::new();
+        let mut control = HaltControl::default();
+
+        let halted = control.halt(market_id, HaltReason::MatchingCorePanic, at(10), None).unwrap();
+        control.apply_halted(&halted);
+        assert!(control.halt(market_id, HaltReason::Operator { note: String::new() }, at(11), None).is_none());
+        assert!(matches!(control.resume(market_id, operator_id), Err(Error::HaltNotAcknowledged)));
+
+        control.apply_acknowledged(&control.acknowledge(market_id, operator_id).unwrap());
+        control.apply_resumed(&control.resume(market_id, operator_id).unwrap());
+        assert!(!control.is_halted());
+    }
+
+    #[test]
+    fn replaying_a_resumed_halt_leaves_the_processor_running() {
+        let market_id = MarketId::new();
+        let operator_id = OperatorId::new();
+        let mut live = HaltControl::default();
+        let halted = live.halt(market_id, HaltReason::MatchingCorePanic, at(10), None).unwrap();
+        live.apply_halted(&halted);
+        let acknowledged = live.acknowledge(market_id, operator_id).unwrap();
+        live.apply_acknowledged(&acknowledged);
+        let resumed = live.resume(market_id, operator_id).unwrap();
+        live.apply_resumed(&resumed);
+
+        // The log as replayed; the trigger fires again and logs a duplicate
+        // halt after the resume
+        let mut replayed = HaltControl::default();
+        for event in [&halted, &halted] {
+            replayed.apply_halted(event);
+        }
+        replayed.apply_acknowledged(&acknowledged);
+        replayed.apply_resumed(&resumed);
+        replayed.apply_halted(&halted);
+        assert_eq!(replayed, live);
+
+        // A resume for an earlier halt leaves a later one in place
+        let later = replayed.halt(market_id, HaltReason::Operator { note: String::new() }, at(20), Some(operator_id)).unwrap();
+        replayed.apply_halted(&later);
+        replayed.apply_resumed(&resumed);
+        assert!(replayed.is_halted());
+    }
+}
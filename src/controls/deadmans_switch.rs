@@ -0,0 +1,98 @@
+use std::collections::HashMap;
+use std::time::Duration;
+use serde::Serialize;
+use crate::error::{Error, Result};
+use crate::types::ids::UserId;
+use crate::types::timestamp::Timestamp;
+
+/// Shortest countdown a client may arm (shorter would fire on network jitter)
+pub const MIN_TIMEOUT: Duration = Duration::from_secs(5);
+/// Longest countdown a client may arm
+pub const MAX_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// Armed countdown for one user
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct SwitchState {
+    pub user_id: UserId,
+    pub timeout_ms: u64,
+    pub armed_at: Timestamp,
+    pub expires_at: Timestamp,
+}
+
+/// Dead man's switch (auto-cancel on disconnect)
+///
+/// ## Usage
+/// - `arm()` starts (or restarts) a countdown for the user; calling it again
+///   before expiry is the heartbeat
+/// - `disarm()` stops the countdown without cancelling anything
+/// - `expired()` is polled by the engine; each expired switch is removed and
+///   answered with a `CancelAllOrders` for that user
+///
+/// Countdowns run on the API host's clock; the resulting cancel goes through
+/// the event log like any other, so replay does not depend on timing.
+pub struct DeadMansSwitch {
+    switches: HashMap<UserId, SwitchState>,
+}
+
+impl Default for DeadMansSwitch {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DeadMansSwitch {
+    pub fn new() -> Self {
+        DeadMansSwitch {
+            switches: HashMap::new(),
+        }
+    }
+
+    /// Arm or heartbeat the user's switch
+    pub fn arm(&mut self, user_id: UserId, timeout: Duration, now: Timestamp) -> Result<SwitchState> {
+        if timeout < MIN_TIMEOUT || timeout > MAX_TIMEOUT {
+            return Err(Error::ConfigError(format!(
+                "Dead man's switch timeout must be between {}s and {}s",
+                MIN_TIMEOUT.as_secs(), MAX_TIMEOUT.as_secs()
+            )));
+        }
+
+        let state = SwitchState {
+            user_id,
+            timeout_ms: timeout.as_millis() as u64,
+            armed_at: now,
+            expires_at: now + timeout,
+        };
+        self.switches.insert(user_id, state);
+        Ok(state)
+    }
+
+    pub fn disarm(&mut self, user_id: &UserId) -> Option<SwitchState> {
+        self.switches.remove(user_id)
+    }
+
+    pub fn get(&self, user_id: &UserId) -> Option<&SwitchState> {
+        self.switches.get(user_id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.switches.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.switches.is_empty()
+    }
+
+    /// Remove and return every switch whose countdown ran out, oldest first
+    pub fn expired(&mut self, now: Timestamp) -> Vec<SwitchState> {
+        let mut expired: Vec<SwitchState> = self.switches.values()
+            .filter(|state| state.expires_at <= now)
+            .copied()
+            .collect();
+        expired.sort_by_key(|state| (state.expires_at, state.user_id.0));
+
+        for state in &expired {
+            self.switches.remove(&state.user_id);
+        }
+        expired
+    }
+}
@@ -0,0 +1,342 @@
+use std::sync::Arc;
+use serde::Serialize;
+use tokio::sync::{oneshot, RwLock};
+use crate::error::{Error, Result};
+use crate::event_log::snapshot_manager::SnapshotManager;
+use crate::events::base::EventType;
+use crate::events::control::HaltReason;
+use crate::interfaces::event_producer::EventProducer;
+use crate::interfaces::event_source::EventSource;
+use crate::invariants::checks::InvariantChecks;
+use crate::core::event_processor::EventProcessor;
+use crate::core::matching_core::MatchingCoreHandle;
+use crate::event_log::snapshot::Snapshot;
+use crate::matching::matcher::Matcher;
+use crate::observability::metrics::RECOVERY_RUNS;
+use crate::settlement::balance_manager::BalanceManager;
+use crate::settlement::position_manager::PositionManager;
+use crate::types::ids::{MarketId, OperatorId};
+use crate::types::position::Position;
+use crate::types::price::Price;
+use crate::types::timestamp::Timestamp;
+
+/// Steps of the recovery runbook, in execution order
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum RecoveryStep {
+    PauseIntake,
+    Snapshot,
+    VerifyInvariants,
+    SeekConsumer,
+    Resume,
+}
+
+/// Outcome of one recovery run
+#[derive(Clone, Debug, Serialize)]
+pub struct RecoveryReport {
+    pub market_id: MarketId,
+    pub operator_id: OperatorId,
+    pub started_at: Timestamp,
+    pub finished_at: Timestamp,
+    pub snapshot_sequence: Option<u64>,
+    pub resumed_from: Option<u64>,      // First sequence consumed after resume
+    pub completed: Vec<RecoveryStep>,
+    pub failed_step: Option<RecoveryStep>,
+    pub error: Option<String>,          // Processor stays halted when set
+}
+
+impl RecoveryReport {
+    pub fn succeeded(&self) -> bool {
+        self.failed_step.is_none()
+    }
+}
+
+/// Recovery request from the admin API, run by the event loop that owns the consumer
+pub struct RecoveryCommand {
+    pub operator_id: OperatorId,
+    pub note: String,
+    pub reply: oneshot::Sender<Result<RecoveryReport>>,
+}
+
+/// OrderSubmit disabled at ingress for as long as this lives
+struct IntakePaused {
+    market_id: MarketId,
+}
+
+impl IntakePaused {
+    fn new(market_id: MarketId) -> Self {
+        crate::controls::disable_event_type(market_id, EventType::OrderSubmit);
+        IntakePaused { market_id }
+    }
+}
+
+impl Drop for IntakePaused {
+    fn drop(&mut self) {
+        crate::controls::enable_event_type(self.market_id, EventType::OrderSubmit);
+    }
+}
+
+/// Guarded one-click recovery (the manual runbook as code)
+///
+/// ## Steps
+/// 1. Pause intake: halt the event processor and disable OrderSubmit
+/// 2. Snapshot the current state at the last processed sequence
+/// 3. Verify invariants (book, balances, reserved margin, margin requirements)
+/// 4. Re-seek the consumer to the event after the snapshot
+/// 5. Resume: clear the halt
+///
+/// ## Guards
+/// - Refused up front (nothing changed) for an automatic halt nobody has
+///   acknowledged; the operator is authenticated by the admin API
+/// - Any failing step stops the run and leaves the processor halted; the
+///   report names the step so the operator can take over
+/// - OrderSubmit is re-enabled however the run ends, so orders queue in the
+///   log behind a halt instead of being refused until someone notices
+///
+/// Must run on the task that drives `EventConsumer`, while it is not polling.
+pub struct RecoveryProcedure {
+    market_id: MarketId,
+    snapshot_manager: Arc<SnapshotManager>,
+    balance_manager: Arc<RwLock<BalanceManager>>,
+    position_manager: Arc<RwLock<PositionManager>>,
+    matching: MatchingCoreHandle<Matcher>,
+}
+
+impl RecoveryProcedure {
+    pub fn new(
+        market_id: MarketId,
+        snapshot_manager: Arc<SnapshotManager>,
+        balance_manager: Arc<RwLock<BalanceManager>>,
+        position_manager: Arc<RwLock<PositionManager>>,
+        matching: MatchingCoreHandle<Matcher>,
+    ) -> Self {
+        RecoveryProcedure {
+            market_id,
+            snapshot_manager,
+            balance_manager,
+            position_manager,
+            matching,
+        }
+    }
+
+    /// Run every step; Err only when a guard refused the run
+    pub async fn run<C: EventSource, P: EventProducer>(
+        &mut self,
+        consumer: &C,
+        processor: &mut EventProcessor<BalanceManager, PositionManager, Matcher, P>,
+        operator_id: OperatorId,
+        note: String,
+    ) -> Result<RecoveryReport> {
+        if let Some(halt) = processor.halt_state()
+            && halt.reason.requires_acknowledgement()
+            && halt.acknowledged_by.is_none()
+        {
+            return Err(Error::HaltNotAcknowledged);
+        }
+
+        let last_sequence = processor.last_sequence();
+        tracing::warn!("Recovery started by {} at sequence {}: {}", operator_id, last_sequence, note);
+
+        let mut report = RecoveryReport {
+            market_id: self.market_id,
+            operator_id,
+            started_at: Timestamp::now(),
+            finished_at: Timestamp::now(),
+            snapshot_sequence: None,
+            resumed_from: None,
+            completed: Vec::new(),
+            failed_step: None,
+            error: None,
+        };
+
+        let steps = [
+            RecoveryStep::PauseIntake,
+            RecoveryStep::Snapshot,
+            RecoveryStep::VerifyInvariants,
+            RecoveryStep::SeekConsumer,
+            RecoveryStep::Resume,
+        ];
+
+        let mut mark_price = None;  // The snapshot's, checked against in VerifyInvariants
+        let mut intake_paused = None;  // Re-enables OrderSubmit when dropped, on any exit
+        for step in steps {
+            let result = match step {
+                RecoveryStep::PauseIntake => self.pause_intake(processor, operator_id, &note).await
+                    .map(|paused| intake_paused = Some(paused)),
+                RecoveryStep::Snapshot => self.snapshot(processor).await
+                    .map(|snapshot| {
+                        report.snapshot_sequence = Some(snapshot.sequence);
+                        mark_price = Some(snapshot.mark_price);
+                    }),
+                RecoveryStep::VerifyInvariants => self.verify_invariants(mark_price).await,
+                RecoveryStep::SeekConsumer => consumer.seek_to_sequence(last_sequence + 1)
+                    .map(|_| report.resumed_from = Some(last_sequence + 1)),
+                RecoveryStep::Resume => self.resume(processor, operator_id).await,
+            };
+
+            match result {
+                Ok(()) => report.completed.push(step),
+                Err(e) => {
+                    tracing::error!("Recovery step {:?} failed, processor left halted: {:?}", step, e);
+                    crate::utils::helper::alert_operations_team_critical(
+                        format!("Recovery failed at {:?}: {}", step, e),
+                    );
+                    report.failed_step = Some(step);
+                    report.error = Some(e.to_string());
+                    break;
+                }
+            }
+        }
+
+        drop(intake_paused);
+        report.finished_at = Timestamp::now();
+        let outcome = if report.succeeded() { "success" } else { "failed" };
+        RECOVERY_RUNS.with_label_values(&[outcome]).inc();
+
+        tracing::warn!("Recovery finished: {} ({} steps completed)", outcome, report.completed.len());
+        Ok(report)
+    }
+
+    async fn pause_intake<P: EventProducer>(
+        &self,
+        processor: &mut EventProcessor<BalanceManager, PositionManager, Matcher, P>,
+        operator_id: OperatorId,
+        note: &str,
+    ) -> Result<IntakePaused> {
+        let paused = IntakePaused::new(self.market_id);
+
+        // An existing (acknowledged) halt is kept; the run resumes it
+        processor.halt(
+            HaltReason::Operator { note: format!("recovery: {}", note) },
+            Timestamp::now(),
+            Some(operator_id),
+        ).await?;
+
+        Ok(paused)
+    }
+
+    /// Cut and save a snapshot at the processor's last sequence; returns it
+    async fn snapshot<P: EventProducer>(
+        &self,
+        processor: &EventProcessor<BalanceManager, PositionManager, Matcher, P>,
+    ) -> Result<Snapshot> {
+        let snapshot = processor.snapshot().await?;
+        self.snapshot_manager.save_snapshot(&snapshot).await?;
+        Ok(snapshot)
+    }
+
+    async fn verify_invariants(&self, mark_price: Option<Price>) -> Result<()> {
+        let mark_price = mark_price
+            .ok_or(Error::ConfigError("No price available for invariant checks".to_string()))?;
+
+        for (_, result) in self.matching.book_invariants().await? {
+            result?;
+        }
+
+        let positions = self.positions().await;
+        let balance_mgr = self.balance_manager.read().await;
+        InvariantChecks::check_no_negative_balances(&balance_mgr)?;
+        InvariantChecks::check_reserved_margin(&balance_mgr)?;
+        InvariantChecks::check_margin_requirements(&balance_mgr, &positions, mark_price)?;
+
+        Ok(())
+    }
+
+    async fn resume<P: EventProducer>(
+        &self,
+        processor: &mut EventProcessor<BalanceManager, PositionManager, Matcher, P>,
+        operator_id: OperatorId,
+    ) -> Result<()> {
+        processor.resume(operator_id).await
+    }
+
+    async fn positions(&self) -> Vec<Position> {
+        self.position_manager.read().await
+            .get_all_positions()
+            .into_iter()
+            .cloned()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::base::{BaseEvent, EventPayload};
+    use crate::events::price::{AggregationMethod, PriceSnapshot};
+    use crate::interfaces::memory::{InMemoryEventProducer, InMemoryEventSource};
+
+    type Processor = EventProcessor<BalanceManager, PositionManager, Matcher, InMemoryEventProducer>;
+
+    /// A procedure over the processor's state; toggles go to a market of its own
+    fn procedure(processor: &Processor) -> RecoveryProcedure {
+        let (balances, positions, matching, _) = processor.state_handles();
+        let dir = std::env::temp_dir().join(format!("recovery-{}", uuid::Uuid::new_v4()));
+        RecoveryProcedure::new(MarketId::new(), Arc::new(SnapshotManager::new(&dir)), balances, positions, matching)
+    }
+
+    async fn mark(processor: &mut Processor) -> BaseEvent {
+        let snapshot = PriceSnapshot {
+            base: BaseEvent::new(EventType::PriceSnapshot, MarketId::btc_perp()),
+            mark_price: Price::from_f64(50_000.0),
+            index_price: Price::from_f64(50_000.0),
+            perp_last_price: Price::from_f64(50_000.0),
+            premium_ema: Price::zero(),
+            source_prices: Vec::new(),
+            aggregation_method: AggregationMethod::WeightedMedian,
+            staleness_flags: vec![false],
+        };
+        let base = snapshot.base.clone();
+        let mut event = BaseEvent { payload: EventPayload::PriceSnapshot(Box::new(snapshot)), ..base };
+        event.sequence = processor.last_sequence() + 1;
+        event.checksum = event.calculate_checksum();
+        processor.process_event(event.clone()).await.unwrap();
+        event
+    }
+
+    fn intake_open(procedure: &RecoveryProcedure) -> bool {
+        crate::controls::is_event_type_enabled(procedure.market_id, EventType::OrderSubmit)
+    }
+
+    #[tokio::test]
+    async fn clean_run_resumes_after_the_last_processed_event() {
+        let mut processor = Processor::in_memory();
+        let logged = mark(&mut processor).await;
+        let source = InMemoryEventSource::new(vec![logged]);
+        let mut recovery = procedure(&processor);
+
+        let report = recovery.run(&source, &mut processor, OperatorId::new(), "drill".to_string()).await.unwrap();
+
+        assert!(report.succeeded(), "{:?}", report.error);
+        assert_eq!((report.snapshot_sequence, report.resumed_from), (Some(1), Some(2)));
+        assert!(!processor.is_halted());
+        assert!(intake_open(&recovery));
+        assert!(source.next_event().is_none());
+    }
+
+    #[tokio::test]
+    async fn failed_run_stays_halted_with_intake_reopened() {
+        let mut processor = Processor::in_memory();  // No price yet: the snapshot step fails
+        let source = InMemoryEventSource::new(Vec::new());
+        let mut recovery = procedure(&processor);
+
+        let report = recovery.run(&source, &mut processor, OperatorId::new(), "drill".to_string()).await.unwrap();
+
+        assert_eq!(report.completed, vec![RecoveryStep::PauseIntake]);
+        assert_eq!(report.failed_step, Some(RecoveryStep::Snapshot));
+        assert!(processor.is_halted());
+        assert!(intake_open(&recovery));
+    }
+
+    #[tokio::test]
+    async fn unacknowledged_automatic_halt_refuses_the_run() {
+        let mut processor = Processor::in_memory();
+        processor.halt(HaltReason::MatchingCorePanic, Timestamp::now(), None).await.unwrap();
+        let source = InMemoryEventSource::new(Vec::new());
+        let mut recovery = procedure(&processor);
+
+        let refused = recovery.run(&source, &mut processor, OperatorId::new(), "drill".to_string()).await;
+
+        assert!(matches!(refused, Err(Error::HaltNotAcknowledged)));
+        assert!(intake_open(&recovery));
+    }
+}
@@ -0,0 +1,74 @@
+use std::time::Duration;
+use crate::config::WarmUpConfig;
+use crate::events::base::{BaseEvent, EventType};
+use crate::events::control::{TradingPhase, TradingPhaseChanged};
+use crate::types::ids::MarketId;
+use crate::types::timestamp::Timestamp;
+
+/// Engine warm-up after a restart
+///
+/// ## Flow
+/// 1. Once the log is replayed, `start_event()` puts the market into the
+///    configured restricted phase (post-only or cancel-only)
+/// 2. The host feeds it price updates and the result of a full invariant pass
+/// 3. When `duration` has passed, `min_price_updates` fresh prices arrived and
+///    the latest invariant pass succeeded, `open_event()` opens the market
+///
+/// Timing runs on the host's clock; both phase changes go through the event
+/// log like any other, so replay does not depend on it.
+pub struct WarmUp {
+    market_id: MarketId,
+    phase: TradingPhase,
+    duration: Duration,
+    min_price_updates: u32,
+    started_at: Timestamp,
+    price_updates: u32,
+    invariants_passed: bool,
+}
+
+impl WarmUp {
+    pub fn new(config: &WarmUpConfig, market_id: MarketId, now: Timestamp) -> Self {
+        WarmUp {
+            market_id,
+            phase: config.phase,
+            duration: Duration::from_secs(config.duration_secs),
+            min_price_updates: config.min_price_updates,
+            started_at: now,
+            price_updates: 0,
+            invariants_passed: false,
+        }
+    }
+
+    pub fn record_price_update(&mut self) {
+        self.price_updates = self.price_updates.saturating_add(1);
+    }
+
+    /// Result of the latest full invariant pass; a failure keeps the market restricted
+    pub fn record_invariant_pass(&mut self, passed: bool) {
+        self.invariants_passed = passed;
+    }
+
+    pub fn prices_settled(&self) -> bool {
+        self.price_updates >= self.min_price_updates
+    }
+
+    pub fn is_ready(&self, now: Timestamp) -> bool {
+        now - self.started_at >= self.duration && self.prices_settled() && self.invariants_passed
+    }
+
+    pub fn start_event(&self) -> TradingPhaseChanged {
+        self.phase_event(self.phase, "warm-up after restart")
+    }
+
+    pub fn open_event(&self) -> TradingPhaseChanged {
+        self.phase_event(TradingPhase::Open, "warm-up complete")
+    }
+
+    fn phase_event(&self, phase: TradingPhase, reason: &str) -> TradingPhaseChanged {
+        TradingPhaseChanged {
+            base: BaseEvent::new(EventType::TradingPhaseChanged, self.market_id),
+            phase,
+            reason: reason.to_string(),
+        }
+    }
+}
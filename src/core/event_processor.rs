@@ -1,86 +1,265 @@
-use crate::types::position::Position;
+use crate::types::position::{Position, PositionMode, PositionSide};
 use crate::events::base::{BaseEvent, EventPayload, EventType};
 use crate::event_log::snapshot::Snapshot;
 use crate::settlement::balance_manager::BalanceManager;
-use crate::matching::order_book::{Order, OrderBook};
+use crate::matching::order_book::{Order, QueuePosition};
 use crate::error::{Error, Result};
 use std::collections::HashMap;
 use crate::interfaces::balance_provider::BalanceProvider;
-use crate::types::ids::{MarketId, UserId};
+use crate::types::ids::{AccountId, EventId, MarketId, OperatorId, OrderId, UserId};
 use std::sync::Arc;
-use std::sync::atomic::{AtomicBool, Ordering};
-use tokio::sync::RwLock;
-use crate::config::market::MarketConfig;
+use std::sync::atomic::Ordering;
+use tokio::sync::{broadcast, RwLock};
+use crate::api::websocket::WsEvent;
+use crate::config::market::{AmendPriorityPolicy, MarketConfig};
+use crate::config::{LiquidationConfig, VolatilityConfig};
+use crate::core::matching_core::MatchingCoreHandle;
+use crate::config::risk::{RemarginConfig, RiskConfig};
 use crate::event_log::producer::KafkaEventProducer;
-use crate::events::balance::BalanceUpdateType;
-use crate::events::liquidation::LiquidationType;
-use crate::events::order::Side;
-use crate::events::trade::TradeEvent;
+use crate::events::balance::{BalanceUpdateType, SubAccountTransfer, Transfer};
+use crate::events::book::{CrossedBook, CrossedBookAction};
+use crate::events::funding::{FundingEvent, FundingRateClamped};
+use crate::controls::{HaltControl, ProcessorAction, ProcessorHaltState};
+use crate::events::control::{HaltReason, TradingPhase};
+use crate::events::genesis::GenesisRecord;
+use crate::events::liquidation::{LiquidationEvent, LiquidationExecution, LiquidationType, SocializedLoss, SocializedLossHaircut};
+use crate::events::order::{AllOrdersCancelled, OrderAmend, OrderAmended, OrderExpired, OrderRejected, OrderSubmit, OrderType, RejectReason, RequestRejected, SelfTradePrevented, Side};
+use crate::events::trade::{ExecutionReport, TradeEvent};
 use crate::funding::applicator::FundingApplicator;
+use crate::funding::payment_calculator::FundingPaymentCalculator;
+use crate::funding::payment_history::FundingPaymentHistory;
 use crate::interfaces::event_producer::EventProducer;
+use crate::interfaces::order_book_store::OrderBookStore;
+use crate::interfaces::order_matcher::OrderMatcher;
+use crate::interfaces::position_provider::PositionProvider;
+use crate::interfaces::position_store::PositionStore;
 use crate::liquidation::detector::LiquidationCandidate;
 use crate::liquidation::executor::LiquidationExecutor;
+use crate::liquidation::socialized_loss::{self, SettlementPeriod};
 use crate::matching::matcher::Matcher;
+use crate::matching::order_archive::{OrderArchive, TerminalStatus};
+use crate::matching::trigger_engine::TriggerEngine;
+use crate::matching::trigger_monitor::TriggerMonitor;
 use crate::matching::validator::OrderValidator;
-use crate::observability::metrics::{LIQUIDATIONS_EXECUTED, LIQUIDATION_VOLUME, ORDERS_SUBMITTED};
+use crate::notifications::dispatcher::NotificationSender;
+use crate::notifications::notification::Notification;
+use crate::observability::exemplars;
+use crate::observability::metrics::{
+    record_order_rejected, CROSSED_BOOK_DETECTED, EVENT_PROCESSING_LATENCY, LIQUIDATIONS_EXECUTED, LIQUIDATION_VOLUME, ORDERS_SUBMITTED,
+    TRANSFERS_PROCESSED, TRANSFER_VOLUME,
+};
+use crate::risk::daily_report::RiskTally;
+use crate::risk::liquidation_price::{leg_liquidation_prices, leg_maintenance_surplus};
 use crate::risk::margin::MarginCalculator;
+use crate::risk::pnl::{FundingAccrual, PnLCalculator};
+use crate::risk::order_margin::OrderMarginBook;
+use crate::risk::portfolio::{MarketHoldings, PortfolioRiskCheck};
+use crate::risk::pre_trade_check::{OpenExposure, PreTradeRiskCheck};
+use crate::risk::withdrawal_check::WithdrawalRiskCheck;
+use crate::settlement::daily_statement::DailyStatementGenerator;
+use crate::settlement::migration::AccountExport;
 use crate::settlement::position_manager::PositionManager;
+use crate::settlement::settled_trades::SettledTrades;
+use crate::settlement::in_flight_fills::{InFlightFills, PendingPositions};
+use crate::settlement::sub_accounts;
 use crate::types::balance::Balance;
 use crate::types::price::Price;
 use crate::types::quantity::Quantity;
 use crate::types::ratio::Ratio;
-use crate::utils::helper::alert_operations_team_critical;
+use crate::utils::helper::{alert_operations_team_critical, alert_operations_team_warning};
+use crate::price_infra::circuit_breaker::PriceCircuitBreaker;
+use crate::price_infra::history::IndexPriceHistory;
+use crate::types::timestamp::Timestamp;
+use crate::LIQUIDATION_ENGINE_USER_ID;
+use std::time::Duration;
+
+/// Trade ids remembered for duplicate detection
+const SETTLED_TRADES_CAPACITY: usize = 100_000;
+
+/// Everything a match or uncross produced, drained on the matching core
+struct MatchOutcome {
+    trades: Vec<TradeEvent>,
+    completed_orders: Vec<(Order, TerminalStatus)>,
+    self_trades: Vec<SelfTradePrevented>,
+    execution_reports: Vec<ExecutionReport>,
+}
 
-pub struct EventProcessor {
+/// Applies logged events to engine state
+///
+/// Generic over its state and output components so handlers can be driven
+/// in isolation: the defaults are the production types, and any
+/// `BalanceProvider` / `PositionStore` / `OrderMatcher` / `EventProducer`
+/// (e.g. `InMemoryEventProducer`) can stand in for them.
+///
+/// The order book belongs to the matcher on the matching core; the
+/// processor reads and changes it only through core commands.
+pub struct EventProcessor<
+    B = BalanceManager,
+    S = PositionManager,
+    M = Matcher,
+    P = KafkaEventProducer,
+> {
     // Core state
     market_id: MarketId,
     last_sequence: u64,
     last_mark_price: Price,
-    halted: AtomicBool,
+    last_mark_price_at: Option<Timestamp>,      // Event time of the last PriceSnapshot
+    last_index_price: Price,
+    circuit_breaker: PriceCircuitBreaker,
+    circuit_breaker_tripped_at: Option<Timestamp>,
+    max_mark_price_age: Duration,
+    circuit_breaker_cooldown: Duration,
+    index_history: Option<IndexPriceHistory>,  // Scales the breaker's movement threshold when set
+    trading_phase: TradingPhase,               // Restricted during warm-up after a restart
+    halt_control: HaltControl,                 // Halt state, from the control events applied
+    event_time: Timestamp,                     // Time of the event being processed; dates automatic halts
+    settlement_price: Option<Price>,           // Set once a dated future has been settled
+    funding_accrual: Option<FundingAccrual>,   // Unsettled funding, from the latest prices (perpetuals only)
+    settlement_period: SettlementPeriod,       // Winners that absorb deficits beyond the insurance fund
 
     market_config: MarketConfig,
+    withdrawal_check: WithdrawalRiskCheck,
+    portfolio_check: PortfolioRiskCheck,  // Account-wide margin across positions and resting orders
+    order_margin: OrderMarginBook,        // Netted margin held by open orders and pending stops
+    pre_trade_check: PreTradeRiskCheck,   // Exposure caps (open orders, notional, open interest)
+    remargin: RemarginConfig,
+    remargin_mark: Price,                 // Mark of the last re-margining pass
 
     // Shared dependencies (injected)
-    balance_manager: Arc<RwLock<BalanceManager>>,
-    position_manager: Arc<RwLock<PositionManager>>,
-    order_book: Arc<RwLock<OrderBook>>,
-    matcher: Arc<RwLock<Matcher>>,
+    balance_manager: Arc<RwLock<B>>,
+    position_manager: Arc<RwLock<S>>,
+    matching: MatchingCoreHandle<M>,  // Single-writer matcher (and the book) on its own thread
+    order_archive: Arc<RwLock<OrderArchive>>,
+    trigger_monitor: TriggerMonitor,  // Stop orders waiting for the mark price
+    trigger_engine: TriggerEngine,    // Trailing stops following the mark price
+    lp_makers: Vec<UserId>,           // Makers on the LP fee rate, from the last LpMakersDesignated
+    settled_trades: SettledTrades,    // Trade ids already applied to positions/fees
+    in_flight_fills: InFlightFills,   // Logged trades not yet applied to positions
     margin_calculator: Arc<MarginCalculator>,
     funding_applicator: Arc<FundingApplicator>,
     liquidation_executor: Arc<LiquidationExecutor>,
-    event_producer: Arc<KafkaEventProducer>,
+    event_producer: Arc<P>,
+    user_stream: Option<broadcast::Sender<WsEvent>>,
+    risk_tally: Option<Arc<RiskTally>>,  // Liquidation/funding activity for the daily risk report
+    notifier: Option<NotificationSender>,  // User notifications (fills, liquidations, withdrawals)
+    funding_history: Option<Arc<RwLock<FundingPaymentHistory>>>,  // Queryable record of applied funding payments
 }
 
-impl EventProcessor {
+impl<B, S, M, P> EventProcessor<B, S, M, P>
+where
+    B: BalanceProvider + Send + Sync + 'static,
+    S: PositionStore + Send + Sync + 'static,
+    M: OrderMatcher + Send + 'static,
+    P: EventProducer,
+{
     pub fn new_with_dependencies(
         market_id: MarketId,
         market_config: MarketConfig,
-        balance_manager: Arc<RwLock<BalanceManager>>,
-        position_manager: Arc<RwLock<PositionManager>>,
-        order_book: Arc<RwLock<OrderBook>>,
-        matcher: Arc<RwLock<Matcher>>,
+        risk_config: RiskConfig,
+        balance_manager: Arc<RwLock<B>>,
+        position_manager: Arc<RwLock<S>>,
+        matching: MatchingCoreHandle<M>,
+        order_archive: Arc<RwLock<OrderArchive>>,
         margin_calculator: Arc<MarginCalculator>,
         funding_applicator: Arc<FundingApplicator>,
         liquidation_executor: Arc<LiquidationExecutor>,
-        event_producer: Arc<KafkaEventProducer>,
+        event_producer: Arc<P>,
     ) -> Self {
         EventProcessor {
             market_id,
             last_sequence: 0,
             last_mark_price: Price::from_i64(50000_00000000), // Default BTC price $50k
-            halted: AtomicBool::new(false),
+            last_mark_price_at: None,
+            last_index_price: Price::zero(),
+            circuit_breaker: PriceCircuitBreaker::new(),
+            circuit_breaker_tripped_at: None,
+            max_mark_price_age: Duration::from_millis(risk_config.max_mark_price_age_ms),
+            circuit_breaker_cooldown: Duration::from_millis(risk_config.circuit_breaker_cooldown_ms),
+            index_history: None,
+            trading_phase: TradingPhase::Open,
+            halt_control: HaltControl::default(),
+            event_time: Timestamp::from_millis(0),
+            settlement_price: None,
+            funding_accrual: None,
+            settlement_period: SettlementPeriod::new(Duration::from_secs(
+                LiquidationConfig::default().socialized_loss_period_secs,
+            )),
+            portfolio_check: PortfolioRiskCheck::new(risk_config.clone())
+                .with_market(market_id, market_config.contract.clone()),
+            order_margin: OrderMarginBook::new(),
+            pre_trade_check: PreTradeRiskCheck::new(risk_config.clone())
+                .with_contract(market_config.contract.clone()),
+            remargin: risk_config.remargin.clone(),
+            remargin_mark: Price::from_i64(50000_00000000),
+            withdrawal_check: WithdrawalRiskCheck::new(risk_config)
+                .with_contract(market_config.contract.clone()),
             market_config,
             balance_manager,
             position_manager,
-            order_book,
-            matcher,
+            matching,
+            order_archive,
+            trigger_monitor: TriggerMonitor::new(),
+            trigger_engine: TriggerEngine::new(),
+            lp_makers: Vec::new(),
+            settled_trades: SettledTrades::new(SETTLED_TRADES_CAPACITY),
+            in_flight_fills: InFlightFills::new(),
             margin_calculator,
             funding_applicator,
             liquidation_executor,
             event_producer,
+            user_stream: None,
+            risk_tally: None,
+            notifier: None,
+            funding_history: None,
+        }
+    }
+
+    /// Attach the user WebSocket stream (order rejections are pushed to it)
+    pub fn set_user_stream(&mut self, user_stream: broadcast::Sender<WsEvent>) {
+        self.user_stream = Some(user_stream);
+    }
+
+    /// Length of the settlement period whose winners absorb losses beyond
+    /// the insurance fund
+    pub fn set_socialized_loss_period(&mut self, length: Duration) {
+        self.settlement_period = SettlementPeriod::new(length);
+    }
+
+    /// Attach the user notification queue
+    pub fn set_notifier(&mut self, notifier: NotificationSender) {
+        self.notifier = Some(notifier);
+    }
+
+    /// Scale the circuit breaker's movement threshold by realized index volatility
+    /// Sampled from PriceSnapshot event times, so replay trips the breaker identically
+    pub fn set_adaptive_thresholds(&mut self, config: &VolatilityConfig) {
+        self.index_history = config.adaptive_thresholds.then(|| IndexPriceHistory::new(config));
+    }
+
+    /// Attach the daily risk report's activity tally
+    pub fn set_risk_tally(&mut self, risk_tally: Arc<RiskTally>) {
+        self.risk_tally = Some(risk_tally);
+    }
+
+    /// Funding a leg owes against the funding index (lazy settlement), which
+    /// it is then brought up to; the caller books the amount
+    fn collect_index_funding(position_mgr: &mut S, user_id: UserId, leg: PositionSide) -> Result<Balance> {
+        let index = position_mgr.cumulative_funding().index;
+        match position_mgr.get_leg_mut(&user_id, leg) {
+            Some(position) => {
+                let owed = FundingPaymentCalculator::index_payment(position, index)?;
+                position.funding_index = index;
+                Ok(owed)
+            }
+            None => Ok(Balance::zero()),
         }
     }
 
+    /// Attach the funding payment history (payments are recorded as applied)
+    pub fn set_funding_history(&mut self, funding_history: Arc<RwLock<FundingPaymentHistory>>) {
+        self.funding_history = Some(funding_history);
+    }
+
     pub async fn restore_from_snapshot(&mut self, snapshot: &Snapshot) -> Result<()> {
         tracing::info!("Restoring state from snapshot at sequence {}", snapshot.sequence);
 
@@ -89,28 +268,106 @@ impl EventProcessor {
         for account in &snapshot.accounts {
             balance_mgr.create_account(account.user_id)?;
             balance_mgr.adjust_balance(account.user_id, account.balance)?;
+            balance_mgr.get_account_mut(account.user_id)?.realized_pnl = account.realized_pnl;
         }
         drop(balance_mgr);
 
-        // Restore positions
+        // Restore positions, leverage choices and limit overrides
         let mut position_mgr = self.position_manager.write().await;
         for position in &snapshot.positions {
             position_mgr.set_position(position.user_id, position.clone());
         }
+        for (user_id, leverage) in &snapshot.leverage {
+            position_mgr.set_leverage(*user_id, Some(*leverage));
+        }
+        for (user_id, limits) in &snapshot.limit_overrides {
+            position_mgr.set_user_limits(*user_id, *limits);
+        }
+        position_mgr.set_cumulative_funding(snapshot.cumulative_funding);
         drop(position_mgr);
 
+        // Rebuild the book with its original queue order (also recovers a poisoned core)
+        self.matching.restore(snapshot.open_orders.clone()).await?;
+        self.trigger_monitor.restore(&snapshot.pending_stops);
+        self.trigger_engine.restore(&snapshot.trailing_stops);
+        self.set_lp_makers(snapshot.lp_makers.clone()).await?;
+        self.reconcile_reserved_margin(snapshot).await?;
+
         self.last_sequence = snapshot.sequence;
+        self.last_index_price = snapshot.index_price;
+        self.settled_trades.restore(&snapshot.settled_trades);
+        self.in_flight_fills.restore(&snapshot.in_flight_fills);
+        self.halt_control = snapshot.halt.clone();
+        self.settlement_period.restore(&snapshot.settlement_period);
+        self.sync_insurance_fund().await;
 
         tracing::info!("State restored successfully");
         Ok(())
     }
 
-    pub async fn process_event(&mut self, event: BaseEvent) -> Result<()> {
-        if self.halted.load(Ordering::SeqCst) {
-            tracing::warn!("EventProcessor is halted, rejecting event");
-            return Err(Error::KillSwitchActive);
+    /// Reserve initial margin for every restored resting order, pending stop and isolated bucket
+    /// Accounts are restored from their balance alone, so the reservation is
+    /// recomputed the same way fills and cancels rebalance it (netted open
+    /// orders against the restored position, at the snapshot's mark);
+    /// drift from the snapshot's recorded reservation is logged
+    async fn reconcile_reserved_margin(&mut self, snapshot: &Snapshot) -> Result<()> {
+        self.order_margin.clear();
+        self.remargin_mark = snapshot.mark_price;
+        let mut owners: Vec<UserId> = Vec::new();
+        for order in &snapshot.open_orders {
+            self.order_margin.track(order.order_id, order.user_id, order.side, order.quantity - order.filled);
+            if !owners.contains(&order.user_id) {
+                owners.push(order.user_id);
+            }
+        }
+        // Stops hold margin from acceptance until they fire or are cancelled
+        let stops = snapshot.pending_stops.iter().map(|stop| &stop.order)
+            .chain(snapshot.trailing_stops.pending.iter().map(|stop| &stop.order));
+        for order in stops {
+            self.order_margin.track(order.order_id, order.user_id, order.side, order.quantity);
+            if !owners.contains(&order.user_id) {
+                owners.push(order.user_id);
+            }
+        }
+
+        let position_mgr = self.position_manager.read().await;
+        let mut balance_mgr = self.balance_manager.write().await;
+        for account in &snapshot.accounts {
+            owners.retain(|owner| *owner != account.user_id);
+            let delta = self.order_margin.rebalance(
+                account.user_id,
+                position_mgr.position_size(account.user_id),
+                &self.margin_calculator,
+                snapshot.mark_price,
+                position_mgr.leverage(&account.user_id),
+                &mut *balance_mgr,
+            )?;
+
+            // Isolated buckets are held out of the account as reserved margin too
+            let isolated = position_mgr.positions_for(&account.user_id).iter()
+                .filter(|position| position.is_isolated())
+                .fold(Balance::zero(), |total, position| total + position.isolated_margin);
+            if isolated > Balance::zero() {
+                balance_mgr.reserve_margin(account.user_id, isolated)?;
+            }
+
+            if delta + isolated != account.reserved_margin {
+                tracing::warn!(
+                    "Reserved margin drift for {:?}: snapshot={}, open orders={}, isolated={}",
+                    account.user_id, account.reserved_margin.to_i64(), delta.to_i64(), isolated.to_i64()
+                );
+            }
+        }
+
+        // Resting orders and stops must belong to a restored account
+        if let Some(user_id) = owners.first() {
+            return Err(Error::AccountNotFound(AccountId::from_user(*user_id)));
         }
 
+        Ok(())
+    }
+
+    pub async fn process_event(&mut self, event: BaseEvent) -> Result<()> {
         // FIX IGD-S-040: Verify sequence with proper gap handling
         let expected_sequence = self.last_sequence + 1;
 
@@ -133,6 +390,14 @@ impl EventProcessor {
             // Activate kill switch for sequence gap
             crate::KILL_SWITCH.store(true, Ordering::SeqCst);
 
+            // Stays halted until an operator acknowledges the gap and resumes
+            if let Err(e) = self.halt(HaltReason::SequenceGap {
+                expected: expected_sequence,
+                actual: event.sequence,
+            }, event.timestamp, None).await {
+                tracing::error!("Failed to record halt event: {:?}", e);
+            }
+
             // Alert operations team
             alert_operations_team_critical(
                 format!(
@@ -156,20 +421,55 @@ impl EventProcessor {
         }
 
         let event_sequence = event.sequence;
+        self.event_time = event.timestamp;
+        let _timer = exemplars::start_timer(&EVENT_PROCESSING_LATENCY, &[&format!("{:?}", event.event_type)]);
 
         // Process based on event type
-        match event.event_type {
-            EventType::OrderSubmit => self.process_order_submit(event).await?,
-            EventType::OrderCancel => self.process_order_cancel(event).await?,
-            EventType::Trade => self.process_trade(event).await?,
-            EventType::Funding => self.process_funding(event).await?,
-            EventType::Liquidation => self.process_liquidation(event).await?,
-            EventType::BalanceUpdate => self.process_balance_update(event).await?,
-            EventType::PriceSnapshot => self.process_price_update(event).await?,
+        let result = match event.event_type {
+            EventType::OrderSubmit => self.process_order_submit(event).await,
+            EventType::OrderCancel => self.process_order_cancel(event).await,
+            EventType::CancelAllOrders => self.process_cancel_all_orders(event).await,
+            EventType::ExpireOrders => self.process_expire_orders(event).await,
+            EventType::PositionModeChange => self.process_position_mode_change(event).await,
+            EventType::MarginModeChange => self.process_margin_mode_change(event).await,
+            EventType::IsolatedMarginTransfer => self.process_isolated_margin_transfer(event).await,
+            EventType::SetLeverage => self.process_set_leverage(event).await,
+            EventType::UserLimitsSet => self.process_user_limits_set(event).await,
+            EventType::LpMakersDesignated => self.process_lp_makers_designated(event).await,
+            EventType::OrderAmend => self.process_order_amend(event).await,
+            EventType::Trade => self.process_trade(event).await,
+            EventType::Funding | EventType::FundingCatchUp => self.process_funding(event).await,
+            EventType::Liquidation => self.process_liquidation(event).await,
+            EventType::MarginCallWarning => self.process_margin_call_warning(event),
+            EventType::BalanceUpdate => self.process_balance_update(event).await,
+            EventType::SubAccountCreated => self.process_sub_account_created(event).await,
+            EventType::SubAccountTransfer => self.process_sub_account_transfer(event).await,
+            EventType::Transfer => self.process_transfer(event).await,
+            EventType::PriceSnapshot => self.process_price_update(event).await,
+            EventType::Genesis => self.process_genesis(event).await,
+            EventType::StateRepair => self.process_state_repair(event).await,
+            EventType::TradingPhaseChanged => self.process_trading_phase_change(event),
+            EventType::ExpirySettlement => self.process_expiry_settlement(event).await,
+            EventType::ProcessorHalted
+            | EventType::ProcessorHaltAcknowledged
+            | EventType::ProcessorResumed => self.process_halt_control(event),
             _ => {
                 tracing::debug!("Skipping event type: {:?}", event.event_type);
+                Ok(())
             }
+        };
+
+        // A panic on the matching core leaves the book untrusted: stop until
+        // an operator restores from a snapshot (which rebuilds the core)
+        if let Err(Error::MatchingCoreStopped) = &result
+            && let Err(e) = self.halt(HaltReason::MatchingCorePanic, self.event_time, None).await
+        {
+            tracing::error!("Failed to record halt event: {:?}", e);
         }
+        result?;
+
+        // Every book mutation must leave best_bid < best_ask
+        self.check_crossed_book().await?;
 
         self.last_sequence = event_sequence;
         Ok(())
@@ -189,40 +489,541 @@ impl EventProcessor {
             }
         };
 
-        // 1. Validate order parameters
-        let validator = OrderValidator::new(self.market_config.clone());
-        validator.validate(&order_submit)?;
+        // A replayed PriceSnapshot logs a fired trailing stop's submission again
+        if !self.trigger_engine.claim_submission(&order_submit.order_id) {
+            tracing::warn!("Trailing stop {} already submitted, skipping the copy at seq={}", order_submit.order_id, event.sequence);
+            return Ok(());
+        }
 
-        // 2. Check margin requirements
-        let balance_mgr = self.balance_manager.blocking_read();
-        let account = balance_mgr.get_account(order_submit.user_id)?;
+        // 1-3. Validate, check and reserve margin. A refused order is a normal
+        // outcome: the client is told why and processing moves on.
+        if let Err(e) = self.admit_order(&order_submit).await {
+            return self.reject_order(&Self::order_from_submit(&order_submit), &e).await;
+        }
+
+        let side = match order_submit.side {
+            Side::Buy => "buy",
+            Side::Sell => "sell",
+        };
+        let order_type = match order_submit.order_type {
+            OrderType::StopMarket => "stop_market",
+            OrderType::StopLimit => "stop_limit",
+            OrderType::TrailingStop => "trailing_stop",
+            _ if order_submit.price.is_some() => "limit",
+            _ => "market",
+        };
+        ORDERS_SUBMITTED.with_label_values(&[side, order_type]).inc();
+
+        // Stops wait for their trigger; margin stays reserved meanwhile
+        if order_submit.order_type.is_stop() {
+            return self.trigger_monitor.add(order_submit, self.last_mark_price);
+        }
+        if order_submit.order_type == OrderType::TrailingStop {
+            return self.trigger_engine.add(order_submit, self.last_mark_price);
+        }
+
+        self.execute_order(Self::order_from_submit(&order_submit)).await
+    }
+
+    /// Book and match an admitted order (new submission or triggered stop)
+    async fn execute_order(&mut self, order: Order) -> Result<()> {
+        // Reduce-only, FOK, IOC minimum fill and post-only are decided before the order touches the book
+        let prepared = {
+            let position_mgr = self.position_manager.clone().read_owned().await;
+            let candidate = order.clone();
+            let post_only_mode = self.market_config.post_only_mode;
+            let tick_size = self.market_config.tick_size;
+            let in_flight = self.in_flight_fills.clone();
+            let mark_price = self.last_mark_price;
+            self.matching.execute(move |matcher| {
+                let positions = PendingPositions { positions: &*position_mgr, in_flight: &in_flight };
+                matcher.reduce_only_quantity(&candidate, &positions)
+                    .map(|quantity| Order { quantity, ..candidate.clone() })  // Shrunk to the position
+                    .and_then(|shrunk| matcher.check_fill_or_kill(&shrunk, mark_price).map(|_| shrunk))
+                    .and_then(|shrunk| matcher.check_min_fill(&shrunk, mark_price).map(|_| shrunk))
+                    .and_then(|shrunk| matcher.prepare_post_only(&shrunk, post_only_mode, tick_size))
+            }).await?
+        };
+        let order = match prepared {
+            Ok(order) => order,
+            Err(e) => return self.kill_admitted_order(&order, &e).await,
+        };
+
+        // 4. Match; the matcher rests whatever remainder may rest
+        let taker = order.clone();
+        let mark_price = self.last_mark_price;
+        let MatchOutcome { trades, completed_orders, self_trades, execution_reports } =
+            self.run_match(move |matcher, position_mgr| {
+                matcher.match_order(&taker, position_mgr, mark_price)
+            }).await?;
+
+        // Archive orders that reached a terminal state during matching
+        // (including the taker when self-trade prevention cancelled it);
+        // expired GTD orders are archived with their margin released below
+        let taker_completed = completed_orders.iter().any(|(o, _)| o.order_id == order.order_id);
+        let (expired, completed_orders): (Vec<_>, Vec<_>) = completed_orders.into_iter()
+            .partition(|(_, status)| *status == TerminalStatus::Expired);
+        let mut order_archive = self.order_archive.write().await;
+        for (completed, status) in completed_orders {
+            order_archive.archive(completed, status)?;
+        }
 
-        let position_mgr = self.position_manager.blocking_read();
-        let position = position_mgr.get_position(&order_submit.user_id);
+        // Levels whose queues changed: the taker's own level and every maker level hit
+        let mut changed_levels: Vec<(Side, Price)> = trades.iter()
+            .map(|t| (t.maker_side, t.price))
+            .collect();
+        changed_levels.push((order.side, order.price));
+        changed_levels.dedup();
+        self.publish_queue_positions(&changed_levels).await?;
+
+        let taker_filled: Quantity = trades.iter().map(|t| t.quantity).sum();
+        if taker_completed {
+            // Archived above
+        } else if taker_filled == order.quantity {
+            let mut filled_order = order.clone();
+            filled_order.filled = taker_filled;
+            order_archive.archive(filled_order, TerminalStatus::Filled)?;
+        } else if !order.time_in_force.rests() {
+            // IOC/FOK remainder is never rested
+            let mut cancelled_order = order.clone();
+            cancelled_order.filled = taker_filled;
+            order_archive.archive(cancelled_order, TerminalStatus::Cancelled)?;
+        }
+        drop(order_archive);
+
+        // A resting taker holds margin for its (possibly reduce-only shrunk)
+        // size until its fills settle; any other taker holds none
+        let taker_rests = !taker_completed && taker_filled < order.quantity && order.time_in_force.rests();
+        if taker_rests {
+            self.order_margin.track(order.order_id, order.user_id, order.side, order.quantity - order.filled);
+        } else {
+            self.order_margin.untrack(&order.order_id);
+        }
+        self.rebalance_order_margin(order.user_id).await?;
+        self.expire_orders(expired.into_iter().map(|(expired, _)| expired).collect(), self.event_time).await?;
+
+        self.publish_self_trades(self_trades).await?;
+        self.publish_execution_reports(execution_reports).await?;
+
+        // 5. Emit trades; positions and fees are settled when the logged Trade
+        //    event comes back through process_trade (single settlement point)
+        self.emit_trades(&trades).await
+    }
+
+    /// Refuse new orders while prices cannot be trusted
+    /// Uses event timestamps only, so replay gates the same orders
+    fn check_price_gate(&self, at: Timestamp) -> Result<()> {
+        if let Some(tripped_at) = self.circuit_breaker_tripped_at
+            && at - tripped_at < self.circuit_breaker_cooldown
+        {
+            return Err(Error::CircuitBreakerOpen);
+        }
+
+        let age = match self.last_mark_price_at {
+            Some(updated_at) => at - updated_at,
+            None => return Err(Error::StaleMarkPrice { age_ms: u64::MAX }),  // No price since start
+        };
+        if age > self.max_mark_price_age {
+            return Err(Error::StaleMarkPrice { age_ms: age.as_millis() as u64 });
+        }
+
+        Ok(())
+    }
+
+    fn check_trading_phase(&self, post_only: bool) -> Result<()> {
+        if !self.trading_phase.admits(post_only) {
+            return Err(Error::TradingPhaseRestricted(self.trading_phase));
+        }
+        Ok(())
+    }
+
+    fn process_trading_phase_change(&mut self, event: BaseEvent) -> Result<()> {
+        let change = match event.payload {
+            EventPayload::TradingPhaseChanged(payload) => *payload,
+            _ => {
+                return Err(Error::InvalidEventPayload {
+                    expected: "TradingPhaseChanged".to_string(),
+                    found: format!("{:?}", event.event_type),
+                });
+            }
+        };
+
+        tracing::info!("Trading phase {:?} -> {:?} ({})", self.trading_phase, change.phase, change.reason);
+        self.trading_phase = change.phase;
+        Ok(())
+    }
+
+    /// Tell the user (WebSocket and their other channels) before liquidation fires
+    fn process_margin_call_warning(&mut self, event: BaseEvent) -> Result<()> {
+        let warning = match event.payload {
+            EventPayload::MarginCallWarning(payload) => *payload,
+            _ => {
+                return Err(Error::InvalidEventPayload {
+                    expected: "MarginCallWarning".to_string(),
+                    found: format!("{:?}", event.event_type),
+                });
+            }
+        };
+
+        tracing::info!(
+            "Margin call: user={:?}, leg={:?}, margin_ratio={:.3}",
+            warning.user_id, warning.position_side, warning.margin_ratio.to_f64()
+        );
+        crate::observability::metrics::MARGIN_CALLS.inc();
+        if let Some(notifier) = &self.notifier {
+            notifier.notify(Notification::margin_call(&warning));
+        }
+        Ok(())
+    }
+
+    pub fn trading_phase(&self) -> TradingPhase {
+        self.trading_phase
+    }
+
+    /// Sequence of the last event processed
+    pub fn last_sequence(&self) -> u64 {
+        self.last_sequence
+    }
+
+    /// Dated futures take no orders or amends from their expiry on
+    /// Judged on event time, so replay refuses exactly the same orders
+    fn check_expiry(&self, at: Timestamp) -> Result<()> {
+        if let Some(expiry) = self.market_config.expiry()
+            && (self.settlement_price.is_some() || at >= expiry.expires_at())
+        {
+            return Err(Error::MarketExpired { expires_at: expiry.expires_at() });
+        }
+        Ok(())
+    }
+
+    /// Pre-trade admission: price gate, validation, margin check and margin reservation
+    async fn admit_order(&mut self, order_submit: &OrderSubmit) -> Result<()> {
+        // Warm-up and other restricted phases only take post-only orders, or none
+        self.check_trading_phase(order_submit.post_only)?;
+        self.check_expiry(order_submit.base.timestamp)?;
+
+        // 0. Fresh mark price and no recent circuit breaker trip
+        self.check_price_gate(order_submit.base.timestamp)?;
+
+        // 1. Validate order parameters and price bands around mark
+        let validator = OrderValidator::new(self.market_config.clone())
+            .with_reference_price(self.last_mark_price);
+        validator.validate(order_submit)?;
+
+        if order_submit.order_type == OrderType::Market {
+            let (side, quantity) = (order_submit.side, order_submit.quantity);
+            let estimated_fill = self.matching.execute(move |matcher| {
+                matcher.order_book().sweep_price(side, quantity)
+            }).await?;
+            if let Some(estimated_fill) = estimated_fill {
+                validator.validate_slippage_band(estimated_fill)?;
+            }
+        }
+
+        // Hedge-mode accounts trade a Long or Short leg, one-way accounts the net position
+        let hedged = self.position_manager.read().await.position_mode(&order_submit.user_id) == PositionMode::Hedge;
+        if hedged != order_submit.position_side.is_hedge_leg() {
+            return Err(Error::PositionSideMismatch);
+        }
+
+        // The liquidation engine's reduce-only unwind orders only shrink the
+        // insurance fund's inventory: no exposure caps, no margin
+        if order_submit.user_id == *LIQUIDATION_ENGINE_USER_ID && order_submit.reduce_only {
+            return Ok(());
+        }
 
-        let required_margin = self.margin_calculator.calculate_initial_margin(
-            order_submit.quantity,
+        // 1b. Exposure caps: open orders, open notional, market open interest
+        {
+            let position_mgr = self.position_manager.read().await;
+            let (resting_bids, resting_asks) = self.order_margin.exposure(&order_submit.user_id);
+            let exposure = OpenExposure {
+                position_size: position_mgr.position_size(order_submit.user_id),
+                resting_bids,
+                resting_asks,
+                open_orders: self.order_margin.open_orders(&order_submit.user_id),
+            };
+            // Summed over every position, so only when the market is capped
+            let open_interest = match self.pre_trade_check.limits().max_open_interest {
+                Some(_) => position_mgr.open_interest(),
+                None => Quantity::zero(),
+            };
+            self.pre_trade_check.check_limits(
+                order_submit,
+                &exposure,
+                position_mgr.user_limits(&order_submit.user_id),
+                open_interest,
+                self.last_mark_price,
+            )?;
+        }
+
+        let user_id = order_submit.user_id;
+        let open_orders: Vec<Order> = self.matching.execute(move |matcher| {
+            matcher.order_book().user_orders(&user_id).into_iter().cloned().collect()
+        }).await?;
+
+        // 2. Check margin requirements: the order joins the user's open
+        //    orders and only the netted increase must be available
+        let balance_mgr = self.balance_manager.read().await;
+        let account = balance_mgr.get_account(order_submit.user_id)?;
+
+        let (position_size, leverage) = {
+            let position_mgr = self.position_manager.read().await;
+            (position_mgr.position_size(order_submit.user_id), position_mgr.leverage(&order_submit.user_id))
+        };
+        self.order_margin.track(order_submit.order_id, order_submit.user_id, order_submit.side, order_submit.quantity);
+        let required_margin = self.order_margin.shortfall(
+            &order_submit.user_id,
+            position_size,
+            &self.margin_calculator,
             self.last_mark_price,
+            leverage,
         );
 
         let available_balance = account.available_balance();
         if available_balance < required_margin {
+            self.order_margin.untrack(&order_submit.order_id);
             return Err(Error::InsufficientMargin {
                 required: required_margin,
                 available: available_balance,
             });
         }
+
+        // 2b. Account-wide: every position and resting order plus this one
+        {
+            let position_mgr = self.position_manager.read().await;
+            let holdings = [MarketHoldings {
+                market_id: self.market_id,
+                mark_price: self.last_mark_price,
+                leverage,
+                positions: position_mgr.positions_for(&order_submit.user_id),
+                open_orders: open_orders.iter().collect(),
+            }];
+            if let Err(e) = self.portfolio_check.check_order(order_submit, self.market_id, account, &holdings) {
+                self.order_margin.untrack(&order_submit.order_id);
+                return Err(e);
+            }
+        }
         drop(balance_mgr);
-        drop(position_mgr);
 
         // 3. Reserve margin
-        let mut balance_mgr = self.balance_manager.blocking_write();
-        balance_mgr.reserve_margin(order_submit.user_id, required_margin)?;
-        drop(balance_mgr);
+        self.rebalance_order_margin(order_submit.user_id).await?;
+
+        Ok(())
+    }
+
+    /// Bring a user's reserved order margin to the netted requirement of
+    /// their open orders against the current position
+    /// Returns the amount reserved (negative: released)
+    async fn rebalance_order_margin(&mut self, user_id: UserId) -> Result<Balance> {
+        let position_mgr = self.position_manager.read().await;
+        let mut balance_mgr = self.balance_manager.write().await;
+        self.order_margin.rebalance(
+            user_id,
+            position_mgr.position_size(user_id),
+            &self.margin_calculator,
+            self.last_mark_price,
+            position_mgr.leverage(&user_id),
+            &mut *balance_mgr,
+        )
+    }
+
+    /// Re-margin every user's open orders at the current mark once it has
+    /// moved past the `RemarginConfig` tolerance since the last pass
+    ///
+    /// Driven by PriceSnapshot events, so replay moves the same margin. A
+    /// top-up the account can't cover is logged and retried on the next pass;
+    /// the orders keep resting on what is already reserved.
+    async fn remargin_open_orders(&mut self) {
+        let reference = self.remargin_mark.to_f64();
+        let moved = (self.last_mark_price.to_f64() - reference).abs();
+        if !self.remargin.enabled || (reference > 0.0 && moved < reference * self.remargin.tolerance) {
+            return;
+        }
+        self.remargin_mark = self.last_mark_price;
+
+        for user_id in self.order_margin.users() {
+            match self.rebalance_order_margin(user_id).await {
+                Ok(delta) if delta != Balance::zero() => {
+                    tracing::debug!("Re-margined open orders of {:?} at {}: {}", user_id, self.last_mark_price, delta);
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    tracing::warn!("Order margin top-up for {:?} at {} failed: {}", user_id, self.last_mark_price, e);
+                }
+            }
+        }
+    }
+
+    /// Run a match on the matching core, draining the matcher's per-match
+    /// output in the same command
+    /// Positions are read-locked here and the guard moves into the command,
+    /// so the core never waits on engine locks; fills logged but not yet
+    /// settled are added to them
+    async fn run_match<F>(&self, f: F) -> Result<MatchOutcome>
+    where
+        F: FnOnce(&mut M, &dyn PositionProvider) -> Result<Vec<TradeEvent>> + Send + 'static,
+    {
+        let position_mgr = self.position_manager.clone().read_owned().await;
+        let in_flight = self.in_flight_fills.clone();
+        let event_time = self.event_time;
+        self.matching.execute(move |matcher| {
+            matcher.set_event_time(event_time);
+            let positions = PendingPositions { positions: &*position_mgr, in_flight: &in_flight };
+            let trades = f(matcher, &positions)?;
+            Ok(MatchOutcome {
+                trades,
+                completed_orders: matcher.drain_completed_orders(),
+                self_trades: matcher.drain_self_trades(),
+                execution_reports: matcher.drain_execution_reports(),
+            })
+        }).await?
+    }
+
+    async fn publish_self_trades(&self, self_trades: Vec<SelfTradePrevented>) -> Result<()> {
+        for prevented in self_trades {
+            let base = prevented.base.clone();
+            self.event_producer.produce(BaseEvent {
+                payload: EventPayload::SelfTradePrevented(Box::new(prevented)),
+                ..base
+            }).await?;
+        }
+        Ok(())
+    }
+
+    /// Log execution reports and push them to the user stream and notifications
+    async fn publish_execution_reports(&self, execution_reports: Vec<ExecutionReport>) -> Result<()> {
+        for report in execution_reports {
+            if let Some(notifier) = &self.notifier {
+                notifier.notify_fill(&report);
+            }
+            if let Some(user_stream) = &self.user_stream {
+                let _ = user_stream.send(WsEvent::ExecutionReport {
+                    order_id: report.order_id.to_string(),
+                    user_id: report.user_id.to_string(),
+                    liquidity: report.liquidity,
+                    fill_quantity: report.fill_quantity.to_i64(),
+                    average_price: report.average_price.to_i64(),
+                    fees: report.fees.to_i64(),
+                    filled_quantity: report.filled_quantity.to_i64(),
+                    remaining_quantity: report.remaining_quantity.to_i64(),
+                    status: report.status,
+                });
+            }
+
+            let base = report.base.clone();
+            self.event_producer.produce(BaseEvent {
+                payload: EventPayload::ExecutionReport(Box::new(report)),
+                ..base
+            }).await?;
+        }
+        Ok(())
+    }
+
+    /// Log matched trades; their fills count toward reduce-only caps until they settle
+    async fn emit_trades(&mut self, trades: &[TradeEvent]) -> Result<()> {
+        for trade in trades {
+            // Emit trade event
+            let trade_event = TradeEvent {
+                base: BaseEvent::new(
+                    EventType::Trade,
+                    self.market_id,
+                ),
+                trade_id: trade.trade_id,
+                maker_order_id: trade.maker_order_id,
+                taker_order_id: trade.taker_order_id,
+                maker_user_id: trade.maker_user_id,
+                taker_user_id: trade.taker_user_id,
+                price: trade.price,
+                quantity: trade.quantity,
+                maker_side: trade.maker_side,
+                maker_fee: trade.maker_fee,
+                taker_fee: trade.taker_fee,
+                liquidation: trade.liquidation,
+                maker_position_side: trade.maker_position_side,
+                taker_position_side: trade.taker_position_side,
+            };
+
+            // Emit trade event to event log
+            let base = trade_event.base.clone();
+            let base_event = BaseEvent {
+                payload: EventPayload::Trade(Box::new(trade_event)),
+                ..base
+            };
+            self.event_producer.produce(base_event).await?;
+            self.in_flight_fills.record(trade);
+
+            // In production, collect events and emit in batch
+            tracing::info!("Trade executed: {:?}", trade.trade_id);
+        }
+        Ok(())
+    }
+
+    /// Detect a crossed (bid > ask) or locked (bid == ask) book after a mutation
+    /// Logs a CrossedBook event, then matches the crossing orders or halts
+    /// the processor per `market_config.crossed_book_action`
+    async fn check_crossed_book(&mut self) -> Result<()> {
+        // The core publishes its view before replying, so this reflects our last command
+        let (best_bid, best_ask) = match self.matching.book().crossed() {
+            Some(crossed) => crossed,
+            None => return Ok(()),
+        };
+
+        let action = self.market_config.crossed_book_action;
+        let locked = best_bid == best_ask;
+        CROSSED_BOOK_DETECTED.with_label_values(&[if locked { "locked" } else { "crossed" }]).inc();
+        tracing::error!(
+            "{} book detected: best_bid={} best_ask={}, action={:?}",
+            if locked { "Locked" } else { "Crossed" }, best_bid.to_f64(), best_ask.to_f64(), action
+        );
+
+        let crossed_book = CrossedBook {
+            base: BaseEvent::new(EventType::CrossedBook, self.market_id),
+            best_bid,
+            best_ask,
+            locked,
+            action,
+        };
+        let base = crossed_book.base.clone();
+        self.event_producer.produce(BaseEvent {
+            payload: EventPayload::CrossedBook(Box::new(crossed_book)),
+            ..base
+        }).await?;
+
+        match action {
+            CrossedBookAction::Uncross => {
+                let mark_price = self.last_mark_price;
+                let MatchOutcome { trades, completed_orders, self_trades, execution_reports } =
+                    self.run_match(move |matcher, position_mgr| {
+                        matcher.uncross(position_mgr, mark_price)
+                    }).await?;
+
+                let (expired, completed_orders): (Vec<_>, Vec<_>) = completed_orders.into_iter()
+                    .partition(|(_, status)| *status == TerminalStatus::Expired);
+                let mut order_archive = self.order_archive.write().await;
+                for (completed, status) in completed_orders {
+                    order_archive.archive(completed, status)?;
+                }
+                drop(order_archive);
+                self.expire_orders(expired.into_iter().map(|(expired, _)| expired).collect(), self.event_time).await?;
+
+                tracing::warn!("Book uncrossed with {} trades", trades.len());
+                self.publish_self_trades(self_trades).await?;
+                self.publish_execution_reports(execution_reports).await?;
+                self.emit_trades(&trades).await
+            }
+            CrossedBookAction::Halt => {
+                self.halt(HaltReason::InvariantViolation {
+                    details: format!(
+                        "Crossed book: best_bid={} best_ask={}",
+                        best_bid.to_i64(), best_ask.to_i64()
+                    ),
+                }, self.event_time, None).await
+            }
+        }
+    }
 
-        // 4. Add order to order book
-        let mut order_book = self.order_book.blocking_write();
-        let order = Order {
+    fn order_from_submit(order_submit: &OrderSubmit) -> Order {
+        Order {
             order_id: order_submit.order_id,
             user_id: order_submit.user_id,
             side: order_submit.side,
@@ -235,95 +1036,111 @@ impl EventProcessor {
             reduce_only: order_submit.reduce_only,
             post_only: order_submit.post_only,
             slippage_limit: order_submit.slippage_limit,
-        };
-        order_book.add_order(order.clone())?;
-        drop(order_book);
+            self_trade_prevention: order_submit.self_trade_prevention,
+            position_side: order_submit.position_side,
+            min_fill_quantity: order_submit.min_fill_quantity,
+        }
+    }
 
-        // 5. Attempt matching
-        let mut matcher = self.matcher.write().await;
-        let mut balance_mgr = self.balance_manager.write().await;
-        let trades = matcher.match_order(&order, &mut *balance_mgr, self.last_mark_price)?;
-        drop(balance_mgr);
-        drop(matcher);
-
-        // 6. Update positions and balances based on trades
-        if !trades.is_empty() {
-            let mut position_mgr = self.position_manager.blocking_write();
-            let mut balance_mgr = self.balance_manager.blocking_write();
-
-            for trade in &trades {
-                // Update maker position (opposite side of trade)
-                let maker_trade_side = match trade.maker_side {
-                    Side::Buy => Side::Sell,  // Maker was buying, so they receive
-                    Side::Sell => Side::Buy,  // Maker was selling, so they deliver
-                };
-                position_mgr.update_position(
-                    trade.maker_user_id,
-                    maker_trade_side,
-                    trade.quantity,
-                    trade.price,
-                )?;
+    /// Push queue positions of every resting order at the given levels to the user stream
+    async fn publish_queue_positions(&self, levels: &[(Side, Price)]) -> Result<()> {
+        let user_stream = match &self.user_stream {
+            Some(user_stream) => user_stream,
+            None => return Ok(()),
+        };
 
-                // Update taker position (same side as trade)
-                let taker_trade_side = match trade.maker_side {
-                    Side::Buy => Side::Buy,   // Taker was selling to maker's buy
-                    Side::Sell => Side::Sell, // Taker was buying from maker's sell
-                };
-                position_mgr.update_position(
-                    trade.taker_user_id,
-                    taker_trade_side,
-                    trade.quantity,
-                    trade.price,
-                )?;
+        let levels = levels.to_vec();
+        let positions: Vec<(OrderId, UserId, QueuePosition)> = self.matching.execute(move |matcher| {
+            levels.iter()
+                .flat_map(|(side, price)| matcher.order_book().level_queue_positions(*side, *price))
+                .map(|(order, position)| (order.order_id, order.user_id, position))
+                .collect()
+        }).await?;
+
+        for (order_id, user_id, position) in positions {
+            // No subscribers is not an error
+            let _ = user_stream.send(WsEvent::QueuePosition {
+                order_id: order_id.to_string(),
+                user_id: user_id.to_string(),
+                price: position.price.to_i64(),
+                orders_ahead: position.orders_ahead,
+                quantity_ahead: position.quantity_ahead.to_i64(),
+            });
+        }
+        Ok(())
+    }
 
-                // Apply fees
-                balance_mgr.adjust_balance(
-                    trade.maker_user_id,
-                    Balance::from_i64(-trade.maker_fee.amount.to_i64()),
-                )?;
-                balance_mgr.adjust_balance(
-                    trade.taker_user_id,
-                    Balance::from_i64(-trade.taker_fee.amount.to_i64()),
-                )?;
+    /// Reject an order that already passed admission, releasing its reserved margin
+    async fn kill_admitted_order(&mut self, order: &Order, error: &Error) -> Result<()> {
+        self.order_margin.untrack(&order.order_id);
+        self.rebalance_order_margin(order.user_id).await?;
+        self.reject_order(order, error).await
+    }
 
-                // Emit trade event
-                let trade_event = TradeEvent {
-                    base: BaseEvent::new(
-                        EventType::Trade,
-                        self.market_id,
-                    ),
-                    trade_id: trade.trade_id,
-                    maker_order_id: trade.maker_order_id,
-                    taker_order_id: trade.taker_order_id,
-                    maker_user_id: trade.maker_user_id,
-                    taker_user_id: trade.taker_user_id,
-                    price: trade.price,
-                    quantity: trade.quantity,
-                    maker_side: trade.maker_side,
-                    maker_fee: trade.maker_fee,
-                    taker_fee: trade.taker_fee,
-                    liquidation: trade.liquidation,
-                };
+    /// Emit OrderRejected to the event log and user stream, and archive the
+    /// order so GET /orders/:id reports the reason
+    async fn reject_order(&mut self, order: &Order, error: &Error) -> Result<()> {
+        let reason = RejectReason::from_error(error);
+        tracing::info!("Order rejected: order_id={:?}, reason={}", order.order_id, reason);
+        record_order_rejected(reason.code());
+
+        let rejected = OrderRejected {
+            base: BaseEvent::new(EventType::OrderRejected, self.market_id),
+            order_id: order.order_id,
+            user_id: order.user_id,
+            reason: reason.clone(),
+        };
 
-                // Emit trade event to event log
-                let base = trade_event.base.clone();
-                let base_event = BaseEvent {
-                    payload: EventPayload::Trade(Box::new(trade_event)),
-                    ..base
-                };
-                self.event_producer.produce(base_event).await?;
-                
-                // In production, collect events and emit in batch
-                tracing::info!("Trade executed: {:?}", trade.trade_id);
-            }
+        let base = rejected.base.clone();
+        self.event_producer.produce(BaseEvent {
+            payload: EventPayload::OrderRejected(Box::new(rejected)),
+            ..base
+        }).await?;
+
+        if let Some(user_stream) = &self.user_stream {
+            // No subscribers is not an error
+            let _ = user_stream.send(WsEvent::OrderRejected {
+                order_id: order.order_id.to_string(),
+                user_id: order.user_id.to_string(),
+                reason: reason.clone(),
+            });
         }
 
-        let side = match order_submit.side {
-            Side::Buy => "buy",
-            Side::Sell => "sell",
+        let mut order_archive = self.order_archive.write().await;
+        order_archive.archive_rejected(order.clone(), reason)?;
+
+        Ok(())
+    }
+
+    /// Tell a user their account request was refused: RequestRejected to the
+    /// event log and user stream
+    async fn reject_request(&mut self, request_id: EventId, request_type: EventType, user_id: UserId, error: &Error) -> Result<()> {
+        let reason = RejectReason::from_error(error);
+        tracing::info!("{:?} from {:?} rejected: {}", request_type, user_id, reason);
+
+        let rejected = RequestRejected {
+            base: BaseEvent::new(EventType::RequestRejected, self.market_id),
+            request_id,
+            request_type,
+            user_id,
+            reason: reason.clone(),
         };
-        let order_type = if order_submit.price.is_some() { "limit" } else { "market" };
-        ORDERS_SUBMITTED.with_label_values(&[side, order_type]).inc();
+
+        let base = rejected.base.clone();
+        self.event_producer.produce(BaseEvent {
+            payload: EventPayload::RequestRejected(Box::new(rejected)),
+            ..base
+        }).await?;
+
+        if let Some(user_stream) = &self.user_stream {
+            // No subscribers is not an error
+            let _ = user_stream.send(WsEvent::RequestRejected {
+                request_id: request_id.to_string(),
+                request_type: format!("{:?}", request_type),
+                user_id: user_id.to_string(),
+                reason,
+            });
+        }
 
         Ok(())
     }
@@ -341,38 +1158,36 @@ impl EventProcessor {
             }
         };
 
-        // 1. Find order in order book
-        let mut order_book = self.order_book.blocking_write();
-        let order = order_book.get_order(&order_cancel.order_id)
-            .ok_or(Error::OrderNotFound(order_cancel.order_id))?;
-
-        // Verify user owns this order
-        if order.user_id != order_cancel.user_id {
-            return Err(Error::Unauthorized);
+        // Untriggered stops are not in the book
+        let pending_owner = self.trigger_monitor.get(&order_cancel.order_id)
+            .map(|pending| pending.order.user_id)
+            .or_else(|| self.trigger_engine.get(&order_cancel.order_id).map(|stop| stop.order.user_id));
+        if let Some(owner) = pending_owner {
+            if owner != order_cancel.user_id {
+                return Err(Error::Unauthorized);
+            }
+            return self.cancel_pending_stop(order_cancel.order_id).await;
         }
 
-        // 2. Calculate unfilled quantity
-        let unfilled_quantity = order.quantity - order.filled;
-
-        // 3. Remove order from order book
-        order_book.remove_order(&order_cancel.order_id)?;
-        drop(order_book);
-
-        // 4. Release reserved margin
-        if unfilled_quantity > Quantity::zero() {
-            let mut balance_mgr = self.balance_manager.blocking_write();
-
-            // Calculate margin to release based on unfilled quantity
-            let position_mgr = self.position_manager.blocking_read();
-            let position = position_mgr.get_position(&order_cancel.user_id);
+        // 1-3. Find the order, verify the user owns it and take it off the book
+        let (order_id, user_id) = (order_cancel.order_id, order_cancel.user_id);
+        let removed = self.matching.execute(move |matcher| {
+            let book = matcher.order_book_mut();
+            let owner = book.get_order(&order_id).ok_or(Error::OrderNotFound(order_id))?.user_id;
+            if owner != user_id {
+                return Err(Error::Unauthorized);
+            }
+            book.remove_order(&order_id)
+        }).await??;
+        let unfilled_quantity = removed.quantity - removed.filled;
 
-            let margin_to_release = self.margin_calculator.calculate_initial_margin(
-                unfilled_quantity,
-                self.last_mark_price,
-            );
+        // Orders behind the cancelled one moved up
+        self.publish_queue_positions(&[(removed.side, removed.price)]).await?;
+        self.order_archive.write().await.archive(removed, TerminalStatus::Cancelled)?;
 
-            balance_mgr.release_margin(order_cancel.user_id, margin_to_release)?;
-        }
+        // 4. Release reserved margin (whatever the remaining orders no longer need)
+        self.order_margin.untrack(&order_cancel.order_id);
+        self.rebalance_order_margin(order_cancel.user_id).await?;
 
         // Observability
         use crate::observability::metrics::*;
@@ -384,372 +1199,2586 @@ impl EventProcessor {
         Ok(())
     }
 
-    async fn process_trade(&mut self, event: BaseEvent) -> Result<()> {
-        tracing::debug!("Processing trade event: {:?}", event.event_id);
+    /// Cancel every resting order of a user (or of every user, operator only)
+    /// Margin is rebalanced once per user after all their orders are gone;
+    /// untriggered stops are kept since they protect open positions
+    async fn process_cancel_all_orders(&mut self, event: BaseEvent) -> Result<()> {
+        tracing::debug!("Processing cancel-all event: {:?}", event.event_id);
 
-        // Deserialize TradeEvent from event.metadata
-        let trade_event = match event.payload {
-            crate::events::base::EventPayload::Trade(payload) => *payload,
+        let cancel_all = match event.payload {
+            EventPayload::CancelAllOrders(payload) => *payload,
             _ => {
                 return Err(Error::InvalidEventPayload {
-                    expected: "Trade".to_string(),
+                    expected: "CancelAllOrders".to_string(),
                     found: format!("{:?}", event.event_type),
                 });
             }
         };
 
-        // 1. Update maker position
-        let mut position_mgr = self.position_manager.blocking_write();
+        if cancel_all.user_id.is_none()
+            && !cancel_all.operator_id.is_some_and(crate::utils::helper::is_authorized_operator)
+        {
+            return Err(Error::Unauthorized);
+        }
 
-        position_mgr.update_position(
-            trade_event.maker_user_id,
-            trade_event.maker_side,
-            trade_event.quantity,
-            trade_event.price,
-        )?;
+        self.cancel_resting_orders(cancel_all.user_id, cancel_all.reason).await
+    }
 
-        // 2. Update taker position (opposite side of maker)
-        let taker_side = match trade_event.maker_side {
-            Side::Buy => Side::Sell,
-            Side::Sell => Side::Buy,
-        };
+    /// Remove the resting orders of `user_id` (every user if None), release
+    /// their margin and publish AllOrdersCancelled
+    async fn cancel_resting_orders(&mut self, user_id: Option<UserId>, reason: String) -> Result<()> {
+        // 1. Remove every matching order from the book
+        let removed = self.matching.execute(move |matcher| {
+            let book = matcher.order_book_mut();
+            let orders = match user_id {
+                Some(user_id) => book.user_orders(&user_id),
+                None => book.resting_orders(),
+            };
+            let mut order_ids: Vec<(Timestamp, OrderId)> = orders.into_iter()
+                .map(|order| (order.timestamp, order.order_id))
+                .collect();
+            order_ids.sort_by_key(|(timestamp, order_id)| (*timestamp, order_id.0));
+
+            order_ids.iter()
+                .map(|(_, order_id)| book.remove_order(order_id))
+                .collect::<Result<Vec<Order>>>()
+        }).await??;
+
+        // 2. Aggregate unfilled quantity per user and rebalance margin once each
+        let mut unfilled: HashMap<UserId, Quantity> = HashMap::new();
+        for order in &removed {
+            let entry = unfilled.entry(order.user_id).or_insert(Quantity::zero());
+            *entry = *entry + (order.quantity - order.filled);
+            self.order_margin.untrack(&order.order_id);
+        }
 
-        position_mgr.update_position(
-            trade_event.taker_user_id,
-            taker_side,
-            trade_event.quantity,
-            trade_event.price,
-        )?;
+        let mut released_margin = Balance::zero();
+        let mut users: Vec<UserId> = unfilled.keys().copied().collect();
+        users.sort_by_key(|user_id| user_id.0);
+        for user_id in users {
+            released_margin = released_margin - self.rebalance_order_margin(user_id).await?;
+        }
 
-        drop(position_mgr);
+        // 3. Archive and notify queues that moved
+        let mut levels: Vec<(Side, Price)> = Vec::new();
+        for order in &removed {
+            if !levels.contains(&(order.side, order.price)) {
+                levels.push((order.side, order.price));
+            }
+        }
 
-        // 3. Apply maker and taker fees
-        let mut balance_mgr = self.balance_manager.blocking_write();
-        balance_mgr.adjust_balance(
-            trade_event.maker_user_id,
-            Balance::from_i64(-trade_event.maker_fee.amount.to_i64()),
-        )?;
-        balance_mgr.adjust_balance(
-            trade_event.taker_user_id,
-            Balance::from_i64(-trade_event.taker_fee.amount.to_i64()),
-        )?;
-        drop(balance_mgr);
+        let order_ids: Vec<OrderId> = removed.iter().map(|order| order.order_id).collect();
+        let mut order_archive = self.order_archive.write().await;
+        for order in removed {
+            order_archive.archive(order, TerminalStatus::Cancelled)?;
+        }
+        drop(order_archive);
+        self.publish_queue_positions(&levels).await?;
 
-        // 4. Update margin requirements (recalculate after position change)
-        let position_mgr = self.position_manager.blocking_read();
-        let maker_position = position_mgr.get_position(&trade_event.maker_user_id);
-        let taker_position = position_mgr.get_position(&trade_event.taker_user_id);
+        crate::observability::metrics::ORDERS_CANCELLED.inc_by(order_ids.len() as u64);
+        tracing::warn!(
+            "Cancel-all ({}): {} orders across {} users, released margin {}",
+            reason, order_ids.len(), unfilled.len(), released_margin.to_f64()
+        );
 
-        if let Some(pos) = maker_position {
-            let required_margin = self.margin_calculator.calculate_maintenance_margin(
-                Quantity::from_i64(pos.size.abs()),
-                trade_event.price,
-            );
-            tracing::debug!("Maker margin requirement: {}", required_margin.to_i64());
-        }
+        // 4. Summary event
+        let summary = AllOrdersCancelled {
+            base: BaseEvent::new(EventType::AllOrdersCancelled, self.market_id),
+            user_id,
+            order_ids,
+            users: unfilled.len() as u32,
+            released_margin,
+            reason,
+        };
+        let base = summary.base.clone();
+        self.event_producer.produce(BaseEvent {
+            payload: EventPayload::AllOrdersCancelled(Box::new(summary)),
+            ..base
+        }).await?;
 
-        if let Some(pos) = taker_position {
-            let required_margin = self.margin_calculator.calculate_maintenance_margin(
-                Quantity::from_i64(pos.size.abs()),
-                trade_event.price,
-            );
-            tracing::debug!("Taker margin requirement: {}", required_margin.to_i64());
+        Ok(())
+    }
+
+    /// Remove GTD orders expired as of the event timestamp (not the wall
+    /// clock, so replay expires exactly the same orders)
+    async fn process_expire_orders(&mut self, event: BaseEvent) -> Result<()> {
+        match event.payload {
+            EventPayload::ExpireOrders(_) => {}
+            _ => {
+                return Err(Error::InvalidEventPayload {
+                    expected: "ExpireOrders".to_string(),
+                    found: format!("{:?}", event.event_type),
+                });
+            }
         }
 
-        // 5. Remove fully filled orders from order book
-        let mut order_book = self.order_book.blocking_write();
+        let now = event.timestamp;
+        let expired = self.matching.execute(move |matcher| matcher.order_book_mut().sweep_expired(now)).await?;
+        self.expire_orders(expired, now).await
+    }
 
-        if let Some(maker_order) = order_book.get_order(&trade_event.maker_order_id) {
-            if maker_order.filled >= maker_order.quantity {
-                order_book.remove_order(&trade_event.maker_order_id)?;
-            }
+    /// Release the margin of GTD orders that left the book at their expiry
+    /// (swept, or found expired while matching) and log `OrderExpired` for each
+    async fn expire_orders(&mut self, expired: Vec<Order>, now: Timestamp) -> Result<()> {
+        if expired.is_empty() {
+            return Ok(());
         }
 
-        if let Some(taker_order) = order_book.get_order(&trade_event.taker_order_id) {
-            if taker_order.filled >= taker_order.quantity {
-                order_book.remove_order(&trade_event.taker_order_id)?;
-            }
+        let mut notices = Vec::with_capacity(expired.len());
+        for order in &expired {
+            let unfilled = order.quantity - order.filled;
+            self.order_margin.untrack(&order.order_id);
+            let released_margin = -self.rebalance_order_margin(order.user_id).await?;
+
+            notices.push(OrderExpired {
+                base: BaseEvent::new(EventType::OrderExpired, self.market_id),
+                order_id: order.order_id,
+                user_id: order.user_id,
+                expires_at: order.time_in_force.expires_at().unwrap_or(now),
+                unfilled_quantity: unfilled,
+                released_margin,
+            });
         }
 
-        // Observability
-        use crate::observability::metrics::*;
-        TRADES_EXECUTED.inc();
-        VOLUME_TRADED.inc_by(trade_event.quantity.to_i64() as f64);
+        let mut levels: Vec<(Side, Price)> = Vec::new();
+        for order in &expired {
+            if !levels.contains(&(order.side, order.price)) {
+                levels.push((order.side, order.price));
+            }
+        }
 
-        tracing::info!("Trade processed: {:?}, qty: {}, price: {}", 
-                      trade_event.trade_id,
-                      trade_event.quantity.to_i64(),
-                      trade_event.price.to_f64());
+        let mut order_archive = self.order_archive.write().await;
+        for order in expired {
+            order_archive.archive(order, TerminalStatus::Expired)?;
+        }
+        drop(order_archive);
+        self.publish_queue_positions(&levels).await?;
+
+        crate::observability::metrics::ORDERS_EXPIRED.inc_by(notices.len() as u64);
+        tracing::info!("Expired {} GTD orders as of {}", notices.len(), now.physical);
+
+        for notice in notices {
+            let base = notice.base.clone();
+            self.event_producer.produce(BaseEvent {
+                payload: EventPayload::OrderExpired(Box::new(notice)),
+                ..base
+            }).await?;
+        }
 
-        TRADES_PROCESSED.inc();
-    
         Ok(())
     }
 
-    async fn process_funding(&mut self, event: BaseEvent) -> Result<()> {
-        tracing::debug!("Processing funding event: {:?}", event.event_id);
-
-        // Deserialize FundingEvent from event.metadata
-        let funding_event = match &event.payload {
-            EventPayload::Funding(payload) => payload.as_ref().clone(),
+    /// Switch an account's position mode; an account with open positions or
+    /// resting orders keeps its current mode
+    async fn process_position_mode_change(&mut self, event: BaseEvent) -> Result<()> {
+        let change = match event.payload {
+            EventPayload::PositionModeChange(payload) => *payload,
             _ => {
                 return Err(Error::InvalidEventPayload {
-                    expected: "Funding".to_string(),
+                    expected: "PositionModeChange".to_string(),
                     found: format!("{:?}", event.event_type),
                 });
             }
         };
 
-        // 1. Apply each funding payment
-        let mut balance_mgr = self.balance_manager.blocking_write();
-        let mut total_payments: i64 = 0;
-
-        for payment in &funding_event.payments {
-            balance_mgr.adjust_balance(payment.user_id, payment.payment)?;
-            total_payments += payment.payment.to_i64();
-
-            tracing::debug!("Applied funding payment: user={:?}, amount={}", 
-                          payment.user_id, payment.payment.to_i64());
-        }
-
-        drop(balance_mgr);
-
-        // 2. Verify zero-sum property (critical invariant)
-        if total_payments != 0 {
-            return Err(Error::FundingNotZeroSum { sum: total_payments });
-        }
+        let has_orders = self.has_resting_orders(change.user_id).await?;
+        let result = if has_orders {
+            Err(Error::PositionModeLocked)
+        } else {
+            self.position_manager.write().await.set_position_mode(change.user_id, change.mode)
+        };
 
-        // 3. Update position funding timestamps
-        let mut position_mgr = self.position_manager.blocking_write();
-        for payment in &funding_event.payments {
-            if let Some(position) = position_mgr.get_position_mut(&payment.user_id) {
-                position.last_funding_timestamp = funding_event.base.timestamp;
+        match result {
+            Ok(()) => {
+                tracing::info!("Position mode for {:?} set to {:?}", change.user_id, change.mode);
+                Ok(())
             }
+            Err(e) => self.reject_request(event.event_id, event.event_type, change.user_id, &e).await,
         }
-
-        // Observability
-        use crate::observability::metrics::*;
-        FUNDING_EVENTS_PROCESSED.inc();
-        FUNDING_RATE.with_label_values(&["default"]).set(funding_event.funding_rate.to_f64());
-
-        tracing::info!("Funding applied: rate={:.6}, payments={}", 
-                      funding_event.funding_rate.to_f64(),
-                      funding_event.payments.len());
-
-        Ok(())
     }
 
-    async fn process_liquidation(&mut self, event: BaseEvent) -> Result<()> {
-        tracing::debug!("Processing liquidation event: {:?}", event.event_id);
+    async fn has_resting_orders(&self, user_id: UserId) -> Result<bool> {
+        self.matching.execute(move |matcher| !matcher.order_book().user_orders(&user_id).is_empty()).await
+    }
 
-        // Deserialize LiquidationTriggered from event.metadata
-        let liquidation_event = match event.payload {
-            crate::events::base::EventPayload::Liquidation(payload) => *payload,
+    async fn process_margin_mode_change(&mut self, event: BaseEvent) -> Result<()> {
+        let change = match event.payload {
+            EventPayload::MarginModeChange(payload) => *payload,
             _ => {
                 return Err(Error::InvalidEventPayload {
-                    expected: "Liquidation".to_string(),
+                    expected: "MarginModeChange".to_string(),
                     found: format!("{:?}", event.event_type),
                 });
             }
         };
 
-
-        // Get position to create proper liquidation candidate
-        let position_mgr = self.position_manager.blocking_read();
-        let position = position_mgr.get_position(&liquidation_event.user_id)
-            .ok_or(Error::ConfigError("Position not found for liquidation".to_string()))?;
-
-        // Create liquidation candidate from event
-        // LiquidationCandidate fields: user_id, position, margin_ratio, maintenance_margin, mark_price
-        let margin_ratio_value = if liquidation_event.account_value.to_i64() != 0 {
-            liquidation_event.maintenance_margin.to_f64() / liquidation_event.account_value.to_f64()
+        let has_orders = self.has_resting_orders(change.user_id).await?;
+        let result = if has_orders {
+            Err(Error::MarginModeLocked)
         } else {
-            0.0
+            self.position_manager.write().await.set_margin_mode(change.user_id, change.mode)
         };
 
-        let candidate = crate::liquidation::detector::LiquidationCandidate {
-            user_id: liquidation_event.user_id,
-            position: position.clone(),
-            margin_ratio: Ratio::from_f64(margin_ratio_value),
-            maintenance_margin: liquidation_event.maintenance_margin,
-            mark_price: liquidation_event.mark_price,
+        match result {
+            Ok(()) => {
+                tracing::info!("Margin mode for {:?} set to {:?}", change.user_id, change.mode);
+                Ok(())
+            }
+            Err(e) => self.reject_request(event.event_id, event.event_type, change.user_id, &e).await,
+        }
+    }
+
+    async fn process_set_leverage(&mut self, event: BaseEvent) -> Result<()> {
+        let change = match event.payload {
+            EventPayload::SetLeverage(payload) => *payload,
+            _ => {
+                return Err(Error::InvalidEventPayload {
+                    expected: "SetLeverage".to_string(),
+                    found: format!("{:?}", event.event_type),
+                });
+            }
         };
-        drop(position_mgr);
 
-        // Execute liquidation
-        let mut matcher = self.matcher.blocking_write();
-        let mut balance_mgr = self.balance_manager.blocking_write();
+        let result = self.set_leverage(change.user_id, change.leverage).await;
 
-        // Add candidate to executor queue
-        let mut executor = self.liquidation_executor.clone();
-        executor.add_candidate(candidate);
+        match result {
+            Ok(()) => {
+                tracing::info!("Leverage for {:?} set to {}x", change.user_id, change.leverage);
+                Ok(())
+            }
+            Err(e) => self.reject_request(event.event_id, event.event_type, change.user_id, &e).await,
+        }
+    }
 
-        match executor.execute_next(&mut *matcher, &mut *balance_mgr) {
-            Ok(Some(liq_event)) => {
-                drop(matcher);
-                drop(balance_mgr);
+    /// Operator override of an account's exposure caps; applies to orders
+    /// admitted from now on (open orders are left alone)
+    async fn process_user_limits_set(&mut self, event: BaseEvent) -> Result<()> {
+        let change = match event.payload {
+            EventPayload::UserLimitsSet(payload) => *payload,
+            _ => {
+                return Err(Error::InvalidEventPayload {
+                    expected: "UserLimitsSet".to_string(),
+                    found: format!("{:?}", event.event_type),
+                });
+            }
+        };
 
-                // Update position
-                let mut position_mgr = self.position_manager.blocking_write();
+        if !crate::utils::helper::is_authorized_operator(change.operator_id) {
+            tracing::error!("Rejected limit override from unauthorized operator {:?}", change.operator_id);
+            return Err(Error::Unauthorized);
+        }
 
-                if let Some(position) = position_mgr.get_position_mut(&liquidation_event.user_id) {
-                    // Calculate new position size after liquidation
-                    let liquidated_qty = liq_event.liquidated_size.to_i64();
+        self.position_manager.write().await.set_user_limits(change.user_id, change.limits);
+        tracing::warn!(
+            "Limits for {:?} set to {:?} by {:?}: {}",
+            change.user_id, change.limits, change.operator_id, change.reason
+        );
+        Ok(())
+    }
 
-                    if position.size > 0 {
-                        // Long position
-                        position.size = position.size.saturating_sub(liquidated_qty);
-                    } else {
-                        // Short position
-                        position.size = position.size.saturating_add(liquidated_qty);
-                    }
+    /// Put the designated makers on the LP maker fee rate for trades matched
+    /// from now on
+    async fn process_lp_makers_designated(&mut self, event: BaseEvent) -> Result<()> {
+        let designation = match event.payload {
+            EventPayload::LpMakersDesignated(payload) => *payload,
+            _ => {
+                return Err(Error::InvalidEventPayload {
+                    expected: "LpMakersDesignated".to_string(),
+                    found: format!("{:?}", event.event_type),
+                });
+            }
+        };
 
-                    // Record insurance fund charge if any
-                    if liq_event.insurance_fund_loss > Balance::zero() {
-                        tracing::warn!("Insurance fund charged: {}", 
-                                      liq_event.insurance_fund_loss.to_i64());
-                    }
+        self.set_lp_makers(designation.makers).await?;
+        tracing::info!(
+            "{} LP makers designated for the period from {:?}",
+            self.lp_makers.len(), designation.period_end
+        );
+        Ok(())
+    }
 
-                    // Remove position if fully liquidated
-                    if position.size == 0 {
-                        position_mgr.remove_position(&liquidation_event.user_id);
-                        tracing::info!("Position fully liquidated: {:?}", liquidation_event.user_id);
-                    }
-                }
+    async fn set_lp_makers(&mut self, makers: Vec<UserId>) -> Result<()> {
+        let lp_makers = makers.iter().copied().collect();
+        self.matching.execute(move |matcher| matcher.set_lp_makers(lp_makers)).await?;
+        self.lp_makers = makers;
+        Ok(())
+    }
 
-                // Observability
-                let liq_type = match liq_event.liquidation_type {
-                    LiquidationType::Full => "full",
-                    LiquidationType::Partial => "partial",
-                };
-                LIQUIDATIONS_EXECUTED.with_label_values(&[liq_type]).inc();
-                LIQUIDATION_VOLUME.inc_by(liq_event.liquidated_size.to_i64() as f64);
+    /// Apply a leverage choice and re-margin open orders at it
+    /// The cap is the tier of the account's current position
+    async fn set_leverage(&mut self, user_id: UserId, leverage: f64) -> Result<()> {
+        if leverage.is_nan() || leverage < 1.0 {
+            return Err(Error::InvalidLeverage(leverage));
+        }
 
-                tracing::info!("Liquidation executed: user={:?}, size={}, price={}", 
-                              liquidation_event.user_id,
-                              liq_event.liquidated_size.to_i64(),
-                              liq_event.liquidation_price.to_f64());
-            }
-            Ok(None) => {
-                tracing::warn!("Liquidation execution returned no result");
-            }
-            Err(e) => {
-                tracing::error!("Liquidation execution failed: {:?}", e);
-                return Err(e);
-            }
+        let mut position_mgr = self.position_manager.write().await;
+        let size = Quantity::from_i64(position_mgr.position_size(user_id).abs());
+        let max = self.margin_calculator.max_leverage(size, self.last_mark_price);
+        if leverage > max {
+            return Err(Error::LeverageExceeded { leverage, max });
         }
 
+        let previous = position_mgr.leverage(&user_id);
+        position_mgr.set_leverage(user_id, Some(leverage));
+        drop(position_mgr);
+
+        // Lower leverage needs more order margin; refuse if it isn't available
+        if let Err(e) = self.rebalance_order_margin(user_id).await {
+            self.position_manager.write().await.set_leverage(user_id, previous);
+            return Err(e);
+        }
         Ok(())
     }
 
-    async fn process_balance_update(&mut self, event: BaseEvent) -> Result<()> {
-        tracing::debug!("Processing balance update event: {:?}", event.event_id);
-
-        let balance_update = match event.payload {
-            crate::events::base::EventPayload::BalanceUpdate(payload) => *payload,
+    /// Move collateral into or out of an isolated position's bucket
+    /// Removal must leave bucket + unrealized loss covering initial margin
+    async fn process_isolated_margin_transfer(&mut self, event: BaseEvent) -> Result<()> {
+        let transfer = match event.payload {
+            EventPayload::IsolatedMarginTransfer(payload) => *payload,
             _ => {
                 return Err(Error::InvalidEventPayload {
-                    expected: "BalanceUpdate".to_string(),
+                    expected: "IsolatedMarginTransfer".to_string(),
                     found: format!("{:?}", event.event_type),
                 });
             }
         };
 
-        let mut balance_mgr = self.balance_manager.blocking_write();
-
-        // 1. Apply balance change (deposit or withdrawal)
-        match balance_update.update_type {
-            BalanceUpdateType::Deposit => {
-                // Create account if it doesn't exist
-                if balance_mgr.get_account(balance_update.user_id).is_err() {
-                    balance_mgr.create_account(balance_update.user_id)?;
-                }
-
-                balance_mgr.adjust_balance(balance_update.user_id, balance_update.amount)?;
+        let result = self.transfer_isolated_margin(transfer.user_id, transfer.position_side, transfer.amount).await;
 
-                tracing::info!("Deposit processed: user={:?}, amount={}", 
-                              balance_update.user_id, balance_update.amount.to_i64());
+        match result {
+            Ok(bucket) => {
+                tracing::info!(
+                    "Isolated margin for {:?} {:?} moved by {}, now {}",
+                    transfer.user_id, transfer.position_side, transfer.amount.to_i64(), bucket.to_i64()
+                );
+                Ok(())
             }
-            BalanceUpdateType::Withdrawal => {
-                // Verify sufficient available balance
-                let account = balance_mgr.get_account(balance_update.user_id)?;
-
-                if account.available_balance() < balance_update.amount {
-                    return Err(Error::InsufficientAvailableBalance);
-                }
+            Err(e) => self.reject_request(event.event_id, event.event_type, transfer.user_id, &e).await,
+        }
+    }
 
-                balance_mgr.adjust_balance(
-                    balance_update.user_id,
-                    Balance::from_i64(-balance_update.amount.to_i64())
-                )?;
+    async fn transfer_isolated_margin(&self, user_id: UserId, leg: PositionSide, amount: Balance) -> Result<Balance> {
+        let mut position_mgr = self.position_manager.write().await;
+        let leverage = position_mgr.leverage(&user_id);
+        let position = position_mgr.get_leg_mut(&user_id, leg)
+            .filter(|position| position.is_isolated())
+            .ok_or(Error::PositionNotIsolated)?;
 
-                tracing::info!("Withdrawal processed: user={:?}, amount={}", 
-                              balance_update.user_id, balance_update.amount.to_i64());
+        if amount >= Balance::zero() {
+            self.balance_manager.write().await.reserve_margin(user_id, amount)?;
+        } else {
+            let unrealized = PnLCalculator::calculate_unrealized_pnl_for(
+                self.margin_calculator.contract(), position, self.last_mark_price,
+            )?;
+            let remaining = position.isolated_margin + amount + unrealized.min(Balance::zero());
+            let required = self.margin_calculator.calculate_initial_margin(position.abs_size(), self.last_mark_price, leverage);
+            if position.isolated_margin + amount < Balance::zero() || remaining < required {
+                return Err(Error::InsufficientIsolatedMargin { required, remaining });
             }
+            self.balance_manager.write().await.release_margin(user_id, -amount)?;
         }
 
-        // 2. Record ledger entry
-        let account = balance_mgr.get_account(balance_update.user_id)?;
+        position.isolated_margin = position.isolated_margin + amount;
+        Ok(position.isolated_margin)
+    }
 
-        // 3. Verify balance remains non-negative
-        if account.balance < Balance::zero() {
-            tracing::error!("Negative balance detected: user={:?}, balance={}", 
-                          balance_update.user_id, account.balance.to_i64());
-            return Err(Error::InsufficientBalance);
-        }
+    /// Keep an isolated leg's bucket in step with a fill
+    /// - Closed size releases its pro-rata share of the bucket to the account
+    /// - Opened size reserves its initial margin into the bucket (the order
+    ///   margin it replaces was released when the fill settled), capped at
+    ///   what the account has available
+    async fn rebalance_isolated_margin(&self, user_id: UserId, leg: PositionSide, size_before: i64, price: Price) -> Result<()> {
+        let mut position_mgr = self.position_manager.write().await;
+        let leverage = position_mgr.leverage(&user_id);
+        let position = match position_mgr.get_leg_mut(&user_id, leg) {
+            Some(position) if position.is_isolated() => position,
+            _ => return Ok(()),
+        };
 
-        // Observability
-        use crate::observability::metrics::*;
-        match balance_update.update_type {
-            BalanceUpdateType::Deposit => {
-                DEPOSITS_PROCESSED.inc();
-                DEPOSIT_VOLUME.inc_by(balance_update.amount.to_i64() as f64);
-            }
-            BalanceUpdateType::Withdrawal => {
-                WITHDRAWALS_PROCESSED.inc();
-                WITHDRAWAL_VOLUME.inc_by(balance_update.amount.to_i64() as f64);
-            }
-        }
+        let (before, after) = (size_before.unsigned_abs() as i64, position.size.unsigned_abs() as i64);
+        let flipped = size_before.signum() * position.size.signum() < 0;
+        let (closed, opened) = if flipped {
+            (before, after)
+        } else {
+            ((before - after).max(0), (after - before).max(0))
+        };
+
+        let released = match closed {
+            0 => Balance::zero(),
+            _ => Balance::from_i64(
+                (position.isolated_margin.to_i64() as i128 * closed as i128 / before as i128) as i64
+            ),
+        };
+        let mut balance_mgr = self.balance_manager.write().await;
+        let available = balance_mgr.get_account(user_id)?.available_balance() + released;
+        let added = self.margin_calculator.calculate_initial_margin(Quantity::from_i64(opened), price, leverage)
+            .min(available.max(Balance::zero()));
+        position.isolated_margin = position.isolated_margin - released + added;
+        drop(position_mgr);
 
+        let delta = added - released;
+        if delta > Balance::zero() {
+            balance_mgr.reserve_margin(user_id, delta)?;
+        } else if delta < Balance::zero() {
+            balance_mgr.release_margin(user_id, -delta)?;
+        }
         Ok(())
     }
 
-    async fn process_price_update(&mut self, event: BaseEvent) -> Result<()> {
-        tracing::debug!("Processing price update event: {:?}", event.event_id);
-
-        // Extract PriceSnapshot from typed payload
-        let price_snapshot = match event.payload {
-            crate::events::base::EventPayload::PriceSnapshot(payload) => *payload,
+    /// Amend a resting order's price and/or quantity in place
+    /// Margin is re-reserved for the new unfilled size; amends that would
+    /// cross the book are refused (cancel and resubmit to take liquidity)
+    async fn process_order_amend(&mut self, event: BaseEvent) -> Result<()> {
+        let order_amend = match event.payload {
+            EventPayload::OrderAmend(payload) => *payload,
             _ => {
                 return Err(Error::InvalidEventPayload {
-                    expected: "PriceSnapshot".to_string(),
+                    expected: "OrderAmend".to_string(),
                     found: format!("{:?}", event.event_type),
                 });
             }
         };
 
-        // Update last mark price
-        self.last_mark_price = price_snapshot.mark_price;
+        // 1. Find the order; refusals are recorded, not failures of the log
+        let order_id = order_amend.order_id;
+        let (order, best_bid, best_ask) = self.matching.execute(move |matcher| {
+            let book = matcher.order_book();
+            (book.get_order(&order_id).cloned(), book.best_bid(), book.best_ask())
+        }).await?;
+
+        let (order, new_price, new_quantity, margin_delta) =
+            match self.admit_amend(&order_amend, order, best_bid, best_ask).await {
+                Ok(Some(admitted)) => admitted,
+                Ok(None) => return Ok(()),
+                Err(e) => {
+                    return self.reject_request(event.event_id, event.event_type, order_amend.user_id, &e).await;
+                }
+            };
 
-        tracing::debug!("Mark price updated: {}", price_snapshot.mark_price.to_f64());
+        // 4. Apply to the book per the priority policy
+        let priority_kept = new_price == order.price
+            && new_quantity < order.quantity
+            && self.market_config.amend_priority == AmendPriorityPolicy::KeepOnDecrease;
 
-        Ok(())
-    }
+        let amended_at = order_amend.base.timestamp;
+        self.matching.execute(move |matcher| {
+            matcher.order_book_mut().amend_order(&order_id, new_price, new_quantity, priority_kept, amended_at)
+        }).await??;
 
-    /// Halt event processing per docs/architecture/invariants.md Section 4.3
-    pub fn halt(&self) {
-        self.halted.store(true, Ordering::SeqCst);
-        tracing::warn!("EventProcessor HALTED");
-    }
+        let mut changed_levels = vec![(order.side, order.price)];
+        if new_price != order.price {
+            changed_levels.push((order.side, new_price));
+        }
+        self.publish_queue_positions(&changed_levels).await?;
+
+        // 5. Record the amendment
+        let amended = OrderAmended {
+            base: BaseEvent::new(EventType::OrderAmended, self.market_id),
+            order_id: order.order_id,
+            user_id: order.user_id,
+            old_price: order.price,
+            new_price,
+            old_quantity: order.quantity,
+            new_quantity,
+            filled: order.filled,
+            priority_kept,
+            margin_delta,
+        };
 
-    /// Resume event processing per docs/architecture/invariants.md Section 4.3
-    pub fn resume(&self) {
-        self.halted.store(false, Ordering::SeqCst);
-        tracing::info!("EventProcessor RESUMED");
-    }
+        let base = amended.base.clone();
+        self.event_producer.produce(BaseEvent {
+            payload: EventPayload::OrderAmended(Box::new(amended)),
+            ..base
+        }).await?;
 
-    /// Check if event processor is halted
-    pub fn is_halted(&self) -> bool {
-        self.halted.load(Ordering::SeqCst)
+        tracing::info!(
+            "Order amended: {:?} price {} -> {}, quantity {} -> {}, priority_kept={}",
+            order.order_id, order.price.to_i64(), new_price.to_i64(),
+            order.quantity.to_i64(), new_quantity.to_i64(), priority_kept
+        );
+
+        Ok(())
     }
-}
\ No newline at end of file
+
+    /// Verify ownership, re-validate and re-net reserved margin for an amend
+    /// None when the amend changes nothing; on error nothing has changed
+    async fn admit_amend(
+        &mut self,
+        order_amend: &OrderAmend,
+        order: Option<Order>,
+        best_bid: Option<Price>,
+        best_ask: Option<Price>,
+    ) -> Result<Option<(Order, Price, Quantity, Balance)>> {
+        let order = order.ok_or(Error::OrderNotFound(order_amend.order_id))?;
+
+        if order.user_id != order_amend.user_id {
+            return Err(Error::Unauthorized);
+        }
+
+        let new_price = order_amend.new_price.unwrap_or(order.price);
+        let new_quantity = order_amend.new_quantity.unwrap_or(order.quantity);
+        if new_price == order.price && new_quantity == order.quantity {
+            tracing::debug!("Amend of {:?} changes nothing", order.order_id);
+            return Ok(None);
+        }
+
+        // 2. Re-validate against market rules; amends never cross, so only
+        //    cancel-only refuses them
+        self.check_trading_phase(true)?;
+        self.check_expiry(order_amend.base.timestamp)?;
+        let validator = OrderValidator::new(self.market_config.clone())
+            .with_reference_price(self.last_mark_price);
+        validator.validate_amend(&order, new_price, new_quantity)?;
+
+        if new_price != order.price {
+            let crosses = match order.side {
+                Side::Buy => best_ask.is_some_and(|ask| new_price >= ask),
+                Side::Sell => best_bid.is_some_and(|bid| new_price <= bid),
+            };
+            if crosses {
+                return Err(Error::AmendWouldCross);
+            }
+        }
+
+        // 3. Re-net reserved margin with the new unfilled size
+        let old_remaining = self.order_margin.remaining(&order.order_id)
+            .unwrap_or(order.quantity - order.filled);
+        self.order_margin.track(order.order_id, order.user_id, order.side, new_quantity - order.filled);
+        match self.rebalance_order_margin(order.user_id).await {
+            Ok(margin_delta) => Ok(Some((order, new_price, new_quantity, margin_delta))),
+            Err(e) => {
+                self.order_margin.track(order.order_id, order.user_id, order.side, old_remaining);
+                Err(e)
+            }
+        }
+    }
+
+    /// Cancel a stop that has not triggered: release its margin and archive it
+    async fn cancel_pending_stop(&mut self, order_id: crate::types::ids::OrderId) -> Result<()> {
+        let pending = self.trigger_monitor.cancel(&order_id)
+            .map(|pending| pending.order)
+            .or_else(|| self.trigger_engine.cancel(&order_id).map(|stop| stop.order))
+            .ok_or(Error::OrderNotFound(order_id))?;
+
+        self.release_stop_margin(&pending).await?;
+
+        self.order_archive.write().await
+            .archive(Self::order_from_submit(&pending), TerminalStatus::Cancelled)?;
+
+        crate::observability::metrics::ORDERS_CANCELLED.inc();
+        tracing::info!("Stop order cancelled before trigger: {:?}", order_id);
+
+        Ok(())
+    }
+
+    /// Release the margin reserved when a stop order was accepted
+    async fn release_stop_margin(&mut self, order_submit: &OrderSubmit) -> Result<()> {
+        self.order_margin.untrack(&order_submit.order_id);
+        self.rebalance_order_margin(order_submit.user_id).await.map(|_| ())
+    }
+
+    async fn process_trade(&mut self, event: BaseEvent) -> Result<()> {
+        tracing::debug!("Processing trade event: {:?}", event.event_id);
+
+        // Deserialize TradeEvent from event.metadata
+        let trade_event = match event.payload {
+            crate::events::base::EventPayload::Trade(payload) => *payload,
+            _ => {
+                return Err(Error::InvalidEventPayload {
+                    expected: "Trade".to_string(),
+                    found: format!("{:?}", event.event_type),
+                });
+            }
+        };
+
+        // Settle each trade exactly once, even if the event is redelivered
+        self.in_flight_fills.settle(&trade_event.trade_id);
+        if !self.settled_trades.insert(trade_event.trade_id) {
+            tracing::warn!("Trade {} already settled, skipping", trade_event.trade_id);
+            TRADES_DUPLICATE_SKIPPED.inc();
+            return Ok(());
+        }
+
+        // 1. Update maker position (leg)
+        let mut position_mgr = self.position_manager.write().await;
+        let maker_size_before = position_mgr.leg_size(trade_event.maker_user_id, trade_event.maker_position_side);
+        let taker_size_before = position_mgr.leg_size(trade_event.taker_user_id, trade_event.taker_position_side);
+
+        // Lazily settled funding is collected before the size changes
+        let maker_funding = Self::collect_index_funding(
+            &mut *position_mgr, trade_event.maker_user_id, trade_event.maker_position_side,
+        )?;
+        let taker_funding = Self::collect_index_funding(
+            &mut *position_mgr, trade_event.taker_user_id, trade_event.taker_position_side,
+        )?;
+
+        let maker_realized = position_mgr.update_leg(
+            trade_event.maker_user_id,
+            trade_event.maker_position_side,
+            trade_event.maker_side,
+            trade_event.quantity,
+            trade_event.price,
+        )?;
+
+        // 2. Update taker position (opposite side of maker)
+        let taker_side = match trade_event.maker_side {
+            Side::Buy => Side::Sell,
+            Side::Sell => Side::Buy,
+        };
+
+        let taker_realized = position_mgr.update_leg(
+            trade_event.taker_user_id,
+            trade_event.taker_position_side,
+            taker_side,
+            trade_event.quantity,
+            trade_event.price,
+        )?;
+
+        // A leg opened from flat accrues funding from the fill, not from
+        // whenever it was last funded (or created)
+        for (user_id, leg, size_before) in [
+            (trade_event.maker_user_id, trade_event.maker_position_side, maker_size_before),
+            (trade_event.taker_user_id, trade_event.taker_position_side, taker_size_before),
+        ] {
+            if size_before == 0
+                && let Some(position) = position_mgr.get_leg_mut(&user_id, leg)
+                && !position.is_flat()
+            {
+                position.last_funding_timestamp = trade_event.base.timestamp;
+            }
+        }
+
+        drop(position_mgr);
+
+        // 3. Settle realized PnL and fees, each under its own ledger entry
+        let reference_id = trade_event.trade_id.to_string();
+        let mut balance_mgr = self.balance_manager.write().await;
+        if maker_realized != Balance::zero() {
+            balance_mgr.settle_realized_pnl(trade_event.maker_user_id, maker_realized, &reference_id)?;
+        }
+        if taker_realized != Balance::zero() {
+            balance_mgr.settle_realized_pnl(trade_event.taker_user_id, taker_realized, &reference_id)?;
+        }
+        if maker_funding != Balance::zero() {
+            balance_mgr.settle_funding(trade_event.maker_user_id, maker_funding, &reference_id)?;
+        }
+        if taker_funding != Balance::zero() {
+            balance_mgr.settle_funding(trade_event.taker_user_id, taker_funding, &reference_id)?;
+        }
+        balance_mgr.charge_fee(trade_event.maker_user_id, trade_event.maker_fee.amount, &reference_id)?;
+        balance_mgr.charge_fee(trade_event.taker_user_id, trade_event.taker_fee.amount, &reference_id)?;
+        drop(balance_mgr);
+
+        // Filled quantity no longer holds order margin, and the new position
+        // changes how much the remaining orders need
+        self.order_margin.fill(&trade_event.maker_order_id, trade_event.quantity);
+        self.order_margin.fill(&trade_event.taker_order_id, trade_event.quantity);
+        self.rebalance_order_margin(trade_event.maker_user_id).await?;
+        if trade_event.taker_user_id != trade_event.maker_user_id {
+            self.rebalance_order_margin(trade_event.taker_user_id).await?;
+        }
+
+        // Isolated legs carry their own collateral through the fill
+        self.rebalance_isolated_margin(
+            trade_event.maker_user_id, trade_event.maker_position_side, maker_size_before, trade_event.price,
+        ).await?;
+        self.rebalance_isolated_margin(
+            trade_event.taker_user_id, trade_event.taker_position_side, taker_size_before, trade_event.price,
+        ).await?;
+
+        // 4. Update margin requirements (recalculate after position change)
+        let position_mgr = self.position_manager.read().await;
+        let maker_position = position_mgr.get_leg(&trade_event.maker_user_id, trade_event.maker_position_side);
+        let taker_position = position_mgr.get_leg(&trade_event.taker_user_id, trade_event.taker_position_side);
+
+        if let Some(pos) = maker_position {
+            let required_margin = self.margin_calculator.calculate_maintenance_margin(
+                Quantity::from_i64(pos.size.abs()),
+                trade_event.price,
+            );
+            tracing::debug!("Maker margin requirement: {}", required_margin.to_i64());
+        }
+
+        if let Some(pos) = taker_position {
+            let required_margin = self.margin_calculator.calculate_maintenance_margin(
+                Quantity::from_i64(pos.size.abs()),
+                trade_event.price,
+            );
+            tracing::debug!("Taker margin requirement: {}", required_margin.to_i64());
+        }
+
+        // Filled orders already left the book on the matching core and were
+        // archived when the match returned
+
+        // Observability
+        use crate::observability::metrics::*;
+        TRADES_EXECUTED.inc();
+        VOLUME_TRADED.inc_by(trade_event.quantity.to_i64() as f64);
+
+        tracing::info!("Trade processed: {:?}, qty: {}, price: {}", 
+                      trade_event.trade_id,
+                      trade_event.quantity.to_i64(),
+                      trade_event.price.to_f64());
+
+        TRADES_PROCESSED.inc();
+    
+        Ok(())
+    }
+
+    async fn process_funding(&mut self, event: BaseEvent) -> Result<()> {
+        tracing::debug!("Processing funding event: {:?}", event.event_id);
+
+        // Deserialize FundingEvent from event.metadata
+        let mut funding_event = match &event.payload {
+            EventPayload::Funding(payload) => payload.as_ref().clone(),
+            _ => {
+                return Err(Error::InvalidEventPayload {
+                    expected: "Funding".to_string(),
+                    found: format!("{:?}", event.event_type),
+                });
+            }
+        };
+
+        // Dated futures converge through expiry settlement, never funding
+        if !self.market_config.has_funding() {
+            tracing::warn!("Ignoring funding event {:?} for a dated future", event.event_id);
+            return Ok(());
+        }
+
+        // An interval settles once, whether it arrives from the ticker, a
+        // catch-up or a redelivery
+        let funded_at = funding_event.interval_end();
+        let settled_until = self.position_manager.read().await.cumulative_funding().last_funding;
+        if funded_at <= settled_until {
+            tracing::warn!(
+                "Ignoring funding event {:?}: interval ending {} already settled",
+                event.event_id, funded_at.physical,
+            );
+            return Ok(());
+        }
+
+        // 1. Compute the payments from the positions as of this event (the
+        // published event carries only the rate and prices) and apply them
+        let positions: Vec<Position> = self.position_manager.read().await.get_all_positions()
+            .into_iter()
+            .cloned()
+            .collect();
+        let (payments, residual) = self.funding_applicator.settle_payments(&funding_event, &positions)?;
+        funding_event.payments = payments;
+        funding_event.residual = residual;
+
+        let mut balance_mgr = self.balance_manager.write().await;
+        let mut total_payments: i64 = 0;
+
+        let reference_id = event.event_id.to_string();
+        for payment in &funding_event.payments {
+            balance_mgr.settle_funding(payment.user_id, payment.payment, &reference_id)?;
+            total_payments += payment.payment.to_i64();
+            if let Some(tally) = &self.risk_tally && payment.payment < Balance::zero() {
+                tally.record_funding_paid(payment.payment);
+            }
+
+            tracing::debug!("Applied funding payment: user={:?}, amount={}", 
+                          payment.user_id, payment.payment.to_i64());
+        }
+
+        // Rounding residue goes to the funding residual account, under its own ledger entry
+        if funding_event.residual != Balance::zero() {
+            balance_mgr.settle_funding_residual(funding_event.residual, &reference_id)?;
+            total_payments += funding_event.residual.to_i64();
+            tracing::debug!("Funding rounding residual: {}", funding_event.residual.to_i64());
+        }
+
+        drop(balance_mgr);
+
+        // 2. Verify zero-sum property (critical invariant)
+        if total_payments != 0 {
+            return Err(Error::FundingNotZeroSum { sum: total_payments });
+        }
+
+        // 3. Advance the funding index; swept positions are paid up to it.
+        // Update position funding timestamps to the settled interval's end
+        let mut position_mgr = self.position_manager.write().await;
+        let mut cumulative = position_mgr.cumulative_funding();
+        let mut index_owed = Vec::new();
+        for payment in &funding_event.payments {
+            for position in position_mgr.positions_for_mut(&payment.user_id) {
+                // Anything left from lazy settlement is collected, not forgiven
+                let owed = FundingPaymentCalculator::index_payment(position, cumulative.index)?;
+                if owed != Balance::zero() {
+                    index_owed.push((payment.user_id, owed));
+                }
+                position.last_funding_timestamp = funded_at;
+                position.funding_index = cumulative.index + funding_event.index_delta;
+            }
+        }
+        cumulative.advance(funding_event.index_delta, funded_at);
+        position_mgr.set_cumulative_funding(cumulative);
+        drop(position_mgr);
+
+        if !index_owed.is_empty() {
+            let mut balance_mgr = self.balance_manager.write().await;
+            for (user_id, owed) in index_owed {
+                balance_mgr.settle_funding(user_id, owed, &reference_id)?;
+            }
+        }
+        if let Some(accrual) = &mut self.funding_accrual {
+            accrual.cumulative = cumulative;
+        }
+
+        // 4. Record the payments for history queries (they are applied
+        // either way, so a failed write doesn't fail the event)
+        if let Some(history) = &self.funding_history
+            && let Err(e) = history.write().await.record(&funding_event, funded_at)
+        {
+            tracing::warn!("Failed to record funding payments for {:?}: {}", event.event_id, e);
+        }
+
+        // Observability
+        use crate::observability::metrics::*;
+        FUNDING_EVENTS_PROCESSED.inc();
+        FUNDING_RATE.with_label_values(&["default"]).set(funding_event.funding_rate.to_f64());
+
+        tracing::info!("Funding applied: rate={:.6}, payments={}", 
+                      funding_event.funding_rate.to_f64(),
+                      funding_event.payments.len());
+        if let Some(components) = &funding_event.components {
+            tracing::info!(
+                "Funding rate components: premium_index={:.6}, interest_adjustment={:.6}, unclamped={:.6}",
+                components.premium_index.to_f64(),
+                components.interest_adjustment.to_f64(),
+                components.unclamped_rate.to_f64(),
+            );
+        }
+
+        self.report_funding_clamp(&funding_event).await
+    }
+
+    /// Logs a FundingRateClamped event when the interval settled at the
+    /// market's cap or floor, so risk can see how often the bound binds
+    async fn report_funding_clamp(&self, funding_event: &FundingEvent) -> Result<()> {
+        let components = match &funding_event.components {
+            Some(components) => components,
+            None => return Ok(()),
+        };
+        let bound = match components.clamped_by() {
+            Some(bound) => bound,
+            None => return Ok(()),
+        };
+
+        crate::observability::metrics::FUNDING_RATE_CLAMPED.with_label_values(&[bound.as_str()]).inc();
+        alert_operations_team_warning(format!(
+            "Funding rate clamped at {}: computed {:.6}, settled {:.6}",
+            bound.as_str(), components.unclamped_rate.to_f64(), components.funding_rate.to_f64()
+        ));
+
+        let clamped = FundingRateClamped {
+            base: BaseEvent::new(EventType::FundingRateClamped, self.market_id),
+            funding_event_id: funding_event.base.event_id,
+            unclamped_rate: components.unclamped_rate,
+            funding_rate: components.funding_rate,
+            bound,
+        };
+        let base = clamped.base.clone();
+        self.event_producer.produce(BaseEvent {
+            payload: EventPayload::FundingRateClamped(Box::new(clamped)),
+            ..base
+        }).await?;
+
+        Ok(())
+    }
+
+    /// Final settlement of a dated future
+    /// Resting orders and pending stops are cancelled, then every position is
+    /// closed at the settlement price and its PnL paid to the account
+    async fn process_expiry_settlement(&mut self, event: BaseEvent) -> Result<()> {
+        let settlement = match event.payload {
+            EventPayload::ExpirySettlement(payload) => *payload,
+            _ => {
+                return Err(Error::InvalidEventPayload {
+                    expected: "ExpirySettlement".to_string(),
+                    found: format!("{:?}", event.event_type),
+                });
+            }
+        };
+
+        if self.market_config.expiry().is_none() {
+            tracing::warn!("Ignoring expiry settlement {:?}: market does not expire", event.event_id);
+            return Ok(());
+        }
+        if let Some(price) = self.settlement_price {
+            tracing::warn!("Market already settled at {}, skipping", price.to_f64());
+            return Ok(());
+        }
+        self.settlement_price = Some(settlement.settlement_price);
+
+        // 1. Nothing may trade after settlement
+        self.cancel_resting_orders(None, "expiry settlement".to_string()).await?;
+
+        let mut stops = self.trigger_monitor.drain();
+        stops.extend(self.trigger_engine.drain());
+        for stop in &stops {
+            self.release_stop_margin(stop).await?;
+            self.order_archive.write().await
+                .archive(Self::order_from_submit(stop), TerminalStatus::Cancelled)?;
+        }
+
+        // 2. Close every position at the settlement price, in a stable order
+        let price = settlement.settlement_price;
+        let mut position_mgr = self.position_manager.write().await;
+        let mut positions: Vec<Position> = position_mgr.get_all_positions().into_iter()
+            .filter(|position| !position.is_flat())
+            .cloned()
+            .collect();
+        positions.sort_by_key(|position| (position.user_id.0, position.position_side as u8));
+
+        let mut balance_mgr = self.balance_manager.write().await;
+        let reference_id = format!("expiry-{}", self.market_id);
+        let mut net_pnl = Balance::zero();
+        for position in &positions {
+            let closing_side = if position.size > 0 { Side::Sell } else { Side::Buy };
+
+            let funding = Self::collect_index_funding(&mut *position_mgr, position.user_id, position.position_side)?;
+            if funding != Balance::zero() {
+                balance_mgr.settle_funding(position.user_id, funding, &reference_id)?;
+            }
+            let pnl = position_mgr.update_leg(
+                position.user_id,
+                position.position_side,
+                closing_side,
+                Quantity::from_i64(position.size.abs()),
+                price,
+            )?;
+            position_mgr.remove_leg(&position.user_id, position.position_side);
+            balance_mgr.settle_realized_pnl(position.user_id, pnl, &reference_id)?;
+            net_pnl = net_pnl + pnl;
+        }
+        drop(balance_mgr);
+        drop(position_mgr);
+
+        tracing::warn!(
+            "Market expired: settled {} positions at {} (TWAP of {} samples), cancelled {} stops, net PnL {}",
+            positions.len(), price.to_f64(), settlement.twap_samples, stops.len(), net_pnl.to_f64()
+        );
+
+        Ok(())
+    }
+
+    async fn process_liquidation(&mut self, event: BaseEvent) -> Result<()> {
+        tracing::debug!("Processing liquidation event: {:?}", event.event_id);
+
+        // Deserialize LiquidationTriggered from event.metadata
+        let liquidation_event = match event.payload {
+            crate::events::base::EventPayload::Liquidation(payload) => *payload,
+            _ => {
+                return Err(Error::InvalidEventPayload {
+                    expected: "Liquidation".to_string(),
+                    found: format!("{:?}", event.event_type),
+                });
+            }
+        };
+
+
+        // Get position to create proper liquidation candidate
+        let position_mgr = self.position_manager.read().await;
+        let position = position_mgr.get_leg(&liquidation_event.user_id, liquidation_event.position_side)
+            .ok_or(Error::ConfigError("Position not found for liquidation".to_string()))?;
+
+        // Create liquidation candidate from event
+        // LiquidationCandidate fields: user_id, position, margin_ratio, maintenance_margin, mark_price
+        let margin_ratio_value = if liquidation_event.account_value.to_i64() != 0 {
+            liquidation_event.maintenance_margin.to_f64() / liquidation_event.account_value.to_f64()
+        } else {
+            0.0
+        };
+
+        // Health and takeover price from current state, so a sliced liquidation
+        // stops once margin is restored, and replay does the same
+        let balance = self.balance_manager.read().await.get_account(liquidation_event.user_id)?.balance;
+        let account_legs = position_mgr.positions_for(&liquidation_event.user_id);
+        let open_interest = position_mgr.open_interest();
+        let bankruptcy_price = leg_liquidation_prices(
+            &self.margin_calculator,
+            position,
+            &account_legs,
+            balance,
+            liquidation_event.mark_price,
+            open_interest,
+            self.funding_accrual.as_ref(),
+        ).bankruptcy_price;
+        let maintenance_surplus = leg_maintenance_surplus(
+            &self.margin_calculator,
+            position,
+            &account_legs,
+            balance,
+            liquidation_event.mark_price,
+            open_interest,
+            self.funding_accrual.as_ref(),
+        )?;
+
+        let candidate = crate::liquidation::detector::LiquidationCandidate {
+            user_id: liquidation_event.user_id,
+            position: position.clone(),
+            margin_ratio: Ratio::from_f64(margin_ratio_value),
+            maintenance_margin: liquidation_event.maintenance_margin,
+            mark_price: liquidation_event.mark_price,
+            bankruptcy_price,
+            maintenance_surplus,
+        };
+        drop(position_mgr);
+
+        // Execute liquidation on the matching core
+        // (balances and positions are read-locked here, never on the core)
+        let executor = self.liquidation_executor.clone();
+        let balance_mgr = self.balance_manager.clone().read_owned().await;
+        let position_mgr = self.position_manager.clone().read_owned().await;
+        let now = liquidation_event.base.timestamp;
+        let result = self.matching.execute(move |matcher| {
+            executor.add_candidate(candidate);
+            executor.execute_next(matcher, &*balance_mgr, &*position_mgr, now)
+        }).await?;
+
+        // Log what the executor did (started, fills, completed or failed), whatever the outcome
+        for lifecycle_event in self.liquidation_executor.drain_lifecycle() {
+            self.event_producer.produce(lifecycle_event).await?;
+        }
+
+        match result {
+            Ok(Some(liq_event)) => {
+                // Update position
+                let mut position_mgr = self.position_manager.write().await;
+
+                let leg = liquidation_event.position_side;
+                let funding = Self::collect_index_funding(&mut *position_mgr, liquidation_event.user_id, leg)?;
+                let mut released_isolated = Balance::zero();
+                if let Some(position) = position_mgr.get_leg_mut(&liquidation_event.user_id, leg) {
+                    // Calculate new position size after liquidation
+                    let liquidated_qty = liq_event.liquidated_size.to_i64();
+
+                    // Isolated bucket shrinks with the position it backs
+                    if position.is_isolated() && position.size != 0 {
+                        let before = position.size.unsigned_abs() as i128;
+                        released_isolated = Balance::from_i64(
+                            (position.isolated_margin.to_i64() as i128 * (liquidated_qty as i128).min(before) / before) as i64
+                        );
+                        position.isolated_margin = position.isolated_margin - released_isolated;
+                    }
+
+                    if liq_event.execution == LiquidationExecution::Book {
+                        if position.size > 0 {
+                            // Long position
+                            position.size = position.size.saturating_sub(liquidated_qty);
+                        } else {
+                            // Short position
+                            position.size = position.size.saturating_add(liquidated_qty);
+                        }
+                    }
+
+                    // Record insurance fund charge if any
+                    if liq_event.insurance_fund_loss > Balance::zero() {
+                        tracing::warn!("Insurance fund charged: {}", 
+                                      liq_event.insurance_fund_loss.to_i64());
+                    }
+                }
+
+                let (user_pnl, engine_pnl) = match liq_event.execution {
+                    LiquidationExecution::Book => (Balance::zero(), Balance::zero()),
+                    LiquidationExecution::InsuranceTakeover => Self::take_over_leg(
+                        &mut *position_mgr,
+                        liquidation_event.user_id,
+                        leg,
+                        liq_event.liquidated_size,
+                        liq_event.liquidation_price,
+                    )?,
+                };
+
+                // Remove position if fully liquidated
+                if position_mgr.get_leg(&liquidation_event.user_id, leg).is_some_and(|p| p.size == 0) {
+                    position_mgr.remove_leg(&liquidation_event.user_id, leg);
+                    tracing::info!("Position fully liquidated: {:?}", liquidation_event.user_id);
+                }
+                drop(position_mgr);
+
+                let reference_id = event.event_id.to_string();
+                if funding != Balance::zero() {
+                    self.balance_manager.write().await
+                        .settle_funding(liquidation_event.user_id, funding, &reference_id)?;
+                }
+                if user_pnl != Balance::zero() || engine_pnl != Balance::zero() {
+                    let mut balance_mgr = self.balance_manager.write().await;
+                    balance_mgr.settle_realized_pnl(liquidation_event.user_id, user_pnl, &reference_id)?;
+                    if balance_mgr.get_account(*LIQUIDATION_ENGINE_USER_ID).is_err() {
+                        balance_mgr.create_account(*LIQUIDATION_ENGINE_USER_ID)?;
+                    }
+                    balance_mgr.settle_realized_pnl(*LIQUIDATION_ENGINE_USER_ID, engine_pnl, &reference_id)?;
+                }
+                if liq_event.penalty > Balance::zero() {
+                    self.balance_manager.write().await
+                        .charge_liquidation_penalty(liquidation_event.user_id, liq_event.penalty, &reference_id)?;
+                }
+                if liq_event.insurance_fund_loss > Balance::zero() {
+                    self.balance_manager.write().await
+                        .cover_from_insurance_fund(liquidation_event.user_id, liq_event.insurance_fund_loss, &reference_id)?;
+                }
+                if liq_event.unbacked_loss > Balance::zero() {
+                    self.socialize_loss(&liq_event, event.timestamp, &reference_id).await?;
+                }
+                if released_isolated > Balance::zero() {
+                    self.balance_manager.write().await.release_margin(liquidation_event.user_id, released_isolated)?;
+                }
+                self.rebalance_order_margin(liquidation_event.user_id).await?;
+                self.sync_insurance_fund().await;
+
+                // Observability
+                let liq_type = match liq_event.liquidation_type {
+                    LiquidationType::Full => "full",
+                    LiquidationType::Partial => "partial",
+                };
+                LIQUIDATIONS_EXECUTED.with_label_values(&[liq_type]).inc();
+                LIQUIDATION_VOLUME.inc_by(liq_event.liquidated_size.to_i64() as f64);
+                if let Some(tally) = &self.risk_tally {
+                    tally.record_liquidation(liq_event.liquidated_size);
+                }
+                if let Some(notifier) = &self.notifier {
+                    notifier.notify(Notification::liquidation(&liq_event));
+                }
+
+                tracing::info!("Liquidation executed: user={:?}, size={}, price={}, slice={}, remaining={}", 
+                              liquidation_event.user_id,
+                              liq_event.liquidated_size.to_i64(),
+                              liq_event.liquidation_price.to_f64(),
+                              liq_event.slice,
+                              liq_event.remaining_size.to_i64());
+            }
+            Ok(None) => {
+                tracing::debug!("Liquidation execution returned no result (halted, throttled, deferred or healthy)");
+            }
+            Err(e) => {
+                tracing::error!("Liquidation execution failed: {:?}", e);
+                return Err(e);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Mirror the insurance fund account's ledger balance on the executor's fund
+    async fn sync_insurance_fund(&self) {
+        let fund_balance = self.balance_manager.read().await
+            .get_account(UserId::insurance_fund())
+            .map(|fund| fund.balance)
+            .unwrap_or(Balance::zero());
+        self.liquidation_executor.sync_insurance_fund(fund_balance);
+    }
+
+    /// Claw back a deficit the insurance fund could not cover from the
+    /// settlement period's winners, pro rata to their realized PnL in it
+    async fn socialize_loss(&mut self, liq_event: &LiquidationEvent, at: Timestamp, reference_id: &str) -> Result<()> {
+        let (haircuts, unbacked) = {
+            let mut balance_mgr = self.balance_manager.write().await;
+            if self.settlement_period.is_due(at) {
+                self.settlement_period.start(balance_mgr.accounts(), at);
+            }
+
+            let winners = self.settlement_period.winners(
+                balance_mgr.accounts().into_iter().filter(|account| account.user_id != liq_event.user_id)
+            );
+            let (haircuts, unbacked) = socialized_loss::allocate(liq_event.unbacked_loss, &winners);
+            if !haircuts.is_empty() {
+                let amounts: Vec<_> = haircuts.iter().map(|haircut| (haircut.user_id, haircut.amount)).collect();
+                balance_mgr.socialize_loss(liq_event.user_id, &amounts, reference_id)?;
+            }
+            (haircuts, unbacked)
+        };
+
+        let socialized = liq_event.unbacked_loss - unbacked;
+        crate::observability::metrics::SOCIALIZED_LOSSES.inc_by(socialized.to_f64());
+        tracing::warn!(
+            "Socialized loss for {:?}: deficit={}, {} winners haircut {}, unbacked={}",
+            liq_event.liquidation_id, liq_event.unbacked_loss.to_i64(), haircuts.len(), socialized.to_i64(), unbacked.to_i64()
+        );
+        if unbacked > Balance::zero() {
+            alert_operations_team_critical(format!(
+                "Bankruptcy deficit of {} for user {} left unbacked after insurance fund and socialized loss",
+                unbacked.to_i64(), liq_event.user_id
+            ));
+        }
+
+        let event = SocializedLoss {
+            base: BaseEvent::new(EventType::SocializedLoss, self.market_id),
+            liquidation_id: liq_event.liquidation_id,
+            user_id: liq_event.user_id,
+            deficit: liq_event.unbacked_loss,
+            period_start: self.settlement_period.started_at().unwrap_or(at),
+            haircuts: haircuts.into_iter()
+                .map(|haircut| SocializedLossHaircut {
+                    user_id: haircut.user_id,
+                    period_pnl: haircut.period_pnl,
+                    amount: haircut.amount,
+                })
+                .collect(),
+            unbacked,
+        };
+        let base = event.base.clone();
+        self.event_producer.produce(BaseEvent {
+            payload: EventPayload::SocializedLoss(Box::new(event)),
+            ..base
+        }).await?;
+        Ok(())
+    }
+
+    /// Insurance fund takeover: move `size` of the leg to the liquidation
+    /// engine's net position at `price`; returns the PnL realized by the
+    /// account and by the engine
+    fn take_over_leg(
+        position_mgr: &mut S,
+        user_id: UserId,
+        leg: PositionSide,
+        size: Quantity,
+        price: Price,
+    ) -> Result<(Balance, Balance)> {
+        let close_side = match position_mgr.get_leg(&user_id, leg) {
+            Some(position) if position.is_long() => Side::Sell,
+            Some(position) if position.is_short() => Side::Buy,
+            _ => return Ok((Balance::zero(), Balance::zero())),
+        };
+
+        let user_pnl = position_mgr.update_leg(user_id, leg, close_side, size, price)?;
+        let engine_pnl = position_mgr.update_leg(
+            *LIQUIDATION_ENGINE_USER_ID,
+            PositionSide::Both,
+            close_side.opposite(),
+            size,
+            price,
+        )?;
+        Ok((user_pnl, engine_pnl))
+    }
+
+    async fn process_balance_update(&mut self, event: BaseEvent) -> Result<()> {
+        tracing::debug!("Processing balance update event: {:?}", event.event_id);
+
+        let balance_update = match event.payload {
+            crate::events::base::EventPayload::BalanceUpdate(payload) => *payload,
+            _ => {
+                return Err(Error::InvalidEventPayload {
+                    expected: "BalanceUpdate".to_string(),
+                    found: format!("{:?}", event.event_type),
+                });
+            }
+        };
+
+        let mut balance_mgr = self.balance_manager.write().await;
+
+        // 1. Apply balance change (deposit or withdrawal)
+        match balance_update.update_type {
+            BalanceUpdateType::Deposit => {
+                // Create account if it doesn't exist
+                if balance_mgr.get_account(balance_update.user_id).is_err() {
+                    balance_mgr.create_account(balance_update.user_id)?;
+                }
+
+                balance_mgr.adjust_balance(balance_update.user_id, balance_update.amount)?;
+
+                tracing::info!("Deposit processed: user={:?}, amount={}", 
+                              balance_update.user_id, balance_update.amount.to_i64());
+            }
+            BalanceUpdateType::Withdrawal => {
+                // Verify sufficient available balance and post-withdrawal margin buffer
+                let position_mgr = self.position_manager.read().await;
+                let checked = balance_mgr.get_account(balance_update.user_id).and_then(|account| {
+                    self.withdrawal_check.check(
+                        account,
+                        &position_mgr.positions_for(&balance_update.user_id),
+                        balance_update.amount,
+                        self.last_mark_price,
+                        self.funding_accrual.as_ref(),
+                    )
+                });
+                drop(position_mgr);
+
+                // A refused withdrawal is recorded, not a failure of the log
+                if let Err(e) = checked {
+                    drop(balance_mgr);
+                    if let Some(notifier) = &self.notifier {
+                        notifier.notify(Notification::withdrawal(
+                            balance_update.user_id,
+                            balance_update.amount,
+                            balance_update.reference_id.as_deref(),
+                            Some(e.to_string()),
+                        ));
+                    }
+                    return self.reject_request(event.event_id, event.event_type, balance_update.user_id, &e).await;
+                }
+
+                balance_mgr.adjust_balance(
+                    balance_update.user_id,
+                    Balance::from_i64(-balance_update.amount.to_i64())
+                )?;
+
+                tracing::info!("Withdrawal processed: user={:?}, amount={}", 
+                              balance_update.user_id, balance_update.amount.to_i64());
+                if let Some(notifier) = &self.notifier {
+                    notifier.notify(Notification::withdrawal(
+                        balance_update.user_id,
+                        balance_update.amount,
+                        balance_update.reference_id.as_deref(),
+                        None,
+                    ));
+                }
+            }
+        }
+
+        // 2. Record ledger entry
+        let account = balance_mgr.get_account(balance_update.user_id)?;
+
+        // 3. Verify balance remains non-negative
+        if account.balance < Balance::zero() {
+            tracing::error!("Negative balance detected: user={:?}, balance={}", 
+                          balance_update.user_id, account.balance.to_i64());
+            return Err(Error::InsufficientBalance);
+        }
+
+        // Observability
+        use crate::observability::metrics::*;
+        match balance_update.update_type {
+            BalanceUpdateType::Deposit => {
+                DEPOSITS_PROCESSED.inc();
+                DEPOSIT_VOLUME.inc_by(balance_update.amount.to_i64() as f64);
+            }
+            BalanceUpdateType::Withdrawal => {
+                WITHDRAWALS_PROCESSED.inc();
+                WITHDRAWAL_VOLUME.inc_by(balance_update.amount.to_i64() as f64);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Open a sub-account under its master (see `settlement::sub_accounts`)
+    async fn process_sub_account_created(&mut self, event: BaseEvent) -> Result<()> {
+        let created = match event.payload {
+            EventPayload::SubAccountCreated(payload) => *payload,
+            _ => {
+                return Err(Error::InvalidEventPayload {
+                    expected: "SubAccountCreated".to_string(),
+                    found: format!("{:?}", event.event_type),
+                });
+            }
+        };
+
+        let result = self.balance_manager.write().await
+            .create_sub_account(created.master_id, created.sub_account_id, &created.label);
+
+        match result {
+            Ok(_) => {
+                tracing::info!(
+                    "Sub-account {:?} ({}) opened under {:?}",
+                    created.sub_account_id, created.label, created.master_id
+                );
+                Ok(())
+            }
+            Err(e) => self.reject_request(event.event_id, event.event_type, created.master_id, &e).await,
+        }
+    }
+
+    /// Move funds within a master's family; the source must pass the same
+    /// checks as a withdrawal
+    async fn process_sub_account_transfer(&mut self, event: BaseEvent) -> Result<()> {
+        let transfer = match event.payload {
+            EventPayload::SubAccountTransfer(payload) => *payload,
+            _ => {
+                return Err(Error::InvalidEventPayload {
+                    expected: "SubAccountTransfer".to_string(),
+                    found: format!("{:?}", event.event_type),
+                });
+            }
+        };
+
+        let result = self.transfer_within_family(&transfer, &event.event_id.to_string()).await;
+
+        // An unbalanced ledger pair is a processing failure, not a refusal
+        match result {
+            Ok(()) => {
+                tracing::info!(
+                    "Sub-account transfer of {} from {:?} to {:?}",
+                    transfer.amount.to_i64(), transfer.from_user, transfer.to_user
+                );
+                Ok(())
+            }
+            Err(e @ Error::DoubleEntryImbalance { .. }) => Err(e),
+            Err(e) => self.reject_request(event.event_id, event.event_type, transfer.master_id, &e).await,
+        }
+    }
+
+    async fn transfer_within_family(&self, transfer: &SubAccountTransfer, reference_id: &str) -> Result<()> {
+        let mut balance_mgr = self.balance_manager.write().await;
+        let master = balance_mgr.get_account(transfer.master_id)?;
+        if master.is_sub_account() {
+            return Err(Error::InvalidSubAccount("only the master account moves funds".to_string()));
+        }
+        sub_accounts::check_family(transfer.master_id, balance_mgr.get_account(transfer.to_user)?)?;
+        sub_accounts::check_family(transfer.master_id, balance_mgr.get_account(transfer.from_user)?)?;
+        if transfer.from_user == transfer.to_user {
+            return Err(Error::InvalidSubAccount("transfer to the same account".to_string()));
+        }
+
+        self.checked_transfer(&mut balance_mgr, transfer.from_user, transfer.to_user, transfer.amount, reference_id).await
+    }
+
+    /// Move funds between two accounts as one debit/credit pair; the sender
+    /// must pass the same checks as a withdrawal of `amount`
+    async fn checked_transfer(
+        &self,
+        balance_mgr: &mut B,
+        from_user: UserId,
+        to_user: UserId,
+        amount: Balance,
+        reference_id: &str,
+    ) -> Result<()> {
+        let position_mgr = self.position_manager.read().await;
+        self.withdrawal_check.check(
+            balance_mgr.get_account(from_user)?,
+            &position_mgr.positions_for(&from_user),
+            amount,
+            self.last_mark_price,
+            self.funding_accrual.as_ref(),
+        )?;
+        drop(position_mgr);
+
+        balance_mgr.transfer(from_user, to_user, amount, reference_id)
+    }
+
+    /// Move funds from one account to another; the ledger pair is checked to
+    /// balance before the transfer counts as done
+    async fn process_transfer(&mut self, event: BaseEvent) -> Result<()> {
+        let transfer = match event.payload {
+            EventPayload::Transfer(payload) => *payload,
+            _ => {
+                return Err(Error::InvalidEventPayload {
+                    expected: "Transfer".to_string(),
+                    found: format!("{:?}", event.event_type),
+                });
+            }
+        };
+
+        let reference_id = transfer.reference_id.clone().unwrap_or_else(|| event.event_id.to_string());
+        let result = self.transfer_between_accounts(&transfer, &reference_id).await;
+
+        // An unbalanced ledger pair is a processing failure, not a refusal
+        match result {
+            Ok(()) => {
+                tracing::info!(
+                    "Transfer processed: {:?} -> {:?}, amount={}",
+                    transfer.from_user, transfer.to_user, transfer.amount.to_i64()
+                );
+                TRANSFERS_PROCESSED.inc();
+                TRANSFER_VOLUME.inc_by(transfer.amount.to_i64() as f64);
+                Ok(())
+            }
+            Err(e @ Error::DoubleEntryImbalance { .. }) => Err(e),
+            Err(e) => self.reject_request(event.event_id, event.event_type, transfer.from_user, &e).await,
+        }
+    }
+
+    async fn transfer_between_accounts(&self, transfer: &Transfer, reference_id: &str) -> Result<()> {
+        if transfer.from_user == transfer.to_user {
+            return Err(Error::SelfTransfer);
+        }
+        if [transfer.from_user, transfer.to_user].iter().any(|user_id| user_id.is_system() || *user_id == *crate::LIQUIDATION_ENGINE_USER_ID) {
+            return Err(Error::Unauthorized);
+        }
+
+        let mut balance_mgr = self.balance_manager.write().await;
+        balance_mgr.get_account(transfer.to_user)?;
+        self.checked_transfer(&mut balance_mgr, transfer.from_user, transfer.to_user, transfer.amount, reference_id).await
+    }
+
+    /// Seed state from an account export (venue migration / DR drill)
+    /// Expected only at the head of a fresh event log
+    async fn process_genesis(&mut self, event: BaseEvent) -> Result<()> {
+        let genesis = match event.payload {
+            EventPayload::Genesis(payload) => *payload,
+            _ => {
+                return Err(Error::InvalidEventPayload {
+                    expected: "Genesis".to_string(),
+                    found: format!("{:?}", event.event_type),
+                });
+            }
+        };
+
+        match genesis.record {
+            GenesisRecord::Account(account) => {
+                let mut balance_mgr = self.balance_manager.write().await;
+                balance_mgr.create_account(account.user_id)?;
+                balance_mgr.adjust_balance(account.user_id, account.balance)?;
+
+                if let Ok(imported) = balance_mgr.get_account_mut(account.user_id) {
+                    imported.reserved_margin = account.reserved_margin;
+                    imported.realized_pnl = account.realized_pnl;
+                    imported.created_at = account.created_at;
+                }
+            }
+            GenesisRecord::Position(position) => {
+                let mut position_mgr = self.position_manager.write().await;
+                position_mgr.set_position(position.user_id, position);
+            }
+            GenesisRecord::Order(order) => {
+                // Orders arrive in original queue order, so FIFO priority is preserved
+                self.matching.execute(move |matcher| matcher.order_book_mut().add_order(order)).await??;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Apply an operator-approved single-account repair
+    /// Deltas are computed against state at apply time, so replaying the log
+    /// reproduces the same result
+    async fn process_state_repair(&mut self, event: BaseEvent) -> Result<()> {
+        let repair = match event.payload {
+            EventPayload::StateRepair(payload) => *payload,
+            _ => {
+                return Err(Error::InvalidEventPayload {
+                    expected: "StateRepair".to_string(),
+                    found: format!("{:?}", event.event_type),
+                });
+            }
+        };
+
+        if !crate::utils::helper::is_authorized_operator(repair.operator_id) {
+            tracing::error!("Rejected state repair from unauthorized operator {:?}", repair.operator_id);
+            return Err(Error::Unauthorized);
+        }
+
+        let reference_id = event.event_id.to_string();
+        if let Some(target_balance) = repair.target_balance {
+            let mut balance_mgr = self.balance_manager.write().await;
+            if balance_mgr.get_account(repair.user_id).is_err() {
+                balance_mgr.create_account(repair.user_id)?;
+            }
+
+            let current = balance_mgr.get_account(repair.user_id)?.balance;
+            let delta = target_balance - current;
+            balance_mgr.repair_balance(repair.user_id, delta, &reference_id)?;
+
+            tracing::warn!(
+                "State repair: user={:?} balance {} -> {} (delta {}, countered on the repair suspense account) from snapshot seq={}",
+                repair.user_id, current.to_i64(), target_balance.to_i64(), delta.to_i64(),
+                repair.snapshot_sequence
+            );
+        }
+
+        if let Some(target_position) = repair.target_position {
+            let mut position_mgr = self.position_manager.write().await;
+            let current = position_mgr.get_position(&repair.user_id).cloned();
+            let current_size = current.as_ref().map_or(0, |p| p.size);
+            let current_isolated = current.as_ref().map_or(Balance::zero(), |p| p.isolated_margin);
+            let target_size = target_position.size;
+            let target_isolated = target_position.isolated_margin;
+
+            // The insurance fund's inventory takes the other side, so open
+            // interest stays matched: at the repaired entry, or the closed one
+            let delta = target_size - current_size;
+            let price = if target_size != 0 {
+                target_position.entry_price
+            } else {
+                current.as_ref().map_or(self.last_mark_price, |p| p.entry_price)
+            };
+            let engine_pnl = if delta != 0 {
+                let side = if delta > 0 { Side::Sell } else { Side::Buy };
+                position_mgr.update_leg(
+                    *LIQUIDATION_ENGINE_USER_ID,
+                    PositionSide::Both,
+                    side,
+                    Quantity::from_i64(delta.abs()),
+                    price,
+                )?
+            } else {
+                Balance::zero()
+            };
+
+            if target_size == 0 {
+                position_mgr.remove_position(&repair.user_id);
+            } else {
+                position_mgr.set_position(repair.user_id, target_position);
+            }
+            drop(position_mgr);
+
+            let mut balance_mgr = self.balance_manager.write().await;
+            if engine_pnl != Balance::zero() {
+                if balance_mgr.get_account(*LIQUIDATION_ENGINE_USER_ID).is_err() {
+                    balance_mgr.create_account(*LIQUIDATION_ENGINE_USER_ID)?;
+                }
+                balance_mgr.settle_realized_pnl(*LIQUIDATION_ENGINE_USER_ID, engine_pnl, &reference_id)?;
+            }
+
+            // The isolated bucket is held as reserved margin
+            if balance_mgr.get_account(repair.user_id).is_ok() {
+                if target_isolated > current_isolated {
+                    balance_mgr.reserve_margin(repair.user_id, target_isolated - current_isolated)?;
+                } else if target_isolated < current_isolated {
+                    balance_mgr.release_margin(repair.user_id, current_isolated - target_isolated)?;
+                }
+            }
+            drop(balance_mgr);
+
+            tracing::warn!(
+                "State repair: user={:?} position {} -> {} ({} taken by the insurance fund) from snapshot seq={}",
+                repair.user_id, current_size, target_size, -delta, repair.snapshot_sequence
+            );
+        }
+
+        // Open orders are netted against the repaired position and balance
+        if self.balance_manager.read().await.get_account(repair.user_id).is_ok() {
+            self.rebalance_order_margin(repair.user_id).await?;
+        }
+
+        tracing::warn!(
+            "State repair applied by operator {:?}: {}",
+            repair.operator_id, repair.reason
+        );
+
+        Ok(())
+    }
+
+    async fn process_price_update(&mut self, event: BaseEvent) -> Result<()> {
+        tracing::debug!("Processing price update event: {:?}", event.event_id);
+
+        // Extract PriceSnapshot from typed payload
+        let price_snapshot = match event.payload {
+            crate::events::base::EventPayload::PriceSnapshot(payload) => *payload,
+            _ => {
+                return Err(Error::InvalidEventPayload {
+                    expected: "PriceSnapshot".to_string(),
+                    found: format!("{:?}", event.event_type),
+                });
+            }
+        };
+
+        // Update last mark price
+        self.last_mark_price = price_snapshot.mark_price;
+        self.last_mark_price_at = Some(price_snapshot.base.timestamp);
+        self.last_index_price = price_snapshot.index_price;
+        self.liquidation_executor.reprice(price_snapshot.mark_price);
+        if self.settlement_period.is_due(price_snapshot.base.timestamp) {
+            let balance_mgr = self.balance_manager.read().await;
+            self.settlement_period.start(balance_mgr.accounts(), price_snapshot.base.timestamp);
+        }
+        if self.market_config.has_funding() {
+            self.funding_accrual = Some(self.funding_applicator.accrual(
+                price_snapshot.mark_price,
+                price_snapshot.index_price,
+                price_snapshot.base.timestamp,
+                self.position_manager.read().await.cumulative_funding(),
+            ));
+        }
+
+        if let Some(index_history) = &mut self.index_history {
+            index_history.record(price_snapshot.index_price, price_snapshot.base.timestamp);
+            self.circuit_breaker.set_threshold_scale(index_history.threshold_scale());
+        }
+
+        // Breaker trips hold order intake for the cooldown (see check_price_gate)
+        if let Err(e) = self.circuit_breaker.check(&price_snapshot) {
+            tracing::warn!("Order intake gated: {:?}", e);
+            self.circuit_breaker_tripped_at = Some(price_snapshot.base.timestamp);
+        }
+
+        tracing::debug!("Mark price updated: {}", price_snapshot.mark_price.to_f64());
+
+        // Reservations made at an older mark are topped up or released
+        self.remargin_open_orders().await;
+
+        // Stops are held while prices settle after a restart; they fire on the
+        // first price after the market opens
+        if self.trading_phase != TradingPhase::Open {
+            return Ok(());
+        }
+
+        // Release stops whose trigger this mark price reached
+        let triggered = self.trigger_monitor.on_mark_price(
+            price_snapshot.mark_price,
+            price_snapshot.base.timestamp,
+        );
+        for order in triggered {
+            self.execute_order(order).await?;
+        }
+
+        // Fired trailing stops go back through the log as Market orders and
+        // reserve margin again when admitted
+        let submissions = self.trigger_engine.on_price_snapshot(&price_snapshot);
+        for order_submit in submissions {
+            self.release_stop_margin(&order_submit).await?;
+
+            let base = order_submit.base.clone();
+            self.event_producer.produce(BaseEvent {
+                payload: EventPayload::OrderSubmit(Box::new(order_submit)),
+                ..base
+            }).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Halt event processing per docs/architecture/invariants.md Section 4.3
+    /// Takes effect at once (the event loop stops feeding events) and is
+    /// recorded as a ProcessorHalted event, which replays to the same state.
+    /// Automatic halts are dated by the triggering event, so the trigger seen
+    /// again on replay is recognised as the halt already resumed.
+    pub async fn halt(&mut self, reason: HaltReason, halted_at: Timestamp, halted_by: Option<OperatorId>) -> Result<()> {
+        let halted = match self.halt_control.halt(self.market_id, reason, halted_at, halted_by) {
+            Some(halted) => halted,
+            None => return Ok(()),  // Already halted, or a halt already resumed
+        };
+        self.halt_control.apply_halted(&halted);
+
+        let base = halted.base.clone();
+        self.event_producer.produce(BaseEvent {
+            payload: EventPayload::ProcessorHalted(Box::new(halted)),
+            ..base
+        }).await?;
+
+        Ok(())
+    }
+
+    /// Operator confirms the triggering condition has been investigated
+    pub async fn acknowledge_halt(&mut self, operator_id: OperatorId) -> Result<()> {
+        let acknowledged = self.halt_control.acknowledge(self.market_id, operator_id)?;
+
+        let base = acknowledged.base.clone();
+        self.event_producer.produce(BaseEvent {
+            payload: EventPayload::ProcessorHaltAcknowledged(Box::new(acknowledged.clone())),
+            ..base
+        }).await?;
+
+        self.halt_control.apply_acknowledged(&acknowledged);
+        Ok(())
+    }
+
+    /// Resume event processing per docs/architecture/invariants.md Section 4.3
+    /// Fails until an operator has acknowledged an automatic halt
+    pub async fn resume(&mut self, operator_id: OperatorId) -> Result<()> {
+        let resumed = self.halt_control.resume(self.market_id, operator_id)?;
+
+        let base = resumed.base.clone();
+        self.event_producer.produce(BaseEvent {
+            payload: EventPayload::ProcessorResumed(Box::new(resumed.clone())),
+            ..base
+        }).await?;
+
+        self.halt_control.apply_resumed(&resumed);
+        Ok(())
+    }
+
+    /// Run a halt-control request from the admin API; returns the halt state after it
+    pub async fn control(&mut self, action: ProcessorAction) -> Result<Option<ProcessorHaltState>> {
+        match action {
+            ProcessorAction::Status => {}
+            ProcessorAction::Halt { operator_id, note } => {
+                self.halt(HaltReason::Operator { note }, Timestamp::now(), Some(operator_id)).await?;
+            }
+            ProcessorAction::Acknowledge { operator_id } => self.acknowledge_halt(operator_id).await?,
+            ProcessorAction::Resume { operator_id } => self.resume(operator_id).await?,
+        }
+        Ok(self.halt_state().cloned())
+    }
+
+    /// Apply a logged halt, acknowledgement or resume
+    /// Ones this processor made live were applied already and change nothing
+    fn process_halt_control(&mut self, event: BaseEvent) -> Result<()> {
+        match &event.payload {
+            EventPayload::ProcessorHalted(halted) => self.halt_control.apply_halted(halted),
+            EventPayload::ProcessorHaltAcknowledged(acknowledged) => self.halt_control.apply_acknowledged(acknowledged),
+            EventPayload::ProcessorResumed(resumed) => self.halt_control.apply_resumed(resumed),
+            _ => {
+                return Err(Error::InvalidEventPayload {
+                    expected: "ProcessorHalted, ProcessorHaltAcknowledged or ProcessorResumed".to_string(),
+                    found: format!("{:?}", event.event_type),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Check if event processor is halted
+    /// The event loop stops feeding events while it is; logged events are
+    /// applied either way, so a replay through a halt and its resume matches
+    pub fn is_halted(&self) -> bool {
+        self.halt_control.is_halted()
+    }
+
+    pub fn halt_state(&self) -> Option<&ProcessorHaltState> {
+        self.halt_control.state()
+    }
+}
+
+impl<P: EventProducer> EventProcessor<BalanceManager, PositionManager, Matcher, P> {
+    /// Snapshot of the state after `last_sequence`
+    /// Taken between events (by the loop that feeds the processor), so the
+    /// state is exactly what the events up to that sequence produced
+    /// Resting orders are captured in queue order so a restore keeps maker priority
+    /// Statements of record at `cutoff`, read at the last processed event
+    /// (the records' as_of_sequence) and its mark price
+    /// Refused like `snapshot` until the first price has been processed.
+    pub async fn daily_statement(&self, generator: &mut DailyStatementGenerator, cutoff: Timestamp) -> Result<Vec<BaseEvent>> {
+        if self.last_mark_price_at.is_none() {
+            return Err(Error::StaleMarkPrice { age_ms: u64::MAX });  // No price since start
+        }
+
+        let balance_mgr = self.balance_manager.read().await;
+        let position_mgr = self.position_manager.read().await;
+        generator.generate(cutoff, self.last_sequence, &balance_mgr, &position_mgr, self.last_mark_price)
+    }
+
+    /// Accounts, positions and resting orders for a venue migration, as of
+    /// the last processed event
+    pub async fn export_accounts(&self) -> Result<AccountExport> {
+        let open_orders = self.matching.orders_in_priority().await?;
+        let balance_mgr = self.balance_manager.read().await;
+        let position_mgr = self.position_manager.read().await;
+        Ok(AccountExport::capture(self.market_id, self.last_sequence, &balance_mgr, &position_mgr, open_orders))
+    }
+
+    pub async fn snapshot(&self) -> Result<Snapshot> {
+        if self.last_mark_price_at.is_none() {
+            return Err(Error::StaleMarkPrice { age_ms: u64::MAX });  // No price since start
+        }
+
+        let open_orders = self.matching.orders_in_priority().await?;
+        let accounts = self.balance_manager.read().await.accounts.values().cloned().collect();
+        let position_mgr = self.position_manager.read().await;
+
+        let snapshot = Snapshot::new(
+            self.last_sequence,
+            self.market_id,
+            accounts,
+            position_mgr.get_all_positions().into_iter().cloned().collect(),
+            open_orders,
+            self.trigger_monitor.pending(),
+            self.trigger_engine.state(),
+            self.lp_makers.clone(),
+            position_mgr.leverage_settings(),
+            position_mgr.limit_overrides(),
+            position_mgr.cumulative_funding(),
+            self.last_mark_price,
+            self.last_index_price,
+            self.settled_trades.ids(),
+            self.in_flight_fills.fills(),
+            self.halt_control.clone(),
+            self.settlement_period.state(),
+        );
+
+        tracing::info!(
+            "Created snapshot at sequence {} with {} accounts, {} positions and {} open orders",
+            snapshot.sequence,
+            snapshot.accounts.len(),
+            snapshot.positions.len(),
+            snapshot.open_orders.len()
+        );
+        Ok(snapshot)
+    }
+}
+/// Shared state of an in-memory processor, and the log it produces to
+#[cfg(test)]
+pub(crate) type StateHandles = (
+    Arc<RwLock<BalanceManager>>,
+    Arc<RwLock<PositionManager>>,
+    MatchingCoreHandle<Matcher>,
+    Arc<crate::interfaces::memory::InMemoryEventProducer>,
+);
+
+#[cfg(test)]
+impl EventProcessor<BalanceManager, PositionManager, Matcher, crate::interfaces::memory::InMemoryEventProducer> {
+    /// A processor over fresh in-memory state, sized in whole contracts
+    pub(crate) fn in_memory() -> Self {
+        use crate::config::fees::FeeConfig;
+        use crate::config::FundingConfig;
+        use crate::core::matching_core::MatchingCore;
+        use crate::funding::rate_calculator::FundingRateCalculator;
+        use crate::interfaces::memory::InMemoryEventProducer;
+        use crate::liquidation::insurance_fund::InsuranceFund;
+        use crate::config::OrderArchiveConfig;
+        use crate::matching::order_book::OrderBook;
+
+        let market_config = MarketConfig {
+            lot_size: Quantity::from_i64(1),
+            min_order_size: Quantity::from_i64(1),
+            max_order_size: Quantity::from_i64(1_000),
+            ..MarketConfig::default()
+        };
+        let market_id = market_config.market_id;
+        let risk_config = RiskConfig::default();
+        let matching = MatchingCore::spawn(Matcher::new(OrderBook::new(), FeeConfig::default(), market_id), 64)
+            .expect("matching core");
+        let funding_config = FundingConfig::default();
+        let funding_interval = funding_config.funding_interval.duration();
+
+        EventProcessor::new_with_dependencies(
+            market_id,
+            market_config,
+            risk_config.clone(),
+            Arc::new(RwLock::new(BalanceManager::new())),
+            Arc::new(RwLock::new(PositionManager::new_with_market(market_id))),
+            matching,
+            Arc::new(RwLock::new(OrderArchive::new(OrderArchiveConfig::default()))),
+            Arc::new(MarginCalculator::new(risk_config)),
+            Arc::new(FundingApplicator::new(FundingRateCalculator::new(funding_config), funding_interval)),
+            Arc::new(LiquidationExecutor::new(market_id, Arc::new(InsuranceFund::new()))),
+            Arc::new(InMemoryEventProducer::new()),
+        )
+    }
+
+    /// The shared state the processor writes, and the log it produces to
+    pub(crate) fn state_handles(&self) -> StateHandles {
+        (
+            self.balance_manager.clone(),
+            self.position_manager.clone(),
+            self.matching.clone(),
+            self.event_producer.clone(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algo::twap::{TwapEngine, TwapRequest};
+    use crate::config::StatementConfig;
+    use crate::events::balance::BalanceUpdate;
+    use crate::events::control::LpMakersDesignated;
+    use crate::events::repair::StateRepair;
+    use crate::events::funding::{FundingCatchUp, PremiumSource};
+    use crate::types::funding_rate::FundingRate;
+    use crate::events::order::{CancelAllOrders, TimeInForce, TrailingOffset};
+    use crate::events::price::{AggregationMethod, PriceSnapshot};
+    use crate::interfaces::memory::InMemoryEventProducer;
+    use crate::interfaces::position_provider::PositionProvider;
+
+    const MARK: f64 = 50_000.0;
+
+    /// An EventProcessor over in-memory state, fed the way the consumer feeds it
+    struct Engine {
+        processor: EventProcessor<BalanceManager, PositionManager, Matcher, InMemoryEventProducer>,
+        balances: Arc<RwLock<BalanceManager>>,
+        positions: Arc<RwLock<PositionManager>>,
+        producer: Arc<InMemoryEventProducer>,
+        market_id: MarketId,
+    }
+
+    impl Engine {
+        fn new() -> Self {
+            let processor = EventProcessor::in_memory();
+            let (balances, positions, _, producer) = processor.state_handles();
+            let market_id = processor.market_id;
+            Engine { processor, balances, positions, producer, market_id }
+        }
+
+        /// Log an event at the next sequence and process it
+        async fn apply(&mut self, event: BaseEvent) -> Result<()> {
+            let mut event = event;
+            event.sequence = self.processor.last_sequence + 1;
+            event.checksum = event.calculate_checksum();
+            self.processor.process_event(event).await
+        }
+
+        fn base(&self, event_type: EventType) -> BaseEvent {
+            BaseEvent::new(event_type, self.market_id)
+        }
+
+        async fn deposit(&mut self, user_id: UserId, amount: f64) {
+            let update = BalanceUpdate {
+                base: self.base(EventType::BalanceUpdate),
+                user_id,
+                amount: Balance::from_f64(amount),
+                update_type: BalanceUpdateType::Deposit,
+                reference_id: None,
+            };
+            let base = update.base.clone();
+            self.apply(BaseEvent { payload: EventPayload::BalanceUpdate(Box::new(update)), ..base }).await.unwrap();
+        }
+
+        async fn mark(&mut self, price: f64) {
+            let event = self.price(price);
+            self.apply(event).await.unwrap();
+        }
+
+        fn price(&self, price: f64) -> BaseEvent {
+            let snapshot = PriceSnapshot {
+                base: self.base(EventType::PriceSnapshot),
+                mark_price: Price::from_f64(price),
+                index_price: Price::from_f64(price),
+                perp_last_price: Price::from_f64(price),
+                premium_ema: Price::zero(),
+                source_prices: Vec::new(),
+                aggregation_method: AggregationMethod::WeightedMedian,
+                staleness_flags: vec![false],  // One fresh source
+            };
+            let base = snapshot.base.clone();
+            BaseEvent { payload: EventPayload::PriceSnapshot(Box::new(snapshot)), ..base }
+        }
+
+        async fn limit(&mut self, user_id: UserId, side: Side, price: f64, contracts: i64) -> OrderId {
+            let order = OrderSubmit { price: Some(Price::from_f64(price)), ..self.order(user_id, side, OrderType::Limit, contracts) };
+            self.submit(order).await
+        }
+
+        async fn stop_limit(&mut self, user_id: UserId, side: Side, trigger: f64, price: f64, contracts: i64) -> OrderId {
+            let order = OrderSubmit {
+                price: Some(Price::from_f64(price)),
+                trigger_price: Some(Price::from_f64(trigger)),
+                ..self.order(user_id, side, OrderType::StopLimit, contracts)
+            };
+            self.submit(order).await
+        }
+
+        fn order(&self, user_id: UserId, side: Side, order_type: OrderType, contracts: i64) -> OrderSubmit {
+            OrderSubmit {
+                base: self.base(EventType::OrderSubmit),
+                order_id: OrderId::new(),
+                user_id,
+                side,
+                order_type,
+                price: None,
+                quantity: Quantity::from_i64(contracts),
+                time_in_force: TimeInForce::GTC,
+                reduce_only: false,
+                post_only: false,
+                slippage_limit: None,
+                trigger_price: None,
+                trailing_offset: None,
+                self_trade_prevention: None,
+                position_side: PositionSide::Both,
+                min_fill_quantity: None,
+            }
+        }
+
+        async fn submit(&mut self, order: OrderSubmit) -> OrderId {
+            let order_id = order.order_id;
+            let base = order.base.clone();
+            self.apply(BaseEvent { payload: EventPayload::OrderSubmit(Box::new(order)), ..base }).await.unwrap();
+            order_id
+        }
+
+        /// Trade events the processor produced, taken off the log
+        fn produced_trades(&self) -> Vec<BaseEvent> {
+            self.producer.drain().into_iter().filter(|event| event.event_type == EventType::Trade).collect()
+        }
+
+        async fn balance(&self, user_id: UserId) -> Balance {
+            self.balances.read().await.get_account(user_id).unwrap().balance
+        }
+
+        async fn reserved(&self, user_id: UserId) -> Balance {
+            self.balances.read().await.get_account(user_id).unwrap().reserved_margin
+        }
+
+        async fn position(&self, user_id: UserId) -> i64 {
+            self.positions.read().await.position_size(user_id)
+        }
+    }
+
+    /// Two funded accounts and a mark price, ready to trade
+    async fn funded_pair(engine: &mut Engine) -> (UserId, UserId) {
+        let (maker, taker) = (UserId::new(), UserId::new());
+        engine.deposit(maker, 100_000.0).await;
+        engine.deposit(taker, 100_000.0).await;
+        engine.mark(MARK).await;
+        (maker, taker)
+    }
+
+    #[tokio::test]
+    async fn deposit_credits_the_account() {
+        let mut engine = Engine::new();
+        let user_id = UserId::new();
+        engine.deposit(user_id, 1_000.0).await;
+
+        assert_eq!(engine.balance(user_id).await, Balance::from_f64(1_000.0));
+        assert_eq!(engine.processor.last_sequence, 1);
+    }
+
+    #[tokio::test]
+    async fn crossing_orders_settle_only_through_the_logged_trade() {
+        let mut engine = Engine::new();
+        let (maker, taker) = funded_pair(&mut engine).await;
+
+        engine.limit(maker, Side::Sell, MARK, 2).await;
+        engine.limit(taker, Side::Buy, MARK, 2).await;
+
+        // Matching only emits the trade
+        assert_eq!(engine.position(taker).await, 0);
+        let trades = engine.produced_trades();
+        assert_eq!(trades.len(), 1);
+
+        for trade in trades {
+            engine.apply(trade).await.unwrap();
+        }
+        assert_eq!(engine.position(taker).await, 2);
+        assert_eq!(engine.position(maker).await, -2);
+        assert_eq!(engine.balance(taker).await, Balance::from_f64(100_000.0 - 50.0));  // 0.05% taker fee
+        assert_eq!(engine.balance(maker).await, Balance::from_f64(100_000.0 - 20.0));  // 0.02% maker fee
+    }
+
+    #[tokio::test]
+    async fn a_trade_moves_balances_exactly_once() {
+        let mut engine = Engine::new();
+        let (maker, taker) = funded_pair(&mut engine).await;
+        engine.limit(maker, Side::Sell, MARK, 2).await;
+        engine.limit(taker, Side::Buy, MARK, 2).await;
+        let trade = engine.produced_trades().pop().unwrap();
+
+        engine.apply(trade.clone()).await.unwrap();
+        let settled = (engine.balance(taker).await, engine.position(taker).await);
+
+        // Redelivered later in the log
+        engine.apply(trade.clone()).await.unwrap();
+        assert_eq!((engine.balance(taker).await, engine.position(taker).await), settled);
+
+        // Replayed over a snapshot that already holds it
+        let snapshot = engine.processor.snapshot().await.unwrap();
+        let mut restored = Engine::new();
+        restored.processor.restore_from_snapshot(&snapshot).await.unwrap();
+        restored.apply(trade).await.unwrap();
+        assert_eq!((restored.balance(taker).await, restored.position(taker).await), settled);
+    }
+
+    #[tokio::test]
+    async fn resting_orders_match_in_the_same_order_after_a_restart() {
+        let mut engine = Engine::new();
+        let (first, taker) = funded_pair(&mut engine).await;
+        let second = UserId::new();
+        engine.deposit(second, 100_000.0).await;
+
+        // The second maker joins the level last, stamped earlier than the first,
+        // and the first has been partly filled: only the queue itself says who is next
+        let first_order = engine.limit(first, Side::Sell, MARK, 3).await;
+        let early = BaseEvent { timestamp: Timestamp::from_millis(0), ..engine.base(EventType::OrderSubmit) };
+        let second_order = engine.submit(OrderSubmit {
+            base: early,
+            price: Some(Price::from_f64(MARK)),
+            ..engine.order(second, Side::Sell, OrderType::Limit, 3)
+        }).await;
+        engine.limit(taker, Side::Buy, MARK, 1).await;
+        for trade in engine.produced_trades() {
+            engine.apply(trade).await.unwrap();
+        }
+
+        let snapshot = engine.processor.snapshot().await.unwrap();
+        let mut restored = Engine::new();
+        restored.processor.restore_from_snapshot(&snapshot).await.unwrap();
+        restored.mark(MARK).await;
+
+        let fills = |trades: Vec<BaseEvent>| -> Vec<(OrderId, Quantity)> {
+            trades.into_iter().map(|event| match event.payload {
+                EventPayload::Trade(trade) => (trade.maker_order_id, trade.quantity),
+                other => panic!("not a trade: {:?}", other),
+            }).collect()
+        };
+        let expected = vec![(first_order, Quantity::from_i64(2)), (second_order, Quantity::from_i64(1))];
+
+        engine.limit(taker, Side::Buy, MARK, 3).await;
+        assert_eq!(fills(engine.produced_trades()), expected);
+        restored.limit(taker, Side::Buy, MARK, 3).await;
+        assert_eq!(fills(restored.produced_trades()), expected);
+    }
+
+    /// Reasons of the requests the processor refused, taken off the log
+    fn rejections(engine: &Engine) -> Vec<RejectReason> {
+        engine.producer.drain().into_iter().filter_map(|event| match event.payload {
+            EventPayload::RequestRejected(rejected) => Some(rejected.reason),
+            _ => None,
+        }).collect()
+    }
+
+    #[tokio::test]
+    async fn a_refused_amend_is_rejected_and_the_log_moves_on() {
+        let mut engine = Engine::new();
+        let (maker, taker) = funded_pair(&mut engine).await;
+        let ask = engine.limit(maker, Side::Sell, MARK + 10.0, 2).await;
+        engine.limit(maker, Side::Buy, MARK - 10.0, 2).await;
+        engine.producer.drain();
+
+        let amend = |engine: &Engine, user_id: UserId, price: f64| {
+            let amend = OrderAmend {
+                base: engine.base(EventType::OrderAmend),
+                order_id: ask,
+                user_id,
+                new_price: Some(Price::from_f64(price)),
+                new_quantity: None,
+            };
+            let base = amend.base.clone();
+            BaseEvent { payload: EventPayload::OrderAmend(Box::new(amend)), ..base }
+        };
+
+        // Someone else's order, then the owner's amend across the spread
+        let sequence = engine.processor.last_sequence;
+        engine.apply(amend(&engine, taker, MARK + 20.0)).await.unwrap();
+        engine.apply(amend(&engine, maker, MARK - 10.0)).await.unwrap();
+        assert_eq!(engine.processor.last_sequence, sequence + 2);
+        assert_eq!(rejections(&engine).len(), 2);
+
+        let price = engine.processor.matching.execute(move |matcher| {
+            matcher.order_book().get_order(&ask).map(|order| order.price)
+        }).await.unwrap();
+        assert_eq!(price, Some(Price::from_f64(MARK + 10.0)));
+    }
+
+    #[tokio::test]
+    async fn a_refused_withdrawal_leaves_the_balance_and_the_log_moves_on() {
+        let mut engine = Engine::new();
+        let user_id = UserId::new();
+        engine.deposit(user_id, 1_000.0).await;
+        engine.mark(MARK).await;
+        engine.producer.drain();
+
+        let withdrawal = BalanceUpdate {
+            base: engine.base(EventType::BalanceUpdate),
+            user_id,
+            amount: Balance::from_f64(5_000.0),
+            update_type: BalanceUpdateType::Withdrawal,
+            reference_id: None,
+        };
+        let base = withdrawal.base.clone();
+        let sequence = engine.processor.last_sequence;
+        engine.apply(BaseEvent { payload: EventPayload::BalanceUpdate(Box::new(withdrawal)), ..base }).await.unwrap();
+
+        assert_eq!(engine.processor.last_sequence, sequence + 1);
+        assert_eq!(engine.balance(user_id).await, Balance::from_f64(1_000.0));
+        assert_eq!(rejections(&engine).len(), 1);
+    }
+
+    #[tokio::test]
+    async fn a_market_twap_slice_is_admitted_and_fills() {
+        let mut engine = Engine::new();
+        let (maker, taker) = funded_pair(&mut engine).await;
+        engine.limit(maker, Side::Sell, MARK, 4).await;
+
+        let mut twap = TwapEngine::new(engine.market_id, Quantity::from_i64(1));
+        let now = Timestamp::now();
+        twap.submit(TwapRequest {
+            user_id: taker,
+            side: Side::Buy,
+            total_quantity: Quantity::from_i64(4),
+            duration: Duration::from_secs(4),
+            slice_interval: Duration::from_secs(1),
+            limit_price: None,
+            slippage_limit: Some(Ratio::from_f64(0.01)),
+            reduce_only: false,
+            position_side: PositionSide::Both,
+        }, now).unwrap();
+        let slice = twap.due_slices(now).pop().unwrap();
+
+        engine.processor.admit_order(&slice).await.unwrap();
+        engine.submit(slice).await;
+        let trades = engine.produced_trades();
+        assert_eq!(trades.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn reduce_only_counts_fills_that_have_not_settled_yet() {
+        let mut engine = Engine::new();
+        let (maker, taker) = funded_pair(&mut engine).await;
+        engine.limit(maker, Side::Sell, MARK, 2).await;
+        engine.limit(taker, Side::Buy, MARK, 2).await;
+        for trade in engine.produced_trades() {
+            engine.apply(trade).await.unwrap();
+        }
+        assert_eq!(engine.position(taker).await, 2);
+
+        let bidder = UserId::new();
+        engine.deposit(bidder, 100_000.0).await;
+        engine.limit(bidder, Side::Buy, MARK, 4).await;
+        let reduce_only = |engine: &Engine| OrderSubmit {
+            price: Some(Price::from_f64(MARK)),
+            reduce_only: true,
+            ..engine.order(taker, Side::Sell, OrderType::Limit, 2)
+        };
+
+        // The first close is matched but its trade has not come back yet
+        engine.submit(reduce_only(&engine)).await;
+        let closing = engine.produced_trades();
+        assert_eq!(closing.len(), 1);
+
+        // A second close of the same position finds nothing left to reduce, also after a restart
+        let snapshot = engine.processor.snapshot().await.unwrap();
+        engine.submit(reduce_only(&engine)).await;
+        assert!(engine.produced_trades().is_empty());
+
+        let mut restored = Engine::new();
+        restored.processor.restore_from_snapshot(&snapshot).await.unwrap();
+        restored.mark(MARK).await;
+        restored.submit(reduce_only(&restored)).await;
+        assert!(restored.produced_trades().is_empty());
+
+        for trade in closing {
+            engine.apply(trade).await.unwrap();
+        }
+        assert_eq!(engine.position(taker).await, 0);
+        assert!(engine.processor.in_flight_fills.is_empty());
+    }
+
+    #[tokio::test]
+    async fn cancel_all_releases_the_margin_of_the_cancelled_orders() {
+        let mut engine = Engine::new();
+        let (maker, other) = funded_pair(&mut engine).await;
+        engine.limit(maker, Side::Buy, MARK - 100.0, 1).await;
+        engine.limit(maker, Side::Sell, MARK + 100.0, 2).await;
+        engine.limit(other, Side::Buy, MARK - 200.0, 1).await;
+        let other_reserved = engine.reserved(other).await;
+        assert!(engine.reserved(maker).await > Balance::zero());
+
+        let cancel_all = CancelAllOrders {
+            base: engine.base(EventType::CancelAllOrders),
+            user_id: Some(maker),
+            operator_id: None,
+            reason: "panic button".to_string(),
+        };
+        let base = cancel_all.base.clone();
+        engine.apply(BaseEvent { payload: EventPayload::CancelAllOrders(Box::new(cancel_all)), ..base }).await.unwrap();
+
+        assert_eq!(engine.reserved(maker).await, Balance::zero());
+        assert_eq!(engine.reserved(other).await, other_reserved);
+        assert!(engine.processor.order_margin.users().iter().all(|user_id| *user_id != maker));
+    }
+
+    #[tokio::test]
+    async fn statements_read_the_state_at_their_sequence_once_there_is_a_price() {
+        let mut engine = Engine::new();
+        let user_id = UserId::new();
+        engine.deposit(user_id, 1_000.0).await;
+        let dir = std::env::temp_dir().join(format!("statements-{}", uuid::Uuid::new_v4()));
+        let config = StatementConfig {
+            enabled: true,
+            cutoff_hour_utc: 0,
+            cutoff_minute_utc: 0,
+            statements_dir: dir.to_string_lossy().into_owned(),
+        };
+        let mut generator = DailyStatementGenerator::new(config, engine.market_id).unwrap();
+        let cutoff = generator.next_cutoff();
+
+        let before_price = engine.processor.daily_statement(&mut generator, cutoff).await;
+        assert!(matches!(before_price, Err(Error::StaleMarkPrice { .. })));
+
+        engine.mark(MARK).await;
+        let records = engine.processor.daily_statement(&mut generator, cutoff).await.unwrap();
+        let record = match &records[0].payload {
+            EventPayload::DailyRecord(record) => record,
+            _ => panic!("not a daily record"),
+        };
+        assert_eq!(record.as_of_sequence, engine.processor.last_sequence);
+        assert_eq!(record.mark_price, Price::from_f64(MARK));
+        assert_eq!(record.balance, Balance::from_f64(1_000.0));
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[tokio::test]
+    async fn a_pending_stop_survives_a_restart() {
+        let mut engine = Engine::new();
+        let (maker, taker) = funded_pair(&mut engine).await;
+        engine.limit(maker, Side::Sell, MARK + 100.0, 2).await;
+        let stop = engine.stop_limit(taker, Side::Buy, MARK + 50.0, MARK + 100.0, 2).await;
+        let reserved = engine.reserved(taker).await;
+        assert!(reserved > Balance::zero());
+
+        let snapshot = engine.processor.snapshot().await.unwrap();
+        let mut restored = Engine::new();
+        restored.processor.restore_from_snapshot(&snapshot).await.unwrap();
+        assert!(restored.processor.trigger_monitor.get(&stop).is_some());
+        assert_eq!(restored.reserved(taker).await, reserved);
+
+        // Still fires once the mark reaches its trigger
+        restored.mark(MARK + 60.0).await;
+        assert!(restored.processor.trigger_monitor.is_empty());
+        assert_eq!(restored.produced_trades().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn a_fired_trailing_stop_is_submitted_once_across_replay() {
+        let mut engine = Engine::new();
+        let (maker, taker) = funded_pair(&mut engine).await;
+        engine.limit(maker, Side::Buy, MARK - 200.0, 2).await;
+        let trailing = OrderSubmit {
+            trailing_offset: Some(TrailingOffset::Absolute(Price::from_f64(100.0))),
+            slippage_limit: Some(Ratio::from_f64(0.01)),
+            ..engine.order(taker, Side::Sell, OrderType::TrailingStop, 2)
+        };
+        let stop = engine.submit(trailing).await;
+        engine.producer.drain();
+        let snapshot = engine.processor.snapshot().await.unwrap();
+
+        // The fall fires the stop, which goes through the log as a Market order
+        let fall = engine.price(MARK - 150.0);
+        engine.apply(fall.clone()).await.unwrap();
+        let submission = engine.producer.drain().pop().unwrap();
+        assert_eq!(submission.event_type, EventType::OrderSubmit);
+
+        // Replay from the snapshot: the fall fires the stop again and logs a copy
+        let mut restored = Engine::new();
+        restored.processor.restore_from_snapshot(&snapshot).await.unwrap();
+        restored.apply(fall).await.unwrap();
+        let copy = restored.producer.drain().pop().unwrap();
+        assert!(matches!(&copy.payload, EventPayload::OrderSubmit(order) if order.order_id == stop));
+
+        restored.apply(submission).await.unwrap();
+        assert_eq!(restored.produced_trades().len(), 1);
+        restored.apply(copy).await.unwrap();
+        assert!(restored.producer.drain().is_empty());
+    }
+
+    #[tokio::test]
+    async fn a_logged_lp_designation_sets_the_maker_rate_across_a_restart() {
+        let mut engine = Engine::new();
+        let (maker, taker) = funded_pair(&mut engine).await;
+        let designation = LpMakersDesignated {
+            base: engine.base(EventType::LpMakersDesignated),
+            makers: vec![maker],
+            period_start: Timestamp::from_millis(0),
+            period_end: Timestamp::from_millis(86_400_000),
+        };
+        let base = designation.base.clone();
+        engine.apply(BaseEvent { payload: EventPayload::LpMakersDesignated(Box::new(designation)), ..base }).await.unwrap();
+
+        let snapshot = engine.processor.snapshot().await.unwrap();
+        let mut restored = Engine::new();
+        restored.processor.restore_from_snapshot(&snapshot).await.unwrap();
+        restored.mark(MARK).await;
+
+        restored.limit(maker, Side::Sell, MARK, 2).await;
+        restored.limit(taker, Side::Buy, MARK, 2).await;
+        for trade in restored.produced_trades() {
+            restored.apply(trade).await.unwrap();
+        }
+        assert_eq!(restored.balance(maker).await, Balance::from_f64(100_000.0 + 10.0));  // 0.01% LP rebate
+    }
+
+    #[tokio::test]
+    async fn a_state_repair_is_countered_and_keeps_open_interest_matched() {
+        let mut engine = Engine::new();
+        let (maker, taker) = funded_pair(&mut engine).await;
+        engine.limit(maker, Side::Sell, MARK, 2).await;
+        engine.limit(taker, Side::Buy, MARK, 2).await;
+        for trade in engine.produced_trades() {
+            engine.apply(trade).await.unwrap();
+        }
+        let before = engine.balance(taker).await;
+
+        let operator_id = OperatorId::new();
+        crate::utils::helper::add_authorized_operator(operator_id);
+        let repair = StateRepair {
+            base: engine.base(EventType::StateRepair),
+            user_id: taker,
+            snapshot_sequence: 1,
+            operator_id,
+            reason: "drill".to_string(),
+            target_balance: Some(before + Balance::from_f64(500.0)),
+            target_position: Some(Position::new(taker, engine.market_id)),
+        };
+        let base = repair.base.clone();
+        engine.apply(BaseEvent { payload: EventPayload::StateRepair(Box::new(repair)), ..base }).await.unwrap();
+
+        assert_eq!(engine.balance(taker).await, before + Balance::from_f64(500.0));
+        assert_eq!(engine.balance(UserId::repair_suspense()).await, Balance::from_f64(-500.0));
+        assert_eq!(engine.position(taker).await, 0);
+        assert_eq!(engine.position(*LIQUIDATION_ENGINE_USER_ID).await, 2);  // Took the closed long
+        let net: i64 = engine.positions.read().await.get_all_positions().iter().map(|p| p.size).sum();
+        assert_eq!(net, 0);
+    }
+
+    /// One interval's funding: the long pays the short
+    fn funding(engine: &Engine, at: Timestamp, catch_up: bool) -> BaseEvent {
+        let base = BaseEvent { timestamp: at, ..engine.base(EventType::Funding) };
+        let interval = std::time::Duration::from_secs(8 * 3600);
+        let funding = FundingEvent {
+            base: base.clone(),
+            funding_rate: FundingRate::from_f64(0.0001),
+            mark_price: Price::from_f64(MARK),
+            index_price: Price::from_f64(MARK),
+            premium: Price::zero(),
+            funding_interval: interval,
+            payments: Vec::new(),  // The processor pays from its own positions
+            residual: Balance::zero(),
+            index_delta: Balance::from_f64(5.0),
+            catch_up: catch_up.then(|| FundingCatchUp {
+                interval_start: Timestamp::from_millis(at.physical - interval.as_millis() as u64),
+                interval_end: at,
+                premium_source: PremiumSource::RecordedTwap { samples: 1 },
+            }),
+            components: None,
+        };
+        BaseEvent { payload: EventPayload::Funding(Box::new(funding)), ..base }
+    }
+
+    #[tokio::test]
+    async fn a_funding_interval_settles_once() {
+        let mut engine = Engine::new();
+        let (short, long) = funded_pair(&mut engine).await;
+        engine.limit(short, Side::Sell, MARK, 2).await;
+        engine.limit(long, Side::Buy, MARK, 2).await;
+        for trade in engine.produced_trades() {
+            engine.apply(trade).await.unwrap();
+        }
+        let (before, short_before) = (engine.balance(long).await, engine.balance(short).await);
+
+        // Ticker event a moment past the boundary a full interval after the fill
+        let interval_ms = 8 * 3600 * 1000;
+        let boundary = Timestamp::from_millis((Timestamp::now().physical / interval_ms + 2) * interval_ms);
+        let tick = Timestamp::from_millis(boundary.physical + 250);
+        engine.apply(funding(&engine, tick, false)).await.unwrap();
+        assert_eq!(engine.balance(long).await, before - Balance::from_f64(10.0));
+
+        // Redelivered, then settled again by a restart's catch-up
+        engine.apply(funding(&engine, tick, false)).await.unwrap();
+        engine.apply(funding(&engine, boundary, true)).await.unwrap();
+        assert_eq!(engine.balance(long).await, before - Balance::from_f64(10.0));
+        assert_eq!(engine.balance(short).await, short_before + Balance::from_f64(10.0));
+        assert_eq!(engine.positions.read().await.cumulative_funding().last_funding, boundary);
+
+        // The next interval still pays
+        let next = Timestamp::from_millis(boundary.physical + 8 * 3600 * 1000);
+        engine.apply(funding(&engine, next, true)).await.unwrap();
+        assert_eq!(engine.balance(long).await, before - Balance::from_f64(20.0));
+    }
+
+    #[tokio::test]
+    async fn halt_and_resume_replay_from_the_log() {
+        let mut engine = Engine::new();
+        let operator_id = OperatorId::new();
+        engine.processor.control(ProcessorAction::Halt { operator_id, note: "drill".to_string() }).await.unwrap();
+        assert!(engine.processor.is_halted());
+        engine.processor.control(ProcessorAction::Resume { operator_id }).await.unwrap();
+        assert!(!engine.processor.is_halted());
+        let log = engine.producer.drain();
+        assert_eq!(log.len(), 2);
+
+        // Events logged while halted are applied in order on replay
+        let mut replayed = Engine::new();
+        let user_id = UserId::new();
+        replayed.apply(log[0].clone()).await.unwrap();
+        assert!(replayed.processor.is_halted());
+        replayed.deposit(user_id, 1_000.0).await;
+        replayed.apply(log[1].clone()).await.unwrap();
+        assert!(!replayed.processor.is_halted());
+        assert_eq!(replayed.balance(user_id).await, Balance::from_f64(1_000.0));
+    }
+
+    #[tokio::test]
+    async fn replayed_sequence_is_skipped() {
+        let mut engine = Engine::new();
+        let user_id = UserId::new();
+        engine.deposit(user_id, 1_000.0).await;
+
+        let update = BalanceUpdate {
+            base: engine.base(EventType::BalanceUpdate),
+            user_id,
+            amount: Balance::from_f64(1_000.0),
+            update_type: BalanceUpdateType::Deposit,
+            reference_id: None,
+        };
+        let mut event = BaseEvent { payload: EventPayload::BalanceUpdate(Box::new(update.clone())), ..update.base };
+        event.sequence = 1;  // Already processed
+        event.checksum = event.calculate_checksum();
+        engine.processor.process_event(event).await.unwrap();
+
+        assert_eq!(engine.balance(user_id).await, Balance::from_f64(1_000.0));
+    }
+}
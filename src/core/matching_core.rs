@@ -0,0 +1,295 @@
+use crate::error::{Error, Result};
+use crate::interfaces::order_matcher::OrderMatcher;
+use crate::invariants::checks::InvariantChecks;
+use crate::matching::matcher::Matcher;
+use crate::matching::order_book::{DepthSnapshot, Order, OrderBook};
+use crate::observability::metrics::{MATCHING_CORE_EPOCH, MATCHING_QUEUE_FULL};
+use crate::types::price::Price;
+use crate::utils::helper::alert_operations_team_critical;
+use arc_swap::ArcSwap;
+use crossbeam_queue::ArrayQueue;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, Thread};
+use tokio::sync::oneshot;
+
+/// Commands the matching core can hold before producers wait
+pub const MATCHING_QUEUE_CAPACITY: usize = 4096;
+/// Levels per side captured in each published book view
+pub const BOOK_VIEW_LEVELS: usize = 50;
+/// Empty polls before the core thread parks (keeps wake-up latency off the hot path)
+const SPINS_BEFORE_PARK: u32 = 1_000;
+
+enum Command<M> {
+    Apply(Box<dyn FnOnce(&mut M) + Send>),
+    /// Rebuilds the book; runs even when poisoned (and clears the poison once it succeeds)
+    Reset(Box<dyn FnOnce(&mut M) + Send>),
+}
+
+/// Read-only view of the book published after every command
+///
+/// Epochs increase by one per applied command; a reader holding an older
+/// view can tell it has been superseded without touching the matcher.
+#[derive(Clone, Debug, Default)]
+pub struct BookView {
+    pub epoch: u64,
+    pub depth: DepthSnapshot,
+}
+
+impl BookView {
+    fn capture<M: OrderMatcher>(matcher: &M, epoch: u64) -> Self {
+        BookView {
+            epoch,
+            depth: matcher.depth(BOOK_VIEW_LEVELS),
+        }
+    }
+
+    pub fn best_bid(&self) -> Option<Price> {
+        self.depth.bids.first().map(|level| level.price)
+    }
+
+    pub fn best_ask(&self) -> Option<Price> {
+        self.depth.asks.first().map(|level| level.price)
+    }
+
+    /// (best_bid, best_ask) if the book is crossed or locked
+    pub fn crossed(&self) -> Option<(Price, Price)> {
+        match (self.best_bid(), self.best_ask()) {
+            (Some(bid), Some(ask)) if bid >= ask => Some((bid, ask)),
+            _ => None,
+        }
+    }
+}
+
+/// Single-writer matching engine
+///
+/// The matcher, and with it the market's only order book, is owned by a
+/// dedicated OS thread and only ever mutated there. Producers push commands
+/// onto a bounded lock-free queue and await the reply; readers load the
+/// latest `BookView` without blocking the core. Commands never wait on a
+/// lock: whatever state they read is acquired by the caller and moved in.
+///
+/// A panicking command poisons the core: the book can't be trusted, so every
+/// later command fails with `MatchingCoreStopped` until `restore` rebuilds it.
+pub struct MatchingCore;
+
+impl MatchingCore {
+    /// Move `matcher` onto a new "matching-core" thread
+    ///
+    /// The thread exits once every handle has been dropped.
+    pub fn spawn<M>(matcher: M, capacity: usize) -> Result<MatchingCoreHandle<M>>
+    where
+        M: OrderMatcher + Send + 'static,
+    {
+        let queue = Arc::new(ArrayQueue::<Command<M>>::new(capacity));
+        let view = Arc::new(ArcSwap::from_pointee(BookView::capture(&matcher, 0)));
+        let poisoned = Arc::new(AtomicBool::new(false));
+
+        let core_queue = queue.clone();
+        let core_view = view.clone();
+        let core_poisoned = poisoned.clone();
+        let join = thread::Builder::new()
+            .name("matching-core".to_string())
+            .spawn(move || run(matcher, core_queue, core_view, core_poisoned))?;
+
+        Ok(MatchingCoreHandle {
+            queue,
+            view,
+            poisoned,
+            core: join.thread().clone(),
+        })
+    }
+}
+
+fn run<M: OrderMatcher>(
+    mut matcher: M,
+    queue: Arc<ArrayQueue<Command<M>>>,
+    view: Arc<ArcSwap<BookView>>,
+    poisoned: Arc<AtomicBool>,
+) {
+    let mut epoch = 0u64;
+    let mut idle = 0u32;
+
+    loop {
+        match queue.pop() {
+            // After a panic the book can't be trusted: drop commands so their callers get MatchingCoreStopped
+            Some(Command::Apply(command)) if poisoned.load(Ordering::Acquire) => drop(command),
+            Some(command) => {
+                idle = 0;
+                let applied = panic::catch_unwind(AssertUnwindSafe(|| match command {
+                    Command::Apply(command) | Command::Reset(command) => command(&mut matcher),
+                }));
+                if applied.is_err() {
+                    alert_operations_team_critical("Matching core command panicked; rejecting further commands".to_string());
+                    poisoned.store(true, Ordering::Release);
+                    continue;
+                }
+
+                // Publish before the reply is observed so the sender sees its own write
+                epoch += 1;
+                view.store(Arc::new(BookView::capture(&matcher, epoch)));
+                MATCHING_CORE_EPOCH.set(epoch as i64);
+            }
+            None if Arc::strong_count(&queue) == 1 => {
+                tracing::info!("Matching core stopping at epoch {}", epoch);
+                return;
+            }
+            None if idle < SPINS_BEFORE_PARK => {
+                idle += 1;
+                std::hint::spin_loop();
+            }
+            None => {
+                idle = 0;
+                thread::park();
+            }
+        }
+    }
+}
+
+/// Sends commands to the matching core and reads its published book views
+pub struct MatchingCoreHandle<M> {
+    queue: Arc<ArrayQueue<Command<M>>>,
+    view: Arc<ArcSwap<BookView>>,
+    poisoned: Arc<AtomicBool>,
+    core: Thread,
+}
+
+impl<M> Clone for MatchingCoreHandle<M> {
+    fn clone(&self) -> Self {
+        MatchingCoreHandle {
+            queue: self.queue.clone(),
+            view: self.view.clone(),
+            poisoned: self.poisoned.clone(),
+            core: self.core.clone(),
+        }
+    }
+}
+
+impl<M: OrderMatcher + Send + 'static> MatchingCoreHandle<M> {
+    /// Run `f` against the matcher on the core thread and await its result
+    ///
+    /// Commands are applied in the order they were queued. A full queue is
+    /// backpressure: the caller yields until the core drains a slot.
+    pub async fn execute<R, F>(&self, f: F) -> Result<R>
+    where
+        R: Send + 'static,
+        F: FnOnce(&mut M) -> R + Send + 'static,
+    {
+        if self.is_poisoned() {
+            return Err(Error::MatchingCoreStopped);
+        }
+
+        let (reply_tx, reply_rx) = oneshot::channel();
+        let poisoned = self.poisoned.clone();
+        self.send(Command::Apply(Box::new(move |matcher: &mut M| {
+            match panic::catch_unwind(AssertUnwindSafe(|| f(matcher))) {
+                Ok(result) => {
+                    let _ = reply_tx.send(result);
+                }
+                // Poison before the reply is dropped, so the caller already sees it
+                Err(payload) => {
+                    poisoned.store(true, Ordering::Release);
+                    panic::resume_unwind(payload);
+                }
+            }
+        }))).await;
+
+        // A dropped reply means the command panicked and the core stopped matching
+        reply_rx.await.map_err(|_| Error::MatchingCoreStopped)
+    }
+
+    /// Replace the book with `orders` (in priority order) and discard any
+    /// unread match output
+    ///
+    /// Accepted while the core is poisoned; a successful rebuild clears the
+    /// poison, so this is how a snapshot restore brings the core back.
+    pub async fn restore(&self, orders: Vec<Order>) -> Result<()> {
+        let (reply_tx, reply_rx) = oneshot::channel();
+        let poisoned = self.poisoned.clone();
+        self.send(Command::Reset(Box::new(move |matcher: &mut M| {
+            let result = matcher.reset(orders);
+            if result.is_ok() {
+                poisoned.store(false, Ordering::Release);
+            }
+            let _ = reply_tx.send(result);
+        }))).await;
+
+        reply_rx.await.map_err(|_| Error::MatchingCoreStopped)?
+    }
+
+    /// A command panicked and the book has not been restored since
+    pub fn is_poisoned(&self) -> bool {
+        self.poisoned.load(Ordering::Acquire)
+    }
+
+    async fn send(&self, mut command: Command<M>) {
+        loop {
+            match self.queue.push(command) {
+                Ok(()) => break,
+                Err(rejected) => {
+                    MATCHING_QUEUE_FULL.inc();
+                    command = rejected;
+                    self.core.unpark();
+                    tokio::task::yield_now().await;
+                }
+            }
+        }
+        self.core.unpark();
+    }
+
+    /// Latest published book view; never blocks the core
+    pub fn book(&self) -> Arc<BookView> {
+        self.view.load_full()
+    }
+}
+
+impl MatchingCoreHandle<Matcher> {
+    /// Resting orders in queue priority, copied off the core (snapshots, exports)
+    pub async fn orders_in_priority(&self) -> Result<Vec<Order>> {
+        self.execute(|matcher| matcher.order_book().orders_in_priority()).await
+    }
+
+    /// Private copy of the book with the same queue order, rebuilt off the core
+    pub async fn copy_book(&self) -> Result<OrderBook> {
+        let mut book = OrderBook::new();
+        book.restore_orders(self.orders_in_priority().await?)?;
+        Ok(book)
+    }
+
+    /// Book invariants, checked on the core against the live book
+    pub async fn book_invariants(&self) -> Result<Vec<(&'static str, Result<()>)>> {
+        self.execute(|matcher| {
+            let book = matcher.order_book();
+            vec![
+                ("order_book_consistency", InvariantChecks::check_order_book_consistency(book)),
+                ("book_not_crossed", InvariantChecks::check_book_not_crossed(book)),
+            ]
+        }).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::fees::FeeConfig;
+    use crate::types::ids::MarketId;
+
+    fn spawn_core() -> MatchingCoreHandle<Matcher> {
+        MatchingCore::spawn(Matcher::new(OrderBook::new(), FeeConfig::default(), MarketId::new()), 16).unwrap()
+    }
+
+    #[tokio::test]
+    async fn a_panic_poisons_the_core_until_restored() {
+        let core = spawn_core();
+
+        let panicked = core.execute(|_: &mut Matcher| -> () { panic!("corrupt book") }).await;
+        assert!(matches!(panicked, Err(Error::MatchingCoreStopped)));
+        assert!(core.is_poisoned());
+        assert!(matches!(core.orders_in_priority().await, Err(Error::MatchingCoreStopped)));
+
+        core.restore(Vec::new()).await.unwrap();
+        assert!(!core.is_poisoned());
+        assert!(core.orders_in_priority().await.unwrap().is_empty());
+    }
+}
@@ -1 +1,2 @@
-pub mod event_processor;
\ No newline at end of file
+pub mod event_processor;
+pub mod matching_core;
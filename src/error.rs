@@ -1,8 +1,9 @@
 use thiserror::Error;
 use crate::types::balance::Balance;
-use crate::types::ids::{AccountId, EventId, OrderId};
+use crate::types::ids::{AccountId, EventId, MarketId, OrderId, TenantId, UserId};
 use crate::types::price::Price;
 use crate::types::quantity::Quantity;
+use crate::types::timestamp::Timestamp;
 
 #[derive(Error, Debug)]
 pub enum Error {
@@ -36,6 +37,9 @@ pub enum Error {
     #[error("No more events available")]
     NoMoreEvents,
 
+    #[error("Sequence {0} is not among the events consumed from the log")]
+    UnknownLogSequence(u64),
+
     #[error("Sequence gap: expected {expected}, got {actual}")]
     SequenceGap {
         expected: u64,
@@ -101,10 +105,46 @@ pub enum Error {
     #[error("Limit order requires price")]
     LimitOrderRequiresPrice,
 
+    #[error("Stop order requires trigger price")]
+    StopOrderRequiresTriggerPrice,
+
+    #[error("Trailing stop requires a valid trailing offset")]
+    TrailingStopRequiresOffset,
+
+    #[error("Price {price} is outside the {band_bps}bps band around mark {reference}")]
+    PriceOutsideBand { price: Price, reference: Price, band_bps: u32 },
+
     // Order Book Errors
     #[error("Duplicate order ID: {0}")]
     DuplicateOrderId(OrderId),
 
+    #[error("Amended price would cross the book")]
+    AmendWouldCross,
+
+    #[error("Fill-or-kill order cannot be filled in full")]
+    FillOrKillNotFilled,
+
+    #[error("IOC order cannot fill its minimum fill quantity")]
+    MinFillQuantityNotMet,
+
+    #[error("Minimum fill quantity is only valid for IOC orders, within the order quantity")]
+    InvalidMinFillQuantity,
+
+    #[error("Post-only order would take liquidity")]
+    PostOnlyWouldCross,
+
+    #[error("Mark price is stale ({age_ms}ms old)")]
+    StaleMarkPrice { age_ms: u64 },
+
+    #[error("Price circuit breaker is open")]
+    CircuitBreakerOpen,
+
+    #[error("Market is in {0:?} phase")]
+    TradingPhaseRestricted(crate::events::control::TradingPhase),
+
+    #[error("Market expired at {expires_at:?}")]
+    MarketExpired { expires_at: Timestamp },
+
     #[error("Order not found: {0}")]
     OrderNotFound(OrderId),
 
@@ -135,12 +175,74 @@ pub enum Error {
         max: f64,
     },
 
+    #[error("Leverage must be at least 1x: {0}")]
+    InvalidLeverage(f64),
+
     #[error("Position limit exceeded")]
     PositionLimitExceeded,
 
+    #[error("Open notional limit exceeded: notional={notional}, limit={limit}")]
+    OpenNotionalLimitExceeded {
+        notional: Balance,
+        limit: Balance,
+    },
+
+    #[error("Open order limit reached: {limit}")]
+    OpenOrderLimitExceeded {
+        limit: u32,
+    },
+
+    #[error("Open interest cap exceeded: open_interest={open_interest}, cap={cap}")]
+    OpenInterestCapExceeded {
+        open_interest: Quantity,
+        cap: Quantity,
+    },
+
     #[error("Reduce-only violation")]
     ReduceOnlyViolation,
 
+    #[error("Batch orders must all belong to the same user")]
+    BatchUserMismatch,
+
+    #[error("Position side does not match the account's position mode")]
+    PositionSideMismatch,
+
+    #[error("Position mode can only change while the account is flat")]
+    PositionModeLocked,
+
+    #[error("Margin mode can only change while the account is flat")]
+    MarginModeLocked,
+
+    #[error("Position is not isolated")]
+    PositionNotIsolated,
+
+    #[error("Isolated margin removal would leave the position under-margined: required={required}, remaining={remaining}")]
+    InsufficientIsolatedMargin {
+        required: Balance,
+        remaining: Balance,
+    },
+
+    #[error("GTD expiry must be in the future and is only valid for limit orders")]
+    InvalidExpiry,
+
+    #[error("Ingress queue full")]
+    IngressQueueFull,
+
+    #[error("Ingress sequencer stopped")]
+    IngressClosed,
+
+    #[error("{0:?} events are disabled for this market")]
+    EventTypeDisabled(crate::events::base::EventType),
+
+    #[error("Notification delivery failed: {0}")]
+    NotificationDeliveryFailed(String),
+
+    #[error("Withdrawal would breach margin buffer: margin_ratio={margin_ratio}, min={min_ratio}")]
+    WithdrawalMarginBreach {
+        margin_ratio: f64,
+        min_ratio: f64,
+    },
+
     // Liquidation Errors
     #[error("Liquidation failed: no liquidity")]
     LiquidationFailedNoLiquidity,
@@ -171,6 +273,21 @@ pub enum Error {
     #[error("Insufficient available balance")]
     InsufficientAvailableBalance,
 
+    #[error("Invalid transfer amount")]
+    InvalidTransferAmount,
+
+    #[error("Cannot transfer to the same account")]
+    SelfTransfer,
+
+    #[error("Invalid sub-account: {0}")]
+    InvalidSubAccount(String),
+
+    #[error("Account {account} is not {master} or one of its sub-accounts")]
+    NotInSubAccountFamily {
+        master: UserId,
+        account: UserId,
+    },
+
     #[error("Double-entry imbalance: debits={debits}, credits={credits}")]
     DoubleEntryImbalance {
         debits: i64,
@@ -200,15 +317,57 @@ pub enum Error {
     #[error("Unauthorized")]
     Unauthorized,
 
+    #[error("API key lacks the {0:?} scope")]
+    ApiKeyScopeDenied(crate::api::auth::ApiKeyScope),
+
+    #[error("Event processor is not halted")]
+    ProcessorNotHalted,
+
+    #[error("Halt must be acknowledged by an operator before resuming")]
+    HaltNotAcknowledged,
+
     #[error("Authentication error: {0}")]
     AuthenticationError(String),
 
     #[error("Rate limit exceeded")]
     RateLimitExceeded,
 
+    #[error("Tenant not found: {0:?}")]
+    TenantNotFound(TenantId),
+
     #[error("No snapshot found")]
     NoSnapshotFound,
 
+    #[error("Unsupported snapshot version: {version}, expected: {expected}")]
+    UnsupportedSnapshotVersion {
+        version: u32,
+        expected: u32,
+    },
+
+    #[error("Unsupported account export version: {version}, max supported: {max_supported}")]
+    UnsupportedExportVersion {
+        version: u32,
+        max_supported: u32,
+    },
+
+    #[error("Account export is for market {export:?}, not {market:?}")]
+    ExportMarketMismatch {
+        export: MarketId,
+        market: MarketId,
+    },
+
+    #[error("Genesis import needs an empty event log, found sequence {0}")]
+    ImportTargetNotEmpty(u64),
+
+    #[error("Unsupported order book export version: {version}, max supported: {max_supported}")]
+    UnsupportedBookExportVersion {
+        version: u32,
+        max_supported: u32,
+    },
+
+    #[error("Matching core thread has stopped")]
+    MatchingCoreStopped,
+
     #[error("Configuration error: {0}")]
     ConfigError(String),
 
@@ -0,0 +1,122 @@
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use crate::error::{Error, Result};
+use crate::types::timestamp::Timestamp;
+
+// lz4-compressed archive files shared by snapshot and ledger archival
+
+pub fn compress(data: &[u8]) -> Result<Vec<u8>> {
+    let mut encoder = lz4::EncoderBuilder::new()
+        .level(4)
+        .build(Vec::new())
+        .map_err(Error::IoError)?;
+    encoder.write_all(data).map_err(Error::IoError)?;
+
+    let (compressed, result) = encoder.finish();
+    result.map_err(Error::IoError)?;
+    Ok(compressed)
+}
+
+pub fn decompress(data: &[u8]) -> Result<Vec<u8>> {
+    let mut decoder = lz4::Decoder::new(data).map_err(Error::IoError)?;
+    let mut out = Vec::new();
+    decoder.read_to_end(&mut out).map_err(Error::IoError)?;
+    Ok(out)
+}
+
+/// `{kind}_{id:020}_{last_ms}.{ext}.lz4`, where `last_ms` is the newest
+/// timestamp the file covers (pruning reads only the name, never the contents)
+pub fn file_name(kind: &str, id: u64, last: Timestamp, ext: &str) -> String {
+    format!("{}_{:020}_{}.{}.lz4", kind, id, last.physical, ext)
+}
+
+/// Newest timestamp (ms) covered by an archive file of `kind`
+fn last_millis(path: &Path, kind: &str) -> Option<u64> {
+    let name = path.file_name()?.to_str()?;
+    let stem = name.strip_prefix(kind)?.strip_prefix('_')?;
+    let stem = stem.split('.').next()?;
+    stem.rsplit('_').next()?.parse().ok()
+}
+
+/// Write a compressed archive file; existing archives are never overwritten
+pub fn write_file(path: &Path, data: &[u8]) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(Error::IoError)?;
+    }
+
+    let compressed = compress(data)?;
+
+    // Temp file + rename, so a crash never leaves a truncated archive behind
+    let tmp = path.with_extension("tmp");
+    let mut file = std::fs::File::create(&tmp).map_err(Error::IoError)?;
+    file.write_all(&compressed).map_err(Error::IoError)?;
+    file.sync_all().map_err(Error::IoError)?;
+
+    if path.exists() {
+        let _ = std::fs::remove_file(&tmp);
+        return Err(Error::IoError(std::io::Error::new(
+            std::io::ErrorKind::AlreadyExists,
+            format!("archive file {:?} already exists", path),
+        )));
+    }
+    std::fs::rename(&tmp, path).map_err(Error::IoError)?;
+    Ok(())
+}
+
+pub fn read_file(path: &Path) -> Result<Vec<u8>> {
+    let data = std::fs::read(path).map_err(Error::IoError)?;
+    decompress(&data)
+}
+
+/// Archive files of `kind` in `dir`, oldest first
+pub fn list_files(dir: &Path, kind: &str) -> Result<Vec<PathBuf>> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(Error::IoError(e)),
+    };
+
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.extension().is_some_and(|ext| ext == "lz4") && last_millis(p, kind).is_some())
+        .collect();
+
+    // Zero-padded ids, so lexical order is archive order
+    files.sort();
+    Ok(files)
+}
+
+/// Remove expired archive files of `kind`; returns how many were removed
+///
+/// `retention` None keeps everything. Files covering anything at or after
+/// `floor` (the newest snapshot) are kept regardless of age, so nothing
+/// needed to rebuild state from the newest snapshot is ever removed.
+pub fn prune_expired(
+    dir: &Path,
+    kind: &str,
+    retention: Option<Duration>,
+    floor: Timestamp,
+    now: Timestamp,
+) -> Result<usize> {
+    let retention = match retention {
+        Some(retention) => retention,
+        None => return Ok(0),
+    };
+
+    let expiry = now.physical.saturating_sub(retention.as_millis() as u64);
+    let cutoff = expiry.min(floor.physical);
+
+    let mut removed = 0;
+    for path in list_files(dir, kind)? {
+        match last_millis(&path, kind) {
+            Some(last) if last < cutoff => {
+                std::fs::remove_file(&path).map_err(Error::IoError)?;
+                tracing::info!("Pruned archive file {:?}", path);
+                removed += 1;
+            }
+            _ => {}
+        }
+    }
+    Ok(removed)
+}
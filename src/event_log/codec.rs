@@ -0,0 +1,170 @@
+use rkyv::{AlignedVec, Deserialize as RkyvDeserialize, Infallible};
+use serde::{Deserialize, Serialize};
+use crate::error::{Error, Result};
+use crate::events::base::{ArchivedBaseEvent, BaseEvent};
+
+/// Frame magic bytes ("PI")
+const FRAME_MAGIC: [u8; 2] = *b"PI";
+const FRAME_VERSION: u8 = 1;
+
+/// Header is padded to 16 bytes so the archived body keeps rkyv's alignment
+/// whenever the message buffer itself is aligned
+const HEADER_LEN: usize = 16;
+const BODY_ALIGN: usize = 16;
+
+/// Event wire codec
+///
+/// ## Frame Layout
+/// - **Bytes 0-1**: Magic `PI`
+/// - **Byte 2**: Frame version
+/// - **Byte 3**: Codec id (1 = binary, 2 = JSON)
+/// - **Bytes 4-15**: Reserved (zero)
+/// - **Bytes 16..**: Body
+///
+/// ## Codecs
+/// - **Binary**: rkyv archive of `BaseEvent`. `with_view()` validates the body and
+///   reads it in place, so tooling can inspect sequence/type without materializing
+///   the event; `decode()` validates it once and deserializes from the same view
+/// - **Json**: serde_json of `BaseEvent` for debugging and tooling
+///
+/// Decoding detects the codec from the header, so both can share a topic.
+/// Unframed payloads are the bincode events logged before framing and are
+/// decoded as such, so older logs still replay.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventCodec {
+    #[default]
+    Binary,
+    Json,
+}
+
+impl EventCodec {
+    fn id(&self) -> u8 {
+        match self {
+            EventCodec::Binary => 1,
+            EventCodec::Json => 2,
+        }
+    }
+
+    fn from_id(id: u8) -> Result<Self> {
+        match id {
+            1 => Ok(EventCodec::Binary),
+            2 => Ok(EventCodec::Json),
+            other => Err(Error::DeserializationError(format!("unknown codec id {}", other))),
+        }
+    }
+
+    pub fn encode(&self, event: &BaseEvent) -> Result<Vec<u8>> {
+        let body = match self {
+            EventCodec::Binary => rkyv::to_bytes::<_, 1024>(event)
+                .map_err(|e| Error::SerializationError(e.to_string()))?
+                .into_vec(),
+            EventCodec::Json => serde_json::to_vec(event)
+                .map_err(|e| Error::SerializationError(e.to_string()))?,
+        };
+
+        let mut frame = Vec::with_capacity(HEADER_LEN + body.len());
+        frame.extend_from_slice(&FRAME_MAGIC);
+        frame.push(FRAME_VERSION);
+        frame.push(self.id());
+        frame.resize(HEADER_LEN, 0);
+        frame.extend_from_slice(&body);
+
+        Ok(frame)
+    }
+
+    /// Decode a frame of either codec (or a legacy bincode payload) into an owned event
+    pub fn decode(frame: &[u8]) -> Result<BaseEvent> {
+        if !frame.starts_with(&FRAME_MAGIC) {
+            return bincode::deserialize(frame)
+                .map_err(|e| Error::DeserializationError(e.to_string()));
+        }
+        let (codec, body) = Self::split_frame(frame)?;
+
+        match codec {
+            EventCodec::Binary => Self::with_archived(body, |archived| {
+                archived.deserialize(&mut Infallible)
+                    .map_err(|_| Error::DeserializationError("rkyv deserialize failed".to_string()))
+            })?,
+            EventCodec::Json => serde_json::from_slice(body)
+                .map_err(|e| Error::DeserializationError(e.to_string())),
+        }
+    }
+
+    /// Run `f` against the archived event without deserializing it
+    /// Only binary frames support this; the body is copied only if misaligned
+    pub fn with_view<R>(frame: &[u8], f: impl FnOnce(&ArchivedBaseEvent) -> R) -> Result<R> {
+        let (codec, body) = Self::split_frame(frame)?;
+
+        if codec != EventCodec::Binary {
+            return Err(Error::DeserializationError(
+                "zero-copy view requires a binary frame".to_string()
+            ));
+        }
+
+        Self::with_archived(body, f)
+    }
+
+    /// Render any frame as pretty JSON (debug tooling)
+    pub fn to_debug_json(frame: &[u8]) -> Result<String> {
+        let event = Self::decode(frame)?;
+        serde_json::to_string_pretty(&event)
+            .map_err(|e| Error::SerializationError(e.to_string()))
+    }
+
+    fn split_frame(frame: &[u8]) -> Result<(EventCodec, &[u8])> {
+        if frame.len() < HEADER_LEN || frame[0..2] != FRAME_MAGIC {
+            return Err(Error::DeserializationError("invalid event frame header".to_string()));
+        }
+
+        if frame[2] > FRAME_VERSION {
+            return Err(Error::UnsupportedEventVersion {
+                event_version: frame[2] as u32,
+                max_supported: FRAME_VERSION as u32,
+            });
+        }
+
+        Ok((Self::from_id(frame[3])?, &frame[HEADER_LEN..]))
+    }
+
+    fn with_archived<R>(body: &[u8], f: impl FnOnce(&ArchivedBaseEvent) -> R) -> Result<R> {
+        // Zero-copy when the broker buffer is aligned (the common case)
+        if (body.as_ptr() as usize).is_multiple_of(BODY_ALIGN) {
+            let archived = rkyv::check_archived_root::<BaseEvent>(body)
+                .map_err(|e| Error::DeserializationError(e.to_string()))?;
+            return Ok(f(archived));
+        }
+
+        let mut aligned = AlignedVec::with_capacity(body.len());
+        aligned.extend_from_slice(body);
+        let archived = rkyv::check_archived_root::<BaseEvent>(&aligned)
+            .map_err(|e| Error::DeserializationError(e.to_string()))?;
+        Ok(f(archived))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::base::EventType;
+    use crate::types::ids::MarketId;
+
+    fn event() -> BaseEvent {
+        let mut event = BaseEvent::new(EventType::OrderSubmit, MarketId::new());
+        event.sequence = 42;
+        event
+    }
+
+    #[test]
+    fn every_codec_and_legacy_bincode_decode_to_the_event() {
+        let event = event();
+        for frame in [
+            EventCodec::Binary.encode(&event).unwrap(),
+            EventCodec::Json.encode(&event).unwrap(),
+            bincode::serialize(&event).unwrap(),  // Logged before framing
+        ] {
+            let decoded = EventCodec::decode(&frame).unwrap();
+            assert_eq!((decoded.event_id, decoded.sequence), (event.event_id, 42));
+        }
+    }
+}
@@ -1,18 +1,61 @@
 use crate::error::{Error, Result};
+use crate::event_log::codec::EventCodec;
 use crate::events::base::BaseEvent;
-use rdkafka::config::ClientConfig;
-use rdkafka::consumer::{Consumer, StreamConsumer};
+use crate::config::loader::KafkaSecurityConfig;
+use crate::event_log::kafka_client::client_config;
+use crate::interfaces::event_source::EventSource;
+use rdkafka::consumer::{BaseConsumer, Consumer, StreamConsumer};
 use rdkafka::message::Message;
+use rdkafka::{Offset, TopicPartitionList};
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Sequences remembered for re-seeking (recovery seeks to the event after
+/// the last one processed, so only the recent tail is ever needed)
+const TRACKED_OFFSETS: usize = 100_000;
 
 pub struct EventConsumer {
     consumer: StreamConsumer,
     topic: String,
+    offsets: Mutex<SequenceOffsets>,
+}
+
+/// Partition offsets of the events delivered so far, by sequence
+///
+/// Sequences and offsets both grow along the log but are not the same
+/// numbers (the log starts at sequence 1, retention moves the first offset),
+/// so a seek by sequence goes through the offsets actually seen.
+#[derive(Debug, Default)]
+struct SequenceOffsets {
+    by_sequence: BTreeMap<u64, i64>,
+    next_offset: i64,  // Offset after the last delivered event
+}
+
+impl SequenceOffsets {
+    fn record(&mut self, sequence: u64, offset: i64) {
+        self.by_sequence.insert(sequence, offset);
+        self.next_offset = offset + 1;
+        if self.by_sequence.len() > TRACKED_OFFSETS {
+            self.by_sequence.pop_first();
+        }
+    }
+
+    /// Offset holding `sequence`, or the next offset for the sequence after the last delivered
+    fn offset_of(&self, sequence: u64) -> Result<i64> {
+        if let Some(offset) = self.by_sequence.get(&sequence) {
+            return Ok(*offset);
+        }
+        match self.by_sequence.last_key_value() {
+            Some((last, _)) if *last + 1 == sequence => Ok(self.next_offset),
+            _ => Err(Error::UnknownLogSequence(sequence)),
+        }
+    }
 }
 
 impl EventConsumer {
-    pub fn new(brokers: &str, topic: &str, group_id: &str) -> Result<Self> {
-        let consumer: StreamConsumer = ClientConfig::new()
-            .set("bootstrap.servers", brokers)
+    pub fn new(brokers: &str, topic: &str, group_id: &str, security: &KafkaSecurityConfig) -> Result<Self> {
+        let consumer: StreamConsumer = client_config(brokers, security)
             .set("group.id", group_id)
             .set("enable.auto.commit", "false")
             .set("auto.offset.reset", "earliest")
@@ -25,9 +68,55 @@ impl EventConsumer {
         Ok(EventConsumer {
             consumer,
             topic: topic.to_string(),
+            offsets: Mutex::new(SequenceOffsets::default()),
         })
     }
 
+    /// Sequence of the last event in the log, None when it is empty
+    /// Read with a short-lived consumer, so no consumer's position moves
+    pub fn last_logged_sequence(brokers: &str, topic: &str, security: &KafkaSecurityConfig) -> Result<Option<u64>> {
+        let consumer: BaseConsumer = client_config(brokers, security)
+            .set("enable.auto.commit", "false")
+            .create()
+            .map_err(|e| Error::KafkaError(e.to_string()))?;
+
+        let (low, high) = consumer.fetch_watermarks(topic, 0, Duration::from_secs(5))
+            .map_err(|e| Error::KafkaError(e.to_string()))?;
+        if high <= low {
+            return Ok(None);
+        }
+
+        let mut assignment = TopicPartitionList::new();
+        assignment.add_partition_offset(topic, 0, Offset::Offset(high - 1))
+            .map_err(|e| Error::KafkaError(e.to_string()))?;
+        consumer.assign(&assignment)
+            .map_err(|e| Error::KafkaError(e.to_string()))?;
+
+        match consumer.poll(Duration::from_secs(5)) {
+            Some(Ok(message)) => {
+                let payload = message.payload().ok_or(Error::EmptyPayload)?;
+                Ok(Some(EventCodec::decode(payload)?.sequence))
+            }
+            Some(Err(e)) => Err(Error::KafkaError(e.to_string())),
+            None => Err(Error::KafkaError("timed out reading the last event".to_string())),
+        }
+    }
+
+    /// First and one-past-last offsets of the log
+    pub fn watermarks(&self) -> Result<(i64, i64)> {
+        self.consumer.fetch_watermarks(&self.topic, 0, Duration::from_secs(5))
+            .map_err(|e| Error::KafkaError(e.to_string()))
+    }
+
+    /// Offset after the last delivered event (0 before the first)
+    pub fn next_offset(&self) -> i64 {
+        self.offsets.lock().expect("offset index poisoned").next_offset
+    }
+
+    fn record_offset(&self, sequence: u64, offset: i64) {
+        self.offsets.lock().expect("offset index poisoned").record(sequence, offset);
+    }
+
     pub async fn fetch_event(&self, sequence: u64) -> Result<BaseEvent> {
         // In a real implementation, this would:
         // 1. Seek to the specific offset/sequence
@@ -40,8 +129,9 @@ impl EventConsumer {
                 let payload = message.payload()
                     .ok_or(Error::EmptyPayload)?;
 
-                let event: BaseEvent = bincode::deserialize(payload)
-                    .map_err(|e| Error::DeserializationError(e.to_string()))?;
+                // Validated once, whatever the codec
+                let event = EventCodec::decode(payload)?;
+                self.record_offset(event.sequence, message.offset());
 
                 // Verify sequence matches
                 if event.sequence != sequence {
@@ -63,8 +153,8 @@ impl EventConsumer {
                 let payload = message.payload()
                     .ok_or(Error::EmptyPayload)?;
 
-                let event: BaseEvent = bincode::deserialize(payload)
-                    .map_err(|e| Error::DeserializationError(e.to_string()))?;
+                let event = EventCodec::decode(payload)?;
+                self.record_offset(event.sequence, message.offset());
 
                 Ok(event)
             }
@@ -85,4 +175,36 @@ impl EventConsumer {
 
         Ok(events)
     }
-}
\ No newline at end of file
+}
+
+impl EventSource for EventConsumer {
+    /// Position the consumer so the next event read is `sequence`: one
+    /// already delivered, or the one after the last delivered
+    fn seek_to_sequence(&self, sequence: u64) -> Result<()> {
+        let offset = self.offsets.lock().expect("offset index poisoned").offset_of(sequence)?;
+        self.consumer.seek(&self.topic, 0, Offset::Offset(offset), Duration::from_secs(5))
+            .map_err(|e| Error::KafkaError(e.to_string()))?;
+
+        tracing::info!("Event consumer re-seeked to sequence {} (offset {})", sequence, offset);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seeks_go_to_the_offset_the_sequence_was_read_from() {
+        let mut offsets = SequenceOffsets::default();
+        // Sequences start at 1 on offset 40 (earlier offsets expired)
+        for (sequence, offset) in [(1, 40), (2, 41), (3, 43)] {
+            offsets.record(sequence, offset);
+        }
+
+        assert_eq!(offsets.offset_of(2).unwrap(), 41);
+        assert_eq!(offsets.offset_of(3).unwrap(), 43);
+        assert_eq!(offsets.offset_of(4).unwrap(), 44);  // The next event
+        assert!(matches!(offsets.offset_of(9), Err(Error::UnknownLogSequence(9))));
+    }
+}
@@ -0,0 +1,40 @@
+use rdkafka::config::ClientConfig;
+use crate::config::loader::KafkaSecurityConfig;
+
+/// Base librdkafka config for every client: brokers plus TLS/SASL settings
+/// Callers add their client-specific keys before `create()`
+pub fn client_config(brokers: &str, security: &KafkaSecurityConfig) -> ClientConfig {
+    let mut config = ClientConfig::new();
+    config
+        .set("bootstrap.servers", brokers)
+        .set("security.protocol", security.protocol.as_str());
+
+    if security.protocol.uses_sasl() {
+        if let Some(mechanism) = &security.sasl_mechanism {
+            config.set("sasl.mechanism", mechanism);
+        }
+        if let Some(username) = &security.sasl_username {
+            config.set("sasl.username", username);
+        }
+        if let Some(password) = security.sasl_password() {
+            config.set("sasl.password", password);
+        }
+    }
+
+    if security.protocol.uses_tls() {
+        if let Some(ca) = &security.ca_location {
+            config.set("ssl.ca.location", ca);
+        }
+        if let Some(certificate) = &security.certificate_location {
+            config.set("ssl.certificate.location", certificate);
+        }
+        if let Some(key) = &security.key_location {
+            config.set("ssl.key.location", key);
+        }
+        if let Some(key_password) = security.key_password() {
+            config.set("ssl.key.password", key_password);
+        }
+    }
+
+    config
+}
@@ -1,4 +1,8 @@
 pub mod snapshot;
 pub mod producer;
 pub mod consumer;
-pub mod snapshot_manager;
\ No newline at end of file
+pub mod snapshot_manager;
+pub mod retention_manager;
+pub mod archive;
+pub mod codec;
+pub mod kafka_client;
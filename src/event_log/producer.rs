@@ -1,22 +1,31 @@
+use crate::event_log::codec::EventCodec;
 use crate::events::base::BaseEvent;
 use crate::error::{Error, Result};
 use crate::interfaces::event_producer::EventProducer;
 use rdkafka::producer::{FutureProducer, FutureRecord};
-use rdkafka::config::ClientConfig;
+use crate::config::loader::KafkaSecurityConfig;
+use crate::event_log::kafka_client::client_config;
 use async_trait::async_trait;
 use std::time::Duration;
+use tokio::sync::Mutex;
 
+/// Appends events to the log, assigning their sequence numbers
+///
+/// The next sequence is held under a lock until the broker acknowledges the
+/// write, so log order is sequence order and a failed write leaves no gap.
+/// Start it after the last logged sequence (`with_next_sequence`); a fresh
+/// log starts at 1.
 pub struct KafkaEventProducer {
     producer: FutureProducer,
     topic: String,
-    sequence_counter: std::sync::atomic::AtomicU64,
+    next_sequence: Mutex<u64>,
     max_retries: u32,
+    codec: EventCodec,
 }
 
 impl KafkaEventProducer {
-    pub fn new(brokers: &str, topic: &str) -> Result<Self> {
-        let producer: FutureProducer = ClientConfig::new()
-            .set("bootstrap.servers", brokers)
+    pub fn new(brokers: &str, topic: &str, security: &KafkaSecurityConfig) -> Result<Self> {
+        let producer: FutureProducer = client_config(brokers, security)
             .set("message.timeout.ms", "5000")
             .set("compression.type", "lz4")
             .set("retries", "10")  // Kafka client-level retries
@@ -27,11 +36,24 @@ impl KafkaEventProducer {
         Ok(KafkaEventProducer {
             producer,
             topic: topic.to_string(),
-            sequence_counter: std::sync::atomic::AtomicU64::new(0),
+            next_sequence: Mutex::new(1),
             max_retries: 5,
+            codec: EventCodec::default(),
         })
     }
 
+    /// Continue an existing log: the next event produced takes `sequence`
+    pub fn with_next_sequence(mut self, sequence: u64) -> Self {
+        self.next_sequence = Mutex::new(sequence);
+        self
+    }
+
+    /// Override the wire codec (JSON for debugging; consumers detect it per frame)
+    pub fn with_codec(mut self, codec: EventCodec) -> Self {
+        self.codec = codec;
+        self
+    }
+
     /// Retry with exponential backoff
     /// Per docs/architecture/event-model.md Section 11.1
     async fn produce_with_retry(&self, key: &str, payload: &[u8]) -> Result<()> {
@@ -73,19 +95,21 @@ impl KafkaEventProducer {
 #[async_trait]
 impl EventProducer for KafkaEventProducer {
     async fn produce(&self, mut event: BaseEvent) -> Result<u64> {
-        // Assign sequence number
-        let sequence = self.sequence_counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        // Assign sequence number; the checksum covers it
+        let mut next_sequence = self.next_sequence.lock().await;
+        let sequence = *next_sequence;
         event.sequence = sequence;
+        event.checksum = event.calculate_checksum();
 
         // Serialize event
-        let payload = bincode::serialize(&event)
-            .map_err(|e| Error::SerializationError(e.to_string()))?;
+        let payload = self.codec.encode(&event)?;
 
         let key = sequence.to_string();
 
         // Send to Kafka
         self.produce_with_retry(&key, &payload).await?;
 
+        *next_sequence += 1;
         Ok(sequence)
     }
 }
\ No newline at end of file
@@ -0,0 +1,365 @@
+use std::collections::BTreeMap;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use rdkafka::admin::{AdminClient, AdminOptions, AlterConfig, ResourceSpecifier};
+use rdkafka::client::DefaultClientContext;
+use crate::config::EventLogRetentionConfig;
+use crate::config::loader::KafkaSecurityConfig;
+use crate::error::{Error, Result};
+use crate::event_log::kafka_client::client_config;
+use crate::events::base::BaseEvent;
+use crate::observability::metrics::{EVENT_LOG_ARCHIVED_SEQUENCE, EVENT_LOG_REQUIRED_RETENTION};
+use crate::types::timestamp::Timestamp;
+
+/// Event-log retention coordinator - keeps the broker, the archive and the
+/// snapshot set consistent so every required replay stays possible
+///
+/// ## Replay Floor
+/// - **Required snapshot**: Newest snapshot older than `compliance_window`
+///   (or the oldest snapshot if none is that old)
+/// - Events after the required snapshot must live on the broker or in the archive
+/// - Events before it are no longer needed for recovery or compliance
+///
+/// ## Broker Retention
+/// - Broker must keep events from `max(required snapshot, archived_through + 1)`
+/// - `required_broker_retention()` converts that point into a `retention.ms`
+/// - `apply_topic_retention()` pushes it to the topic via the Kafka admin API
+///
+/// ## Archival
+/// - `buffer_event()` collects processed events; every `segment_events` they
+///   are written by `archive_segment()` to
+///   `archive_dir/events_{first}_{last}.bin` (bincode) before the broker drops them
+/// - Buffered events are also appended to `archive_dir/pending.bin`, so a
+///   restart picks the segment up where it stopped instead of leaving a gap
+///
+/// ## Startup
+/// - `load_archive()` recovers `archived_through` from the segment files and
+///   the buffered events from `pending.bin`
+/// - Saved snapshots are fed to `record_snapshot()` before the first new one
+pub struct EventLogRetentionManager {
+    config: EventLogRetentionConfig,
+    snapshots: BTreeMap<u64, Timestamp>,
+    archived_through: Option<(u64, Timestamp)>,
+    pending: Vec<BaseEvent>,  // Processed, not yet in a segment
+}
+
+const PENDING_FILE: &str = "pending.bin";
+
+impl EventLogRetentionManager {
+    pub fn new(config: EventLogRetentionConfig) -> Self {
+        EventLogRetentionManager {
+            config,
+            snapshots: BTreeMap::new(),
+            archived_through: None,
+            pending: Vec::new(),
+        }
+    }
+
+    fn compliance_window(&self) -> Duration {
+        Duration::from_secs(self.config.compliance_window_secs)
+    }
+
+    fn archive_dir(&self) -> Option<PathBuf> {
+        self.config.archive_dir.as_ref().map(PathBuf::from)
+    }
+
+    /// Recover the archive position and the unarchived buffer after a restart
+    pub fn load_archive(&mut self) -> Result<()> {
+        let archive_dir = match self.archive_dir() {
+            Some(dir) => dir,
+            None => return Ok(()),
+        };
+        let entries = match std::fs::read_dir(&archive_dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),  // Nothing archived yet
+            Err(e) => return Err(Error::IoError(e)),
+        };
+
+        // Newest segment: the one ending at the highest sequence
+        let newest = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter_map(|path| Self::segment_end(&path).map(|last| (last, path)))
+            .max_by_key(|(last, _)| *last);
+        if let Some((_, path)) = newest {
+            let data = std::fs::read(&path).map_err(Error::IoError)?;
+            let events: Vec<BaseEvent> = bincode::deserialize(&data)
+                .map_err(|e| Error::DeserializationError(e.to_string()))?;
+            if let Some(last) = events.last() {
+                self.archived_through = Some((last.sequence, last.timestamp));
+                EVENT_LOG_ARCHIVED_SEQUENCE.set(last.sequence as i64);
+            }
+        }
+
+        self.pending.clear();
+        for event in Self::read_pending(&archive_dir.join(PENDING_FILE))? {
+            let nothing_known = self.pending.is_empty() && self.archived_through.is_none();
+            if nothing_known || event.sequence == self.next_to_archive() {
+                self.pending.push(event);
+            }
+        }
+
+        tracing::info!(
+            "Event archive loaded: archived through {:?}, {} events buffered",
+            self.archived_through(), self.pending.len()
+        );
+        Ok(())
+    }
+
+    /// Last sequence of an `events_{first}_{last}.bin` segment
+    fn segment_end(path: &Path) -> Option<u64> {
+        path.file_name()?
+            .to_str()?
+            .strip_prefix("events_")?
+            .strip_suffix(".bin")?
+            .split('_')
+            .nth(1)?
+            .parse()
+            .ok()
+    }
+
+    /// Length-prefixed events; a torn last frame (crash mid-write) is dropped
+    fn read_pending(path: &Path) -> Result<Vec<BaseEvent>> {
+        let mut data = Vec::new();
+        match std::fs::File::open(path) {
+            Ok(mut file) => file.read_to_end(&mut data).map_err(Error::IoError)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(Error::IoError(e)),
+        };
+
+        let mut events = Vec::new();
+        let mut rest = data.as_slice();
+        while rest.len() >= 4 {
+            let len = u32::from_le_bytes([rest[0], rest[1], rest[2], rest[3]]) as usize;
+            let Some(frame) = rest.get(4..4 + len) else { break };
+            match bincode::deserialize::<BaseEvent>(frame) {
+                Ok(event) => events.push(event),
+                Err(_) => break,
+            }
+            rest = &rest[4 + len..];
+        }
+        Ok(events)
+    }
+
+    /// Sequence the next buffered event must have
+    fn next_to_archive(&self) -> u64 {
+        match (self.pending.last(), self.archived_through) {
+            (Some(last), _) => last.sequence + 1,
+            (None, Some((archived_seq, _))) => archived_seq + 1,
+            (None, None) => 1,
+        }
+    }
+
+    /// Buffer a processed event for archival, writing a segment once
+    /// `segment_events` are buffered. Events already archived or buffered
+    /// (replayed after a restart) are ignored.
+    pub fn buffer_event(&mut self, event: &BaseEvent) -> Result<()> {
+        let archive_dir = match self.archive_dir() {
+            Some(dir) => dir,
+            None => return Ok(()),
+        };
+        let expected = self.next_to_archive();
+        if event.sequence < expected {
+            return Ok(());
+        }
+        if event.sequence > expected && (self.archived_through.is_some() || !self.pending.is_empty()) {
+            return Err(Error::SequenceGap { expected, actual: event.sequence });
+        }
+
+        std::fs::create_dir_all(&archive_dir).map_err(Error::IoError)?;
+        let frame = bincode::serialize(event)
+            .map_err(|e| Error::SerializationError(e.to_string()))?;
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(archive_dir.join(PENDING_FILE))
+            .map_err(Error::IoError)?;
+        file.write_all(&(frame.len() as u32).to_le_bytes()).map_err(Error::IoError)?;
+        file.write_all(&frame).map_err(Error::IoError)?;
+        self.pending.push(event.clone());
+
+        if self.pending.len() >= self.config.segment_events.max(1) {
+            let segment = std::mem::take(&mut self.pending);
+            if let Err(e) = self.archive_segment(&segment) {
+                self.pending = segment;  // Retried with the next event
+                return Err(e);
+            }
+            std::fs::File::create(archive_dir.join(PENDING_FILE)).map_err(Error::IoError)?;
+        }
+        Ok(())
+    }
+
+    /// Track a snapshot that was saved successfully
+    /// Snapshots older than the required one are dropped from tracking
+    pub fn record_snapshot(&mut self, sequence: u64, timestamp: Timestamp) {
+        self.snapshots.insert(sequence, timestamp);
+
+        if let Some(floor) = self.snapshot_floor() {
+            self.snapshots = self.snapshots.split_off(&floor);
+        }
+    }
+
+    /// Stop tracking a snapshot that was deleted
+    pub fn forget_snapshot(&mut self, sequence: u64) {
+        self.snapshots.remove(&sequence);
+    }
+
+    /// Oldest snapshot still needed for recovery/compliance replay
+    pub fn required_snapshot(&self) -> Option<(u64, Timestamp)> {
+        let now = Timestamp::now();
+
+        self.snapshots.iter()
+            .rev()
+            .find(|(_, ts)| now - **ts >= self.compliance_window())
+            .or_else(|| self.snapshots.iter().next())
+            .map(|(seq, ts)| (*seq, *ts))
+    }
+
+    /// Snapshots at or after this sequence must not be deleted
+    pub fn snapshot_floor(&self) -> Option<u64> {
+        self.required_snapshot().map(|(seq, _)| seq)
+    }
+
+    /// First sequence (and its approximate time) the broker must still hold
+    pub fn broker_floor(&self) -> Option<(u64, Timestamp)> {
+        let required = self.required_snapshot()?;
+
+        match self.archived_through {
+            Some((archived_seq, archived_ts)) if archived_seq >= required.0 => {
+                Some((archived_seq + 1, archived_ts))
+            }
+            _ => Some(required),
+        }
+    }
+
+    /// Broker retention needed so nothing unarchived and required is deleted
+    /// None until the first snapshot exists (keep broker defaults until then)
+    pub fn required_broker_retention(&self) -> Option<Duration> {
+        let (_, floor_ts) = self.broker_floor()?;
+        let retention = (Timestamp::now() - floor_ts) + Duration::from_secs(self.config.safety_margin_secs);
+
+        EVENT_LOG_REQUIRED_RETENTION.set(retention.as_secs() as i64);
+        Some(retention)
+    }
+
+    /// Archive a contiguous range of events before the broker deletes them
+    pub fn archive_segment(&mut self, events: &[BaseEvent]) -> Result<()> {
+        let archive_dir = match self.archive_dir() {
+            Some(dir) => dir,
+            None => return Ok(()),
+        };
+
+        let (first, last) = match (events.first(), events.last()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return Ok(()),
+        };
+
+        // Segments must extend the archive without gaps
+        if let Some((archived_seq, _)) = self.archived_through
+            && first.sequence != archived_seq + 1
+        {
+            return Err(Error::SequenceGap {
+                expected: archived_seq + 1,
+                actual: first.sequence,
+            });
+        }
+
+        std::fs::create_dir_all(&archive_dir).map_err(Error::IoError)?;
+
+        let data = bincode::serialize(events)
+            .map_err(|e| Error::SerializationError(e.to_string()))?;
+
+        let path = archive_dir.join(format!("events_{}_{}.bin", first.sequence, last.sequence));
+        std::fs::write(&path, data).map_err(Error::IoError)?;
+
+        self.archived_through = Some((last.sequence, last.timestamp));
+        EVENT_LOG_ARCHIVED_SEQUENCE.set(last.sequence as i64);
+
+        tracing::info!("Archived events {}..={} to {:?}", first.sequence, last.sequence, path);
+        Ok(())
+    }
+
+    pub fn archived_through(&self) -> Option<u64> {
+        self.archived_through.map(|(seq, _)| seq)
+    }
+
+    /// Set `retention.ms` on the event topic to the required retention
+    pub async fn apply_topic_retention(
+        &self,
+        brokers: &str,
+        topic: &str,
+        security: &KafkaSecurityConfig,
+    ) -> Result<()> {
+        let retention = match self.required_broker_retention() {
+            Some(retention) => retention,
+            None => {
+                tracing::debug!("No snapshots tracked yet, leaving topic retention unchanged");
+                return Ok(());
+            }
+        };
+
+        let admin: AdminClient<DefaultClientContext> = client_config(brokers, security)
+            .create()
+            .map_err(|e| Error::KafkaError(e.to_string()))?;
+
+        let retention_ms = retention.as_millis().to_string();
+        let alter = AlterConfig::new(ResourceSpecifier::Topic(topic))
+            .set("retention.ms", &retention_ms);
+
+        let results = admin.alter_configs(&[alter], &AdminOptions::new())
+            .await
+            .map_err(|e| Error::KafkaError(e.to_string()))?;
+
+        for result in results {
+            result.map_err(|(_, code)| Error::KafkaError(code.to_string()))?;
+        }
+
+        tracing::info!("Set retention.ms={} on topic {}", retention_ms, topic);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::base::EventType;
+    use crate::types::ids::MarketId;
+
+    fn manager(archive_dir: &Path, segment_events: usize) -> EventLogRetentionManager {
+        EventLogRetentionManager::new(EventLogRetentionConfig {
+            archive_dir: Some(archive_dir.to_string_lossy().into_owned()),
+            segment_events,
+            ..EventLogRetentionConfig::default()
+        })
+    }
+
+    fn event(sequence: u64) -> BaseEvent {
+        let mut event = BaseEvent::new(EventType::OrderSubmit, MarketId::new());
+        event.sequence = sequence;
+        event
+    }
+
+    #[test]
+    fn a_restart_resumes_the_archive_and_its_buffered_events() {
+        let dir = std::env::temp_dir().join(format!("event-archive-{}", uuid::Uuid::new_v4()));
+        let mut before = manager(&dir, 3);
+        for sequence in 1..=5 {
+            before.buffer_event(&event(sequence)).unwrap();
+        }
+        assert_eq!(before.archived_through(), Some(3));
+
+        let mut after = manager(&dir, 3);
+        after.load_archive().unwrap();
+        assert_eq!(after.archived_through(), Some(3));
+
+        // 4 and 5 were buffered before the restart; replaying them changes nothing
+        for sequence in 4..=6 {
+            after.buffer_event(&event(sequence)).unwrap();
+        }
+        assert_eq!(after.archived_through(), Some(6));
+        let segment: Vec<BaseEvent> = bincode::deserialize(&std::fs::read(dir.join("events_4_6.bin")).unwrap()).unwrap();
+        assert_eq!(segment.iter().map(|e| e.sequence).collect::<Vec<_>>(), vec![4, 5, 6]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,4 +1,12 @@
-use crate::types::ids::MarketId;
+use crate::config::risk::UserLimits;
+use crate::controls::HaltControl;
+use crate::funding::index::CumulativeFunding;
+use crate::liquidation::socialized_loss::SettlementPeriodState;
+use crate::matching::order_book::Order;
+use crate::matching::trigger_engine::TriggerEngineState;
+use crate::matching::trigger_monitor::PendingStop;
+use crate::settlement::in_flight_fills::InFlightFill;
+use crate::types::ids::{MarketId, TradeId, UserId};
 use crate::types::position::Position;
 use crate::types::price::Price;
 use crate::types::timestamp::Timestamp;
@@ -14,8 +22,19 @@ pub struct Snapshot {
     pub market_id: MarketId,
     pub accounts: Vec<Account>,
     pub positions: Vec<Position>,
+    pub open_orders: Vec<Order>,    // Resting orders in book priority order
+    pub pending_stops: Vec<PendingStop>,     // Untriggered stop orders, oldest first
+    pub trailing_stops: TriggerEngineState,  // Untriggered and fired trailing stops
+    pub lp_makers: Vec<UserId>,              // Makers on the LP fee rate
+    pub leverage: Vec<(UserId, f64)>,  // Chosen leverage per account
+    pub limit_overrides: Vec<(UserId, UserLimits)>,  // Operator exposure caps per account
+    pub cumulative_funding: CumulativeFunding,
     pub mark_price: Price,
     pub index_price: Price,
+    pub settled_trades: Vec<TradeId>,  // Recently settled trade ids, oldest first
+    pub in_flight_fills: Vec<(TradeId, [InFlightFill; 2])>,  // Logged trades not yet settled
+    pub halt: HaltControl,             // Processor halt state as of `sequence`
+    pub settlement_period: SettlementPeriodState,  // Socialized loss period and its baselines
     pub checksum: String,
 }
 
@@ -25,8 +44,19 @@ impl Snapshot {
         market_id: MarketId,
         accounts: Vec<Account>,
         positions: Vec<Position>,
+        open_orders: Vec<Order>,
+        pending_stops: Vec<PendingStop>,
+        trailing_stops: TriggerEngineState,
+        lp_makers: Vec<UserId>,
+        leverage: Vec<(UserId, f64)>,
+        limit_overrides: Vec<(UserId, UserLimits)>,
+        cumulative_funding: CumulativeFunding,
         mark_price: Price,
         index_price: Price,
+        settled_trades: Vec<TradeId>,
+        in_flight_fills: Vec<(TradeId, [InFlightFill; 2])>,
+        halt: HaltControl,
+        settlement_period: SettlementPeriodState,
     ) -> Self {
         let mut snapshot = Snapshot {
             version: crate::SNAPSHOT_VERSION,
@@ -35,8 +65,19 @@ impl Snapshot {
             market_id,
             accounts,
             positions,
+            open_orders,
+            pending_stops,
+            trailing_stops,
+            lp_makers,
+            leverage,
+            limit_overrides,
+            cumulative_funding,
             mark_price,
             index_price,
+            settled_trades,
+            in_flight_fills,
+            halt,
+            settlement_period,
             checksum: String::new(),
         };
 
@@ -59,6 +100,56 @@ impl Snapshot {
             hasher.update(position.size.to_le_bytes());
         }
 
+        for order in &self.open_orders {
+            hasher.update(order.order_id.0.as_bytes());
+            hasher.update(order.price.to_i64().to_le_bytes());
+            hasher.update((order.quantity - order.filled).to_i64().to_le_bytes());
+        }
+
+        for stop in &self.pending_stops {
+            hasher.update(stop.order.order_id.0.as_bytes());
+            hasher.update(stop.trigger_price.to_i64().to_le_bytes());
+        }
+
+        for stop in &self.trailing_stops.pending {
+            hasher.update(stop.order.order_id.0.as_bytes());
+            hasher.update(stop.best_mark.to_i64().to_le_bytes());
+        }
+
+        for order_id in self.trailing_stops.fired.iter().chain(&self.trailing_stops.submitted) {
+            hasher.update(order_id.0.as_bytes());
+        }
+
+        for maker in &self.lp_makers {
+            hasher.update(maker.0.as_bytes());
+        }
+
+        for trade_id in &self.settled_trades {
+            hasher.update(trade_id.0.as_bytes());
+        }
+
+        for (trade_id, fills) in &self.in_flight_fills {
+            hasher.update(trade_id.0.as_bytes());
+            for fill in fills {
+                hasher.update(fill.user_id.0.as_bytes());
+                hasher.update(fill.delta.to_le_bytes());
+            }
+        }
+
+        if let Some(halt) = self.halt.state() {
+            hasher.update(halt.halted_at.physical.to_le_bytes());
+            hasher.update(halt.halted_at.logical.to_le_bytes());
+        }
+
+        if let Some(started_at) = self.settlement_period.started_at {
+            hasher.update(started_at.physical.to_le_bytes());
+            hasher.update(started_at.logical.to_le_bytes());
+        }
+        for (user_id, baseline) in &self.settlement_period.baselines {
+            hasher.update(user_id.0.as_bytes());
+            hasher.update(baseline.to_i64().to_le_bytes());
+        }
+
         let result = hasher.finalize();
         hex::encode(result)
     }
@@ -1,10 +1,13 @@
 use std::path::{Path, PathBuf};
+use std::time::Duration;
+use crate::config::ArchivalConfig;
 use crate::error::{Error, Result};
+use crate::event_log::archive;
 use crate::event_log::snapshot::Snapshot;
-use crate::settlement::balance_manager::BalanceManager;
+use crate::observability::metrics::SNAPSHOTS_ARCHIVED;
 use crate::types::ids::MarketId;
-use crate::types::position::Position;
-use crate::types::price::Price;
+use crate::types::timestamp::Timestamp;
+use std::sync::atomic::{AtomicU64, Ordering};
 use tokio::fs as async_fs;
 
 /// Snapshot Manager - Handles creation, persistence, and restoration of system state snapshots
@@ -17,9 +20,15 @@ use tokio::fs as async_fs;
 /// - **Naming Convention**: `snapshot_{market_id}_{sequence}.bin`
 ///
 /// ## Retention Policy
-/// - **Max Snapshots**: 100 per market (configurable via `max_snapshots`)
+/// - **Max Snapshots**: 100 per market (configurable via `with_max_snapshots`);
+///   the newest snapshot is never deleted
 /// - **Cleanup Strategy**: FIFO - oldest snapshots deleted when limit exceeded
 /// - **Cleanup Trigger**: After each successful snapshot save
+/// - **Retention Floor**: Snapshots at or after `set_retention_floor()` are never
+///   deleted (set by EventLogRetentionManager so compliance replays stay possible)
+/// - **Archival**: With `with_archive_dir`, pruned snapshots are first exported
+///   (lz4) to `archive_dir/{market_id}/`; a failed export keeps the snapshot.
+///   `prune_archive()` removes exports past the archive retention
 ///
 /// ## Atomicity Guarantees
 /// - **Write**: Atomic file write using `tokio::fs::write` (writes to temp file, then renames)
@@ -30,6 +39,9 @@ use tokio::fs as async_fs;
 /// - **Startup**: Attempts to load latest snapshot via `load_latest()`
 /// - **No Snapshot**: Returns `Error::NoSnapshotFound`, system starts from beginning
 /// - **Corrupted Snapshot**: Returns `Error::InvalidChecksum`, falls back to previous snapshot
+/// - **Other Version**: The `version` prefix is read before the body; a snapshot
+///   not written at `SNAPSHOT_VERSION` returns `Error::UnsupportedSnapshotVersion`
+///   (layouts are not migrated: move it aside and replay from the event log)
 /// - **Missing Sequence**: Can load specific sequence via `load_snapshot_at_sequence()`
 ///
 /// ## Disk Space Management
@@ -40,6 +52,9 @@ use tokio::fs as async_fs;
 pub struct SnapshotManager {
     snapshot_dir: PathBuf,
     max_snapshots: usize,
+    retention_floor: AtomicU64,  // u64::MAX = no floor
+    archive_dir: Option<PathBuf>,
+    newest_timestamp: AtomicU64,  // Physical ms of the newest snapshot, 0 = none
 }
 
 impl SnapshotManager {
@@ -47,40 +62,50 @@ impl SnapshotManager {
         SnapshotManager {
             snapshot_dir: snapshot_dir.as_ref().to_path_buf(),
             max_snapshots: 100,
+            retention_floor: AtomicU64::new(u64::MAX),
+            archive_dir: None,
+            newest_timestamp: AtomicU64::new(0),
         }
     }
 
-    /// Create a snapshot from current system state
-    pub fn create_snapshot(
-        &self,
-        sequence: u64,
-        market_id: MarketId,
-        balance_manager: &BalanceManager,
-        positions: &[Position],
-        mark_price: Price,
-        index_price: Price,
-    ) -> Result<Snapshot> {
-        // Collect all accounts
-        let accounts: Vec<_> = balance_manager.accounts.values().cloned().collect();
+    pub fn from_config(config: &ArchivalConfig) -> Self {
+        let manager = SnapshotManager::new(&config.snapshot_dir)
+            .with_max_snapshots(config.max_snapshots);
 
-        // Create snapshot
-        let snapshot = Snapshot::new(
-            sequence,
-            market_id,
-            accounts,
-            positions.to_vec(),
-            mark_price,
-            index_price,
-        );
+        if config.archive_snapshots {
+            manager.with_archive_dir(&config.archive_dir)
+        } else {
+            manager
+        }
+    }
 
-        tracing::info!(
-            "Created snapshot at sequence {} with {} accounts and {} positions",
-            sequence,
-            snapshot.accounts.len(),
-            snapshot.positions.len()
-        );
+    /// Snapshots kept on disk per market (at least one)
+    pub fn with_max_snapshots(mut self, max_snapshots: usize) -> Self {
+        self.max_snapshots = max_snapshots.max(1);
+        self
+    }
 
-        Ok(snapshot)
+    /// Export pruned snapshots here instead of discarding them
+    pub fn with_archive_dir(mut self, archive_dir: impl AsRef<Path>) -> Self {
+        self.archive_dir = Some(archive_dir.as_ref().to_path_buf());
+        self
+    }
+
+    /// Time of the newest snapshot saved or loaded by this manager
+    pub fn newest_snapshot_time(&self) -> Option<Timestamp> {
+        match self.newest_timestamp.load(Ordering::SeqCst) {
+            0 => None,
+            millis => Some(Timestamp::from_millis(millis)),
+        }
+    }
+
+    fn record_newest(&self, snapshot: &Snapshot) {
+        self.newest_timestamp.fetch_max(snapshot.timestamp.physical, Ordering::SeqCst);
+    }
+
+    /// Protect snapshots at or after `sequence` from FIFO cleanup
+    pub fn set_retention_floor(&self, sequence: u64) {
+        self.retention_floor.store(sequence, Ordering::SeqCst);
     }
 
     /// Save snapshot to disk
@@ -108,6 +133,7 @@ impl SnapshotManager {
             .map_err(|e| Error::IoError(e))?;
 
         tracing::info!("Saved snapshot to {:?}", filepath);
+        self.record_newest(snapshot);
 
         // Cleanup old snapshots
         self.cleanup_old_snapshots(snapshot.market_id).await?;
@@ -125,7 +151,9 @@ impl SnapshotManager {
 
         // Get latest snapshot (highest sequence)
         let latest = snapshots.last().unwrap();
-        self.load_snapshot(latest).await
+        let snapshot = self.load_snapshot(latest).await?;
+        self.record_newest(&snapshot);
+        Ok(snapshot)
     }
 
     /// Load a specific snapshot by sequence
@@ -146,6 +174,13 @@ impl SnapshotManager {
             .await
             .map_err(|e| Error::IoError(e))?;
 
+        // `version` leads the encoding, so it decodes whatever the rest of the layout is
+        let version: u32 = bincode::deserialize(&data)
+            .map_err(|e| Error::DeserializationError(e.to_string()))?;
+        if version != crate::SNAPSHOT_VERSION {
+            return Err(Error::UnsupportedSnapshotVersion { version, expected: crate::SNAPSHOT_VERSION });
+        }
+
         let snapshot: Snapshot = bincode::deserialize(&data)
             .map_err(|e| Error::DeserializationError(e.to_string()))?;
 
@@ -158,13 +193,33 @@ impl SnapshotManager {
         Ok(snapshot)
     }
 
+    /// Sequence and timestamp of each loadable snapshot on disk (oldest first),
+    /// read from the file headers so retention can be seeded at startup
+    pub async fn saved_snapshots(&self, market_id: MarketId) -> Result<Vec<(u64, Timestamp)>> {
+        let mut saved = Vec::new();
+        for path in self.list_snapshots(market_id).await? {
+            let data = async_fs::read(&path)
+                .await
+                .map_err(Error::IoError)?;
+            // `version`, `sequence` and `timestamp` lead the encoding
+            let (version, sequence, timestamp): (u32, u64, Timestamp) = bincode::deserialize(&data)
+                .map_err(|e| Error::DeserializationError(e.to_string()))?;
+            if version == crate::SNAPSHOT_VERSION {
+                saved.push((sequence, timestamp));
+            }
+        }
+        Ok(saved)
+    }
+
     /// List all snapshots for a market (sorted by sequence)
     async fn list_snapshots(&self, market_id: MarketId) -> Result<Vec<PathBuf>> {
         let mut snapshots = Vec::new();
 
-        let mut entries = async_fs::read_dir(&self.snapshot_dir)
-            .await
-            .map_err(|e| Error::IoError(e))?;
+        let mut entries = match async_fs::read_dir(&self.snapshot_dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(snapshots),  // Nothing saved yet
+            Err(e) => return Err(Error::IoError(e)),
+        };
 
         while let Some(entry) = entries.next_entry()
             .await
@@ -179,18 +234,20 @@ impl SnapshotManager {
         }
 
         // Sort by sequence number (extracted from filename)
-        snapshots.sort_by_key(|path| {
-            path.file_name()
-                .and_then(|n| n.to_str())
-                .and_then(|s| s.split('_').nth(2))
-                .and_then(|s| s.strip_suffix(".bin"))
-                .and_then(|s| s.parse::<u64>().ok())
-                .unwrap_or(0)
-        });
+        snapshots.sort_by_key(|path| Self::sequence_of(path));
 
         Ok(snapshots)
     }
 
+    fn sequence_of(path: &Path) -> u64 {
+        path.file_name()
+            .and_then(|n| n.to_str())
+            .and_then(|s| s.split('_').nth(2))
+            .and_then(|s| s.strip_suffix(".bin"))
+            .and_then(|s| s.parse::<u64>().ok())
+            .unwrap_or(0)
+    }
+
     /// Cleanup old snapshots, keeping only the most recent N
     async fn cleanup_old_snapshots(&self, market_id: MarketId) -> Result<()> {
         let snapshots = self.list_snapshots(market_id).await?;
@@ -199,9 +256,20 @@ impl SnapshotManager {
             return Ok(());
         }
 
-        // Delete oldest snapshots
+        // Delete oldest snapshots, stopping at the retention floor
+        let retention_floor = self.retention_floor.load(Ordering::SeqCst);
         let to_delete = snapshots.len() - self.max_snapshots;
         for snapshot_path in snapshots.iter().take(to_delete) {
+            if Self::sequence_of(snapshot_path) >= retention_floor {
+                tracing::info!("Keeping snapshot {:?}: required by retention floor", snapshot_path);
+                break;
+            }
+
+            if let Err(e) = self.archive_snapshot(market_id, snapshot_path).await {
+                tracing::warn!("Keeping snapshot {:?}: archival failed: {:?}", snapshot_path, e);
+                break;
+            }
+
             async_fs::remove_file(snapshot_path)
                 .await
                 .map_err(|e| Error::IoError(e))?;
@@ -211,4 +279,109 @@ impl SnapshotManager {
 
         Ok(())
     }
-}
\ No newline at end of file
+
+    /// Export a snapshot file to the archive before it is pruned
+    async fn archive_snapshot(&self, market_id: MarketId, snapshot_path: &Path) -> Result<()> {
+        let archive_dir = match &self.archive_dir {
+            Some(dir) => dir.join(market_id.to_string()),
+            None => return Ok(()),
+        };
+
+        let data = async_fs::read(snapshot_path)
+            .await
+            .map_err(Error::IoError)?;
+
+        // Decode to name the export after the snapshot's own sequence and time
+        let snapshot: Snapshot = bincode::deserialize(&data)
+            .map_err(|e| Error::DeserializationError(e.to_string()))?;
+
+        let path = archive_dir.join(archive::file_name("snapshot", snapshot.sequence, snapshot.timestamp, "bin"));
+        archive::write_file(&path, &data)?;
+
+        SNAPSHOTS_ARCHIVED.inc();
+        tracing::info!("Archived snapshot {} to {:?}", snapshot.sequence, path);
+        Ok(())
+    }
+
+    /// Remove archived snapshots older than `retention`
+    pub fn prune_archive(&self, market_id: MarketId, retention: Option<Duration>, now: Timestamp) -> Result<usize> {
+        let (archive_dir, newest) = match (&self.archive_dir, self.newest_snapshot_time()) {
+            (Some(dir), Some(newest)) => (dir.join(market_id.to_string()), newest),
+            _ => return Ok(0),
+        };
+
+        archive::prune_expired(&archive_dir, "snapshot", retention, newest, now)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::controls::HaltControl;
+    use crate::liquidation::socialized_loss::SettlementPeriodState;
+    use crate::matching::trigger_engine::TriggerEngineState;
+    use crate::funding::index::CumulativeFunding;
+    use crate::types::price::Price;
+
+    fn scratch_dir() -> PathBuf {
+        std::env::temp_dir().join(format!("snapshots-{}", uuid::Uuid::new_v4()))
+    }
+
+    fn snapshot(sequence: u64, market_id: MarketId) -> Snapshot {
+        Snapshot::new(
+            sequence,
+            market_id,
+            Vec::new(),
+            Vec::new(),
+            Vec::new(),
+            Vec::new(),
+            TriggerEngineState::default(),
+            Vec::new(),
+            Vec::new(),
+            Vec::new(),
+            CumulativeFunding::new(),
+            Price::zero(),
+            Price::zero(),
+            Vec::new(),
+            Vec::new(),
+            HaltControl::default(),
+            SettlementPeriodState::default(),
+        )
+    }
+
+    #[tokio::test]
+    async fn latest_snapshot_round_trips() {
+        let dir = scratch_dir();
+        let manager = SnapshotManager::new(&dir);
+        let market_id = MarketId::new();
+        manager.save_snapshot(&snapshot(7, market_id)).await.unwrap();
+
+        let loaded = manager.load_latest(market_id).await.unwrap();
+        assert_eq!((loaded.version, loaded.sequence), (crate::SNAPSHOT_VERSION, 7));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn snapshot_of_another_version_is_refused() {
+        let dir = scratch_dir();
+        let manager = SnapshotManager::new(&dir);
+        let market_id = MarketId::new();
+        let mut old = snapshot(7, market_id);
+        old.version = crate::SNAPSHOT_VERSION - 1;
+        manager.save_snapshot(&old).await.unwrap();
+
+        match manager.load_latest(market_id).await {
+            Err(Error::UnsupportedSnapshotVersion { version, expected }) => {
+                assert_eq!((version, expected), (crate::SNAPSHOT_VERSION - 1, crate::SNAPSHOT_VERSION));
+            }
+            other => panic!("expected a version error, got {:?}", other.map(|s| s.version)),
+        }
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn missing_directory_means_no_snapshot() {
+        let manager = SnapshotManager::new(scratch_dir());
+        assert!(matches!(manager.load_latest(MarketId::new()).await, Err(Error::NoSnapshotFound)));
+    }
+}
@@ -1,9 +1,11 @@
 use crate::events::base::BaseEvent;
 use crate::types::balance::Balance;
 use crate::types::ids::UserId;
+use crate::types::position::PositionSide;
 use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
 pub struct BalanceUpdate {
     pub base: BaseEvent,
     pub user_id: UserId,
@@ -12,8 +14,53 @@ pub struct BalanceUpdate {
     pub reference_id: Option<String>,  // External transaction ID
 }
 
-#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
 pub enum BalanceUpdateType {
     Deposit,
     Withdrawal,
-}
\ No newline at end of file
+}
+/// Move collateral between the account and an isolated position's bucket
+#[derive(Clone, Debug, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
+pub struct IsolatedMarginTransfer {
+    pub base: BaseEvent,
+    pub user_id: UserId,
+    pub position_side: PositionSide,
+    pub amount: Balance,  // Positive adds to the bucket, negative removes
+}
+
+/// A master account opens a named sub-account with its own balance,
+/// positions and API keys
+#[derive(Clone, Debug, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
+pub struct SubAccountCreated {
+    pub base: BaseEvent,
+    pub master_id: UserId,
+    pub sub_account_id: UserId,  // Assigned by the gateway
+    pub label: String,
+}
+
+/// Move funds between a master account and its sub-accounts, or between
+/// two of its sub-accounts, on the master's request
+#[derive(Clone, Debug, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
+pub struct SubAccountTransfer {
+    pub base: BaseEvent,
+    pub master_id: UserId,
+    pub from_user: UserId,
+    pub to_user: UserId,
+    pub amount: Balance,
+}
+
+/// Move funds from one account to another, posted as a paired debit and
+/// credit in the ledger
+#[derive(Clone, Debug, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
+pub struct Transfer {
+    pub base: BaseEvent,
+    pub from_user: UserId,
+    pub to_user: UserId,
+    pub amount: Balance,
+    pub reference_id: Option<String>,  // Client reference; the event ID when absent
+}
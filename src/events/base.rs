@@ -5,7 +5,8 @@ use crate::error::{Error, Result};
 use crate::types::ids::{EventId, MarketId, UserId};
 use crate::types::timestamp::Timestamp;
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
 pub struct BaseEvent {
     pub event_id: EventId,
     pub event_type: EventType,
@@ -63,7 +64,8 @@ impl BaseEvent {
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
 pub struct CorrelationId(pub Uuid);
 
 impl CorrelationId {
@@ -78,7 +80,8 @@ impl CorrelationId {
     }
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
 pub struct EventMetadata {
     pub source: String,
     pub user_id: Option<UserId>,
@@ -95,31 +98,104 @@ impl Default for EventMetadata {
     }
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
+#[archive(bound(serialize = "__S: rkyv::ser::ScratchSpace + rkyv::ser::Serializer"))]
+#[archive_attr(check_bytes(bound = "__C: rkyv::validation::ArchiveContext, <__C as rkyv::Fallible>::Error: rkyv::bytecheck::Error"))]
 pub enum EventPayload {
     Empty,
-    OrderSubmit(Box<crate::events::order::OrderSubmit>),
-    OrderCancel(Box<crate::events::order::OrderCancel>),
-    Trade(Box<crate::events::trade::TradeEvent>),
-    PriceSnapshot(Box<crate::events::price::PriceSnapshot>),
-    Funding(Box<crate::events::funding::FundingEvent>),
-    Liquidation(Box<crate::events::liquidation::LiquidationTriggered>),
-    BalanceUpdate(Box<crate::events::balance::BalanceUpdate>),
+    OrderSubmit(#[omit_bounds] #[archive_attr(omit_bounds)] Box<crate::events::order::OrderSubmit>),
+    OrderCancel(#[omit_bounds] #[archive_attr(omit_bounds)] Box<crate::events::order::OrderCancel>),
+    OrderAmend(#[omit_bounds] #[archive_attr(omit_bounds)] Box<crate::events::order::OrderAmend>),
+    OrderAmended(#[omit_bounds] #[archive_attr(omit_bounds)] Box<crate::events::order::OrderAmended>),
+    OrderRejected(#[omit_bounds] #[archive_attr(omit_bounds)] Box<crate::events::order::OrderRejected>),
+    Trade(#[omit_bounds] #[archive_attr(omit_bounds)] Box<crate::events::trade::TradeEvent>),
+    PriceSnapshot(#[omit_bounds] #[archive_attr(omit_bounds)] Box<crate::events::price::PriceSnapshot>),
+    Funding(#[omit_bounds] #[archive_attr(omit_bounds)] Box<crate::events::funding::FundingEvent>),
+    Liquidation(#[omit_bounds] #[archive_attr(omit_bounds)] Box<crate::events::liquidation::LiquidationTriggered>),
+    BalanceUpdate(#[omit_bounds] #[archive_attr(omit_bounds)] Box<crate::events::balance::BalanceUpdate>),
+    Genesis(#[omit_bounds] #[archive_attr(omit_bounds)] Box<crate::events::genesis::GenesisEvent>),
+    DailyRecord(#[omit_bounds] #[archive_attr(omit_bounds)] Box<crate::events::daily_record::DailyRecord>),
+    ProcessorHalted(#[omit_bounds] #[archive_attr(omit_bounds)] Box<crate::events::control::ProcessorHalted>),
+    ProcessorResumed(#[omit_bounds] #[archive_attr(omit_bounds)] Box<crate::events::control::ProcessorResumed>),
+    StateRepair(#[omit_bounds] #[archive_attr(omit_bounds)] Box<crate::events::repair::StateRepair>),
+    SelfTradePrevented(#[omit_bounds] #[archive_attr(omit_bounds)] Box<crate::events::order::SelfTradePrevented>),
+    CancelAllOrders(#[omit_bounds] #[archive_attr(omit_bounds)] Box<crate::events::order::CancelAllOrders>),
+    AllOrdersCancelled(#[omit_bounds] #[archive_attr(omit_bounds)] Box<crate::events::order::AllOrdersCancelled>),
+    PositionModeChange(#[omit_bounds] #[archive_attr(omit_bounds)] Box<crate::events::order::PositionModeChange>),
+    ExpireOrders(#[omit_bounds] #[archive_attr(omit_bounds)] Box<crate::events::order::ExpireOrders>),
+    OrderExpired(#[omit_bounds] #[archive_attr(omit_bounds)] Box<crate::events::order::OrderExpired>),
+    ExecutionReport(#[omit_bounds] #[archive_attr(omit_bounds)] Box<crate::events::trade::ExecutionReport>),
+    CrossedBook(#[omit_bounds] #[archive_attr(omit_bounds)] Box<crate::events::book::CrossedBook>),
+    TradingPhaseChanged(#[omit_bounds] #[archive_attr(omit_bounds)] Box<crate::events::control::TradingPhaseChanged>),
+    ExpirySettlement(#[omit_bounds] #[archive_attr(omit_bounds)] Box<crate::events::expiry::ExpirySettlement>),
+    MarginModeChange(#[omit_bounds] #[archive_attr(omit_bounds)] Box<crate::events::order::MarginModeChange>),
+    IsolatedMarginTransfer(#[omit_bounds] #[archive_attr(omit_bounds)] Box<crate::events::balance::IsolatedMarginTransfer>),
+    SetLeverage(#[omit_bounds] #[archive_attr(omit_bounds)] Box<crate::events::order::SetLeverage>),
+    UserLimitsSet(#[omit_bounds] #[archive_attr(omit_bounds)] Box<crate::events::control::UserLimitsSet>),
+    FundingRateClamped(#[omit_bounds] #[archive_attr(omit_bounds)] Box<crate::events::funding::FundingRateClamped>),
+    LiquidationStarted(#[omit_bounds] #[archive_attr(omit_bounds)] Box<crate::events::liquidation::LiquidationStarted>),
+    LiquidationFillReceived(#[omit_bounds] #[archive_attr(omit_bounds)] Box<crate::events::liquidation::LiquidationFillReceived>),
+    LiquidationCompleted(#[omit_bounds] #[archive_attr(omit_bounds)] Box<crate::events::liquidation::LiquidationCompleted>),
+    LiquidationFailed(#[omit_bounds] #[archive_attr(omit_bounds)] Box<crate::events::liquidation::LiquidationFailed>),
+    MarginCallWarning(#[omit_bounds] #[archive_attr(omit_bounds)] Box<crate::events::liquidation::MarginCallWarning>),
+    SocializedLoss(#[omit_bounds] #[archive_attr(omit_bounds)] Box<crate::events::liquidation::SocializedLoss>),
+    SubAccountCreated(#[omit_bounds] #[archive_attr(omit_bounds)] Box<crate::events::balance::SubAccountCreated>),
+    SubAccountTransfer(#[omit_bounds] #[archive_attr(omit_bounds)] Box<crate::events::balance::SubAccountTransfer>),
+    Transfer(#[omit_bounds] #[archive_attr(omit_bounds)] Box<crate::events::balance::Transfer>),
+    RequestRejected(#[omit_bounds] #[archive_attr(omit_bounds)] Box<crate::events::order::RequestRejected>),
+    ProcessorHaltAcknowledged(#[omit_bounds] #[archive_attr(omit_bounds)] Box<crate::events::control::ProcessorHaltAcknowledged>),
+    LpMakersDesignated(#[omit_bounds] #[archive_attr(omit_bounds)] Box<crate::events::control::LpMakersDesignated>),
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
 pub enum EventType {
     OrderSubmit,
     OrderCancel,
     OrderAmend,
+    OrderAmended,
     OrderAccepted,
     OrderRejected,
     Trade,
     PriceSnapshot,
     Funding,
+    FundingCatchUp,
     Liquidation,
     BalanceUpdate,
     InvariantViolation,
     KillSwitchActivated,
+    ProcessorHalted,
+    ProcessorResumed,
+    StateRepair,
     CircuitBreakerTriggered,
+    Genesis,
+    DailyRecord,
+    SelfTradePrevented,
+    CancelAllOrders,
+    AllOrdersCancelled,
+    PositionModeChange,
+    ExpireOrders,
+    OrderExpired,
+    ExecutionReport,
+    CrossedBook,
+    TradingPhaseChanged,
+    ExpirySettlement,
+    MarginModeChange,
+    IsolatedMarginTransfer,
+    SetLeverage,
+    UserLimitsSet,
+    FundingRateClamped,
+    LiquidationStarted,
+    LiquidationFillReceived,
+    LiquidationCompleted,
+    LiquidationFailed,
+    MarginCallWarning,
+    SocializedLoss,
+    SubAccountCreated,
+    SubAccountTransfer,
+    Transfer,
+    RequestRejected,
+    ProcessorHaltAcknowledged,
+    LpMakersDesignated,
 }
\ No newline at end of file
@@ -0,0 +1,26 @@
+use serde::{Deserialize, Serialize};
+use crate::events::base::BaseEvent;
+use crate::types::price::Price;
+
+/// What the processor does when it finds the book crossed or locked
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
+#[serde(rename_all = "snake_case")]
+pub enum CrossedBookAction {
+    /// Match the crossing resting orders against each other
+    #[default]
+    Uncross,
+    /// Halt the processor for an operator to investigate
+    Halt,
+}
+
+/// The book was found with best_bid >= best_ask after a mutation
+#[derive(Clone, Debug, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
+pub struct CrossedBook {
+    pub base: BaseEvent,
+    pub best_bid: Price,
+    pub best_ask: Price,
+    pub locked: bool,  // best_bid == best_ask
+    pub action: CrossedBookAction,
+}
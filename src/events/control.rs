@@ -0,0 +1,104 @@
+use serde::{Deserialize, Serialize};
+use crate::config::risk::UserLimits;
+use crate::events::base::BaseEvent;
+use crate::types::ids::{OperatorId, UserId};
+use crate::types::timestamp::Timestamp;
+
+/// Why the event processor stopped
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
+pub enum HaltReason {
+    SequenceGap { expected: u64, actual: u64 },
+    InvariantViolation { details: String },
+    Operator { note: String },  // Manual halt from the admin API
+    MatchingCorePanic,          // A matching core command panicked; the book needs restoring
+}
+
+impl HaltReason {
+    /// Automatic halts need an operator to confirm the cause before resuming
+    pub fn requires_acknowledgement(&self) -> bool {
+        !matches!(self, HaltReason::Operator { .. })
+    }
+}
+
+/// Durable record of an event processor halt
+#[derive(Clone, Debug, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
+pub struct ProcessorHalted {
+    pub base: BaseEvent,
+    pub reason: HaltReason,
+    pub halted_at: Timestamp,
+    pub halted_by: Option<OperatorId>,  // None = automatic halt
+}
+
+/// Durable record of an operator confirming an automatic halt's cause was investigated
+#[derive(Clone, Debug, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
+pub struct ProcessorHaltAcknowledged {
+    pub base: BaseEvent,
+    pub halted_at: Timestamp,  // The halt being acknowledged
+    pub acknowledged_by: OperatorId,
+}
+
+/// Durable record of an event processor resume
+#[derive(Clone, Debug, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
+pub struct ProcessorResumed {
+    pub base: BaseEvent,
+    pub reason: HaltReason,             // The halt being cleared
+    pub halted_at: Timestamp,
+    pub acknowledged_by: Option<OperatorId>,
+    pub resumed_by: OperatorId,
+}
+
+/// Which new orders the market accepts; cancels are always accepted
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
+#[serde(rename_all = "snake_case")]
+pub enum TradingPhase {
+    #[default]
+    Open,
+    PostOnly,    // Only post-only orders; amends are allowed
+    CancelOnly,  // No new orders or amends
+}
+
+impl TradingPhase {
+    pub fn admits(&self, post_only: bool) -> bool {
+        match self {
+            TradingPhase::Open => true,
+            TradingPhase::PostOnly => post_only,
+            TradingPhase::CancelOnly => false,
+        }
+    }
+}
+
+/// Durable record of a trading phase change (e.g. warm-up after a restart)
+#[derive(Clone, Debug, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
+pub struct TradingPhaseChanged {
+    pub base: BaseEvent,
+    pub phase: TradingPhase,
+    pub reason: String,
+}
+
+/// Operator override of one account's exposure caps (empty limits clear it)
+#[derive(Clone, Debug, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
+pub struct UserLimitsSet {
+    pub base: BaseEvent,
+    pub operator_id: OperatorId,
+    pub user_id: UserId,
+    pub limits: UserLimits,
+    pub reason: String,
+}
+
+/// Makers qualified for the LP maker fee rate from `period_end` on, from the
+/// LP program's scorecards for the period just closed (replaces the previous set)
+#[derive(Clone, Debug, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
+pub struct LpMakersDesignated {
+    pub base: BaseEvent,
+    pub makers: Vec<UserId>,  // Sorted by user id
+    pub period_start: Timestamp,
+    pub period_end: Timestamp,
+}
@@ -0,0 +1,28 @@
+use serde::{Deserialize, Serialize};
+use crate::events::base::BaseEvent;
+use crate::types::balance::Balance;
+use crate::types::ids::UserId;
+use crate::types::price::Price;
+use crate::types::timestamp::Timestamp;
+
+/// End-of-day statement of record for one account
+/// Immutable once published; used for user statements and dispute resolution,
+/// independent of operational snapshots (which are pruned)
+#[derive(Clone, Debug, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
+pub struct DailyRecord {
+    pub base: BaseEvent,
+    pub business_date: String,       // YYYY-MM-DD (UTC) of the day ending at `cutoff`
+    pub cutoff: Timestamp,
+    pub as_of_sequence: u64,         // Last event applied to the recorded state
+    pub user_id: UserId,
+    pub balance: Balance,
+    pub reserved_margin: Balance,
+    pub position_size: i64,
+    pub entry_price: Price,
+    pub mark_price: Price,
+    pub cumulative_realized_pnl: Balance,
+    pub daily_realized_pnl: Balance,
+    pub unrealized_pnl: Balance,
+    pub daily_unrealized_pnl: Balance,  // Change in unrealized PnL since the previous cutoff
+}
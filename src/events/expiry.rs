@@ -0,0 +1,16 @@
+use serde::{Deserialize, Serialize};
+use crate::events::base::BaseEvent;
+use crate::types::price::Price;
+use crate::types::timestamp::Timestamp;
+
+/// Final settlement of a dated future
+/// Produced once after expiry; the processor cancels resting orders and
+/// closes every position at `settlement_price`
+#[derive(Clone, Debug, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
+pub struct ExpirySettlement {
+    pub base: BaseEvent,
+    pub expires_at: Timestamp,
+    pub settlement_price: Price,  // Index TWAP over the settlement window
+    pub twap_samples: u32,        // 0: no sample in the window, last index price before it was used
+}
@@ -2,11 +2,13 @@ use serde::{Deserialize, Serialize};
 use crate::events::base::BaseEvent;
 use crate::types::balance::Balance;
 use crate::types::funding_rate::FundingRate;
-use crate::types::ids::UserId;
+use crate::types::ids::{EventId, UserId};
 use crate::types::price::Price;
 use crate::types::quantity::Quantity;
+use crate::types::timestamp::Timestamp;
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
 pub struct FundingEvent {
     pub base: BaseEvent,
     pub funding_rate: FundingRate,
@@ -14,10 +16,102 @@ pub struct FundingEvent {
     pub index_price: Price,
     pub premium: Price,
     pub funding_interval: std::time::Duration,
-    pub payments: Vec<FundingPayment>,
+    pub payments: Vec<FundingPayment>,  // Empty as published: filled in by the processor from its positions
+    #[serde(default = "Balance::zero")]
+    pub residual: Balance,  // Rounding residue taken (+) or paid (-) by the funding residual account (set with payments)
+    #[serde(default = "Balance::zero")]
+    pub index_delta: Balance,  // Advance of the cumulative funding index (what one long contract pays)
+    pub catch_up: Option<FundingCatchUp>,  // Set when settling an interval missed during downtime
+    #[serde(default)]
+    pub components: Option<FundingRateComponents>,  // How funding_rate was derived (None: fallback, zero rate)
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+/// Decomposition of a funding rate, published for transparency
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
+pub struct FundingRateComponents {
+    pub premium_index: FundingRate,        // Premium / index
+    pub interest_rate: FundingRate,        // Configured rate per interval
+    pub interest_adjustment: FundingRate,  // clamp(interest_rate - premium_index)
+    pub unclamped_rate: FundingRate,       // premium_index + interest_adjustment
+    pub funding_rate: FundingRate,         // Clamped to the market's min/max rate
+}
+
+impl FundingEvent {
+    /// End of the interval this event settles: the missed boundary for a
+    /// catch-up, otherwise the interval boundary nearest the event's timestamp
+    pub fn interval_end(&self) -> Timestamp {
+        if let Some(catch_up) = &self.catch_up {
+            return catch_up.interval_end;
+        }
+        let interval_ms = self.funding_interval.as_millis() as u64;
+        if interval_ms == 0 {
+            return self.base.timestamp;
+        }
+        let at = self.base.timestamp.physical;
+        Timestamp::from_millis((at + interval_ms / 2) / interval_ms * interval_ms)
+    }
+}
+
+impl FundingRateComponents {
+    /// Which bound clamped the rate, if one did
+    pub fn clamped_by(&self) -> Option<FundingRateBound> {
+        if self.unclamped_rate > self.funding_rate {
+            Some(FundingRateBound::Cap)
+        } else if self.unclamped_rate < self.funding_rate {
+            Some(FundingRateBound::Floor)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
+#[serde(rename_all = "snake_case")]
+pub enum FundingRateBound {
+    Cap,    // max_funding_rate
+    Floor,  // min_funding_rate
+}
+
+impl FundingRateBound {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FundingRateBound::Cap => "cap",
+            FundingRateBound::Floor => "floor",
+        }
+    }
+}
+
+/// A funding interval settled at a clamped rate: the computed rate was
+/// outside the market's bounds
+#[derive(Clone, Debug, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
+pub struct FundingRateClamped {
+    pub base: BaseEvent,
+    pub funding_event_id: EventId,  // FundingEvent settled at the clamped rate
+    pub unclamped_rate: FundingRate,
+    pub funding_rate: FundingRate,
+    pub bound: FundingRateBound,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
+pub struct FundingCatchUp {
+    pub interval_start: Timestamp,
+    pub interval_end: Timestamp,
+    pub premium_source: PremiumSource,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
+pub enum PremiumSource {
+    RecordedTwap { samples: u32 },  // TWAP of premium samples recorded during the interval
+    Fallback,                       // No samples recorded: settled at zero premium
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
 pub struct FundingPayment {
     pub user_id: UserId,
     pub position_size: Quantity,
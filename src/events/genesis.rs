@@ -0,0 +1,23 @@
+use crate::events::base::BaseEvent;
+use crate::matching::order_book::Order;
+use crate::types::account::Account;
+use crate::types::position::Position;
+use serde::{Deserialize, Serialize};
+
+/// Seeds state in a fresh deployment from an account export
+/// Replaces the normal deposit/trade history that produced the state
+#[derive(Clone, Debug, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
+pub struct GenesisEvent {
+    pub base: BaseEvent,
+    pub export_version: u32,
+    pub record: GenesisRecord,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
+pub enum GenesisRecord {
+    Account(Account),
+    Position(Position),
+    Order(Order),
+}
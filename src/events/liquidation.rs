@@ -1,12 +1,15 @@
 use serde::{Deserialize, Serialize};
 use crate::events::base::BaseEvent;
 use crate::types::balance::Balance;
-use crate::types::ids::{LiquidationId, UserId};
+use crate::types::ids::{LiquidationId, TradeId, UserId};
+use crate::types::position::PositionSide;
 use crate::types::price::Price;
 use crate::types::quantity::Quantity;
 use crate::types::ratio::Ratio;
+use crate::types::timestamp::Timestamp;
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
 pub struct LiquidationTriggered {
     pub base: BaseEvent,
     pub user_id: UserId,
@@ -14,6 +17,8 @@ pub struct LiquidationTriggered {
     pub mark_price: Price,
     pub maintenance_margin: Balance,
     pub account_value: Balance,
+    #[serde(default)]
+    pub position_side: PositionSide,  // Leg to liquidate (hedge mode)
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -26,12 +31,139 @@ pub struct LiquidationEvent {
     pub liquidation_price: Price,
     pub margin_ratio: Ratio,
     pub maintenance_margin: Balance,
-    pub insurance_fund_loss: Balance,
+    pub insurance_fund_loss: Balance,  // Deficit the insurance fund covered
+    #[serde(default = "Balance::zero")]
+    pub unbacked_loss: Balance,        // Deficit beyond the fund, socialized across winners
+    #[serde(default = "Balance::zero")]
+    pub penalty: Balance,  // Charged to the account and paid into the insurance fund
     pub liquidation_type: LiquidationType,
+    #[serde(default)]
+    pub slice: u32,                // 1-based child order number within the liquidation
+    #[serde(default = "Quantity::zero")]
+    pub remaining_size: Quantity,  // Still to close in later slices (zero when done)
+    #[serde(default)]
+    pub execution: LiquidationExecution,
+}
+
+/// How the liquidated size left the account
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LiquidationExecution {
+    /// Closed against the book by IOC orders laddered away from mark
+    #[default]
+    Book,
+    /// Nothing filled out to the backstop: the position moved to the
+    /// liquidation engine (insurance fund) at the bankruptcy price
+    InsuranceTakeover,
 }
 
 #[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub enum LiquidationType {
     Partial,
     Full,
-}
\ No newline at end of file
+}
+/// The executor sent the first child order of a liquidation
+#[derive(Clone, Debug, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
+pub struct LiquidationStarted {
+    pub base: BaseEvent,
+    pub liquidation_id: LiquidationId,
+    pub user_id: UserId,
+    pub position_side: PositionSide,
+    pub position_size: Quantity,
+    pub target_size: Quantity,  // Size to close (partial or full)
+    pub mark_price: Price,
+    pub bankruptcy_price: Option<Price>,
+    pub margin_ratio: Ratio,
+    pub maintenance_margin: Balance,
+}
+
+/// One fill of a liquidation's child order
+#[derive(Clone, Debug, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
+pub struct LiquidationFillReceived {
+    pub base: BaseEvent,
+    pub liquidation_id: LiquidationId,
+    pub user_id: UserId,
+    pub trade_id: TradeId,
+    pub price: Price,
+    pub quantity: Quantity,
+    pub slice: u32,
+}
+
+/// A liquidation is over; nothing more is sent for it
+#[derive(Clone, Debug, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
+pub struct LiquidationCompleted {
+    pub base: BaseEvent,
+    pub liquidation_id: LiquidationId,
+    pub user_id: UserId,
+    pub outcome: LiquidationOutcome,
+    pub slices: u32,
+    pub unfilled_size: Quantity,  // Left of the target (non-zero once margin is restored)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
+#[serde(rename_all = "snake_case")]
+pub enum LiquidationOutcome {
+    Closed,             // Target size filled on the book
+    MarginRestored,     // Account healthy again before the target was reached
+    InsuranceTakeover,  // Remainder moved to the insurance fund at bankruptcy price
+}
+
+/// A liquidation step failed
+#[derive(Clone, Debug, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
+pub struct LiquidationFailed {
+    pub base: BaseEvent,
+    pub liquidation_id: LiquidationId,
+    pub user_id: UserId,
+    pub reason: LiquidationFailureReason,
+    pub slice: u32,
+    pub will_retry: bool,  // The liquidation stays active for its next step
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
+#[serde(rename_all = "snake_case")]
+pub enum LiquidationFailureReason {
+    NoLiquidity,            // Nothing filled out to the backstop, and no bankruptcy price for a takeover
+    InsuranceFundDepleted,  // The fund could not cover the account's deficit
+}
+
+/// A bankrupt account's deficit beyond the insurance fund, clawed back
+/// from the settlement period's winners pro rata to their realized PnL
+#[derive(Clone, Debug, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
+pub struct SocializedLoss {
+    pub base: BaseEvent,
+    pub liquidation_id: LiquidationId,
+    pub user_id: UserId,                   // Bankrupt account the haircuts are credited to
+    pub deficit: Balance,                  // Unbacked loss to socialize
+    pub period_start: Timestamp,           // Start of the settlement period the winners are taken from
+    pub haircuts: Vec<SocializedLossHaircut>,
+    pub unbacked: Balance,                 // Left over once every winner is exhausted
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
+pub struct SocializedLossHaircut {
+    pub user_id: UserId,
+    pub period_pnl: Balance,  // Realized PnL in the period
+    pub amount: Balance,      // Debited from the account
+}
+
+/// An account (or isolated leg) is close to liquidation: its margin ratio
+/// is under the margin call ratio
+#[derive(Clone, Debug, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
+pub struct MarginCallWarning {
+    pub base: BaseEvent,
+    pub user_id: UserId,
+    pub position_side: Option<PositionSide>,  // Isolated leg; None for the account's cross legs
+    pub margin_ratio: Ratio,                  // Equity / maintenance margin
+    pub equity: Balance,
+    pub maintenance_margin: Balance,
+    pub mark_price: Price,
+}
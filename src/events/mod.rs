@@ -4,4 +4,10 @@ pub mod trade;
 pub mod price;
 pub mod funding;
 pub mod liquidation;
-pub mod balance;
\ No newline at end of file
+pub mod balance;
+pub mod genesis;
+pub mod daily_record;
+pub mod control;
+pub mod repair;
+pub mod book;
+pub mod expiry;
\ No newline at end of file
@@ -1,9 +1,14 @@
 use serde::{Deserialize, Serialize};
-use crate::events::base::BaseEvent;
-use crate::types::ids::{OrderId, UserId};
+use crate::error::Error;
+use crate::events::base::{BaseEvent, EventType};
+use crate::events::control::TradingPhase;
+use crate::types::balance::Balance;
+use crate::types::ids::{EventId, OperatorId, OrderId, UserId};
+use crate::types::position::{MarginMode, PositionMode, PositionSide};
 use crate::types::price::Price;
 use crate::types::quantity::Quantity;
 use crate::types::ratio::Ratio;
+use crate::types::timestamp::Timestamp;
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum OrderEvent {
@@ -12,7 +17,8 @@ pub enum OrderEvent {
     Amend(OrderAmend),
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
 pub struct OrderSubmit {
     pub base: BaseEvent,
     pub order_id: OrderId,
@@ -25,22 +31,119 @@ pub struct OrderSubmit {
     pub reduce_only: bool,
     pub post_only: bool,
     pub slippage_limit: Option<Ratio>,  // For market orders
+    #[serde(default)]
+    pub trigger_price: Option<Price>,   // For stop orders
+    #[serde(default)]
+    pub trailing_offset: Option<TrailingOffset>,  // For trailing stops
+    #[serde(default)]
+    pub self_trade_prevention: Option<SelfTradePrevention>,  // API-key override of the account/market mode
+    #[serde(default)]
+    pub position_side: PositionSide,  // Leg traded in hedge mode (Both in one-way mode)
+    #[serde(default)]
+    pub min_fill_quantity: Option<Quantity>,  // IOC: rejected unless at least this much can fill
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
 pub struct OrderCancel {
     pub base: BaseEvent,
     pub order_id: OrderId,
     pub user_id: UserId,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+/// Cancel every resting order of one user, or of the whole market ("panic button")
+#[derive(Clone, Debug, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
+pub struct CancelAllOrders {
+    pub base: BaseEvent,
+    pub user_id: Option<UserId>,          // None: every user in the market
+    pub operator_id: Option<OperatorId>,  // Required for market-wide cancels
+    pub reason: String,
+}
+
+/// Switch an account between one-way and hedge mode (refused unless flat)
+#[derive(Clone, Debug, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
+pub struct PositionModeChange {
+    pub base: BaseEvent,
+    pub user_id: UserId,
+    pub mode: PositionMode,
+}
+
+/// Switch an account between cross and isolated margin (refused unless flat)
+#[derive(Clone, Debug, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
+pub struct MarginModeChange {
+    pub base: BaseEvent,
+    pub user_id: UserId,
+    pub mode: MarginMode,
+}
+
+/// Choose the account's leverage in this market (refused above the tier cap
+/// or when open orders can't cover the higher margin)
+#[derive(Clone, Debug, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
+pub struct SetLeverage {
+    pub base: BaseEvent,
+    pub user_id: UserId,
+    pub leverage: f64,
+}
+
+/// Sweep resting GTD orders whose expiry is at or before the event timestamp
+#[derive(Clone, Debug, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
+pub struct ExpireOrders {
+    pub base: BaseEvent,
+}
+
+/// A GTD order reached its expiry and left the book
+#[derive(Clone, Debug, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
+pub struct OrderExpired {
+    pub base: BaseEvent,
+    pub order_id: OrderId,
+    pub user_id: UserId,
+    pub expires_at: Timestamp,
+    pub unfilled_quantity: Quantity,
+    pub released_margin: Balance,
+}
+
+/// Summary of a CancelAllOrders
+#[derive(Clone, Debug, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
+pub struct AllOrdersCancelled {
+    pub base: BaseEvent,
+    pub user_id: Option<UserId>,
+    pub order_ids: Vec<OrderId>,
+    pub users: u32,                // Accounts that had orders cancelled
+    pub released_margin: Balance,  // Total reserved margin released
+    pub reason: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
 pub struct OrderAmend {
     pub base: BaseEvent,
     pub order_id: OrderId,
     pub user_id: UserId,
     pub new_price: Option<Price>,
-    pub new_quantity: Option<Quantity>,
+    pub new_quantity: Option<Quantity>,  // Total order quantity, including any filled part
+}
+
+/// Resting order changed in place by an OrderAmend
+#[derive(Clone, Debug, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
+pub struct OrderAmended {
+    pub base: BaseEvent,
+    pub order_id: OrderId,
+    pub user_id: UserId,
+    pub old_price: Price,
+    pub new_price: Price,
+    pub old_quantity: Quantity,
+    pub new_quantity: Quantity,
+    pub filled: Quantity,
+    pub priority_kept: bool,   // false = moved to the back of the (new) level
+    pub margin_delta: Balance, // Positive = additional margin reserved
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -50,15 +153,150 @@ pub struct OrderAccepted {
     pub user_id: UserId,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
 pub struct OrderRejected {
     pub base: BaseEvent,
     pub order_id: OrderId,
     pub user_id: UserId,
-    pub reason: String,
+    pub reason: RejectReason,
+}
+
+/// An account request (position or margin mode, leverage, collateral or a
+/// funds move) the engine refused, reported back to the user who sent it
+#[derive(Clone, Debug, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
+pub struct RequestRejected {
+    pub base: BaseEvent,
+    pub request_id: EventId,  // event_id of the refused request
+    pub request_type: EventType,
+    pub user_id: UserId,
+    pub reason: RejectReason,
+}
+
+/// Structured rejection reason returned to the submitting client
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
+pub enum RejectReason {
+    Validation { code: String },
+    InsufficientMargin { required: Balance, available: Balance },
+    InsufficientBalance,
+    LeverageExceeded,
+    PositionLimitExceeded,
+    ReduceOnlyViolation,
+    PriceBand,
+    MarketHalted,
+    FillOrKill,  // Not enough liquidity at the limit to fill in full
+    MinFillQuantity,  // Not enough liquidity at the limit to fill the IOC minimum
+    PostOnly,    // Would have taken liquidity
+    StaleMarkPrice { age_ms: u64 },
+    CircuitBreakerOpen,
+    TradingPhase { phase: TradingPhase },  // Post-only or cancel-only (e.g. warm-up)
+    MarketExpired,  // Dated future past its expiry
+    Internal { message: String },
+}
+
+impl RejectReason {
+    pub fn from_error(error: &Error) -> Self {
+        match error {
+            Error::InvalidTickSize => RejectReason::validation("invalid_tick_size"),
+            Error::InvalidLotSize => RejectReason::validation("invalid_lot_size"),
+            Error::InvalidPrice => RejectReason::validation("invalid_price"),
+            Error::InvalidQuantity => RejectReason::validation("invalid_quantity"),
+            Error::BelowMinOrderSize => RejectReason::validation("below_min_size"),
+            Error::AboveMaxOrderSize => RejectReason::validation("above_max_size"),
+            Error::MarketOrderCannotBePostOnly => RejectReason::validation("market_post_only"),
+            Error::MarketOrderRequiresSlippageLimit => RejectReason::validation("missing_slippage_limit"),
+            Error::LimitOrderRequiresPrice => RejectReason::validation("missing_price"),
+            Error::StopOrderRequiresTriggerPrice => RejectReason::validation("missing_trigger_price"),
+            Error::TrailingStopRequiresOffset => RejectReason::validation("invalid_trailing_offset"),
+            Error::DuplicateOrderId(_) => RejectReason::validation("duplicate_order_id"),
+            Error::AmendWouldCross => RejectReason::validation("amend_would_cross"),
+            Error::InsufficientMargin { required, available } => RejectReason::InsufficientMargin {
+                required: *required,
+                available: *available,
+            },
+            Error::InsufficientAvailableBalance => RejectReason::InsufficientBalance,
+            Error::LeverageExceeded { .. } => RejectReason::LeverageExceeded,
+            Error::PositionLimitExceeded => RejectReason::PositionLimitExceeded,
+            Error::OpenNotionalLimitExceeded { .. } => RejectReason::PositionLimitExceeded,
+            Error::OpenOrderLimitExceeded { .. } => RejectReason::validation("open_order_limit"),
+            Error::OpenInterestCapExceeded { .. } => RejectReason::PositionLimitExceeded,
+            Error::ReduceOnlyViolation => RejectReason::ReduceOnlyViolation,
+            Error::BatchUserMismatch => RejectReason::validation("batch_user_mismatch"),
+            Error::PositionSideMismatch => RejectReason::validation("position_side_mismatch"),
+            Error::InvalidExpiry => RejectReason::validation("invalid_expiry"),
+            Error::CircuitBreakerTriggered(_) => RejectReason::PriceBand,
+            Error::PriceOutsideBand { .. } => RejectReason::PriceBand,
+            Error::KillSwitchActive => RejectReason::MarketHalted,
+            Error::FillOrKillNotFilled => RejectReason::FillOrKill,
+            Error::MinFillQuantityNotMet => RejectReason::MinFillQuantity,
+            Error::InvalidMinFillQuantity => RejectReason::validation("invalid_min_fill_quantity"),
+            Error::PostOnlyWouldCross => RejectReason::PostOnly,
+            Error::StaleMarkPrice { age_ms } => RejectReason::StaleMarkPrice { age_ms: *age_ms },
+            Error::CircuitBreakerOpen => RejectReason::CircuitBreakerOpen,
+            Error::TradingPhaseRestricted(phase) => RejectReason::TradingPhase { phase: *phase },
+            Error::MarketExpired { .. } => RejectReason::MarketExpired,
+            Error::Overflow { .. } => RejectReason::validation("value_overflow"),  // Order or position too large to value
+            Error::PositionModeLocked => RejectReason::validation("position_mode_locked"),
+            Error::MarginModeLocked => RejectReason::validation("margin_mode_locked"),
+            Error::InvalidLeverage(_) => RejectReason::validation("invalid_leverage"),
+            Error::PositionNotIsolated => RejectReason::validation("position_not_isolated"),
+            Error::InsufficientIsolatedMargin { .. } => RejectReason::InsufficientBalance,
+            Error::WithdrawalMarginBreach { .. } => RejectReason::InsufficientBalance,
+            Error::InsufficientBalance => RejectReason::InsufficientBalance,
+            Error::InvalidTransferAmount => RejectReason::validation("invalid_amount"),
+            Error::SelfTransfer => RejectReason::validation("self_transfer"),
+            Error::InvalidSubAccount(_) => RejectReason::validation("invalid_sub_account"),
+            Error::NotInSubAccountFamily { .. } => RejectReason::validation("not_in_sub_account_family"),
+            Error::AccountNotFound(_) => RejectReason::validation("account_not_found"),
+            Error::Unauthorized => RejectReason::validation("unauthorized"),
+            other => RejectReason::Internal { message: other.to_string() },
+        }
+    }
+
+    fn validation(code: &str) -> Self {
+        RejectReason::Validation { code: code.to_string() }
+    }
+
+    /// Short label for metrics
+    pub fn code(&self) -> &str {
+        match self {
+            RejectReason::Validation { code } => code,
+            RejectReason::InsufficientMargin { .. } => "insufficient_margin",
+            RejectReason::InsufficientBalance => "insufficient_balance",
+            RejectReason::LeverageExceeded => "leverage_exceeded",
+            RejectReason::PositionLimitExceeded => "position_limit_exceeded",
+            RejectReason::ReduceOnlyViolation => "reduce_only_violation",
+            RejectReason::PriceBand => "price_band",
+            RejectReason::MarketHalted => "market_halted",
+            RejectReason::FillOrKill => "fill_or_kill",
+            RejectReason::MinFillQuantity => "min_fill_quantity",
+            RejectReason::PostOnly => "post_only_would_cross",
+            RejectReason::StaleMarkPrice { .. } => "stale_mark_price",
+            RejectReason::CircuitBreakerOpen => "circuit_breaker_open",
+            RejectReason::TradingPhase { .. } => "trading_phase_restricted",
+            RejectReason::MarketExpired => "market_expired",
+            RejectReason::Internal { .. } => "internal",
+        }
+    }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+impl std::fmt::Display for RejectReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RejectReason::InsufficientMargin { required, available } => write!(
+                f, "insufficient_margin (required={}, available={})",
+                required.to_i64(), available.to_i64()
+            ),
+            RejectReason::Internal { message } => write!(f, "internal ({})", message),
+            other => write!(f, "{}", other.code()),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
 pub enum Side {
     Buy,
     Sell,
@@ -80,15 +318,99 @@ impl Side {
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
 pub enum OrderType {
     Limit,
     Market,
+    StopMarket,  // Becomes Market when mark price reaches trigger_price
+    StopLimit,   // Becomes Limit at `price` when mark price reaches trigger_price
+    TrailingStop,  // Becomes Market when mark price retraces trailing_offset from its best
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+impl OrderType {
+    pub fn is_stop(&self) -> bool {
+        matches!(self, OrderType::StopMarket | OrderType::StopLimit)
+    }
+}
+
+/// What happens when an order would trade against the same account
+/// The incoming (taker) order's mode applies
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
+#[serde(rename_all = "snake_case")]
+pub enum SelfTradePrevention {
+    /// Cancel the resting order, keep matching the taker
+    #[default]
+    CancelMaker,
+    /// Cancel the rest of the taker
+    CancelTaker,
+    /// Cancel the resting order and the rest of the taker
+    CancelBoth,
+    /// Reduce both by the smaller remaining quantity without trading; the
+    /// smaller one is cancelled
+    DecrementAndCancel,
+}
+
+/// Self-trade prevented by the matcher (no trade took place)
+#[derive(Clone, Debug, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
+pub struct SelfTradePrevented {
+    pub base: BaseEvent,
+    pub user_id: UserId,
+    pub maker_order_id: OrderId,
+    pub taker_order_id: OrderId,
+    pub mode: SelfTradePrevention,
+    pub maker_cancelled: Quantity,  // Quantity removed from the resting order
+    pub taker_cancelled: Quantity,  // Quantity removed from the incoming order
+}
+
+/// Distance the trailing-stop trigger keeps from the best mark price seen
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
+pub enum TrailingOffset {
+    Absolute(Price),
+    Bps(u32),  // Of the best mark price, 1-9999
+}
+
+impl TrailingOffset {
+    /// Offset in price terms at the given reference price
+    pub fn at(&self, reference: Price) -> Price {
+        match self {
+            TrailingOffset::Absolute(offset) => *offset,
+            TrailingOffset::Bps(bps) => {
+                Price::from_i64((reference.to_i64() as i128 * *bps as i128 / 10_000) as i64)
+            }
+        }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        match self {
+            TrailingOffset::Absolute(offset) => *offset > Price::zero(),
+            TrailingOffset::Bps(bps) => *bps > 0 && *bps < 10_000,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
 pub enum TimeInForce {
     GTC,  // Good Till Cancel
     IOC,  // Immediate Or Cancel
     FOK,  // Fill Or Kill
+    GTD { expires_at: Timestamp },  // Good Till Date: rests until expires_at
+}
+
+impl TimeInForce {
+    /// Unfilled remainder rests on the book
+    pub fn rests(&self) -> bool {
+        matches!(self, TimeInForce::GTC | TimeInForce::GTD { .. })
+    }
+
+    pub fn expires_at(&self) -> Option<Timestamp> {
+        match self {
+            TimeInForce::GTD { expires_at } => Some(*expires_at),
+            _ => None,
+        }
+    }
 }
\ No newline at end of file
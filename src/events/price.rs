@@ -3,7 +3,8 @@ use crate::events::base::BaseEvent;
 use crate::types::timestamp::Timestamp;
 use crate::types::price::Price;
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
 pub struct PriceSnapshot {
     pub base: BaseEvent,
     pub mark_price: Price,
@@ -15,7 +16,8 @@ pub struct PriceSnapshot {
     pub staleness_flags: Vec<bool>,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
 pub struct SourcePrice {
     pub source_id: String,
     pub price: Price,
@@ -25,7 +27,8 @@ pub struct SourcePrice {
     pub is_outlier: bool,
 }
 
-#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
 pub enum AggregationMethod {
     WeightedMedian,
     TWAP,
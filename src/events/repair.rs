@@ -0,0 +1,20 @@
+use serde::{Deserialize, Serialize};
+use crate::events::base::BaseEvent;
+use crate::types::balance::Balance;
+use crate::types::ids::{OperatorId, UserId};
+use crate::types::position::Position;
+
+/// Operator-approved correction of one account back to a snapshot's values
+/// Applied as a compensating change on top of current state, so the event
+/// log remains the full history (the bad state and its repair are both kept)
+#[derive(Clone, Debug, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
+pub struct StateRepair {
+    pub base: BaseEvent,
+    pub user_id: UserId,
+    pub snapshot_sequence: u64,
+    pub operator_id: OperatorId,
+    pub reason: String,
+    pub target_balance: Option<Balance>,    // None = balance untouched
+    pub target_position: Option<Position>,  // None = position untouched; flat position = close
+}
@@ -3,11 +3,13 @@ use crate::events::base::BaseEvent;
 use crate::events::order::Side;
 use crate::types::balance::Balance;
 use crate::types::ids::{OrderId, TradeId, UserId};
+use crate::types::position::PositionSide;
 use crate::types::price::Price;
 use crate::types::quantity::Quantity;
 use crate::types::ratio::Ratio;
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
 pub struct TradeEvent {
     pub base: BaseEvent,
     pub trade_id: TradeId,
@@ -21,10 +23,54 @@ pub struct TradeEvent {
     pub maker_fee: Fee,
     pub taker_fee: Fee,
     pub liquidation: bool,
+    #[serde(default)]
+    pub maker_position_side: PositionSide,  // Legs settled in hedge mode
+    #[serde(default)]
+    pub taker_position_side: PositionSide,
 }
 
-#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
 pub struct Fee {
     pub amount: Balance,
     pub rate: Ratio,
-}
\ No newline at end of file
+}
+/// Which side of the book an order's fills took
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
+pub enum Liquidity {
+    Maker,
+    Taker,
+}
+
+/// Order state after a match
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
+pub enum ExecutionStatus {
+    New,              // Resting, nothing filled yet
+    PartiallyFilled,  // Resting with some quantity filled
+    Filled,
+    Cancelled,        // Remainder removed (IOC/FOK, self-trade prevention)
+}
+
+/// Per-order outcome of one match, for the taker and every maker it hit
+///
+/// `fill_quantity`, `average_price` and `fees` cover this match only;
+/// `filled_quantity` and `remaining_quantity` are the order's running totals,
+/// so a client can follow an order without replaying its trades.
+#[derive(Clone, Debug, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
+pub struct ExecutionReport {
+    pub base: BaseEvent,
+    pub order_id: OrderId,
+    pub user_id: UserId,
+    pub side: Side,
+    pub liquidity: Liquidity,
+    pub fill_quantity: Quantity,
+    pub average_price: Price,       // Zero when nothing filled
+    pub fees: Balance,
+    pub filled_quantity: Quantity,
+    pub remaining_quantity: Quantity,  // Still open on the book (zero once terminal)
+    pub status: ExecutionStatus,
+    pub trade_ids: Vec<TradeId>,
+}
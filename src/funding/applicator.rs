@@ -1,10 +1,16 @@
 use std::sync::atomic::{AtomicBool, Ordering};
+use crate::config::market::ContractSpec;
 use crate::error::{Error, Result};
-use crate::events::base::BaseEvent;
-use crate::events::funding::FundingEvent;
+use crate::events::base::{BaseEvent, EventType};
+use crate::config::FundingSettlement;
+use crate::events::funding::{FundingCatchUp, FundingEvent, FundingPayment, PremiumSource};
+use crate::funding::index::CumulativeFunding;
+use crate::funding::catch_up::{time_weighted_prices, PremiumSample};
 use crate::funding::payment_calculator::FundingPaymentCalculator;
 use crate::funding::rate_calculator::FundingRateCalculator;
-use crate::interfaces::balance_provider::BalanceProvider;
+use crate::risk::pnl::FundingAccrual;
+use crate::types::balance::Balance;
+use crate::types::funding_rate::FundingRate;
 use crate::types::ids::MarketId;
 use crate::types::position::Position;
 use crate::types::price::Price;
@@ -14,6 +20,7 @@ use std::time::Duration;
 pub struct FundingApplicator {
     rate_calculator: FundingRateCalculator,
     funding_interval: Duration,
+    contract: ContractSpec,
     halted: AtomicBool,
 }
 
@@ -25,16 +32,24 @@ impl FundingApplicator {
         FundingApplicator {
             rate_calculator,
             funding_interval,
+            contract: ContractSpec::default(),
             halted: AtomicBool::new(false),
         }
     }
 
-    pub fn apply_funding(
+    pub fn with_contract(mut self, contract: ContractSpec) -> Self {
+        self.contract = contract;
+        self
+    }
+
+    /// Funding rate for the interval ending now, at these prices
+    /// Carries no payments: `EventProcessor::process_funding`, the only
+    /// reader of positions that are current as of the event, computes them
+    /// with `settle_payments`
+    pub fn calculate_funding(
         &self,
-        positions: &mut [Position],
         mark_price: Price,
         index_price: Price,
-        balance_provider: &mut dyn BalanceProvider,
         market_id: MarketId,
     ) -> Result<FundingEvent> {
 
@@ -43,46 +58,160 @@ impl FundingApplicator {
             return Err(Error::KillSwitchActive);
         }
 
-        // Calculate funding rate
+        // Calculate funding rate from the premium index averaged over the
+        // interval (the current premium alone until it has been sampled)
+        let base = BaseEvent::new(EventType::Funding, market_id);
         let premium = self.rate_calculator.calculate_premium(mark_price, index_price);
-        let funding_rate = self.rate_calculator.calculate_rate(premium, index_price);
+        let premium_index = self.rate_calculator.premium_twap(base.timestamp)
+            .unwrap_or_else(|| self.rate_calculator.premium_index(mark_price, index_price));
+        let components = self.rate_calculator.components_for(premium_index);
+        let funding_rate = components.funding_rate;
+        let index_delta = FundingPaymentCalculator::index_delta(&self.contract, mark_price, funding_rate)?;
 
-        // Calculate payments
-        let payments = FundingPaymentCalculator::calculate_all_payments(
-            positions,
-            mark_price,
+        Ok(FundingEvent {
+            base,
             funding_rate,
-        );
-
-        // Verify zero-sum
-        if !FundingPaymentCalculator::verify_zero_sum(&payments) {
-            let sum: i64 = payments.iter().map(|p| p.payment.to_i64()).sum();
-            return Err(Error::FundingNotZeroSum { sum });
-        }
+            mark_price,
+            index_price,
+            premium,
+            funding_interval: self.funding_interval,
+            payments: Vec::new(),
+            residual: Balance::zero(),
+            index_delta,
+            catch_up: None,
+            components: Some(components),
+        })
+    }
 
-        // Apply payments to balances
-        for payment in &payments {
-            balance_provider.adjust_balance(payment.user_id, payment.payment)?;
-        }
+    /// Funding rate for an interval missed while the process was down
+    /// Like `calculate_funding`, the event carries no payments: the
+    /// EventProcessor computes and applies them when it reads the event.
+    ///
+    /// ## Premium
+    /// - **RecordedTwap**: time-weighted mark, index and premium over the samples
+    ///   recorded inside the interval
+    /// - **Fallback**: no samples were recorded, so the interval settles at zero
+    ///   premium (zero rate, no value moves) rather than guessing a price
+    pub fn calculate_catch_up(
+        &self,
+        interval_start: Timestamp,
+        interval_end: Timestamp,
+        samples: &[PremiumSample],
+        market_id: MarketId,
+    ) -> Result<FundingEvent> {
+        let (mark_price, index_price, premium, premium_source) =
+            match time_weighted_prices(samples, interval_end) {
+                Some((mark, index, premium)) => (
+                    mark,
+                    index,
+                    premium,
+                    PremiumSource::RecordedTwap { samples: samples.len() as u32 },
+                ),
+                None => (Price::zero(), Price::zero(), Price::zero(), PremiumSource::Fallback),
+            };
 
-        // Update position timestamps
-        let now = Timestamp::now();
-        for position in positions.iter_mut() {
-            position.last_funding_timestamp = now;
-        }
+        let (funding_rate, components, index_delta) = match premium_source {
+            PremiumSource::Fallback => (FundingRate::zero(), None, Balance::zero()),
+            PremiumSource::RecordedTwap { .. } => {
+                let components = self.rate_calculator.calculate_components(premium, index_price);
+                let funding_rate = components.funding_rate;
+                let index_delta = FundingPaymentCalculator::index_delta(&self.contract, mark_price, funding_rate)?;
+                (funding_rate, Some(components), index_delta)
+            }
+        };
 
-        // Create event
         Ok(FundingEvent {
-            base: BaseEvent::new(crate::events::base::EventType::Funding, market_id),
+            base: BaseEvent::new(EventType::FundingCatchUp, market_id),
             funding_rate,
             mark_price,
             index_price,
             premium,
             funding_interval: self.funding_interval,
-            payments,
+            payments: Vec::new(),
+            residual: Balance::zero(),
+            index_delta,
+            catch_up: Some(FundingCatchUp {
+                interval_start,
+                interval_end,
+                premium_source,
+            }),
+            components,
         })
     }
 
+    /// Accrual for the current interval, at the rate these prices would set,
+    /// on top of the market's cumulative funding
+    pub fn accrual(
+        &self,
+        mark_price: Price,
+        index_price: Price,
+        as_of: Timestamp,
+        cumulative: CumulativeFunding,
+    ) -> FundingAccrual {
+        let premium = self.rate_calculator.calculate_premium(mark_price, index_price);
+        FundingAccrual {
+            funding_rate: self.rate_calculator.calculate_rate(premium, index_price),
+            funding_interval: self.funding_interval,
+            as_of,
+            cumulative,
+        }
+    }
+
+    /// Payments and rounding residual of `positions` for the interval `event`
+    /// settles, at its rate and mark, prorated up to its interval end
+    ///
+    /// Called by the EventProcessor on its own positions, so replaying the
+    /// log pays the same accounts the same amounts. A fallback catch-up
+    /// (no premium samples) moves no value.
+    pub fn settle_payments(&self, event: &FundingEvent, positions: &[Position]) -> Result<(Vec<FundingPayment>, Balance)> {
+        let fallback = event.catch_up.as_ref()
+            .is_some_and(|catch_up| catch_up.premium_source == PremiumSource::Fallback);
+        if fallback {
+            return Ok((Vec::new(), Balance::zero()));
+        }
+
+        let (mut payments, residual) = self.payments(positions, event.mark_price, event.funding_rate, event.interval_end())?;
+        if !FundingPaymentCalculator::verify_zero_sum(&payments, residual) {
+            let sum: i64 = payments.iter().map(|p| p.payment.to_i64()).sum::<i64>() + residual.to_i64();
+            return Err(Error::FundingNotZeroSum { sum });
+        }
+        payments.sort_by_key(|payment| payment.user_id.0);
+        Ok((payments, residual))
+    }
+
+    /// Payments and rounding residual for the interval ending at `as_of`
+    /// Lazy settlement pays nobody here: positions settle against the
+    /// funding index when next touched
+    fn payments(
+        &self,
+        positions: &[Position],
+        mark_price: Price,
+        funding_rate: FundingRate,
+        as_of: Timestamp,
+    ) -> Result<(Vec<FundingPayment>, Balance)> {
+        match self.rate_calculator.settlement() {
+            FundingSettlement::Sweep => FundingPaymentCalculator::calculate_all_payments(
+                &self.contract,
+                positions,
+                mark_price,
+                funding_rate,
+                &self.rate_calculator.payment_rounding(),
+                as_of,
+                self.funding_interval,
+            ),
+            FundingSettlement::Lazy => Ok((Vec::new(), Balance::zero())),
+        }
+    }
+
+    /// Sample the premium index for the interval's funding rate
+    pub fn record_premium(&self, mark_price: Price, index_price: Price, at: Timestamp) {
+        self.rate_calculator.record_premium(mark_price, index_price, at);
+    }
+
+    pub fn funding_interval(&self) -> Duration {
+        self.funding_interval
+    }
+
     pub fn halt(&self) {
         self.halted.store(true, Ordering::SeqCst);
         tracing::warn!("FundingApplicator HALTED");
@@ -0,0 +1,142 @@
+use std::fs::OpenOptions;
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use crate::error::{Error, Result};
+use crate::types::price::Price;
+use crate::types::timestamp::Timestamp;
+
+/// One recorded mark/index observation
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct PremiumSample {
+    pub timestamp: Timestamp,
+    pub mark_price: Price,
+    pub index_price: Price,
+}
+
+/// Append-only premium journal (one JSON sample per line)
+///
+/// Written by the price task at most once per `sample_every`, read back on
+/// startup to settle funding intervals that elapsed while the process was down.
+pub struct PremiumLog {
+    path: PathBuf,
+    sample_every: Duration,
+    last_recorded: Option<Timestamp>,
+}
+
+impl PremiumLog {
+    pub fn new(path: impl Into<PathBuf>, sample_every: Duration) -> Self {
+        PremiumLog {
+            path: path.into(),
+            sample_every,
+            last_recorded: None,
+        }
+    }
+
+    /// Append a sample if `sample_every` has passed since the last one
+    pub fn record(&mut self, mark_price: Price, index_price: Price, now: Timestamp) -> Result<()> {
+        if let Some(last) = self.last_recorded && now - last < self.sample_every {
+            return Ok(());
+        }
+
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir).map_err(Error::IoError)?;
+        }
+
+        let sample = PremiumSample { timestamp: now, mark_price, index_price };
+        let mut line = serde_json::to_vec(&sample)
+            .map_err(|e| Error::SerializationError(e.to_string()))?;
+        line.push(b'\n');
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(Error::IoError)?;
+        file.write_all(&line).map_err(Error::IoError)?;
+
+        self.last_recorded = Some(now);
+        Ok(())
+    }
+
+    /// Samples with `start <= timestamp < end`, oldest first
+    pub fn load_between(&self, start: Timestamp, end: Timestamp) -> Result<Vec<PremiumSample>> {
+        let file = match std::fs::File::open(&self.path) {
+            Ok(file) => file,
+            Err(_) => return Ok(Vec::new()),  // Nothing recorded yet
+        };
+
+        let mut samples = Vec::new();
+        for line in BufReader::new(file).lines() {
+            let line = line.map_err(Error::IoError)?;
+
+            // A torn final line from a crash is skipped, not fatal
+            let sample: PremiumSample = match serde_json::from_str(&line) {
+                Ok(sample) => sample,
+                Err(_) => continue,
+            };
+
+            if sample.timestamp >= start && sample.timestamp < end {
+                samples.push(sample);
+            }
+        }
+
+        samples.sort_by_key(|s| s.timestamp);
+        Ok(samples)
+    }
+}
+
+/// Funding intervals (epoch-aligned) that ended after `last_funding` and at or before `now`
+pub fn missed_intervals(
+    last_funding: Timestamp,
+    now: Timestamp,
+    interval: Duration,
+) -> Vec<(Timestamp, Timestamp)> {
+    let interval_ms = interval.as_millis() as u64;
+    if interval_ms == 0 {
+        return Vec::new();
+    }
+
+    let mut intervals = Vec::new();
+    let mut boundary = (last_funding.physical / interval_ms + 1) * interval_ms;
+
+    while boundary <= now.physical {
+        intervals.push((
+            Timestamp::from_millis(boundary - interval_ms),
+            Timestamp::from_millis(boundary),
+        ));
+        boundary += interval_ms;
+    }
+
+    intervals
+}
+
+/// Time-weighted (mark, index, premium) over samples sorted by time
+/// Each sample holds until the next one; the last holds until `interval_end`
+pub fn time_weighted_prices(
+    samples: &[PremiumSample],
+    interval_end: Timestamp,
+) -> Option<(Price, Price, Price)> {
+    let mut weight_sum: i128 = 0;
+    let mut mark_sum: i128 = 0;
+    let mut index_sum: i128 = 0;
+
+    for (i, sample) in samples.iter().enumerate() {
+        let until = samples.get(i + 1).map_or(interval_end, |next| next.timestamp);
+        // At least 1ms so a single sample at the interval end still counts
+        let weight = (until.physical.saturating_sub(sample.timestamp.physical)).max(1) as i128;
+
+        weight_sum += weight;
+        mark_sum += sample.mark_price.to_i64() as i128 * weight;
+        index_sum += sample.index_price.to_i64() as i128 * weight;
+    }
+
+    if weight_sum == 0 {
+        return None;
+    }
+
+    let mark = Price::from_i64((mark_sum / weight_sum) as i64);
+    let index = Price::from_i64((index_sum / weight_sum) as i64);
+    Some((mark, index, mark - index))
+}
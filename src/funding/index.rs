@@ -0,0 +1,37 @@
+use serde::{Deserialize, Serialize};
+use crate::types::balance::Balance;
+use crate::types::timestamp::Timestamp;
+
+/// Market-wide cumulative funding, for settling positions lazily
+///
+/// `index` is the funding one long contract has paid since the market
+/// opened (negative when longs have received on balance). Each position
+/// stores the index it last settled at, so what it owes is
+/// `(index - position.funding_index) * size` whenever it is next touched,
+/// without visiting every position at each interval.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CumulativeFunding {
+    pub index: Balance,
+    pub last_funding: Timestamp,  // End of the last settled interval (epoch: none yet)
+}
+
+impl CumulativeFunding {
+    pub fn new() -> Self {
+        CumulativeFunding {
+            index: Balance::zero(),
+            last_funding: Timestamp::from_millis(0),
+        }
+    }
+
+    /// Settle one interval ending at `at`
+    pub fn advance(&mut self, delta: Balance, at: Timestamp) {
+        self.index = self.index + delta;
+        self.last_funding = self.last_funding.max(at);
+    }
+}
+
+impl Default for CumulativeFunding {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,4 +1,8 @@
 pub mod rate_calculator;
 pub mod payment_calculator;
 pub mod applicator;
-pub mod ticker;
\ No newline at end of file
+pub mod ticker;
+pub mod catch_up;
+pub mod predicted;
+pub mod payment_history;
+pub mod index;
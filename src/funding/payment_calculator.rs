@@ -1,81 +1,171 @@
+use crate::config::market::ContractSpec;
+use crate::error::Result;
 use crate::events::funding::FundingPayment;
 use crate::types::balance::Balance;
 use crate::types::funding_rate::FundingRate;
 use crate::types::position::Position;
 use crate::types::price::Price;
 use crate::types::quantity::Quantity;
+use crate::types::rounding::RoundingPolicy;
+use crate::types::timestamp::Timestamp;
+use std::time::Duration;
+
+/// Raw size of one whole contract
+const ONE_CONTRACT: i64 = 100_000_000;
 
 pub struct FundingPaymentCalculator;
 
 impl FundingPaymentCalculator {
     /// Calculate funding payment for a position
-    /// Payment = position_size * mark_price * funding_rate
+    /// Payment = notional(position_size, mark_price) * funding_rate, in the
+    /// contract's settlement currency (base asset for inverse contracts)
     /// Positive = receive, Negative = pay
+    /// Rounding applies to the payment magnitude, so payers and receivers are treated alike
     pub fn calculate_payment(
+        contract: &ContractSpec,
         position: &Position,
         mark_price: Price,
         funding_rate: FundingRate,
-    ) -> Balance {
+        rounding: &RoundingPolicy,
+    ) -> Result<Balance> {
         if position.is_flat() {
-            return Balance::zero();
+            return Ok(Balance::zero());
         }
 
-        let notional = Quantity::from_i64(position.size.abs()) * mark_price;
-        let payment = notional.to_f64() * funding_rate.to_f64();
+        let notional = contract.notional(Quantity::from_i64(position.size.abs()), mark_price)?;
+        let payment = rounding.div(
+            notional.to_i64() as i128 * (funding_rate.to_i64() as i128).abs(),
+            FundingRate::MULTIPLIER as i128,
+        )?;
 
         // Long positions pay when rate is positive, receive when negative
         // Short positions receive when rate is positive, pay when negative
-        let signed_payment = if position.is_long() {
-            -payment
+        let pays = position.is_long() == (funding_rate.to_i64() > 0);
+        if pays {
+            Ok(-payment)
         } else {
-            payment
-        };
+            Ok(payment)
+        }
+    }
+
+    /// Payment for the part of the interval ending at `as_of` the position
+    /// was open: the full payment pro rata of the time since
+    /// `last_funding_timestamp`, at most one interval
+    pub fn calculate_prorated_payment(
+        contract: &ContractSpec,
+        position: &Position,
+        mark_price: Price,
+        funding_rate: FundingRate,
+        rounding: &RoundingPolicy,
+        as_of: Timestamp,
+        interval: Duration,
+    ) -> Result<Balance> {
+        let payment = Self::calculate_payment(contract, position, mark_price, funding_rate, rounding)?;
+        Self::prorate(payment, position.last_funding_timestamp, as_of, interval)
+    }
+
+    /// `payment` pro rata of the time from `since` to `as_of`, at most one interval
+    pub fn prorate(payment: Balance, since: Timestamp, as_of: Timestamp, interval: Duration) -> Result<Balance> {
+        let interval_ms = interval.as_millis() as i128;
+        if interval_ms == 0 {
+            return Ok(Balance::zero());
+        }
+        let elapsed_ms = ((as_of - since).as_millis() as i128).min(interval_ms);
+        if elapsed_ms == interval_ms {
+            return Ok(payment);
+        }
+        Balance::try_from_i128(payment.to_i64() as i128 * elapsed_ms / interval_ms, "prorated funding")
+    }
+
+    /// Funding one long contract (one whole unit of size) pays for an
+    /// interval at this rate: what the cumulative funding index advances by
+    /// (negative when longs receive)
+    pub fn index_delta(contract: &ContractSpec, mark_price: Price, funding_rate: FundingRate) -> Result<Balance> {
+        let notional = contract.notional(Quantity::from_i64(ONE_CONTRACT), mark_price)?;
+        Balance::try_from_i128(
+            (notional.to_i64() as i128 * funding_rate.to_i64() as i128).div_euclid(FundingRate::MULTIPLIER as i128),
+            "funding index delta",
+        )
+    }
 
-        Balance::from_f64(signed_payment)
+    /// Funding a position owes (negative) or is owed (positive) for the
+    /// index moving from its `funding_index` to `index`
+    /// Rounded down, so payers never pay less and receivers never get more
+    /// than their exact share
+    pub fn index_payment(position: &Position, index: Balance) -> Result<Balance> {
+        let moved = index.to_i64() as i128 - position.funding_index.to_i64() as i128;
+        Balance::try_from_i128(
+            (-moved * position.size as i128).div_euclid(ONE_CONTRACT as i128),
+            "funding index payment",
+        )
     }
 
-    /// Calculate all funding payments for a market
+    /// Calculate all funding payments for the interval ending at `as_of`
+    ///
+    /// Positions opened mid-interval pay or receive pro rata, so the side
+    /// owed more in total is scaled down to what the other side owes:
+    /// nobody pays or receives more than their prorated share. Rounding can
+    /// leave a residual, returned alongside the payments: it is settled
+    /// against the funding residual account, never a user
     pub fn calculate_all_payments(
+        contract: &ContractSpec,
         positions: &[Position],
         mark_price: Price,
         funding_rate: FundingRate,
-    ) -> Vec<FundingPayment> {
-        positions.iter()
+        rounding: &RoundingPolicy,
+        as_of: Timestamp,
+        interval: Duration,
+    ) -> Result<(Vec<FundingPayment>, Balance)> {
+        let mut payments = positions.iter()
             .filter(|p| !p.is_flat())
-            .map(|p| FundingPayment {
+            .map(|p| Ok(FundingPayment {
                 user_id: p.user_id,
                 position_size: Quantity::from_i64(p.size),
-                payment: Self::calculate_payment(p, mark_price, funding_rate),
-            })
-            .collect()
+                payment: Self::calculate_prorated_payment(contract, p, mark_price, funding_rate, rounding, as_of, interval)?,
+            }))
+            .collect::<Result<Vec<FundingPayment>>>()?;
+
+        Self::match_sides(&mut payments);
+        let residual = Self::residual(&payments);
+        Ok((payments, residual))
+    }
+
+    /// Scale the larger of total paid and total received down to the smaller
+    fn match_sides(payments: &mut [FundingPayment]) {
+        let paid: i128 = payments.iter().map(|p| p.payment.to_i64().min(0) as i128).sum::<i128>().abs();
+        let received: i128 = payments.iter().map(|p| p.payment.to_i64().max(0) as i128).sum();
+        if paid == received {
+            return;
+        }
+
+        let (larger, smaller, payers) = if paid > received {
+            (paid, received, true)
+        } else {
+            (received, paid, false)
+        };
+        for payment in payments.iter_mut() {
+            let raw = payment.payment.to_i64();
+            if (raw < 0) == payers && raw != 0 {
+                // |raw| <= larger, so the scaled value fits
+                payment.payment = Balance::from_i64((raw as i128 * smaller / larger) as i64);
+            }
+        }
     }
 
-    /// Verify zero-sum property
-    pub fn verify_zero_sum(payments: &[FundingPayment]) -> bool {
+    /// Verify zero-sum property: payments and the residual net to zero
+    pub fn verify_zero_sum(payments: &[FundingPayment], residual: Balance) -> bool {
         let sum: i64 = payments.iter()
             .map(|p| p.payment.to_i64())
             .sum();
 
-        // Allow small rounding error (< 1 unit)
-        sum.abs() < 1
+        sum + residual.to_i64() == 0
     }
 
-    /// Ensure zero-sum by adjusting largest payment
+    /// What the funding residual account takes (positive) or pays
+    /// (negative) for `payments` to net to zero
     /// Per docs/architecture/funding-engine.md Section 5.2
-    pub fn ensure_zero_sum(payments: &mut [FundingPayment]) {
+    pub fn residual(payments: &[FundingPayment]) -> Balance {
         let sum: i64 = payments.iter().map(|p| p.payment.to_i64()).sum();
-
-        if sum != 0 {
-            // Find largest absolute payment and adjust
-            if let Some(largest) = payments.iter_mut()
-                .max_by_key(|p| p.payment.to_i64().abs())
-            {
-                largest.payment = Balance::from_i64(largest.payment.to_i64() - sum);
-            }
-        }
-
-        // Verify final sum is zero
-        let final_sum: i64 = payments.iter().map(|p| p.payment.to_i64()).sum();
-        assert_eq!(final_sum, 0, "Funding payments must sum to zero after adjustment");
+        Balance::from_i64(-sum)
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,128 @@
+use std::collections::{HashMap, HashSet};
+use std::fs::OpenOptions;
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+use crate::error::{Error, Result};
+use crate::events::funding::FundingEvent;
+use crate::types::balance::Balance;
+use crate::types::funding_rate::FundingRate;
+use crate::types::ids::{EventId, MarketId, UserId};
+use crate::types::price::Price;
+use crate::types::quantity::Quantity;
+use crate::types::timestamp::Timestamp;
+
+/// One funding payment applied to an account
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FundingPaymentRecord {
+    pub event_id: EventId,        // FundingEvent that carried the payment
+    pub user_id: UserId,
+    pub market_id: MarketId,
+    pub amount: Balance,          // Signed: positive = received, negative = paid
+    pub funding_rate: FundingRate,
+    pub position_size: Quantity,
+    pub mark_price: Price,
+    pub timestamp: Timestamp,     // When the interval settled
+}
+
+/// Funding payments per user, journaled to disk (one JSON record per line)
+///
+/// The journal is loaded back on `open`, so history survives restarts.
+/// Records are keyed by (event, user): a funding event redelivered or
+/// replayed after a restore is not recorded twice.
+pub struct FundingPaymentHistory {
+    path: PathBuf,
+    by_user: HashMap<UserId, Vec<FundingPaymentRecord>>,  // Oldest first
+    recorded: HashSet<(EventId, UserId)>,
+}
+
+impl FundingPaymentHistory {
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        let mut history = FundingPaymentHistory {
+            path: path.into(),
+            by_user: HashMap::new(),
+            recorded: HashSet::new(),
+        };
+
+        let file = match std::fs::File::open(&history.path) {
+            Ok(file) => file,
+            Err(_) => return Ok(history),  // Nothing recorded yet
+        };
+        for line in BufReader::new(file).lines() {
+            let line = line.map_err(Error::IoError)?;
+
+            // A torn final line from a crash is skipped, not fatal
+            if let Ok(record) = serde_json::from_str::<FundingPaymentRecord>(&line) {
+                history.insert(record);
+            }
+        }
+
+        Ok(history)
+    }
+
+    /// Journal every payment of a settled funding event; returns how many
+    /// were new
+    pub fn record(&mut self, event: &FundingEvent, settled_at: Timestamp) -> Result<usize> {
+        let records: Vec<FundingPaymentRecord> = event.payments.iter()
+            .filter(|p| !self.recorded.contains(&(event.base.event_id, p.user_id)))
+            .map(|p| FundingPaymentRecord {
+                event_id: event.base.event_id,
+                user_id: p.user_id,
+                market_id: event.base.market_id,
+                amount: p.payment,
+                funding_rate: event.funding_rate,
+                position_size: p.position_size,
+                mark_price: event.mark_price,
+                timestamp: settled_at,
+            })
+            .collect();
+        if records.is_empty() {
+            return Ok(0);
+        }
+
+        let mut lines = Vec::new();
+        for record in &records {
+            serde_json::to_writer(&mut lines, record)
+                .map_err(|e| Error::SerializationError(e.to_string()))?;
+            lines.push(b'\n');
+        }
+
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir).map_err(Error::IoError)?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(Error::IoError)?;
+        file.write_all(&lines).map_err(Error::IoError)?;
+
+        let count = records.len();
+        for record in records {
+            self.insert(record);
+        }
+        Ok(count)
+    }
+
+    /// A user's payments with `from <= timestamp < to`, oldest first
+    pub fn payments(&self, user_id: &UserId, from: Timestamp, to: Timestamp) -> Vec<FundingPaymentRecord> {
+        self.by_user.get(user_id)
+            .map(|records| {
+                records.iter()
+                    .filter(|r| r.timestamp >= from && r.timestamp < to)
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    fn insert(&mut self, record: FundingPaymentRecord) {
+        if !self.recorded.insert((record.event_id, record.user_id)) {
+            return;
+        }
+        let records = self.by_user.entry(record.user_id).or_default();
+        // Catch-up intervals can settle older than the latest record
+        let at = records.partition_point(|r| r.timestamp <= record.timestamp);
+        records.insert(at, record);
+    }
+}
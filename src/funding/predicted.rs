@@ -0,0 +1,73 @@
+use std::sync::Mutex;
+use serde::Serialize;
+use crate::config::FundingConfig;
+use crate::funding::rate_calculator::FundingRateCalculator;
+use crate::observability::metrics::PREDICTED_FUNDING_RATE;
+use crate::types::funding_rate::FundingRate;
+use crate::types::price::Price;
+use crate::types::timestamp::Timestamp;
+
+/// Next funding rate as it stands between funding events
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct PredictedFunding {
+    pub premium_index_ema: f64,
+    pub funding_rate: FundingRate,  // Rate the EMA would set, clamped like a settled one
+    pub funding_interval_secs: u64,
+    pub as_of: Timestamp,
+}
+
+/// Running premium index EMA over price updates, and the funding rate it
+/// predicts
+///
+/// Each update moves the EMA by `premium_ema_alpha` towards the current
+/// premium index; the rate goes through the same interest adjustment and
+/// clamps as the settled one. Memory only: the EMA starts again from the
+/// first price after a restart.
+pub struct PredictedFundingTracker {
+    rate_calculator: FundingRateCalculator,
+    alpha: f64,
+    funding_interval_secs: u64,
+    latest: Mutex<Option<PredictedFunding>>,
+}
+
+impl PredictedFundingTracker {
+    pub fn new(config: FundingConfig) -> Self {
+        PredictedFundingTracker {
+            alpha: config.premium_ema_alpha.clamp(0.0, 1.0),
+            funding_interval_secs: config.funding_interval.duration().as_secs(),
+            rate_calculator: FundingRateCalculator::new(config),
+            latest: Mutex::new(None),
+        }
+    }
+
+    /// Fold in a price update; ignored without an index price
+    pub fn record(&self, mark_price: Price, index_price: Price, at: Timestamp) -> Option<PredictedFunding> {
+        if index_price.to_i64() <= 0 {
+            return self.latest();
+        }
+
+        let premium_index = self.rate_calculator.calculate_premium(mark_price, index_price).to_f64() / index_price.to_f64();
+        let mut latest = self.latest.lock().unwrap();
+        let ema = match *latest {
+            Some(previous) => previous.premium_index_ema + self.alpha * (premium_index - previous.premium_index_ema),
+            None => premium_index,
+        };
+
+        let premium = Price::from_f64(ema * index_price.to_f64());
+        let predicted = PredictedFunding {
+            premium_index_ema: ema,
+            funding_rate: self.rate_calculator.calculate_rate(premium, index_price),
+            funding_interval_secs: self.funding_interval_secs,
+            as_of: at,
+        };
+        PREDICTED_FUNDING_RATE.with_label_values(&["default"]).set(predicted.funding_rate.to_f64());
+
+        *latest = Some(predicted);
+        Some(predicted)
+    }
+
+    /// None until the first price update with an index
+    pub fn latest(&self) -> Option<PredictedFunding> {
+        *self.latest.lock().unwrap()
+    }
+}
@@ -1,28 +1,146 @@
-use crate::config::FundingConfig;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::Duration;
+use crate::config::{FundingConfig, FundingSettlement};
+use crate::events::funding::FundingRateComponents;
 use crate::types::funding_rate::FundingRate;
 use crate::types::price::Price;
+use crate::types::rounding::RoundingPolicy;
+use crate::types::timestamp::Timestamp;
+
+/// Premium index samples over the current funding interval (ring buffer)
+///
+/// One sample is kept per `sample_every`, and only as many as fit in one
+/// funding interval, so a price pushed around the funding time moves the
+/// average by at most its share of the interval.
+pub struct PremiumSampler {
+    sample_every: Duration,
+    capacity: usize,
+    samples: VecDeque<(Timestamp, FundingRate)>,  // Oldest first
+}
+
+impl PremiumSampler {
+    pub fn new(sample_every: Duration, funding_interval: Duration) -> Self {
+        let sample_ms = sample_every.as_millis().max(1);
+        PremiumSampler {
+            sample_every,
+            capacity: (funding_interval.as_millis() / sample_ms).max(1) as usize,
+            samples: VecDeque::new(),
+        }
+    }
+
+    /// Record a sample if `sample_every` has passed since the last one;
+    /// true when it was kept
+    pub fn record(&mut self, premium_index: FundingRate, at: Timestamp) -> bool {
+        if let Some((last, _)) = self.samples.back() && at - *last < self.sample_every {
+            return false;
+        }
+        if self.samples.len() == self.capacity {
+            self.samples.pop_front();
+        }
+        self.samples.push_back((at, premium_index));
+        true
+    }
+
+    /// Time-weighted average of the samples with `since <= timestamp <= until`
+    /// Each sample holds until the next one; the last holds until `until`
+    pub fn twap(&self, since: Timestamp, until: Timestamp) -> Option<FundingRate> {
+        let window: Vec<&(Timestamp, FundingRate)> = self.samples.iter()
+            .filter(|(at, _)| *at >= since && *at <= until)
+            .collect();
+
+        let mut weight_sum: i128 = 0;
+        let mut rate_sum: i128 = 0;
+        for (i, (at, premium_index)) in window.iter().enumerate() {
+            let next = window.get(i + 1).map_or(until, |(next, _)| *next);
+            // At least 1ms so a sample taken at `until` still counts
+            let weight = next.physical.saturating_sub(at.physical).max(1) as i128;
+            weight_sum += weight;
+            rate_sum += premium_index.to_i64() as i128 * weight;
+        }
+
+        (weight_sum > 0).then(|| FundingRate::from_i64((rate_sum / weight_sum) as i64))
+    }
+}
 
 pub struct FundingRateCalculator {
     config: FundingConfig,
+    sampler: Mutex<PremiumSampler>,  // Fed by the price task, read at funding time
 }
 
 impl FundingRateCalculator {
     pub fn new(config: FundingConfig) -> Self {
-        FundingRateCalculator { config }
+        FundingRateCalculator {
+            sampler: Mutex::new(PremiumSampler::new(
+                Duration::from_secs(config.premium_sample_secs),
+                config.funding_interval.duration(),
+            )),
+            config,
+        }
+    }
+
+    /// Premium index at these prices: premium / index_price (zero without an index)
+    pub fn premium_index(&self, mark_price: Price, index_price: Price) -> FundingRate {
+        Self::index_ratio(self.calculate_premium(mark_price, index_price), index_price)
+    }
+
+    /// Sample the premium index for the funding TWAP (at most once per
+    /// `premium_sample_secs`)
+    pub fn record_premium(&self, mark_price: Price, index_price: Price, at: Timestamp) {
+        let premium_index = self.premium_index(mark_price, index_price);
+        self.sampler.lock().unwrap().record(premium_index, at);
+    }
+
+    /// Time-weighted premium index over the funding interval ending at
+    /// `until`; None until a sample has been recorded in it
+    pub fn premium_twap(&self, until: Timestamp) -> Option<FundingRate> {
+        let interval_ms = self.config.funding_interval.duration().as_millis() as u64;
+        let since = Timestamp::from_millis(until.physical.saturating_sub(interval_ms));
+        self.sampler.lock().unwrap().twap(since, until)
     }
 
     /// Calculate funding rate from premium
-    /// Formula: funding_rate = clamp(premium / index_price, -max_rate, +max_rate)
     pub fn calculate_rate(
         &self,
         premium: Price,
         index_price: Price,
     ) -> FundingRate {
-        let rate = premium.to_f64() / index_price.to_f64();
-        let clamped = rate.max(-self.config.max_funding_rate)
-            .min(self.config.max_funding_rate);
+        self.calculate_components(premium, index_price).funding_rate
+    }
 
-        FundingRate::from_f64(clamped)
+    /// Funding rate and the components it is built from, for a single
+    /// premium observation
+    pub fn calculate_components(
+        &self,
+        premium: Price,
+        index_price: Price,
+    ) -> FundingRateComponents {
+        self.components_for(Self::index_ratio(premium, index_price))
+    }
+
+    /// Funding rate and the components it is built from
+    /// Formula (see `FundingConfig`):
+    /// - interest_adjustment = clamp(interest_rate - premium_index, -interest_clamp, +interest_clamp)
+    /// - funding_rate = clamp(premium_index + interest_adjustment, min_rate, max_rate)
+    pub fn components_for(&self, premium_index: FundingRate) -> FundingRateComponents {
+        let interest_rate = FundingRate::from_f64(self.config.interest_rate);
+        let interest_clamp = FundingRate::from_f64(self.config.interest_clamp);
+
+        let interest_adjustment = FundingRate::from_i64(interest_rate.to_i64() - premium_index.to_i64())
+            .clamp(FundingRate::from_i64(-interest_clamp.to_i64()), interest_clamp);
+        let unclamped_rate = FundingRate::from_i64(premium_index.to_i64() + interest_adjustment.to_i64());
+        let funding_rate = unclamped_rate.clamp(
+            FundingRate::from_f64(self.config.min_funding_rate()),
+            FundingRate::from_f64(self.config.max_funding_rate),
+        );
+
+        FundingRateComponents {
+            premium_index,
+            interest_rate,
+            interest_adjustment,
+            unclamped_rate,
+            funding_rate,
+        }
     }
 
     /// Calculate premium from mark and index prices
@@ -33,4 +151,21 @@ impl FundingRateCalculator {
     ) -> Price {
         mark_price - index_price
     }
+
+    /// Rounding policy applied to individual funding payments
+    pub fn payment_rounding(&self) -> RoundingPolicy {
+        self.config.payment_rounding
+    }
+
+    pub fn settlement(&self) -> FundingSettlement {
+        self.config.settlement
+    }
+
+    fn index_ratio(premium: Price, index_price: Price) -> FundingRate {
+        if index_price.to_i64() > 0 {
+            FundingRate::from_f64(premium.to_f64() / index_price.to_f64())
+        } else {
+            FundingRate::zero()
+        }
+    }
 }
\ No newline at end of file
@@ -1,51 +1,49 @@
-use tokio::time::{interval, Duration};
+use std::sync::Arc;
+use tokio::time::Duration;
+use crate::events::base::{BaseEvent, EventPayload};
+use crate::events::funding::FundingEvent;
 use crate::funding::applicator::FundingApplicator;
-use crate::types::*;
-use crate::types::position::Position;
-use crate::interfaces::balance_provider::BalanceProvider;
+use crate::interfaces::event_producer::EventProducer;
 use crate::error::Result;
 use crate::types::ids::MarketId;
 use crate::types::price::Price;
 
-pub struct FundingTicker {
-    applicator: FundingApplicator,
+/// Publishes a `Funding` event to the event log once per funding interval
+///
+/// The event carries the rate and the prices it was set at, never payments:
+/// `EventProcessor::process_funding` computes them from its own positions
+/// and applies them like any other event, so funding is replayed from the
+/// log and the processor stays the single reader and writer of positions.
+pub struct FundingTicker<P: EventProducer> {
+    applicator: Arc<FundingApplicator>,
+    producer: Arc<P>,
     interval: Duration,
 }
 
-impl FundingTicker {
-    pub fn new(applicator: FundingApplicator, interval: Duration) -> Self {
-        FundingTicker { applicator, interval }
+impl<P: EventProducer> FundingTicker<P> {
+    pub fn new(applicator: Arc<FundingApplicator>, producer: Arc<P>, interval: Duration) -> Self {
+        FundingTicker { applicator, producer, interval }
     }
 
-    pub async fn run(
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Compute the funding rate at these prices and publish it
+    pub async fn publish(
         &self,
-        mut positions: Vec<Position>,
         mark_price: Price,
         index_price: Price,
-        balance_provider: &mut dyn BalanceProvider,
         market_id: MarketId,
-    ) -> Result<()> {
-        let mut ticker = interval(self.interval);
-
-        loop {
-            ticker.tick().await;
+    ) -> Result<FundingEvent> {
+        let event = self.applicator.calculate_funding(mark_price, index_price, market_id)?;
 
-            // Apply funding
-            let event = self.applicator.apply_funding(
-                &mut positions,
-                mark_price,
-                index_price,
-                balance_provider,
-                market_id,
-            )?;
+        let base = event.base.clone();
+        self.producer.produce(BaseEvent {
+            payload: EventPayload::Funding(Box::new(event.clone())),
+            ..base
+        }).await?;
 
-            tracing::info!(
-                "Funding applied: rate={:.6}, payments={}",
-                event.funding_rate.to_f64(),
-                event.payments.len()
-            );
-
-            // Emit event (would be sent to event log in production)
-        }
+        Ok(event)
     }
-}
\ No newline at end of file
+}
@@ -5,7 +5,39 @@ use crate::types::ids::UserId;
 
 pub trait BalanceProvider {
     fn get_account(&self, user_id: UserId) -> Result<&Account>;
+    fn get_account_mut(&mut self, user_id: UserId) -> Result<&mut Account>;
+    fn create_account(&mut self, user_id: UserId) -> Result<Account>;
     fn adjust_balance(&mut self, user_id: UserId, amount: Balance) -> Result<()>;
+    /// Credit (or debit) realized PnL, also adding it to the account's running total
+    fn settle_realized_pnl(&mut self, user_id: UserId, pnl: Balance, reference_id: &str) -> Result<()>;
+    /// Debit a fee; a negative fee is a rebate and credits the account
+    fn charge_fee(&mut self, user_id: UserId, fee: Balance, reference_id: &str) -> Result<()>;
+    /// Credit (or debit) a funding payment
+    fn settle_funding(&mut self, user_id: UserId, payment: Balance, reference_id: &str) -> Result<()>;
+    /// Book a funding interval's rounding residual on the funding residual
+    /// account (created on first use); negative debits it
+    fn settle_funding_residual(&mut self, residual: Balance, reference_id: &str) -> Result<()>;
+    /// Move a liquidation penalty from the account to the insurance fund
+    /// account (created on first use)
+    fn charge_liquidation_penalty(&mut self, user_id: UserId, penalty: Balance, reference_id: &str) -> Result<()>;
+    /// Credit a bankrupt account with what the insurance fund covered,
+    /// debiting the insurance fund account (created on first use)
+    fn cover_from_insurance_fund(&mut self, user_id: UserId, amount: Balance, reference_id: &str) -> Result<()>;
+    /// Debit each winner its haircut and credit the total to the bankrupt account
+    fn socialize_loss(&mut self, user_id: UserId, haircuts: &[(UserId, Balance)], reference_id: &str) -> Result<()>;
+    /// Move an account's balance by `delta` for an operator repair, posting
+    /// the opposite amount on the repair suspense account (created on first use)
+    fn repair_balance(&mut self, user_id: UserId, delta: Balance, reference_id: &str) -> Result<()>;
+    /// Open a sub-account under `master_id` (see `settlement::sub_accounts`)
+    fn create_sub_account(&mut self, master_id: UserId, sub_account_id: UserId, label: &str) -> Result<Account>;
+    /// Sub-accounts of `master_id`, oldest first
+    fn sub_accounts(&self, master_id: UserId) -> Vec<&Account>;
+    /// Move `amount` from one account to another as a paired debit and credit;
+    /// fails without posting if the sender has less available, and with
+    /// `DoubleEntryImbalance` if the ledger pair does not balance
+    fn transfer(&mut self, from_user: UserId, to_user: UserId, amount: Balance, reference_id: &str) -> Result<()>;
+    /// Every account, system accounts included
+    fn accounts(&self) -> Vec<&Account>;
     fn reserve_margin(&mut self, user_id: UserId, amount: Balance) -> Result<()>;
     fn release_margin(&mut self, user_id: UserId, amount: Balance) -> Result<()>;
 }
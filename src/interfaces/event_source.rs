@@ -0,0 +1,7 @@
+use crate::error::Result;
+
+/// Read side of the event log, as the event loop drives it
+pub trait EventSource {
+    /// Position the source so the next event read is `sequence`
+    fn seek_to_sequence(&self, sequence: u64) -> Result<()>;
+}
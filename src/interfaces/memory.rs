@@ -0,0 +1,119 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use async_trait::async_trait;
+use crate::error::{Error, Result};
+use crate::events::base::BaseEvent;
+use crate::interfaces::event_producer::EventProducer;
+use crate::interfaces::event_source::EventSource;
+use crate::interfaces::position_provider::PositionProvider;
+use crate::types::ids::UserId;
+
+/// Event producer that keeps events in memory instead of sending them to Kafka
+///
+/// Stands in for `KafkaEventProducer` when the EventProcessor is driven
+/// without a broker (handler tests, dry runs); sequences start at 1.
+pub struct InMemoryEventProducer {
+    events: Mutex<Vec<BaseEvent>>,
+    next_sequence: AtomicU64,
+}
+
+impl Default for InMemoryEventProducer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl InMemoryEventProducer {
+    pub fn new() -> Self {
+        InMemoryEventProducer {
+            events: Mutex::new(Vec::new()),
+            next_sequence: AtomicU64::new(1),
+        }
+    }
+
+    /// Events produced so far, in production order
+    pub fn events(&self) -> Vec<BaseEvent> {
+        self.events.lock().expect("event buffer poisoned").clone()
+    }
+
+    /// Take the produced events, leaving the buffer empty
+    pub fn drain(&self) -> Vec<BaseEvent> {
+        std::mem::take(&mut *self.events.lock().expect("event buffer poisoned"))
+    }
+}
+
+#[async_trait]
+impl EventProducer for InMemoryEventProducer {
+    async fn produce(&self, mut event: BaseEvent) -> Result<u64> {
+        let sequence = self.next_sequence.fetch_add(1, Ordering::SeqCst);
+        event.sequence = sequence;
+        event.checksum = event.calculate_checksum();
+        self.events.lock().expect("event buffer poisoned").push(event);
+        Ok(sequence)
+    }
+}
+
+/// Event source over a fixed run of logged events
+///
+/// Stands in for `EventConsumer`: seeking works by sequence over the events
+/// it holds, or to the one after the last.
+pub struct InMemoryEventSource {
+    events: Vec<BaseEvent>,
+    next: Mutex<usize>,
+}
+
+impl InMemoryEventSource {
+    pub fn new(events: Vec<BaseEvent>) -> Self {
+        InMemoryEventSource {
+            events,
+            next: Mutex::new(0),
+        }
+    }
+
+    /// The event the next read returns, if any
+    pub fn next_event(&self) -> Option<BaseEvent> {
+        let mut next = self.next.lock().expect("event source poisoned");
+        let event = self.events.get(*next).cloned();
+        if event.is_some() {
+            *next += 1;
+        }
+        event
+    }
+}
+
+impl EventSource for InMemoryEventSource {
+    fn seek_to_sequence(&self, sequence: u64) -> Result<()> {
+        let index = match self.events.iter().position(|event| event.sequence == sequence) {
+            Some(index) => index,
+            None if self.events.last().map_or(0, |event| event.sequence) + 1 == sequence => self.events.len(),
+            None => return Err(Error::UnknownLogSequence(sequence)),
+        };
+        *self.next.lock().expect("event source poisoned") = index;
+        Ok(())
+    }
+}
+
+/// Fixed one-way position sizes, for driving the matcher or risk checks
+/// without a PositionManager
+#[derive(Clone, Debug, Default)]
+pub struct StaticPositions {
+    sizes: HashMap<UserId, i64>,
+}
+
+impl StaticPositions {
+    pub fn new() -> Self {
+        StaticPositions::default()
+    }
+
+    pub fn with_position(mut self, user_id: UserId, size: i64) -> Self {
+        self.sizes.insert(user_id, size);
+        self
+    }
+}
+
+impl PositionProvider for StaticPositions {
+    fn position_size(&self, user_id: UserId) -> i64 {
+        self.sizes.get(&user_id).copied().unwrap_or(0)
+    }
+}
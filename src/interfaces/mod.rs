@@ -1,3 +1,10 @@
 pub mod balance_provider;
 pub mod event_producer;
-pub mod order_submitter;
\ No newline at end of file
+pub mod event_source;
+pub mod order_submitter;
+pub mod position_provider;
+pub mod position_store;
+pub mod order_book_store;
+pub mod order_matcher;
+pub mod memory;
+
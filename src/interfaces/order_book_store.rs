@@ -0,0 +1,37 @@
+use crate::error::Result;
+use crate::events::order::Side;
+use crate::matching::order_book::{Order, QueuePosition};
+use crate::types::ids::{OrderId, UserId};
+use crate::types::price::Price;
+use crate::types::quantity::Quantity;
+use crate::types::timestamp::Timestamp;
+
+/// Resting-order book the EventProcessor keeps (`OrderBook` in production)
+pub trait OrderBookStore {
+    fn add_order(&mut self, order: Order) -> Result<()>;
+    /// Rebuild an empty book from orders in priority order (snapshot restore)
+    fn restore_orders(&mut self, orders: Vec<Order>) -> Result<()>;
+    fn remove_order(&mut self, order_id: &OrderId) -> Result<Order>;
+    fn amend_order(
+        &mut self,
+        order_id: &OrderId,
+        new_price: Price,
+        new_quantity: Quantity,
+        keep_priority: bool,
+        timestamp: Timestamp,
+    ) -> Result<Order>;
+
+    fn get_order(&self, order_id: &OrderId) -> Option<&Order>;
+    /// Every resting order, in no particular order
+    fn resting_orders(&self) -> Vec<&Order>;
+    /// Resting orders of one user, in no particular order
+    fn user_orders(&self, user_id: &UserId) -> Vec<&Order>;
+    fn best_bid(&self) -> Option<Price>;
+    fn best_ask(&self) -> Option<Price>;
+    /// Worst price a taker on `side` would reach filling `quantity`
+    fn sweep_price(&self, side: Side, quantity: Quantity) -> Option<Price>;
+    fn level_queue_positions(&self, side: Side, price: Price) -> Vec<(&Order, QueuePosition)>;
+
+    /// Remove every GTD order expiring at or before `now`
+    fn sweep_expired(&mut self, now: Timestamp) -> Vec<Order>;
+}
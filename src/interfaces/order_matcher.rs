@@ -0,0 +1,68 @@
+use std::collections::HashSet;
+use crate::config::market::PostOnlyMode;
+use crate::error::Result;
+use crate::events::order::{SelfTradePrevented, Side};
+use crate::events::trade::{ExecutionReport, TradeEvent};
+use crate::interfaces::order_book_store::OrderBookStore;
+use crate::interfaces::position_provider::PositionProvider;
+use crate::matching::order_archive::TerminalStatus;
+use crate::matching::order_book::{DepthSnapshot, Order};
+use crate::types::ids::UserId;
+use crate::types::price::Price;
+use crate::types::quantity::Quantity;
+use crate::types::timestamp::Timestamp;
+
+/// Matching engine as seen by the EventProcessor and the liquidation
+/// executor (`Matcher` in production)
+///
+/// The matcher owns the market's only order book; everything else reaches
+/// it through the matching core.
+pub trait OrderMatcher {
+    type Book: OrderBookStore;
+
+    fn order_book(&self) -> &Self::Book;
+    fn order_book_mut(&mut self) -> &mut Self::Book;
+
+    fn match_order(
+        &mut self,
+        order: &Order,
+        position_provider: &dyn PositionProvider,
+        mark_price: Price,
+    ) -> Result<Vec<TradeEvent>>;
+
+    /// Discard the book and any undrained match output, then rebuild the
+    /// book from orders in priority order (snapshot restore)
+    fn reset(&mut self, orders: Vec<Order>) -> Result<()>;
+
+    /// Replace the makers charged the LP maker fee rate
+    fn set_lp_makers(&mut self, lp_makers: HashSet<UserId>);
+
+    /// Time of the event being processed; GTD orders expired as of it never trade
+    fn set_event_time(&mut self, now: Timestamp);
+
+    /// Resting quantity a taker on `side` could reach without going past `limit`
+    fn depth_within(&self, side: Side, limit: Price) -> Quantity;
+    /// Aggregated top `levels` levels per side
+    fn depth(&self, levels: usize) -> DepthSnapshot;
+    /// (best_bid, best_ask) if the book is crossed or locked
+    fn crossed(&self) -> Option<(Price, Price)>;
+    /// Match crossing resting orders until the book is uncrossed
+    fn uncross(
+        &mut self,
+        position_provider: &dyn PositionProvider,
+        mark_price: Price,
+    ) -> Result<Vec<TradeEvent>>;
+    /// Quantity a reduce-only / closing order may still trade
+    fn reduce_only_quantity(&self, order: &Order, position_provider: &dyn PositionProvider) -> Result<Quantity>;
+    fn check_fill_or_kill(&self, order: &Order, mark_price: Price) -> Result<()>;
+    /// Error unless an IOC order can fill its `min_fill_quantity`
+    fn check_min_fill(&self, order: &Order, mark_price: Price) -> Result<()>;
+    fn prepare_post_only(&self, order: &Order, mode: PostOnlyMode, tick_size: Price) -> Result<Order>;
+
+    /// Orders that reached a terminal state during the last match
+    fn drain_completed_orders(&mut self) -> Vec<(Order, TerminalStatus)>;
+    /// Self-trade preventions applied during the last match
+    fn drain_self_trades(&mut self) -> Vec<SelfTradePrevented>;
+    /// Execution reports (taker and makers) from the last match
+    fn drain_execution_reports(&mut self) -> Vec<ExecutionReport>;
+}
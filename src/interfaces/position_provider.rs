@@ -0,0 +1,29 @@
+use crate::events::order::Side;
+use crate::types::ids::UserId;
+use crate::types::position::PositionSide;
+use crate::types::quantity::Quantity;
+
+pub trait PositionProvider {
+    /// Signed position size: positive = long, negative = short, 0 = flat
+    /// (hedge mode: net of both legs)
+    fn position_size(&self, user_id: UserId) -> i64;
+
+    /// Signed size of one leg; `Both` is the one-way net position
+    fn leg_size(&self, user_id: UserId, leg: PositionSide) -> i64 {
+        match leg {
+            PositionSide::Both => self.position_size(user_id),
+            _ => 0,
+        }
+    }
+
+    /// Quantity an order on `side` can trade against `leg` without growing or
+    /// flipping it
+    fn reducible_quantity(&self, user_id: UserId, side: Side, leg: PositionSide) -> Quantity {
+        let size = self.leg_size(user_id, leg);
+        match side {
+            Side::Sell if size > 0 => Quantity::from_i64(size),
+            Side::Buy if size < 0 => Quantity::from_i64(-size),
+            _ => Quantity::zero(),
+        }
+    }
+}
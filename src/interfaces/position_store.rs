@@ -0,0 +1,52 @@
+use crate::config::risk::UserLimits;
+use crate::error::Result;
+use crate::events::order::Side;
+use crate::funding::index::CumulativeFunding;
+use crate::interfaces::position_provider::PositionProvider;
+use crate::types::balance::Balance;
+use crate::types::ids::UserId;
+use crate::types::position::{MarginMode, Position, PositionMode, PositionSide};
+use crate::types::price::Price;
+use crate::types::quantity::Quantity;
+
+/// Position state the EventProcessor reads and writes
+/// (`PositionManager` in production)
+pub trait PositionStore: PositionProvider {
+    fn position_mode(&self, user_id: &UserId) -> PositionMode;
+    fn set_position_mode(&mut self, user_id: UserId, mode: PositionMode) -> Result<()>;
+    fn margin_mode(&self, user_id: &UserId) -> MarginMode;
+    fn set_margin_mode(&mut self, user_id: UserId, mode: MarginMode) -> Result<()>;
+    /// Chosen leverage (None: margined at the tier cap)
+    fn leverage(&self, user_id: &UserId) -> Option<f64>;
+    fn set_leverage(&mut self, user_id: UserId, leverage: Option<f64>);
+    /// Operator override of the account's exposure caps
+    fn user_limits(&self, user_id: &UserId) -> Option<&UserLimits>;
+    fn set_user_limits(&mut self, user_id: UserId, limits: UserLimits);
+    /// Sum of long sizes in the market
+    fn open_interest(&self) -> Quantity;
+    fn cumulative_funding(&self) -> CumulativeFunding;
+    fn set_cumulative_funding(&mut self, funding: CumulativeFunding);
+
+    /// One-way (net) position
+    fn get_position(&self, user_id: &UserId) -> Option<&Position>;
+    fn get_leg(&self, user_id: &UserId, leg: PositionSide) -> Option<&Position>;
+    fn get_leg_mut(&mut self, user_id: &UserId, leg: PositionSide) -> Option<&mut Position>;
+    /// Every leg the user holds
+    fn positions_for(&self, user_id: &UserId) -> Vec<&Position>;
+    fn positions_for_mut(&mut self, user_id: &UserId) -> Vec<&mut Position>;
+    fn get_all_positions(&self) -> Vec<&Position>;
+
+    fn set_position(&mut self, user_id: UserId, position: Position);
+    fn remove_position(&mut self, user_id: &UserId) -> Option<Position>;
+    fn remove_leg(&mut self, user_id: &UserId, leg: PositionSide) -> Option<Position>;
+
+    /// Apply a fill to one leg; returns the PnL it realized
+    fn update_leg(
+        &mut self,
+        user_id: UserId,
+        leg: PositionSide,
+        trade_side: Side,
+        trade_quantity: Quantity,
+        trade_price: Price,
+    ) -> Result<Balance>;
+}
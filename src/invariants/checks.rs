@@ -9,15 +9,59 @@ use crate::settlement::balance_manager::BalanceManager;
 use crate::types::balance::Balance;
 use crate::types::price::Price;
 use crate::types::quantity::Quantity;
+use crate::types::timestamp::Timestamp;
+use std::time::Duration;
 
 pub struct InvariantChecks;
 
+/// Tracks how long mark price has diverged from index price beyond a hard bound
+pub struct MarkIndexDivergenceTracker {
+    max_deviation: f64,
+    max_duration: Duration,
+    diverging_since: Option<Timestamp>,
+}
+
+impl MarkIndexDivergenceTracker {
+    pub fn new(max_deviation: f64, max_duration: Duration) -> Self {
+        MarkIndexDivergenceTracker {
+            max_deviation,
+            max_duration,
+            diverging_since: None,
+        }
+    }
+}
+
+impl Default for MarkIndexDivergenceTracker {
+    fn default() -> Self {
+        MarkIndexDivergenceTracker::new(
+            0.03,                     // 3% hard bound
+            Duration::from_secs(60),  // sustained for 60 seconds
+        )
+    }
+}
+
 impl InvariantChecks {
+    /// Check the book is neither crossed nor locked (best_bid < best_ask)
+    pub fn check_book_not_crossed(order_book: &OrderBook) -> Result<()> {
+        if let Some((best_bid, best_ask)) = order_book.crossed() {
+            return Err(Error::InvariantViolation(InvariantViolation {
+                invariant: "book_not_crossed",
+                details: format!(
+                    "Best bid {} is not below best ask {}",
+                    best_bid.to_i64(),
+                    best_ask.to_i64()
+                ),
+            }));
+        }
+
+        Ok(())
+    }
+
     /// Check order book consistency - IMPLEMENTED
     pub fn check_order_book_consistency(order_book: &OrderBook) -> Result<()> {
         // Verify price levels match order quantities
         for (price, level) in &order_book.bids {
-            let calculated_qty: i64 = level.orders.iter()
+            let calculated_qty: i64 = order_book.level_orders(level)
                 .map(|o| (o.quantity - o.filled).to_i64())
                 .sum();
 
@@ -35,7 +79,7 @@ impl InvariantChecks {
         }
 
         for (price, level) in &order_book.asks {
-            let calculated_qty: i64 = level.orders.iter()
+            let calculated_qty: i64 = order_book.level_orders(level)
                 .map(|o| (o.quantity - o.filled).to_i64())
                 .sum();
 
@@ -56,10 +100,12 @@ impl InvariantChecks {
     }
 
     /// Check no negative balances
+    /// System accounts are exempt: the funding residual account nets
+    /// rounding in both directions and can run slightly negative
     pub fn check_no_negative_balances(
         balance_manager: &BalanceManager,
     ) -> Result<()> {
-        for account in balance_manager.accounts.values() {
+        for account in balance_manager.accounts.values().filter(|a| !a.user_id.is_system()) {
             if account.balance < Balance::zero() {
                 return Err(Error::InvariantViolation(InvariantViolation {
                     invariant: "no_negative_balances",
@@ -89,7 +135,7 @@ impl InvariantChecks {
             }
 
             let account = balance_manager.get_account(position.user_id)?;
-            let unrealized_pnl = PnLCalculator::calculate_unrealized_pnl(position, mark_price);
+            let unrealized_pnl = PnLCalculator::calculate_unrealized_pnl(position, mark_price)?;
             let maintenance_margin = margin_calc.calculate_maintenance_margin(
                 position.abs_size(),
                 mark_price,
@@ -117,15 +163,16 @@ impl InvariantChecks {
         Ok(())
     }
 
-    /// Check funding zero-sum
+    /// Check funding zero-sum (payments plus the rounding residual)
     pub fn check_funding_zero_sum(
         payments: &[crate::events::funding::FundingPayment],
+        residual: Balance,
     ) -> Result<()> {
         let sum: i64 = payments.iter()
             .map(|p| p.payment.to_i64())
-            .sum();
+            .sum::<i64>() + residual.to_i64();
 
-        if sum.abs() > 1 {
+        if sum != 0 {
             return Err(Error::InvariantViolation(InvariantViolation {
                 invariant: "funding_zero_sum",
                 details: format!("Funding payments sum to {}, expected 0", sum),
@@ -206,7 +253,7 @@ impl InvariantChecks {
             }
 
             let account = balance_manager.get_account(position.user_id)?;
-            let unrealized_pnl = PnLCalculator::calculate_unrealized_pnl(position, mark_price);
+            let unrealized_pnl = PnLCalculator::calculate_unrealized_pnl(position, mark_price)?;
             let collateral = account.balance.to_i64() + unrealized_pnl.to_i64();
             let maintenance_margin = margin_calc.calculate_maintenance_margin(
                 position.abs_size(),
@@ -261,4 +308,45 @@ impl InvariantChecks {
 
         Ok(())
     }
+
+    /// INV-009: Check mark price does not stay diverged from index price
+    /// Unlike the price circuit breaker (instantaneous), this flags a premium EMA
+    /// that has run away for longer than the tracker's allowed duration
+    pub fn check_mark_index_divergence(
+        tracker: &mut MarkIndexDivergenceTracker,
+        mark_price: Price,
+        index_price: Price,
+        now: Timestamp,
+    ) -> Result<()> {
+        if index_price <= Price::zero() {
+            return Ok(());
+        }
+
+        let deviation = (mark_price - index_price).abs().to_f64() / index_price.to_f64();
+
+        if deviation <= tracker.max_deviation {
+            tracker.diverging_since = None;
+            return Ok(());
+        }
+
+        let since = *tracker.diverging_since.get_or_insert(now);
+        let diverged_for = now - since;
+
+        if diverged_for > tracker.max_duration {
+            return Err(Error::InvariantViolation(InvariantViolation {
+                invariant: "INV-009: mark_index_divergence",
+                details: format!(
+                    "Mark price {} diverged from index price {} by {:.4}% for {}s (max {:.4}% for {}s)",
+                    mark_price,
+                    index_price,
+                    deviation * 100.0,
+                    diverged_for.as_secs(),
+                    tracker.max_deviation * 100.0,
+                    tracker.max_duration.as_secs()
+                ),
+            }));
+        }
+
+        Ok(())
+    }
 }
\ No newline at end of file
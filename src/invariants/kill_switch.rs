@@ -2,6 +2,7 @@ use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use crate::types::ids::OperatorId;
 
+#[derive(Clone)]
 pub struct KillSwitch {
     active: Arc<AtomicBool>,
 }
@@ -1,15 +1,17 @@
-use crate::invariants::checks::InvariantChecks;
+use crate::invariants::checks::{InvariantChecks, MarkIndexDivergenceTracker};
 use crate::invariants::kill_switch::KillSwitch;
-use crate::matching::order_book::OrderBook;
 use crate::settlement::balance_manager::BalanceManager;
 use crate::types::*;
 use crate::error::Result;
 use tokio::time::{interval, Duration};
 use crate::types::price::Price;
+use crate::types::timestamp::Timestamp;
+use std::sync::Mutex;
 
 pub struct InvariantMonitor {
     kill_switch: KillSwitch,
     check_interval: Duration,
+    divergence_tracker: Mutex<MarkIndexDivergenceTracker>,
 }
 
 impl InvariantMonitor {
@@ -17,15 +19,16 @@ impl InvariantMonitor {
         InvariantMonitor {
             kill_switch,
             check_interval: Duration::from_secs(1),
+            divergence_tracker: Mutex::new(MarkIndexDivergenceTracker::default()),
         }
     }
 
     pub async fn run(
         &self,
-        order_book: &OrderBook,
         balance_manager: &BalanceManager,
         positions: &[position::Position],
         mark_price: Price,
+        index_price: Price,
     ) {
         let mut ticker = interval(self.check_interval);
 
@@ -33,10 +36,10 @@ impl InvariantMonitor {
             ticker.tick().await;
 
             if let Err(e) = self.check_all_invariants(
-                order_book,
                 balance_manager,
                 positions,
                 mark_price,
+                index_price,
             ) {
                 tracing::error!("Invariant violation detected: {:?}", e);
                 self.kill_switch.activate(format!("{:?}", e));
@@ -44,17 +47,26 @@ impl InvariantMonitor {
         }
     }
 
-    fn check_all_invariants(
+    /// Account, margin and price invariants; the book's are checked on the
+    /// matching core (`MatchingCoreHandle::book_invariants`)
+    pub fn check_all_invariants(
         &self,
-        order_book: &OrderBook,
         balance_manager: &BalanceManager,
         positions: &[crate::types::position::Position],
         mark_price: Price,
+        index_price: Price,
     ) -> Result<()> {
-        InvariantChecks::check_order_book_consistency(order_book)?;
         InvariantChecks::check_no_negative_balances(balance_manager)?;
         InvariantChecks::check_margin_requirements(balance_manager, positions, mark_price)?;
 
+        let mut divergence_tracker = self.divergence_tracker.lock().unwrap();
+        InvariantChecks::check_mark_index_divergence(
+            &mut divergence_tracker,
+            mark_price,
+            index_price,
+            Timestamp::now(),
+        )?;
+
         Ok(())
     }
 }
\ No newline at end of file
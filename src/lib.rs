@@ -1,3 +1,5 @@
+#![recursion_limit = "256"]  // lazy_static! in observability::metrics
+
 use std::sync::Arc;
 use std::sync::atomic::AtomicBool;
 use lazy_static::lazy_static;
@@ -23,6 +25,8 @@ pub mod observability;
 pub mod interfaces;
 pub mod api;
 pub mod controls;
+pub mod algo;
+pub mod notifications;
 
 lazy_static! {
     pub static ref KILL_SWITCH: Arc<AtomicBool> = Arc::new(AtomicBool::new(false));
@@ -32,7 +36,7 @@ lazy_static! {
 }
 
 // Snapshot version
-pub const SNAPSHOT_VERSION: u32 = 1;
+pub const SNAPSHOT_VERSION: u32 = 14;  // v14: in-flight fills
 
 // Funding rate multiplier
 pub const FUNDING_RATE_MULTIPLIER: i64 = 100_000_000;
\ No newline at end of file
@@ -1,62 +1,212 @@
+use std::collections::HashMap;
 use crate::types::*;
-use crate::types::position::Position;
+use crate::types::position::{Position, PositionSide};
+use crate::risk::liquidation_price::{liquidation_prices, maintenance_surplus};
 use crate::risk::margin::MarginCalculator;
-use crate::risk::pnl::PnLCalculator;
+use crate::risk::pnl::{FundingAccrual, PnLCalculator};
 use crate::interfaces::balance_provider::BalanceProvider;
 use crate::error::Result;
 use crate::types::balance::Balance;
 use crate::types::ids::UserId;
 use crate::types::price::Price;
+use crate::types::quantity::Quantity;
 use crate::types::ratio::Ratio;
 
 pub struct LiquidationDetector {
     margin_calculator: MarginCalculator,
+    margin_call_ratio: f64,  // Margin calls below this margin ratio (0 = none)
 }
 
 impl LiquidationDetector {
     pub fn new(margin_calculator: MarginCalculator) -> Self {
-        LiquidationDetector { margin_calculator }
+        LiquidationDetector { margin_calculator, margin_call_ratio: 0.0 }
     }
 
+    pub fn with_margin_call_ratio(mut self, margin_call_ratio: f64) -> Self {
+        self.margin_call_ratio = margin_call_ratio;
+        self
+    }
+
+    /// Liquidation candidates only (see `scan`)
     pub fn detect_liquidations(
         &self,
         positions: &[Position],
         mark_price: Price,
+        funding: Option<&FundingAccrual>,
         balance_provider: &dyn BalanceProvider,
     ) -> Result<Vec<LiquidationCandidate>> {
+        Ok(self.scan(positions, mark_price, funding, balance_provider)?.liquidations)
+    }
+
+    /// Cross legs are checked per account: hedge-mode legs share the
+    /// account's equity (less any isolated buckets) and their maintenance
+    /// margins add up (no netting between legs). A liquidatable account
+    /// yields one candidate per open cross leg.
+    /// Isolated legs are checked alone against their own bucket.
+    ///
+    /// Legs are liquidatable once their `maintenance_surplus` is negative,
+    /// the same test `risk::liquidation_price` solves for, so published
+    /// liquidation prices match this detector. Equity includes funding
+    /// accrued since the last settlement when `funding` is given, and
+    /// maintenance margin the concentration surcharge for each leg's share
+    /// of open interest (the long sizes in `positions`).
+    ///
+    /// Legs not liquidatable but with a margin ratio under
+    /// `margin_call_ratio` get a margin call instead (one per isolated leg,
+    /// one per account for its cross legs).
+    /// Fails with `Error::Overflow` if a leg can't be valued at `mark_price`.
+    pub fn scan(
+        &self,
+        positions: &[Position],
+        mark_price: Price,
+        funding: Option<&FundingAccrual>,
+        balance_provider: &dyn BalanceProvider,
+    ) -> Result<MarginScan> {
+        // Group legs by account, keeping first-seen order
+        let mut accounts: Vec<UserId> = Vec::new();
+        let mut legs: HashMap<UserId, Vec<&Position>> = HashMap::new();
+        for position in positions.iter().filter(|p| !p.is_flat()) {
+            legs.entry(position.user_id)
+                .or_insert_with(|| {
+                    accounts.push(position.user_id);
+                    Vec::new()
+                })
+                .push(position);
+        }
+
+        let open_interest = positions.iter()
+            .filter(|position| position.is_long())
+            .fold(Quantity::zero(), |total, position| total + position.abs_size());
+        let contract = self.margin_calculator.contract();
         let mut candidates = Vec::new();
+        let mut margin_calls = Vec::new();
+
+        for user_id in accounts {
+            let (isolated, cross): (Vec<&Position>, Vec<&Position>) = legs[&user_id].iter()
+                .partition(|position| position.is_isolated());
+
+            for position in &isolated {
+                let maintenance_margin = self.margin_calculator.calculate_concentrated_maintenance_margin(
+                    position.abs_size(),
+                    mark_price,
+                    open_interest,
+                );
+                let equity = PnLCalculator::account_equity(contract, position.isolated_margin, &[*position], mark_price, funding)?;
+                let margin_ratio = self.margin_calculator.calculate_margin_ratio(equity, Balance::zero(), maintenance_margin);
+                let surplus = maintenance_surplus(
+                    &self.margin_calculator,
+                    &[*position],
+                    position.isolated_margin,
+                    mark_price,
+                    open_interest,
+                    funding,
+                )?;
+                if surplus < Balance::zero() {
+                    let prices = liquidation_prices(
+                        &self.margin_calculator,
+                        &[*position],
+                        position.isolated_margin,
+                        mark_price,
+                        open_interest,
+                        funding,
+                    );
+                    candidates.push(LiquidationCandidate {
+                        user_id,
+                        position: (*position).clone(),
+                        margin_ratio,
+                        maintenance_margin,
+                        mark_price,
+                        bankruptcy_price: prices.bankruptcy_price,
+                        maintenance_surplus: surplus,
+                    });
+                } else if self.is_margin_call(margin_ratio) {
+                    margin_calls.push(MarginCall {
+                        user_id,
+                        position_side: Some(position.position_side),
+                        margin_ratio,
+                        equity,
+                        maintenance_margin,
+                        mark_price,
+                    });
+                }
+            }
 
-        for position in positions {
-            if position.is_flat() {
+            if cross.is_empty() {
                 continue;
             }
 
-            let account = balance_provider.get_account(position.user_id)?;
-            let unrealized_pnl = PnLCalculator::calculate_unrealized_pnl(position, mark_price);
-            let maintenance_margin = self.margin_calculator.calculate_maintenance_margin(
-                position.abs_size(),
-                mark_price,
-            );
-
-            let margin_ratio = self.margin_calculator.calculate_margin_ratio(
-                account.balance,
-                unrealized_pnl,
-                maintenance_margin,
-            );
-
-            if self.margin_calculator.is_liquidatable(margin_ratio) {
-                candidates.push(LiquidationCandidate {
-                    user_id: position.user_id,
-                    position: position.clone(),
+            // Isolated buckets are not available to cross positions
+            let account = balance_provider.get_account(user_id)?;
+            let isolated_margin = isolated.iter()
+                .fold(Balance::zero(), |total, position| total + position.isolated_margin);
+
+            let mut maintenance_margin = Balance::zero();
+            for position in &cross {
+                maintenance_margin = maintenance_margin + self.margin_calculator.calculate_concentrated_maintenance_margin(
+                    position.abs_size(),
+                    mark_price,
+                    open_interest,
+                );
+            }
+
+            let collateral = account.balance - isolated_margin;
+            let equity = PnLCalculator::account_equity(contract, collateral, &cross, mark_price, funding)?;
+            let margin_ratio = self.margin_calculator.calculate_margin_ratio(equity, Balance::zero(), maintenance_margin);
+
+            let surplus = maintenance_surplus(&self.margin_calculator, &cross, collateral, mark_price, open_interest, funding)?;
+            if surplus < Balance::zero() {
+                let prices = liquidation_prices(&self.margin_calculator, &cross, collateral, mark_price, open_interest, funding);
+                for position in cross {
+                    candidates.push(LiquidationCandidate {
+                        user_id,
+                        position: position.clone(),
+                        margin_ratio,
+                        maintenance_margin: self.margin_calculator.calculate_concentrated_maintenance_margin(
+                            position.abs_size(),
+                            mark_price,
+                            open_interest,
+                        ),
+                        mark_price,
+                        bankruptcy_price: prices.bankruptcy_price,
+                        maintenance_surplus: surplus,
+                    });
+                }
+            } else if self.is_margin_call(margin_ratio) {
+                margin_calls.push(MarginCall {
+                    user_id,
+                    position_side: None,
                     margin_ratio,
+                    equity,
                     maintenance_margin,
                     mark_price,
                 });
             }
         }
 
-        Ok(candidates)
+        Ok(MarginScan { liquidations: candidates, margin_calls })
     }
+
+    fn is_margin_call(&self, margin_ratio: Ratio) -> bool {
+        self.margin_call_ratio > 0.0 && margin_ratio.to_f64() < self.margin_call_ratio
+    }
+}
+
+/// Outcome of one detector pass
+#[derive(Clone, Debug, Default)]
+pub struct MarginScan {
+    pub liquidations: Vec<LiquidationCandidate>,
+    pub margin_calls: Vec<MarginCall>,
+}
+
+/// Account (cross legs) or isolated leg close to liquidation
+#[derive(Clone, Debug)]
+pub struct MarginCall {
+    pub user_id: UserId,
+    pub position_side: Option<PositionSide>,  // Isolated leg; None for the account's cross legs
+    pub margin_ratio: Ratio,
+    pub equity: Balance,
+    pub maintenance_margin: Balance,
+    pub mark_price: Price,
 }
 
 #[derive(Clone, Debug)]
@@ -66,4 +216,6 @@ pub struct LiquidationCandidate {
     pub margin_ratio: Ratio,
     pub maintenance_margin: Balance,
     pub mark_price: Price,
+    pub bankruptcy_price: Option<Price>,  // Where an insurance fund takeover is priced (None: never bankrupt)
+    pub maintenance_surplus: Balance,     // Negative while the leg is liquidatable
 }
\ No newline at end of file
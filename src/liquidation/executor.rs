@@ -1,52 +1,189 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 use std::sync::atomic::{AtomicBool, Ordering};
+use crate::config::{LiquidationConfig, LiquidationPriority};
+use crate::config::market::ContractSpec;
 use crate::error::{Error, Result};
-use crate::events::base::BaseEvent;
-use crate::events::liquidation::{LiquidationEvent, LiquidationType};
+use crate::events::base::{BaseEvent, EventPayload, EventType};
+use crate::events::liquidation::{
+    LiquidationCompleted, LiquidationEvent, LiquidationExecution, LiquidationFailed, LiquidationFailureReason,
+    LiquidationFillReceived, LiquidationOutcome, LiquidationStarted, LiquidationType,
+};
 use crate::events::order::{OrderType, Side, TimeInForce};
 use crate::interfaces::balance_provider::BalanceProvider;
+use crate::interfaces::order_matcher::OrderMatcher;
+use crate::interfaces::position_provider::PositionProvider;
 use crate::liquidation::detector::LiquidationCandidate;
 use crate::liquidation::insurance_fund::InsuranceFund;
+use crate::liquidation::pending::PendingLiquidations;
 use crate::liquidation::priority_queue::LiquidationPriorityQueue;
 use crate::liquidation::rate_limiter::RateLimiter;
-use crate::matching::matcher::Matcher;
 use crate::matching::order_book::Order;
 use crate::types::balance::Balance;
-use crate::types::ids::MarketId;
+use crate::types::ids::{LiquidationId, MarketId, UserId};
 use crate::types::quantity::Quantity;
 use crate::types::timestamp::Timestamp;
 use std::time::Duration;
 use crate::LIQUIDATION_ENGINE_USER_ID;
-use crate::observability::metrics::{INSURANCE_FUND_BALANCE, LIQUIDATIONS_EXECUTED};
-use crate::types::position::Position;
+use crate::observability::metrics::{INSURANCE_FUND_CONTRIBUTIONS, INSURANCE_TAKEOVERS, LIQUIDATIONS_EXECUTED, LIQUIDATIONS_RESTORED, LIQUIDATION_SLICES_PENDING, LIQUIDATION_TRIGGERS_SUPPRESSED};
+use crate::types::position::{Position, PositionSide};
 use crate::types::price::Price;
+use crate::types::ratio::Ratio;
+
+/// Liquidation being closed out over several child orders
+#[derive(Clone, Debug)]
+pub struct SlicedLiquidation {
+    pub liquidation_id: LiquidationId,
+    pub user_id: UserId,
+    pub position_side: PositionSide,
+    pub margin_ratio: Ratio,          // At the trigger that started it
+    pub maintenance_margin: Balance,
+    pub remaining: Quantity,
+    pub slices: u32,                  // Child orders sent so far
+    pub next_slice_at: Timestamp,
+}
 
+/// Executes liquidations from the priority queue
+///
+/// ## Laddering
+/// - Each child order is an IOC limit at mark; what it leaves unfilled is
+///   retried `ladder_step_bps` further from mark, out to `backstop_bps`
+/// - If nothing fills out to the backstop, the insurance fund takes the
+///   rest of the liquidation over at the account's bankruptcy price
+/// - Fills are charged `penalty_bps` of their notional, paid into the
+///   insurance fund (a takeover, at zero equity, is not)
+///
+/// ## Steps
+/// - Child orders share a sliding-window rate limit (event time); a step
+///   over the limit is deferred to when the window reopens, keeping its
+///   liquidation's progress
+/// - Each step re-checks the leg's maintenance surplus: once it is no
+///   longer negative the liquidation ends with what is left unsold
+///
+/// ## Lifecycle
+/// - Each liquidation records `LiquidationStarted` with its first child
+///   order, `LiquidationFillReceived` per fill, and ends with
+///   `LiquidationCompleted` or a final `LiquidationFailed`; the processor
+///   logs them after every step (`drain_lifecycle`)
+///
+/// ## Triggers
+/// - The monitor claims a leg before sending its trigger (`claim_trigger`);
+///   the claim holds until the executor has handled the trigger, then until
+///   the next child order is due, and is released when the liquidation ends
+///
+/// ## Slicing
+/// - A close-out is sent as IOC child orders, each at most
+///   `max_participation_rate` of the opposite depth within `depth_band_bps`
+///   of mark (never below `min_slice_size`)
+/// - What a child leaves unfilled stays with the liquidation: the next
+///   trigger for the same leg after `slice_interval_ms` sends the next child
+///   under the same `liquidation_id` instead of sizing a new liquidation
+/// - Triggers arriving before the interval has passed are skipped, and all
+///   timing uses event time, so replay slices identically
 pub struct LiquidationExecutor {
-    queue: LiquidationPriorityQueue,
-    rate_limiter: RateLimiter,
-    insurance_fund: InsuranceFund,
+    queue: Mutex<LiquidationPriorityQueue>,  // Re-ordered on every mark price
+    rate_limiter: Mutex<RateLimiter>,
+    insurance_fund: Arc<InsuranceFund>,
     market_id: MarketId,
+    contract: ContractSpec,
     halted: AtomicBool,
+    slicing: LiquidationConfig,
+    active: Mutex<HashMap<(UserId, PositionSide), SlicedLiquidation>>,
+    lifecycle: Mutex<Vec<BaseEvent>>,  // Started/fill/completed/failed events not yet logged
+    pending: Mutex<PendingLiquidations>,  // Legs with a trigger in flight
 }
 
 impl LiquidationExecutor {
-    pub fn new(market_id: MarketId) -> Self {
+    pub fn new(market_id: MarketId, insurance_fund: Arc<InsuranceFund>) -> Self {
         LiquidationExecutor {
-            queue: LiquidationPriorityQueue::new(),
-            rate_limiter: RateLimiter::new(10, Duration::from_secs(1)),
-            insurance_fund: InsuranceFund::new(),
+            queue: Mutex::new(LiquidationPriorityQueue::new(LiquidationPriority::default())),
+            rate_limiter: Mutex::new(RateLimiter::new(10, Duration::from_secs(1))),
+            insurance_fund,
             market_id,
+            contract: ContractSpec::default(),
             halted: AtomicBool::new(false),
+            slicing: LiquidationConfig::default(),
+            active: Mutex::new(HashMap::new()),
+            lifecycle: Mutex::new(Vec::new()),
+            pending: Mutex::new(PendingLiquidations::new(Duration::from_millis(LiquidationConfig::default().trigger_ttl_ms))),
+        }
+    }
+
+    pub fn with_slicing(mut self, slicing: LiquidationConfig) -> Self {
+        self.queue = Mutex::new(LiquidationPriorityQueue::new(slicing.priority));
+        self.pending = Mutex::new(PendingLiquidations::new(Duration::from_millis(slicing.trigger_ttl_ms)));
+        self.slicing = slicing;
+        self
+    }
+
+    pub fn with_contract(mut self, contract: ContractSpec) -> Self {
+        self.contract = contract;
+        self
+    }
+
+    /// Sliced liquidations whose next child order is due at `now`
+    pub fn due_slices(&self, now: Timestamp) -> Vec<SlicedLiquidation> {
+        let mut due: Vec<SlicedLiquidation> = self.active.lock().unwrap().values()
+            .filter(|sliced| sliced.next_slice_at <= now)
+            .cloned()
+            .collect();
+        due.sort_by_key(|sliced| (sliced.next_slice_at, sliced.user_id.0));
+        due
+    }
+
+    pub fn active_slices(&self) -> usize {
+        self.active.lock().unwrap().len()
+    }
+
+    /// Size of the next child order: a share of the depth near mark
+    fn slice_size<M: OrderMatcher>(&self, matcher: &M, side: Side, target: Quantity, mark_price: Price) -> Quantity {
+        if self.slicing.max_participation_rate <= 0.0 {
+            return target;
         }
+
+        let band = mark_price.to_i64() as i128 * self.slicing.depth_band_bps as i128 / 10_000;
+        let limit = match side {
+            Side::Buy => Price::from_i64(mark_price.to_i64() + band as i64),
+            Side::Sell => Price::from_i64(mark_price.to_i64() - band as i64),
+        };
+
+        let depth = matcher.depth_within(side, limit);
+        let share = (depth.to_i64() as f64 * self.slicing.max_participation_rate) as i64;
+        target.min(Quantity::from_i64(share).max(self.slicing.min_slice_size))
+    }
+
+    pub fn add_candidate(&self, candidate: LiquidationCandidate) {
+        self.queue.lock().unwrap().push(candidate);
+    }
+
+    /// Claim a leg before sending a liquidation trigger for it at `now`;
+    /// false while an earlier trigger is still being handled
+    pub fn claim_trigger(&self, user_id: UserId, leg: PositionSide, now: Timestamp) -> bool {
+        let claimed = self.pending.lock().unwrap().claim(user_id, leg, now);
+        if !claimed {
+            LIQUIDATION_TRIGGERS_SUPPRESSED.inc();
+        }
+        claimed
+    }
+
+    /// Give up a claim whose trigger was never sent
+    pub fn release_trigger(&self, user_id: UserId, leg: PositionSide) {
+        self.pending.lock().unwrap().release(user_id, leg);
     }
 
-    pub fn add_candidate(&mut self, candidate: LiquidationCandidate) {
-        self.queue.push(candidate);
+    /// Revalue queued candidates at a new mark so they are taken in the
+    /// configured priority at current prices
+    pub fn reprice(&self, mark_price: Price) {
+        self.queue.lock().unwrap().reprice(mark_price, &self.contract);
     }
 
-    pub fn execute_next(
-        &mut self,
-        matcher: &mut Matcher,
-        balance_provider: &mut dyn BalanceProvider,
+    /// Send the next child order for the top candidate; `now` is the event time
+    pub fn execute_next<M: OrderMatcher>(
+        &self,
+        matcher: &mut M,
+        balance_provider: &dyn BalanceProvider,
+        position_provider: &dyn PositionProvider,
+        now: Timestamp,
     ) -> Result<Option<LiquidationEvent>> {
 
         if self.halted.load(Ordering::SeqCst) {
@@ -54,61 +191,159 @@ impl LiquidationExecutor {
             return Ok(None);
         }
 
-        // Check rate limit
-        if !self.rate_limiter.check_and_record() {
-            return Err(Error::LiquidationRateLimitExceeded);
-        }
-
         // Get next candidate
-        let candidate = match self.queue.pop() {
+        let candidate = match self.queue.lock().unwrap().pop() {
             Some(c) => c,
             None => return Ok(None),
         };
+        let (user_id, leg) = (candidate.user_id, candidate.position.position_side);
+        let result = self.step(candidate, matcher, balance_provider, position_provider, now);
+
+        // Triggers for the leg stay suppressed while its liquidation has a step to come
+        let mut pending = self.pending.lock().unwrap();
+        match self.active.lock().unwrap().get(&(user_id, leg)) {
+            Some(sliced) => pending.hold(user_id, leg, sliced.next_slice_at),
+            None => pending.release(user_id, leg),
+        }
+        result
+    }
+
+    /// One step of the candidate's liquidation
+    fn step<M: OrderMatcher>(
+        &self,
+        candidate: LiquidationCandidate,
+        matcher: &mut M,
+        balance_provider: &dyn BalanceProvider,
+        position_provider: &dyn PositionProvider,
+        now: Timestamp,
+    ) -> Result<Option<LiquidationEvent>> {
+        let mut active = self.active.lock().unwrap();
+
+        // Margin restored since the last step (or the trigger): stop here
+        let key = (candidate.user_id, candidate.position.position_side);
+        if candidate.maintenance_surplus >= Balance::zero() {
+            if let Some(sliced) = active.remove(&key) {
+                tracing::info!(
+                    "Liquidation {:?} ended after {} slice(s): margin restored with {} left",
+                    sliced.liquidation_id, sliced.slices, sliced.remaining.to_i64()
+                );
+                LIQUIDATIONS_RESTORED.inc();
+                LIQUIDATION_SLICES_PENDING.set(active.len() as i64);
+                if sliced.slices > 0 {
+                    self.completed(&sliced, LiquidationOutcome::MarginRestored, sliced.remaining);
+                }
+            }
+            return Ok(None);
+        }
+
+        // Continue a sliced liquidation of this leg, or size a new one (partial or full)
+        let sliced = match active.remove(&key) {
+            Some(sliced) if sliced.next_slice_at > now => {
+                tracing::debug!("Liquidation {:?} throttled until next slice", sliced.liquidation_id);
+                active.insert(key, sliced);
+                return Ok(None);
+            }
+            Some(sliced) => SlicedLiquidation {
+                remaining: sliced.remaining.min(candidate.position.abs_size()),
+                slices: sliced.slices + 1,
+                ..sliced
+            },
+            None => SlicedLiquidation {
+                liquidation_id: crate::utils::helper::generate_liquidation_id(),
+                user_id: candidate.user_id,
+                position_side: candidate.position.position_side,
+                margin_ratio: candidate.margin_ratio,
+                maintenance_margin: candidate.maintenance_margin,
+                remaining: self.calculate_liquidation_size(&candidate, balance_provider)?,
+                slices: 1,
+                next_slice_at: now,
+            },
+        };
 
-        // Calculate liquidation size (partial or full)
-        let liquidation_size = self.calculate_liquidation_size(
-            &candidate,
-            balance_provider,
-        )?;
+        // Rate limited: the step waits for the window to reopen
+        let deferred_until = {
+            let mut rate_limiter = self.rate_limiter.lock().unwrap();
+            (!rate_limiter.check_and_record(now)).then(|| rate_limiter.next_slot(now))
+        };
+        if let Some(next_slice_at) = deferred_until {
+            tracing::debug!("Liquidation {:?} deferred by the rate limiter", sliced.liquidation_id);
+            active.insert(key, SlicedLiquidation { slices: sliced.slices - 1, next_slice_at, ..sliced });
+            LIQUIDATION_SLICES_PENDING.set(active.len() as i64);
+            return Ok(None);
+        }
+        if sliced.slices == 1 {
+            self.started(&candidate, &sliced);
+        }
 
-        // Create liquidation order (opposite side of position)
+        // Close out on the opposite side of the position
         let liquidation_side = if candidate.position.is_long() {
             Side::Sell
         } else {
             Side::Buy
         };
 
-        let liquidation_order = Order {
-            order_id: crate::utils::helper::generate_order_id(),
-            user_id: *LIQUIDATION_ENGINE_USER_ID,
-            side: liquidation_side,
-            order_type: OrderType::Limit,
-            price: candidate.mark_price,
-            quantity: liquidation_size,
-            filled: Quantity::zero(),
-            timestamp: Timestamp::now(),
-            time_in_force: TimeInForce::IOC,
-            reduce_only: false,
-            post_only: false,
-            slippage_limit: None,
-        };
-
-        // Execute liquidation through matcher
-        let trades = matcher.match_order(
-            &liquidation_order,
-            balance_provider,
-            candidate.mark_price,
-        )?;
-
-        // Calculate liquidated size
-        let liquidated_size: Quantity = trades.iter()
-            .map(|t| t.quantity)
-            .sum();
+        let liquidation_size = self.slice_size(&*matcher, liquidation_side, sliced.remaining, candidate.mark_price);
+
+        // Walk the ladder until the child order is filled
+        let mut liquidated_size = Quantity::zero();
+        let mut filled_notional: i128 = 0;
+        for price in self.ladder(liquidation_side, candidate.mark_price) {
+            let unfilled = liquidation_size - liquidated_size;
+            if unfilled == Quantity::zero() {
+                break;
+            }
+
+            let trades = matcher.match_order(
+                &Self::close_out_order(liquidation_side, price, unfilled, now),
+                position_provider,
+                candidate.mark_price,
+            )?;
+            for trade in &trades {
+                self.record(EventPayload::LiquidationFillReceived(Box::new(LiquidationFillReceived {
+                    base: BaseEvent::new(EventType::LiquidationFillReceived, self.market_id),
+                    liquidation_id: sliced.liquidation_id,
+                    user_id: sliced.user_id,
+                    trade_id: trade.trade_id,
+                    price: trade.price,
+                    quantity: trade.quantity,
+                    slice: sliced.slices,
+                })));
+                liquidated_size = liquidated_size + trade.quantity;
+                filled_notional += trade.price.to_i64() as i128 * trade.quantity.to_i64() as i128;
+            }
+        }
 
+        let next_slice_at = now + Duration::from_millis(self.slicing.slice_interval_ms);
         if liquidated_size == Quantity::zero() {
+            // Book empty out to the backstop: the fund takes the rest at bankruptcy price
+            if let Some(bankruptcy_price) = candidate.bankruptcy_price {
+                LIQUIDATION_SLICES_PENDING.set(active.len() as i64);
+                self.completed(&sliced, LiquidationOutcome::InsuranceTakeover, Quantity::zero());
+                return Ok(Some(self.take_over(&candidate, &sliced, bankruptcy_price)));
+            }
+
+            // Keep an already started liquidation; a new one is sized afresh next time
+            let will_retry = sliced.slices > 1;
+            self.failed(&sliced, LiquidationFailureReason::NoLiquidity, will_retry);
+            if will_retry {
+                active.insert(key, SlicedLiquidation { next_slice_at, ..sliced });
+            }
             return Err(Error::LiquidationFailedNoLiquidity);
         }
 
+        let remaining_size = sliced.remaining - liquidated_size;
+        if remaining_size > Quantity::zero() {
+            active.insert(key, SlicedLiquidation {
+                remaining: remaining_size,
+                next_slice_at,
+                ..sliced.clone()
+            });
+        }
+        LIQUIDATION_SLICES_PENDING.set(active.len() as i64);
+        if remaining_size == Quantity::zero() {
+            self.completed(&sliced, LiquidationOutcome::Closed, Quantity::zero());
+        }
+
         // Calculate loss
         let account = balance_provider.get_account(candidate.user_id)?;
         let loss = if account.balance < Balance::zero() {
@@ -117,9 +352,20 @@ impl LiquidationExecutor {
             Balance::zero()
         };
 
-        // Cover loss with insurance fund
-        if loss > Balance::zero() {
-            self.insurance_fund.cover_loss(loss)?;
+        // Cover loss with insurance fund; the processor socializes the rest
+        let covered = if loss > Balance::zero() {
+            let fund_balance = balance_provider.get_account(UserId::insurance_fund())
+                .map(|fund| fund.balance)
+                .unwrap_or(Balance::zero());
+            InsuranceFund::coverable(loss, fund_balance)
+        } else {
+            Balance::zero()
+        };
+
+        let liquidation_price = Price::from_i64((filled_notional / liquidated_size.to_i64() as i128) as i64);  // Average fill
+        let penalty = self.penalty(liquidated_size, liquidation_price, account.balance)?;
+        if penalty > Balance::zero() {
+            INSURANCE_FUND_CONTRIBUTIONS.inc_by(penalty.to_f64());
         }
 
         // Determine liquidation type
@@ -131,16 +377,21 @@ impl LiquidationExecutor {
 
         // Create event
         let event = LiquidationEvent {
-            base: BaseEvent::new(crate::events::base::EventType::Liquidation, self.market_id),
-            liquidation_id: crate::utils::helper::generate_liquidation_id(),
+            base: BaseEvent::new(EventType::Liquidation, self.market_id),
+            liquidation_id: sliced.liquidation_id,
             user_id: candidate.user_id,
             position_size: candidate.position.abs_size(),
             liquidated_size,
-            liquidation_price: candidate.mark_price,
-            margin_ratio: candidate.margin_ratio,
-            maintenance_margin: candidate.maintenance_margin,
-            insurance_fund_loss: loss,
+            liquidation_price,
+            margin_ratio: sliced.margin_ratio,
+            maintenance_margin: sliced.maintenance_margin,
+            insurance_fund_loss: covered,
+            unbacked_loss: loss - covered,
+            penalty,
             liquidation_type,
+            slice: sliced.slices,
+            remaining_size,
+            execution: LiquidationExecution::Book,
         };
 
         // Observability: Record liquidation metrics
@@ -148,11 +399,149 @@ impl LiquidationExecutor {
             LiquidationType::Full => "full",
             LiquidationType::Partial => "partial",
         };        LIQUIDATIONS_EXECUTED.with_label_values(&[liq_type]).inc();
-        INSURANCE_FUND_BALANCE.set(self.insurance_fund.get_balance().to_i64());
 
         Ok(Some(event))
     }
 
+    /// Mirror the insurance fund account's ledger balance once the
+    /// processor has posted a liquidation (or restored a snapshot)
+    pub fn sync_insurance_fund(&self, balance: Balance) {
+        self.insurance_fund.sync(balance);
+    }
+
+    /// Lifecycle events recorded since the last call, oldest first, for
+    /// the processor to log
+    pub fn drain_lifecycle(&self) -> Vec<BaseEvent> {
+        std::mem::take(&mut *self.lifecycle.lock().unwrap())
+    }
+
+    fn record(&self, payload: EventPayload) {
+        let base = match &payload {
+            EventPayload::LiquidationStarted(event) => event.base.clone(),
+            EventPayload::LiquidationFillReceived(event) => event.base.clone(),
+            EventPayload::LiquidationCompleted(event) => event.base.clone(),
+            EventPayload::LiquidationFailed(event) => event.base.clone(),
+            _ => return,
+        };
+        self.lifecycle.lock().unwrap().push(BaseEvent { payload, ..base });
+    }
+
+    fn started(&self, candidate: &LiquidationCandidate, sliced: &SlicedLiquidation) {
+        self.record(EventPayload::LiquidationStarted(Box::new(LiquidationStarted {
+            base: BaseEvent::new(EventType::LiquidationStarted, self.market_id),
+            liquidation_id: sliced.liquidation_id,
+            user_id: sliced.user_id,
+            position_side: sliced.position_side,
+            position_size: candidate.position.abs_size(),
+            target_size: sliced.remaining,
+            mark_price: candidate.mark_price,
+            bankruptcy_price: candidate.bankruptcy_price,
+            margin_ratio: sliced.margin_ratio,
+            maintenance_margin: sliced.maintenance_margin,
+        })));
+    }
+
+    fn completed(&self, sliced: &SlicedLiquidation, outcome: LiquidationOutcome, unfilled_size: Quantity) {
+        self.record(EventPayload::LiquidationCompleted(Box::new(LiquidationCompleted {
+            base: BaseEvent::new(EventType::LiquidationCompleted, self.market_id),
+            liquidation_id: sliced.liquidation_id,
+            user_id: sliced.user_id,
+            outcome,
+            slices: sliced.slices,
+            unfilled_size,
+        })));
+    }
+
+    fn failed(&self, sliced: &SlicedLiquidation, reason: LiquidationFailureReason, will_retry: bool) {
+        self.record(EventPayload::LiquidationFailed(Box::new(LiquidationFailed {
+            base: BaseEvent::new(EventType::LiquidationFailed, self.market_id),
+            liquidation_id: sliced.liquidation_id,
+            user_id: sliced.user_id,
+            reason,
+            slice: sliced.slices,
+            will_retry,
+        })));
+    }
+
+    /// Limit prices for a close-out on `side`: mark first, then each rung
+    /// `ladder_step_bps` further from mark, out to `backstop_bps`
+    fn ladder(&self, side: Side, mark_price: Price) -> Vec<Price> {
+        let step = self.slicing.ladder_step_bps;
+        let rungs = self.slicing.backstop_bps.checked_div(step).unwrap_or(0);
+
+        (0..=rungs)
+            .map(|rung| {
+                let offset = (mark_price.to_i64() as i128 * (rung * step) as i128 / 10_000) as i64;
+                match side {
+                    Side::Buy => Price::from_i64(mark_price.to_i64().saturating_add(offset)),
+                    Side::Sell => Price::from_i64((mark_price.to_i64() - offset).max(1)),
+                }
+            })
+            .collect()
+    }
+
+    fn close_out_order(side: Side, price: Price, quantity: Quantity, now: Timestamp) -> Order {
+        Order {
+            order_id: crate::utils::helper::generate_order_id(),
+            user_id: *LIQUIDATION_ENGINE_USER_ID,
+            side,
+            order_type: OrderType::Limit,
+            price,
+            quantity,
+            filled: Quantity::zero(),
+            timestamp: now,
+            time_in_force: TimeInForce::IOC,
+            reduce_only: false,
+            post_only: false,
+            slippage_limit: None,
+            self_trade_prevention: None,
+            position_side: PositionSide::Both,  // The engine takes over the position net
+            min_fill_quantity: None,
+        }
+    }
+
+    /// Hand what is left of the liquidation to the insurance fund at the
+    /// bankruptcy price; the processor moves the leg to the liquidation
+    /// engine. The account closes at zero equity, so nothing is charged to
+    /// the fund now: its loss is in the position it holds
+    fn take_over(&self, candidate: &LiquidationCandidate, sliced: &SlicedLiquidation, bankruptcy_price: Price) -> LiquidationEvent {
+        tracing::warn!(
+            "No liquidity within {} bps of mark for {:?}: insurance fund takes over {} at {}",
+            self.slicing.backstop_bps, sliced.liquidation_id, sliced.remaining.to_i64(), bankruptcy_price.to_f64()
+        );
+        INSURANCE_TAKEOVERS.inc();
+
+        LiquidationEvent {
+            base: BaseEvent::new(EventType::Liquidation, self.market_id),
+            liquidation_id: sliced.liquidation_id,
+            user_id: candidate.user_id,
+            position_size: candidate.position.abs_size(),
+            liquidated_size: sliced.remaining,
+            liquidation_price: bankruptcy_price,
+            margin_ratio: sliced.margin_ratio,
+            maintenance_margin: sliced.maintenance_margin,
+            insurance_fund_loss: Balance::zero(),
+            unbacked_loss: Balance::zero(),
+            penalty: Balance::zero(),  // Nothing left to charge at bankruptcy
+            liquidation_type: if sliced.remaining == candidate.position.abs_size() {
+                LiquidationType::Full
+            } else {
+                LiquidationType::Partial
+            },
+            slice: sliced.slices,
+            remaining_size: Quantity::zero(),
+            execution: LiquidationExecution::InsuranceTakeover,
+        }
+    }
+
+    /// `penalty_bps` of the liquidated notional, capped at what the account
+    /// still holds so the penalty never leaves it negative
+    fn penalty(&self, liquidated_size: Quantity, price: Price, balance: Balance) -> Result<Balance> {
+        let notional = self.contract.notional(liquidated_size, price)?;
+        let penalty = Balance::from_i64((notional.to_i64() as i128 * self.slicing.penalty_bps as i128 / 10_000) as i64);
+        Ok(penalty.min(balance.max(Balance::zero())))
+    }
+
     /// Calculate partial liquidation size to restore margin health
     /// Per docs/architecture/liquidation-engine.md Section 4.1
     fn calculate_partial_liquidation_size(
@@ -165,25 +554,26 @@ impl LiquidationExecutor {
         const TARGET_MARGIN_RATIO: f64 = 0.15;
         const MIN_POSITION_SIZE: i64 = 1;
 
-        let position_value = position.abs_size().to_i64() * mark_price.to_i64();
-        let unrealized_pnl = (mark_price.to_i64() - position.entry_price.to_i64()) * position.size;
-        let collateral = balance.to_i64() + unrealized_pnl;
+        // Products of two raw values need i128; only the clamped size narrows back
+        let position_value = position.abs_size().to_i64() as i128 * mark_price.to_i64() as i128;
+        let unrealized_pnl = (mark_price.to_i64() as i128 - position.entry_price.to_i64() as i128) * position.size as i128;
+        let collateral = balance.to_i64() as i128 + unrealized_pnl;
 
         // Solve for liquidation_size:
         // (collateral + liquidation_pnl) / (position_value - liquidation_value) = target_ratio
         // Simplified: target_position_value = collateral / target_ratio
-        let target_position_value = (collateral as f64 / TARGET_MARGIN_RATIO) as i64;
+        let target_position_value = (collateral as f64 / TARGET_MARGIN_RATIO) as i128;
 
-        if target_position_value <= 0 {
+        if target_position_value <= 0 || mark_price.to_i64() <= 0 {
             // Full liquidation required
             return position.abs_size();
         }
 
         let liquidation_value = position_value - target_position_value;
-        let liquidation_size = liquidation_value / mark_price.to_i64();
+        let liquidation_size = liquidation_value / mark_price.to_i64() as i128;
 
         // Clamp to position size
-        let clamped_size = liquidation_size.max(0).min(position.abs_size().to_i64());
+        let clamped_size = liquidation_size.clamp(0, position.abs_size().to_i64() as i128) as i64;
 
         // If remaining position would be too small, liquidate fully
         let remaining_size = position.abs_size().to_i64() - clamped_size;
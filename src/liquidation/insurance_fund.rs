@@ -45,7 +45,47 @@ impl InsuranceFund {
         Ok(())
     }
 
+    /// How much of `loss` the fund covers out of `fund_balance`, the
+    /// insurance fund account's ledger balance. Whatever is left is
+    /// socialized (see `socialized_loss`)
+    ///
+    /// Judged on the ledger, not on this in-memory balance, so replay from
+    /// a snapshot covers the same amounts; the ledger posts the draw.
+    pub fn coverable(loss: Balance, fund_balance: Balance) -> Balance {
+        let loss = loss.to_i64().max(0);
+        let covered = loss.min(fund_balance.to_i64().max(0));
+
+        if covered < loss {
+            tracing::error!("Insurance fund depleted: required={}, covered={}", loss, covered);
+            crate::utils::helper::alert_operations_team_critical(
+                format!("Insurance fund depleted: required={}, covered={}, socializing {}", loss, covered, loss - covered)
+            );
+        } else if covered > 0 {
+            tracing::warn!("Insurance fund covered loss: {}", covered);
+        }
+
+        Balance::from_i64(covered)
+    }
+
+    /// Mirror the insurance fund account's ledger balance (for reports and
+    /// the stress test)
+    pub fn sync(&self, balance: Balance) {
+        self.balance.store(balance.to_i64(), Ordering::SeqCst);
+        crate::observability::metrics::update_insurance_fund_balance(balance.to_i64());
+    }
+
     pub fn get_balance(&self) -> Balance {
         Balance::from_i64(self.balance.load(Ordering::SeqCst))
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cover_is_capped_at_the_ledger_balance() {
+        assert_eq!(InsuranceFund::coverable(Balance::from_i64(300), Balance::from_i64(1_000)), Balance::from_i64(300));
+        assert_eq!(InsuranceFund::coverable(Balance::from_i64(300), Balance::from_i64(120)), Balance::from_i64(120));
+        assert_eq!(InsuranceFund::coverable(Balance::from_i64(300), Balance::from_i64(-50)), Balance::zero());
+    }
+}
@@ -2,4 +2,8 @@ pub mod detector;
 pub mod priority_queue;
 pub mod executor;
 pub mod rate_limiter;
-pub mod insurance_fund;
\ No newline at end of file
+pub mod pending;
+pub mod insurance_fund;
+pub mod socialized_loss;
+pub mod unwinder;
+pub mod stress;
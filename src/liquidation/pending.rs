@@ -0,0 +1,57 @@
+use std::collections::HashMap;
+use std::time::Duration;
+use crate::types::ids::UserId;
+use crate::types::position::PositionSide;
+use crate::types::timestamp::Timestamp;
+
+/// Liquidation triggers in flight, so the monitor sends one per underwater
+/// user (leg, in hedge mode) instead of one every detector cycle
+///
+/// A trigger claims its leg until the executor has handled it. The executor
+/// releases the claim when the liquidation ends, or holds it until the next
+/// child order of a sliced liquidation is due. Claims lapse after `ttl`, so
+/// a trigger that never reaches the executor (dropped, rejected, executor
+/// halted) does not block the leg for good.
+pub struct PendingLiquidations {
+    ttl: Duration,
+    claims: HashMap<(UserId, PositionSide), Timestamp>,  // Leg -> when a new trigger is allowed
+}
+
+impl PendingLiquidations {
+    pub fn new(ttl: Duration) -> Self {
+        PendingLiquidations {
+            ttl,
+            claims: HashMap::new(),
+        }
+    }
+
+    /// Claim the leg for a trigger sent at `now`; false while an earlier
+    /// trigger still holds it
+    pub fn claim(&mut self, user_id: UserId, leg: PositionSide, now: Timestamp) -> bool {
+        match self.claims.get(&(user_id, leg)) {
+            Some(&until) if now < until => false,
+            _ => {
+                self.claims.insert((user_id, leg), now + self.ttl);
+                true
+            }
+        }
+    }
+
+    /// Suppress triggers for the leg until `until`
+    pub fn hold(&mut self, user_id: UserId, leg: PositionSide, until: Timestamp) {
+        self.claims.insert((user_id, leg), until);
+    }
+
+    /// The leg's liquidation is over (or its trigger was never sent)
+    pub fn release(&mut self, user_id: UserId, leg: PositionSide) {
+        self.claims.remove(&(user_id, leg));
+    }
+
+    pub fn len(&self) -> usize {
+        self.claims.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.claims.is_empty()
+    }
+}
@@ -1,60 +1,116 @@
-use std::collections::BinaryHeap;
-use std::cmp::Ordering;
+use crate::config::LiquidationPriority;
+use crate::config::market::ContractSpec;
 use crate::liquidation::detector::LiquidationCandidate;
+use crate::types::balance::Balance;
 use crate::types::ids::UserId;
+use crate::types::price::Price;
+use crate::types::ratio::Ratio;
 
+/// Candidates waiting for the executor, popped in `LiquidationPriority` order
+///
+/// Priority is worked out at pop time from the candidates as they are now,
+/// so `reprice` on a new mark re-orders the queue instead of leaving it
+/// frozen at insertion. A leg is queued once: a newer candidate for it
+/// replaces the waiting one and keeps its place for `Oldest`.
 pub struct LiquidationPriorityQueue {
-    heap: BinaryHeap<PriorityCandidate>,
+    priority: LiquidationPriority,
+    entries: Vec<QueuedCandidate>,
+    next_seq: u64,
+}
+
+struct QueuedCandidate {
+    candidate: LiquidationCandidate,
+    seq: u64,  // Insertion order
 }
 
 impl LiquidationPriorityQueue {
-    pub fn new() -> Self {
+    pub fn new(priority: LiquidationPriority) -> Self {
         LiquidationPriorityQueue {
-            heap: BinaryHeap::new(),
+            priority,
+            entries: Vec::new(),
+            next_seq: 0,
         }
     }
 
     pub fn push(&mut self, candidate: LiquidationCandidate) {
-        self.heap.push(PriorityCandidate(candidate));
+        let leg = (candidate.user_id, candidate.position.position_side);
+        if let Some(queued) = self.entries.iter_mut()
+            .find(|queued| (queued.candidate.user_id, queued.candidate.position.position_side) == leg)
+        {
+            queued.candidate = candidate;
+            return;
+        }
+
+        self.entries.push(QueuedCandidate { candidate, seq: self.next_seq });
+        self.next_seq += 1;
     }
 
     pub fn pop(&mut self) -> Option<LiquidationCandidate> {
-        self.heap.pop().map(|p| p.0)
+        let index = (0..self.entries.len()).min_by(|&a, &b| self.rank(a).cmp(&self.rank(b)))?;
+        Some(self.entries.swap_remove(index).candidate)
+    }
+
+    /// Revalue waiting candidates at a new mark: equity and surplus move by
+    /// the leg's PnL, maintenance margin with its notional. Approximate (the
+    /// detector's next pass has the exact figures) but enough to re-order
+    pub fn reprice(&mut self, mark_price: Price, contract: &ContractSpec) {
+        for queued in &mut self.entries {
+            let candidate = &mut queued.candidate;
+            let old_mark = candidate.mark_price;
+            if old_mark == mark_price {
+                continue;
+            }
+
+            let pnl = match contract.pnl(candidate.position.size, old_mark, mark_price) {
+                Ok(pnl) => pnl,
+                Err(_) => continue,  // Kept at its last valuation
+            };
+            let size = candidate.position.abs_size();
+            let (old_notional, new_notional) = match (contract.notional(size, old_mark), contract.notional(size, mark_price)) {
+                (Ok(old), Ok(new)) if old.to_i64() > 0 => (old, new),
+                _ => continue,
+            };
+
+            let old_margin = candidate.maintenance_margin;
+            let new_margin = (old_margin.to_f64() * new_notional.to_f64() / old_notional.to_f64()) as i64;
+            let equity = candidate.margin_ratio.to_f64() * old_margin.to_f64() + pnl.to_f64();
+
+            candidate.maintenance_margin = Balance::from_i64(new_margin);
+            candidate.maintenance_surplus = candidate.maintenance_surplus + pnl
+                - (candidate.maintenance_margin - old_margin);
+            candidate.margin_ratio = if new_margin > 0 {
+                Ratio::from(equity / new_margin as f64)
+            } else {
+                candidate.margin_ratio
+            };
+            candidate.mark_price = mark_price;
+        }
     }
 
     pub fn is_empty(&self) -> bool {
-        self.heap.is_empty()
+        self.entries.is_empty()
     }
 
     pub fn len(&self) -> usize {
-        self.heap.len()
+        self.entries.len()
     }
 
     pub fn contains(&self, user_id: UserId) -> bool {
-        self.heap.iter().any(|p| p.0.user_id == user_id)
+        self.entries.iter().any(|queued| queued.candidate.user_id == user_id)
     }
-}
-
-struct PriorityCandidate(LiquidationCandidate);
 
-impl PartialEq for PriorityCandidate {
-    fn eq(&self, other: &Self) -> bool {
-        self.0.margin_ratio == other.0.margin_ratio
+    /// Sort key of an entry: lowest pops first, ties go to the oldest
+    fn rank(&self, index: usize) -> (i128, u64) {
+        let queued = &self.entries[index];
+        let candidate = &queued.candidate;
+        let key = match self.priority {
+            // Lower margin ratio = higher priority (ratio in millionths)
+            LiquidationPriority::MostUnderwater => (candidate.margin_ratio.to_f64() * 1e6) as i128,
+            LiquidationPriority::LargestNotional => {
+                -(candidate.position.abs_size().to_i64() as i128 * candidate.mark_price.to_i64() as i128)
+            }
+            LiquidationPriority::Oldest => 0,
+        };
+        (key, queued.seq)
     }
 }
-
-impl Eq for PriorityCandidate {}
-
-impl PartialOrd for PriorityCandidate {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
-    }
-}
-
-impl Ord for PriorityCandidate {
-    fn cmp(&self, other: &Self) -> Ordering {
-        // Lower margin ratio = higher priority (reverse order)
-        other.0.margin_ratio.partial_cmp(&self.0.margin_ratio)
-            .unwrap_or(Ordering::Equal)
-    }
-}
\ No newline at end of file
@@ -1,10 +1,13 @@
-use std::time::{Duration, Instant};
+use std::time::Duration;
 use std::collections::VecDeque;
+use crate::types::timestamp::Timestamp;
 
+/// Sliding-window limit on liquidation orders, kept in event time so
+/// replay throttles identically
 pub struct RateLimiter {
     max_per_interval: usize,
     interval: Duration,
-    timestamps: VecDeque<Instant>,
+    timestamps: VecDeque<Timestamp>,
 }
 
 impl RateLimiter {
@@ -16,17 +19,8 @@ impl RateLimiter {
         }
     }
 
-    pub fn check_and_record(&mut self) -> bool {
-        let now = Instant::now();
-
-        // Remove old timestamps
-        while let Some(&front) = self.timestamps.front() {
-            if now.duration_since(front) > self.interval {
-                self.timestamps.pop_front();
-            } else {
-                break;
-            }
-        }
+    pub fn check_and_record(&mut self, now: Timestamp) -> bool {
+        self.expire(now);
 
         // Check limit
         if self.timestamps.len() >= self.max_per_interval {
@@ -37,4 +31,26 @@ impl RateLimiter {
         self.timestamps.push_back(now);
         true
     }
-}
\ No newline at end of file
+
+    /// Earliest time at or after `now` a check would pass
+    pub fn next_slot(&mut self, now: Timestamp) -> Timestamp {
+        self.expire(now);
+
+        if self.timestamps.len() < self.max_per_interval {
+            return now;
+        }
+        // The oldest order in the window leaves it first
+        self.timestamps.front().map_or(now, |&oldest| oldest + self.interval + Duration::from_millis(1))
+    }
+
+    /// Remove old timestamps
+    fn expire(&mut self, now: Timestamp) {
+        while let Some(&front) = self.timestamps.front() {
+            if now - front > self.interval {
+                self.timestamps.pop_front();
+            } else {
+                break;
+            }
+        }
+    }
+}
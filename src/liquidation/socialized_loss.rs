@@ -0,0 +1,168 @@
+use std::collections::HashMap;
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use crate::types::account::Account;
+use crate::types::balance::Balance;
+use crate::types::ids::UserId;
+use crate::types::timestamp::Timestamp;
+
+/// Realized PnL baselines for the current settlement period: a winner is an
+/// account whose realized PnL rose since the period started
+///
+/// Periods are judged on event time and baselines are taken from the
+/// ledger, so replay haircuts the same accounts. The running period is
+/// carried in snapshots (`SettlementPeriodState`), so a restart resumes it
+/// instead of starting a new one.
+pub struct SettlementPeriod {
+    length: Duration,
+    started_at: Option<Timestamp>,
+    baselines: HashMap<UserId, Balance>,  // Realized PnL at period start
+}
+
+/// The running period as of a snapshot: its start and each account's baseline
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SettlementPeriodState {
+    pub started_at: Option<Timestamp>,
+    pub baselines: Vec<(UserId, Balance)>,  // Sorted by user id
+}
+
+/// One winner's share of a socialized loss
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Haircut {
+    pub user_id: UserId,
+    pub period_pnl: Balance,
+    pub amount: Balance,
+}
+
+impl SettlementPeriod {
+    pub fn new(length: Duration) -> Self {
+        SettlementPeriod {
+            length,
+            started_at: None,
+            baselines: HashMap::new(),
+        }
+    }
+
+    pub fn started_at(&self) -> Option<Timestamp> {
+        self.started_at
+    }
+
+    /// The running period, for a snapshot
+    pub fn state(&self) -> SettlementPeriodState {
+        let mut baselines: Vec<_> = self.baselines.iter().map(|(user_id, pnl)| (*user_id, *pnl)).collect();
+        baselines.sort_by_key(|(user_id, _)| user_id.0);
+        SettlementPeriodState {
+            started_at: self.started_at,
+            baselines,
+        }
+    }
+
+    /// Resume the period recorded in a snapshot
+    pub fn restore(&mut self, state: &SettlementPeriodState) {
+        self.started_at = state.started_at;
+        self.baselines = state.baselines.iter().copied().collect();
+    }
+
+    /// No period is running, or the current one is over at `now`
+    pub fn is_due(&self, now: Timestamp) -> bool {
+        self.started_at.is_none_or(|start| now - start >= self.length)
+    }
+
+    /// Start a new period at `now`, taking each account's realized PnL as
+    /// its baseline
+    pub fn start<'a>(&mut self, accounts: impl IntoIterator<Item = &'a Account>, now: Timestamp) {
+        self.baselines = accounts.into_iter()
+            .map(|account| (account.user_id, account.realized_pnl))
+            .collect();
+        self.started_at = Some(now);
+    }
+
+    /// Accounts with positive realized PnL in the period, with what they can
+    /// be haircut: the lesser of that PnL and their balance. System accounts
+    /// never count as winners; an account opened mid-period has a zero baseline
+    pub fn winners<'a>(&self, accounts: impl IntoIterator<Item = &'a Account>) -> Vec<(UserId, Balance, Balance)> {
+        let mut winners: Vec<_> = accounts.into_iter()
+            .filter(|account| !account.user_id.is_system())
+            .filter_map(|account| {
+                let baseline = self.baselines.get(&account.user_id).copied().unwrap_or(Balance::zero());
+                let period_pnl = account.realized_pnl - baseline;
+                let capacity = period_pnl.min(account.balance);
+                (period_pnl > Balance::zero() && capacity > Balance::zero())
+                    .then_some((account.user_id, period_pnl, capacity))
+            })
+            .collect();
+        winners.sort_by_key(|&(user_id, _, _)| user_id.0);
+        winners
+    }
+}
+
+/// Split `deficit` across `winners` (user, period PnL, capacity) pro rata to
+/// their period PnL, never taking more than a winner's capacity
+///
+/// Shares are floored; the rounding remainder goes to the largest winners
+/// first, so the split is deterministic. Returns the haircuts and whatever
+/// the winners could not absorb.
+pub fn allocate(deficit: Balance, winners: &[(UserId, Balance, Balance)]) -> (Vec<Haircut>, Balance) {
+    let deficit = deficit.to_i64().max(0) as i128;
+    let total_pnl: i128 = winners.iter().map(|(_, pnl, _)| pnl.to_i64() as i128).sum();
+    if deficit == 0 || total_pnl == 0 {
+        return (Vec::new(), Balance::from_i64(deficit as i64));
+    }
+
+    let mut amounts: Vec<i128> = winners.iter()
+        .map(|(_, pnl, capacity)| (deficit * pnl.to_i64() as i128 / total_pnl).min(capacity.to_i64() as i128))
+        .collect();
+
+    // Rounding remainder (and any capped share) from the largest winners down
+    let mut order: Vec<usize> = (0..winners.len()).collect();
+    order.sort_by_key(|&i| (std::cmp::Reverse(winners[i].1), winners[i].0 .0));
+    let mut remaining = deficit - amounts.iter().sum::<i128>();
+    for i in order {
+        if remaining == 0 {
+            break;
+        }
+        let extra = remaining.min(winners[i].2.to_i64() as i128 - amounts[i]);
+        amounts[i] += extra;
+        remaining -= extra;
+    }
+
+    let haircuts = winners.iter().zip(amounts)
+        .filter(|&(_, amount)| amount > 0)
+        .map(|(&(user_id, period_pnl, _), amount)| Haircut {
+            user_id,
+            period_pnl,
+            amount: Balance::from_i64(amount as i64),
+        })
+        .collect();
+    (haircuts, Balance::from_i64(remaining as i64))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    fn account(id: u128, balance: i64, realized_pnl: i64) -> Account {
+        let mut account = Account::new(UserId(Uuid::from_u128(id)));
+        account.balance = Balance::from_i64(balance);
+        account.realized_pnl = Balance::from_i64(realized_pnl);
+        account
+    }
+
+    #[test]
+    fn a_restored_period_judges_winners_against_the_original_baselines() {
+        let length = Duration::from_secs(3600);
+        let mut period = SettlementPeriod::new(length);
+        period.start(&[account(1, 1_000, 100), account(2, 1_000, 0)], Timestamp::from_millis(1_000));
+
+        // Restart: the period comes back from the snapshot instead of starting over
+        let mut restored = SettlementPeriod::new(length);
+        restored.restore(&period.state());
+        assert_eq!(restored.state(), period.state());
+        assert!(!restored.is_due(Timestamp::from_millis(2_000)));
+
+        let later = [account(1, 1_000, 150), account(2, 1_000, 30)];
+        assert_eq!(restored.winners(&later), period.winners(&later));
+        assert_eq!(restored.winners(&later)[0].1, Balance::from_i64(50));  // 150 - 100, not 150
+    }
+}
@@ -0,0 +1,263 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use serde::{Deserialize, Serialize};
+use crate::config::LiquidationConfig;
+use crate::config::market::ContractSpec;
+use crate::error::{Error, Result};
+use crate::events::order::Side;
+use crate::liquidation::detector::LiquidationDetector;
+use crate::liquidation::insurance_fund::InsuranceFund;
+use crate::liquidation::priority_queue::LiquidationPriorityQueue;
+use crate::matching::order_book::{DepthLevel, DepthSnapshot};
+use crate::risk::pnl::PnLCalculator;
+use crate::settlement::balance_manager::BalanceManager;
+use crate::types::account::Account;
+use crate::types::balance::Balance;
+use crate::types::ids::UserId;
+use crate::types::position::Position;
+use crate::types::price::Price;
+use crate::types::quantity::Quantity;
+
+/// Hypothetical mark price move
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct StressScenario {
+    pub shock: f64,  // Fraction of the mark, e.g. -0.2 = mark down 20%
+}
+
+/// What liquidations the scenario would set off and who pays for them
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StressReport {
+    pub scenario: StressScenario,
+    pub mark_price: Price,
+    pub shocked_mark_price: Price,
+    pub accounts_liquidated: u32,
+    pub legs_liquidated: u32,
+    pub liquidation_volume: Quantity,     // Size closed, on the book or taken over
+    pub liquidation_notional: Balance,    // At the shocked mark
+    pub filled_on_book: Quantity,         // Absorbed by resting depth within the backstop
+    pub insurance_takeover: Quantity,     // Left for the insurance fund at bankruptcy price
+    pub bankruptcy_deficit: Balance,      // Negative equity left by liquidated accounts
+    pub penalties: Balance,               // Paid into the insurance fund
+    pub insurance_fund_balance: Balance,
+    pub insurance_fund_drawdown: Balance, // Deficit the fund covers
+    pub shortfall: Balance,               // Deficit beyond the fund, socialized
+    pub adl_depth: f64,                   // Share of surviving accounts' unrealized profit the shortfall takes
+    pub legs: Vec<StressedLeg>,           // In execution order
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StressedLeg {
+    pub user_id: UserId,
+    pub size: i64,
+    pub filled_on_book: Quantity,
+    pub average_fill: Option<Price>,
+    pub insurance_takeover: Quantity,
+}
+
+/// Liquidation dry run: the detector and a simulated executor against a
+/// copy of current state at a shocked mark price
+///
+/// ## Simulation
+/// - Candidates come from `LiquidationDetector::scan` at the shocked mark
+///   (without accrued funding) and are executed in the configured
+///   `LiquidationPriority`, each leg closed in full
+/// - Resting depth moves with the mark: each level keeps its distance from
+///   it. Close-outs walk the opposite side out to `backstop_bps`, sharing
+///   the depth; the rest is taken over by the insurance fund
+/// - An account's deficit is its equity with every leg at the shocked mark
+///   plus the slippage of its fills, where negative; the fund (after
+///   `penalty_bps` penalties) covers what it can
+///
+/// Nothing is written back: state, the book and the fund are left untouched.
+pub struct LiquidationStress {
+    detector: Arc<LiquidationDetector>,
+    config: LiquidationConfig,
+    contract: ContractSpec,
+    insurance_fund: Arc<InsuranceFund>,
+}
+
+impl LiquidationStress {
+    pub fn new(detector: Arc<LiquidationDetector>, config: LiquidationConfig, insurance_fund: Arc<InsuranceFund>) -> Self {
+        LiquidationStress {
+            detector,
+            config,
+            contract: ContractSpec::default(),
+            insurance_fund,
+        }
+    }
+
+    pub fn with_contract(mut self, contract: ContractSpec) -> Self {
+        self.contract = contract;
+        self
+    }
+
+    pub fn run(
+        &self,
+        scenario: StressScenario,
+        mark_price: Price,
+        accounts: Vec<Account>,
+        positions: &[Position],
+        depth: &DepthSnapshot,
+    ) -> Result<StressReport> {
+        if !scenario.shock.is_finite() || scenario.shock <= -1.0 {
+            return Err(Error::ConfigError("Stress shock must be a finite fraction above -1".to_string()));
+        }
+        let shocked = Price::from_i64((mark_price.to_i64() as f64 * (1.0 + scenario.shock)) as i64);
+
+        let mut balances = BalanceManager::new();
+        balances.accounts = accounts.into_iter().map(|account| (account.user_id, account)).collect();
+        let scan = self.detector.scan(positions, shocked, None, &balances)?;
+
+        let mut queue = LiquidationPriorityQueue::new(self.config.priority);
+        for candidate in scan.liquidations {
+            queue.push(candidate);
+        }
+
+        let mut bids = Self::shifted(&depth.bids, scenario.shock);
+        let mut asks = Self::shifted(&depth.asks, scenario.shock);
+        let band = shocked.to_i64() as i128 * self.config.backstop_bps as i128 / 10_000;
+
+        let mut legs = Vec::new();
+        let mut slippage: HashMap<UserId, Balance> = HashMap::new();
+        let mut filled_notional: HashMap<UserId, Balance> = HashMap::new();
+        while let Some(candidate) = queue.pop() {
+            let size = candidate.position.size;
+            let (side, levels) = if size > 0 { (Side::Sell, &mut bids) } else { (Side::Buy, &mut asks) };
+            let limit = match side {
+                Side::Sell => shocked.to_i64() as i128 - band,
+                Side::Buy => shocked.to_i64() as i128 + band,
+            };
+
+            let (filled, notional) = Self::sweep(levels, side, size.unsigned_abs() as i64, limit);
+            let average_fill = (filled > 0).then(|| Price::from_i64((notional / filled as i128) as i64));
+            if let Some(average) = average_fill {
+                let signed = if size > 0 { filled } else { -filled };
+                let fill_pnl = self.contract.pnl(signed, shocked, average)?;
+                let fill_notional = self.contract.notional(Quantity::from_i64(filled), average)?;
+                let slipped = slippage.entry(candidate.user_id).or_insert(Balance::zero());
+                *slipped = *slipped + fill_pnl;
+                let user_notional = filled_notional.entry(candidate.user_id).or_insert(Balance::zero());
+                *user_notional = *user_notional + fill_notional;
+            }
+
+            legs.push(StressedLeg {
+                user_id: candidate.user_id,
+                size,
+                filled_on_book: Quantity::from_i64(filled),
+                average_fill,
+                insurance_takeover: Quantity::from_i64(size.abs() - filled),
+            });
+        }
+
+        // Equity per account with every leg at the shocked mark
+        let mut equity: HashMap<UserId, Balance> = balances.accounts.iter()
+            .map(|(user_id, account)| (*user_id, account.balance))
+            .collect();
+        for position in positions.iter().filter(|position| !position.is_flat()) {
+            let pnl = PnLCalculator::calculate_unrealized_pnl_for(&self.contract, position, shocked)?;
+            let user_equity = equity.entry(position.user_id).or_insert(Balance::zero());
+            *user_equity = *user_equity + pnl;
+        }
+
+        let mut bankruptcy_deficit = Balance::zero();
+        let mut penalties = Balance::zero();
+        for (user_id, slipped) in &slippage {
+            let after = equity.get(user_id).copied().unwrap_or(Balance::zero()) + *slipped;
+            if after < Balance::zero() {
+                bankruptcy_deficit = bankruptcy_deficit - after;
+            } else {
+                let notional = filled_notional.get(user_id).copied().unwrap_or(Balance::zero());
+                let penalty = Balance::from_i64(
+                    (notional.to_i64() as i128 * self.config.penalty_bps as i128 / 10_000) as i64
+                );
+                penalties = penalties + penalty.min(after);
+            }
+        }
+        // Taken over without a fill: the account's negative equity moves to the fund
+        let liquidated: Vec<UserId> = {
+            let mut users: Vec<UserId> = legs.iter().map(|leg| leg.user_id).collect();
+            users.sort_by_key(|user_id| user_id.0);
+            users.dedup();
+            users
+        };
+        for user_id in liquidated.iter().filter(|user_id| !slippage.contains_key(user_id)) {
+            let after = equity.get(user_id).copied().unwrap_or(Balance::zero());
+            if after < Balance::zero() {
+                bankruptcy_deficit = bankruptcy_deficit - after;
+            }
+        }
+
+        let insurance_fund_balance = self.insurance_fund.get_balance();
+        let insurance_fund_drawdown = bankruptcy_deficit.min((insurance_fund_balance + penalties).max(Balance::zero()));
+        let shortfall = bankruptcy_deficit - insurance_fund_drawdown;
+
+        // Shortfall against the profit still standing in surviving accounts
+        let surviving_profit: i64 = positions.iter()
+            .filter(|position| !position.is_flat() && !liquidated.contains(&position.user_id))
+            .map(|position| PnLCalculator::calculate_unrealized_pnl_for(&self.contract, position, shocked))
+            .collect::<Result<Vec<Balance>>>()?
+            .into_iter()
+            .map(|pnl| pnl.to_i64().max(0))
+            .sum();
+        let adl_depth = if shortfall == Balance::zero() {
+            0.0
+        } else if surviving_profit > 0 {
+            shortfall.to_f64() / Balance::from_i64(surviving_profit).to_f64()
+        } else {
+            f64::INFINITY
+        };
+
+        let liquidation_volume = legs.iter().fold(Quantity::zero(), |total, leg| total + Quantity::from_i64(leg.size.abs()));
+        Ok(StressReport {
+            scenario,
+            mark_price,
+            shocked_mark_price: shocked,
+            accounts_liquidated: liquidated.len() as u32,
+            legs_liquidated: legs.len() as u32,
+            liquidation_volume,
+            liquidation_notional: self.contract.notional(liquidation_volume, shocked)?,
+            filled_on_book: legs.iter().fold(Quantity::zero(), |total, leg| total + leg.filled_on_book),
+            insurance_takeover: legs.iter().fold(Quantity::zero(), |total, leg| total + leg.insurance_takeover),
+            bankruptcy_deficit,
+            penalties,
+            insurance_fund_balance,
+            insurance_fund_drawdown,
+            shortfall,
+            adl_depth,
+            legs,
+        })
+    }
+
+    /// Levels moved by `shock`, keeping their distance from mark
+    fn shifted(levels: &[DepthLevel], shock: f64) -> Vec<DepthLevel> {
+        levels.iter()
+            .map(|level| DepthLevel {
+                price: Price::from_i64((level.price.to_i64() as f64 * (1.0 + shock)) as i64),
+                ..*level
+            })
+            .collect()
+    }
+
+    /// Take up to `quantity` from `levels` (best first) without going past
+    /// `limit`; returns the filled quantity and its notional in raw price units
+    fn sweep(levels: &mut [DepthLevel], side: Side, quantity: i64, limit: i128) -> (i64, i128) {
+        let mut filled = 0;
+        let mut notional = 0i128;
+        for level in levels.iter_mut() {
+            let price = level.price.to_i64() as i128;
+            let reachable = match side {
+                Side::Sell => price >= limit,
+                Side::Buy => price <= limit,
+            };
+            if !reachable || filled == quantity {
+                break;
+            }
+
+            let take = (quantity - filled).min(level.quantity.to_i64());
+            level.quantity = level.quantity - Quantity::from_i64(take);
+            filled += take;
+            notional += price * take as i128;
+        }
+        (filled, notional)
+    }
+}
@@ -0,0 +1,161 @@
+use crate::config::UnwindConfig;
+use crate::config::market::ContractSpec;
+use crate::events::base::{BaseEvent, EventPayload, EventType};
+use crate::events::order::{OrderCancel, OrderSubmit, OrderType, Side, TimeInForce};
+use crate::matching::order_book::Order;
+use crate::observability::metrics::{ENGINE_INVENTORY, ENGINE_INVENTORY_NOTIONAL, ENGINE_UNWIND_ORDERS};
+use crate::types::balance::Balance;
+use crate::types::ids::{MarketId, OrderId};
+use crate::types::position::{Position, PositionSide};
+use crate::types::price::Price;
+use crate::types::quantity::Quantity;
+use crate::types::timestamp::Timestamp;
+use crate::LIQUIDATION_ENGINE_USER_ID;
+
+/// Top of book the unwinder quotes against
+#[derive(Clone, Copy, Debug)]
+pub struct BookTop {
+    pub best_bid: Option<Price>,
+    pub best_ask: Option<Price>,
+}
+
+/// Works the liquidation engine's inventory (positions the insurance fund
+/// took over) back into the market
+///
+/// ## Quoting
+/// - One passive post-only, reduce-only order at a time: joining the best
+///   ask to sell a long, the best bid to buy back a short, at most
+///   `order_size` per order
+/// - An unwind order the touch has moved away from is cancelled and
+///   quoted again at the new touch
+///
+/// ## Recovery
+/// - Quotes only while the book is two-sided within `max_spread_bps`
+/// - Never quotes more than `max_loss_bps` worse than the engine's entry
+///   price, unless the inventory is worth more than `max_inventory_notional`
+///   at mark: then getting the exposure down comes first
+///
+/// Orders go through the event log like any client order.
+pub struct InventoryUnwinder {
+    market_id: MarketId,
+    config: UnwindConfig,
+    contract: ContractSpec,
+}
+
+impl InventoryUnwinder {
+    pub fn new(market_id: MarketId, config: UnwindConfig, contract: ContractSpec) -> Self {
+        InventoryUnwinder {
+            market_id,
+            config,
+            contract,
+        }
+    }
+
+    /// Orders for this pass: cancels of stale unwind orders, then at most
+    /// one new quote. `resting` are the engine's open orders
+    pub fn plan(
+        &self,
+        inventory: Option<&Position>,
+        top: BookTop,
+        mark_price: Price,
+        resting: &[&Order],
+        now: Timestamp,
+    ) -> Vec<BaseEvent> {
+        let size = inventory.map_or(0, |position| position.size);
+        let notional = self.contract.notional(Quantity::from_i64(size.abs()), mark_price).unwrap_or(Balance::zero());
+        ENGINE_INVENTORY.set(size);
+        ENGINE_INVENTORY_NOTIONAL.set(notional.to_f64());
+
+        let quote = match inventory {
+            Some(position) if size != 0 => {
+                let over_limit = notional > self.config.max_inventory_notional;
+                if over_limit {
+                    tracing::warn!(
+                        "Liquidation engine inventory {} (notional {}) over its limit {}",
+                        size, notional.to_i64(), self.config.max_inventory_notional.to_i64()
+                    );
+                }
+                self.quote(position, top, over_limit)
+            }
+            _ => None,
+        };
+
+        let mut events = Vec::new();
+        let mut quoted = false;
+        for order in resting {
+            let current = quote.is_some_and(|(side, price)| order.side == side && order.price == price);
+            if current && !quoted {
+                quoted = true;
+                continue;
+            }
+            events.push(self.cancel(order, now));
+        }
+
+        if let (Some((side, price)), false) = (quote, quoted) {
+            let quantity = Quantity::from_i64(size.abs()).min(self.config.order_size);
+            tracing::info!("Liquidation engine unwind: {:?} {} at {}", side, quantity.to_i64(), price.to_f64());
+            events.push(self.submit(side, price, quantity, now));
+        }
+        events
+    }
+
+    /// Side and price to quote, or None while the market has not recovered
+    /// or the touch is past the loss limit
+    fn quote(&self, position: &Position, top: BookTop, over_limit: bool) -> Option<(Side, Price)> {
+        let (best_bid, best_ask) = (top.best_bid?.to_i64() as i128, top.best_ask?.to_i64() as i128);
+        let mid = (best_bid + best_ask) / 2;
+        if mid <= 0 || (best_ask - best_bid) * 10_000 > self.config.max_spread_bps as i128 * mid {
+            return None;
+        }
+
+        let entry = position.entry_price.to_i64() as i128;
+        let max_loss = self.config.max_loss_bps as i128;
+        let (side, price, acceptable) = if position.size > 0 {
+            (Side::Sell, best_ask, best_ask * 10_000 >= entry * (10_000 - max_loss))
+        } else {
+            (Side::Buy, best_bid, best_bid * 10_000 <= entry * (10_000 + max_loss))
+        };
+        (acceptable || over_limit).then_some((side, Price::from_i64(price as i64)))
+    }
+
+    fn submit(&self, side: Side, price: Price, quantity: Quantity, now: Timestamp) -> BaseEvent {
+        let mut base = BaseEvent::new(EventType::OrderSubmit, self.market_id);
+        base.timestamp = now;
+        base.checksum = base.calculate_checksum();
+
+        let order = OrderSubmit {
+            base: base.clone(),
+            order_id: OrderId::new(),
+            user_id: *LIQUIDATION_ENGINE_USER_ID,
+            side,
+            order_type: OrderType::Limit,
+            price: Some(price),
+            quantity,
+            time_in_force: TimeInForce::GTC,
+            reduce_only: true,
+            post_only: true,
+            slippage_limit: None,
+            trigger_price: None,
+            trailing_offset: None,
+            self_trade_prevention: None,
+            position_side: PositionSide::Both,  // The engine holds its inventory net
+            min_fill_quantity: None,
+        };
+        ENGINE_UNWIND_ORDERS.with_label_values(&["quote"]).inc();
+        BaseEvent { payload: EventPayload::OrderSubmit(Box::new(order)), ..base }
+    }
+
+    fn cancel(&self, order: &Order, now: Timestamp) -> BaseEvent {
+        let mut base = BaseEvent::new(EventType::OrderCancel, self.market_id);
+        base.timestamp = now;
+        base.checksum = base.calculate_checksum();
+
+        let cancel = OrderCancel {
+            base: base.clone(),
+            order_id: order.order_id,
+            user_id: *LIQUIDATION_ENGINE_USER_ID,
+        };
+        ENGINE_UNWIND_ORDERS.with_label_values(&["cancel"]).inc();
+        BaseEvent { payload: EventPayload::OrderCancel(Box::new(cancel)), ..base }
+    }
+}
@@ -2,21 +2,78 @@ use tokio::signal;
 use tokio::sync::{RwLock, mpsc, broadcast};
 use tokio::time::{interval, Duration};
 use tracing::{info, error, warn};
-use axum::Server;
 use prometheus::{Encoder, TextEncoder};
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::net::SocketAddr;
+use PerpInfra::algo::twap::TwapEngine;
+use PerpInfra::api::auth::ApiKeyAuth;
+use PerpInfra::api::ingress::IngressSequencer;
+use PerpInfra::api::websocket::{create_ws_router, WsState};
 use PerpInfra::config::loader::AppConfig;
+use PerpInfra::controls::deadmans_switch::DeadMansSwitch;
+use PerpInfra::controls::recovery::{RecoveryCommand, RecoveryProcedure};
+use PerpInfra::controls::ProcessorCommand;
+use PerpInfra::settlement::daily_statement::DailyStatementGenerator;
+use PerpInfra::settlement::migration::{publish_genesis, MigrationCommand};
+use PerpInfra::controls::warm_up::WarmUp;
 use PerpInfra::core::event_processor::EventProcessor;
+use PerpInfra::core::matching_core::{MatchingCore, MATCHING_QUEUE_CAPACITY};
 use PerpInfra::error::{Error, Result};
-use PerpInfra::events::base::{BaseEvent, EventType};
-use PerpInfra::events::price::PriceSnapshot;
+use PerpInfra::events::base::{BaseEvent, EventPayload, EventType};
+use PerpInfra::events::liquidation::MarginCallWarning;
+use PerpInfra::events::order::{CancelAllOrders, ExpireOrders};
+use PerpInfra::funding::predicted::PredictedFundingTracker;
 use PerpInfra::funding::ticker::FundingTicker;
+use PerpInfra::funding::payment_history::FundingPaymentHistory;
+use PerpInfra::settlement::expiry::ExpirySettler;
 use PerpInfra::liquidation::insurance_fund::InsuranceFund;
+use PerpInfra::liquidation::stress::LiquidationStress;
+use PerpInfra::liquidation::unwinder::{BookTop, InventoryUnwinder};
+use PerpInfra::matching::algorithm;
+use PerpInfra::matching::self_trade::SelfTradePolicy;
+use PerpInfra::matching::order_archive::OrderArchive;
+use PerpInfra::matching::order_book::{Order, OrderBook};
+use PerpInfra::notifications::dispatcher::NotificationDispatcher;
+use PerpInfra::notifications::preferences::NotificationPreferences;
 use PerpInfra::price_infra::aggregator::PriceAggregator;
-use PerpInfra::price_infra::connectors::binance::BinanceConnector;
-use PerpInfra::price_infra::connectors::coinbase::CoinbaseConnector;
-use PerpInfra::price_infra::connectors::kraken::KrakenConnector;
+use PerpInfra::observability::exemplars;
+use PerpInfra::observability::metrics::{PRICE_THRESHOLD_SCALE, REALIZED_VARIANCE, REALIZED_VOLATILITY};
+use PerpInfra::price_infra::history::IndexPriceHistory;
+use PerpInfra::risk::daily_report::{DailyRiskReporter, RiskTally};
+use PerpInfra::types::position::PositionSide;
+use PerpInfra::settlement::ledger_archive::LedgerArchiver;
+use PerpInfra::price_infra::oracle::OraclePublisher;
+use PerpInfra::price_infra::connectors::{connectors_for_market, run_connector};
+use PerpInfra::price_infra::RawPriceUpdate;
+use PerpInfra::types::price::Price;
+use PerpInfra::core::matching_core::BookView;
+use PerpInfra::api::read_model::ReadModelPublisher;
+use PerpInfra::api::rest::{create_router, ApiState};
+use PerpInfra::api::tenant::TenantRegistry;
+use PerpInfra::event_log::consumer::EventConsumer;
+use PerpInfra::event_log::producer::KafkaEventProducer;
+use PerpInfra::event_log::retention_manager::EventLogRetentionManager;
+use PerpInfra::event_log::snapshot_manager::SnapshotManager;
+use PerpInfra::funding::applicator::FundingApplicator;
+use PerpInfra::funding::catch_up::{missed_intervals, PremiumLog};
+use PerpInfra::funding::rate_calculator::FundingRateCalculator;
+use PerpInfra::invariants::kill_switch::KillSwitch;
+use PerpInfra::invariants::monitor::InvariantMonitor;
+use PerpInfra::liquidation::detector::LiquidationDetector;
+use PerpInfra::liquidation::executor::LiquidationExecutor;
+use PerpInfra::matching::lp_program::LpProgram;
+use PerpInfra::matching::matcher::Matcher;
+use PerpInfra::risk::margin::MarginCalculator;
+use PerpInfra::risk::pre_trade_check::PreTradeRiskCheck;
+use PerpInfra::risk::withdrawal_check::WithdrawalRiskCheck;
+use PerpInfra::settlement::balance_manager::BalanceManager;
+use PerpInfra::settlement::position_manager::PositionManager;
+use PerpInfra::types::balance::Balance;
+use PerpInfra::types::quantity::Quantity;
+use PerpInfra::types::timestamp::Timestamp;
+use PerpInfra::utils::task_supervisor::TaskSupervisor;
+use PerpInfra::interfaces::event_producer::EventProducer;
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -31,14 +88,14 @@ async fn main() -> Result<()> {
     // Load configuration
     let env = std::env::var("ENV").unwrap_or_else(|_| "development".to_string());
     info!("Loading configuration for environment: {}", env);
-    let config = ConfigLoader::load(&env)?;
+    let config = AppConfig::load(&env)?;
 
     // Validate configuration
     validate_config(&config)?;
     info!("Configuration loaded and validated");
 
     // Initialize market
-    let market_id = MarketId::from_string(&config.market.symbol);
+    let market_id = config.market.market_id;
     info!("Initializing market: {}", config.market.symbol);
 
     // ============================================================================
@@ -59,16 +116,26 @@ async fn main() -> Result<()> {
         &config.kafka.brokers,
         &config.kafka.topic,
         &config.kafka.group_id,
-    ).await?;
+        &config.kafka.security,
+    )?;
 
+    // Sequences continue from the last logged event
+    let last_logged = EventConsumer::last_logged_sequence(
+        &config.kafka.brokers,
+        &config.kafka.topic,
+        &config.kafka.security,
+    )?;
     let event_producer = Arc::new(KafkaEventProducer::new(
         &config.kafka.brokers,
         &config.kafka.topic,
-    )?);
+        &config.kafka.security,
+    )?
+    .with_codec(config.kafka.codec)
+    .with_next_sequence(last_logged.map_or(1, |sequence| sequence + 1)));
     info!("Kafka connection established");
 
     // Snapshot manager for fast recovery
-    let snapshot_manager = Arc::new(SnapshotManager::new("./snapshots"));
+    let snapshot_manager = Arc::new(SnapshotManager::from_config(&config.archival));
 
     // ============================================================================
     // PHASE 2: CREATE ENGINE COMPONENTS
@@ -76,37 +143,47 @@ async fn main() -> Result<()> {
 
     // Settlement layer
     let balance_manager = Arc::new(RwLock::new(BalanceManager::new()));
-    let position_manager = Arc::new(RwLock::new(PositionManager::new()));
+    let position_manager = Arc::new(RwLock::new(
+        PositionManager::new_with_market(market_id).with_contract(config.market.contract.clone()),
+    ));
     info!("Settlement layer initialized");
 
     // Matching engine
-    let order_book = Arc::new(RwLock::new(OrderBook::new()));
-    let matcher = Arc::new(RwLock::new(Matcher::new(
-        OrderBook::new(),
-        config.fees.clone(),
-        market_id,
-    )));
+    // The matcher, and with it the only order book, lives on its own thread;
+    // everything else talks to it through the handle
+    let matching_core = MatchingCore::spawn(
+        Matcher::new(OrderBook::new(), config.fees.clone(), market_id)
+            .with_contract(config.market.contract.clone())
+            .with_self_trade_policy(SelfTradePolicy::from_market(&config.market))
+            .with_matching_algorithm(algorithm::for_market(&config.market)),
+        MATCHING_QUEUE_CAPACITY,
+    )?;
+    let order_archive = Arc::new(RwLock::new(OrderArchive::new(config.order_archive.clone())));
     info!("Matching engine initialized");
 
     // Risk engine
-    let margin_calculator = Arc::new(MarginCalculator::new(config.risk.clone()));
+    let margin_calculator = Arc::new(
+        MarginCalculator::new(config.risk.clone()).with_contract(config.market.contract.clone()),
+    );
     info!("Risk engine initialized");
 
     // Funding engine
     let funding_rate_calculator = FundingRateCalculator::new(config.funding.clone());
     let funding_applicator = Arc::new(FundingApplicator::new(
         funding_rate_calculator,
-        Duration::from_secs(28800), // 8 hours
-    ));
+        config.funding.funding_interval.duration(),
+    ).with_contract(config.market.contract.clone()));
     info!("Funding engine initialized");
 
     // Liquidation engine
     let insurance_fund = Arc::new(InsuranceFund::new());
-    let liquidation_detector = Arc::new(LiquidationDetector::new(margin_calculator.clone()));
+    let liquidation_detector = Arc::new(LiquidationDetector::new(MarginCalculator::clone(&margin_calculator))
+        .with_margin_call_ratio(config.liquidation.margin_call_ratio));
     let liquidation_executor = Arc::new(LiquidationExecutor::new(
         market_id,
         insurance_fund.clone(),
-    ));
+    ).with_slicing(config.liquidation.clone())
+        .with_contract(config.market.contract.clone()));
     info!("Liquidation engine initialized");
 
     // ============================================================================
@@ -115,26 +192,126 @@ async fn main() -> Result<()> {
 
     let mut event_processor = EventProcessor::new_with_dependencies(
         market_id,
+        config.market.clone(),
+        config.risk.clone(),
         balance_manager.clone(),
         position_manager.clone(),
-        order_book.clone(),
-        matcher.clone(),
+        matching_core.clone(),
+        order_archive.clone(),
         margin_calculator.clone(),
         funding_applicator.clone(),
         liquidation_executor.clone(),
         event_producer.clone(),
     );
 
+    // User stream (order rejections etc.) shared with the WebSocket API
+    let (user_stream_tx, _) = broadcast::channel(1000);
+    event_processor.set_user_stream(user_stream_tx.clone());
+    let risk_tally = Arc::new(RiskTally::new());
+    event_processor.set_risk_tally(risk_tally.clone());
+    event_processor.set_adaptive_thresholds(&config.volatility);
+    event_processor.set_socialized_loss_period(Duration::from_secs(config.liquidation.socialized_loss_period_secs));
+    let funding_history = Arc::new(RwLock::new(
+        FundingPaymentHistory::open(&config.funding.payment_history_path)?,
+    ));
+    event_processor.set_funding_history(funding_history.clone());
+
+    // User notifications (large fills, liquidations, withdrawals) per saved preferences
+    let notification_preferences = Arc::new(RwLock::new(
+        NotificationPreferences::load(&config.notifications.preferences_path)?,
+    ));
+    if config.notifications.enabled {
+        let (notifier, notification_dispatcher) = NotificationDispatcher::new(
+            &config.notifications,
+            notification_preferences.clone(),
+            user_stream_tx.clone(),
+        )?;
+        event_processor.set_notifier(notifier);
+        task_supervisor.spawn("notification_dispatcher", async move {
+            notification_dispatcher.run().await;
+        });
+    }
+
     // Try to restore from snapshot
     match snapshot_manager.load_latest(market_id).await {
         Ok(snapshot) => {
             info!("Restoring from snapshot at sequence {}", snapshot.sequence);
-            event_processor.restore_from_snapshot(&snapshot)?;
+            event_processor.restore_from_snapshot(&snapshot).await?;
             info!("State restored from snapshot");
         }
-        Err(_) => {
+        Err(Error::NoSnapshotFound) => {
             info!("No snapshot found, starting from beginning");
         }
+        Err(e) => {
+            // Events before the snapshot may already be pruned, so starting empty is unsafe
+            error!("Cannot restore the latest snapshot: {}", e);
+            return Err(e);
+        }
+    }
+
+    // Replay to the end of the log before anything is decided from state:
+    // the snapshot may predate funding (or trades) that are already logged
+    let (log_start, log_end) = event_consumer.watermarks()?;
+    if let Some(last) = last_logged.filter(|last| *last > event_processor.last_sequence()) {
+        info!("Replaying events {}..={}", event_processor.last_sequence() + 1, last);
+    }
+    while event_consumer.next_offset().max(log_start) < log_end {
+        let event = event_consumer.fetch_next_event().await?;
+        let sequence = event.sequence;
+        if let Err(e) = event_processor.process_event(event).await {
+            if is_fatal_error(&e) {
+                error!("Replay stopped at sequence {}: {:?}", sequence, e);
+                return Err(e);
+            }
+            error!("Event processing failed during replay: {:?}", e);
+        }
+    }
+
+    // Settle funding intervals that elapsed while the process was down,
+    // from the last interval the log settled. Only rates are published: the
+    // processor pays each interval from its positions and ignores one that
+    // is already settled. Dated futures pay no funding.
+    let last_funding = if !config.market.has_funding() {
+        None
+    } else {
+        let position_mgr = position_manager.read().await;
+        let settled_until = position_mgr.cumulative_funding().last_funding;
+        position_mgr.get_all_positions().iter()
+            .filter(|p| !p.is_flat())
+            .map(|p| p.last_funding_timestamp.max(settled_until))
+            .max()
+    };
+
+    if let Some(last_funding) = last_funding {
+        let premium_log = PremiumLog::new(&config.funding.premium_log_path, Duration::from_secs(60));
+        let missed = missed_intervals(last_funding, Timestamp::now(), funding_applicator.funding_interval());
+
+        if !missed.is_empty() {
+            warn!("Catching up {} missed funding interval(s)", missed.len());
+        }
+
+        for (interval_start, interval_end) in missed {
+            let samples = premium_log.load_between(interval_start, interval_end)?;
+            let catch_up_event = funding_applicator.calculate_catch_up(
+                interval_start,
+                interval_end,
+                &samples,
+                market_id,
+            )?;
+
+            info!(
+                "Funding catch-up for interval ending {}: rate={:.6}, source={:?}",
+                interval_end.physical,
+                catch_up_event.funding_rate.to_f64(),
+                catch_up_event.catch_up.as_ref().map(|c| c.premium_source),
+            );
+
+            let base = catch_up_event.base.clone();
+            event_producer.produce(BaseEvent {
+                payload: EventPayload::Funding(Box::new(catch_up_event)),
+                ..base
+            }).await?;
+        }
     }
 
     info!("Event processor initialized");
@@ -144,19 +321,21 @@ async fn main() -> Result<()> {
     // ============================================================================
 
     info!("Connecting to price sources...");
-    let mut binance = BinanceConnector::new("btcusdt");
-    let mut coinbase = CoinbaseConnector::new("BTC-USD");
-    let mut kraken = KrakenConnector::new("XBTUSD");
-
-    binance.connect().await?;
-    coinbase.connect().await?;
-    kraken.connect().await?;
-
-    let price_aggregator = Arc::new(RwLock::new(PriceAggregator::new(vec![
-        Box::new(binance),
-        Box::new(coinbase),
-        Box::new(kraken),
-    ])));
+    // Exchange symbols come from [symbol_map] so markets are onboarded through config
+    let mut connectors = connectors_for_market(&config.market.symbol, &config.symbol_map)?;
+    for connector in connectors.iter_mut() {
+        connector.connect().await?;
+    }
+
+    // Each source streams on its own task and reconnects with backoff when its stream drops
+    let (raw_price_tx, mut raw_price_rx) = mpsc::channel(1000);
+    for connector in connectors {
+        let name = format!("price_connector_{}", connector.source_id());
+        task_supervisor.spawn(name, run_connector(connector, raw_price_tx.clone(), config.price_connectors.clone()));
+    }
+    drop(raw_price_tx);
+
+    let price_aggregator = Arc::new(RwLock::new(PriceAggregator::new(config.price_sources.clone())));
     info!("Price infrastructure connected");
 
     // Channel for price updates (broadcast for multiple consumers)
@@ -166,31 +345,71 @@ async fn main() -> Result<()> {
     let price_agg_clone = price_aggregator.clone();
     let price_producer = event_producer.clone();
     let price_market_id = market_id;
+    let price_matching = matching_core.clone();
+    let mut price_premium_log = PremiumLog::new(&config.funding.premium_log_path, Duration::from_secs(60));
+    let price_funding_applicator = funding_applicator.clone();
+    // Realized index volatility: served by the API and, when adaptive, scales the outlier threshold
+    let mut price_history = IndexPriceHistory::new(&config.volatility);
+    let adaptive_thresholds = config.volatility.adaptive_thresholds;
+    let volatility = Arc::new(RwLock::new(None));
+    let volatility_writer = volatility.clone();
+    let snapshot_tx = price_tx.clone();
     task_supervisor.spawn("price_aggregation", async move {
         let mut interval = interval(Duration::from_millis(100)); // 10 Hz
+        let mut latest_prices = HashMap::new();  // source_id -> latest update
         loop {
             interval.tick().await;
 
-            let aggregator = price_agg_clone.read().await;
-            match aggregator.aggregate().await {
+            while let Ok(update) = raw_price_rx.try_recv() {
+                latest_prices.insert(update.source_id.clone(), update);
+            }
+
+            if adaptive_thresholds {
+                price_agg_clone.write().await.set_threshold_scale(price_history.threshold_scale());
+            }
+
+            let raw_prices: Vec<RawPriceUpdate> = latest_prices.values().cloned().collect();
+            let perp_last_price = match perp_reference_price(&price_matching.book(), &raw_prices) {
+                Some(price) => price,
+                None => continue,  // No source has reported yet
+            };
+
+            let aggregated = price_agg_clone.write().await.aggregate(raw_prices, perp_last_price, price_market_id);
+            match aggregated {
                 Ok(snapshot) => {
                     // Send to price channel (broadcast)
-                    let _ = price_tx.send(snapshot.clone());
+                    let _ = snapshot_tx.send(snapshot.clone());
+
+                    if price_history.record(snapshot.index_price, Timestamp::now()) {
+                        let stats = price_history.stats();
+                        if let Some(stats) = &stats {
+                            REALIZED_VOLATILITY.set(stats.realized_volatility);
+                            REALIZED_VARIANCE.set(stats.realized_variance);
+                            PRICE_THRESHOLD_SCALE.set(stats.threshold_scale);
+                        }
+                        *volatility_writer.write().await = stats;
+                    }
+
+                    // Premium index TWAP the funding rate is set from
+                    price_funding_applicator.record_premium(
+                        snapshot.mark_price,
+                        snapshot.index_price,
+                        Timestamp::now(),
+                    );
+
+                    // Premium journal for funding catch-up after downtime
+                    if let Err(e) = price_premium_log.record(
+                        snapshot.mark_price,
+                        snapshot.index_price,
+                        Timestamp::now(),
+                    ) {
+                        warn!("Failed to record premium sample: {:?}", e);
+                    }
 
                     // Emit price event
-                    let price_event = PriceSnapshot {
-                        base: BaseEvent::new(
-                            EventType::PriceUpdate,
-                            price_market_id,
-                        ),
-                        mark_price: snapshot.mark_price,
-                        index_price: snapshot.index_price,
-                        source_prices: snapshot.source_prices,
-                        aggregation_method: snapshot.aggregation_method,
-                        staleness_flags: snapshot.staleness_flags,
-                    };
-
-                    if let Err(e) = price_producer.produce(price_event.base).await {
+                    let base = snapshot.base.clone();
+                    let price_event = BaseEvent { payload: EventPayload::PriceSnapshot(Box::new(snapshot)), ..base };
+                    if let Err(e) = price_producer.produce(price_event).await {
                         error!("Failed to produce price event: {:?}", e);
                     }
                 }
@@ -201,55 +420,122 @@ async fn main() -> Result<()> {
         }
     });
 
+    // Official price feed for downstream consumers (dashboards, bridges, other venues)
+    if config.oracle.enabled {
+        let mut oracle_publisher = OraclePublisher::new(
+            &config.kafka.brokers,
+            &config.kafka.security,
+            &config.oracle,
+            market_id,
+        )?;
+        let mut oracle_price_rx = price_tx.subscribe();
+        task_supervisor.spawn("oracle_publisher", async move {
+            loop {
+                match oracle_price_rx.recv().await {
+                    Ok(price_snapshot) => {
+                        if let Err(e) = oracle_publisher.publish(&price_snapshot).await {
+                            warn!("Failed to publish oracle price: {:?}", e);
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,  // Only the latest price matters
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        });
+    }
+
     // ============================================================================
     // PHASE 5: START FUNDING TICKER
     // ============================================================================
 
-    let funding_ticker = FundingTicker::new(
-        funding_applicator.clone(),
-        Duration::from_secs(28800), // 8 hours
-    );
+    // Dated futures converge to the index through expiry settlement instead
+    if config.market.has_funding() {
+        let funding_ticker = FundingTicker::new(
+            funding_applicator.clone(),
+            event_producer.clone(),
+            funding_applicator.funding_interval(),
+        );
+
+        // Funding rates are published to the event log; the processor pays them
+        let funding_market_id = market_id;
+        let mut funding_price_rx = price_tx.subscribe();
+        task_supervisor.spawn("funding_ticker", async move {
+            let mut interval = interval(funding_ticker.interval());
+            loop {
+                interval.tick().await;
+
+                // Get current mark and index prices
+                match funding_price_rx.try_recv() {
+                    Ok(price_snapshot) => {
+                        match funding_ticker.publish(
+                            price_snapshot.mark_price,
+                            price_snapshot.index_price,
+                            funding_market_id,
+                        ).await {
+                            Ok(funding_event) => {
+                                info!("Funding published: rate={:.6}", funding_event.funding_rate.to_f64());
+                            }
+                            Err(e) => {
+                                error!("Funding publication failed: {:?}", e);
+                            }
+                        }
+                    }
+                    Err(_) => {
+                        warn!("No price data available for funding");
+                    }
+                }
+            }
+        });
+    }
 
-    let funding_balance_mgr = balance_manager.clone();
-    let funding_position_mgr = position_manager.clone();
-    let funding_market_id = market_id;
-    let mut funding_price_rx = price_tx.subscribe();
-    task_supervisor.spawn("funding_ticker", async move {
-        let mut interval = interval(Duration::from_secs(28800)); // 8 hours
-        loop {
-            interval.tick().await;
+    // Expiry settlement: index TWAP over the final window, then close every position
+    if let Some(expiry) = config.market.expiry() {
+        let mut expiry_settler = ExpirySettler::new(expiry, market_id);
+        let expiry_producer = event_producer.clone();
+        let mut expiry_price_rx = price_tx.subscribe();
+
+        task_supervisor.spawn("expiry_settlement", async move {
+            let mut interval = interval(Duration::from_secs(1));
+            loop {
+                interval.tick().await;
+
+                loop {
+                    match expiry_price_rx.try_recv() {
+                        Ok(price_snapshot) => expiry_settler.record_index(
+                            price_snapshot.index_price,
+                            price_snapshot.base.timestamp,
+                        ),
+                        Err(broadcast::error::TryRecvError::Lagged(_)) => continue,
+                        Err(_) => break,
+                    }
+                }
 
-            info!("Applying funding payments");
-            let positions = funding_position_mgr.read().await;
-            let mut balance_mgr = funding_balance_mgr.write().await;
+                if !expiry_settler.is_due(Timestamp::now()) {
+                    continue;
+                }
 
-            // Get current mark and index prices
-            match funding_price_rx.try_recv() {
-                Ok(price_snapshot) => {
-                    let positions_vec: Vec<_> = positions.positions.values().cloned().collect();
-                    match funding_ticker.applicator.apply_funding(
-                        &positions_vec,
-                        price_snapshot.mark_price,
-                        price_snapshot.index_price,
-                        &mut *balance_mgr,
-                        funding_market_id,
-                    ) {
-                        Ok(funding_event) => {
-                            info!("Funding applied: rate={:.6}, payments={}", 
-                                  funding_event.funding_rate.to_f64(),
-                                  funding_event.payments.len());
-                        }
-                        Err(e) => {
-                            error!("Funding application failed: {:?}", e);
-                        }
+                let settlement = match expiry_settler.settlement_event() {
+                    Some(settlement) => settlement,
+                    None => {
+                        warn!("Market expired but no index price yet, settlement waiting");
+                        continue;
                     }
-                }
-                Err(_) => {
-                    warn!("No price data available for funding");
+                };
+                info!(
+                    "Settling expired market at {} (TWAP of {} samples)",
+                    settlement.settlement_price.to_f64(), settlement.twap_samples
+                );
+                let base = settlement.base.clone();
+                match expiry_producer.produce(BaseEvent {
+                    payload: EventPayload::ExpirySettlement(Box::new(settlement)),
+                    ..base
+                }).await {
+                    Ok(_) => break,
+                    Err(e) => error!("Failed to publish expiry settlement: {:?}", e),
                 }
             }
-        }
-    });
+        });
+    }
 
     // ============================================================================
     // PHASE 6: START LIQUIDATION MONITOR
@@ -259,48 +545,97 @@ async fn main() -> Result<()> {
     let liq_executor = liquidation_executor.clone();
     let liq_balance_mgr = balance_manager.clone();
     let liq_position_mgr = position_manager.clone();
-    let liq_matcher = matcher.clone();
     let liq_producer = event_producer.clone();
     let liq_market_id = market_id;
+    let liq_funding_applicator = funding_applicator.clone();
+    let liq_has_funding = config.market.has_funding();
     let mut liq_price_rx = price_tx.subscribe();
     task_supervisor.spawn("liquidation_monitor", async move {
         let mut interval = interval(Duration::from_secs(1)); // Check every second
+        let mut liq_mark_price = Price::zero();
+        let mut margin_called = std::collections::HashSet::new();  // Warned once until they recover
         loop {
             interval.tick().await;
 
             // Get current price
             match liq_price_rx.try_recv() {
                 Ok(price_snapshot) => {
+                    liq_mark_price = price_snapshot.mark_price;
                     let positions = liq_position_mgr.read().await;
                     let balance_mgr = liq_balance_mgr.read().await;
-                    let positions_vec: Vec<_> = positions.positions.values().cloned().collect();
+                    let positions_vec: Vec<_> = positions.get_all_positions().into_iter().cloned().collect();
 
-                    match liq_detector.detect_liquidations(
+                    // Perpetuals margin the funding accrued since the last settlement
+                    let funding = liq_has_funding.then(|| liq_funding_applicator.accrual(
+                        price_snapshot.mark_price,
+                        price_snapshot.index_price,
+                        Timestamp::now(),
+                        positions.cumulative_funding(),
+                    ));
+                    match liq_detector.scan(
                         &positions_vec,
                         price_snapshot.mark_price,
+                        funding.as_ref(),
                         &*balance_mgr,
                     ) {
-                        Ok(candidates) => {
+                        Ok(scan) => {
+                            let mut still_called = std::collections::HashSet::new();
+                            for call in scan.margin_calls {
+                                let key = (call.user_id, call.position_side);
+                                still_called.insert(key);
+                                if margin_called.contains(&key) {
+                                    continue;
+                                }
+
+                                let warning = MarginCallWarning {
+                                    base: BaseEvent::new(EventType::MarginCallWarning, liq_market_id),
+                                    user_id: call.user_id,
+                                    position_side: call.position_side,
+                                    margin_ratio: call.margin_ratio,
+                                    equity: call.equity,
+                                    maintenance_margin: call.maintenance_margin,
+                                    mark_price: call.mark_price,
+                                };
+                                let base = warning.base.clone();
+                                if let Err(e) = liq_producer.produce(BaseEvent {
+                                    payload: EventPayload::MarginCallWarning(Box::new(warning)),
+                                    ..base
+                                }).await {
+                                    error!("Failed to produce margin call for {:?}: {:?}", call.user_id, e);
+                                    still_called.remove(&key);  // Retried next cycle
+                                }
+                            }
+                            margin_called = still_called;
+
+                            let candidates = scan.liquidations;
                             if !candidates.is_empty() {
                                 warn!("Detected {} liquidation candidates", candidates.len());
 
                                 // Emit liquidation events to Kafka (event-driven approach)
                                 // This maintains single-writer principle - EventProcessor will handle execution
                                 for candidate in candidates {
-                                    let liquidation_event = crate::events::liquidation::LiquidationTriggered {
-                                        base: crate::events::base::BaseEvent::new(
-                                            crate::events::base::EventType::Liquidation,
+                                    // One trigger per leg until the executor has handled it
+                                    let leg = candidate.position.position_side;
+                                    if !liq_executor.claim_trigger(candidate.user_id, leg, Timestamp::now()) {
+                                        continue;
+                                    }
+
+                                    let liquidation_event = PerpInfra::events::liquidation::LiquidationTriggered {
+                                        base: PerpInfra::events::base::BaseEvent::new(
+                                            PerpInfra::events::base::EventType::Liquidation,
                                             liq_market_id,
                                         ),
                                         user_id: candidate.user_id,
-                                        position_size: candidate.position_size,
+                                        position_size: Quantity::from_i64(candidate.position.size.abs()),
                                         mark_price: price_snapshot.mark_price,
                                         maintenance_margin: candidate.maintenance_margin,
-                                        account_value: candidate.account_value,
+                                        account_value: candidate.maintenance_margin + candidate.maintenance_surplus,
+                                        position_side: candidate.position.position_side,
                                     };
 
                                     if let Err(e) = liq_producer.produce(liquidation_event.base).await {
                                         error!("Failed to produce liquidation event: {:?}", e);
+                                        liq_executor.release_trigger(candidate.user_id, leg);  // Retried next cycle
                                     } else {
                                         info!("Liquidation event emitted for user={:?}", candidate.user_id);
                                     }
@@ -316,40 +651,151 @@ async fn main() -> Result<()> {
                     // No price update, skip this cycle
                 }
             }
+
+            // Next child orders of sliced liquidations (ended by the executor once margin is restored)
+            for sliced in liq_executor.due_slices(Timestamp::now()) {
+                if !liq_executor.claim_trigger(sliced.user_id, sliced.position_side, Timestamp::now()) {
+                    continue;  // Already triggered by the detector or an earlier cycle
+                }
+                let liquidation_event = PerpInfra::events::liquidation::LiquidationTriggered {
+                    base: PerpInfra::events::base::BaseEvent::new(
+                        PerpInfra::events::base::EventType::Liquidation,
+                        liq_market_id,
+                    ),
+                    user_id: sliced.user_id,
+                    position_size: sliced.remaining,
+                    mark_price: liq_mark_price,
+                    maintenance_margin: sliced.maintenance_margin,
+                    account_value: Balance::zero(),  // Not used to size a continuing liquidation
+                    position_side: sliced.position_side,
+                };
+
+                if let Err(e) = liq_producer.produce(liquidation_event.base).await {
+                    error!("Failed to produce liquidation slice for {:?}: {:?}", sliced.liquidation_id, e);
+                    liq_executor.release_trigger(sliced.user_id, sliced.position_side);
+                }
+            }
         }
     });
 
+    // Warm-up: restricted trading until prices settle and a full invariant pass succeeds
+    if config.warm_up.enabled {
+        let mut warm_up = WarmUp::new(&config.warm_up, market_id, Timestamp::now());
+        let warm_up_monitor = InvariantMonitor::new(KillSwitch::clone(&kill_switch));
+        let warm_up_matching = matching_core.clone();
+        let warm_up_balance_mgr = balance_manager.clone();
+        let warm_up_position_mgr = position_manager.clone();
+        let warm_up_producer = event_producer.clone();
+        let mut warm_up_price_rx = price_tx.subscribe();
+
+        let start = warm_up.start_event();
+        info!("Market warming up in {:?} phase", start.phase);
+        let base = start.base.clone();
+        event_producer.produce(BaseEvent {
+            payload: EventPayload::TradingPhaseChanged(Box::new(start)),
+            ..base
+        }).await?;
+
+        task_supervisor.spawn("engine_warm_up", async move {
+            let mut interval = interval(Duration::from_secs(1));
+            let mut latest_price = None;
+            loop {
+                interval.tick().await;
+
+                loop {
+                    match warm_up_price_rx.try_recv() {
+                        Ok(price_snapshot) => {
+                            warm_up.record_price_update();
+                            latest_price = Some(price_snapshot);
+                        }
+                        Err(broadcast::error::TryRecvError::Lagged(_)) => continue,
+                        Err(_) => break,
+                    }
+                }
+
+                let price_snapshot = match &latest_price {
+                    Some(price_snapshot) if warm_up.prices_settled() => price_snapshot,
+                    _ => continue,
+                };
+
+                let book_checks = warm_up_matching.book_invariants().await
+                    .and_then(|checks| checks.into_iter().try_for_each(|(_, result)| result));
+                let result = book_checks.and({
+                    let balance_mgr_guard = warm_up_balance_mgr.read().await;
+                    let position_mgr_guard = warm_up_position_mgr.read().await;
+                    let positions_vec: Vec<_> = position_mgr_guard.get_all_positions().into_iter().cloned().collect();
+                    warm_up_monitor.check_all_invariants(
+                        &balance_mgr_guard,
+                        &positions_vec,
+                        price_snapshot.mark_price,
+                        price_snapshot.index_price,
+                    )
+                });
+                if let Err(e) = &result {
+                    warn!("Warm-up invariant pass failed, market stays restricted: {:?}", e);
+                }
+                warm_up.record_invariant_pass(result.is_ok());
+
+                if warm_up.is_ready(Timestamp::now()) {
+                    let open = warm_up.open_event();
+                    let base = open.base.clone();
+                    match warm_up_producer.produce(BaseEvent {
+                        payload: EventPayload::TradingPhaseChanged(Box::new(open)),
+                        ..base
+                    }).await {
+                        Ok(_) => {
+                            info!("Warm-up complete, market open for trading");
+                            break;
+                        }
+                        Err(e) => error!("Failed to open market after warm-up: {:?}", e),
+                    }
+                }
+            }
+        });
+    }
+
     // ============================================================================
     // PHASE 7: START INVARIANT MONITOR
     // ============================================================================
 
-    let invariant_monitor = InvariantMonitor::new(kill_switch.clone());
-    let inv_order_book = order_book.clone();
+    let invariant_monitor = InvariantMonitor::new(KillSwitch::clone(&kill_switch));
+    let inv_matching = matching_core.clone();
     let inv_balance_mgr = balance_manager.clone();
     let inv_position_mgr = position_manager.clone();
     let mut inv_price_rx = price_tx.subscribe();
+    let inv_kill_switch = kill_switch.clone();
     task_supervisor.spawn("invariant_monitor", async move {
         let mut interval = interval(Duration::from_secs(1)); // Check every second
         loop {
             interval.tick().await;
 
-            let order_book_guard = inv_order_book.read().await;
+            // Book invariants run on the matching core against the live book
+            match inv_matching.book_invariants().await {
+                Ok(checks) => {
+                    if let Some((invariant, Err(e))) = checks.into_iter().find(|(_, result)| result.is_err()) {
+                        error!("INVARIANT VIOLATION ({}): {:?}", invariant, e);
+                        inv_kill_switch.activate(format!("Invariant violation: {:?}", e));
+                    }
+                }
+                Err(e) => warn!("Book invariants not checked: {:?}", e),
+            }
+
             let balance_mgr_guard = inv_balance_mgr.read().await;
             let position_mgr_guard = inv_position_mgr.read().await;
 
             // Get current price
             match inv_price_rx.try_recv() {
                 Ok(price_snapshot) => {
-                    let positions_vec: Vec<_> = position_mgr_guard.positions.values().cloned().collect();
+                    let positions_vec: Vec<_> = position_mgr_guard.get_all_positions().into_iter().cloned().collect();
 
                     if let Err(e) = invariant_monitor.check_all_invariants(
-                        &*order_book_guard,
-                        &*balance_mgr_guard,
+                        &balance_mgr_guard,
                         &positions_vec,
                         price_snapshot.mark_price,
+                        price_snapshot.index_price,
                     ) {
                         error!("INVARIANT VIOLATION: {:?}", e);
-                        kill_switch.activate(format!("Invariant violation: {:?}", e));
+                        inv_kill_switch.activate(format!("Invariant violation: {:?}", e));
                     }
                 }
                 Err(_) => {
@@ -363,20 +809,307 @@ async fn main() -> Result<()> {
     // PHASE 8: START REST API SERVER
     // ============================================================================
 
+    // API reads come from published views so the engine's locks stay uncontended
+    let (read_model_publisher, read_models) = ReadModelPublisher::new(
+        balance_manager.clone(),
+        position_manager.clone(),
+        matching_core.clone(),
+    );
+    task_supervisor.spawn("read_model_publisher", async move {
+        read_model_publisher.run(Duration::from_millis(50)).await;
+    });
+
+    // Latest mark price and funding accrual for API-side risk calculations
+    let api_mark_price = Arc::new(RwLock::new(Price::zero()));
+    let api_mark_price_writer = api_mark_price.clone();
+    let api_funding_accrual = Arc::new(RwLock::new(None));
+    let api_funding_accrual_writer = api_funding_accrual.clone();
+    let api_funding_applicator = funding_applicator.clone();
+    let api_funding_read_models = read_models.clone();
+    let predicted_funding = Arc::new(PredictedFundingTracker::new(config.funding.clone()));
+    let predicted_funding_writer = predicted_funding.clone();
+    let api_has_funding = config.market.has_funding();
+    let mut api_price_rx = price_tx.subscribe();
+    task_supervisor.spawn("api_mark_price", async move {
+        while let Ok(price_snapshot) = api_price_rx.recv().await {
+            *api_mark_price_writer.write().await = price_snapshot.mark_price;
+            if api_has_funding {
+                *api_funding_accrual_writer.write().await = Some(api_funding_applicator.accrual(
+                    price_snapshot.mark_price,
+                    price_snapshot.index_price,
+                    price_snapshot.base.timestamp,
+                    api_funding_read_models.cumulative_funding(),
+                ));
+                predicted_funding_writer.record(
+                    price_snapshot.mark_price,
+                    price_snapshot.index_price,
+                    price_snapshot.base.timestamp,
+                );
+            }
+        }
+    });
+
+    // Recovery and halt controls run on the event loop below, which owns the
+    // consumer and the processor
+    let (recovery_tx, mut recovery_rx) = mpsc::channel::<RecoveryCommand>(1);
+    let (processor_tx, mut processor_rx) = mpsc::channel::<ProcessorCommand>(8);
+    let (migration_tx, mut migration_rx) = mpsc::channel::<MigrationCommand>(1);
+    let mut recovery = RecoveryProcedure::new(
+        market_id,
+        snapshot_manager.clone(),
+        balance_manager.clone(),
+        position_manager.clone(),
+        matching_core.clone(),
+    );
+
+    // Daily risk report for the risk committee
+    let latest_risk_report = Arc::new(RwLock::new(None));
+    if config.risk_report.enabled {
+        let mut risk_reporter = DailyRiskReporter::new(config.risk_report.clone(), market_id)?
+            .with_contract(config.market.contract.clone())
+            .with_tally(risk_tally.clone());
+        *latest_risk_report.write().await = risk_reporter.latest().cloned();
+
+        let report_balance_mgr = balance_manager.clone();
+        let report_position_mgr = position_manager.clone();
+        let report_matching = matching_core.clone();
+        let report_insurance_fund = insurance_fund.clone();
+        let report_mark_price = api_mark_price.clone();
+        let report_latest = latest_risk_report.clone();
+
+        task_supervisor.spawn("daily_risk_report", async move {
+            let mut interval = interval(Duration::from_secs(30));
+            loop {
+                interval.tick().await;
+
+                let cutoff = match risk_reporter.due_cutoff(Timestamp::now()) {
+                    Some(cutoff) => cutoff,
+                    None => continue,
+                };
+
+                let mark_price = *report_mark_price.read().await;
+                let book_checks = match report_matching.book_invariants().await {
+                    Ok(book_checks) => book_checks,
+                    Err(e) => {
+                        error!("Daily risk report postponed, book unavailable: {:?}", e);
+                        continue;
+                    }
+                };
+                let report = {
+                    let balance_mgr = report_balance_mgr.read().await;
+                    let position_mgr = report_position_mgr.read().await;
+                    risk_reporter.generate(
+                        cutoff,
+                        &balance_mgr,
+                        &position_mgr,
+                        book_checks,
+                        report_insurance_fund.get_balance(),
+                        mark_price,
+                    )
+                };
+
+                match report {
+                    Ok(report) => *report_latest.write().await = Some(report),
+                    Err(e) => error!("Daily risk report failed: {:?}", e),
+                }
+            }
+        });
+    }
+
+    // Dead man's switch: expired countdowns become CancelAllOrders in the event log
+    let deadmans_switch = Arc::new(RwLock::new(DeadMansSwitch::new()));
+    let dms_switches = deadmans_switch.clone();
+    let dms_producer = event_producer.clone();
+    task_supervisor.spawn("deadmans_switch", async move {
+        let mut interval = interval(Duration::from_millis(500));
+        loop {
+            interval.tick().await;
+
+            let expired = dms_switches.write().await.expired(Timestamp::now());
+            for switch in expired {
+                warn!("Dead man's switch expired for {:?}, cancelling all orders", switch.user_id);
+                PerpInfra::observability::metrics::DEADMANS_SWITCH_TRIGGERED.inc();
+
+                let cancel_all = CancelAllOrders {
+                    base: BaseEvent::new(EventType::CancelAllOrders, market_id),
+                    user_id: Some(switch.user_id),
+                    operator_id: None,
+                    reason: "deadmans_switch".to_string(),
+                };
+                let base = cancel_all.base.clone();
+                if let Err(e) = dms_producer.produce(BaseEvent {
+                    payload: EventPayload::CancelAllOrders(Box::new(cancel_all)),
+                    ..base
+                }).await {
+                    error!("Failed to produce dead man's switch cancel for {:?}: {:?}", switch.user_id, e);
+                }
+            }
+        }
+    });
+
+    // GTD expiry: once the earliest expiry on the book has passed, an
+    // ExpireOrders sweep goes through the event log
+    let expiry_matching = matching_core.clone();
+    let expiry_producer = event_producer.clone();
+    task_supervisor.spawn("gtd_expiry", async move {
+        let mut interval = interval(Duration::from_millis(500));
+        loop {
+            interval.tick().await;
+
+            let now = Timestamp::now();
+            let due = expiry_matching.execute(move |matcher| {
+                matcher.order_book().next_expiry().is_some_and(|expires_at| expires_at <= now)
+            }).await.unwrap_or(false);
+            if !due {
+                continue;
+            }
+
+            let sweep = ExpireOrders {
+                base: BaseEvent::new(EventType::ExpireOrders, market_id),
+            };
+            let base = sweep.base.clone();
+            if let Err(e) = expiry_producer.produce(BaseEvent {
+                payload: EventPayload::ExpireOrders(Box::new(sweep)),
+                ..base
+            }).await {
+                error!("Failed to produce GTD expiry sweep: {:?}", e);
+            }
+        }
+    });
+
+    // TWAP slicing: due child orders go through the event log like client orders
+    let twap_engine = Arc::new(RwLock::new(TwapEngine::new(market_id, config.market.lot_size)));
+    let twap_slicer = twap_engine.clone();
+    let twap_producer = event_producer.clone();
+    task_supervisor.spawn("twap_slicer", async move {
+        let mut interval = interval(Duration::from_millis(250));
+        loop {
+            interval.tick().await;
+
+            let children = twap_slicer.write().await.due_slices(Timestamp::now());
+            for child in children {
+                let base = child.base.clone();
+                if let Err(e) = twap_producer.produce(BaseEvent {
+                    payload: EventPayload::OrderSubmit(Box::new(child)),
+                    ..base
+                }).await {
+                    error!("Failed to produce TWAP slice for {:?}: {:?}", base.correlation_id, e);
+                }
+            }
+        }
+    });
+
+    // Liquidation engine unwinding: inventory the insurance fund took over
+    // is quoted back passively through the event log
+    if config.liquidation.unwind.enabled {
+        let unwinder = InventoryUnwinder::new(market_id, config.liquidation.unwind.clone(), config.market.contract.clone());
+        let unwind_interval = Duration::from_millis(config.liquidation.unwind.interval_ms);
+        let unwind_matching = matching_core.clone();
+        let unwind_position_mgr = position_manager.clone();
+        let unwind_mark_price = api_mark_price.clone();
+        let unwind_producer = event_producer.clone();
+        task_supervisor.spawn("engine_unwinder", async move {
+            let mut interval = interval(unwind_interval);
+            loop {
+                interval.tick().await;
+
+                let mark_price = *unwind_mark_price.read().await;
+                if mark_price == Price::zero() {
+                    continue;  // No price yet
+                }
+                let engine = *PerpInfra::LIQUIDATION_ENGINE_USER_ID;
+                let book = unwind_matching.execute(move |matcher| {
+                    let book = matcher.order_book();
+                    let engine_orders: Vec<Order> = book.user_orders(&engine).into_iter().cloned().collect();
+                    (BookTop { best_bid: book.best_bid(), best_ask: book.best_ask() }, engine_orders)
+                }).await;
+                let (top, engine_orders) = match book {
+                    Ok(book) => book,
+                    Err(e) => {
+                        error!("Liquidation engine unwind skipped, book unavailable: {:?}", e);
+                        continue;
+                    }
+                };
+                let orders = {
+                    let positions = unwind_position_mgr.read().await;
+                    let engine_orders: Vec<&Order> = engine_orders.iter().collect();
+                    unwinder.plan(
+                        positions.get_leg(&engine, PositionSide::Both),
+                        top,
+                        mark_price,
+                        &engine_orders,
+                        Timestamp::now(),
+                    )
+                };
+                for order in orders {
+                    if let Err(e) = unwind_producer.produce(order).await {
+                        error!("Failed to produce liquidation engine unwind order: {:?}", e);
+                    }
+                }
+            }
+        });
+    }
+
+    // Ingress sequencing: API orders are produced in receive order
+    let (ingress, ingress_sequencer) = IngressSequencer::new(&config.ingress);
+    let ingress_producer = event_producer.clone();
+    task_supervisor.spawn("ingress_sequencer", async move {
+        ingress_sequencer.run(ingress_producer).await;
+    });
+
+    // Tenants from config, plus the users holding keys issued to them
+    let api_keys = ApiKeyAuth::load(&config.api.api_keys_path)?;
+    let mut tenant_registry = TenantRegistry::from_config(&config.tenants);
+    for (tenant_id, user_id) in api_keys.tenant_members() {
+        tenant_registry.add_member(tenant_id, user_id);
+    }
+
     let api_state = Arc::new(ApiState {
-        balance_manager: balance_manager.clone(),
-        position_manager: position_manager.clone(),
+        read_models,
+        order_archive: order_archive.clone(),
+        funding_history,
+        tenant_registry: Arc::new(RwLock::new(tenant_registry)),
+        withdrawal_check: Arc::new(
+            WithdrawalRiskCheck::new(config.risk.clone()).with_contract(config.market.contract.clone()),
+        ),
+        margin_calculator: Arc::new(
+            MarginCalculator::new(config.risk.clone()).with_contract(config.market.contract.clone()),
+        ),
+        pre_trade_check: Arc::new(
+            PreTradeRiskCheck::new(config.risk.clone()).with_contract(config.market.contract.clone()),
+        ),
+        mark_price: api_mark_price,
+        funding_accrual: api_funding_accrual,
+        predicted_funding,
+        market_id,
+        event_producer: event_producer.clone(),
+        snapshot_manager: snapshot_manager.clone(),
+        recovery_tx,
+        processor_tx,
+        migration_tx,
+        api_keys: Arc::new(RwLock::new(api_keys)),
+        deadmans_switch,
+        latest_risk_report,
+        risk_reports_dir: config.risk_report.reports_dir.clone(),
+        twap_engine,
+        ingress,
+        notification_preferences,
+        volatility,
+        liquidation_stress: Arc::new(
+            LiquidationStress::new(liquidation_detector.clone(), config.liquidation.clone(), insurance_fund.clone())
+                .with_contract(config.market.contract.clone()),
+        ),
     });
 
-    let app = create_router(api_state);
+    let ws_state = Arc::new(WsState { event_tx: user_stream_tx });
+    let api_keys = api_state.api_keys.clone();
+    let app = create_router(api_state).merge(create_ws_router(ws_state, api_keys));
     let api_addr: SocketAddr = "0.0.0.0:8080".parse().unwrap();
 
     task_supervisor.spawn("rest_api_server", async move {
         info!("REST API listening on {}", api_addr);
-        Server::bind(&api_addr)
-            .serve(app.into_make_service())
-            .await
-            .unwrap();
+        let listener = tokio::net::TcpListener::bind(api_addr).await.unwrap();
+        axum::serve(listener, app).await.unwrap();
     });
 
     // ============================================================================
@@ -389,71 +1122,150 @@ async fn main() -> Result<()> {
 
     task_supervisor.spawn("metrics_exporter", async move {
         info!("Metrics endpoint listening on {}/metrics", metrics_addr);
-        Server::bind(&metrics_addr)
-            .serve(metrics_app.into_make_service())
-            .await
-            .unwrap();
+        let listener = tokio::net::TcpListener::bind(metrics_addr).await.unwrap();
+        axum::serve(listener, metrics_app).await.unwrap();
     });
 
     // ============================================================================
-    // PHASE 10: START SNAPSHOT CREATION TASK
+    // PHASE 10: SNAPSHOT SCHEDULE AND EVENT-LOG RETENTION
     // ============================================================================
 
-    let snapshot_mgr = snapshot_manager.clone();
-    let snapshot_balance_mgr = balance_manager.clone();
-    let snapshot_position_mgr = position_manager.clone();
-    let snapshot_market_id = market_id;
-    let mut snapshot_price_rx = price_tx.subscribe();
+    // Event-log retention: archives events and keeps broker retention aligned
+    // with the oldest snapshot still needed for recovery/compliance
+    let mut retention = EventLogRetentionManager::new(config.event_log_retention.clone());
+    if let Err(e) = retention.load_archive() {
+        error!("Failed to load the event archive: {:?}", e);
+    }
+    // Snapshots saved before this start still bound what the broker must keep
+    match snapshot_manager.saved_snapshots(market_id).await {
+        Ok(saved) => {
+            for (sequence, timestamp) in saved {
+                retention.record_snapshot(sequence, timestamp);
+            }
+            if let Some(floor) = retention.snapshot_floor() {
+                snapshot_manager.set_retention_floor(floor);
+            }
+        }
+        Err(e) => warn!("Failed to read saved snapshots for retention: {:?}", e),
+    }
+    let retention_manager = Arc::new(RwLock::new(retention));
+    let retention_brokers = config.kafka.brokers.clone();
+    let retention_topic = config.kafka.topic.clone();
+    let retention_security = config.kafka.security.clone();
+
+    // Snapshots are cut on the event loop, between events
+    let mut snapshot_interval = interval(Duration::from_secs(3600)); // Every hour
+    snapshot_interval.tick().await;  // The first tick is immediate
+
+    // Ledger archival: seal full segments, export them and prune expired
+    // archives (never past the newest snapshot)
+    let mut ledger_archiver = LedgerArchiver::new(&config.archival, market_id);
+    let archive_balance_mgr = balance_manager.clone();
+    let archive_snapshot_mgr = snapshot_manager.clone();
+    let archive_retention = match config.archival.retention_days {
+        0 => None,
+        days => Some(Duration::from_secs(days as u64 * 86400)),
+    };
+    let archive_interval = Duration::from_secs(config.archival.check_interval_secs);
+
+    task_supervisor.spawn("ledger_archival", async move {
+        let mut interval = interval(archive_interval);
+        loop {
+            interval.tick().await;
+
+            {
+                let mut balance_mgr = archive_balance_mgr.write().await;
+                ledger_archiver.roll_if_full(&mut balance_mgr.ledger);
+            }
 
-    // Create a channel to get last_sequence from event processor
-    let (snapshot_seq_tx, mut snapshot_seq_rx) = mpsc::channel::<u64>(1);
+            if let Err(e) = ledger_archiver.export_pending() {
+                error!("Ledger archival failed ({} segments pending): {:?}", ledger_archiver.pending_len(), e);
+            }
 
-    task_supervisor.spawn("snapshot_creator", async move {
-        let mut interval = interval(Duration::from_secs(3600)); // Every hour
+            let now = Timestamp::now();
+            let newest_snapshot = archive_snapshot_mgr.newest_snapshot_time();
+            if let Err(e) = ledger_archiver.prune(newest_snapshot, now) {
+                warn!("Failed to prune ledger archive: {:?}", e);
+            }
+            if let Err(e) = archive_snapshot_mgr.prune_archive(market_id, archive_retention, now) {
+                warn!("Failed to prune snapshot archive: {:?}", e);
+            }
+        }
+    });
+
+    // Terminal orders past their retention leave the archive (memory and spill files)
+    let purge_order_archive = order_archive.clone();
+    let purge_interval = Duration::from_secs(config.order_archive.purge_interval_secs);
+    task_supervisor.spawn("order_archive_purge", async move {
+        let mut interval = interval(purge_interval);
         loop {
             interval.tick().await;
+            if let Err(e) = purge_order_archive.write().await.purge_expired() {
+                warn!("Failed to purge the order archive: {:?}", e);
+            }
+        }
+    });
 
-            info!("Creating snapshot");
-            let balance_mgr = snapshot_balance_mgr.read().await;
-            let position_mgr = snapshot_position_mgr.read().await;
+    // Daily statements of record (independent of operational snapshots)
+    // Generated on the event loop, like snapshots, so each record reads the
+    // state at exactly its as_of_sequence; logged from here
+    let mut statement_generator = if config.statements.enabled {
+        Some(DailyStatementGenerator::new(config.statements.clone(), market_id)?)
+    } else {
+        None
+    };
+    let mut statement_interval = interval(Duration::from_secs(30));
+    let (statement_tx, mut statement_rx) = mpsc::channel::<Vec<BaseEvent>>(4);
+    let statement_producer = event_producer.clone();
+    task_supervisor.spawn("daily_statements", async move {
+        while let Some(records) = statement_rx.recv().await {
+            for record in records {
+                if let Err(e) = statement_producer.produce(record).await {
+                    error!("Failed to produce daily record: {:?}", e);
+                }
+            }
+        }
+    });
 
-            // Get current price
-            match snapshot_price_rx.try_recv() {
-                Ok(price_snapshot) => {
-                    let positions_vec: Vec<_> = position_mgr.positions.values().cloned().collect();
+    // Liquidity provider program: sample maker quotes, close daily scorecards
+    // and log the qualified makers for the processor to put on the LP fee rate
+    if config.lp_program.enabled {
+        let mut lp_program = LpProgram::new(config.lp_program.clone(), market_id);
+        let lp_matching_core = matching_core.clone();
+        let lp_producer = event_producer.clone();
+        let lp_sample_interval = Duration::from_secs(config.lp_program.sample_interval_secs);
+
+        task_supervisor.spawn("lp_program", async move {
+            let mut interval = interval(lp_sample_interval);
+            loop {
+                interval.tick().await;
+
+                match lp_matching_core.copy_book().await {
+                    Ok(order_book) => lp_program.sample(&order_book),
+                    Err(e) => warn!("LP quote sample skipped: {:?}", e),
+                }
 
-                    // Get last sequence from channel (sent by main loop)
-                    let last_sequence = snapshot_seq_rx.try_recv().unwrap_or(0);
+                // Close the period at each UTC day boundary
+                let now = Timestamp::now();
+                if now.physical / 86_400_000 > lp_program.period_start().physical / 86_400_000 {
+                    let scorecards = lp_program.close_period(now);
+                    if let Err(e) = lp_program.write_scorecards(&scorecards) {
+                        error!("Failed to write LP scorecards: {:?}", e);
+                    }
 
-                    match snapshot_mgr.create_snapshot(
-                        last_sequence,
-                        snapshot_market_id,
-                        &*balance_mgr,
-                        &positions_vec,
-                        price_snapshot.mark_price,
-                        price_snapshot.index_price,
-                    ) {
-                        Ok(snapshot) => {
-                            match snapshot_mgr.save_snapshot(&snapshot).await {
-                                Ok(_) => {
-                                    info!("Snapshot saved at sequence {}", snapshot.sequence);
-                                }
-                                Err(e) => {
-                                    error!("Failed to save snapshot: {:?}", e);
-                                }
-                            }
-                        }
-                        Err(e) => {
-                            error!("Failed to create snapshot: {:?}", e);
+                    if let Some(designation) = lp_program.designation(&scorecards) {
+                        let base = designation.base.clone();
+                        if let Err(e) = lp_producer.produce(BaseEvent {
+                            payload: EventPayload::LpMakersDesignated(Box::new(designation)),
+                            ..base
+                        }).await {
+                            error!("Failed to log LP maker designation: {:?}", e);
                         }
                     }
                 }
-                Err(_) => {
-                    warn!("No price data available for snapshot");
-                }
             }
-        }
-    });
+        });
+    }
 
     // ============================================================================
     // PHASE 11: MAIN EVENT LOOP
@@ -461,7 +1273,8 @@ async fn main() -> Result<()> {
 
     info!("System ready - starting event processing loop");
 
-    let mut shutdown_signal = signal::ctrl_c();
+    let shutdown_signal = signal::ctrl_c();
+    tokio::pin!(shutdown_signal);
 
     loop {
         tokio::select! {
@@ -486,12 +1299,88 @@ async fn main() -> Result<()> {
                 }
             }
             
+            // Periodic snapshot, consistent with the sequence it is taken at
+            _ = snapshot_interval.tick() => {
+                match event_processor.snapshot().await {
+                    Ok(snapshot) => match snapshot_manager.save_snapshot(&snapshot).await {
+                        Ok(_) => {
+                            info!("Snapshot saved at sequence {}", snapshot.sequence);
+
+                            let mut retention = retention_manager.write().await;
+                            retention.record_snapshot(snapshot.sequence, snapshot.timestamp);
+                            if let Some(floor) = retention.snapshot_floor() {
+                                snapshot_manager.set_retention_floor(floor);
+                            }
+                            if let Err(e) = retention.apply_topic_retention(
+                                &retention_brokers,
+                                &retention_topic,
+                                &retention_security,
+                            ).await {
+                                warn!("Failed to update topic retention: {:?}", e);
+                            }
+                        }
+                        Err(e) => error!("Failed to save snapshot: {:?}", e),
+                    },
+                    Err(e) => error!("Failed to create snapshot: {:?}", e),
+                }
+            }
+
+            // Daily statements, once a cutoff has passed
+            _ = statement_interval.tick(), if statement_generator.is_some() => {
+                if let Some(generator) = statement_generator.as_mut()
+                    && let Some(cutoff) = generator.due_cutoff(Timestamp::now())
+                {
+                    // Retried on the next tick until there is a mark price
+                    match event_processor.daily_statement(generator, cutoff).await {
+                        Ok(records) => {
+                            if statement_tx.send(records).await.is_err() {
+                                error!("Daily statement publisher has stopped");
+                            }
+                        }
+                        Err(e) => error!("Daily statement generation failed: {:?}", e),
+                    }
+                }
+            }
+
+            // Operator-requested recovery; the consumer is not polled meanwhile
+            Some(command) = recovery_rx.recv() => {
+                let result = recovery.run(
+                    &event_consumer,
+                    &mut event_processor,
+                    command.operator_id,
+                    command.note,
+                ).await;
+                let _ = command.reply.send(result);
+            }
+
+            // Operator halt, acknowledge and resume
+            Some(command) = processor_rx.recv() => {
+                let _ = command.reply.send(event_processor.control(command.action).await);
+            }
+
+            // Account export (between two events) and genesis import
+            Some(command) = migration_rx.recv() => match command {
+                MigrationCommand::Export { reply } => {
+                    let _ = reply.send(event_processor.export_accounts().await);
+                }
+                MigrationCommand::Import { export, reply } => {
+                    let last_sequence = event_processor.last_sequence();
+                    let _ = reply.send(publish_genesis(&export, market_id, last_sequence, event_producer.as_ref()).await);
+                }
+            },
+
             // Process events
-            event_result = event_consumer.fetch_next_event() => {
+            // Paused while halted so no events are consumed (and lost) until resume
+            event_result = event_consumer.fetch_next_event(), if !event_processor.is_halted() => {
                 match event_result {
                     Ok(event) => {
+                        // Buffered (on disk) for archival before the broker can expire it
+                        if let Err(e) = retention_manager.write().await.buffer_event(&event) {
+                            error!("Event archival failed: {:?}", e);
+                        }
+
                         // Process event
-                        if let Err(e) = event_processor.process_event(event) {
+                        if let Err(e) = event_processor.process_event(event).await {
                             error!("Event processing failed: {:?}", e);
                             
                             // Check if error is fatal
@@ -500,9 +1389,6 @@ async fn main() -> Result<()> {
                                 kill_switch.activate(format!("Fatal error: {:?}", e));
                                 break;
                             }
-                        } else {
-                            // Send sequence update to snapshot task
-                            let _ = snapshot_seq_tx.try_send(event_processor.last_sequence);
                         }
                     }
                     Err(e) => {
@@ -527,25 +1413,12 @@ async fn main() -> Result<()> {
 
     // Create final snapshot
     info!("Creating final snapshot");
-    let balance_mgr = balance_manager.read().await;
-    let position_mgr = position_manager.read().await;
-
-    // Subscribe to get latest price
-    let mut final_price_rx = price_tx.subscribe();
-    if let Ok(price_snapshot) = final_price_rx.try_recv() {
-        let positions_vec: Vec<_> = position_mgr.positions.values().cloned().collect();
-
-        if let Ok(snapshot) = snapshot_manager.create_snapshot(
-            event_processor.last_sequence,
-            market_id,
-            &*balance_mgr,
-            &positions_vec,
-            price_snapshot.mark_price,
-            price_snapshot.index_price,
-        ) {
-            let _ = snapshot_manager.save_snapshot(&snapshot).await;
-            info!("Final snapshot saved");
-        }
+    match event_processor.snapshot().await {
+        Ok(snapshot) => match snapshot_manager.save_snapshot(&snapshot).await {
+            Ok(_) => info!("Final snapshot saved"),
+            Err(e) => error!("Failed to save final snapshot: {:?}", e),
+        },
+        Err(e) => error!("Failed to create final snapshot: {:?}", e),
     }
 
     info!("Shutdown complete");
@@ -566,6 +1439,9 @@ fn validate_config(config: &AppConfig) -> Result<()> {
         return Err(Error::ConfigError("Invalid lot_size".to_string()));
     }
 
+    config.market.contract.validate()?;
+    config.market.validate_market_type()?;
+
     // Validate risk config
     if config.risk.max_leverage <= 0.0 || config.risk.max_leverage > 125.0 {
         return Err(Error::ConfigError("Invalid max_leverage".to_string()));
@@ -575,6 +1451,9 @@ fn validate_config(config: &AppConfig) -> Result<()> {
         return Err(Error::ConfigError("Invalid maintenance_margin_rate".to_string()));
     }
 
+    config.risk.validate_tiers()?;
+    config.risk.validate_concentration()?;
+
     // Validate Kafka config
     if config.kafka.brokers.is_empty() {
         return Err(Error::ConfigError("Kafka brokers not configured".to_string()));
@@ -584,6 +1463,8 @@ fn validate_config(config: &AppConfig) -> Result<()> {
         return Err(Error::ConfigError("Kafka topic not configured".to_string()));
     }
 
+    config.kafka.security.validate()?;
+
     info!("Configuration validation passed");
     Ok(())
 }
@@ -597,10 +1478,35 @@ fn is_fatal_error(error: &Error) -> bool {
     )
 }
 
-async fn metrics_handler() -> String {
-    let encoder = TextEncoder::new();
+async fn metrics_handler(headers: axum::http::HeaderMap) -> axum::response::Response {
+    use axum::response::IntoResponse;
+
     let metric_families = prometheus::gather();
+
+    // Exemplars (trace ids on latency buckets) exist only in OpenMetrics
+    let wants_openmetrics = headers.get(axum::http::header::ACCEPT)
+        .and_then(|h| h.to_str().ok())
+        .is_some_and(|accept| accept.contains("application/openmetrics-text"));
+    if wants_openmetrics {
+        let body = exemplars::encode_openmetrics(&metric_families);
+        return ([(axum::http::header::CONTENT_TYPE, exemplars::OPENMETRICS_CONTENT_TYPE)], body).into_response();
+    }
+
+    let encoder = TextEncoder::new();
     let mut buffer = vec![];
     encoder.encode(&metric_families, &mut buffer).unwrap();
-    String::from_utf8(buffer).unwrap()
-}
\ No newline at end of file
+    String::from_utf8(buffer).unwrap().into_response()
+}
+
+/// Perp price the premium is measured against: the book's mid, or the
+/// sources' average while the book is one-sided (no premium)
+fn perp_reference_price(book: &BookView, raw_prices: &[RawPriceUpdate]) -> Option<Price> {
+    if let (Some(bid), Some(ask)) = (book.best_bid(), book.best_ask()) {
+        return Some(Price::from_i64((bid.to_i64() + ask.to_i64()) / 2));
+    }
+    if raw_prices.is_empty() {
+        return None;
+    }
+    let average = raw_prices.iter().map(|p| p.price).sum::<f64>() / raw_prices.len() as f64;
+    Some(Price::from_f64(average))
+}
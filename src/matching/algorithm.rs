@@ -0,0 +1,107 @@
+use crate::config::market::{MarketConfig, MatchingAlgorithmKind, ProRataConfig};
+use crate::types::quantity::Quantity;
+
+/// Splits a taker's quantity across the resting orders of one price level
+///
+/// `resting` is the unfilled quantity of each order in queue (time) order;
+/// the result has one allocation per order, each at most its resting
+/// quantity, summing to `min(quantity, level total)`. The matcher then fills
+/// the level in queue order, each maker up to its allocation.
+pub trait MatchingAlgorithm: Send + Sync {
+    fn allocate(&self, quantity: Quantity, resting: &[Quantity]) -> Vec<Quantity>;
+
+    fn name(&self) -> &'static str;
+}
+
+/// Algorithm configured for the market
+pub fn for_market(config: &MarketConfig) -> Box<dyn MatchingAlgorithm> {
+    match config.matching_algorithm {
+        MatchingAlgorithmKind::Fifo => Box::new(Fifo),
+        MatchingAlgorithmKind::ProRata => Box::new(ProRata::new(config.pro_rata.clone())),
+    }
+}
+
+/// Price-time priority: each order fills in full before the next one
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Fifo;
+
+impl MatchingAlgorithm for Fifo {
+    fn allocate(&self, quantity: Quantity, resting: &[Quantity]) -> Vec<Quantity> {
+        let mut left = quantity;
+        resting.iter()
+            .map(|resting| {
+                let allocation = left.min(*resting);
+                left = left - allocation;
+                allocation
+            })
+            .collect()
+    }
+
+    fn name(&self) -> &'static str {
+        "fifo"
+    }
+}
+
+/// Proportional allocation by resting size
+///
+/// ## Allocation
+/// 1. Top order (optional): the front of the queue is filled first, up to its size
+/// 2. Pro-rata: each order gets floor(quantity * resting / level total);
+///    shares below `min_allocation` are dropped
+/// 3. Leftover from rounding and dropped shares is allocated FIFO
+///
+/// Integer arithmetic throughout, so replay allocates identically.
+#[derive(Clone, Debug)]
+pub struct ProRata {
+    config: ProRataConfig,
+}
+
+impl ProRata {
+    pub fn new(config: ProRataConfig) -> Self {
+        ProRata { config }
+    }
+}
+
+impl MatchingAlgorithm for ProRata {
+    fn allocate(&self, quantity: Quantity, resting: &[Quantity]) -> Vec<Quantity> {
+        let mut allocations = vec![Quantity::zero(); resting.len()];
+        let level_total: Quantity = resting.iter().copied().sum();
+        let mut left = quantity.min(level_total);
+
+        if self.config.top_order_priority && let Some(front) = resting.first() {
+            allocations[0] = left.min(*front);
+            left = left - allocations[0];
+        }
+
+        let pool_total = level_total - allocations.first().copied().unwrap_or_else(Quantity::zero);
+        if left > Quantity::zero() && pool_total > Quantity::zero() {
+            let pool = left.to_i64() as i128;
+            for (allocation, resting) in allocations.iter_mut().zip(resting) {
+                let unallocated = *resting - *allocation;
+                let share = Quantity::from_i64(
+                    (pool * unallocated.to_i64() as i128 / pool_total.to_i64() as i128) as i64
+                );
+                if share >= self.config.min_allocation && share > Quantity::zero() {
+                    *allocation = *allocation + share;
+                    left = left - share;
+                }
+            }
+        }
+
+        // Rounding and dropped shares: front of the queue first
+        for (allocation, resting) in allocations.iter_mut().zip(resting) {
+            if left == Quantity::zero() {
+                break;
+            }
+            let extra = left.min(*resting - *allocation);
+            *allocation = *allocation + extra;
+            left = left - extra;
+        }
+
+        allocations
+    }
+
+    fn name(&self) -> &'static str {
+        "pro_rata"
+    }
+}
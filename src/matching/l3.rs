@@ -0,0 +1,151 @@
+use rkyv::{AlignedVec, Deserialize as RkyvDeserialize, Infallible};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use crate::error::{Error, Result};
+use crate::events::order::Side;
+use crate::matching::order_book::Order;
+use crate::types::ids::{OrderId, UserId};
+use crate::types::price::Price;
+use crate::types::quantity::Quantity;
+use crate::types::timestamp::Timestamp;
+
+/// Current L3 export format version
+/// Bump when the layout of L3Snapshot changes; readers reject newer versions
+pub const L3_FORMAT_VERSION: u32 = 1;
+
+/// Binary frame magic bytes ("PIL3")
+const L3_MAGIC: [u8; 4] = *b"PIL3";
+
+/// Header is padded to 16 bytes so the archived body stays aligned (see EventCodec)
+const HEADER_LEN: usize = 16;
+
+/// Hex characters of the salted user hash kept per order
+const USER_HASH_LEN: usize = 16;
+
+/// One resting order as seen by an L3 consumer
+/// The user is replaced by a salted hash so dumps can leave the engine
+/// without exposing account ids, while orders from the same user still group
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
+pub struct L3Order {
+    pub order_id: OrderId,
+    pub side: Side,
+    pub price: Price,
+    pub quantity: Quantity,     // Remaining (unfilled) size
+    pub timestamp: Timestamp,
+    pub user_hash: String,
+}
+
+impl L3Order {
+    pub fn from_order(order: &Order, salt: &[u8]) -> Self {
+        Self {
+            order_id: order.order_id,
+            side: order.side,
+            price: order.price,
+            quantity: order.quantity - order.filled,
+            timestamp: order.timestamp,
+            user_hash: user_hash(&order.user_id, salt),
+        }
+    }
+}
+
+/// Per-order dump of the book
+///
+/// ## Layout
+/// - **bids**: best price first, FIFO within a level
+/// - **asks**: best price first, FIFO within a level
+///
+/// ## Encodings
+/// - **JSON**: `to_json()` for debugging and drop-copy consumers
+/// - **Binary**: `to_binary()` frames an rkyv archive behind a 16-byte header
+///   (magic `PIL3`, then the format version as little-endian u32)
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
+pub struct L3Snapshot {
+    pub format_version: u32,
+    pub exported_at: Timestamp,
+    pub bids: Vec<L3Order>,
+    pub asks: Vec<L3Order>,
+}
+
+impl L3Snapshot {
+    /// Build from orders in book priority order (`OrderBook::orders_in_priority()`)
+    pub fn from_orders(orders: &[Order], salt: &[u8], exported_at: Timestamp) -> Self {
+        let (bids, asks): (Vec<&Order>, Vec<&Order>) = orders.iter()
+            .partition(|o| o.side == Side::Buy);
+
+        Self {
+            format_version: L3_FORMAT_VERSION,
+            exported_at,
+            bids: bids.into_iter().map(|o| L3Order::from_order(o, salt)).collect(),
+            asks: asks.into_iter().map(|o| L3Order::from_order(o, salt)).collect(),
+        }
+    }
+
+    pub fn order_count(&self) -> usize {
+        self.bids.len() + self.asks.len()
+    }
+
+    pub fn to_json(&self) -> Result<Vec<u8>> {
+        serde_json::to_vec(self)
+            .map_err(|e| Error::SerializationError(e.to_string()))
+    }
+
+    pub fn from_json(bytes: &[u8]) -> Result<Self> {
+        let snapshot: Self = serde_json::from_slice(bytes)
+            .map_err(|e| Error::DeserializationError(e.to_string()))?;
+        Self::check_version(snapshot.format_version)?;
+        Ok(snapshot)
+    }
+
+    pub fn to_binary(&self) -> Result<Vec<u8>> {
+        let body = rkyv::to_bytes::<_, 4096>(self)
+            .map_err(|e| Error::SerializationError(e.to_string()))?;
+
+        let mut frame = Vec::with_capacity(HEADER_LEN + body.len());
+        frame.extend_from_slice(&L3_MAGIC);
+        frame.extend_from_slice(&self.format_version.to_le_bytes());
+        frame.resize(HEADER_LEN, 0);
+        frame.extend_from_slice(&body);
+
+        Ok(frame)
+    }
+
+    pub fn from_binary(frame: &[u8]) -> Result<Self> {
+        if frame.len() < HEADER_LEN || frame[0..4] != L3_MAGIC {
+            return Err(Error::DeserializationError("invalid L3 snapshot header".to_string()));
+        }
+
+        let version = u32::from_le_bytes([frame[4], frame[5], frame[6], frame[7]]);
+        Self::check_version(version)?;
+
+        // Copy into an aligned buffer; exports are off the hot path
+        let mut aligned = AlignedVec::with_capacity(frame.len() - HEADER_LEN);
+        aligned.extend_from_slice(&frame[HEADER_LEN..]);
+        let archived = rkyv::check_archived_root::<L3Snapshot>(&aligned)
+            .map_err(|e| Error::DeserializationError(e.to_string()))?;
+
+        archived.deserialize(&mut Infallible)
+            .map_err(|_| Error::DeserializationError("rkyv deserialize failed".to_string()))
+    }
+
+    fn check_version(version: u32) -> Result<()> {
+        if version > L3_FORMAT_VERSION {
+            return Err(Error::UnsupportedBookExportVersion {
+                version,
+                max_supported: L3_FORMAT_VERSION,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Truncated hex SHA-256 of `salt || user_id`
+pub fn user_hash(user_id: &UserId, salt: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(salt);
+    hasher.update(user_id.0.as_bytes());
+    let mut hash = hex::encode(hasher.finalize());
+    hash.truncate(USER_HASH_LEN);
+    hash
+}
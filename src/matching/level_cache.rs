@@ -0,0 +1,174 @@
+use crate::events::order::Side;
+use crate::types::balance::Balance;
+use crate::types::price::Price;
+use crate::types::quantity::Quantity;
+use std::cmp::Ordering;
+
+/// Levels per side kept in `OrderBook`'s aggregate cache
+pub const CACHED_LEVELS: usize = 32;
+
+/// One of the best levels of a side, with running totals from the top
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CachedLevel {
+    pub price: Price,
+    pub quantity: Quantity,
+    pub orders: usize,
+    pub notional: Balance,              // quantity * price
+    pub cumulative_quantity: Quantity,  // This level and every better one
+    pub cumulative_notional: Balance,
+}
+
+/// Incrementally maintained aggregates of one side's best levels, best first
+///
+/// The book reports every level change; updates cost O(capacity) and only
+/// touch the cache when the level is (or becomes) one of the best
+/// `capacity`. Queries answer from the cache alone whenever it covers them
+/// and return None when the caller has to walk the book instead.
+#[derive(Clone, Debug)]
+pub struct LevelCache {
+    side: Side,  // Resting side: bids best-first descending, asks ascending
+    capacity: usize,
+    levels: Vec<CachedLevel>,
+}
+
+impl LevelCache {
+    pub fn new(side: Side, capacity: usize) -> Self {
+        LevelCache {
+            side,
+            capacity,
+            levels: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Cached levels, best first
+    pub fn levels(&self) -> &[CachedLevel] {
+        &self.levels
+    }
+
+    /// True when the cache holds every level on its side
+    pub fn is_complete(&self) -> bool {
+        self.levels.len() < self.capacity
+    }
+
+    /// Worst cached price (the level a refill continues after)
+    pub fn last_price(&self) -> Option<Price> {
+        self.levels.last().map(|level| level.price)
+    }
+
+    /// Aggregate of the best `n` levels (fewer if the side is thinner)
+    pub fn top(&self, n: usize) -> Option<&CachedLevel> {
+        if n == 0 {
+            return None;
+        }
+        match self.levels.get(n - 1) {
+            Some(level) => Some(level),
+            None if self.is_complete() => self.levels.last(),
+            None => None,
+        }
+    }
+
+    /// Worst price reached filling `quantity` from the top
+    /// Some(None) when the side is empty; None when the cache can't tell
+    pub fn sweep_price(&self, quantity: Quantity) -> Option<Option<Price>> {
+        let reached = self.levels.partition_point(|level| level.cumulative_quantity < quantity);
+        match self.levels.get(reached) {
+            Some(level) => Some(Some(level.price)),
+            None if self.is_complete() => Some(self.last_price()),
+            None => None,
+        }
+    }
+
+    /// Resting quantity at prices a taker with `limit` would trade through
+    /// None when every cached level crosses and more may lie beyond the cache
+    pub fn quantity_within(&self, limit: Price) -> Option<Quantity> {
+        let crossing = self.levels.partition_point(|level| self.crosses(limit, level.price));
+        if crossing == self.levels.len() && !self.is_complete() {
+            return None;
+        }
+        Some(match crossing {
+            0 => Quantity::zero(),
+            n => self.levels[n - 1].cumulative_quantity,
+        })
+    }
+
+    /// Apply a change to the level at `price`: `level` is its new
+    /// (quantity, orders), or None once the level is gone
+    /// Returns true when a level left a full cache and the next level from
+    /// the book should be appended with `refill`
+    pub fn update(&mut self, price: Price, level: Option<(Quantity, usize)>) -> bool {
+        let was_full = !self.is_complete();
+        match (self.position(price), level) {
+            (Ok(i), Some((quantity, orders))) => {
+                self.levels[i].quantity = quantity;
+                self.levels[i].orders = orders;
+                self.levels[i].notional = quantity * price;
+                self.accumulate_from(i);
+                false
+            }
+            (Ok(i), None) => {
+                self.levels.remove(i);
+                self.accumulate_from(i);
+                was_full
+            }
+            (Err(i), Some((quantity, orders))) if i < self.capacity => {
+                self.levels.truncate(self.capacity - 1);
+                self.levels.insert(i, Self::level(price, quantity, orders));
+                self.accumulate_from(i);
+                false
+            }
+            (Err(_), _) => false,
+        }
+    }
+
+    /// Append the level just past the cached ones after a removal
+    pub fn refill(&mut self, price: Price, quantity: Quantity, orders: usize) {
+        if self.is_complete() {
+            self.levels.push(Self::level(price, quantity, orders));
+            self.accumulate_from(self.levels.len() - 1);
+        }
+    }
+
+    fn level(price: Price, quantity: Quantity, orders: usize) -> CachedLevel {
+        CachedLevel {
+            price,
+            quantity,
+            orders,
+            notional: quantity * price,
+            cumulative_quantity: Quantity::zero(),
+            cumulative_notional: Balance::zero(),
+        }
+    }
+
+    fn accumulate_from(&mut self, start: usize) {
+        let (mut quantity, mut notional) = match start.checked_sub(1).map(|i| &self.levels[i]) {
+            Some(prev) => (prev.cumulative_quantity, prev.cumulative_notional),
+            None => (Quantity::zero(), Balance::zero()),
+        };
+        for level in &mut self.levels[start..] {
+            quantity = quantity + level.quantity;
+            notional = notional + level.notional;
+            level.cumulative_quantity = quantity;
+            level.cumulative_notional = notional;
+        }
+    }
+
+    /// Index of `price` in best-first order (Err: where it would go)
+    fn position(&self, price: Price) -> Result<usize, usize> {
+        self.levels.binary_search_by(|level| self.rank(level.price, price))
+    }
+
+    fn rank(&self, level_price: Price, price: Price) -> Ordering {
+        match self.side {
+            Side::Buy => price.cmp(&level_price),
+            Side::Sell => level_price.cmp(&price),
+        }
+    }
+
+    /// Whether a taker on the other side with `limit` trades at `level_price`
+    fn crosses(&self, limit: Price, level_price: Price) -> bool {
+        match self.side {
+            Side::Sell => limit >= level_price,  // Buy taker lifting asks
+            Side::Buy => limit <= level_price,   // Sell taker hitting bids
+        }
+    }
+}
@@ -0,0 +1,240 @@
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+use crate::config::LpProgramConfig;
+use crate::error::{Error, Result};
+use crate::events::base::{BaseEvent, EventType};
+use crate::events::control::LpMakersDesignated;
+use crate::events::order::Side;
+use crate::matching::order_book::OrderBook;
+use crate::observability::metrics::{LP_QUOTE_UPTIME, LP_SPREAD_BPS};
+use crate::types::ids::{MarketId, UserId};
+use crate::types::price::Price;
+use crate::types::quantity::Quantity;
+use crate::types::timestamp::Timestamp;
+
+#[derive(Default)]
+struct MakerStats {
+    samples: u64,
+    present_samples: u64,     // Quoting both sides
+    compliant_samples: u64,   // Both sides, spread and depth within obligations
+    spread_bps_sum: f64,      // Over present samples
+    bid_depth_sum: i128,
+    ask_depth_sum: i128,
+}
+
+/// Daily result for one registered market maker
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LpScorecard {
+    pub user_id: UserId,
+    pub market_id: MarketId,
+    pub period_start: Timestamp,
+    pub period_end: Timestamp,
+    pub samples: u64,
+    pub quote_uptime: f64,        // Fraction of samples with a two-sided quote
+    pub compliant_uptime: f64,    // Fraction of samples meeting spread and depth obligations
+    pub avg_spread_bps: f64,
+    pub avg_bid_depth: Quantity,
+    pub avg_ask_depth: Quantity,
+    pub qualified: bool,          // Earns the LP maker rate next period
+}
+
+/// Liquidity provider program - measures registered makers against their obligations
+///
+/// ## Sampling
+/// - `sample()` inspects the book every `sample_interval_secs`
+/// - **Present**: maker has resting bid and ask
+/// - **Compliant**: present, maker spread <= `max_spread_bps` of mid, and at least
+///   `min_depth` resting per side within `max_spread_bps` of mid
+///
+/// ## Scorecards
+/// - `close_period()` produces one scorecard per maker and resets the counters
+/// - Makers with `compliant_uptime >= min_uptime` qualify for the LP maker fee rate
+///   for the next period; the qualified set is logged as `LpMakersDesignated`
+///   and the processor hands it to the matcher, so replay charges the same fees
+pub struct LpProgram {
+    config: LpProgramConfig,
+    market_id: MarketId,
+    makers: HashSet<UserId>,
+    stats: HashMap<UserId, MakerStats>,
+    period_start: Timestamp,
+}
+
+impl LpProgram {
+    pub fn new(config: LpProgramConfig, market_id: MarketId) -> Self {
+        LpProgram {
+            makers: config.makers.iter().copied().collect(),
+            config,
+            market_id,
+            stats: HashMap::new(),
+            period_start: Timestamp::now(),
+        }
+    }
+
+    pub fn register_maker(&mut self, user_id: UserId) {
+        self.makers.insert(user_id);
+    }
+
+    pub fn unregister_maker(&mut self, user_id: &UserId) {
+        self.makers.remove(user_id);
+        self.stats.remove(user_id);
+    }
+
+    pub fn period_start(&self) -> Timestamp {
+        self.period_start
+    }
+
+    /// Record one observation of the book for every registered maker
+    pub fn sample(&mut self, order_book: &OrderBook) {
+        // No two-sided market, nothing to measure against
+        let mid = match (order_book.best_bid(), order_book.best_ask()) {
+            (Some(bid), Some(ask)) => (bid.to_i64() as i128 + ask.to_i64() as i128) / 2,
+            _ => return,
+        };
+        if mid <= 0 {
+            return;
+        }
+
+        let band = mid * self.config.max_spread_bps as i128 / 10_000;
+        let min_depth = self.config.min_depth.to_i64() as i128;
+
+        // (best bid, best ask, bid depth in band, ask depth in band) per maker
+        let mut quotes: HashMap<UserId, (Option<Price>, Option<Price>, i128, i128)> = HashMap::new();
+        for order in order_book.orders() {
+            if !self.makers.contains(&order.user_id) {
+                continue;
+            }
+
+            let quote = quotes.entry(order.user_id).or_insert((None, None, 0, 0));
+            let unfilled = (order.quantity - order.filled).to_i64() as i128;
+            let distance = (order.price.to_i64() as i128 - mid).abs();
+
+            match order.side {
+                Side::Buy => {
+                    quote.0 = Some(quote.0.map_or(order.price, |p| p.max(order.price)));
+                    if distance <= band {
+                        quote.2 += unfilled;
+                    }
+                }
+                Side::Sell => {
+                    quote.1 = Some(quote.1.map_or(order.price, |p| p.min(order.price)));
+                    if distance <= band {
+                        quote.3 += unfilled;
+                    }
+                }
+            }
+        }
+
+        for maker in &self.makers {
+            let stats = self.stats.entry(*maker).or_default();
+            stats.samples += 1;
+
+            let (bid, ask, bid_depth, ask_depth) = match quotes.get(maker) {
+                Some(quote) => *quote,
+                None => continue,
+            };
+
+            stats.bid_depth_sum += bid_depth;
+            stats.ask_depth_sum += ask_depth;
+
+            if let (Some(bid), Some(ask)) = (bid, ask) {
+                let spread_bps = (ask.to_i64() - bid.to_i64()) as f64 * 10_000.0 / mid as f64;
+                stats.present_samples += 1;
+                stats.spread_bps_sum += spread_bps;
+
+                if spread_bps <= self.config.max_spread_bps as f64
+                    && bid_depth >= min_depth
+                    && ask_depth >= min_depth
+                {
+                    stats.compliant_samples += 1;
+                }
+            }
+        }
+    }
+
+    /// Scorecards for the current period; counters reset for the next one
+    pub fn close_period(&mut self, now: Timestamp) -> Vec<LpScorecard> {
+        let mut makers: Vec<UserId> = self.makers.iter().copied().collect();
+        makers.sort_by_key(|m| m.0);
+
+        let scorecards: Vec<LpScorecard> = makers.into_iter()
+            .map(|user_id| {
+                let stats = self.stats.remove(&user_id).unwrap_or_default();
+                let samples = stats.samples.max(1);
+
+                let scorecard = LpScorecard {
+                    user_id,
+                    market_id: self.market_id,
+                    period_start: self.period_start,
+                    period_end: now,
+                    samples: stats.samples,
+                    quote_uptime: stats.present_samples as f64 / samples as f64,
+                    compliant_uptime: stats.compliant_samples as f64 / samples as f64,
+                    avg_spread_bps: if stats.present_samples > 0 {
+                        stats.spread_bps_sum / stats.present_samples as f64
+                    } else {
+                        0.0
+                    },
+                    avg_bid_depth: Quantity::from_i64((stats.bid_depth_sum / samples as i128) as i64),
+                    avg_ask_depth: Quantity::from_i64((stats.ask_depth_sum / samples as i128) as i64),
+                    qualified: stats.samples > 0
+                        && stats.compliant_samples as f64 / samples as f64 >= self.config.min_uptime,
+                };
+
+                let maker_label = user_id.to_string();
+                LP_QUOTE_UPTIME.with_label_values(&[&maker_label]).set(scorecard.compliant_uptime);
+                LP_SPREAD_BPS.with_label_values(&[&maker_label]).set(scorecard.avg_spread_bps);
+
+                scorecard
+            })
+            .collect();
+
+        self.stats.clear();
+        self.period_start = now;
+
+        tracing::info!(
+            "LP period closed: {} makers, {} qualified",
+            scorecards.len(),
+            scorecards.iter().filter(|s| s.qualified).count()
+        );
+
+        scorecards
+    }
+
+    /// Designation of the qualified makers in `scorecards`, for the event log
+    pub fn designation(&self, scorecards: &[LpScorecard]) -> Option<LpMakersDesignated> {
+        let first = scorecards.first()?;
+        let mut makers: Vec<UserId> = scorecards.iter()
+            .filter(|scorecard| scorecard.qualified)
+            .map(|scorecard| scorecard.user_id)
+            .collect();
+        makers.sort_by_key(|maker| maker.0);
+
+        Some(LpMakersDesignated {
+            base: BaseEvent::new(EventType::LpMakersDesignated, self.market_id),
+            makers,
+            period_start: first.period_start,
+            period_end: first.period_end,
+        })
+    }
+
+    /// Persist scorecards as `scorecard_dir/{market_id}/{period_end_ms}.json`
+    pub fn write_scorecards(&self, scorecards: &[LpScorecard]) -> Result<()> {
+        let period_end = match scorecards.first() {
+            Some(scorecard) => scorecard.period_end,
+            None => return Ok(()),
+        };
+
+        let dir = PathBuf::from(&self.config.scorecard_dir).join(self.market_id.to_string());
+        std::fs::create_dir_all(&dir).map_err(Error::IoError)?;
+
+        let data = serde_json::to_vec_pretty(scorecards)
+            .map_err(|e| Error::SerializationError(e.to_string()))?;
+
+        let path = dir.join(format!("{}.json", period_end.physical));
+        std::fs::write(&path, data).map_err(Error::IoError)?;
+
+        tracing::info!("LP scorecards written to {:?}", path);
+        Ok(())
+    }
+}
@@ -1,44 +1,200 @@
 use crate::config::fees::FeeConfig;
-use crate::error::Result;
-use crate::events::base::BaseEvent;
-use crate::events::order::{OrderType, Side};
-use crate::events::trade::{Fee, TradeEvent};
-use crate::interfaces::balance_provider::BalanceProvider;
-use crate::matching::order_book::{Order, OrderBook};
-use crate::matching::self_trade::{check_self_trade, SelfTradeAction};
+use crate::config::market::ContractSpec;
+use crate::config::market::PostOnlyMode;
+use crate::error::{Error, Result};
+use crate::events::base::{BaseEvent, EventType};
+use crate::events::order::{OrderType, SelfTradePrevented, Side, TimeInForce};
+use crate::events::trade::{ExecutionReport, ExecutionStatus, Fee, Liquidity, TradeEvent};
+use crate::interfaces::order_matcher::OrderMatcher;
+use crate::interfaces::position_provider::PositionProvider;
+use crate::matching::algorithm::{Fifo, MatchingAlgorithm};
+use crate::matching::order_archive::TerminalStatus;
+use crate::matching::order_book::{DepthSnapshot, Order, OrderBook, PriceLevel};
+use crate::matching::self_trade::{SelfTradeAction, SelfTradePolicy};
 use crate::types::balance::Balance;
-use crate::types::ids::MarketId;
+use crate::types::ids::{MarketId, OrderId, UserId};
+use crate::types::position::PositionSide;
 use crate::types::price::Price;
 use crate::types::quantity::Quantity;
+use crate::types::timestamp::Timestamp;
 use crate::types::ratio::Ratio;
-use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+use crate::observability::exemplars;
 use crate::observability::metrics::{MATCHING_LATENCY, ORDERS_REJECTED, TRADES_EXECUTED, TRADE_VOLUME};
 
 pub struct Matcher {
     order_book: OrderBook,
     fee_config: FeeConfig,
+    contract: ContractSpec,  // Values fills for fees
     market_id: MarketId,
+    completed_orders: Vec<(Order, TerminalStatus)>,
+    lp_makers: HashSet<UserId>,  // Qualified LP program makers (charged lp_maker_fee_rate)
+    self_trade_policy: SelfTradePolicy,
+    self_trades: Vec<SelfTradePrevented>,
+    execution_reports: Vec<ExecutionReport>,
+    algorithm: Box<dyn MatchingAlgorithm>,  // How a level's fills are split (FIFO by default)
+    event_time: Option<Timestamp>,  // Time of the event being matched, for GTD expiry
+}
+
+/// Where an order stands once its fills are applied
+struct OrderProgress {
+    filled: Quantity,
+    open: Quantity,
+    status: ExecutionStatus,
 }
 
 impl Matcher {
     pub fn new(order_book: OrderBook, fee_config: FeeConfig, market_id: MarketId) -> Self {
-        Matcher { order_book, fee_config, market_id }
+        Matcher {
+            order_book,
+            fee_config,
+            contract: ContractSpec::default(),
+            market_id,
+            completed_orders: Vec::new(),
+            lp_makers: HashSet::new(),
+            self_trade_policy: SelfTradePolicy::default(),
+            self_trades: Vec::new(),
+            execution_reports: Vec::new(),
+            algorithm: Box::new(Fifo),
+            event_time: None,
+        }
+    }
+
+    pub fn with_contract(mut self, contract: ContractSpec) -> Self {
+        self.contract = contract;
+        self
+    }
+
+    pub fn with_self_trade_policy(mut self, policy: SelfTradePolicy) -> Self {
+        self.self_trade_policy = policy;
+        self
+    }
+
+    pub fn with_matching_algorithm(mut self, algorithm: Box<dyn MatchingAlgorithm>) -> Self {
+        tracing::info!("Matching algorithm: {}", algorithm.name());
+        self.algorithm = algorithm;
+        self
+    }
+
+    pub fn self_trade_policy_mut(&mut self) -> &mut SelfTradePolicy {
+        &mut self.self_trade_policy
+    }
+
+    /// Replace the set of makers qualified for the LP fee rate (applied by the
+    /// processor from logged `LpMakersDesignated` events)
+    pub fn set_lp_makers(&mut self, lp_makers: HashSet<UserId>) {
+        self.lp_makers = lp_makers;
+    }
+
+    /// Set the time of the event being processed (the processor's, never the
+    /// wall clock, so replay expires the same orders)
+    pub fn set_event_time(&mut self, now: Timestamp) {
+        self.event_time = Some(now);
+    }
+
+    /// GTD order whose expiry has passed as of the event being matched
+    /// (it may still be on the book until the next `ExpireOrders` sweep)
+    fn is_expired(&self, order: &Order) -> bool {
+        match (order.time_in_force.expires_at(), self.event_time) {
+            (Some(expires_at), Some(now)) => expires_at <= now,
+            _ => false,
+        }
+    }
+
+    pub fn market_id(&self) -> MarketId {
+        self.market_id
+    }
+
+    /// The matcher's own book (read-only)
+    pub fn order_book(&self) -> &OrderBook {
+        &self.order_book
+    }
+
+    /// The matcher's own book, for cancels, amends and expiry sweeps
+    pub fn order_book_mut(&mut self) -> &mut OrderBook {
+        &mut self.order_book
+    }
+
+    /// Take a resting order off the matcher's book
+    pub fn cancel_order(&mut self, order_id: &OrderId) -> Result<Order> {
+        self.order_book.remove_order(order_id)
+    }
+
+    /// Rebuild the internal book from a snapshot's resting orders
+    pub fn restore_orders(&mut self, orders: Vec<Order>) -> Result<()> {
+        self.order_book.restore_orders(orders)
+    }
+
+    /// Take resting orders that left the book (filled or self-trade cancelled) during matching
+    pub fn drain_completed_orders(&mut self) -> Vec<(Order, TerminalStatus)> {
+        std::mem::take(&mut self.completed_orders)
     }
 
-    pub fn match_order(&mut self, order: &Order, balance_provider: &mut dyn BalanceProvider, mark_price: Price) -> Result<Vec<TradeEvent>> {
+    /// Take the self-trade preventions applied during matching
+    pub fn drain_self_trades(&mut self) -> Vec<SelfTradePrevented> {
+        std::mem::take(&mut self.self_trades)
+    }
+
+    /// Execution reports (taker and makers) from matches since the last drain
+    pub fn drain_execution_reports(&mut self) -> Vec<ExecutionReport> {
+        std::mem::take(&mut self.execution_reports)
+    }
+
+    pub fn match_order(
+        &mut self,
+        order: &Order,
+        position_provider: &dyn PositionProvider,
+        mark_price: Price,
+    ) -> Result<Vec<TradeEvent>> {
         // Observability: Start timing
         let order_type_label = match order.order_type {
             OrderType::Market => "market",
             OrderType::Limit => "limit",
+            OrderType::StopMarket => "stop_market",
+            OrderType::StopLimit => "stop_limit",
+            OrderType::TrailingStop => "trailing_stop",
         };
-        let _timer = MATCHING_LATENCY.with_label_values(&[order_type_label]).start_timer();
+        let _timer = exemplars::start_timer(&MATCHING_LATENCY, &[order_type_label]);
+
+        // A GTD taker past its expiry (a stop triggered late) never trades or rests
+        if self.is_expired(order) {
+            tracing::info!("Order {} expired before it could match", order.order_id);
+            self.completed_orders.push((order.clone(), TerminalStatus::Expired));
+            return Ok(Vec::new());
+        }
+
+        // FOK is all-or-nothing and IOC minimums are pre-scanned: refuse before touching the book
+        self.check_fill_or_kill(order, mark_price)?;
+        self.check_min_fill(order, mark_price)?;
+
+        // Post-only orders must have been repriced already if they crossed
+        if order.post_only && self.would_take(order.side, order.price) {
+            return Err(Error::PostOnlyWouldCross);
+        }
+
+        // Reduce-only takers are shrunk to what closes the position
+        let target_quantity = self.reduce_only_quantity(order, position_provider)?;
+        if target_quantity < order.quantity {
+            tracing::info!(
+                "Reduce-only order {} shrunk from {} to {}",
+                order.order_id, order.quantity.to_i64(), target_quantity.to_i64()
+            );
+        }
 
         let mut trades = Vec::new();
-        let mut remaining = order.quantity;
-        let initial_best_price = match order.side {
-            Side::Buy => self.order_book.best_ask(),
-            Side::Sell => self.order_book.best_bid(),
+        let mut remaining = target_quantity - order.filled;
+        let mut taker_decremented = Quantity::zero();  // Removed by DecrementAndCancel, never traded
+        let mut taker_cancelled = false;
+        // Reduce-only makers: what their position (leg) still allows within this match
+        let mut maker_reducible: HashMap<(UserId, PositionSide), Quantity> = HashMap::new();
+        // Makers filled in this match: (filled, quantity, still open) afterwards
+        let mut maker_states: HashMap<OrderId, (Quantity, Quantity, Quantity)> = HashMap::new();
+        // Market orders stop filling past mark * (1 ± slippage_limit)
+        let match_price = match order.order_type {
+            OrderType::Market => self.slippage_bound(order, mark_price),
+            _ => Some(order.price),
         };
+        let mut slippage_breached = false;
 
         while remaining > Quantity::zero() {
             // Get best opposite price
@@ -52,112 +208,152 @@ impl Matcher {
                 None => break,  // No more liquidity
             };
 
-            // ADDED: Slippage protection for market orders
-            // Per docs/architecture/matching-execution.md Section 6.2
-            if order.order_type == crate::events::order::OrderType::Market {
-                if let Some(slippage_limit) = order.slippage_limit {
-                    if let Some(initial_price) = initial_best_price {
-                        let slippage = match order.side {
-                            Side::Buy => {
-                                // For buy orders, slippage is (current_price - initial_price) / initial_price
-                                if best_price > initial_price {
-                                    let diff = best_price.to_i64() - initial_price.to_i64();
-                                    (diff as f64) / (initial_price.to_i64() as f64)
-                                } else {
-                                    0.0
-                                }
-                            }
-                            Side::Sell => {
-                                // For sell orders, slippage is (initial_price - current_price) / initial_price
-                                if initial_price > best_price {
-                                    let diff = initial_price.to_i64() - best_price.to_i64();
-                                    (diff as f64) / (initial_price.to_i64() as f64)
-                                } else {
-                                    0.0
-                                }
-                            }
-                        };
-
-                        if slippage > slippage_limit.to_f64() {
-                            // Slippage exceeded, reject remaining quantity
-                            tracing::warn!(
-                                "Market order {} slippage exceeded: {:.4}% > {:.4}%",
-                                order.order_id,
-                                slippage * 100.0,
-                                slippage_limit.to_f64() * 100.0
-                            );
-                            break;
-                        }
-                    }
-                }
-            }
-
             // Check if price crosses
-            if !self.price_crosses(order.side, order.price, best_price) {
+            let match_price = match match_price {
+                Some(price) => price,
+                None => break,  // Market order without a usable bound
+            };
+            if !self.price_crosses(order.side, match_price, best_price) {
+                if order.order_type == OrderType::Market {
+                    tracing::warn!(
+                        "Market order {} slippage limit reached: best {} beyond bound {}",
+                        order.order_id, best_price.to_f64(), match_price.to_f64()
+                    );
+                    slippage_breached = true;
+                }
                 break;  // No match
             }
 
-            // Get price level (CORRECTED: handle Reverse wrapper)
-            let level_orders = if order.side == Side::Buy {
-                self.order_book.asks.get_mut(&best_price)
-            } else {
-                self.order_book.bids.get_mut(&Reverse(best_price))
-            };
-
-            let level = match level_orders {
-                Some(l) => l,
-                None => break,
-            };
+            // Split the taker across this level per the matching algorithm and
+            // fill in queue order; the level is dropped from the book once its
+            // last order is removed. Quantity freed by self-trade or reduce-only
+            // removals is allocated again on the next pass over the level
+            let resting_side = order.side.opposite();
+            let queue = self.order_book.level_queue(resting_side, best_price);
+            let resting: Vec<Quantity> = queue.iter().map(|(_, unfilled)| *unfilled).collect();
+            let allocations = self.algorithm.allocate(remaining, &resting);
+            for (&(handle, _), allocation) in queue.iter().zip(allocations) {
+                if remaining == Quantity::zero() {
+                    break;
+                }
+                if allocation == Quantity::zero() {
+                    continue;
+                }
+                let maker_order = self.order_book.order_at(handle);
 
-            // Match against orders at this level (FIFO)
-            while remaining > Quantity::zero() && !level.orders.is_empty() {
-                let maker_order = level.orders.front_mut().unwrap();
+                // Expired but not yet swept: leaves the book instead of trading
+                if self.is_expired(maker_order) {
+                    let expired = self.order_book.remove_at(handle);
+                    tracing::info!("GTD maker {} expired, removed during matching", expired.order_id);
+                    self.completed_orders.push((expired, TerminalStatus::Expired));
+                    continue;
+                }
 
                 // Check self-trade
-                let self_trade_action = check_self_trade(maker_order, &order);
-                match self_trade_action {
-                    SelfTradeAction::CancelMaker => {
-                        let cancelled = level.orders.pop_front().unwrap();
-                        self.order_book.orders.remove(&cancelled.order_id);
-                        level.total_quantity = level.total_quantity - (cancelled.quantity - cancelled.filled);
-                        continue;
-                    }
-                    SelfTradeAction::CancelTaker => {
-                        return Ok(trades);  // Stop matching
+                let self_trade_action = self.self_trade_policy.check(maker_order, order);
+                if !matches!(self_trade_action, SelfTradeAction::Allow) {
+                    let mode = self.self_trade_policy.mode_for(order);
+                    let maker_remaining = maker_order.quantity - maker_order.filled;
+                    let (maker_removed, taker_removed) = match self_trade_action {
+                        SelfTradeAction::CancelMaker => (maker_remaining, Quantity::zero()),
+                        SelfTradeAction::CancelTaker => (Quantity::zero(), remaining),
+                        SelfTradeAction::CancelBoth => (maker_remaining, remaining),
+                        SelfTradeAction::DecrementAndCancel => {
+                            let decrement = remaining.min(maker_remaining);
+                            (decrement, decrement)
+                        }
+                        SelfTradeAction::Allow => unreachable!(),
+                    };
+
+                    tracing::info!(
+                        "Self-trade prevented ({:?}): maker {} -{}, taker {} -{}",
+                        mode, maker_order.order_id, maker_removed.to_i64(),
+                        order.order_id, taker_removed.to_i64()
+                    );
+                    self.self_trades.push(SelfTradePrevented {
+                        base: BaseEvent::new(EventType::SelfTradePrevented, self.market_id),
+                        user_id: order.user_id,
+                        maker_order_id: maker_order.order_id,
+                        taker_order_id: order.order_id,
+                        mode,
+                        maker_cancelled: maker_removed,
+                        taker_cancelled: taker_removed,
+                    });
+
+                    // Maker: cancelled outright, or shrunk and cancelled once nothing is left
+                    let maker_order = self.order_book.shrink_at(handle, maker_removed);
+                    if maker_order.filled == maker_order.quantity {
+                        let mut cancelled = self.order_book.remove_at(handle);
+                        cancelled.quantity = cancelled.quantity + maker_removed;  // Archived as submitted
+                        self.completed_orders.push((cancelled, TerminalStatus::Cancelled));
                     }
-                    SelfTradeAction::CancelBoth => {
-                        let cancelled = level.orders.pop_front().unwrap();
-                        self.order_book.orders.remove(&cancelled.order_id);
-                        level.total_quantity = level.total_quantity - (cancelled.quantity - cancelled.filled);
-                        return Ok(trades);
+
+                    // Taker: the rest is cancelled, or shrunk by the decrement
+                    match self_trade_action {
+                        SelfTradeAction::CancelTaker | SelfTradeAction::CancelBoth => taker_cancelled = true,
+                        _ => {
+                            remaining = remaining - taker_removed;
+                            taker_decremented = taker_decremented + taker_removed;
+                        }
                     }
-                    SelfTradeAction::Allow => {
-                        // Continue with matching
+                    if taker_cancelled {
+                        break;
                     }
+                    continue;
                 }
 
                 // Calculate fill quantity
                 let maker_remaining = maker_order.quantity - maker_order.filled;
-                let fill_qty = remaining.min(maker_remaining);
+                let mut fill_qty = remaining.min(maker_remaining).min(allocation);
+
+                // A resting reduce-only order may no longer have a position to reduce
+                let maker_leg = (maker_order.user_id, maker_order.position_side);
+                let maker_reducing = maker_order.is_reducing();
+                if maker_reducing {
+                    let reducible = *maker_reducible.entry(maker_leg).or_insert_with(|| {
+                        position_provider.reducible_quantity(maker_order.user_id, maker_order.side, maker_order.position_side)
+                    });
+
+                    if reducible == Quantity::zero() {
+                        let cancelled = self.order_book.remove_at(handle);
+                        tracing::info!("Reduce-only maker {} cancelled: no position to reduce", cancelled.order_id);
+                        self.completed_orders.push((cancelled, TerminalStatus::Cancelled));
+                        continue;
+                    }
+
+                    fill_qty = fill_qty.min(reducible);
+                    maker_reducible.insert(maker_leg, reducible - fill_qty);
+                }
+
+                let (maker_order_id, maker_user_id, maker_side, maker_price, maker_position_side) = (
+                    maker_order.order_id,
+                    maker_order.user_id,
+                    maker_order.side,
+                    maker_order.price,
+                    maker_order.position_side,
+                );
 
                 // Calculate fees
-                let maker_fee = self.calculate_maker_fee(fill_qty, maker_order.price);
-                let taker_fee = self.calculate_taker_fee(fill_qty, maker_order.price);
+                let maker_fee = self.calculate_maker_fee(fill_qty, maker_price, maker_user_id)?;
+                let taker_fee = self.calculate_taker_fee(fill_qty, maker_price)?;
 
                 // Create trade
                 let trade = TradeEvent {
                     base: BaseEvent::new(crate::events::base::EventType::Trade, self.market_id),
-                    trade_id: crate::utils::helper::generate_trade_id(),
-                    maker_order_id: maker_order.order_id,
+                    // What the taker had traded before this fill: unique per fill of the order
+                    trade_id: crate::utils::helper::trade_id_for(order.order_id, target_quantity - remaining - taker_decremented),
+                    maker_order_id,
                     taker_order_id: order.order_id,
-                    maker_user_id: maker_order.user_id,
+                    maker_user_id,
                     taker_user_id: order.user_id,
-                    price: maker_order.price,  // Maker price (price-time priority)
+                    price: maker_price,  // Maker price (price-time priority)
                     quantity: fill_qty,
-                    maker_side: maker_order.side,
+                    maker_side,
                     maker_fee,
                     taker_fee,
                     liquidation: false,
+                    maker_position_side,
+                    taker_position_side: order.position_side,
                 };
 
                 trades.push(trade);
@@ -167,46 +363,371 @@ impl Matcher {
                 TRADE_VOLUME.with_label_values(&["default"]).inc_by(fill_qty.to_i64() as f64);
 
                 // Update orders
-                maker_order.filled = maker_order.filled + fill_qty;
+                let maker_order = self.order_book.fill_at(handle, fill_qty);
                 remaining = remaining - fill_qty;
+                maker_states.insert(
+                    maker_order_id,
+                    (maker_order.filled, maker_order.quantity, maker_order.quantity - maker_order.filled),
+                );
 
                 // Remove maker if fully filled
                 if maker_order.filled == maker_order.quantity {
-                    let filled_order = level.orders.pop_front().unwrap();
-                    self.order_book.orders.remove(&filled_order.order_id);
+                    let filled_order = self.order_book.remove_at(handle);
+                    self.completed_orders.push((filled_order, TerminalStatus::Filled));
+                } else if maker_reducing
+                    && maker_reducible.get(&maker_leg) == Some(&Quantity::zero())
+                {
+                    // Position closed: the rest of the reduce-only maker is cancelled
+                    if let Some(state) = maker_states.get_mut(&maker_order_id) {
+                        state.2 = Quantity::zero();
+                    }
+                    let cancelled = self.order_book.remove_at(handle);
+                    self.completed_orders.push((cancelled, TerminalStatus::Cancelled));
                 }
-
-                level.total_quantity = level.total_quantity - fill_qty;
             }
 
-            // Remove price level if empty
-            if level.orders.is_empty() {
-                if order.side == Side::Buy {
-                    self.order_book.asks.remove(&best_price);
-                } else {
-                    self.order_book.bids.remove(&Reverse(best_price));
-                }
+            if taker_cancelled {
+                break;
             }
         }
 
-        // CORRECTED: Add remaining quantity to book with margin reservation
-        if remaining > Quantity::zero() && order.time_in_force == crate::events::order::TimeInForce::GTC {
+        // The taker left the book through self-trade prevention, not by filling
+        let taker_filled = target_quantity - taker_decremented - remaining - order.filled;
+        // A market order that hit its slippage bound never rests: the remainder is cancelled
+        taker_cancelled |= slippage_breached;
+        if taker_cancelled || (taker_decremented > Quantity::zero() && remaining == Quantity::zero()) {
+            let mut cancelled = order.clone();
+            cancelled.filled = order.filled + taker_filled;
+            self.completed_orders.push((cancelled, TerminalStatus::Cancelled));
+        }
+
+        // Add remaining quantity to book; its margin is held by the
+        // processor's netted order margin, not per order here
+        let taker_rests = !taker_cancelled && remaining > Quantity::zero() && order.time_in_force.rests();
+        if taker_rests {
             let mut book_order = order.clone();
-            book_order.filled = order.quantity - remaining;
+            book_order.quantity = target_quantity - taker_decremented;
+            book_order.filled = book_order.quantity - remaining;
+
+            self.order_book.add_order(book_order)?;
+        }
+
+        let taker_status = if taker_rests {
+            if order.filled + taker_filled > Quantity::zero() {
+                ExecutionStatus::PartiallyFilled
+            } else {
+                ExecutionStatus::New
+            }
+        } else if !taker_cancelled && remaining == Quantity::zero() && taker_decremented == Quantity::zero() {
+            ExecutionStatus::Filled
+        } else {
+            ExecutionStatus::Cancelled
+        };
+        let taker_open = if taker_rests { remaining } else { Quantity::zero() };
+        self.record_execution_reports(order, &trades, &maker_states, taker_status, taker_open);
 
-            // Calculate required margin for resting order
-            let required_margin = self.calculate_order_margin(&book_order, mark_price);
+        Ok(trades)
+    }
 
-            // Reserve margin
-            balance_provider.reserve_margin(order.user_id, required_margin)?;
+    fn record_execution_reports(
+        &mut self,
+        order: &Order,
+        trades: &[TradeEvent],
+        maker_states: &HashMap<OrderId, (Quantity, Quantity, Quantity)>,
+        taker_status: ExecutionStatus,
+        taker_open: Quantity,
+    ) {
+        let taker_fills: Vec<&TradeEvent> = trades.iter().collect();
+        let taker_filled = order.filled + trades.iter().map(|t| t.quantity).sum::<Quantity>();
+        self.execution_reports.push(self.execution_report(
+            order.order_id,
+            order.user_id,
+            order.side,
+            Liquidity::Taker,
+            &taker_fills,
+            OrderProgress { filled: taker_filled, open: taker_open, status: taker_status },
+        ));
 
-            // Add to book
-            self.order_book.add_order(book_order)?;
+        // Makers in the order they were hit
+        let mut maker_ids: Vec<OrderId> = Vec::new();
+        for trade in trades {
+            if !maker_ids.contains(&trade.maker_order_id) {
+                maker_ids.push(trade.maker_order_id);
+            }
+        }
+
+        for maker_id in maker_ids {
+            let fills: Vec<&TradeEvent> = trades.iter().filter(|t| t.maker_order_id == maker_id).collect();
+            let (filled, quantity, open) = match maker_states.get(&maker_id) {
+                Some(state) => *state,
+                None => continue,
+            };
+            let status = if open > Quantity::zero() {
+                ExecutionStatus::PartiallyFilled
+            } else if filled == quantity {
+                ExecutionStatus::Filled
+            } else {
+                ExecutionStatus::Cancelled
+            };
+
+            self.execution_reports.push(self.execution_report(
+                maker_id,
+                fills[0].maker_user_id,
+                fills[0].maker_side,
+                Liquidity::Maker,
+                &fills,
+                OrderProgress { filled, open, status },
+            ));
+        }
+    }
+
+    fn execution_report(
+        &self,
+        order_id: OrderId,
+        user_id: UserId,
+        side: Side,
+        liquidity: Liquidity,
+        fills: &[&TradeEvent],
+        progress: OrderProgress,
+    ) -> ExecutionReport {
+        let fill_quantity: Quantity = fills.iter().map(|t| t.quantity).sum();
+        let notional: i128 = fills.iter()
+            .map(|t| t.quantity.to_i64() as i128 * t.price.to_i64() as i128)
+            .sum();
+        let average_price = if fill_quantity > Quantity::zero() {
+            Price::from_i64((notional / fill_quantity.to_i64() as i128) as i64)
+        } else {
+            Price::zero()
+        };
+        let fees = fills.iter()
+            .map(|t| match liquidity {
+                Liquidity::Maker => t.maker_fee.amount,
+                Liquidity::Taker => t.taker_fee.amount,
+            })
+            .fold(Balance::zero(), |total, fee| total + fee);
+
+        ExecutionReport {
+            base: BaseEvent::new(EventType::ExecutionReport, self.market_id),
+            order_id,
+            user_id,
+            side,
+            liquidity,
+            fill_quantity,
+            average_price,
+            fees,
+            filled_quantity: progress.filled,
+            remaining_quantity: progress.open,
+            status: progress.status,
+            trade_ids: fills.iter().map(|t| t.trade_id).collect(),
+        }
+    }
+
+    /// Unfilled quantity of `order` the opposite side could fill right now
+    /// Stops at the limit price (market orders: the slippage bound matching
+    /// uses, see `slippage_bound`) and ignores the taker's own orders, which self-trade
+    /// prevention would cancel rather than fill
+    pub fn fillable_quantity(&self, order: &Order, mark_price: Price) -> Quantity {
+        let limit = match self.fill_limit(order, mark_price) {
+            Some(limit) => limit,
+            None => return Quantity::zero(),
+        };
+
+        let levels: Box<dyn Iterator<Item = &PriceLevel>> = match order.side {
+            Side::Buy => Box::new(self.order_book.asks.values()),
+            Side::Sell => Box::new(self.order_book.bids.values()),
+        };
+
+        let needed = order.quantity - order.filled;
+        let mut fillable = Quantity::zero();
+        for level in levels {
+            if !self.price_crosses(order.side, limit, level.price) || fillable >= needed {
+                break;
+            }
+            for maker in self.order_book.level_orders(level).filter(|maker| maker.user_id != order.user_id) {
+                fillable = fillable + (maker.quantity - maker.filled);
+            }
+        }
+
+        fillable.min(needed)
+    }
+
+    /// Whether `order` could fill at least `required` right now
+    /// The cached level totals rule most failures out without walking the
+    /// queues; only a book deep enough overall is walked to exclude the
+    /// taker's own orders
+    fn can_fill(&self, order: &Order, required: Quantity, mark_price: Price) -> bool {
+        let reachable = match self.fill_limit(order, mark_price) {
+            Some(limit) => self.order_book.depth_within(order.side, limit),
+            None => Quantity::zero(),
+        };
+        reachable >= required && self.fillable_quantity(order, mark_price) >= required
+    }
+
+    /// Worst price `order` may fill at: its limit, or for market orders the
+    /// same bound matching stops at; None if there is nothing to bound against
+    fn fill_limit(&self, order: &Order, mark_price: Price) -> Option<Price> {
+        match order.order_type {
+            OrderType::Market => self.slippage_bound(order, mark_price),
+            _ => Some(order.price),
+        }
+    }
+
+    /// Worst price a market order may fill at: mark * (1 ± slippage_limit)
+    /// Falls back to the best opposite price while no mark is known; None
+    /// without a slippage limit or any reference price
+    fn slippage_bound(&self, order: &Order, mark_price: Price) -> Option<Price> {
+        let slippage = order.slippage_limit?.to_f64();
+        let reference = if mark_price > Price::zero() {
+            mark_price
+        } else {
+            match order.side {
+                Side::Buy => self.order_book.best_ask()?,
+                Side::Sell => self.order_book.best_bid()?,
+            }
+        };
+
+        let band = (reference.to_i64() as f64 * slippage) as i64;
+        Some(match order.side {
+            Side::Buy => Price::from_i64(reference.to_i64() + band),
+            Side::Sell => Price::from_i64((reference.to_i64() - band).max(0)),
+        })
+    }
+
+    /// Resting quantity a taker on `side` could reach without going past `limit`
+    pub fn depth_within(&self, side: Side, limit: Price) -> Quantity {
+        self.order_book.depth_within(side, limit)
+    }
+
+    /// (best_bid, best_ask) if the book is crossed or locked
+    pub fn crossed(&self) -> Option<(Price, Price)> {
+        self.order_book.crossed()
+    }
+
+    /// Match resting orders that cross each other until best_bid < best_ask
+    ///
+    /// Of the two orders at the top of the book, the later one is lifted and
+    /// re-matched as a taker (the earlier keeps maker priority). It keeps
+    /// its order id, so the margin it holds carries over to any remainder.
+    pub fn uncross(
+        &mut self,
+        position_provider: &dyn PositionProvider,
+        mark_price: Price,
+    ) -> Result<Vec<TradeEvent>> {
+        let mut trades = Vec::new();
+        // Each pass lifts an order off the book, so this bounds the loop
+        let mut passes = self.order_book.len();
+
+        while passes > 0 && self.order_book.crossed().is_some() {
+            passes -= 1;
+
+            let bid_time = self.order_book.best_front(Side::Buy).map(|o| o.timestamp);
+            let ask_time = self.order_book.best_front(Side::Sell).map(|o| o.timestamp);
+            let lifted_side = match (bid_time, ask_time) {
+                (Some(bid), Some(ask)) if bid > ask => Side::Buy,
+                (Some(_), Some(_)) => Side::Sell,
+                _ => break,
+            };
+
+            let mut taker = match self.order_book.pop_best(lifted_side) {
+                Some(order) => order,
+                None => break,
+            };
+
+            tracing::warn!(
+                "Uncrossing book: re-matching order {} ({:?} {} @ {})",
+                taker.order_id, taker.side, (taker.quantity - taker.filled).to_f64(), taker.price.to_f64()
+            );
+
+            // It already rested, so post-only must not turn the re-match into a reject
+            taker.post_only = false;
+            trades.extend(self.match_order(&taker, position_provider, mark_price)?);
         }
 
         Ok(trades)
     }
 
+    /// Quantity a reduce-only (or hedge-leg closing) order may trade (its full
+    /// quantity otherwise)
+    /// Capped at the position it closes; error if there is nothing to reduce
+    pub fn reduce_only_quantity(&self, order: &Order, position_provider: &dyn PositionProvider) -> Result<Quantity> {
+        if !order.is_reducing() {
+            return Ok(order.quantity);
+        }
+
+        let reducible = position_provider.reducible_quantity(order.user_id, order.side, order.position_side);
+        if reducible == Quantity::zero() {
+            return Err(Error::ReduceOnlyViolation);
+        }
+
+        Ok(order.quantity.min(order.filled + reducible))
+    }
+
+    /// Error unless a FOK order can be filled in full
+    pub fn check_fill_or_kill(&self, order: &Order, mark_price: Price) -> Result<()> {
+        if order.time_in_force != TimeInForce::FOK {
+            return Ok(());
+        }
+
+        if !self.can_fill(order, order.quantity - order.filled, mark_price) {
+            return Err(Error::FillOrKillNotFilled);
+        }
+
+        Ok(())
+    }
+
+    /// Error unless an IOC order with a minimum fill quantity can fill at least that much
+    pub fn check_min_fill(&self, order: &Order, mark_price: Price) -> Result<()> {
+        let min_fill = match order.min_fill_quantity {
+            Some(min_fill) => min_fill,
+            None => return Ok(()),
+        };
+
+        if !self.can_fill(order, min_fill.min(order.quantity) - order.filled, mark_price) {
+            return Err(Error::MinFillQuantityNotMet);
+        }
+
+        Ok(())
+    }
+
+    fn would_take(&self, side: Side, price: Price) -> bool {
+        let best_opposite = match side {
+            Side::Buy => self.order_book.best_ask(),
+            Side::Sell => self.order_book.best_bid(),
+        };
+        best_opposite.is_some_and(|best| self.price_crosses(side, price, best))
+    }
+
+    /// Apply post-only handling before an order reaches the book
+    /// A crossing post-only order is rejected, or with `PostOnlyMode::Reprice`
+    /// moved one tick behind the best opposite level so it rests as a maker
+    pub fn prepare_post_only(&self, order: &Order, mode: PostOnlyMode, tick_size: Price) -> Result<Order> {
+        if !order.post_only || !self.would_take(order.side, order.price) {
+            return Ok(order.clone());
+        }
+
+        if mode == PostOnlyMode::Reject {
+            return Err(Error::PostOnlyWouldCross);
+        }
+
+        let repriced = match order.side {
+            Side::Buy => self.order_book.best_ask().map(|ask| ask - tick_size),
+            Side::Sell => self.order_book.best_bid().map(|bid| bid + tick_size),
+        };
+
+        match repriced {
+            Some(price) if price > Price::zero() => {
+                tracing::info!(
+                    "Post-only order {} repriced {} -> {}",
+                    order.order_id, order.price.to_i64(), price.to_i64()
+                );
+                let mut repriced_order = order.clone();
+                repriced_order.price = price;
+                Ok(repriced_order)
+            }
+            _ => Err(Error::PostOnlyWouldCross),
+        }
+    }
+
     fn price_crosses(&self, side: Side, order_price: Price, level_price: Price) -> bool {
         match side {
             Side::Buy => order_price >= level_price,
@@ -214,28 +735,228 @@ impl Matcher {
         }
     }
 
-    fn calculate_maker_fee(&self, quantity: Quantity, price: Price) -> Fee {
-        let notional = quantity * price;
-        let amount = notional * Balance::from_f64(self.fee_config.maker_fee_rate);
-        Fee {
-            amount,
-            rate: Ratio::from(self.fee_config.maker_fee_rate),
-        }
+    fn calculate_maker_fee(&self, quantity: Quantity, price: Price, maker: UserId) -> Result<Fee> {
+        let rate = if self.lp_makers.contains(&maker) {
+            self.fee_config.lp_maker_fee_rate
+        } else {
+            self.fee_config.maker_fee_rate
+        };
+        self.calculate_fee(quantity, price, Ratio::from(rate))
+    }
+
+    fn calculate_taker_fee(&self, quantity: Quantity, price: Price) -> Result<Fee> {
+        self.calculate_fee(quantity, price, Ratio::from(self.fee_config.taker_fee_rate))
     }
 
-    fn calculate_taker_fee(&self, quantity: Quantity, price: Price) -> Fee {
-        let notional = quantity * price;
-        let mut amount = notional * Balance::from_f64(self.fee_config.taker_fee_rate);
-        // Round up taker fees
-        amount = Balance::from_i64((amount.to_f64().ceil()) as i64);
-        Fee {
-            amount,
-            rate: Ratio::from(self.fee_config.taker_fee_rate),
+    /// Fee = contract notional * rate, in fixed point, rounded per the configured policy
+    fn calculate_fee(&self, quantity: Quantity, price: Price, rate: Ratio) -> Result<Fee> {
+        let notional = self.contract.notional(quantity, price)?;
+        let amount = self.fee_config.rounding.div(
+            notional.to_i64() as i128 * rate.raw_value() as i128,
+            Ratio::one().raw_value() as i128,
+        )?;
+        Ok(Fee { amount, rate })
+    }
+}
+
+impl OrderMatcher for Matcher {
+    type Book = OrderBook;
+
+    fn order_book(&self) -> &OrderBook {
+        Matcher::order_book(self)
+    }
+
+    fn order_book_mut(&mut self) -> &mut OrderBook {
+        Matcher::order_book_mut(self)
+    }
+
+    fn match_order(
+        &mut self,
+        order: &Order,
+        position_provider: &dyn PositionProvider,
+        mark_price: Price,
+    ) -> Result<Vec<TradeEvent>> {
+        Matcher::match_order(self, order, position_provider, mark_price)
+    }
+
+    fn set_lp_makers(&mut self, lp_makers: HashSet<UserId>) {
+        Matcher::set_lp_makers(self, lp_makers)
+    }
+
+    fn set_event_time(&mut self, now: Timestamp) {
+        Matcher::set_event_time(self, now)
+    }
+
+    fn depth_within(&self, side: Side, limit: Price) -> Quantity {
+        Matcher::depth_within(self, side, limit)
+    }
+
+    fn depth(&self, levels: usize) -> DepthSnapshot {
+        self.order_book.depth(levels)
+    }
+
+    fn crossed(&self) -> Option<(Price, Price)> {
+        Matcher::crossed(self)
+    }
+
+    fn uncross(
+        &mut self,
+        position_provider: &dyn PositionProvider,
+        mark_price: Price,
+    ) -> Result<Vec<TradeEvent>> {
+        Matcher::uncross(self, position_provider, mark_price)
+    }
+
+    fn reduce_only_quantity(&self, order: &Order, position_provider: &dyn PositionProvider) -> Result<Quantity> {
+        Matcher::reduce_only_quantity(self, order, position_provider)
+    }
+
+    fn check_fill_or_kill(&self, order: &Order, mark_price: Price) -> Result<()> {
+        Matcher::check_fill_or_kill(self, order, mark_price)
+    }
+
+    fn check_min_fill(&self, order: &Order, mark_price: Price) -> Result<()> {
+        Matcher::check_min_fill(self, order, mark_price)
+    }
+
+    fn prepare_post_only(&self, order: &Order, mode: PostOnlyMode, tick_size: Price) -> Result<Order> {
+        Matcher::prepare_post_only(self, order, mode, tick_size)
+    }
+
+    fn drain_completed_orders(&mut self) -> Vec<(Order, TerminalStatus)> {
+        Matcher::drain_completed_orders(self)
+    }
+
+    fn drain_self_trades(&mut self) -> Vec<SelfTradePrevented> {
+        Matcher::drain_self_trades(self)
+    }
+
+    fn drain_execution_reports(&mut self) -> Vec<ExecutionReport> {
+        Matcher::drain_execution_reports(self)
+    }
+
+    fn reset(&mut self, orders: Vec<Order>) -> Result<()> {
+        self.order_book = OrderBook::new();
+        self.completed_orders.clear();
+        self.self_trades.clear();
+        self.execution_reports.clear();
+        Matcher::restore_orders(self, orders)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::market::ContractType;
+    use crate::interfaces::memory::StaticPositions;
+
+    fn limit(user_id: UserId, side: Side, price: f64, contracts: i64, reduce_only: bool) -> Order {
+        Order {
+            order_id: OrderId::new(),
+            user_id,
+            side,
+            order_type: OrderType::Limit,
+            price: Price::from_f64(price),
+            quantity: Quantity::from_i64(contracts),
+            filled: Quantity::zero(),
+            timestamp: Timestamp::now(),
+            time_in_force: TimeInForce::GTC,
+            reduce_only,
+            post_only: false,
+            slippage_limit: None,
+            self_trade_prevention: None,
+            position_side: PositionSide::Both,
+            min_fill_quantity: None,
         }
     }
 
-    fn calculate_order_margin(&self, order: &Order, mark_price: Price) -> Balance {
-        let notional = order.quantity * mark_price;
-        notional / Balance::from_i64(20)  // Assuming 20x max leverage
+    #[test]
+    fn fees_are_a_share_of_the_contract_notional() {
+        let matcher = Matcher::new(OrderBook::new(), FeeConfig::default(), MarketId::btc_perp());
+        let fee = matcher.calculate_taker_fee(Quantity::from_i64(2), Price::from_f64(50_000.0)).unwrap();
+        assert_eq!(fee.amount, Balance::from_f64(50.0));  // 100,000 notional at 0.05%
+
+        let inverse = ContractSpec { contract_type: ContractType::Inverse, multiplier: 100.0, ..ContractSpec::default() };
+        let matcher = matcher.with_contract(inverse);
+        let fee = matcher.calculate_taker_fee(Quantity::from_i64(500), Price::from_f64(50_000.0)).unwrap();
+        assert_eq!(fee.amount, Balance::from_f64(0.0005));  // 1 BTC notional at 0.05%
+    }
+
+    #[test]
+    fn rematching_the_same_orders_gives_the_same_trade_ids() {
+        let (maker, taker) = (UserId::new(), UserId::new());
+        let resting = [limit(maker, Side::Sell, 50_000.0, 3, false), limit(maker, Side::Sell, 50_000.0, 3, false)];
+        let incoming = limit(taker, Side::Buy, 50_000.0, 5, false);
+
+        let trade_ids = || {
+            let mut matcher = Matcher::new(OrderBook::new(), FeeConfig::default(), MarketId::btc_perp());
+            let (positions, mark) = (StaticPositions::new(), Price::from_f64(50_000.0));
+            for order in &resting {
+                matcher.match_order(order, &positions, mark).unwrap();
+            }
+            matcher.match_order(&incoming, &positions, mark).unwrap()
+                .into_iter().map(|trade| trade.trade_id).collect::<Vec<_>>()
+        };
+
+        let first = trade_ids();
+        assert_eq!(first.len(), 2);
+        assert_ne!(first[0], first[1]);
+        assert_eq!(trade_ids(), first);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn reduce_only_taker_fills_no_more_than_its_position() {
+        let mut matcher = Matcher::new(OrderBook::new(), FeeConfig::default(), MarketId::btc_perp());
+        let (maker, taker) = (UserId::new(), UserId::new());
+        let positions = StaticPositions::new().with_position(taker, -2);
+        let mark = Price::from_f64(50_000.0);
+
+        matcher.match_order(&limit(maker, Side::Sell, 50_000.0, 10, false), &positions, mark).unwrap();
+        let trades = matcher.match_order(&limit(taker, Side::Buy, 50_000.0, 10, true), &positions, mark).unwrap();
+
+        assert_eq!(trades.len(), 1);
+        assert_eq!(trades[0].quantity, Quantity::from_i64(2));
+    }
+
+    #[test]
+    fn an_expired_gtd_maker_leaves_the_book_instead_of_trading() {
+        let mut matcher = Matcher::new(OrderBook::new(), FeeConfig::default(), MarketId::btc_perp());
+        let (maker, taker) = (UserId::new(), UserId::new());
+        let (positions, mark) = (StaticPositions::new(), Price::from_f64(50_000.0));
+        let expires_at = Timestamp::from_millis(1_000);
+        let gtd = Order { time_in_force: TimeInForce::GTD { expires_at }, ..limit(maker, Side::Sell, 50_000.0, 2, false) };
+        let gtc = limit(maker, Side::Sell, 50_000.0, 2, false);
+        matcher.set_event_time(Timestamp::from_millis(500));
+        matcher.match_order(&gtd, &positions, mark).unwrap();
+        matcher.match_order(&gtc, &positions, mark).unwrap();
+
+        // Not swept yet, but past its expiry by the taker's event time
+        matcher.set_event_time(expires_at);
+        let trades = matcher.match_order(&limit(taker, Side::Buy, 50_000.0, 2, false), &positions, mark).unwrap();
+
+        assert_eq!(trades.len(), 1);
+        assert_eq!(trades[0].maker_order_id, gtc.order_id);
+        let completed = matcher.drain_completed_orders();
+        assert!(completed.iter().any(|(order, status)| order.order_id == gtd.order_id && *status == TerminalStatus::Expired));
+    }
+
+    #[test]
+    fn a_fok_market_order_is_prescanned_against_the_mark_bound() {
+        let mut matcher = Matcher::new(OrderBook::new(), FeeConfig::default(), MarketId::btc_perp());
+        let (maker, taker) = (UserId::new(), UserId::new());
+        let (positions, mark) = (StaticPositions::new(), Price::from_f64(49_800.0));
+        matcher.match_order(&limit(maker, Side::Sell, 50_000.0, 5, false), &positions, mark).unwrap();
+        matcher.match_order(&limit(maker, Side::Sell, 50_400.0, 5, false), &positions, mark).unwrap();
+
+        // Within 1% of the best ask, but past mark + 1% where matching stops
+        let fok = Order {
+            order_type: OrderType::Market,
+            time_in_force: TimeInForce::FOK,
+            slippage_limit: Some(Ratio::from_f64(0.01)),
+            ..limit(taker, Side::Buy, 0.0, 10, false)
+        };
+
+        assert!(matches!(matcher.match_order(&fok, &positions, mark), Err(Error::FillOrKillNotFilled)));
+        assert_eq!(matcher.depth_within(Side::Buy, Price::from_f64(50_400.0)), Quantity::from_i64(10));
+    }
+}
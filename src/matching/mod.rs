@@ -1,4 +1,13 @@
 pub mod order_book;
+pub mod algorithm;
+pub mod level_cache;
+pub mod l3;
 pub mod matcher;
 pub mod self_trade;
-pub mod validator;
\ No newline at end of file
+pub mod sim;
+pub mod validator;
+pub mod order_archive;
+pub mod lp_program;
+pub mod trigger_monitor;
+pub mod trigger_store;
+pub mod trigger_engine;
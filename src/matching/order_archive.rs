@@ -0,0 +1,237 @@
+use std::collections::{HashMap, VecDeque};
+use std::path::PathBuf;
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use crate::config::OrderArchiveConfig;
+use crate::error::{Error, Result};
+use crate::events::order::RejectReason;
+use crate::matching::order_book::Order;
+use crate::types::ids::OrderId;
+use crate::types::timestamp::Timestamp;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TerminalStatus {
+    Filled,
+    Cancelled,
+    Rejected,
+    Expired,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ArchivedOrder {
+    pub order: Order,
+    pub status: TerminalStatus,
+    pub reject_reason: Option<RejectReason>,
+    pub archived_at: Timestamp,
+}
+
+/// Terminal-order archive - keeps filled/cancelled orders queryable after
+/// they leave the live book
+///
+/// ## Storage
+/// - **Memory**: Most recent `max_in_memory` orders, FIFO eviction
+/// - **Disk**: Evicted orders spilled to `spill_dir` as `order_{order_id}.bin` (bincode)
+///
+/// ## Retention
+/// - Orders older than `retention_secs` are treated as absent on lookup
+/// - `purge_expired()` removes them from memory and disk; it runs every
+///   `purge_interval_secs`
+pub struct OrderArchive {
+    recent: HashMap<OrderId, ArchivedOrder>,
+    insertion_order: VecDeque<OrderId>,
+    config: OrderArchiveConfig,
+}
+
+impl OrderArchive {
+    pub fn new(config: OrderArchiveConfig) -> Self {
+        OrderArchive {
+            recent: HashMap::new(),
+            insertion_order: VecDeque::new(),
+            config,
+        }
+    }
+
+    /// Archive an order that reached a terminal state
+    pub fn archive(&mut self, order: Order, status: TerminalStatus) -> Result<()> {
+        self.insert(ArchivedOrder {
+            order,
+            status,
+            reject_reason: None,
+            archived_at: Timestamp::now(),
+        })
+    }
+
+    /// Archive an order the engine refused, keeping the reason for status queries
+    pub fn archive_rejected(&mut self, order: Order, reason: RejectReason) -> Result<()> {
+        self.insert(ArchivedOrder {
+            order,
+            status: TerminalStatus::Rejected,
+            reject_reason: Some(reason),
+            archived_at: Timestamp::now(),
+        })
+    }
+
+    fn insert(&mut self, entry: ArchivedOrder) -> Result<()> {
+        let order_id = entry.order.order_id;
+
+        if self.recent.insert(order_id, entry).is_none() {
+            self.insertion_order.push_back(order_id);
+        }
+
+        // Spill oldest orders to disk once over capacity
+        while self.recent.len() > self.config.max_in_memory {
+            let oldest = match self.insertion_order.pop_front() {
+                Some(id) => id,
+                None => break,
+            };
+            if let Some(evicted) = self.recent.remove(&oldest) {
+                self.spill_to_disk(&evicted)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Look up a terminal order (memory first, then disk), honouring retention
+    pub fn get(&self, order_id: &OrderId) -> Option<ArchivedOrder> {
+        let entry = match self.recent.get(order_id) {
+            Some(entry) => Some(entry.clone()),
+            None => self.load_from_disk(order_id),
+        }?;
+
+        if self.is_expired(&entry) {
+            return None;
+        }
+
+        Some(entry)
+    }
+
+    /// Remove expired orders from memory and disk
+    pub fn purge_expired(&mut self) -> Result<usize> {
+        let expired: Vec<OrderId> = self.recent.values()
+            .filter(|e| self.is_expired(e))
+            .map(|e| e.order.order_id)
+            .collect();
+
+        for order_id in &expired {
+            self.recent.remove(order_id);
+        }
+        self.insertion_order.retain(|id| self.recent.contains_key(id));
+
+        let mut purged = expired.len();
+
+        if let Ok(entries) = std::fs::read_dir(self.spill_dir()) {
+            for entry in entries {
+                let path = entry.map_err(Error::IoError)?.path();
+                let data = std::fs::read(&path).map_err(Error::IoError)?;
+
+                if let Ok(archived) = bincode::deserialize::<ArchivedOrder>(&data)
+                    && self.is_expired(&archived)
+                {
+                    std::fs::remove_file(&path).map_err(Error::IoError)?;
+                    purged += 1;
+                }
+            }
+        }
+
+        if purged > 0 {
+            tracing::info!("Purged {} expired orders from archive", purged);
+        }
+
+        Ok(purged)
+    }
+
+    pub fn len_in_memory(&self) -> usize {
+        self.recent.len()
+    }
+
+    fn is_expired(&self, entry: &ArchivedOrder) -> bool {
+        Timestamp::now() - entry.archived_at > Duration::from_secs(self.config.retention_secs)
+    }
+
+    fn spill_dir(&self) -> PathBuf {
+        PathBuf::from(&self.config.spill_dir)
+    }
+
+    fn spill_path(&self, order_id: &OrderId) -> PathBuf {
+        self.spill_dir().join(format!("order_{}.bin", order_id))
+    }
+
+    fn spill_to_disk(&self, entry: &ArchivedOrder) -> Result<()> {
+        std::fs::create_dir_all(self.spill_dir()).map_err(Error::IoError)?;
+
+        let data = bincode::serialize(entry)
+            .map_err(|e| Error::SerializationError(e.to_string()))?;
+
+        std::fs::write(self.spill_path(&entry.order.order_id), data)
+            .map_err(Error::IoError)?;
+
+        Ok(())
+    }
+
+    fn load_from_disk(&self, order_id: &OrderId) -> Option<ArchivedOrder> {
+        let data = std::fs::read(self.spill_path(order_id)).ok()?;
+        bincode::deserialize(&data).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::order::{OrderType, Side, TimeInForce};
+    use crate::types::ids::UserId;
+    use crate::types::position::PositionSide;
+    use crate::types::price::Price;
+    use crate::types::quantity::Quantity;
+
+    fn cancelled(archived_at: Timestamp) -> ArchivedOrder {
+        ArchivedOrder {
+            order: Order {
+                order_id: OrderId::new(),
+                user_id: UserId::new(),
+                side: Side::Buy,
+                order_type: OrderType::Limit,
+                price: Price::from_i64(50_000),
+                quantity: Quantity::from_i64(1),
+                filled: Quantity::zero(),
+                timestamp: archived_at,
+                time_in_force: TimeInForce::GTC,
+                reduce_only: false,
+                post_only: false,
+                slippage_limit: None,
+                self_trade_prevention: None,
+                position_side: PositionSide::Both,
+                min_fill_quantity: None,
+            },
+            status: TerminalStatus::Cancelled,
+            reject_reason: None,
+            archived_at,
+        }
+    }
+
+    #[test]
+    fn a_purge_drops_expired_orders_from_memory_and_disk() {
+        let dir = std::env::temp_dir().join(format!("order-archive-{}", uuid::Uuid::new_v4()));
+        let mut archive = OrderArchive::new(OrderArchiveConfig {
+            max_in_memory: 1,
+            retention_secs: 3600,
+            spill_dir: dir.to_string_lossy().into_owned(),
+            purge_interval_secs: 60,
+        });
+        let old = Timestamp::from_millis(Timestamp::now().physical - 2 * 3600 * 1000);
+        let (spilled, expired, kept) = (cancelled(old), cancelled(old), cancelled(Timestamp::now()));
+        let ids = [spilled.order.order_id, expired.order.order_id, kept.order.order_id];
+        archive.insert(spilled).unwrap();
+        archive.insert(expired).unwrap();
+        archive.insert(kept).unwrap();
+        assert_eq!(archive.len_in_memory(), 1);
+
+        // Both expired orders were spilled once the newer one came in
+        assert_eq!(archive.purge_expired().unwrap(), 2);
+        assert!(archive.load_from_disk(&ids[0]).is_none());
+        assert!(archive.load_from_disk(&ids[1]).is_none());
+        assert!(archive.get(&ids[2]).is_some());
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
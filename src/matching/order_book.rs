@@ -1,26 +1,150 @@
 use std::cmp::Reverse;
-use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::ops::Bound::{Excluded, Unbounded};
 use crate::error::{Error, Result};
-use crate::events::order::{OrderType, Side, TimeInForce};
+use crate::events::order::{OrderType, SelfTradePrevention, Side, TimeInForce};
+use crate::interfaces::order_book_store::OrderBookStore;
+use crate::matching::l3::L3Snapshot;
+use crate::matching::level_cache::{CachedLevel, LevelCache, CACHED_LEVELS};
 use crate::types::ids::{OrderId, UserId};
+use crate::types::balance::Balance;
+use crate::types::position::PositionSide;
 use crate::types::price::Price;
 use crate::types::quantity::Quantity;
 use crate::types::ratio::Ratio;
 use crate::types::timestamp::Timestamp;
+use serde::{Deserialize, Serialize};
 
+/// Price-time priority order book
+///
+/// ## Layout
+/// - **Pool**: every resting order lives in one slab of nodes; freed slots are
+///   reused, so adding and removing orders does not allocate once the pool
+///   has grown to the book's working size
+/// - **Levels**: each price level is an intrusive doubly linked list through
+///   the pool (head = front of the FIFO queue), so cancels unlink in O(1)
+///   instead of scanning the queue
+/// - **Index**: order id -> pool slot, plus user -> resting order ids so
+///   per-user queries (cancel-all, margin, `/orders`) skip the other users
+/// - **Level cache**: running quantity/notional totals of each side's best
+///   `CACHED_LEVELS` levels, updated with every level change, so depth,
+///   sweep and fill pre-checks near the top don't walk the BTreeMaps
+///
+/// Levels stay in BTreeMaps keyed by price; a level allocates only when a new
+/// price appears. The pool keeps the only copy of each order, so partial
+/// fills are visible through `get_order()` as well as the level queue.
 pub struct OrderBook {
     pub bids: BTreeMap<Reverse<Price>, PriceLevel>,     // Sorted descending
     pub asks: BTreeMap<Price, PriceLevel>,              // Sorted ascending
-    pub orders: HashMap<OrderId, Order>,
+    pool: OrderPool,
+    index: HashMap<OrderId, OrderHandle>,
+    by_user: HashMap<UserId, HashSet<OrderId>>,
+    expiries: BTreeMap<Timestamp, Vec<OrderId>>,  // GTD expiry index, cleaned lazily by sweep_expired
+    bid_cache: LevelCache,
+    ask_cache: LevelCache,
 }
 
+/// Slot of a resting order in the book's pool
+/// Valid until the order leaves the book; the slot is then reused
+pub type OrderHandle = u32;
+
+const NIL: OrderHandle = OrderHandle::MAX;
+
+/// Slots preallocated by `OrderBook::new()`
+pub const DEFAULT_ORDER_CAPACITY: usize = 4_096;
+
+/// FIFO queue of one price, linked through the pool
+#[derive(Clone, Copy, Debug)]
 pub struct PriceLevel {
     pub price: Price,
-    pub orders: VecDeque<Order>,
+    pub order_count: usize,
     pub total_quantity: Quantity,
+    head: OrderHandle,
+    tail: OrderHandle,
+}
+
+impl PriceLevel {
+    fn new(price: Price) -> Self {
+        PriceLevel {
+            price,
+            order_count: 0,
+            total_quantity: Quantity::zero(),
+            head: NIL,
+            tail: NIL,
+        }
+    }
+}
+
+struct OrderNode {
+    order: Order,
+    prev: OrderHandle,
+    next: OrderHandle,  // Next in the level queue, or next free slot
+}
+
+/// Slab of order nodes with an intrusive free list
+struct OrderPool {
+    nodes: Vec<OrderNode>,
+    free_head: OrderHandle,
+}
+
+impl OrderPool {
+    fn with_capacity(capacity: usize) -> Self {
+        OrderPool {
+            nodes: Vec::with_capacity(capacity),
+            free_head: NIL,
+        }
+    }
+
+    fn alloc(&mut self, order: Order) -> OrderHandle {
+        if self.free_head != NIL {
+            let handle = self.free_head;
+            let node = &mut self.nodes[handle as usize];
+            self.free_head = node.next;
+            *node = OrderNode { order, prev: NIL, next: NIL };
+            return handle;
+        }
+
+        self.nodes.push(OrderNode { order, prev: NIL, next: NIL });
+        (self.nodes.len() - 1) as OrderHandle
+    }
+
+    fn free(&mut self, handle: OrderHandle) {
+        let node = &mut self.nodes[handle as usize];
+        node.prev = NIL;
+        node.next = self.free_head;
+        self.free_head = handle;
+    }
+
+    fn node(&self, handle: OrderHandle) -> &OrderNode {
+        &self.nodes[handle as usize]
+    }
+
+    fn node_mut(&mut self, handle: OrderHandle) -> &mut OrderNode {
+        &mut self.nodes[handle as usize]
+    }
+}
+
+/// Orders of one level, front of the queue first
+pub struct LevelOrders<'a> {
+    pool: &'a OrderPool,
+    cursor: OrderHandle,
 }
 
-#[derive(Clone, Debug)]
+impl<'a> Iterator for LevelOrders<'a> {
+    type Item = &'a Order;
+
+    fn next(&mut self) -> Option<&'a Order> {
+        if self.cursor == NIL {
+            return None;
+        }
+        let node = self.pool.node(self.cursor);
+        self.cursor = node.next;
+        Some(&node.order)
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
 pub struct Order {
     pub order_id: OrderId,
     pub user_id: UserId,
@@ -34,72 +158,254 @@ pub struct Order {
     pub reduce_only: bool,
     pub post_only: bool,
     pub slippage_limit: Option<Ratio>,
+    #[serde(default)]
+    pub self_trade_prevention: Option<SelfTradePrevention>,  // Overrides the account/market mode
+    #[serde(default)]
+    pub position_side: PositionSide,
+    #[serde(default)]
+    pub min_fill_quantity: Option<Quantity>,  // IOC minimum, checked before matching
+}
+
+impl Order {
+    /// Reduce-only, or closing a hedge-mode leg (which can never open or flip it)
+    pub fn is_reducing(&self) -> bool {
+        self.reduce_only || self.position_side.closes(self.side)
+    }
+}
+
+/// Where a resting order sits in its price level's FIFO queue
+/// An estimate for fill modelling: orders ahead may still cancel
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct QueuePosition {
+    pub price: Price,
+    pub orders_ahead: usize,
+    pub quantity_ahead: Quantity,     // Unfilled quantity ahead at the same level
+    pub level_quantity: Quantity,     // Total unfilled quantity at the level
+}
+
+/// Aggregated price level for market data
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct DepthLevel {
+    pub price: Price,
+    pub quantity: Quantity,  // Unfilled quantity resting at the level
+    pub orders: usize,
+}
+
+/// Top levels of each side, best first
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct DepthSnapshot {
+    pub bids: Vec<DepthLevel>,
+    pub asks: Vec<DepthLevel>,
+}
+
+impl DepthSnapshot {
+    /// At most `levels` levels per side
+    pub fn truncated(&self, levels: usize) -> DepthSnapshot {
+        DepthSnapshot {
+            bids: self.bids.iter().take(levels).copied().collect(),
+            asks: self.asks.iter().take(levels).copied().collect(),
+        }
+    }
 }
 
 impl OrderBook {
     pub fn new() -> Self {
+        Self::with_capacity(DEFAULT_ORDER_CAPACITY)
+    }
+
+    /// Book with `capacity` order slots preallocated
+    pub fn with_capacity(capacity: usize) -> Self {
         OrderBook {
             bids: BTreeMap::new(),
             asks: BTreeMap::new(),
-            orders: HashMap::new(),
+            pool: OrderPool::with_capacity(capacity),
+            index: HashMap::with_capacity(capacity),
+            by_user: HashMap::new(),
+            expiries: BTreeMap::new(),
+            bid_cache: LevelCache::new(Side::Buy, CACHED_LEVELS),
+            ask_cache: LevelCache::new(Side::Sell, CACHED_LEVELS),
         }
     }
 
     pub fn add_order(&mut self, order: Order) -> Result<()> {
         // Check for duplicate
-        if self.orders.contains_key(&order.order_id) {
+        if self.index.contains_key(&order.order_id) {
             return Err(Error::DuplicateOrderId(order.order_id));
         }
 
-        // CORRECTED: Proper handling of Reverse wrapper
-        let level = if order.side == Side::Buy {
-            self.bids.entry(Reverse(order.price)).or_insert_with(|| PriceLevel {
-                price: order.price,
-                orders: VecDeque::new(),
-                total_quantity: Quantity::zero(),
-            })
-        } else {
-            self.asks.entry(order.price).or_insert_with(|| PriceLevel {
-                price: order.price,
-                orders: VecDeque::new(),
-                total_quantity: Quantity::zero(),
-            })
+        if let Some(expires_at) = order.time_in_force.expires_at() {
+            self.expiries.entry(expires_at).or_default().push(order.order_id);
+        }
+
+        let (order_id, user_id, side, price) = (order.order_id, order.user_id, order.side, order.price);
+        let unfilled = order.quantity - order.filled;
+        let handle = self.pool.alloc(order);
+
+        let level = match side {
+            Side::Buy => self.bids.entry(Reverse(price)).or_insert_with(|| PriceLevel::new(price)),
+            Side::Sell => self.asks.entry(price).or_insert_with(|| PriceLevel::new(price)),
         };
 
-        level.total_quantity = level.total_quantity + (order.quantity - order.filled);
-        level.orders.push_back(order.clone());
+        // Link at the back of the level queue
+        let tail = level.tail;
+        level.tail = handle;
+        if tail == NIL {
+            level.head = handle;
+        }
+        level.order_count += 1;
+        level.total_quantity = level.total_quantity + unfilled;
+
+        if tail != NIL {
+            self.pool.node_mut(tail).next = handle;
+        }
+        self.pool.node_mut(handle).prev = tail;
 
-        // Add to orders map
-        self.orders.insert(order.order_id, order);
+        self.index.insert(order_id, handle);
+        self.by_user.entry(user_id).or_default().insert(order_id);
+        self.sync_level(side, price);
 
         Ok(())
     }
 
     pub fn remove_order(&mut self, order_id: &OrderId) -> Result<Order> {
-        let order = self.orders.remove(order_id).ok_or(Error::OrderNotFound(*order_id))?;
+        let handle = *self.index.get(order_id).ok_or(Error::OrderNotFound(*order_id))?;
+        Ok(self.remove_at(handle))
+    }
 
-        // Remove from price level
-        if order.side == Side::Buy {
-            if let Some(level) = self.bids.get_mut(&Reverse(order.price)) {
-                level.orders.retain(|o| o.order_id != *order_id);
-                level.total_quantity = level.total_quantity - (order.quantity - order.filled);
+    /// Unlink the order in `handle` from its level (dropping the level once
+    /// empty) and free the slot
+    pub fn remove_at(&mut self, handle: OrderHandle) -> Order {
+        let node = self.pool.node(handle);
+        let (prev, next) = (node.prev, node.next);
+        let order = node.order.clone();
 
-                if level.orders.is_empty() {
-                    self.bids.remove(&Reverse(order.price));
-                }
+        if prev != NIL {
+            self.pool.node_mut(prev).next = next;
+        }
+        if next != NIL {
+            self.pool.node_mut(next).prev = prev;
+        }
+
+        if let Some(level) = self.level_mut(order.side, order.price) {
+            if level.head == handle {
+                level.head = next;
             }
-        } else {
-            if let Some(level) = self.asks.get_mut(&order.price) {
-                level.orders.retain(|o| o.order_id != *order_id);
-                level.total_quantity = level.total_quantity - (order.quantity - order.filled);
+            if level.tail == handle {
+                level.tail = prev;
+            }
+            level.order_count -= 1;
+            level.total_quantity = level.total_quantity - (order.quantity - order.filled);
 
-                if level.orders.is_empty() {
-                    self.asks.remove(&order.price);
+            if level.order_count == 0 {
+                match order.side {
+                    Side::Buy => self.bids.remove(&Reverse(order.price)),
+                    Side::Sell => self.asks.remove(&order.price),
+                };
+            }
+        }
+
+        self.index.remove(&order.order_id);
+        if let Some(user_orders) = self.by_user.get_mut(&order.user_id) {
+            user_orders.remove(&order.order_id);
+            if user_orders.is_empty() {
+                self.by_user.remove(&order.user_id);
+            }
+        }
+        self.pool.free(handle);
+        self.sync_level(order.side, order.price);
+
+        order
+    }
+
+    /// Record a fill of `quantity` on the resting order in `handle`
+    pub fn fill_at(&mut self, handle: OrderHandle, quantity: Quantity) -> &Order {
+        let (side, price) = {
+            let order = &mut self.pool.node_mut(handle).order;
+            order.filled = order.filled + quantity;
+            (order.side, order.price)
+        };
+        if let Some(level) = self.level_mut(side, price) {
+            level.total_quantity = level.total_quantity - quantity;
+        }
+        self.sync_level(side, price);
+        &self.pool.node(handle).order
+    }
+
+    /// Reduce the total quantity of the resting order in `handle`, keeping its place
+    pub fn shrink_at(&mut self, handle: OrderHandle, quantity: Quantity) -> &Order {
+        let (side, price) = {
+            let order = &mut self.pool.node_mut(handle).order;
+            order.quantity = order.quantity - quantity;
+            (order.side, order.price)
+        };
+        if let Some(level) = self.level_mut(side, price) {
+            level.total_quantity = level.total_quantity - quantity;
+        }
+        self.sync_level(side, price);
+        &self.pool.node(handle).order
+    }
+
+    pub fn order_at(&self, handle: OrderHandle) -> &Order {
+        &self.pool.node(handle).order
+    }
+
+    /// Change a resting order's price and/or total quantity
+    /// With `keep_priority` (same price only) the order stays where it is in
+    /// its level's queue; otherwise it joins the back of the new level
+    pub fn amend_order(
+        &mut self,
+        order_id: &OrderId,
+        new_price: Price,
+        new_quantity: Quantity,
+        keep_priority: bool,
+        timestamp: Timestamp,
+    ) -> Result<Order> {
+        let handle = *self.index.get(order_id).ok_or(Error::OrderNotFound(*order_id))?;
+        let existing = self.order_at(handle);
+
+        if !keep_priority || existing.price != new_price {
+            let mut order = self.remove_at(handle);
+            order.price = new_price;
+            order.quantity = new_quantity;
+            order.timestamp = timestamp;
+            self.add_order(order.clone())?;
+            return Ok(order);
+        }
+
+        let (side, price, old_quantity) = (existing.side, existing.price, existing.quantity);
+        let level = self.level_mut(side, price).ok_or(Error::OrderNotFound(*order_id))?;
+        level.total_quantity = level.total_quantity - old_quantity + new_quantity;
+        self.sync_level(side, price);
+
+        let order = &mut self.pool.node_mut(handle).order;
+        order.quantity = new_quantity;
+        Ok(order.clone())
+    }
+
+    /// Earliest GTD expiry on the book (may belong to an order already gone)
+    pub fn next_expiry(&self) -> Option<Timestamp> {
+        self.expiries.keys().next().copied()
+    }
+
+    /// Remove every GTD order expiring at or before `now`, earliest expiry first
+    /// (then in the order they were added)
+    pub fn sweep_expired(&mut self, now: Timestamp) -> Vec<Order> {
+        let mut expired = Vec::new();
+
+        while let Some(entry) = self.expiries.first_entry() {
+            if *entry.key() > now {
+                break;
+            }
+
+            // Orders filled or cancelled since they were indexed are skipped
+            for order_id in entry.remove() {
+                if let Ok(order) = self.remove_order(&order_id) {
+                    expired.push(order);
                 }
             }
         }
 
-        Ok(order)
+        expired
     }
 
     pub fn best_bid(&self) -> Option<Price> {
@@ -110,6 +416,158 @@ impl OrderBook {
         self.asks.keys().next().copied()
     }
 
+    /// Worst price a taker on `side` would reach filling `quantity` against the book
+    /// Stops at the last level when the book is too thin; None if the opposite side is empty
+    pub fn sweep_price(&self, side: Side, quantity: Quantity) -> Option<Price> {
+        if let Some(worst) = self.level_cache(side.opposite()).sweep_price(quantity) {
+            return worst;
+        }
+
+        let levels: Box<dyn Iterator<Item = &PriceLevel>> = match side {
+            Side::Buy => Box::new(self.asks.values()),
+            Side::Sell => Box::new(self.bids.values()),
+        };
+
+        let mut remaining = quantity;
+        let mut worst = None;
+        for level in levels {
+            worst = Some(level.price);
+            if level.total_quantity >= remaining {
+                break;
+            }
+            remaining = remaining - level.total_quantity;
+        }
+        worst
+    }
+
+    /// (best_bid, best_ask) when the book is crossed or locked (best_bid >= best_ask)
+    /// Matching never leaves the book this way; replay, restore or amend bugs can
+    pub fn crossed(&self) -> Option<(Price, Price)> {
+        match (self.best_bid(), self.best_ask()) {
+            (Some(bid), Some(ask)) if bid >= ask => Some((bid, ask)),
+            _ => None,
+        }
+    }
+
+    /// Front of the queue at `price` on `side` (resting side)
+    pub fn level_front(&self, side: Side, price: Price) -> Option<OrderHandle> {
+        self.level(side, price)
+            .map(|level| level.head)
+            .filter(|head| *head != NIL)
+    }
+
+    /// Orders of the level at `price` on `side` in queue order: (slot, unfilled quantity)
+    pub fn level_queue(&self, side: Side, price: Price) -> Vec<(OrderHandle, Quantity)> {
+        let mut queue = Vec::new();
+        let mut handle = self.level(side, price).map_or(NIL, |level| level.head);
+        while handle != NIL {
+            let node = self.pool.node(handle);
+            queue.push((handle, node.order.quantity - node.order.filled));
+            handle = node.next;
+        }
+        queue
+    }
+
+    /// Front of the best level on `side` (resting side)
+    pub fn best_front(&self, side: Side) -> Option<&Order> {
+        let level = match side {
+            Side::Buy => self.bids.values().next()?,
+            Side::Sell => self.asks.values().next()?,
+        };
+        self.level_orders(level).next()
+    }
+
+    /// Remove the order at the front of the best level on `side` (resting side)
+    pub fn pop_best(&mut self, side: Side) -> Option<Order> {
+        let best = match side {
+            Side::Buy => self.best_bid()?,
+            Side::Sell => self.best_ask()?,
+        };
+        let handle = self.level_front(side, best)?;
+        Some(self.remove_at(handle))
+    }
+
+    /// Aggregated top `levels` levels per side
+    pub fn depth(&self, levels: usize) -> DepthSnapshot {
+        DepthSnapshot {
+            bids: self.side_depth(Side::Buy, levels),
+            asks: self.side_depth(Side::Sell, levels),
+        }
+    }
+
+    fn side_depth(&self, side: Side, levels: usize) -> Vec<DepthLevel> {
+        let cache = self.level_cache(side);
+        if cache.levels().len() >= levels || cache.is_complete() {
+            return cache.levels().iter()
+                .take(levels)
+                .map(|level| DepthLevel { price: level.price, quantity: level.quantity, orders: level.orders })
+                .collect();
+        }
+
+        let aggregate = |level: &PriceLevel| DepthLevel {
+            price: level.price,
+            quantity: level.total_quantity,
+            orders: level.order_count,
+        };
+        match side {
+            Side::Buy => self.bids.values().take(levels).map(aggregate).collect(),
+            Side::Sell => self.asks.values().take(levels).map(aggregate).collect(),
+        }
+    }
+
+    /// Resting quantity a taker on `side` could reach without going past `limit`
+    pub fn depth_within(&self, side: Side, limit: Price) -> Quantity {
+        if let Some(quantity) = self.level_cache(side.opposite()).quantity_within(limit) {
+            return quantity;
+        }
+
+        match side {
+            Side::Buy => self.asks.values()
+                .take_while(|level| limit >= level.price)
+                .map(|level| level.total_quantity)
+                .sum(),
+            Side::Sell => self.bids.values()
+                .take_while(|level| limit <= level.price)
+                .map(|level| level.total_quantity)
+                .sum(),
+        }
+    }
+
+    /// Cumulative quantity and notional of the best `levels` levels on
+    /// `side` (resting side); `price` is the worst level included
+    pub fn top_levels(&self, side: Side, levels: usize) -> Option<CachedLevel> {
+        if let Some(top) = self.level_cache(side).top(levels) {
+            return Some(*top);
+        }
+
+        let walked: Box<dyn Iterator<Item = &PriceLevel>> = match side {
+            Side::Buy => Box::new(self.bids.values()),
+            Side::Sell => Box::new(self.asks.values()),
+        };
+        walked.take(levels).fold(None, |acc: Option<CachedLevel>, level| {
+            let notional = level.total_quantity * level.price;
+            let (quantity, total) = acc.map_or((Quantity::zero(), Balance::zero()), |acc| {
+                (acc.cumulative_quantity, acc.cumulative_notional)
+            });
+            Some(CachedLevel {
+                price: level.price,
+                quantity: level.total_quantity,
+                orders: level.order_count,
+                notional,
+                cumulative_quantity: quantity + level.total_quantity,
+                cumulative_notional: total + notional,
+            })
+        })
+    }
+
+    /// Aggregates of the best levels on `side` (resting side)
+    pub fn level_cache(&self, side: Side) -> &LevelCache {
+        match side {
+            Side::Buy => &self.bid_cache,
+            Side::Sell => &self.ask_cache,
+        }
+    }
+
     pub fn spread(&self) -> Option<Price> {
         match (self.best_ask(), self.best_bid()) {
             (Some(ask), Some(bid)) => Some(ask - bid),
@@ -118,67 +576,210 @@ impl OrderBook {
     }
 
     pub fn get_order(&self, order_id: &OrderId) -> Option<&Order> {
-        self.orders.get(order_id)
+        self.index.get(order_id).map(|handle| self.order_at(*handle))
     }
 
-    /// Lấy tham chiếu mutable tới PriceLevel tốt nhất ở phía đối diện
-    /// (Taker Buy -> Lấy Best Ask, Taker Sell -> Lấy Best Bid)
-    pub fn get_best_level_mut(&mut self, taker_side: Side) -> Option<&mut PriceLevel> {
-        match taker_side {
-            Side::Buy => {
-                // Taker mua -> Cần lấy Ask thấp nhất (First Entry of Asks)
-                let best_price = self.asks.keys().next().copied()?;
-                self.asks.get_mut(&best_price)
-            },
-            Side::Sell => {
-                // Taker bán -> Cần lấy Bid cao nhất (First Entry of Bids - nhờ Reverse)
-                let best_price = *self.bids.keys().next()?; // best_price ở đây là Reverse<Price>
-                self.bids.get_mut(&best_price)
-            }
+    /// Every resting order, in no particular order
+    pub fn orders(&self) -> impl Iterator<Item = &Order> {
+        self.index.values().map(|handle| self.order_at(*handle))
+    }
+
+    /// Number of resting orders
+    /// Resting orders of `user_id`, in no particular order
+    pub fn user_orders(&self, user_id: &UserId) -> Vec<&Order> {
+        self.by_user.get(user_id)
+            .map(|order_ids| order_ids.iter().filter_map(|order_id| self.get_order(order_id)).collect())
+            .unwrap_or_default()
+    }
+
+    pub fn has_user_orders(&self, user_id: &UserId) -> bool {
+        self.by_user.contains_key(user_id)
+    }
+
+    /// Users with at least one resting order
+    pub fn users(&self) -> impl Iterator<Item = &UserId> {
+        self.by_user.keys()
+    }
+
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    /// Orders of `level`, front of the queue first
+    pub fn level_orders(&self, level: &PriceLevel) -> LevelOrders<'_> {
+        LevelOrders {
+            pool: &self.pool,
+            cursor: level.head,
         }
     }
 
-    /// Hàm dọn dẹp sau khi khớp lệnh: Xóa order khỏi map lookup và xóa level rỗng
-    pub fn cleanup_after_match(&mut self, filled_order_id: OrderId, price: Price, side: Side, filled_qty: Quantity) {
-        // 1. Xóa order khỏi hashmap tra cứu nhanh
-        self.orders.remove(&filled_order_id);
+    /// Resting orders in exact matching priority: bids best-first, then asks
+    /// best-first, FIFO within each level. This is what snapshots should persist.
+    pub fn orders_in_priority(&self) -> Vec<Order> {
+        self.bids.values()
+            .chain(self.asks.values())
+            .flat_map(|level| self.level_orders(level).cloned())
+            .collect()
+    }
 
-        // 2. Cập nhật total_quantity của level (việc pop order khỏi queue đã làm ở matcher)
-        // Tuy nhiên, để an toàn và chuẩn logic, ta nên để Matcher gọi hàm này
-        // Ở đây ta giả định Matcher đã pop order ra rồi, ta chỉ cần check xem level có rỗng không để xóa
+    /// Per-order (L3) export of every resting order, users replaced by a salted hash
+    pub fn l3_snapshot(&self, salt: &[u8], exported_at: Timestamp) -> L3Snapshot {
+        L3Snapshot::from_orders(&self.orders_in_priority(), salt, exported_at)
+    }
 
+    /// Rebuild an empty book from `orders_in_priority()` output
+    /// Queue position follows input order and original timestamps are kept,
+    /// so makers keep their place in line across restarts
+    pub fn restore_orders(&mut self, orders: Vec<Order>) -> Result<()> {
+        if !self.index.is_empty() {
+            return Err(Error::InvariantViolation(crate::error::InvariantViolation {
+                invariant: "order_book_restore",
+                details: format!("book not empty: {} resting orders", self.index.len()),
+            }));
+        }
+
+        for order in orders {
+            self.add_order(order)?;
+        }
+
+        Ok(())
+    }
+
+    fn level(&self, side: Side, price: Price) -> Option<&PriceLevel> {
         match side {
-            Side::Buy => { // Maker là Buy (nằm trong Bids)
-                if let Some(level) = self.bids.get_mut(&Reverse(price)) {
-                    // Logic update quantity nên làm lúc match, ở đây chỉ check empty
-                    if level.orders.is_empty() {
-                        self.bids.remove(&Reverse(price));
-                    }
-                }
-            },
-            Side::Sell => { // Maker là Sell (nằm trong Asks)
-                if let Some(level) = self.asks.get_mut(&price) {
-                    if level.orders.is_empty() {
-                        self.asks.remove(&price);
-                    }
-                }
-            }
+            Side::Buy => self.bids.get(&Reverse(price)),
+            Side::Sell => self.asks.get(&price),
+        }
+    }
+
+    fn level_mut(&mut self, side: Side, price: Price) -> Option<&mut PriceLevel> {
+        match side {
+            Side::Buy => self.bids.get_mut(&Reverse(price)),
+            Side::Sell => self.asks.get_mut(&price),
         }
     }
 
-    // Hàm helper cập nhật số lượng level (tránh matcher chọc trực tiếp field)
-    pub fn decrease_level_quantity(&mut self, price: Price, side: Side, amount: Quantity) {
+    /// Bring the level cache in line with the level at `price` after it changed
+    fn sync_level(&mut self, side: Side, price: Price) {
+        let level = self.level(side, price).map(|level| (level.total_quantity, level.order_count));
         match side {
             Side::Buy => {
-                if let Some(level) = self.bids.get_mut(&Reverse(price)) {
-                    level.total_quantity = level.total_quantity - amount;
+                if self.bid_cache.update(price, level) {
+                    let next = match self.bid_cache.last_price() {
+                        Some(last) => self.bids.range((Excluded(Reverse(last)), Unbounded)).next(),
+                        None => self.bids.iter().next(),
+                    };
+                    if let Some((_, next)) = next {
+                        self.bid_cache.refill(next.price, next.total_quantity, next.order_count);
+                    }
                 }
-            },
+            }
             Side::Sell => {
-                if let Some(level) = self.asks.get_mut(&price) {
-                    level.total_quantity = level.total_quantity - amount;
+                if self.ask_cache.update(price, level) {
+                    let next = match self.ask_cache.last_price() {
+                        Some(last) => self.asks.range((Excluded(last), Unbounded)).next(),
+                        None => self.asks.iter().next(),
+                    };
+                    if let Some((_, next)) = next {
+                        self.ask_cache.refill(next.price, next.total_quantity, next.order_count);
+                    }
                 }
             }
         }
     }
-}
\ No newline at end of file
+
+    /// Queue position of a resting order (None if it is not on the book)
+    pub fn queue_position(&self, order_id: &OrderId) -> Option<QueuePosition> {
+        let order = self.get_order(order_id)?;
+
+        self.level_queue_positions(order.side, order.price)
+            .into_iter()
+            .find(|(o, _)| o.order_id == *order_id)
+            .map(|(_, position)| position)
+    }
+
+    /// Queue positions of every order at a level, front of the queue first
+    /// Recomputed whenever the level changes (add, cancel, fill)
+    pub fn level_queue_positions(&self, side: Side, price: Price) -> Vec<(&Order, QueuePosition)> {
+        let level = match self.level(side, price) {
+            Some(level) => level,
+            None => return Vec::new(),
+        };
+
+        let mut quantity_ahead = Quantity::zero();
+        self.level_orders(level)
+            .enumerate()
+            .map(|(orders_ahead, order)| {
+                let position = QueuePosition {
+                    price: level.price,
+                    orders_ahead,
+                    quantity_ahead,
+                    level_quantity: level.total_quantity,
+                };
+                quantity_ahead = quantity_ahead + (order.quantity - order.filled);
+                (order, position)
+            })
+            .collect()
+    }
+}
+
+impl OrderBookStore for OrderBook {
+    fn add_order(&mut self, order: Order) -> Result<()> {
+        OrderBook::add_order(self, order)
+    }
+
+    fn restore_orders(&mut self, orders: Vec<Order>) -> Result<()> {
+        OrderBook::restore_orders(self, orders)
+    }
+
+    fn remove_order(&mut self, order_id: &OrderId) -> Result<Order> {
+        OrderBook::remove_order(self, order_id)
+    }
+
+    fn amend_order(
+        &mut self,
+        order_id: &OrderId,
+        new_price: Price,
+        new_quantity: Quantity,
+        keep_priority: bool,
+        timestamp: Timestamp,
+    ) -> Result<Order> {
+        OrderBook::amend_order(self, order_id, new_price, new_quantity, keep_priority, timestamp)
+    }
+
+    fn get_order(&self, order_id: &OrderId) -> Option<&Order> {
+        OrderBook::get_order(self, order_id)
+    }
+
+    fn resting_orders(&self) -> Vec<&Order> {
+        self.orders().collect()
+    }
+
+    fn user_orders(&self, user_id: &UserId) -> Vec<&Order> {
+        OrderBook::user_orders(self, user_id)
+    }
+
+    fn best_bid(&self) -> Option<Price> {
+        OrderBook::best_bid(self)
+    }
+
+    fn best_ask(&self) -> Option<Price> {
+        OrderBook::best_ask(self)
+    }
+
+    fn sweep_price(&self, side: Side, quantity: Quantity) -> Option<Price> {
+        OrderBook::sweep_price(self, side, quantity)
+    }
+
+    fn level_queue_positions(&self, side: Side, price: Price) -> Vec<(&Order, QueuePosition)> {
+        OrderBook::level_queue_positions(self, side, price)
+    }
+
+    fn sweep_expired(&mut self, now: Timestamp) -> Vec<Order> {
+        OrderBook::sweep_expired(self, now)
+    }
+}
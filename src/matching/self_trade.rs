@@ -1,4 +1,8 @@
+use std::collections::HashMap;
+use crate::config::market::MarketConfig;
+use crate::events::order::SelfTradePrevention;
 use crate::matching::order_book::Order;
+use crate::types::ids::UserId;
 
 #[derive(Clone, Copy, Debug)]
 pub enum SelfTradeAction {
@@ -6,31 +10,69 @@ pub enum SelfTradeAction {
     CancelMaker,
     CancelTaker,
     CancelBoth,
+    DecrementAndCancel,
 }
 
-pub fn check_self_trade(maker: &Order, taker: &Order) -> SelfTradeAction {
-    if maker.user_id == taker.user_id {
-        SelfTradeAction::CancelMaker  // Default policy
-    } else {
-        SelfTradeAction::Allow
+impl From<SelfTradePrevention> for SelfTradeAction {
+    fn from(mode: SelfTradePrevention) -> Self {
+        match mode {
+            SelfTradePrevention::CancelMaker => SelfTradeAction::CancelMaker,
+            SelfTradePrevention::CancelTaker => SelfTradeAction::CancelTaker,
+            SelfTradePrevention::CancelBoth => SelfTradeAction::CancelBoth,
+            SelfTradePrevention::DecrementAndCancel => SelfTradeAction::DecrementAndCancel,
+        }
     }
 }
 
-// ADDED: Configurable self-trade policy
+/// Self-trade check with the default (cancel maker) mode
+pub fn check_self_trade(maker: &Order, taker: &Order) -> SelfTradeAction {
+    SelfTradePolicy::new(SelfTradePrevention::default()).check(maker, taker)
+}
+
+/// Self-trade prevention policy
+///
+/// ## Precedence (taker side)
+/// 1. Order override (set from the submitting API key)
+/// 2. Account mode (`account_self_trade_prevention`)
+/// 3. Market mode (`self_trade_prevention`)
+#[derive(Clone, Debug, Default)]
 pub struct SelfTradePolicy {
-    action: SelfTradeAction,
+    market_mode: SelfTradePrevention,
+    accounts: HashMap<UserId, SelfTradePrevention>,
 }
 
 impl SelfTradePolicy {
-    pub fn new(action: SelfTradeAction) -> Self {
-        SelfTradePolicy { action }
+    pub fn new(market_mode: SelfTradePrevention) -> Self {
+        SelfTradePolicy { market_mode, accounts: HashMap::new() }
+    }
+
+    pub fn from_market(config: &MarketConfig) -> Self {
+        SelfTradePolicy {
+            market_mode: config.self_trade_prevention,
+            accounts: config.account_self_trade_prevention.clone(),
+        }
+    }
+
+    pub fn set_account(&mut self, user_id: UserId, mode: SelfTradePrevention) {
+        self.accounts.insert(user_id, mode);
+    }
+
+    pub fn clear_account(&mut self, user_id: &UserId) {
+        self.accounts.remove(user_id);
+    }
+
+    /// Mode that applies when `taker` meets one of its own resting orders
+    pub fn mode_for(&self, taker: &Order) -> SelfTradePrevention {
+        taker.self_trade_prevention
+            .or_else(|| self.accounts.get(&taker.user_id).copied())
+            .unwrap_or(self.market_mode)
     }
 
     pub fn check(&self, maker: &Order, taker: &Order) -> SelfTradeAction {
         if maker.user_id == taker.user_id {
-            self.action
+            self.mode_for(taker).into()
         } else {
             SelfTradeAction::Allow
         }
     }
-}
\ No newline at end of file
+}
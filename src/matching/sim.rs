@@ -0,0 +1,372 @@
+use serde::{Deserialize, Serialize};
+use crate::error::{Error, Result};
+use crate::events::order::Side;
+use crate::matching::matcher::Matcher;
+use crate::matching::order_book::Order;
+use crate::settlement::position_manager::PositionManager;
+use crate::types::balance::Balance;
+use crate::types::ids::{OrderId, UserId};
+use crate::types::price::Price;
+use crate::types::quantity::Quantity;
+
+/// One step of a recorded order flow
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum SimCommand {
+    Submit(Order),
+    Cancel(OrderId),
+}
+
+/// A trade as compared across runs: everything the matcher decides, without
+/// the event envelope's random ids and wall-clock timestamps
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SimFill {
+    pub maker_order_id: OrderId,
+    pub taker_order_id: OrderId,
+    pub maker_user_id: UserId,
+    pub taker_user_id: UserId,
+    pub price: Price,
+    pub quantity: Quantity,
+    pub maker_side: Side,
+    pub maker_fee: Balance,
+    pub taker_fee: Balance,
+}
+
+/// What a flow produced: fills in execution order and the book left behind
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SimOutcome {
+    pub fills: Vec<SimFill>,
+    pub rejected: Vec<OrderId>,  // Submits the matcher refused (FOK, post-only, ...)
+    pub resting: Vec<Order>,     // Final book in priority order
+}
+
+impl SimOutcome {
+    /// Canonical bytes of the outcome; equal outcomes give equal bytes
+    pub fn fingerprint(&self) -> Result<Vec<u8>> {
+        bincode::serialize(self).map_err(|e| Error::SerializationError(e.to_string()))
+    }
+}
+
+/// Drives a matcher through a recorded flow, outside the event processor
+///
+/// Margin is the processor's concern, not the matcher's, so no accounts are
+/// kept. Positions start flat: reduce-only orders are therefore shrunk to
+/// nothing and should be left out of generated flows.
+pub struct Simulation {
+    matcher: Matcher,
+    positions: PositionManager,
+    mark_price: Price,
+}
+
+impl Simulation {
+    pub fn new(matcher: Matcher, mark_price: Price) -> Self {
+        Simulation {
+            positions: PositionManager::new_with_market(matcher.market_id()),
+            matcher,
+            mark_price,
+        }
+    }
+
+    pub fn run(mut self, flow: &[SimCommand]) -> Result<SimOutcome> {
+        let mut fills = Vec::new();
+        let mut rejected = Vec::new();
+
+        for command in flow {
+            match command {
+                SimCommand::Submit(order) => {
+                    match self.matcher.match_order(order, &self.positions, self.mark_price) {
+                        Ok(trades) => fills.extend(trades.into_iter().map(|trade| SimFill {
+                            maker_order_id: trade.maker_order_id,
+                            taker_order_id: trade.taker_order_id,
+                            maker_user_id: trade.maker_user_id,
+                            taker_user_id: trade.taker_user_id,
+                            price: trade.price,
+                            quantity: trade.quantity,
+                            maker_side: trade.maker_side,
+                            maker_fee: trade.maker_fee.amount,
+                            taker_fee: trade.taker_fee.amount,
+                        })),
+                        Err(_) => rejected.push(order.order_id),
+                    }
+                }
+                SimCommand::Cancel(order_id) => {
+                    // Cancelling an order that already left the book is part of the flow, not an error
+                    let _ = self.matcher.cancel_order(order_id);
+                }
+            }
+
+            self.matcher.drain_completed_orders();
+            self.matcher.drain_self_trades();
+            self.matcher.drain_execution_reports();
+        }
+
+        Ok(SimOutcome {
+            fills,
+            rejected,
+            resting: self.matcher.order_book().orders_in_priority(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use crate::config::fees::FeeConfig;
+    use crate::error::InvariantViolation;
+    use crate::events::order::{OrderType, TimeInForce};
+    use crate::matching::order_book::OrderBook;
+    use crate::types::ids::MarketId;
+    use crate::types::position::PositionSide;
+    use crate::types::timestamp::Timestamp;
+    use proptest::prelude::*;
+    use proptest::test_runner::TestCaseError;
+    use uuid::Uuid;
+
+    /// Replay `flow` against two independently built matchers and require
+    /// byte-identical outcomes
+    fn replay_twice(flow: &[SimCommand], build: impl Fn() -> Matcher, mark_price: Price) -> Result<SimOutcome> {
+        let first = Simulation::new(build(), mark_price).run(flow)?;
+        let second = Simulation::new(build(), mark_price).run(flow)?;
+
+        if first.fingerprint()? != second.fingerprint()? {
+            let diverged_at = first.fills.iter()
+                .zip(&second.fills)
+                .position(|(a, b)| a != b)
+                .unwrap_or(first.fills.len().min(second.fills.len()));
+            return Err(violation("matching_determinism", format!(
+                "replays diverged at fill {} ({} vs {} fills, {} vs {} resting)",
+                diverged_at, first.fills.len(), second.fills.len(), first.resting.len(), second.resting.len()
+            )));
+        }
+
+        Ok(first)
+    }
+
+    /// Check an outcome against the flow that produced it
+    /// - No zero or negative fill quantities, no resting order filled beyond its size
+    /// - Conservation: each order's fills (as maker and taker) never exceed its
+    ///   quantity, and a resting order's `filled` equals the sum of its fills
+    /// - Price-time priority: makers trade at their own price, a taker sweeps
+    ///   prices best first, and same-price makers fill in arrival order
+    /// - Limit orders never fill through their limit
+    fn check_invariants(flow: &[SimCommand], outcome: &SimOutcome) -> Result<()> {
+        let submitted: HashMap<OrderId, (usize, &Order)> = flow.iter()
+            .filter_map(|command| match command {
+                SimCommand::Submit(order) => Some(order),
+                SimCommand::Cancel(_) => None,
+            })
+            .enumerate()
+            .map(|(arrival, order)| (order.order_id, (arrival, order)))
+            .collect();
+        let order = |order_id: &OrderId| submitted.get(order_id).copied().ok_or_else(|| {
+            violation("sim_unknown_order", format!("fill references unsubmitted order {}", order_id))
+        });
+
+        let mut filled: HashMap<OrderId, Quantity> = HashMap::new();
+        // Per taker: (last maker price, last maker arrival) to check priority
+        let mut last_maker: HashMap<OrderId, (Price, usize)> = HashMap::new();
+
+        for fill in &outcome.fills {
+            if fill.quantity <= Quantity::zero() {
+                return Err(violation("sim_fill_quantity", format!(
+                    "fill {} -> {} has quantity {}", fill.taker_order_id, fill.maker_order_id, fill.quantity
+                )));
+            }
+
+            let (maker_arrival, maker) = order(&fill.maker_order_id)?;
+            let (_, taker) = order(&fill.taker_order_id)?;
+
+            if fill.price != maker.price {
+                return Err(violation("sim_maker_price", format!(
+                    "maker {} filled at {} instead of its price {}", maker.order_id, fill.price, maker.price
+                )));
+            }
+            if taker.order_type == crate::events::order::OrderType::Limit && !crosses(taker.side, taker.price, fill.price) {
+                return Err(violation("sim_limit_price", format!(
+                    "taker {} ({:?} limit {}) filled at {}", taker.order_id, taker.side, taker.price, fill.price
+                )));
+            }
+
+            if let Some((last_price, last_arrival)) = last_maker.get(&taker.order_id).copied() {
+                let worse_than_last = match taker.side {
+                    Side::Buy => fill.price < last_price,
+                    Side::Sell => fill.price > last_price,
+                };
+                if worse_than_last || (fill.price == last_price && maker_arrival < last_arrival) {
+                    return Err(violation("sim_price_time_priority", format!(
+                        "taker {} filled maker {} at {} after a later or worse-priced maker",
+                        taker.order_id, maker.order_id, fill.price
+                    )));
+                }
+            }
+            last_maker.insert(taker.order_id, (fill.price, maker_arrival));
+
+            for order_id in [fill.maker_order_id, fill.taker_order_id] {
+                let total = filled.entry(order_id).or_insert_with(Quantity::zero);
+                *total = *total + fill.quantity;
+                let (_, order) = order(&order_id)?;
+                if *total > order.quantity - order.filled {
+                    return Err(violation("sim_overfill", format!(
+                        "order {} filled {} of {}", order_id, total, order.quantity - order.filled
+                    )));
+                }
+            }
+        }
+
+        for resting in &outcome.resting {
+            if resting.filled < Quantity::zero() || resting.filled >= resting.quantity {
+                return Err(violation("sim_resting_quantity", format!(
+                    "resting order {} has filled {} of {}", resting.order_id, resting.filled, resting.quantity
+                )));
+            }
+
+            let (_, submitted) = order(&resting.order_id)?;
+            let traded = filled.get(&resting.order_id).copied().unwrap_or_else(Quantity::zero);
+            if resting.filled != submitted.filled + traded {
+                return Err(violation("sim_fill_conservation", format!(
+                    "resting order {} shows filled {} but traded {}", resting.order_id, resting.filled, traded
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn crosses(side: Side, limit: Price, price: Price) -> bool {
+        match side {
+            Side::Buy => price <= limit,
+            Side::Sell => price >= limit,
+        }
+    }
+
+    fn violation(invariant: &'static str, details: String) -> Error {
+        Error::InvariantViolation(InvariantViolation { invariant, details })
+    }
+
+    /// Price the generated flows are centred on
+    const SIM_MID_PRICE: i64 = 50_000 * 100_000_000;
+    const SIM_TICK: i64 = 1_00000000;
+    const SIM_USERS: u8 = 4;
+
+    /// Flows of up to `max_len` steps: limit, IOC, FOK and market orders
+    /// from a handful of users around one mid price, mixed with cancels of
+    /// earlier orders. Ids and timestamps derive from the step index, so a
+    /// flow replays identically.
+    fn order_flow(max_len: usize) -> impl Strategy<Value = Vec<SimCommand>> {
+        let step = (0u8..10, 0u8..SIM_USERS, any::<bool>(), -10i64..10, 1i64..50, 0u8..4);
+        prop::collection::vec(step, 1..max_len).prop_map(|steps| {
+            let mut submitted = Vec::new();
+            steps.into_iter()
+                .enumerate()
+                .map(|(i, (kind, user, buy, offset, quantity, tif))| {
+                    if kind == 0 && !submitted.is_empty() {
+                        return SimCommand::Cancel(submitted[(quantity as usize) % submitted.len()]);
+                    }
+
+                    let order_id = OrderId(Uuid::from_u128(i as u128 + 1));
+                    submitted.push(order_id);
+                    let (order_type, time_in_force) = match tif {
+                        0 => (OrderType::Limit, TimeInForce::IOC),
+                        1 => (OrderType::Limit, TimeInForce::FOK),
+                        2 if kind < 3 => (OrderType::Market, TimeInForce::IOC),
+                        _ => (OrderType::Limit, TimeInForce::GTC),
+                    };
+                    SimCommand::Submit(Order {
+                        order_id,
+                        user_id: UserId(Uuid::from_u128(user as u128 + 1)),
+                        side: if buy { Side::Buy } else { Side::Sell },
+                        order_type,
+                        price: Price::from_i64(SIM_MID_PRICE + offset * SIM_TICK),
+                        quantity: Quantity::from_i64(quantity),
+                        filled: Quantity::zero(),
+                        timestamp: Timestamp::from_millis(i as u64 + 1),
+                        time_in_force,
+                        reduce_only: false,
+                        post_only: false,
+                        slippage_limit: None,
+                        self_trade_prevention: None,
+                        position_side: PositionSide::default(),
+                        min_fill_quantity: None,
+                    })
+                })
+                .collect()
+        })
+    }
+
+    fn matcher() -> Matcher {
+        Matcher::new(OrderBook::new(), FeeConfig::default(), MarketId::btc_perp())
+    }
+
+    fn mid() -> Price {
+        Price::from_i64(SIM_MID_PRICE)
+    }
+
+    fn limit(step: u128, user: u128, side: Side, offset: i64, quantity: i64) -> Order {
+        Order {
+            order_id: OrderId(Uuid::from_u128(step)),
+            user_id: UserId(Uuid::from_u128(user)),
+            side,
+            order_type: OrderType::Limit,
+            price: Price::from_i64(SIM_MID_PRICE + offset * SIM_TICK),
+            quantity: Quantity::from_i64(quantity),
+            filled: Quantity::zero(),
+            timestamp: Timestamp::from_millis(step as u64),
+            time_in_force: TimeInForce::GTC,
+            reduce_only: false,
+            post_only: false,
+            slippage_limit: None,
+            self_trade_prevention: None,
+            position_side: PositionSide::default(),
+            min_fill_quantity: None,
+        }
+    }
+
+    /// Three asks over two levels, one cancelled, then a bid sweeping both levels
+    fn recorded_flow() -> Vec<SimCommand> {
+        vec![
+            SimCommand::Submit(limit(1, 1, Side::Sell, 1, 5)),
+            SimCommand::Submit(limit(2, 2, Side::Sell, 0, 3)),
+            SimCommand::Submit(limit(3, 3, Side::Sell, 0, 4)),
+            SimCommand::Submit(limit(4, 3, Side::Sell, 0, 2)),
+            SimCommand::Cancel(OrderId(Uuid::from_u128(3))),
+            SimCommand::Submit(limit(5, 4, Side::Buy, 1, 7)),
+        ]
+    }
+
+    #[test]
+    fn a_recorded_flow_replays_identically_in_price_time_order() {
+        let flow = recorded_flow();
+        let outcome = replay_twice(&flow, matcher, mid()).unwrap();
+        check_invariants(&flow, &outcome).unwrap();
+
+        let makers: Vec<(u128, i64)> = outcome.fills.iter()
+            .map(|fill| (fill.maker_order_id.0.as_u128(), fill.quantity.to_i64()))
+            .collect();
+        assert_eq!(makers, vec![(2, 3), (4, 2), (1, 2)]);
+        assert_eq!(outcome.resting.len(), 1);
+        assert_eq!(outcome.resting[0].filled, Quantity::from_i64(2));
+    }
+
+    #[test]
+    fn fills_out_of_priority_are_reported() {
+        let flow = recorded_flow();
+        let mut outcome = replay_twice(&flow, matcher, mid()).unwrap();
+        outcome.fills.swap(0, 1);
+
+        match check_invariants(&flow, &outcome) {
+            Err(Error::InvariantViolation(v)) => assert_eq!(v.invariant, "sim_price_time_priority"),
+            other => panic!("expected a priority violation, got {:?}", other),
+        }
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(128))]
+
+        #[test]
+        fn random_flows_replay_identically_and_keep_invariants(flow in order_flow(60)) {
+            let outcome = replay_twice(&flow, matcher, mid())
+                .map_err(|e| TestCaseError::fail(e.to_string()))?;
+            check_invariants(&flow, &outcome).map_err(|e| TestCaseError::fail(e.to_string()))?;
+        }
+    }
+}
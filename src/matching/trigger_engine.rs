@@ -0,0 +1,227 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use serde::{Deserialize, Serialize};
+use crate::error::{Error, Result};
+use crate::events::base::{BaseEvent, EventType};
+use crate::events::order::{OrderSubmit, OrderType, Side, TrailingOffset};
+use crate::events::price::PriceSnapshot;
+use crate::observability::metrics::STOP_ORDERS_TRIGGERED;
+use crate::types::ids::OrderId;
+use crate::types::price::Price;
+
+/// Fired trailing stops remembered after their submission was logged
+const SUBMITTED_CAPACITY: usize = 10_000;
+
+/// Trailing stop waiting for the mark price to retrace
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TrailingStop {
+    pub order: OrderSubmit,
+    pub offset: TrailingOffset,
+    pub best_mark: Price,      // Highest mark seen (sell) or lowest (buy)
+    pub trigger_price: Price,  // best_mark -/+ offset, never moves against the holder
+}
+
+impl TrailingStop {
+    fn trigger_for(side: Side, offset: &TrailingOffset, best_mark: Price) -> Price {
+        let distance = offset.at(best_mark);
+        match side {
+            Side::Sell => best_mark - distance,
+            Side::Buy => best_mark + distance,
+        }
+    }
+
+    /// Follow a new mark price; true if the stop fires
+    fn on_mark_price(&mut self, mark_price: Price) -> bool {
+        let improved = match self.order.side {
+            Side::Sell => mark_price > self.best_mark,
+            Side::Buy => mark_price < self.best_mark,
+        };
+        if improved {
+            self.best_mark = mark_price;
+            self.trigger_price = Self::trigger_for(self.order.side, &self.offset, mark_price);
+        }
+
+        match self.order.side {
+            Side::Sell => mark_price <= self.trigger_price,
+            Side::Buy => mark_price >= self.trigger_price,
+        }
+    }
+}
+
+/// Trailing-stop trigger engine
+///
+/// ## Trailing
+/// - Sell trailing stop (protects a long): trigger sits `offset` below the
+///   highest mark price since acceptance and fires when the mark falls to it
+/// - Buy trailing stop (protects a short): trigger sits `offset` above the
+///   lowest mark price and fires when the mark rises to it
+/// - Bps offsets are recomputed from the new best mark each time it moves
+///
+/// ## Triggering
+/// - `on_price_snapshot()` is driven by PriceSnapshot events, so replay moves
+///   and fires the same triggers at the same sequence
+/// - A fired stop is returned as a Market `OrderSubmit` (same order id and
+///   correlation id) to be published to the event log and admitted like any
+///   other order
+/// - Replaying the PriceSnapshot that fired a stop produces its submission a
+///   second time; only the first submission logged for a fired stop is
+///   admitted (`claim_submission()`)
+/// - Pending stops, with their best mark, and the fired ones are carried in
+///   snapshots (`state()` / `restore()`)
+pub struct TriggerEngine {
+    pending: HashMap<OrderId, TrailingStop>,
+    fired: HashSet<OrderId>,       // Fired, submission not yet processed
+    submitted: VecDeque<OrderId>,  // Fired, submission processed (most recent)
+}
+
+/// Trailing stops as of a snapshot
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct TriggerEngineState {
+    pub pending: Vec<TrailingStop>,  // Oldest first
+    pub fired: Vec<OrderId>,
+    pub submitted: Vec<OrderId>,     // Oldest first
+}
+
+impl Default for TriggerEngine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TriggerEngine {
+    pub fn new() -> Self {
+        TriggerEngine {
+            pending: HashMap::new(),
+            fired: HashSet::new(),
+            submitted: VecDeque::new(),
+        }
+    }
+
+    /// Start trailing an accepted order from the current mark price
+    pub fn add(&mut self, order: OrderSubmit, mark_price: Price) -> Result<()> {
+        let offset = order.trailing_offset
+            .filter(TrailingOffset::is_valid)
+            .ok_or(Error::TrailingStopRequiresOffset)?;
+
+        if self.pending.contains_key(&order.order_id) {
+            return Err(Error::DuplicateOrderId(order.order_id));
+        }
+
+        let trigger_price = TrailingStop::trigger_for(order.side, &offset, mark_price);
+
+        tracing::info!(
+            "Trailing stop {} parked: mark={} trigger={}",
+            order.order_id, mark_price.to_f64(), trigger_price.to_f64()
+        );
+
+        self.pending.insert(order.order_id, TrailingStop {
+            order,
+            offset,
+            best_mark: mark_price,
+            trigger_price,
+        });
+        Ok(())
+    }
+
+    /// Remove a pending trailing stop (user cancel); None if it is not pending
+    pub fn cancel(&mut self, order_id: &OrderId) -> Option<TrailingStop> {
+        self.pending.remove(order_id)
+    }
+
+    pub fn get(&self, order_id: &OrderId) -> Option<&TrailingStop> {
+        self.pending.get(order_id)
+    }
+
+    /// Remove every pending trailing stop, oldest first (market expiry)
+    pub fn drain(&mut self) -> Vec<OrderSubmit> {
+        let mut orders: Vec<OrderSubmit> = self.pending.drain().map(|(_, stop)| stop.order).collect();
+        orders.sort_by_key(|order| (order.base.timestamp, order.order_id.0));
+        orders
+    }
+
+    /// Pending and fired trailing stops, for a snapshot
+    pub fn state(&self) -> TriggerEngineState {
+        let mut pending: Vec<TrailingStop> = self.pending.values().cloned().collect();
+        pending.sort_by_key(|stop| (stop.order.base.timestamp, stop.order.order_id.0));
+        let mut fired: Vec<OrderId> = self.fired.iter().copied().collect();
+        fired.sort_by_key(|order_id| order_id.0);
+
+        TriggerEngineState {
+            pending,
+            fired,
+            submitted: self.submitted.iter().copied().collect(),
+        }
+    }
+
+    /// Replace the trailing stops with a snapshot's
+    pub fn restore(&mut self, state: &TriggerEngineState) {
+        self.pending = state.pending.iter().map(|stop| (stop.order.order_id, stop.clone())).collect();
+        self.fired = state.fired.iter().copied().collect();
+        self.submitted = state.submitted.iter().copied().collect();
+    }
+
+    /// Record a logged OrderSubmit; false if it is a fired stop's submission
+    /// already processed (re-produced by a replayed PriceSnapshot)
+    pub fn claim_submission(&mut self, order_id: &OrderId) -> bool {
+        if self.fired.remove(order_id) {
+            self.submitted.push_back(*order_id);
+            if self.submitted.len() > SUBMITTED_CAPACITY {
+                self.submitted.pop_front();
+            }
+            return true;
+        }
+        !self.submitted.contains(order_id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// Move every trigger with the snapshot's mark price and return the fired
+    /// stops as Market order submissions, oldest first
+    pub fn on_price_snapshot(&mut self, snapshot: &PriceSnapshot) -> Vec<OrderSubmit> {
+        let mark_price = snapshot.mark_price;
+
+        let fired: Vec<OrderId> = self.pending.values_mut()
+            .filter_map(|stop| stop.on_mark_price(mark_price).then_some(stop.order.order_id))
+            .collect();
+
+        let mut stops: Vec<TrailingStop> = fired.iter()
+            .filter_map(|order_id| self.pending.remove(order_id))
+            .collect();
+        self.fired.extend(fired);
+
+        // HashMap order is not deterministic: release oldest first
+        stops.sort_by_key(|stop| (stop.order.base.timestamp, stop.order.order_id.0));
+
+        stops.into_iter()
+            .map(|stop| {
+                STOP_ORDERS_TRIGGERED.with_label_values(&["trailing_stop"]).inc();
+
+                tracing::info!(
+                    "Trailing stop {} triggered: mark={} best={} trigger={}",
+                    stop.order.order_id, mark_price.to_f64(),
+                    stop.best_mark.to_f64(), stop.trigger_price.to_f64()
+                );
+
+                // Stamped at the snapshot so the converted order replays identically
+                let mut base = BaseEvent::new(EventType::OrderSubmit, snapshot.base.market_id);
+                base.timestamp = snapshot.base.timestamp;
+                base.correlation_id = stop.order.base.correlation_id;
+                base.checksum = base.calculate_checksum();
+
+                OrderSubmit {
+                    base,
+                    order_type: OrderType::Market,
+                    price: None,
+                    trigger_price: None,
+                    trailing_offset: None,
+                    ..stop.order
+                }
+            })
+            .collect()
+    }
+}
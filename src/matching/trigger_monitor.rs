@@ -0,0 +1,167 @@
+use serde::{Deserialize, Serialize};
+use crate::error::{Error, Result};
+use crate::events::order::{OrderSubmit, OrderType};
+use crate::matching::order_book::Order;
+use crate::matching::trigger_store::TriggerStore;
+use crate::observability::metrics::STOP_ORDERS_TRIGGERED;
+use crate::types::ids::OrderId;
+use crate::types::price::Price;
+use crate::types::quantity::Quantity;
+use crate::types::timestamp::Timestamp;
+
+/// Mark price move that fires a pending stop
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TriggerDirection {
+    Rising,   // Fires when mark >= trigger_price
+    Falling,  // Fires when mark <= trigger_price
+}
+
+/// Stop order waiting for its trigger
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PendingStop {
+    pub order: OrderSubmit,
+    pub trigger_price: Price,
+    pub direction: TriggerDirection,
+}
+
+/// Stop-loss / take-profit trigger monitor
+///
+/// ## Direction
+/// - Fixed when the stop is accepted, from the trigger price against the mark price
+/// - Trigger above mark: fires on a rise (buy stop-loss, sell take-profit)
+/// - Trigger at or below mark: fires on a fall (sell stop-loss, buy take-profit)
+///
+/// ## Triggering
+/// - `on_mark_price()` is driven by PriceSnapshot events, so replay fires the
+///   same stops at the same sequence
+/// - Stops are held in a `TriggerStore` indexed by trigger price, never in the
+///   live book; a tick only visits the stops it fires
+/// - Fired stops become Market (StopMarket) or Limit (StopLimit) orders, oldest
+///   first, timestamped at the trigger for book priority
+/// - Margin stays reserved from acceptance until the stop fires or is cancelled
+/// - Pending stops are carried in snapshots (`pending()` / `restore()`)
+pub struct TriggerMonitor {
+    pending: TriggerStore<PendingStop>,
+}
+
+impl Default for TriggerMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TriggerMonitor {
+    pub fn new() -> Self {
+        TriggerMonitor {
+            pending: TriggerStore::new(),
+        }
+    }
+
+    /// Park an accepted stop order until the mark price reaches its trigger
+    pub fn add(&mut self, order: OrderSubmit, mark_price: Price) -> Result<()> {
+        let trigger_price = order.trigger_price.ok_or(Error::StopOrderRequiresTriggerPrice)?;
+
+        if self.pending.contains(&order.order_id) {
+            return Err(Error::DuplicateOrderId(order.order_id));
+        }
+
+        let direction = if trigger_price > mark_price {
+            TriggerDirection::Rising
+        } else {
+            TriggerDirection::Falling
+        };
+
+        tracing::info!(
+            "Stop order {} parked: trigger={} ({:?})",
+            order.order_id, trigger_price.to_f64(), direction
+        );
+
+        self.pending.insert(order.order_id, trigger_price, direction, PendingStop { order, trigger_price, direction });
+        Ok(())
+    }
+
+    /// Remove a pending stop (user cancel); None if it is not pending
+    pub fn cancel(&mut self, order_id: &OrderId) -> Option<PendingStop> {
+        self.pending.remove(order_id)
+    }
+
+    pub fn get(&self, order_id: &OrderId) -> Option<&PendingStop> {
+        self.pending.get(order_id)
+    }
+
+    /// Remove every pending stop, oldest first (market expiry)
+    pub fn drain(&mut self) -> Vec<OrderSubmit> {
+        let mut orders: Vec<OrderSubmit> = self.pending.drain().into_iter().map(|stop| stop.order).collect();
+        orders.sort_by_key(|order| (order.base.timestamp, order.order_id.0));
+        orders
+    }
+
+    /// Every pending stop, oldest first, for a snapshot
+    pub fn pending(&self) -> Vec<PendingStop> {
+        let mut stops: Vec<PendingStop> = self.pending.values().cloned().collect();
+        stops.sort_by_key(|stop| (stop.order.base.timestamp, stop.order.order_id.0));
+        stops
+    }
+
+    /// Replace the pending stops with a snapshot's, keeping their directions
+    pub fn restore(&mut self, stops: &[PendingStop]) {
+        self.pending = TriggerStore::new();
+        for stop in stops {
+            self.pending.insert(stop.order.order_id, stop.trigger_price, stop.direction, stop.clone());
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// Release every stop whose trigger the mark price has reached
+    pub fn on_mark_price(&mut self, mark_price: Price, now: Timestamp) -> Vec<Order> {
+        let mut stops = self.pending.take_reached(mark_price);
+
+        // Release oldest first, whatever their trigger prices
+        stops.sort_by_key(|stop| (stop.order.base.timestamp, stop.order.order_id.0));
+
+        stops.into_iter()
+            .map(|stop| {
+                let order_type = match stop.order.order_type {
+                    OrderType::StopLimit => OrderType::Limit,
+                    _ => OrderType::Market,
+                };
+
+                let label = match stop.order.order_type {
+                    OrderType::StopLimit => "stop_limit",
+                    _ => "stop_market",
+                };
+                STOP_ORDERS_TRIGGERED.with_label_values(&[label]).inc();
+
+                tracing::info!(
+                    "Stop order {} triggered: mark={} trigger={}",
+                    stop.order.order_id, mark_price.to_f64(), stop.trigger_price.to_f64()
+                );
+
+                Order {
+                    order_id: stop.order.order_id,
+                    user_id: stop.order.user_id,
+                    side: stop.order.side,
+                    order_type,
+                    price: stop.order.price.unwrap_or(Price::zero()),
+                    quantity: stop.order.quantity,
+                    filled: Quantity::zero(),
+                    timestamp: now,
+                    time_in_force: stop.order.time_in_force,
+                    reduce_only: stop.order.reduce_only,
+                    post_only: stop.order.post_only,
+                    slippage_limit: stop.order.slippage_limit,
+                    self_trade_prevention: stop.order.self_trade_prevention,
+                    position_side: stop.order.position_side,
+                    min_fill_quantity: stop.order.min_fill_quantity,
+                }
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,110 @@
+use std::collections::{BTreeMap, HashMap};
+use crate::matching::trigger_monitor::TriggerDirection;
+use crate::types::ids::OrderId;
+use crate::types::price::Price;
+
+/// Trigger orders indexed by trigger price, kept apart from the live book
+///
+/// Each direction has its own price-ordered index, so the triggers a mark
+/// price reaches form one contiguous range:
+/// - **Rising**: every trigger <= mark
+/// - **Falling**: every trigger >= mark
+///
+/// `take_reached()` splits that range off in O(log n + k) for k fired
+/// triggers instead of testing every pending one.
+pub struct TriggerStore<T> {
+    entries: HashMap<OrderId, (Price, TriggerDirection, T)>,
+    rising: BTreeMap<Price, Vec<OrderId>>,
+    falling: BTreeMap<Price, Vec<OrderId>>,
+}
+
+impl<T> Default for TriggerStore<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> TriggerStore<T> {
+    pub fn new() -> Self {
+        TriggerStore {
+            entries: HashMap::new(),
+            rising: BTreeMap::new(),
+            falling: BTreeMap::new(),
+        }
+    }
+
+    /// Index `value` under its trigger; false if `order_id` is already stored
+    pub fn insert(&mut self, order_id: OrderId, trigger_price: Price, direction: TriggerDirection, value: T) -> bool {
+        if self.entries.contains_key(&order_id) {
+            return false;
+        }
+
+        self.index_mut(direction).entry(trigger_price).or_default().push(order_id);
+        self.entries.insert(order_id, (trigger_price, direction, value));
+        true
+    }
+
+    pub fn remove(&mut self, order_id: &OrderId) -> Option<T> {
+        let (trigger_price, direction, value) = self.entries.remove(order_id)?;
+
+        let index = self.index_mut(direction);
+        if let Some(ids) = index.get_mut(&trigger_price) {
+            ids.retain(|id| id != order_id);
+            if ids.is_empty() {
+                index.remove(&trigger_price);
+            }
+        }
+
+        Some(value)
+    }
+
+    pub fn get(&self, order_id: &OrderId) -> Option<&T> {
+        self.entries.get(order_id).map(|(_, _, value)| value)
+    }
+
+    /// Every stored value (unordered)
+    pub fn values(&self) -> impl Iterator<Item = &T> {
+        self.entries.values().map(|(_, _, value)| value)
+    }
+
+    pub fn contains(&self, order_id: &OrderId) -> bool {
+        self.entries.contains_key(order_id)
+    }
+
+    /// Remove and return every trigger `mark_price` has reached (unordered)
+    pub fn take_reached(&mut self, mark_price: Price) -> Vec<T> {
+        // Rising: keep triggers strictly above mark
+        let above = self.rising.split_off(&Price::from_i64(mark_price.to_i64().saturating_add(1)));
+        let rising = std::mem::replace(&mut self.rising, above);
+        // Falling: everything at or above mark fires
+        let falling = self.falling.split_off(&mark_price);
+
+        rising.into_values()
+            .chain(falling.into_values())
+            .flatten()
+            .filter_map(|order_id| self.entries.remove(&order_id).map(|(_, _, value)| value))
+            .collect()
+    }
+
+    /// Remove every trigger (unordered)
+    pub fn drain(&mut self) -> Vec<T> {
+        self.rising.clear();
+        self.falling.clear();
+        self.entries.drain().map(|(_, (_, _, value))| value).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn index_mut(&mut self, direction: TriggerDirection) -> &mut BTreeMap<Price, Vec<OrderId>> {
+        match direction {
+            TriggerDirection::Rising => &mut self.rising,
+            TriggerDirection::Falling => &mut self.falling,
+        }
+    }
+}
@@ -1,16 +1,23 @@
-use crate::config::market::MarketConfig;
-use crate::events::order::{OrderSubmit, OrderType, Side};
+use crate::config::market::{MarketConfig, PriceBandConfig};
+use crate::events::order::{OrderSubmit, OrderType, Side, TimeInForce};
 use crate::error::{Error, Result};
+use crate::matching::order_book::Order;
 use crate::types::price::Price;
 use crate::types::quantity::Quantity;
 
 pub struct OrderValidator {
     config: MarketConfig,
+    reference_price: Option<Price>,  // Mark price for the price bands; None skips them
 }
 
 impl OrderValidator {
     pub fn new(config: MarketConfig) -> Self {
-        OrderValidator { config }
+        OrderValidator { config, reference_price: None }
+    }
+
+    pub fn with_reference_price(mut self, reference_price: Price) -> Self {
+        self.reference_price = Some(reference_price);
+        self
     }
 
     pub fn validate(&self, order: &OrderSubmit) -> Result<()> {
@@ -32,20 +39,54 @@ impl OrderValidator {
         self.validate_quantity(order.quantity)?;
 
         // Validate order type constraints
-        match self.validate_order_type_constraints(order) {
-            Ok(_) => Ok(()),
-            Err(e) => {
-                // Observability: Record rejection
-                let reason = match &e {
-                    Error::InsufficientMargin { .. } => "insufficient_margin",
-                    Error::InvalidPrice => "invalid_price",
-                    Error::InvalidQuantity => "invalid_quantity",
-                    _ => "other",
-                };
-                ORDERS_REJECTED.with_label_values(&[reason]).inc();
-                Err(e)
-            }
+        // (rejections are counted by the processor when OrderRejected is emitted)
+        self.validate_order_type_constraints(order)?;
+
+        // Fat-finger protection (stop-limits are checked when they trigger)
+        if let (OrderType::Limit, Some(price)) = (order.order_type, order.price) {
+            self.check_band(price, self.config.price_bands.limit_band_bps)?;
         }
+
+        Ok(())
+    }
+
+    /// Reject a market order whose estimated worst fill (see
+    /// `OrderBook::sweep_price`) is outside the slippage band around mark
+    pub fn validate_slippage_band(&self, estimated_fill: Price) -> Result<()> {
+        self.check_band(estimated_fill, self.config.price_bands.market_slippage_band_bps)
+    }
+
+    fn check_band(&self, price: Price, band_bps: u32) -> Result<()> {
+        let reference = match self.reference_price {
+            Some(reference) if reference > Price::zero() => reference,
+            _ => return Ok(()),
+        };
+        let width = match PriceBandConfig::band_width(reference, band_bps) {
+            Some(width) => width,
+            None => return Ok(()),
+        };
+
+        if price > reference + width || price < reference - width {
+            return Err(Error::PriceOutsideBand { price, reference, band_bps });
+        }
+
+        Ok(())
+    }
+
+    /// Validate the new price/quantity of a resting order being amended
+    pub fn validate_amend(&self, order: &Order, new_price: Price, new_quantity: Quantity) -> Result<()> {
+        if new_price != order.price {
+            self.validate_price(new_price)?;
+            self.check_band(new_price, self.config.price_bands.limit_band_bps)?;
+        }
+        self.validate_quantity(new_quantity)?;
+
+        // Cannot amend below what has already traded
+        if new_quantity <= order.filled {
+            return Err(Error::InvalidQuantity);
+        }
+
+        Ok(())
     }
 
     fn validate_price(&self, price: Price) -> Result<()> {
@@ -97,6 +138,52 @@ impl OrderValidator {
                     return Err(Error::LimitOrderRequiresPrice);
                 }
             }
+            OrderType::StopMarket => {
+                if order.post_only {
+                    return Err(Error::MarketOrderCannotBePostOnly);
+                }
+                if order.slippage_limit.is_none() {
+                    return Err(Error::MarketOrderRequiresSlippageLimit);
+                }
+            }
+            OrderType::StopLimit => {
+                if order.price.is_none() {
+                    return Err(Error::LimitOrderRequiresPrice);
+                }
+            }
+            OrderType::TrailingStop => {
+                if order.post_only {
+                    return Err(Error::MarketOrderCannotBePostOnly);
+                }
+                if order.slippage_limit.is_none() {
+                    return Err(Error::MarketOrderRequiresSlippageLimit);
+                }
+                if !order.trailing_offset.is_some_and(|offset| offset.is_valid()) {
+                    return Err(Error::TrailingStopRequiresOffset);
+                }
+            }
+        }
+
+        if order.order_type.is_stop() && order.trigger_price.is_none_or(|p| p <= Price::zero()) {
+            return Err(Error::StopOrderRequiresTriggerPrice);
+        }
+
+        // A minimum fill only applies to IOC, within the order's own quantity and lot size
+        if let Some(min_fill) = order.min_fill_quantity
+            && (order.time_in_force != TimeInForce::IOC
+                || min_fill <= Quantity::zero()
+                || min_fill > order.quantity
+                || min_fill.raw_value() % self.config.lot_size.raw_value() != 0)
+        {
+            return Err(Error::InvalidMinFillQuantity);
+        }
+
+        // GTD only makes sense for orders that can rest, and must not be already expired
+        if let Some(expires_at) = order.time_in_force.expires_at() {
+            let rests = matches!(order.order_type, OrderType::Limit | OrderType::StopLimit);
+            if !rests || expires_at <= order.base.timestamp {
+                return Err(Error::InvalidExpiry);
+            }
         }
 
         Ok(())
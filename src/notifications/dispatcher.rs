@@ -0,0 +1,257 @@
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tokio::sync::{broadcast, mpsc, RwLock};
+use uuid::Uuid;
+use crate::api::websocket::WsEvent;
+use crate::config::NotificationConfig;
+use crate::error::{Error, Result};
+use crate::events::trade::ExecutionReport;
+use crate::notifications::notification::{Notification, NotificationKind};
+use crate::notifications::preferences::{DeliveryChannel, NotificationPreferences};
+use crate::observability::metrics::{NOTIFICATIONS_DELIVERED, NOTIFICATIONS_DROPPED};
+use crate::types::ids::UserId;
+use crate::types::quantity::Quantity;
+use crate::types::timestamp::Timestamp;
+
+/// One delivery attempt, appended to the audit log
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DeliveryRecord {
+    pub notification_id: Uuid,
+    pub user_id: UserId,
+    pub kind: NotificationKind,
+    pub channel: String,
+    pub attempt: u32,
+    pub delivered: bool,
+    pub error: Option<String>,
+    pub at: Timestamp,
+}
+
+/// Append-only JSONL record of every delivery attempt
+struct DeliveryAudit {
+    path: PathBuf,
+    lock: Mutex<()>,
+}
+
+impl DeliveryAudit {
+    fn record(&self, record: &DeliveryRecord) {
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+
+        let result = (|| -> Result<()> {
+            if let Some(dir) = self.path.parent() {
+                std::fs::create_dir_all(dir).map_err(Error::IoError)?;
+            }
+            let mut line = serde_json::to_vec(record)
+                .map_err(|e| Error::SerializationError(e.to_string()))?;
+            line.push(b'\n');
+
+            let mut file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.path)
+                .map_err(Error::IoError)?;
+            file.write_all(&line).map_err(Error::IoError)
+        })();
+
+        if let Err(e) = result {
+            tracing::error!("Failed to write notification audit record: {:?}", e);
+        }
+    }
+}
+
+/// Handle the engine uses to queue notifications; never blocks
+#[derive(Clone)]
+pub struct NotificationSender {
+    tx: mpsc::Sender<Notification>,
+    preferences: Arc<RwLock<NotificationPreferences>>,
+    large_fill_quantity: Quantity,
+}
+
+impl NotificationSender {
+    pub fn notify(&self, notification: Notification) {
+        if self.tx.try_send(notification).is_err() {
+            NOTIFICATIONS_DROPPED.inc();
+            tracing::warn!("Notification queue full, notification dropped");
+        }
+    }
+
+    /// Notify a fill at or above the user's large-fill threshold
+    pub fn notify_fill(&self, report: &ExecutionReport) {
+        // Never wait on the preferences lock: fall back to the configured threshold
+        let threshold = match self.preferences.try_read() {
+            Ok(preferences) => preferences.large_fill_quantity(&report.user_id, self.large_fill_quantity),
+            Err(_) => self.large_fill_quantity,
+        };
+
+        if report.fill_quantity > Quantity::zero() && report.fill_quantity >= threshold {
+            self.notify(Notification::large_fill(report));
+        }
+    }
+}
+
+/// Notification delivery
+///
+/// ## Delivery
+/// - Each notification goes to every channel the user enabled for its kind
+/// - Channels are delivered independently; a slow webhook never holds up
+///   the WebSocket push or other users
+/// - Failed attempts are retried up to `max_attempts` with exponential
+///   backoff from `initial_backoff_ms`, capped at `max_backoff_ms`
+///
+/// ## Audit
+/// - Every attempt, successful or not, is appended to `audit_path`
+pub struct NotificationDispatcher {
+    rx: mpsc::Receiver<Notification>,
+    preferences: Arc<RwLock<NotificationPreferences>>,
+    user_stream: broadcast::Sender<WsEvent>,
+    client: reqwest::Client,
+    config: NotificationConfig,
+    audit: Arc<DeliveryAudit>,
+}
+
+impl NotificationDispatcher {
+    pub fn new(
+        config: &NotificationConfig,
+        preferences: Arc<RwLock<NotificationPreferences>>,
+        user_stream: broadcast::Sender<WsEvent>,
+    ) -> Result<(NotificationSender, NotificationDispatcher)> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_millis(config.request_timeout_ms))
+            .build()
+            .map_err(|e| Error::ConfigError(format!("Notification HTTP client: {}", e)))?;
+
+        let (tx, rx) = mpsc::channel(config.queue_capacity.max(1));
+        let sender = NotificationSender {
+            tx,
+            preferences: preferences.clone(),
+            large_fill_quantity: config.large_fill_quantity,
+        };
+        let dispatcher = NotificationDispatcher {
+            rx,
+            preferences,
+            user_stream,
+            client,
+            config: config.clone(),
+            audit: Arc::new(DeliveryAudit {
+                path: PathBuf::from(&config.audit_path),
+                lock: Mutex::new(()),
+            }),
+        };
+        Ok((sender, dispatcher))
+    }
+
+    /// Deliver queued notifications until every sender is dropped
+    pub async fn run(mut self) {
+        while let Some(notification) = self.rx.recv().await {
+            let channels = self.preferences.read().await
+                .channels_for(&notification.user_id, notification.kind);
+
+            for channel in channels {
+                let delivery = Delivery {
+                    client: self.client.clone(),
+                    user_stream: self.user_stream.clone(),
+                    audit: self.audit.clone(),
+                    config: self.config.clone(),
+                };
+                let notification = notification.clone();
+                tokio::spawn(async move {
+                    delivery.deliver_with_retry(&channel, &notification).await;
+                });
+            }
+        }
+    }
+}
+
+struct Delivery {
+    client: reqwest::Client,
+    user_stream: broadcast::Sender<WsEvent>,
+    audit: Arc<DeliveryAudit>,
+    config: NotificationConfig,
+}
+
+impl Delivery {
+    async fn deliver_with_retry(&self, channel: &DeliveryChannel, notification: &Notification) {
+        let max_attempts = self.config.max_attempts.max(1);
+
+        for attempt in 1..=max_attempts {
+            let result = self.deliver(channel, notification).await;
+
+            self.audit.record(&DeliveryRecord {
+                notification_id: notification.notification_id,
+                user_id: notification.user_id,
+                kind: notification.kind,
+                channel: channel.label().to_string(),
+                attempt,
+                delivered: result.is_ok(),
+                error: result.as_ref().err().map(|e| e.to_string()),
+                at: Timestamp::now(),
+            });
+
+            match result {
+                Ok(()) => {
+                    NOTIFICATIONS_DELIVERED.with_label_values(&[channel.label(), "delivered"]).inc();
+                    return;
+                }
+                Err(e) if attempt < max_attempts => {
+                    let backoff = self.backoff(attempt);
+                    tracing::debug!(
+                        "Notification {} via {} failed (attempt {}), retrying in {:?}: {}",
+                        notification.notification_id, channel.label(), attempt, backoff, e
+                    );
+                    tokio::time::sleep(backoff).await;
+                }
+                Err(e) => {
+                    NOTIFICATIONS_DELIVERED.with_label_values(&[channel.label(), "failed"]).inc();
+                    tracing::warn!(
+                        "Notification {} via {} failed after {} attempts: {}",
+                        notification.notification_id, channel.label(), attempt, e
+                    );
+                }
+            }
+        }
+    }
+
+    fn backoff(&self, attempt: u32) -> Duration {
+        let backoff_ms = self.config.initial_backoff_ms
+            .saturating_mul(1u64 << (attempt - 1).min(20))
+            .min(self.config.max_backoff_ms);
+        Duration::from_millis(backoff_ms)
+    }
+
+    async fn deliver(&self, channel: &DeliveryChannel, notification: &Notification) -> Result<()> {
+        match channel {
+            DeliveryChannel::WebSocket => {
+                self.user_stream.send(WsEvent::Notification {
+                    user_id: notification.user_id.to_string(),
+                    kind: notification.kind,
+                    subject: notification.subject.clone(),
+                    details: notification.details.clone(),
+                })
+                .map(|_| ())
+                .map_err(|_| Error::NotificationDeliveryFailed("no stream subscribers".to_string()))
+            }
+            DeliveryChannel::Webhook { url } => self.post(url, &json!(notification)).await,
+            DeliveryChannel::EmailWebhook { url, address } => {
+                self.post(url, &json!({
+                    "to": address,
+                    "subject": notification.subject,
+                    "details": notification.details,
+                })).await
+            }
+        }
+    }
+
+    async fn post(&self, url: &str, body: &serde_json::Value) -> Result<()> {
+        self.client.post(url)
+            .json(body)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map(|_| ())
+            .map_err(|e| Error::NotificationDeliveryFailed(e.to_string()))
+    }
+}
@@ -0,0 +1,3 @@
+pub mod notification;
+pub mod preferences;
+pub mod dispatcher;
@@ -0,0 +1,126 @@
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use uuid::Uuid;
+use crate::events::liquidation::{LiquidationEvent, MarginCallWarning};
+use crate::events::trade::ExecutionReport;
+use crate::types::balance::Balance;
+use crate::types::ids::UserId;
+use crate::types::timestamp::Timestamp;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationKind {
+    MarginCall,
+    Liquidation,
+    LargeFill,
+    WithdrawalStatus,
+}
+
+impl NotificationKind {
+    pub const ALL: [NotificationKind; 4] = [
+        NotificationKind::MarginCall,
+        NotificationKind::Liquidation,
+        NotificationKind::LargeFill,
+        NotificationKind::WithdrawalStatus,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            NotificationKind::MarginCall => "margin_call",
+            NotificationKind::Liquidation => "liquidation",
+            NotificationKind::LargeFill => "large_fill",
+            NotificationKind::WithdrawalStatus => "withdrawal_status",
+        }
+    }
+}
+
+/// Message to one user; `details` carries the kind-specific fields
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Notification {
+    pub notification_id: Uuid,
+    pub user_id: UserId,
+    pub kind: NotificationKind,
+    pub subject: String,
+    pub details: serde_json::Value,
+    pub created_at: Timestamp,
+}
+
+impl Notification {
+    pub fn new(user_id: UserId, kind: NotificationKind, subject: String, details: serde_json::Value) -> Self {
+        Notification {
+            notification_id: Uuid::new_v4(),
+            user_id,
+            kind,
+            subject,
+            details,
+            created_at: Timestamp::now(),
+        }
+    }
+
+    pub fn liquidation(event: &LiquidationEvent) -> Self {
+        Notification::new(
+            event.user_id,
+            NotificationKind::Liquidation,
+            format!("Position liquidated: {} at {}", event.liquidated_size.to_f64(), event.liquidation_price.to_f64()),
+            json!({
+                "liquidation_id": event.liquidation_id.to_string(),
+                "liquidated_size": event.liquidated_size.to_i64(),
+                "remaining_size": event.remaining_size.to_i64(),
+                "liquidation_price": event.liquidation_price.to_i64(),
+                "insurance_fund_loss": event.insurance_fund_loss.to_i64(),
+                "penalty": event.penalty.to_i64(),
+            }),
+        )
+    }
+
+    pub fn margin_call(warning: &MarginCallWarning) -> Self {
+        Notification::new(
+            warning.user_id,
+            NotificationKind::MarginCall,
+            format!("Margin call: margin ratio {:.2} at mark {}", warning.margin_ratio.to_f64(), warning.mark_price.to_f64()),
+            json!({
+                "position_side": warning.position_side,
+                "margin_ratio": warning.margin_ratio.to_f64(),
+                "equity": warning.equity.to_i64(),
+                "maintenance_margin": warning.maintenance_margin.to_i64(),
+                "mark_price": warning.mark_price.to_i64(),
+            }),
+        )
+    }
+
+    pub fn large_fill(report: &ExecutionReport) -> Self {
+        Notification::new(
+            report.user_id,
+            NotificationKind::LargeFill,
+            format!(
+                "Order {} filled {} at {}",
+                report.order_id, report.fill_quantity.to_f64(), report.average_price.to_f64()
+            ),
+            json!({
+                "order_id": report.order_id.to_string(),
+                "side": report.side,
+                "liquidity": report.liquidity,
+                "fill_quantity": report.fill_quantity.to_i64(),
+                "average_price": report.average_price.to_i64(),
+                "fees": report.fees.to_i64(),
+                "status": report.status,
+            }),
+        )
+    }
+
+    /// Withdrawal processed (`rejection` None) or refused
+    pub fn withdrawal(user_id: UserId, amount: Balance, reference_id: Option<&str>, rejection: Option<String>) -> Self {
+        let status = if rejection.is_some() { "rejected" } else { "completed" };
+        Notification::new(
+            user_id,
+            NotificationKind::WithdrawalStatus,
+            format!("Withdrawal of {} {}", amount.to_f64(), status),
+            json!({
+                "amount": amount.to_i64(),
+                "reference_id": reference_id,
+                "status": status,
+                "reason": rejection,
+            }),
+        )
+    }
+}
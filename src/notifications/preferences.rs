@@ -0,0 +1,147 @@
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+use crate::error::{Error, Result};
+use crate::notifications::notification::NotificationKind;
+use crate::types::ids::UserId;
+use crate::types::quantity::Quantity;
+
+/// Where a user's notifications are delivered
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DeliveryChannel {
+    /// Email relay: receives `{to, subject, details}` and sends the email
+    EmailWebhook { url: String, address: String },
+    /// The notification JSON is posted as-is
+    Webhook { url: String },
+    /// Pushed to the user stream
+    WebSocket,
+}
+
+impl DeliveryChannel {
+    pub fn label(&self) -> &'static str {
+        match self {
+            DeliveryChannel::EmailWebhook { .. } => "email_webhook",
+            DeliveryChannel::Webhook { .. } => "webhook",
+            DeliveryChannel::WebSocket => "websocket",
+        }
+    }
+
+    fn validate(&self) -> Result<()> {
+        let valid = match self {
+            DeliveryChannel::EmailWebhook { url, address } => is_http_url(url) && address.contains('@'),
+            DeliveryChannel::Webhook { url } => is_http_url(url),
+            DeliveryChannel::WebSocket => true,
+        };
+        if !valid {
+            return Err(Error::ConfigError(format!("Invalid {} notification channel", self.label())));
+        }
+        Ok(())
+    }
+}
+
+fn is_http_url(url: &str) -> bool {
+    url.starts_with("https://") || url.starts_with("http://")
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct UserNotificationPreferences {
+    pub channels: Vec<DeliveryChannel>,
+    pub kinds: HashSet<NotificationKind>,       // Kinds the user wants
+    #[serde(default)]
+    pub large_fill_quantity: Option<Quantity>,  // Overrides the configured large-fill threshold
+}
+
+impl Default for UserNotificationPreferences {
+    fn default() -> Self {
+        UserNotificationPreferences {
+            channels: vec![DeliveryChannel::WebSocket],
+            kinds: NotificationKind::ALL.into_iter().collect(),
+            large_fill_quantity: None,
+        }
+    }
+}
+
+/// Per-user notification preferences
+///
+/// Users without saved preferences get every kind on the WebSocket stream.
+/// With a `path`, every change is written through to a JSON file that is
+/// loaded again on start.
+#[derive(Default)]
+pub struct NotificationPreferences {
+    users: HashMap<UserId, UserNotificationPreferences>,
+    path: Option<PathBuf>,
+}
+
+impl NotificationPreferences {
+    pub fn new() -> Self {
+        NotificationPreferences::default()
+    }
+
+    /// Load saved preferences; a missing file starts empty
+    pub fn load(path: &str) -> Result<Self> {
+        let path = PathBuf::from(path);
+        let users = match std::fs::read(&path) {
+            Ok(data) => serde_json::from_slice(&data)
+                .map_err(|e| Error::DeserializationError(e.to_string()))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => HashMap::new(),
+            Err(e) => return Err(Error::IoError(e)),
+        };
+
+        Ok(NotificationPreferences { users, path: Some(path) })
+    }
+
+    pub fn get(&self, user_id: &UserId) -> UserNotificationPreferences {
+        self.users.get(user_id).cloned().unwrap_or_default()
+    }
+
+    pub fn set(&mut self, user_id: UserId, preferences: UserNotificationPreferences) -> Result<()> {
+        for channel in &preferences.channels {
+            channel.validate()?;
+        }
+
+        let previous = self.users.insert(user_id, preferences);
+        if let Err(e) = self.save() {
+            // Keep memory and file in step
+            match previous {
+                Some(previous) => self.users.insert(user_id, previous),
+                None => self.users.remove(&user_id),
+            };
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// Channels a notification of `kind` goes to (empty if the user opted out)
+    pub fn channels_for(&self, user_id: &UserId, kind: NotificationKind) -> Vec<DeliveryChannel> {
+        let preferences = self.get(user_id);
+        if !preferences.kinds.contains(&kind) {
+            return Vec::new();
+        }
+        preferences.channels
+    }
+
+    /// Fill size from which `user_id` is notified
+    pub fn large_fill_quantity(&self, user_id: &UserId, default: Quantity) -> Quantity {
+        self.users.get(user_id)
+            .and_then(|preferences| preferences.large_fill_quantity)
+            .unwrap_or(default)
+    }
+
+    fn save(&self) -> Result<()> {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(Error::IoError)?;
+        }
+
+        let data = serde_json::to_vec_pretty(&self.users)
+            .map_err(|e| Error::SerializationError(e.to_string()))?;
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, data).map_err(Error::IoError)?;
+        std::fs::rename(&tmp, path).map_err(Error::IoError)?;
+        Ok(())
+    }
+}
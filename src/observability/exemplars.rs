@@ -0,0 +1,170 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Instant;
+use opentelemetry::trace::TraceContextExt;
+use prometheus::HistogramVec;
+use prometheus::core::{Collector, Metric};
+use prometheus::proto::{MetricFamily, MetricType};
+use crate::types::timestamp::Timestamp;
+
+pub const OPENMETRICS_CONTENT_TYPE: &str = "application/openmetrics-text; version=1.0.0; charset=utf-8";
+
+/// Histogram bucket bounds shared by the latency histograms that carry exemplars
+/// (`prometheus::DEFAULT_BUCKETS`, which they are registered with)
+const BUCKETS: &[f64] = prometheus::DEFAULT_BUCKETS;
+
+#[derive(Clone, Debug)]
+struct Exemplar {
+    trace_id: String,
+    value: f64,
+    at: Timestamp,
+}
+
+/// Latest exemplar per (histogram, label pairs sorted by name, bucket upper bound)
+type ExemplarKey = (String, Vec<(String, String)>, String);
+
+lazy_static::lazy_static! {
+    static ref EXEMPLARS: Mutex<HashMap<ExemplarKey, Exemplar>> = Mutex::new(HashMap::new());
+}
+
+/// Trace id of the active OpenTelemetry span, if tracing is exporting one
+pub fn current_trace_id() -> Option<String> {
+    let context = opentelemetry::Context::current();
+    let span = context.span();
+    let span_context = span.span_context();
+    span_context.is_valid().then(|| format!("{:032x}", span_context.trace_id()))
+}
+
+/// Observe `value` and keep it as its bucket's exemplar when a trace is active
+///
+/// Each bucket keeps only its most recent exemplar, so slow buckets point at
+/// a recent slow trace.
+pub fn observe_with_exemplar(histogram: &HistogramVec, labels: &[&str], value: f64) {
+    let histogram = histogram.with_label_values(labels);
+    histogram.observe(value);
+
+    let trace_id = match current_trace_id() {
+        Some(trace_id) => trace_id,
+        None => return,
+    };
+    let le = BUCKETS.iter()
+        .find(|bound| value <= **bound)
+        .map_or("+Inf".to_string(), |bound| bound.to_string());
+
+    let name = histogram.desc().first().map_or(String::new(), |desc| desc.fq_name.clone());
+    let mut pairs: Vec<(String, String)> = histogram.metric().get_label().iter()
+        .map(|pair| (pair.get_name().to_string(), pair.get_value().to_string()))
+        .collect();
+    pairs.sort();
+
+    let key = (name, pairs, le);
+    if let Ok(mut exemplars) = EXEMPLARS.lock() {
+        exemplars.insert(key, Exemplar { trace_id, value, at: Timestamp::now() });
+    }
+}
+
+/// Drop guard observing elapsed seconds through `observe_with_exemplar`
+pub struct ExemplarTimer {
+    histogram: &'static HistogramVec,
+    labels: Vec<String>,
+    started: Instant,
+}
+
+impl Drop for ExemplarTimer {
+    fn drop(&mut self) {
+        let labels: Vec<&str> = self.labels.iter().map(String::as_str).collect();
+        observe_with_exemplar(self.histogram, &labels, self.started.elapsed().as_secs_f64());
+    }
+}
+
+pub fn start_timer(histogram: &'static HistogramVec, labels: &[&str]) -> ExemplarTimer {
+    ExemplarTimer {
+        histogram,
+        labels: labels.iter().map(|label| label.to_string()).collect(),
+        started: Instant::now(),
+    }
+}
+
+/// OpenMetrics exposition of `families` with exemplars on histogram buckets
+///
+/// The text encoder output is rewritten: counter families drop the `_total`
+/// suffix from their `# TYPE`/`# HELP` names, bucket lines with a recorded
+/// exemplar get ` # {trace_id="..."} value timestamp`, and `# EOF` closes it.
+pub fn encode_openmetrics(families: &[MetricFamily]) -> String {
+    let exemplars = EXEMPLARS.lock().map(|e| e.clone()).unwrap_or_default();
+    let mut out = String::new();
+
+    for family in families {
+        let name = family.get_name();
+        let family_name = match family.get_field_type() {
+            MetricType::COUNTER => name.strip_suffix("_total").unwrap_or(name),
+            _ => name,
+        };
+        let is_histogram = family.get_field_type() == MetricType::HISTOGRAM;
+
+        out.push_str(&format!("# HELP {} {}\n", family_name, escape_help(family.get_help())));
+        out.push_str(&format!("# TYPE {} {}\n", family_name, type_name(family.get_field_type())));
+
+        for line in encode_family(family).lines().filter(|line| !line.starts_with('#')) {
+            out.push_str(line);
+            if is_histogram
+                && let Some(exemplar) = bucket_key(line, name).and_then(|key| exemplars.get(&key))
+            {
+                out.push_str(&format!(
+                    " # {{trace_id=\"{}\"}} {} {:.3}",
+                    exemplar.trace_id,
+                    exemplar.value,
+                    exemplar.at.physical as f64 / 1000.0,
+                ));
+            }
+            out.push('\n');
+        }
+    }
+
+    out.push_str("# EOF\n");
+    out
+}
+
+fn encode_family(family: &MetricFamily) -> String {
+    use prometheus::Encoder;
+
+    let mut buffer = Vec::new();
+    if prometheus::TextEncoder::new().encode(std::slice::from_ref(family), &mut buffer).is_err() {
+        return String::new();
+    }
+    String::from_utf8(buffer).unwrap_or_default()
+}
+
+/// Exemplar key for a `{name}_bucket{...,le="x"} n` sample line
+fn bucket_key(line: &str, name: &str) -> Option<ExemplarKey> {
+    let labels = line.strip_prefix(name)?.strip_prefix("_bucket{")?;
+    let labels = &labels[..labels.find('}')?];
+
+    let mut pairs = Vec::new();
+    let mut le = None;
+    for pair in labels.split("\",").map(|pair| pair.trim_end_matches('"')) {
+        let (label, value) = pair.split_once("=\"")?;
+        if label == "le" {
+            le = Some(value.to_string());
+        } else {
+            pairs.push((label.to_string(), value.to_string()));
+        }
+    }
+    pairs.sort();
+
+    Some((name.to_string(), pairs, le?))
+}
+
+fn type_name(metric_type: MetricType) -> &'static str {
+    match metric_type {
+        MetricType::COUNTER => "counter",
+        MetricType::GAUGE => "gauge",
+        MetricType::HISTOGRAM => "histogram",
+        MetricType::SUMMARY => "summary",
+        MetricType::UNTYPED => "unknown",
+    }
+}
+
+fn escape_help(help: &str) -> String {
+    help.replace('\\', "\\\\").replace('\n', "\\n")
+}
@@ -30,11 +30,54 @@ lazy_static! {
         "Total number of orders cancelled"
     ).unwrap();
 
+    pub static ref ORDERS_EXPIRED: IntCounter = register_int_counter!(
+        "perpinfra_orders_expired_total",
+        "GTD orders removed from the book at their expiry"
+    ).unwrap();
+
+    pub static ref STOP_ORDERS_TRIGGERED: IntCounterVec = register_int_counter_vec!(
+        "perpinfra_stop_orders_triggered_total",
+        "Stop orders released to the matcher by a mark price move",
+        &["order_type"]
+    ).unwrap();
+
+    pub static ref EVENTS_SKIPPED: IntCounterVec = register_int_counter_vec!(
+        "perpinfra_events_skipped_total",
+        "Total number of requests refused at ingress because their event type is disabled",
+        &["event_type"]
+    ).unwrap();
+
+    // Event log retention metrics
+    pub static ref EVENT_LOG_ARCHIVED_SEQUENCE: IntGauge = register_int_gauge!(
+        "perpinfra_event_log_archived_sequence",
+        "Highest event sequence archived outside the broker"
+    ).unwrap();
+
+    pub static ref LEDGER_ARCHIVED_SEGMENT: IntGauge = register_int_gauge!(
+        "perpinfra_ledger_archived_segment",
+        "Highest ledger segment exported to the archive"
+    ).unwrap();
+
+    pub static ref SNAPSHOTS_ARCHIVED: IntCounter = register_int_counter!(
+        "perpinfra_snapshots_archived_total",
+        "Snapshots exported to the archive before being pruned"
+    ).unwrap();
+
+    pub static ref EVENT_LOG_REQUIRED_RETENTION: IntGauge = register_int_gauge!(
+        "perpinfra_event_log_required_retention_seconds",
+        "Broker retention required to keep every needed event replayable"
+    ).unwrap();
+
     pub static ref TRADES_PROCESSED: IntCounter = register_int_counter!(
         "perpinfra_trades_processed_total",
         "Total number of trades processed by event processor"
     ).unwrap();
 
+    pub static ref TRADES_DUPLICATE_SKIPPED: IntCounter = register_int_counter!(
+        "perpinfra_trades_duplicate_skipped_total",
+        "Trade events skipped because the trade was already settled"
+    ).unwrap();
+
     pub static ref FUNDING_EVENTS_PROCESSED: IntCounter = register_int_counter!(
         "perpinfra_funding_events_processed_total",
         "Total number of funding events processed"
@@ -50,6 +93,11 @@ lazy_static! {
         "Total number of withdrawals processed"
     ).unwrap();
 
+    pub static ref TRANSFERS_PROCESSED: IntCounter = register_int_counter!(
+        "perpinfra_transfers_processed_total",
+        "Total number of internal transfers processed"
+    ).unwrap();
+
     pub static ref VOLUME_TRADED: Counter = register_counter!(
         "perpinfra_volume_traded_total",
         "Total volume traded"
@@ -65,6 +113,11 @@ lazy_static! {
         "Total withdrawal volume"
     ).unwrap();
 
+    pub static ref TRANSFER_VOLUME: Counter = register_counter!(
+        "perpinfra_transfer_volume_total",
+        "Total internal transfer volume"
+    ).unwrap();
+
     // Trade metrics
     pub static ref TRADES_EXECUTED: IntCounter = register_int_counter!(
         "perpinfra_trades_executed_total",
@@ -82,6 +135,11 @@ lazy_static! {
         &["order_type"]
     ).unwrap();
 
+    pub static ref EVENT_PROCESSING_LATENCY: HistogramVec = register_histogram_vec!(
+        HistogramOpts::new("perpinfra_event_processing_seconds", "Time to apply one event in the event processor"),
+        &["event_type"]
+    ).unwrap();
+
     // Liquidation metrics
     pub static ref LIQUIDATIONS_EXECUTED: IntCounterVec = register_int_counter_vec!(
         "perpinfra_liquidations_executed_total",
@@ -94,12 +152,105 @@ lazy_static! {
         "Total liquidation volume in USD"
     ).unwrap();
 
+    pub static ref NOTIFICATIONS_DELIVERED: IntCounterVec = register_int_counter_vec!(
+        "perpinfra_notifications_total",
+        "Notification deliveries by channel and outcome",
+        &["channel", "outcome"]  // outcome: "delivered" or "failed" (after all retries)
+    ).unwrap();
+
+    pub static ref NOTIFICATIONS_DROPPED: IntCounter = register_int_counter!(
+        "perpinfra_notifications_dropped_total",
+        "Notifications dropped because the delivery queue was full"
+    ).unwrap();
+
+    pub static ref MARGIN_CALLS: IntCounter = register_int_counter!(
+        "perpinfra_margin_calls_total",
+        "Margin call warnings sent to accounts close to liquidation"
+    ).unwrap();
+
+    pub static ref LIQUIDATIONS_RESTORED: IntCounter = register_int_counter!(
+        "perpinfra_liquidations_restored_total",
+        "Sliced liquidations ended early because the account's margin was restored"
+    ).unwrap();
+
+    pub static ref LIQUIDATION_SLICES_PENDING: IntGauge = register_int_gauge!(
+        "perpinfra_liquidation_slices_pending",
+        "Liquidations with child orders still to send"
+    ).unwrap();
+
+    pub static ref LIQUIDATION_TRIGGERS_SUPPRESSED: IntCounter = register_int_counter!(
+        "perpinfra_liquidation_triggers_suppressed_total",
+        "Liquidation triggers not sent because an earlier one for the same leg was still pending"
+    ).unwrap();
+
+    pub static ref INSURANCE_TAKEOVERS: IntCounter = register_int_counter!(
+        "perpinfra_liquidation_insurance_takeovers_total",
+        "Liquidations the insurance fund took over at bankruptcy price for lack of liquidity"
+    ).unwrap();
+
+    pub static ref CROSSED_BOOK_DETECTED: IntCounterVec = register_int_counter_vec!(
+        "perpinfra_crossed_book_detected_total",
+        "Times the book was found crossed or locked after a mutation",
+        &["kind"]  // "crossed" or "locked"
+    ).unwrap();
+
+    pub static ref MATCHING_QUEUE_FULL: IntCounter = register_int_counter!(
+        "perpinfra_matching_queue_full_total",
+        "Commands that found the matching core queue full and had to wait"
+    ).unwrap();
+
+    pub static ref MATCHING_CORE_EPOCH: IntGauge = register_int_gauge!(
+        "perpinfra_matching_core_epoch",
+        "Epoch of the last book view published by the matching core"
+    ).unwrap();
+
+    pub static ref REALIZED_VOLATILITY: Gauge = register_gauge!(
+        "perpinfra_index_realized_volatility",
+        "Annualized realized volatility of the index price over the rolling window"
+    ).unwrap();
+
+    pub static ref REALIZED_VARIANCE: Gauge = register_gauge!(
+        "perpinfra_index_realized_variance",
+        "Mean squared log return of the index price per sample"
+    ).unwrap();
+
+    pub static ref PRICE_THRESHOLD_SCALE: Gauge = register_gauge!(
+        "perpinfra_price_threshold_scale",
+        "Volatility multiplier applied to the outlier and circuit breaker thresholds"
+    ).unwrap();
+
     // Insurance fund metrics
     pub static ref INSURANCE_FUND_BALANCE: IntGauge = register_int_gauge!(
         "perpinfra_insurance_fund_balance",
         "Current insurance fund balance"
     ).unwrap();
 
+    pub static ref INSURANCE_FUND_CONTRIBUTIONS: Counter = register_counter!(
+        "perpinfra_insurance_fund_contributions_total",
+        "Liquidation penalties paid into the insurance fund"
+    ).unwrap();
+
+    pub static ref SOCIALIZED_LOSSES: Counter = register_counter!(
+        "perpinfra_socialized_losses_total",
+        "Bankruptcy deficits beyond the insurance fund clawed back from period winners"
+    ).unwrap();
+
+    pub static ref ENGINE_INVENTORY: IntGauge = register_int_gauge!(
+        "perpinfra_liquidation_engine_inventory",
+        "Signed position the liquidation engine holds from insurance fund takeovers"
+    ).unwrap();
+
+    pub static ref ENGINE_INVENTORY_NOTIONAL: Gauge = register_gauge!(
+        "perpinfra_liquidation_engine_inventory_notional",
+        "Notional of the liquidation engine's inventory at mark"
+    ).unwrap();
+
+    pub static ref ENGINE_UNWIND_ORDERS: IntCounterVec = register_int_counter_vec!(
+        "perpinfra_liquidation_engine_unwind_orders_total",
+        "Unwind orders sent for the liquidation engine's inventory",
+        &["action"]  // "quote" or "cancel"
+    ).unwrap();
+
     // Price metrics
     pub static ref MARK_PRICE: GaugeVec = register_gauge_vec!(
         Opts::new("perpinfra_mark_price", "Current mark price"),
@@ -117,12 +268,77 @@ lazy_static! {
         &["source"]
     ).unwrap();
 
+    // Price connector metrics
+    pub static ref PRICE_CONNECTOR_MESSAGES: IntCounterVec = register_int_counter_vec!(
+        "perpinfra_price_connector_messages_total",
+        "Price updates received per connector",
+        &["source"]
+    ).unwrap();
+
+    pub static ref PRICE_CONNECTOR_PARSE_FAILURES: IntCounterVec = register_int_counter_vec!(
+        "perpinfra_price_connector_parse_failures_total",
+        "Price connector messages that failed to parse",
+        &["source"]
+    ).unwrap();
+
+    pub static ref INGRESS_QUEUE_DELAY: HistogramVec = register_histogram_vec!(
+        HistogramOpts::new("perpinfra_ingress_queue_delay_seconds", "Time from API receipt to event production")
+            .buckets(vec![0.0001, 0.00025, 0.0005, 0.001, 0.0025, 0.005, 0.01, 0.05]),
+        &["request"]
+    ).unwrap();
+
+    pub static ref INGRESS_QUEUE_DEPTH: IntGauge = register_int_gauge!(
+        "perpinfra_ingress_queue_depth",
+        "Order requests waiting in the ingress sequencer"
+    ).unwrap();
+
+    pub static ref INGRESS_OUT_OF_ORDER: IntCounter = register_int_counter!(
+        "perpinfra_ingress_out_of_order_total",
+        "Requests that reached the sequencer after a later-received one was released"
+    ).unwrap();
+
+    pub static ref PRICE_CONNECTOR_LATENCY: HistogramVec = register_histogram_vec!(
+        HistogramOpts::new(
+            "perpinfra_price_connector_latency_seconds",
+            "Exchange timestamp to receipt latency"
+        ).buckets(vec![0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0]),
+        &["source"]
+    ).unwrap();
+
+    pub static ref PRICE_CONNECTOR_RECONNECTS: IntCounterVec = register_int_counter_vec!(
+        "perpinfra_price_connector_reconnects_total",
+        "Price connector reconnections",
+        &["source"]
+    ).unwrap();
+
+    // Liquidity provider program metrics
+    pub static ref LP_QUOTE_UPTIME: GaugeVec = register_gauge_vec!(
+        Opts::new("perpinfra_lp_quote_uptime_ratio", "Fraction of last period's samples meeting LP obligations"),
+        &["maker"]
+    ).unwrap();
+
+    pub static ref LP_SPREAD_BPS: GaugeVec = register_gauge_vec!(
+        Opts::new("perpinfra_lp_spread_bps", "Average quoted spread of a market maker over the last period"),
+        &["maker"]
+    ).unwrap();
+
     // Funding metrics
     pub static ref FUNDING_RATE: GaugeVec = register_gauge_vec!(
         Opts::new("perpinfra_funding_rate", "Current funding rate"),
         &["market"]
     ).unwrap();
 
+    pub static ref PREDICTED_FUNDING_RATE: GaugeVec = register_gauge_vec!(
+        Opts::new("perpinfra_predicted_funding_rate", "Next funding rate at the running premium EMA"),
+        &["market"]
+    ).unwrap();
+
+    pub static ref FUNDING_RATE_CLAMPED: IntCounterVec = register_int_counter_vec!(
+        "perpinfra_funding_rate_clamped_total",
+        "Funding intervals settled at a clamped rate",
+        &["bound"]  // "cap" or "floor"
+    ).unwrap();
+
     // System metrics
     pub static ref CIRCUIT_BREAKER_STATUS: IntGaugeVec = register_int_gauge_vec!(
         "perpinfra_circuit_breaker_status",
@@ -146,6 +362,47 @@ lazy_static! {
         "perpinfra_order_book_spread",
         "Current bid-ask spread"
     ).unwrap();
+
+    // API / tenant metrics
+    pub static ref READ_MODEL_PUBLISH_SKIPPED: IntCounterVec = register_int_counter_vec!(
+        "perpinfra_read_model_publish_skipped_total",
+        "Read model publishes skipped because the engine held the state lock",
+        &["model"]
+    ).unwrap();
+
+    pub static ref RECOVERY_RUNS: IntCounterVec = register_int_counter_vec!(
+        "perpinfra_recovery_runs_total",
+        "Guarded recovery procedure runs by outcome",
+        &["outcome"]
+    ).unwrap();
+
+    pub static ref DEADMANS_SWITCH_TRIGGERED: IntCounter = register_int_counter!(
+        "perpinfra_deadmans_switch_triggered_total",
+        "Dead man's switches that expired and cancelled a user's orders"
+    ).unwrap();
+
+    pub static ref TWAP_SLICES_SUBMITTED: IntCounter = register_int_counter!(
+        "perpinfra_twap_slices_submitted_total",
+        "Child orders released by the TWAP slicing engine"
+    ).unwrap();
+
+    pub static ref API_REQUESTS: IntCounterVec = register_int_counter_vec!(
+        "perpinfra_api_requests_total",
+        "Total number of API requests",
+        &["tenant", "endpoint"]
+    ).unwrap();
+
+    pub static ref API_RATE_LIMITED: IntCounterVec = register_int_counter_vec!(
+        "perpinfra_api_rate_limited_total",
+        "Total number of API requests rejected by tenant quota",
+        &["tenant"]
+    ).unwrap();
+
+    pub static ref TENANT_GROSS_POSITION: IntGaugeVec = register_int_gauge_vec!(
+        "perpinfra_tenant_gross_position",
+        "Gross position size aggregated across a tenant's users",
+        &["tenant"]
+    ).unwrap();
 }
 
 /// Record order submission
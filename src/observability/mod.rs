@@ -1,3 +1,4 @@
 pub mod metrics;
 pub mod logging;
-pub mod tracing;
\ No newline at end of file
+pub mod tracing;
+pub mod exemplars;
\ No newline at end of file
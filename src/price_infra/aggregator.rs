@@ -12,6 +12,7 @@ pub struct PriceAggregator {
     sources: Vec<PriceSourceConfig>,
     staleness_threshold: Duration,
     outlier_threshold: f64,
+    threshold_scale: f64,  // Realized volatility multiplier (1.0 = unscaled)
     ema_alpha: f64,
     premium_ema: Price,
 }
@@ -22,11 +23,21 @@ impl PriceAggregator {
             sources,
             staleness_threshold: Duration::from_secs(5),
             outlier_threshold: 0.05,  // 5%
+            threshold_scale: 1.0,
             ema_alpha: 0.05,
             premium_ema: Price::zero(),
         }
     }
 
+    /// Widen (or tighten) the outlier threshold with the volatility regime
+    pub fn set_threshold_scale(&mut self, scale: f64) {
+        self.threshold_scale = scale;
+    }
+
+    fn effective_outlier_threshold(&self) -> f64 {
+        self.outlier_threshold * self.threshold_scale
+    }
+
     pub fn aggregate(
         &mut self,
         raw_prices: Vec<RawPriceUpdate>,
@@ -45,11 +56,12 @@ impl PriceAggregator {
         }
 
         // Step 2: Detect outliers
+        let outlier_threshold = self.effective_outlier_threshold();
         let median = self.calculate_median(&fresh_prices);
         let non_outliers: Vec<_> = fresh_prices.iter()
             .filter(|p| {
                 let deviation = (p.price - median).abs() / median;
-                deviation <= self.outlier_threshold
+                deviation <= outlier_threshold
             })
             .copied()
             .collect();
@@ -79,7 +91,7 @@ impl PriceAggregator {
                 let is_stale = now - p.received_at > self.staleness_threshold.as_millis() as u64;
                 let is_outlier = {
                     let deviation = (p.price - median).abs() / median;
-                    deviation > self.outlier_threshold
+                    deviation > outlier_threshold
                 };
 
                 SourcePrice {
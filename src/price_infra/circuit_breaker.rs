@@ -8,6 +8,7 @@ use crate::utils::helper::alert_operations_team_critical;
 pub struct PriceCircuitBreaker {
     active: Arc<AtomicBool>,
     price_movement_threshold: f64,
+    threshold_scale: f64,  // Realized volatility multiplier for the movement check
     mark_index_deviation_threshold: f64,
     last_price: Option<Price>,
 }
@@ -17,16 +18,22 @@ impl PriceCircuitBreaker {
         PriceCircuitBreaker {
             active: Arc::new(AtomicBool::new(false)),
             price_movement_threshold: 0.10,  // 10%
+            threshold_scale: 1.0,
             mark_index_deviation_threshold: 0.05,  // 5%
             last_price: None,
         }
     }
 
+    /// Scale the price movement threshold with the volatility regime
+    pub fn set_threshold_scale(&mut self, scale: f64) {
+        self.threshold_scale = scale;
+    }
+
     pub fn check(&mut self, snapshot: &PriceSnapshot) -> Result<()> {
         // Check 1: Price movement
         if let Some(last) = self.last_price {
             let movement = (snapshot.index_price - last).abs() / last.to_i64();
-            if movement.to_f64() > self.price_movement_threshold {
+            if movement.to_f64() > self.price_movement_threshold * self.threshold_scale {
                 self.trigger(CircuitBreakerReason::PriceMovement(movement.to_f64()))?;
             }
         }
@@ -4,7 +4,7 @@ use tokio_tungstenite::MaybeTlsStream;
 use tokio::net::TcpStream;
 use futures_util::StreamExt;
 use serde::Deserialize;
-use crate::price_infra::connectors::PriceConnector;
+use crate::price_infra::connectors::{record_connect, record_message, record_parse_failure, PriceConnector};
 use crate::price_infra::RawPriceUpdate;
 use crate::error::{Error, Result};
 use crate::utils::helper::current_timestamp_ms;
@@ -14,6 +14,7 @@ pub struct BinanceConnector {
     symbol: String,
     ws_url: String,
     stream: Option<WebSocketStream<MaybeTlsStream<TcpStream>>>,
+    has_connected: bool,
 }
 
 impl BinanceConnector {
@@ -23,6 +24,7 @@ impl BinanceConnector {
             symbol: symbol.to_string(),
             ws_url: format!("wss://stream.binance.com:9443/ws/{}@trade", symbol.to_lowercase()),
             stream: None,
+            has_connected: false,
         }
     }
 }
@@ -34,6 +36,8 @@ impl PriceConnector for BinanceConnector {
             .await
             .map_err(|e| Error::KafkaError(format!("WebSocket connection failed: {}", e)))?;
         self.stream = Some(ws_stream);
+        record_connect(&self.source_id, self.has_connected);
+        self.has_connected = true;
         tracing::info!("Connected to Binance: {}", self.symbol);
         Ok(())
     }
@@ -47,17 +51,26 @@ impl PriceConnector for BinanceConnector {
 
                 if let Message::Text(text) = msg {
                     let data: BinanceTradeData = serde_json::from_str(&text)
-                        .map_err(|e| Error::DeserializationError(e.to_string()))?;
+                        .map_err(|e| {
+                            record_parse_failure(&self.source_id);
+                            Error::DeserializationError(e.to_string())
+                        })?;
 
-                    return Ok(RawPriceUpdate {
+                    let update = RawPriceUpdate {
                         source_id: self.source_id.clone(),
                         symbol: self.symbol.clone(),
                         price: data.p.parse()
-                            .map_err(|_| Error::InvalidPrice)?,
+                            .map_err(|_| {
+                                record_parse_failure(&self.source_id);
+                                Error::InvalidPrice
+                            })?,
                         volume: None,
                         timestamp: data.T,
                         received_at: current_timestamp_ms(),
-                    });
+                    };
+
+                    record_message(&update);
+                    return Ok(update);
                 }
             } else {
                 return Err(Error::ConnectionClosed);
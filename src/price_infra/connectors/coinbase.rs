@@ -4,7 +4,7 @@ use tokio_tungstenite::MaybeTlsStream;
 use tokio::net::TcpStream;
 use futures_util::StreamExt;
 use serde::Deserialize;
-use crate::price_infra::connectors::PriceConnector;
+use crate::price_infra::connectors::{record_connect, record_message, record_parse_failure, PriceConnector};
 use crate::price_infra::RawPriceUpdate;
 use crate::error::{Error, Result};
 use crate::utils::helper::current_timestamp_ms;
@@ -14,6 +14,7 @@ pub struct CoinbaseConnector {
     symbol: String,
     ws_url: String,
     stream: Option<WebSocketStream<MaybeTlsStream<TcpStream>>>,
+    has_connected: bool,
 }
 
 impl CoinbaseConnector {
@@ -23,6 +24,7 @@ impl CoinbaseConnector {
             symbol: symbol.to_uppercase(),
             ws_url: "wss://ws-feed.exchange.coinbase.com".to_string(),
             stream: None,
+            has_connected: false,
         }
     }
 }
@@ -35,6 +37,8 @@ impl PriceConnector for CoinbaseConnector {
             .map_err(|e| Error::KafkaError(format!("WebSocket connection failed: {}", e)))?;
 
         self.stream = Some(ws_stream);
+        record_connect(&self.source_id, self.has_connected);
+        self.has_connected = true;
         tracing::info!("Connected to Coinbase: {}", self.symbol);
         Ok(())
     }
@@ -48,18 +52,27 @@ impl PriceConnector for CoinbaseConnector {
 
                 if let Message::Text(text) = msg {
                     let data: CoinbaseTickerData = serde_json::from_str(&text)
-                        .map_err(|e| Error::DeserializationError(e.to_string()))?;
+                        .map_err(|e| {
+                            record_parse_failure(&self.source_id);
+                            Error::DeserializationError(e.to_string())
+                        })?;
 
                     if data.type_field == "ticker" {
-                        return Ok(RawPriceUpdate {
+                        let update = RawPriceUpdate {
                             source_id: self.source_id.clone(),
                             symbol: self.symbol.clone(),
                             price: data.price.parse()
-                                .map_err(|_| Error::InvalidPrice)?,
+                                .map_err(|_| {
+                                    record_parse_failure(&self.source_id);
+                                    Error::InvalidPrice
+                                })?,
                             volume: data.volume_24h.and_then(|v| v.parse().ok()),
                             timestamp: data.time.parse().unwrap_or(0),
                             received_at: current_timestamp_ms(),
-                        });
+                        };
+
+                        record_message(&update);
+                        return Ok(update);
                     }
                 }
             } else {
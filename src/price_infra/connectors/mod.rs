@@ -3,8 +3,15 @@ pub mod coinbase;
 pub mod kraken;
 
 use async_trait::async_trait;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use crate::config::{PriceConnectorConfig, SymbolMapConfig};
 use crate::price_infra::RawPriceUpdate;
-use crate::error::Result;
+use crate::error::{Error, Result};
+use crate::observability::metrics::{
+    PRICE_CONNECTOR_LATENCY, PRICE_CONNECTOR_MESSAGES, PRICE_CONNECTOR_PARSE_FAILURES,
+    PRICE_CONNECTOR_RECONNECTS,
+};
 
 #[async_trait]
 pub trait PriceConnector: Send + Sync {
@@ -12,4 +19,160 @@ pub trait PriceConnector: Send + Sync {
     async fn next_price(&mut self) -> Result<RawPriceUpdate>;
     fn is_healthy(&self) -> bool;
     fn source_id(&self) -> &str;
-}
\ No newline at end of file
+}
+
+/// Create a connector for every source mapped to `market` in the symbol map
+pub fn connectors_for_market(
+    market: &str,
+    symbol_map: &SymbolMapConfig,
+) -> Result<Vec<Box<dyn PriceConnector>>> {
+    let sources = symbol_map.sources(market);
+    if sources.is_empty() {
+        return Err(Error::ConfigError(format!("No price sources mapped for market {}", market)));
+    }
+
+    sources.into_iter()
+        .map(|(source_id, symbol)| -> Result<Box<dyn PriceConnector>> {
+            match source_id {
+                "binance" => Ok(Box::new(binance::BinanceConnector::new(symbol))),
+                "coinbase" => Ok(Box::new(coinbase::CoinbaseConnector::new(symbol))),
+                "kraken" => Ok(Box::new(kraken::KrakenConnector::new(symbol))),
+                other => Err(Error::ConfigError(format!("Unknown price source: {}", other))),
+            }
+        })
+        .collect()
+}
+
+/// Forward a connected source's prices to `tx` until the receiver is dropped
+/// A closed or failed stream is reconnected with exponential backoff; an update
+/// that fails to parse is skipped without dropping the connection
+pub async fn run_connector(
+    mut connector: Box<dyn PriceConnector>,
+    tx: mpsc::Sender<RawPriceUpdate>,
+    config: PriceConnectorConfig,
+) {
+    loop {
+        match connector.next_price().await {
+            Ok(update) => {
+                if tx.send(update).await.is_err() {
+                    return;
+                }
+            }
+            Err(Error::DeserializationError(_)) | Err(Error::InvalidPrice) => continue,
+            Err(e) => {
+                tracing::warn!("Price connector {} disconnected: {:?}", connector.source_id(), e);
+                let mut backoff = Duration::from_millis(config.initial_backoff_ms);
+                loop {
+                    if tx.is_closed() {
+                        return;
+                    }
+                    tokio::time::sleep(backoff).await;
+                    match connector.connect().await {
+                        Ok(()) => break,
+                        Err(e) => {
+                            tracing::warn!("Price connector {} reconnect failed: {:?}", connector.source_id(), e);
+                            backoff = (backoff * 2).min(Duration::from_millis(config.max_backoff_ms));
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Record a parsed update: message rate and exchange-to-receipt latency
+/// Updates without an exchange timestamp only count toward the message rate
+pub(crate) fn record_message(update: &RawPriceUpdate) {
+    PRICE_CONNECTOR_MESSAGES.with_label_values(&[&update.source_id]).inc();
+
+    if update.timestamp > 0 {
+        let latency_ms = update.received_at.saturating_sub(update.timestamp);
+        PRICE_CONNECTOR_LATENCY
+            .with_label_values(&[&update.source_id])
+            .observe(latency_ms as f64 / 1000.0);
+    }
+}
+
+pub(crate) fn record_parse_failure(source_id: &str) {
+    PRICE_CONNECTOR_PARSE_FAILURES.with_label_values(&[source_id]).inc();
+}
+
+/// Count connects after the first one as reconnects
+pub(crate) fn record_connect(source_id: &str, has_connected: bool) {
+    if has_connected {
+        PRICE_CONNECTOR_RECONNECTS.with_label_values(&[source_id]).inc();
+        tracing::warn!("Price connector {} reconnected", source_id);
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+
+    /// Replays scripted results and counts connects like the exchange connectors do
+    struct ScriptedConnector {
+        results: VecDeque<Result<RawPriceUpdate>>,
+        has_connected: bool,
+    }
+
+    #[async_trait]
+    impl PriceConnector for ScriptedConnector {
+        async fn connect(&mut self) -> Result<()> {
+            record_connect("scripted", self.has_connected);
+            self.has_connected = true;
+            Ok(())
+        }
+
+        async fn next_price(&mut self) -> Result<RawPriceUpdate> {
+            match self.results.pop_front() {
+                Some(result) => result,
+                None => std::future::pending().await,
+            }
+        }
+
+        fn is_healthy(&self) -> bool {
+            true
+        }
+
+        fn source_id(&self) -> &str {
+            "scripted"
+        }
+    }
+
+    fn update(price: f64) -> Result<RawPriceUpdate> {
+        Ok(RawPriceUpdate {
+            source_id: "scripted".to_string(),
+            symbol: "BTCUSD".to_string(),
+            price,
+            volume: None,
+            timestamp: 0,
+            received_at: 0,
+        })
+    }
+
+    #[tokio::test]
+    async fn a_closed_stream_is_reconnected_and_counted() {
+        let mut connector = ScriptedConnector {
+            results: VecDeque::from([
+                update(50_000.0),
+                Err(Error::InvalidPrice),
+                Err(Error::ConnectionClosed),
+                update(50_001.0),
+            ]),
+            has_connected: false,
+        };
+        connector.connect().await.unwrap();
+
+        let reconnects = PRICE_CONNECTOR_RECONNECTS.with_label_values(&["scripted"]).get();
+        let config = PriceConnectorConfig { initial_backoff_ms: 1, max_backoff_ms: 1 };
+        let (tx, mut rx) = mpsc::channel(8);
+        let task = tokio::spawn(run_connector(Box::new(connector), tx, config));
+
+        assert_eq!(rx.recv().await.unwrap().price, 50_000.0);
+        // The bad update is skipped; the closed stream is reconnected once
+        assert_eq!(rx.recv().await.unwrap().price, 50_001.0);
+        assert_eq!(PRICE_CONNECTOR_RECONNECTS.with_label_values(&["scripted"]).get(), reconnects + 1);
+
+        task.abort();
+    }
+}
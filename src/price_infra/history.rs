@@ -0,0 +1,135 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use crate::config::VolatilityConfig;
+use crate::types::price::Price;
+use crate::types::timestamp::Timestamp;
+
+const YEAR_MS: f64 = 365.0 * 86_400_000.0;
+
+/// Realized volatility over the rolling window
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct VolatilityStats {
+    pub realized_variance: f64,    // Mean squared log return per sample
+    pub realized_volatility: f64,  // Annualized, e.g. 0.6 = 60%
+    pub samples: usize,
+    pub window_start: Timestamp,
+    pub window_end: Timestamp,
+    pub threshold_scale: f64,      // Multiplier applied to adaptive price thresholds
+}
+
+/// Rolling index price history for realized volatility
+///
+/// ## Sampling
+/// - At most one sample per `sample_interval`; faster ticks are dropped, so
+///   the estimate does not depend on the feed rate
+/// - Samples older than `window` are evicted on every update
+///
+/// ## Estimate
+/// - Realized variance is the mean squared log return between samples
+/// - Volatility annualizes it by the mean sample spacing
+/// - `threshold_scale()` is volatility over `baseline_volatility`, clamped to
+///   `[min_threshold_scale, max_threshold_scale]`; 1.0 until the window has
+///   `min_samples` samples
+pub struct IndexPriceHistory {
+    window: Duration,
+    sample_interval: Duration,
+    min_samples: usize,
+    baseline_volatility: f64,
+    min_threshold_scale: f64,
+    max_threshold_scale: f64,
+    samples: VecDeque<(Timestamp, f64)>,
+}
+
+impl IndexPriceHistory {
+    pub fn new(config: &VolatilityConfig) -> Self {
+        IndexPriceHistory {
+            window: Duration::from_secs(config.window_secs),
+            sample_interval: Duration::from_millis(config.sample_interval_ms),
+            min_samples: config.min_samples.max(2),
+            baseline_volatility: config.baseline_volatility,
+            min_threshold_scale: config.min_threshold_scale,
+            max_threshold_scale: config.max_threshold_scale.max(config.min_threshold_scale),
+            samples: VecDeque::new(),
+        }
+    }
+
+    /// Record an index price; true when it was kept as a sample
+    pub fn record(&mut self, index_price: Price, at: Timestamp) -> bool {
+        if index_price <= Price::zero() {
+            return false;
+        }
+        if let Some((last, _)) = self.samples.back()
+            && (at <= *last || at - *last < self.sample_interval)
+        {
+            return false;
+        }
+
+        self.samples.push_back((at, index_price.to_f64()));
+        while let Some((oldest, _)) = self.samples.front() {
+            if at - *oldest <= self.window {
+                break;
+            }
+            self.samples.pop_front();
+        }
+        true
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    /// Mean squared log return; None with fewer than two samples
+    pub fn realized_variance(&self) -> Option<f64> {
+        if self.samples.len() < 2 {
+            return None;
+        }
+
+        let sum: f64 = self.samples.iter()
+            .zip(self.samples.iter().skip(1))
+            .map(|((_, prev), (_, next))| (next / prev).ln().powi(2))
+            .sum();
+        Some(sum / (self.samples.len() - 1) as f64)
+    }
+
+    /// Annualized realized volatility
+    pub fn realized_volatility(&self) -> Option<f64> {
+        let variance = self.realized_variance()?;
+        let (first, _) = self.samples.front()?;
+        let (last, _) = self.samples.back()?;
+
+        let spacing_ms = (*last - *first).as_millis() as f64 / (self.samples.len() - 1) as f64;
+        if spacing_ms <= 0.0 {
+            return None;
+        }
+        Some((variance * YEAR_MS / spacing_ms).sqrt())
+    }
+
+    /// Multiplier for volatility-adaptive thresholds
+    pub fn threshold_scale(&self) -> f64 {
+        if self.samples.len() < self.min_samples || self.baseline_volatility <= 0.0 {
+            return 1.0;
+        }
+
+        match self.realized_volatility() {
+            Some(volatility) => (volatility / self.baseline_volatility)
+                .clamp(self.min_threshold_scale, self.max_threshold_scale),
+            None => 1.0,
+        }
+    }
+
+    pub fn stats(&self) -> Option<VolatilityStats> {
+        Some(VolatilityStats {
+            realized_variance: self.realized_variance()?,
+            realized_volatility: self.realized_volatility()?,
+            samples: self.samples.len(),
+            window_start: self.samples.front()?.0,
+            window_end: self.samples.back()?.0,
+            threshold_scale: self.threshold_scale(),
+        })
+    }
+}
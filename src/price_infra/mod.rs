@@ -1,6 +1,8 @@
 pub mod connectors;
 pub mod aggregator;
 pub mod circuit_breaker;
+pub mod oracle;
+pub mod history;
 
 use serde::{Deserialize, Serialize};
 use std::time::Duration;
@@ -30,4 +32,4 @@ pub struct RawPriceUpdate {
     pub volume: Option<f64>,
     pub timestamp: u64,
     pub received_at: u64,
-}
\ No newline at end of file
+}
@@ -0,0 +1,334 @@
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use std::sync::Arc;
+use serde::{Deserialize, Serialize};
+use crate::config::RiskReportConfig;
+use crate::config::market::ContractSpec;
+use crate::error::{Error, Result};
+use crate::invariants::checks::InvariantChecks;
+use crate::risk::pnl::PnLCalculator;
+use crate::settlement::balance_manager::BalanceManager;
+use crate::settlement::position_manager::PositionManager;
+use crate::types::balance::Balance;
+use crate::types::ids::{MarketId, UserId};
+use crate::types::position::Position;
+use crate::types::price::Price;
+use crate::types::quantity::Quantity;
+use crate::types::timestamp::Timestamp;
+
+const DAY_MS: u64 = 86_400_000;
+
+/// Liquidation and funding activity since the last report
+///
+/// Shared with the EventProcessor, which records as it applies events;
+/// `take()` reads and resets it when a report is compiled.
+#[derive(Default)]
+pub struct RiskTally {
+    liquidation_count: AtomicU64,
+    liquidation_volume: AtomicI64,  // Raw Quantity
+    funding_paid: AtomicI64,        // Raw Balance, total paid by payers
+}
+
+impl RiskTally {
+    pub fn new() -> Self {
+        RiskTally::default()
+    }
+
+    pub fn record_liquidation(&self, size: Quantity) {
+        self.liquidation_count.fetch_add(1, Ordering::Relaxed);
+        self.liquidation_volume.fetch_add(size.to_i64(), Ordering::Relaxed);
+    }
+
+    pub fn record_funding_paid(&self, amount: Balance) {
+        self.funding_paid.fetch_add(amount.to_i64().abs(), Ordering::Relaxed);
+    }
+
+    /// (liquidation count, liquidation volume, funding paid), reset to zero
+    pub fn take(&self) -> (u64, Quantity, Balance) {
+        (
+            self.liquidation_count.swap(0, Ordering::Relaxed),
+            Quantity::from_i64(self.liquidation_volume.swap(0, Ordering::Relaxed)),
+            Balance::from_i64(self.funding_paid.swap(0, Ordering::Relaxed)),
+        )
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PositionSummary {
+    pub user_id: UserId,
+    pub size: i64,
+    pub entry_price: Price,
+    pub notional: Balance,
+    pub unrealized_pnl: Balance,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct InvariantCheckResult {
+    pub invariant: String,
+    pub passed: bool,
+    pub error: Option<String>,
+}
+
+/// Exchange-wide daily risk report for the risk committee
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DailyRiskReport {
+    pub market_id: MarketId,
+    pub business_date: String,
+    pub cutoff: Timestamp,
+    pub mark_price: Price,
+    pub open_interest: Quantity,           // Sum of long sizes (= sum of short sizes)
+    pub long_accounts: u32,
+    pub short_accounts: u32,
+    pub largest_positions: Vec<PositionSummary>,
+    pub insurance_fund_balance: Balance,
+    pub insurance_fund_change: Balance,    // Since the previous report
+    pub liquidation_count: u64,
+    pub liquidation_volume: Quantity,
+    pub funding_paid: Balance,
+    pub invariant_checks: Vec<InvariantCheckResult>,
+}
+
+impl DailyRiskReport {
+    pub fn invariants_passed(&self) -> bool {
+        self.invariant_checks.iter().all(|check| check.passed)
+    }
+}
+
+/// Compiles the daily risk report at a fixed UTC cutoff
+///
+/// ## Storage
+/// - Written once to `reports_dir/{market_id}/{business_date}.json`
+/// - The latest report seeds the cutoff and insurance fund baseline after a
+///   restart; shipping the directory to object storage is left to the host
+pub struct DailyRiskReporter {
+    config: RiskReportConfig,
+    market_id: MarketId,
+    contract: ContractSpec,
+    tally: Arc<RiskTally>,
+    last_cutoff: u64,
+    last_insurance_fund: Option<Balance>,
+    latest: Option<DailyRiskReport>,
+}
+
+impl DailyRiskReporter {
+    pub fn new(config: RiskReportConfig, market_id: MarketId) -> Result<Self> {
+        let mut reporter = DailyRiskReporter {
+            config,
+            market_id,
+            contract: ContractSpec::default(),
+            tally: Arc::new(RiskTally::new()),
+            last_cutoff: 0,
+            last_insurance_fund: None,
+            latest: None,
+        };
+
+        reporter.last_cutoff = reporter.cutoff_at_or_before(Timestamp::now().physical);
+        if let Some(report) = load_latest_report(&reporter.market_dir())? {
+            reporter.last_cutoff = report.cutoff.physical;
+            reporter.last_insurance_fund = Some(report.insurance_fund_balance);
+            reporter.latest = Some(report);
+        }
+
+        Ok(reporter)
+    }
+
+    pub fn with_contract(mut self, contract: ContractSpec) -> Self {
+        self.contract = contract;
+        self
+    }
+
+    /// Share the tally the event processor feeds
+    pub fn with_tally(mut self, tally: Arc<RiskTally>) -> Self {
+        self.tally = tally;
+        self
+    }
+
+    pub fn latest(&self) -> Option<&DailyRiskReport> {
+        self.latest.as_ref()
+    }
+
+    fn cutoff_offset_ms(&self) -> u64 {
+        (self.config.cutoff_hour_utc as u64 * 3600 + self.config.cutoff_minute_utc as u64 * 60) * 1000
+    }
+
+    fn cutoff_at_or_before(&self, millis: u64) -> u64 {
+        let offset = self.cutoff_offset_ms();
+        (millis.saturating_sub(offset) / DAY_MS) * DAY_MS + offset
+    }
+
+    /// Cutoff to report if one has passed since the last report
+    pub fn due_cutoff(&self, now: Timestamp) -> Option<Timestamp> {
+        let cutoff = self.cutoff_at_or_before(now.physical);
+        (cutoff > self.last_cutoff).then(|| Timestamp::from_millis(cutoff))
+    }
+
+    fn business_date(cutoff: Timestamp) -> String {
+        // Day ending at the cutoff, so a midnight cutoff belongs to the previous date
+        chrono::DateTime::from_timestamp_millis(cutoff.physical as i64 - 1)
+            .map(|dt| dt.format("%Y-%m-%d").to_string())
+            .unwrap_or_default()
+    }
+
+    fn market_dir(&self) -> PathBuf {
+        report_dir(&self.config.reports_dir, self.market_id)
+    }
+
+    /// Compile, write and keep the report for `cutoff`
+    pub fn generate(
+        &mut self,
+        cutoff: Timestamp,
+        balance_manager: &BalanceManager,
+        position_manager: &PositionManager,
+        book_checks: Vec<(&'static str, Result<()>)>,  // From the matching core
+        insurance_fund_balance: Balance,
+        mark_price: Price,
+    ) -> Result<DailyRiskReport> {
+        let positions: Vec<Position> = position_manager.get_all_positions()
+            .into_iter()
+            .filter(|p| !p.is_flat())
+            .cloned()
+            .collect();
+
+        let open_interest = Quantity::from_i64(
+            positions.iter().filter(|p| p.is_long()).map(|p| p.size).sum()
+        );
+
+        let mut largest: Vec<&Position> = positions.iter().collect();
+        largest.sort_by_key(|p| (std::cmp::Reverse(p.size.abs()), p.user_id.0));
+        let largest_positions = largest.into_iter()
+            .take(self.config.top_positions)
+            .map(|p| Ok(PositionSummary {
+                user_id: p.user_id,
+                size: p.size,
+                entry_price: p.entry_price,
+                notional: self.contract.notional(p.abs_size(), mark_price)?,
+                unrealized_pnl: PnLCalculator::calculate_unrealized_pnl_for(&self.contract, p, mark_price)?,
+            }))
+            .collect::<Result<Vec<PositionSummary>>>()?;
+
+        let (liquidation_count, liquidation_volume, funding_paid) = self.tally.take();
+
+        let report = DailyRiskReport {
+            market_id: self.market_id,
+            business_date: Self::business_date(cutoff),
+            cutoff,
+            mark_price,
+            open_interest,
+            long_accounts: positions.iter().filter(|p| p.is_long()).count() as u32,
+            short_accounts: positions.iter().filter(|p| p.is_short()).count() as u32,
+            largest_positions,
+            insurance_fund_balance,
+            insurance_fund_change: insurance_fund_balance
+                - self.last_insurance_fund.unwrap_or(insurance_fund_balance),
+            liquidation_count,
+            liquidation_volume,
+            funding_paid,
+            invariant_checks: Self::run_invariant_checks(balance_manager, &positions, book_checks, mark_price),
+        };
+
+        self.write_report(&report)?;
+
+        if !report.invariants_passed() {
+            crate::utils::helper::alert_operations_team_critical(
+                format!("Daily risk report {} has failing invariant checks", report.business_date),
+            );
+        }
+        tracing::info!(
+            "Daily risk report {} compiled: OI={} liquidations={} funding_paid={}",
+            report.business_date, open_interest.to_f64(), liquidation_count, funding_paid.to_f64()
+        );
+
+        self.last_cutoff = cutoff.physical;
+        self.last_insurance_fund = Some(insurance_fund_balance);
+        self.latest = Some(report.clone());
+        Ok(report)
+    }
+
+    fn run_invariant_checks(
+        balance_manager: &BalanceManager,
+        positions: &[Position],
+        book_checks: Vec<(&'static str, Result<()>)>,
+        mark_price: Price,
+    ) -> Vec<InvariantCheckResult> {
+        let account_checks = [
+            ("no_negative_balances", InvariantChecks::check_no_negative_balances(balance_manager)),
+            ("reserved_margin", InvariantChecks::check_reserved_margin(balance_manager)),
+            ("margin_requirements", InvariantChecks::check_margin_requirements(balance_manager, positions, mark_price)),
+        ];
+
+        book_checks.into_iter()
+            .chain(account_checks)
+            .map(|(invariant, result)| InvariantCheckResult {
+                invariant: invariant.to_string(),
+                passed: result.is_ok(),
+                error: result.err().map(|e| e.to_string()),
+            })
+            .collect()
+    }
+
+    fn write_report(&self, report: &DailyRiskReport) -> Result<()> {
+        let dir = self.market_dir();
+        std::fs::create_dir_all(&dir).map_err(Error::IoError)?;
+
+        let data = serde_json::to_vec_pretty(report)
+            .map_err(|e| Error::SerializationError(e.to_string()))?;
+
+        // create_new: a day's report is never overwritten
+        let path = dir.join(format!("{}.json", report.business_date));
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+            .map_err(Error::IoError)?;
+        file.write_all(&data).map_err(Error::IoError)?;
+        file.sync_all().map_err(Error::IoError)?;
+
+        Ok(())
+    }
+}
+
+fn report_dir(reports_dir: &str, market_id: MarketId) -> PathBuf {
+    PathBuf::from(reports_dir).join(market_id.to_string())
+}
+
+/// Report for one business date (`YYYY-MM-DD`), if it was written
+pub fn load_report(reports_dir: &str, market_id: MarketId, business_date: &str) -> Result<Option<DailyRiskReport>> {
+    // Dates only: keeps the lookup inside the market's directory
+    if business_date.len() != 10 || !business_date.chars().all(|c| c.is_ascii_digit() || c == '-') {
+        return Err(Error::ConfigError(format!("Invalid business date: {}", business_date)));
+    }
+
+    let path = report_dir(reports_dir, market_id).join(format!("{}.json", business_date));
+    match std::fs::read(&path) {
+        Ok(data) => serde_json::from_slice(&data)
+            .map(Some)
+            .map_err(|e| Error::DeserializationError(e.to_string())),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(Error::IoError(e)),
+    }
+}
+
+fn load_latest_report(dir: &Path) -> Result<Option<DailyRiskReport>> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return Ok(None),  // No reports yet
+    };
+
+    // File names are ISO dates, so lexical order is chronological
+    let latest = entries
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.extension().is_some_and(|ext| ext == "json"))
+        .max();
+
+    let path = match latest {
+        Some(path) => path,
+        None => return Ok(None),
+    };
+
+    let data = std::fs::read(&path).map_err(Error::IoError)?;
+    serde_json::from_slice(&data)
+        .map(Some)
+        .map_err(|e| Error::DeserializationError(e.to_string()))
+}
@@ -0,0 +1,200 @@
+use serde::Serialize;
+use crate::error::Result;
+use crate::risk::margin::MarginCalculator;
+use crate::risk::pnl::{FundingAccrual, PnLCalculator};
+use crate::types::balance::Balance;
+use crate::types::position::Position;
+use crate::types::price::Price;
+use crate::types::quantity::Quantity;
+
+/// Prices at which a set of legs backed by `collateral` is liquidated and
+/// at which it is bankrupt (None: never reached)
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub struct LiquidationPrices {
+    pub liquidation_price: Option<Price>,  // Collateral + uPnL falls below maintenance margin
+    pub bankruptcy_price: Option<Price>,   // Collateral + uPnL reaches zero
+}
+
+/// Searches are abandoned this many doublings above the reference price
+const MAX_DOUBLINGS: u32 = 20;
+
+/// Equity (collateral, uPnL and accrued funding) left over maintenance
+/// margin, with every leg valued at `price`; negative means the legs are
+/// liquidatable. Fails if the legs' value overflows at `price`
+///
+/// This is the rule `LiquidationDetector` applies, so prices found here
+/// match what the engine does.
+/// - Isolated leg: `collateral` is its bucket
+/// - Cross legs: `collateral` is the account balance less isolated buckets
+/// - Maintenance margin carries the concentration surcharge for each leg's
+///   share of `open_interest` (zero: none)
+pub fn maintenance_surplus(
+    calculator: &MarginCalculator,
+    legs: &[&Position],
+    collateral: Balance,
+    price: Price,
+    open_interest: Quantity,
+    funding: Option<&FundingAccrual>,
+) -> Result<Balance> {
+    legs.iter().try_fold(equity_at(calculator, legs, collateral, price, funding)?, |surplus, leg| {
+        surplus.checked_sub(calculator.calculate_concentrated_maintenance_margin(leg.abs_size(), price, open_interest))
+    })
+}
+
+/// Collateral plus uPnL and accrued funding with every leg valued at `price`
+pub fn equity_at(
+    calculator: &MarginCalculator,
+    legs: &[&Position],
+    collateral: Balance,
+    price: Price,
+    funding: Option<&FundingAccrual>,
+) -> Result<Balance> {
+    PnLCalculator::account_equity(calculator.contract(), collateral, legs, price, funding)
+}
+
+/// Liquidation and bankruptcy prices of `legs` backed by `collateral`
+///
+/// The adverse direction follows the legs' net size (longs are hurt by a
+/// falling price); fully hedged legs have neither price. Both are exact to
+/// one raw price unit:
+/// - Long: the highest price at which the condition holds
+/// - Short: the lowest price at which it holds
+///
+/// `reference_price` (usually mark) seeds the search for shorts, which is
+/// abandoned past 2^20 times it. A price at which the legs can't be valued
+/// without overflow counts as reached for a net short (it is far on the
+/// adverse side) and as not reached for a net long.
+pub fn liquidation_prices(
+    calculator: &MarginCalculator,
+    legs: &[&Position],
+    collateral: Balance,
+    reference_price: Price,
+    open_interest: Quantity,
+    funding: Option<&FundingAccrual>,
+) -> LiquidationPrices {
+    let net_size: i64 = legs.iter().map(|leg| leg.size).sum();
+    let overflowed = net_size < 0;
+    LiquidationPrices {
+        liquidation_price: boundary(net_size, reference_price, |price| {
+            maintenance_surplus(calculator, legs, collateral, price, open_interest, funding)
+                .map_or(overflowed, |surplus| surplus < Balance::zero())
+        }),
+        bankruptcy_price: boundary(net_size, reference_price, |price| {
+            equity_at(calculator, legs, collateral, price, funding)
+                .map_or(overflowed, |equity| equity <= Balance::zero())
+        }),
+    }
+}
+
+/// Prices of `position` as the liquidation detector sees them (see
+/// `leg_margin_basis`). `account_legs` are all of the account's legs
+pub fn leg_liquidation_prices(
+    calculator: &MarginCalculator,
+    position: &Position,
+    account_legs: &[&Position],
+    balance: Balance,
+    reference_price: Price,
+    open_interest: Quantity,
+    funding: Option<&FundingAccrual>,
+) -> LiquidationPrices {
+    let (legs, collateral) = leg_margin_basis(position, account_legs, balance);
+    liquidation_prices(calculator, &legs, collateral, reference_price, open_interest, funding)
+}
+
+/// `maintenance_surplus` of `position` as the liquidation detector sees it
+/// (see `leg_margin_basis`); negative means the leg is liquidatable
+pub fn leg_maintenance_surplus(
+    calculator: &MarginCalculator,
+    position: &Position,
+    account_legs: &[&Position],
+    balance: Balance,
+    price: Price,
+    open_interest: Quantity,
+    funding: Option<&FundingAccrual>,
+) -> Result<Balance> {
+    let (legs, collateral) = leg_margin_basis(position, account_legs, balance);
+    maintenance_surplus(calculator, &legs, collateral, price, open_interest, funding)
+}
+
+/// Legs margined together with `position`, and the collateral backing
+/// them: an isolated leg alone against its bucket, cross legs together
+/// against `balance` less the account's isolated buckets
+fn leg_margin_basis<'a>(position: &'a Position, account_legs: &[&'a Position], balance: Balance) -> (Vec<&'a Position>, Balance) {
+    if position.is_isolated() {
+        return (vec![position], position.isolated_margin);
+    }
+
+    let (isolated, cross): (Vec<&Position>, Vec<&Position>) = account_legs.iter()
+        .filter(|p| !p.is_flat())
+        .partition(|p| p.is_isolated());
+    let isolated_margin = isolated.iter().fold(Balance::zero(), |total, p| total + p.isolated_margin);
+
+    (cross, balance - isolated_margin)
+}
+
+/// Prices for one position margined at `leverage` from its entry price,
+/// i.e. backed by exactly its initial margin there (no concentration surcharge)
+pub fn liquidation_prices_at_leverage(
+    calculator: &MarginCalculator,
+    position: &Position,
+    leverage: f64,
+) -> LiquidationPrices {
+    let collateral = calculator.calculate_initial_margin(position.abs_size(), position.entry_price, Some(leverage));
+    liquidation_prices(calculator, &[position], collateral, position.entry_price, Quantity::zero(), None)
+}
+
+/// First price, moving against a position of `net_size`, at which `reached`
+/// holds; `reached` must be monotonic in that direction
+fn boundary(net_size: i64, reference_price: Price, reached: impl Fn(Price) -> bool) -> Option<Price> {
+    let min = Price::from_i64(1);
+    match net_size {
+        0 => None,
+        size if size > 0 => {
+            // Falling price: find the highest reached price
+            if !reached(min) {
+                return None;
+            }
+            let mut hi = reference_price.max(min);
+            let mut doublings = 0;
+            while reached(hi) {
+                if doublings == MAX_DOUBLINGS {
+                    return Some(hi);  // Liquidatable even far above the reference
+                }
+                hi = Price::from_i64(hi.to_i64().saturating_mul(2));
+                doublings += 1;
+            }
+            Some(bisect(min, hi, &reached))
+        }
+        _ => {
+            // Rising price: find the lowest reached price
+            if reached(min) {
+                return Some(min);
+            }
+            let mut hi = reference_price.max(min);
+            let mut doublings = 0;
+            while !reached(hi) {
+                if doublings == MAX_DOUBLINGS {
+                    return None;
+                }
+                hi = Price::from_i64(hi.to_i64().saturating_mul(2));
+                doublings += 1;
+            }
+            let lo = bisect(min, hi, &|price| !reached(price));
+            Some(Price::from_i64(lo.to_i64() + 1))
+        }
+    }
+}
+
+/// Last price in `[lo, hi)` for which `holds` is true, given it holds at
+/// `lo`, fails at `hi` and flips once in between
+fn bisect(mut lo: Price, mut hi: Price, holds: &impl Fn(Price) -> bool) -> Price {
+    while hi.to_i64() - lo.to_i64() > 1 {
+        let mid = Price::from_i64(lo.to_i64() + (hi.to_i64() - lo.to_i64()) / 2);
+        if holds(mid) {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+    lo
+}
@@ -1,26 +1,75 @@
-use crate::config::risk::RiskConfig;
+use crate::config::market::ContractSpec;
+use crate::config::risk::{RiskConfig, RiskTier};
 use crate::types::balance::Balance;
 use crate::types::price::Price;
 use crate::types::quantity::Quantity;
 use crate::types::ratio::Ratio;
 
+/// Position margin requirements
+///
+/// ## Tiers
+/// With `RiskConfig::tiers` set, the position's notional picks the bracket
+/// whose maintenance rate and max leverage apply. Maintenance margin is
+/// notional * rate - deduction, where each tier's deduction keeps the
+/// requirement continuous at bracket boundaries (no jump when a position
+/// grows past a cap). Without tiers the flat config rates apply.
+///
+/// ## Concentration
+/// With `RiskConfig::concentration` set, the `_concentrated` requirement
+/// scales maintenance margin up for a position holding a large share of
+/// open interest (see `ConcentrationConfig`).
+#[derive(Clone)]
 pub struct MarginCalculator {
     config: RiskConfig,
+    contract: ContractSpec,  // Margin is held in the contract's settlement currency
+    deductions: Vec<Balance>,  // Maintenance deduction per tier
 }
 
 impl MarginCalculator {
     pub fn new(config: RiskConfig) -> Self {
-        MarginCalculator { config }
+        let deductions = maintenance_deductions(&config.tiers);
+        MarginCalculator { config, contract: ContractSpec::default(), deductions }
+    }
+
+    pub fn with_contract(mut self, contract: ContractSpec) -> Self {
+        self.contract = contract;
+        self
+    }
+
+    pub fn contract(&self) -> &ContractSpec {
+        &self.contract
+    }
+
+    /// Risk tier for a position of `position_size` at `mark_price`; positions
+    /// past the last bracket use the last tier. None without tiers (flat rates)
+    pub fn tier_for(&self, position_size: Quantity, mark_price: Price) -> Option<&RiskTier> {
+        let notional = self.contract.saturating_notional(position_size, mark_price);
+        self.tier_index(notional).map(|i| &self.config.tiers[i])
+    }
+
+    /// Leverage allowed for a position of `position_size` at `mark_price`
+    pub fn max_leverage(&self, position_size: Quantity, mark_price: Price) -> f64 {
+        self.tier_for(position_size, mark_price)
+            .map_or(self.config.max_leverage, |tier| tier.max_leverage)
     }
 
     /// Calculate initial margin requirement
+    /// `leverage` is the account's chosen leverage, capped by the tier;
+    /// None margins at the cap
     pub fn calculate_initial_margin(
         &self,
         position_size: Quantity,
         mark_price: Price,
+        leverage: Option<f64>,
     ) -> Balance {
-        let notional = position_size * mark_price;
-        notional / Balance::from_f64(self.config.max_leverage)
+        let notional = self.contract.saturating_notional(position_size, mark_price);
+        notional / Balance::from_f64(self.effective_leverage(position_size, mark_price, leverage))
+    }
+
+    /// Chosen leverage, never above what the position's tier allows
+    pub fn effective_leverage(&self, position_size: Quantity, mark_price: Price, leverage: Option<f64>) -> f64 {
+        let cap = self.max_leverage(position_size, mark_price);
+        leverage.map_or(cap, |chosen| chosen.min(cap))
     }
 
     /// Calculate maintenance margin requirement
@@ -29,8 +78,54 @@ impl MarginCalculator {
         position_size: Quantity,
         mark_price: Price,
     ) -> Balance {
-        let notional = position_size * mark_price;
-        notional * Balance::from_f64(self.config.maintenance_margin_rate)
+        let notional = self.contract.saturating_notional(position_size, mark_price);
+        match self.tier_index(notional) {
+            Some(i) => {
+                let rate = self.config.tiers[i].maintenance_margin_rate;
+                notional * Balance::from_f64(rate) - self.deductions[i]
+            }
+            None => notional * Balance::from_f64(self.config.maintenance_margin_rate),
+        }
+    }
+
+    /// Maintenance margin with the concentration surcharge for a position of
+    /// `position_size` in a market with `open_interest` (sum of long sizes)
+    pub fn calculate_concentrated_maintenance_margin(
+        &self,
+        position_size: Quantity,
+        mark_price: Price,
+        open_interest: Quantity,
+    ) -> Balance {
+        let maintenance_margin = self.calculate_maintenance_margin(position_size, mark_price);
+        match self.concentration_multiplier(position_size, open_interest) {
+            multiplier if multiplier > 1.0 => maintenance_margin * Balance::from_f64(multiplier),
+            _ => maintenance_margin,
+        }
+    }
+
+    /// Maintenance margin multiplier for a position's share of open interest
+    /// (1 below the threshold, without concentration config or open interest)
+    pub fn concentration_multiplier(&self, position_size: Quantity, open_interest: Quantity) -> f64 {
+        let concentration = match &self.config.concentration {
+            Some(concentration) if open_interest > Quantity::zero() => concentration,
+            _ => return 1.0,
+        };
+        let share = (position_size.abs().to_f64() / open_interest.to_f64()).min(1.0);
+        if share <= concentration.threshold {
+            return 1.0;
+        }
+        1.0 + (concentration.max_multiplier - 1.0) * (share - concentration.threshold) / (1.0 - concentration.threshold)
+    }
+
+    fn tier_index(&self, notional: Balance) -> Option<usize> {
+        if self.config.tiers.is_empty() {
+            return None;
+        }
+        Some(
+            self.config.tiers.iter()
+                .position(|tier| notional <= tier.max_notional)
+                .unwrap_or(self.config.tiers.len() - 1)
+        )
     }
 
     /// Calculate margin ratio (for liquidation check)
@@ -62,4 +157,19 @@ impl MarginCalculator {
         let equity = total_balance + unrealized_pnl;
         equity - reserved_margin
     }
-}
\ No newline at end of file
+}
+
+/// Deduction per tier that makes tiered maintenance margin continuous:
+/// d[0] = 0, d[i] = d[i-1] + cap[i-1] * (rate[i] - rate[i-1])
+fn maintenance_deductions(tiers: &[RiskTier]) -> Vec<Balance> {
+    let mut deductions = Vec::with_capacity(tiers.len());
+    let mut deduction = Balance::zero();
+    for (i, tier) in tiers.iter().enumerate() {
+        if let Some(prev) = i.checked_sub(1).map(|p| &tiers[p]) {
+            let step = tier.maintenance_margin_rate - prev.maintenance_margin_rate;
+            deduction = deduction + prev.max_notional * Balance::from_f64(step);
+        }
+        deductions.push(deduction);
+    }
+    deductions
+}
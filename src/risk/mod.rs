@@ -1,3 +1,8 @@
 pub mod pnl;
 pub mod margin;
-pub mod pre_trade_check;
\ No newline at end of file
+pub mod liquidation_price;
+pub mod pre_trade_check;
+pub mod portfolio;
+pub mod order_margin;
+pub mod withdrawal_check;
+pub mod daily_report;
\ No newline at end of file
@@ -0,0 +1,180 @@
+use std::collections::HashMap;
+use crate::error::Result;
+use crate::events::order::Side;
+use crate::interfaces::balance_provider::BalanceProvider;
+use crate::risk::margin::MarginCalculator;
+use crate::types::balance::Balance;
+use crate::types::ids::{OrderId, UserId};
+use crate::types::price::Price;
+use crate::types::quantity::Quantity;
+
+/// Initial margin held for a user's open orders, netted across sides
+///
+/// Bids and asks can't both fill against the same position, so only the
+/// side that would leave the larger position is charged, less the margin
+/// the current position already needs:
+///
+/// `IM(max(|position + bids|, |position - asks|)) - IM(|position|)`, floored at zero
+///
+/// Orders that only reduce the position therefore hold nothing. The book
+/// tracks every order holding margin (resting and untriggered stops) by
+/// unfilled quantity; after each add, cancel, amend or fill the caller
+/// `rebalance`s the user and the difference is reserved or released.
+pub struct OrderMarginBook {
+    orders: HashMap<OrderId, (UserId, Side, Quantity)>,  // Unfilled quantity per open order
+    exposure: HashMap<UserId, (Quantity, Quantity)>,     // (bids, asks) per user
+    counts: HashMap<UserId, usize>,                      // Open orders per user
+    reserved: HashMap<UserId, Balance>,                  // Currently held per user
+}
+
+impl Default for OrderMarginBook {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl OrderMarginBook {
+    pub fn new() -> Self {
+        OrderMarginBook {
+            orders: HashMap::new(),
+            exposure: HashMap::new(),
+            counts: HashMap::new(),
+            reserved: HashMap::new(),
+        }
+    }
+
+    /// Netted order margin for resting `bids` and `asks` against a net position
+    pub fn netted_margin(
+        calculator: &MarginCalculator,
+        position_size: i64,
+        bids: Quantity,
+        asks: Quantity,
+        mark_price: Price,
+        leverage: Option<f64>,
+    ) -> Balance {
+        let worst = (position_size + bids.to_i64()).abs().max((position_size - asks.to_i64()).abs());
+        let required = calculator.calculate_initial_margin(Quantity::from_i64(worst), mark_price, leverage)
+            - calculator.calculate_initial_margin(Quantity::from_i64(position_size.abs()), mark_price, leverage);
+        required.max(Balance::zero())
+    }
+
+    /// Resting (bids, asks) of a user
+    pub fn exposure(&self, user_id: &UserId) -> (Quantity, Quantity) {
+        self.exposure.get(user_id).copied().unwrap_or((Quantity::zero(), Quantity::zero()))
+    }
+
+    /// Number of open orders (resting and pending stops) a user has
+    pub fn open_orders(&self, user_id: &UserId) -> usize {
+        self.counts.get(user_id).copied().unwrap_or(0)
+    }
+
+    /// Users with open orders or order margin still held, in a stable order
+    pub fn users(&self) -> Vec<UserId> {
+        let mut users: Vec<UserId> = self.exposure.keys()
+            .chain(self.reserved.keys().filter(|user_id| !self.exposure.contains_key(user_id)))
+            .copied()
+            .collect();
+        users.sort_by_key(|user_id| user_id.0);
+        users
+    }
+
+    /// Order margin currently reserved for a user
+    pub fn reserved(&self, user_id: &UserId) -> Balance {
+        self.reserved.get(user_id).copied().unwrap_or_else(Balance::zero)
+    }
+
+    pub fn remaining(&self, order_id: &OrderId) -> Option<Quantity> {
+        self.orders.get(order_id).map(|(_, _, remaining)| *remaining)
+    }
+
+    /// Set an order's unfilled quantity (zero stops tracking it)
+    pub fn track(&mut self, order_id: OrderId, user_id: UserId, side: Side, remaining: Quantity) {
+        self.untrack(&order_id);
+        if remaining > Quantity::zero() {
+            self.orders.insert(order_id, (user_id, side, remaining));
+            *self.counts.entry(user_id).or_insert(0) += 1;
+            self.shift(user_id, side, remaining, true);
+        }
+    }
+
+    /// Reduce an order by a fill; returns its owner if it was tracked
+    pub fn fill(&mut self, order_id: &OrderId, quantity: Quantity) -> Option<UserId> {
+        let (user_id, side, remaining) = self.orders.get(order_id).copied()?;
+        self.track(*order_id, user_id, side, remaining - quantity.min(remaining));
+        Some(user_id)
+    }
+
+    /// Stop tracking an order; returns its owner if it was tracked
+    pub fn untrack(&mut self, order_id: &OrderId) -> Option<UserId> {
+        let (user_id, side, remaining) = self.orders.remove(order_id)?;
+        if let Some(count) = self.counts.get_mut(&user_id) {
+            *count -= 1;
+            if *count == 0 {
+                self.counts.remove(&user_id);
+            }
+        }
+        self.shift(user_id, side, remaining, false);
+        Some(user_id)
+    }
+
+    /// Margin to reserve (positive) or release (negative) to bring the
+    /// user's reservation to the netted requirement
+    pub fn shortfall(
+        &self,
+        user_id: &UserId,
+        position_size: i64,
+        calculator: &MarginCalculator,
+        mark_price: Price,
+        leverage: Option<f64>,
+    ) -> Balance {
+        let (bids, asks) = self.exposure(user_id);
+        Self::netted_margin(calculator, position_size, bids, asks, mark_price, leverage) - self.reserved(user_id)
+    }
+
+    /// Reserve or release the shortfall; returns the amount moved (positive reserved)
+    pub fn rebalance(
+        &mut self,
+        user_id: UserId,
+        position_size: i64,
+        calculator: &MarginCalculator,
+        mark_price: Price,
+        leverage: Option<f64>,
+        balance_provider: &mut dyn BalanceProvider,
+    ) -> Result<Balance> {
+        let delta = self.shortfall(&user_id, position_size, calculator, mark_price, leverage);
+        if delta > Balance::zero() {
+            balance_provider.reserve_margin(user_id, delta)?;
+        } else if delta < Balance::zero() {
+            balance_provider.release_margin(user_id, -delta)?;
+        }
+
+        let reserved = self.reserved(&user_id) + delta;
+        if reserved == Balance::zero() {
+            self.reserved.remove(&user_id);
+        } else {
+            self.reserved.insert(user_id, reserved);
+        }
+        Ok(delta)
+    }
+
+    /// Forget everything (before a restore rebuilds it)
+    pub fn clear(&mut self) {
+        self.orders.clear();
+        self.exposure.clear();
+        self.counts.clear();
+        self.reserved.clear();
+    }
+
+    fn shift(&mut self, user_id: UserId, side: Side, quantity: Quantity, add: bool) {
+        let exposure = self.exposure.entry(user_id).or_insert((Quantity::zero(), Quantity::zero()));
+        let total = match side {
+            Side::Buy => &mut exposure.0,
+            Side::Sell => &mut exposure.1,
+        };
+        *total = if add { *total + quantity } else { *total - quantity };
+
+        if *exposure == (Quantity::zero(), Quantity::zero()) {
+            self.exposure.remove(&user_id);
+        }
+    }
+}
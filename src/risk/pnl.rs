@@ -1,9 +1,34 @@
+use std::time::Duration;
+use crate::config::market::ContractSpec;
+use crate::error::{Error, Result};
 use crate::events::order::Side;
+use crate::funding::index::CumulativeFunding;
+use crate::funding::payment_calculator::FundingPaymentCalculator;
 use crate::types::balance::Balance;
+use crate::types::funding_rate::FundingRate;
 use crate::types::position::Position;
 use crate::types::price::Price;
 use crate::types::quantity::Quantity;
+use crate::types::rounding::RoundingPolicy;
+use crate::types::timestamp::Timestamp;
 
+/// Funding building up toward the next settlement, at the rate the current
+/// interval is tracking (e.g. predicted from the premium), plus settled
+/// intervals not yet collected from lazily settled positions
+#[derive(Clone, Copy, Debug)]
+pub struct FundingAccrual {
+    pub funding_rate: FundingRate,
+    pub funding_interval: Duration,
+    pub as_of: Timestamp,
+    pub cumulative: CumulativeFunding,  // Market's funding index and last settled interval
+}
+
+/// PnL in the contract's settlement currency
+/// The `_for` variants take the market's `ContractSpec`; the others assume
+/// a linear contract with multiplier 1
+///
+/// Values are computed in i128; a result that does not fit a balance fails
+/// with `Error::Overflow` rather than wrapping.
 pub struct PnLCalculator;
 
 impl PnLCalculator {
@@ -11,15 +36,71 @@ impl PnLCalculator {
     pub fn calculate_unrealized_pnl(
         position: &Position,
         mark_price: Price,
-    ) -> Balance {
+    ) -> Result<Balance> {
+        Self::calculate_unrealized_pnl_for(&ContractSpec::default(), position, mark_price)
+    }
+
+    pub fn calculate_unrealized_pnl_for(
+        contract: &ContractSpec,
+        position: &Position,
+        mark_price: Price,
+    ) -> Result<Balance> {
         if position.is_flat() {
-            return Balance::zero();
+            return Ok(Balance::zero());
         }
 
         // size is already signed
-        Balance::from_i64(
-            position.size * (mark_price.to_i64() - position.entry_price.to_i64())
-        )
+        contract.pnl(position.size, position.entry_price, mark_price)
+    }
+
+    /// Funding a position has accrued but not yet settled: the current
+    /// interval's payment, pro rata of the time since the later of
+    /// `last_funding_timestamp` and the last settled interval (at most one
+    /// interval), plus what it owes against the funding index. Positive =
+    /// receivable, negative = owed
+    pub fn calculate_accrued_funding(
+        contract: &ContractSpec,
+        position: &Position,
+        mark_price: Price,
+        accrual: &FundingAccrual,
+    ) -> Result<Balance> {
+        let payment = FundingPaymentCalculator::calculate_payment(
+            contract,
+            position,
+            mark_price,
+            accrual.funding_rate,
+            &RoundingPolicy::default(),
+        )?;
+        let current = FundingPaymentCalculator::prorate(
+            payment,
+            position.last_funding_timestamp.max(accrual.cumulative.last_funding),
+            accrual.as_of,
+            accrual.funding_interval,
+        )?;
+        current.checked_add(FundingPaymentCalculator::index_payment(position, accrual.cumulative.index)?)
+    }
+
+    /// Collateral plus uPnL of `positions` at `mark_price`, plus the funding
+    /// they have accrued since their last settlement
+    ///
+    /// Margin ratios built on this don't jump at the funding boundary: a
+    /// position owing funding is margined as if it had already paid it.
+    pub fn account_equity(
+        contract: &ContractSpec,
+        collateral: Balance,
+        positions: &[&Position],
+        mark_price: Price,
+        funding: Option<&FundingAccrual>,
+    ) -> Result<Balance> {
+        positions.iter().try_fold(collateral, |equity, position| {
+            let accrued = match funding {
+                Some(accrual) => Self::calculate_accrued_funding(contract, position, mark_price, accrual)?,
+                None => Balance::zero(),
+            };
+            equity
+                .checked_add(Self::calculate_unrealized_pnl_for(contract, position, mark_price)?)?
+                .checked_add(accrued)
+        })
     }
 
     /// Calculate realized PnL from a trade
@@ -28,7 +109,17 @@ impl PnLCalculator {
         trade_side: Side,
         trade_quantity: Quantity,
         trade_price: Price,
-    ) -> Balance {
+    ) -> Result<Balance> {
+        Self::calculate_realized_pnl_for(&ContractSpec::default(), position, trade_side, trade_quantity, trade_price)
+    }
+
+    pub fn calculate_realized_pnl_for(
+        contract: &ContractSpec,
+        position: &Position,
+        trade_side: Side,
+        trade_quantity: Quantity,
+        trade_price: Price,
+    ) -> Result<Balance> {
         // Only realize PnL if reducing position
         let is_reducing = match trade_side {
             Side::Buy => position.is_short(),
@@ -36,53 +127,65 @@ impl PnLCalculator {
         };
 
         if !is_reducing {
-            return Balance::zero();
+            return Ok(Balance::zero());
         }
 
         let close_qty = trade_quantity.to_i64().min(position.size.abs());
-        let pnl_per_unit = if position.is_long() {
-            trade_price.to_i64() - position.entry_price.to_i64()
-        } else {
-            position.entry_price.to_i64() - trade_price.to_i64()
-        };
+        let closed_size = if position.is_long() { close_qty } else { -close_qty };
 
-        Balance::from_i64(close_qty * pnl_per_unit)
+        contract.pnl(closed_size, position.entry_price, trade_price)
     }
 
-    /// Update position after trade
+    /// Update position after trade; returns the PnL the fill realized
+    /// On overflow the position is left as it was
     pub fn update_position(
         position: &mut Position,
         trade_side: Side,
         trade_quantity: Quantity,
         trade_price: Price,
-    ) {
+    ) -> Result<Balance> {
+        Self::update_position_for(&ContractSpec::default(), position, trade_side, trade_quantity, trade_price)
+    }
+
+    pub fn update_position_for(
+        contract: &ContractSpec,
+        position: &mut Position,
+        trade_side: Side,
+        trade_quantity: Quantity,
+        trade_price: Price,
+    ) -> Result<Balance> {
         let trade_size_signed = match trade_side {
             Side::Buy => trade_quantity.to_i64(),
             Side::Sell => -trade_quantity.to_i64(),
         };
 
-        let new_size = position.size + trade_size_signed;
+        let new_size = position.size.checked_add(trade_size_signed)
+            .ok_or_else(|| Error::Overflow { operation: "position size".to_string() })?;
 
         // Calculate realized PnL if reducing
-        let realized = Self::calculate_realized_pnl(position, trade_side, trade_quantity, trade_price);
-        position.realized_pnl = position.realized_pnl + realized;
+        let realized = Self::calculate_realized_pnl_for(contract, position, trade_side, trade_quantity, trade_price)?;
+        let realized_pnl = position.realized_pnl.checked_add(realized)?;
 
         // Update entry price if increasing or flipping
-        if (position.size >= 0 && new_size > position.size) ||
+        let entry_price = if (position.size >= 0 && new_size > position.size) ||
             (position.size <= 0 && new_size < position.size) {
             // Increasing position
-            let old_notional = position.size.abs() * position.entry_price.to_i64();
-            let new_notional = trade_quantity.to_i64() * trade_price.to_i64();
-            let total_size = position.size.abs() + trade_quantity.to_i64();
-
-            if total_size > 0 {
-                position.entry_price = Price::from_i64((old_notional + new_notional) / total_size);
-            }
+            contract.average_entry(
+                position.size,
+                position.entry_price,
+                trade_quantity.to_i64(),
+                trade_price,
+            )?
         } else if new_size == 0 {
             // Position closed
-            position.entry_price = Price::zero();
-        }
+            Price::zero()
+        } else {
+            position.entry_price
+        };
 
+        position.realized_pnl = realized_pnl;
+        position.entry_price = entry_price;
         position.size = new_size;
+        Ok(realized)
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,169 @@
+use std::collections::HashMap;
+use crate::config::market::ContractSpec;
+use crate::config::risk::RiskConfig;
+use crate::error::{Error, Result};
+use crate::events::order::{OrderSubmit, Side};
+use crate::matching::order_book::Order;
+use crate::risk::margin::MarginCalculator;
+use crate::risk::pnl::PnLCalculator;
+use crate::types::account::Account;
+use crate::types::balance::Balance;
+use crate::types::ids::MarketId;
+use crate::types::position::Position;
+use crate::types::price::Price;
+use crate::types::quantity::Quantity;
+
+/// One market's share of an account: its legs, its resting orders and the
+/// mark they are valued at
+pub struct MarketHoldings<'a> {
+    pub market_id: MarketId,
+    pub mark_price: Price,
+    pub leverage: Option<f64>,  // Account's chosen leverage in this market (None: tier cap)
+    pub positions: Vec<&'a Position>,
+    pub open_orders: Vec<&'a Order>,
+}
+
+/// Account-wide margin picture across every market it trades
+#[derive(Clone, Debug, PartialEq)]
+pub struct PortfolioSummary {
+    pub equity: Balance,               // Balance + cross uPnL - isolated buckets
+    pub unrealized_pnl: Balance,       // Cross positions only
+    pub initial_margin: Balance,       // Open positions
+    pub maintenance_margin: Balance,   // Open positions
+    pub open_order_margin: Balance,    // Worst case of resting orders filling
+}
+
+impl PortfolioSummary {
+    /// Initial margin the account needs if every resting order fills the bad way
+    pub fn worst_case_initial_margin(&self) -> Balance {
+        self.initial_margin + self.open_order_margin
+    }
+}
+
+/// Account-level risk check across all positions and open orders
+///
+/// `PreTradeRiskCheck` looks at one position; this one margins the whole
+/// account, so exposure in one market uses up collateral for the others.
+///
+/// ## Aggregation
+/// - Cross positions: initial and maintenance margin add up per leg (no
+///   netting between legs or markets); their uPnL counts toward equity
+/// - Isolated positions: carried by their own bucket, which is taken out
+///   of the account's equity
+/// - Open orders, per market: every order on the larger side fills while
+///   the other side does not (as in `PreTradeRiskCheck::check_batch`)
+pub struct PortfolioRiskCheck {
+    config: RiskConfig,
+    default_calculator: MarginCalculator,               // Markets not registered with `with_market`
+    calculators: HashMap<MarketId, MarginCalculator>,
+}
+
+impl PortfolioRiskCheck {
+    pub fn new(config: RiskConfig) -> Self {
+        PortfolioRiskCheck {
+            default_calculator: MarginCalculator::new(config.clone()),
+            config,
+            calculators: HashMap::new(),
+        }
+    }
+
+    /// Margin a market's positions with its own contract
+    pub fn with_market(mut self, market_id: MarketId, contract: ContractSpec) -> Self {
+        self.calculators.insert(
+            market_id,
+            MarginCalculator::new(self.config.clone()).with_contract(contract),
+        );
+        self
+    }
+
+    /// Fails if a position can't be valued at its market's mark
+    pub fn summarize(&self, account: &Account, holdings: &[MarketHoldings]) -> Result<PortfolioSummary> {
+        let mut summary = PortfolioSummary {
+            equity: Balance::zero(),
+            unrealized_pnl: Balance::zero(),
+            initial_margin: Balance::zero(),
+            maintenance_margin: Balance::zero(),
+            open_order_margin: Balance::zero(),
+        };
+        let mut isolated_margin = Balance::zero();
+
+        for market in holdings {
+            let calculator = self.calculator(&market.market_id);
+
+            for position in market.positions.iter().filter(|p| !p.is_flat()) {
+                if position.is_isolated() {
+                    isolated_margin = isolated_margin + position.isolated_margin;
+                    continue;
+                }
+                summary.unrealized_pnl = summary.unrealized_pnl
+                    .checked_add(PnLCalculator::calculate_unrealized_pnl_for(calculator.contract(), position, market.mark_price)?)?;
+                summary.initial_margin = summary.initial_margin
+                    + calculator.calculate_initial_margin(position.abs_size(), market.mark_price, market.leverage);
+                summary.maintenance_margin = summary.maintenance_margin
+                    + calculator.calculate_maintenance_margin(position.abs_size(), market.mark_price);
+            }
+
+            let (bids, asks) = Self::resting_by_side(&market.open_orders);
+            summary.open_order_margin = summary.open_order_margin
+                + calculator.calculate_initial_margin(bids.max(asks), market.mark_price, market.leverage);
+        }
+
+        summary.equity = account.balance.checked_add(summary.unrealized_pnl)? - isolated_margin;
+        Ok(summary)
+    }
+
+    /// Admit `order` only if the account still covers its worst-case initial
+    /// margin with the order added to its market's resting orders
+    pub fn check_order(
+        &self,
+        order: &OrderSubmit,
+        market_id: MarketId,
+        account: &Account,
+        holdings: &[MarketHoldings],
+    ) -> Result<PortfolioSummary> {
+        let mut summary = self.summarize(account, holdings)?;
+
+        // Re-price the order's market with the new order on its side
+        if let Some(market) = holdings.iter().find(|market| market.market_id == market_id) {
+            let calculator = self.calculator(&market_id);
+            let (mut bids, mut asks) = Self::resting_by_side(&market.open_orders);
+            match order.side {
+                Side::Buy => bids = bids + order.quantity,
+                Side::Sell => asks = asks + order.quantity,
+            }
+            summary.open_order_margin = summary.open_order_margin
+                - calculator.calculate_initial_margin(Self::larger_side(&market.open_orders), market.mark_price, market.leverage)
+                + calculator.calculate_initial_margin(bids.max(asks), market.mark_price, market.leverage);
+        }
+
+        let required = summary.worst_case_initial_margin();
+        if summary.equity < required {
+            return Err(Error::InsufficientMargin {
+                required,
+                available: summary.equity,
+            });
+        }
+
+        Ok(summary)
+    }
+
+    fn calculator(&self, market_id: &MarketId) -> &MarginCalculator {
+        self.calculators.get(market_id).unwrap_or(&self.default_calculator)
+    }
+
+    /// Unfilled (bid, ask) quantity of a market's resting orders
+    fn resting_by_side(orders: &[&Order]) -> (Quantity, Quantity) {
+        orders.iter().fold((Quantity::zero(), Quantity::zero()), |(bids, asks), order| {
+            let unfilled = order.quantity - order.filled;
+            match order.side {
+                Side::Buy => (bids + unfilled, asks),
+                Side::Sell => (bids, asks + unfilled),
+            }
+        })
+    }
+
+    fn larger_side(orders: &[&Order]) -> Quantity {
+        let (bids, asks) = Self::resting_by_side(orders);
+        bids.max(asks)
+    }
+}
@@ -1,16 +1,53 @@
 use num_traits::ToPrimitive;
-use crate::config::risk::RiskConfig;
+use serde::Serialize;
+use crate::config::market::ContractSpec;
+use crate::config::risk::{PositionLimits, RiskConfig, UserLimits};
 use crate::types::*;
 use crate::types::position::Position;
-use crate::events::order::{OrderSubmit, Side};
+use crate::events::order::{OrderSubmit, RejectReason, Side};
+use crate::risk::liquidation_price::liquidation_prices;
 use crate::risk::margin::MarginCalculator;
-use crate::risk::pnl::PnLCalculator;
+use crate::risk::pnl::{FundingAccrual, PnLCalculator};
 use crate::error::{Error, Result};
 use crate::interfaces::balance_provider::BalanceProvider;
+use crate::types::account::Account;
 use crate::types::balance::Balance;
 use crate::types::price::Price;
 use crate::types::quantity::Quantity;
 
+/// A user's open exposure in one market, as the exposure limits count it
+#[derive(Clone, Copy, Debug)]
+pub struct OpenExposure {
+    pub position_size: i64,      // Signed net position
+    pub resting_bids: Quantity,  // Unfilled, including pending stops
+    pub resting_asks: Quantity,
+    pub open_orders: usize,
+}
+
+/// Account state a what-if preview is run against (e.g. from read models)
+pub struct PreviewContext<'a> {
+    pub account: &'a Account,
+    pub position: &'a Position,
+    pub exposure: &'a OpenExposure,
+    pub overrides: Option<&'a UserLimits>,
+    pub leverage: Option<f64>,           // Account's chosen leverage (None: tier cap)
+    pub open_interest: Quantity,
+    pub funding: Option<&'a FundingAccrual>,
+}
+
+/// What the pre-trade checks make of a hypothetical order
+#[derive(Clone, Debug, Serialize)]
+pub struct RiskPreview {
+    pub accepted: bool,
+    pub rejection: Option<RejectReason>,
+    pub required_margin: Balance,          // Initial margin for the order at mark
+    pub available_balance: Balance,
+    pub resulting_position_size: i64,      // If the order fills in full at its limit (or mark)
+    pub resulting_leverage: Option<f64>,   // None when the resulting equity is not positive
+    pub liquidation_price: Option<Price>,  // Of the resulting position
+    pub bankruptcy_price: Option<Price>,
+}
+
 pub struct PreTradeRiskCheck {
     margin_calculator: MarginCalculator,
     config: RiskConfig,
@@ -24,18 +61,39 @@ impl PreTradeRiskCheck {
         }
     }
 
+    pub fn with_contract(mut self, contract: ContractSpec) -> Self {
+        self.margin_calculator = self.margin_calculator.with_contract(contract);
+        self
+    }
+
+    pub fn limits(&self) -> &PositionLimits {
+        &self.config.limits
+    }
+
     pub fn check(
         &self,
         order: &OrderSubmit,
         position: &Position,
         balance_provider: &dyn BalanceProvider,
         mark_price: Price,
+    ) -> Result<()> {
+        let account = balance_provider.get_account(order.user_id)?;
+        self.check_account(order, position, account, mark_price)
+    }
+
+    /// `check` against an account already in hand; reads nothing else
+    pub fn check_account(
+        &self,
+        order: &OrderSubmit,
+        position: &Position,
+        account: &Account,
+        mark_price: Price,
     ) -> Result<()> {
         // Check 1: Margin requirement
-        self.check_margin(order, position, balance_provider, mark_price)?;
+        self.check_margin(order, position, account, mark_price)?;
 
         // Check 2: Leverage limit
-        self.check_leverage(order, position, balance_provider, mark_price)?;
+        self.check_leverage(order, position, account, mark_price)?;
 
         // Check 3: Position limit
         self.check_position_limit(order, position)?;
@@ -48,23 +106,234 @@ impl PreTradeRiskCheck {
         Ok(())
     }
 
-    fn check_margin(
+    /// Check a quote set (one user's batch) in a single pass
+    ///
+    /// ## Worst case
+    /// - Every order on one side fills while the other side does not
+    /// - Margin: initial margin on the larger side's total quantity
+    /// - Leverage / position limit: the larger of |position + bids| and
+    ///   |position - asks|
+    ///
+    /// Account, unrealized PnL and available balance are read once for the
+    /// whole batch; the first failing check rejects every order in it.
+    pub fn check_batch(
         &self,
-        order: &OrderSubmit,
+        orders: &[OrderSubmit],
         position: &Position,
         balance_provider: &dyn BalanceProvider,
         mark_price: Price,
     ) -> Result<()> {
-        let account = balance_provider.get_account(order.user_id)?;
+        let user_id = match orders.first() {
+            Some(order) => order.user_id,
+            None => return Ok(()),
+        };
+        let account = balance_provider.get_account(user_id)?;
+        self.check_batch_account(orders, position, account, mark_price)
+    }
+
+    /// `check_batch` against the account already in hand (e.g. from read models)
+    pub fn check_batch_account(
+        &self,
+        orders: &[OrderSubmit],
+        position: &Position,
+        account: &Account,
+        mark_price: Price,
+    ) -> Result<()> {
+        let user_id = account.user_id;
+
+        let mut buy_quantity: i64 = 0;
+        let mut sell_quantity: i64 = 0;
+        for order in orders {
+            if order.user_id != user_id {
+                return Err(Error::BatchUserMismatch);
+            }
+            if order.reduce_only {
+                self.check_reduce_only(order, position)?;
+            }
+            match order.side {
+                Side::Buy => buy_quantity += order.quantity.to_i64(),
+                Side::Sell => sell_quantity += order.quantity.to_i64(),
+            }
+        }
+
+        let contract = self.margin_calculator.contract();
+        let unrealized_pnl = PnLCalculator::calculate_unrealized_pnl_for(contract, position, mark_price)?;
+
+        // Margin for the side that would open the most
+        let order_margin = self.margin_calculator.calculate_initial_margin(
+            Quantity::from_i64(buy_quantity.max(sell_quantity)),
+            mark_price,
+            None,
+        );
+        let available = self.margin_calculator.calculate_available_balance(
+            account.balance,
+            unrealized_pnl,
+            account.reserved_margin,
+        );
+        if available < order_margin {
+            return Err(Error::InsufficientMargin {
+                required: order_margin,
+                available,
+            });
+        }
 
+        // Largest position either side could leave behind
+        let worst_position_size = Quantity::from_i64(
+            (position.size + buy_quantity).abs().max((position.size - sell_quantity).abs())
+        );
+
+        let equity = account.balance.checked_add(unrealized_pnl)?;
+        if equity == Balance::zero() {
+            return Err(Error::InsufficientBalance);
+        }
+        let leverage = contract.notional(worst_position_size, mark_price)?.to_f64() / equity.to_f64();
+        if leverage > self.config.max_leverage {
+            return Err(Error::LeverageExceeded {
+                leverage,
+                max: self.config.max_leverage,
+            });
+        }
+
+        if worst_position_size > self.config.max_position_size {
+            return Err(Error::PositionLimitExceeded);
+        }
+
+        Ok(())
+    }
+
+    /// Exposure caps from `RiskConfig::limits`, with the user's override
+    /// (if any) taking precedence over the per-user caps
+    ///
+    /// ## Checks
+    /// - Open orders: the order may not take the user past the count limit
+    /// - Open notional: the largest position the user's resting orders plus
+    ///   this one could leave (`max(|position + bids|, |position - asks|)`),
+    ///   valued at mark
+    /// - Open interest: only the part of the order that opens a position
+    ///   counts against the market cap; reducing orders always pass
+    pub fn check_limits(
+        &self,
+        order: &OrderSubmit,
+        exposure: &OpenExposure,
+        overrides: Option<&UserLimits>,
+        open_interest: Quantity,
+        mark_price: Price,
+    ) -> Result<()> {
+        let limits = &self.config.limits;
+        let max_open_orders = overrides.and_then(|o| o.max_open_orders).or(limits.max_open_orders);
+        let max_open_notional = overrides.and_then(|o| o.max_open_notional).or(limits.max_open_notional);
+
+        if let Some(limit) = max_open_orders && exposure.open_orders >= limit as usize {
+            return Err(Error::OpenOrderLimitExceeded { limit });
+        }
+
+        let (mut bids, mut asks) = (exposure.resting_bids.to_i64(), exposure.resting_asks.to_i64());
+        match order.side {
+            Side::Buy => bids += order.quantity.to_i64(),
+            Side::Sell => asks += order.quantity.to_i64(),
+        }
+
+        if let Some(limit) = max_open_notional {
+            let worst_position_size = Quantity::from_i64(
+                (exposure.position_size + bids).abs().max((exposure.position_size - asks).abs())
+            );
+            let notional = self.margin_calculator.contract().notional(worst_position_size, mark_price)?;
+            if notional > limit {
+                return Err(Error::OpenNotionalLimitExceeded { notional, limit });
+            }
+        }
+
+        if let Some(cap) = limits.max_open_interest {
+            // Quantity beyond what closes the current position
+            let closing = match order.side {
+                Side::Buy => (-exposure.position_size).max(0),
+                Side::Sell => exposure.position_size.max(0),
+            };
+            let opening = Quantity::from_i64((order.quantity.to_i64() - closing).max(0));
+            let projected = open_interest.checked_add(opening)?;
+            if opening > Quantity::zero() && projected > cap {
+                return Err(Error::OpenInterestCapExceeded {
+                    open_interest: projected,
+                    cap,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Run every check against a hypothetical order without submitting it
+    ///
+    /// Read-only: nothing is reserved and no state is touched, so the API
+    /// can call it on read-model data. The projection assumes the order
+    /// fills in full at its limit price (mark for market orders); fails if
+    /// the projected position overflows.
+    pub fn preview(&self, order: &OrderSubmit, context: &PreviewContext, mark_price: Price) -> Result<RiskPreview> {
+        let contract = self.margin_calculator.contract();
+        let account = context.account;
+
+        let outcome = self.check_account(order, context.position, account, mark_price)
+            .and_then(|_| self.check_limits(order, context.exposure, context.overrides, context.open_interest, mark_price));
+
+        let required_margin = self.margin_calculator.calculate_initial_margin(order.quantity, mark_price, context.leverage);
+        let available_balance = self.margin_calculator.calculate_available_balance(
+            account.balance,
+            PnLCalculator::calculate_unrealized_pnl_for(contract, context.position, mark_price)?,
+            account.reserved_margin,
+        );
+
+        let mut resulting = context.position.clone();
+        let fill_price = order.price.unwrap_or(mark_price);
+        let realized = PnLCalculator::update_position_for(contract, &mut resulting, order.side, order.quantity, fill_price)?;
+
+        let collateral = if resulting.is_isolated() {
+            resulting.isolated_margin.checked_add(required_margin)?.checked_add(realized)?
+        } else {
+            account.balance.checked_add(realized)?
+        };
+        let equity = PnLCalculator::account_equity(contract, collateral, &[&resulting], mark_price, context.funding)?;
+        let resulting_leverage = if equity > Balance::zero() {
+            Some(contract.notional(resulting.abs_size(), mark_price)?.to_f64() / equity.to_f64())
+        } else {
+            None
+        };
+        let prices = liquidation_prices(
+            &self.margin_calculator,
+            &[&resulting],
+            collateral,
+            mark_price,
+            context.open_interest,
+            context.funding,
+        );
+
+        Ok(RiskPreview {
+            accepted: outcome.is_ok(),
+            rejection: outcome.err().map(|e| RejectReason::from_error(&e)),
+            required_margin,
+            available_balance,
+            resulting_position_size: resulting.size,
+            resulting_leverage,
+            liquidation_price: prices.liquidation_price,
+            bankruptcy_price: prices.bankruptcy_price,
+        })
+    }
+
+    fn check_margin(
+        &self,
+        order: &OrderSubmit,
+        position: &Position,
+        account: &Account,
+        mark_price: Price,
+    ) -> Result<()> {
         // Calculate required margin for new order
         let order_margin = self.margin_calculator.calculate_initial_margin(
             order.quantity,
             mark_price,
+            None,
         );
 
         // Calculate available balance
-        let unrealized_pnl = PnLCalculator::calculate_unrealized_pnl(position, mark_price);
+        let unrealized_pnl = PnLCalculator::calculate_unrealized_pnl_for(self.margin_calculator.contract(), position, mark_price)?;
         let available = self.margin_calculator.calculate_available_balance(
             account.balance,
             unrealized_pnl,
@@ -85,11 +354,9 @@ impl PreTradeRiskCheck {
         &self,
         order: &OrderSubmit,
         position: &Position,
-        balance_provider: &dyn BalanceProvider,
+        account: &Account,
         mark_price: Price,
     ) -> Result<()> {
-        let account = balance_provider.get_account(order.user_id)?;
-
         // Calculate new position size
         let order_size_signed = match order.side {
             Side::Buy => order.quantity.to_i64(),
@@ -100,9 +367,9 @@ impl PreTradeRiskCheck {
         );
 
         // Calculate leverage
-        let notional = new_position_size * mark_price;
-        let unrealized_pnl = PnLCalculator::calculate_unrealized_pnl(position, mark_price);
-        let equity = account.balance + unrealized_pnl;
+        let notional = self.margin_calculator.contract().notional(new_position_size, mark_price)?;
+        let unrealized_pnl = PnLCalculator::calculate_unrealized_pnl_for(self.margin_calculator.contract(), position, mark_price)?;
+        let equity = account.balance.checked_add(unrealized_pnl)?;
 
         if equity == Balance::zero() {
             return Err(Error::InsufficientBalance);
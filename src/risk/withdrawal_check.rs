@@ -0,0 +1,172 @@
+use crate::config::market::ContractSpec;
+use crate::config::risk::RiskConfig;
+use crate::error::{Error, Result};
+use crate::risk::margin::MarginCalculator;
+use crate::risk::pnl::{FundingAccrual, PnLCalculator};
+use crate::types::account::Account;
+use crate::types::balance::Balance;
+use crate::types::position::Position;
+use crate::types::price::Price;
+
+pub struct WithdrawalRiskCheck {
+    margin_calculator: MarginCalculator,
+    config: RiskConfig,
+}
+
+impl WithdrawalRiskCheck {
+    pub fn new(config: RiskConfig) -> Self {
+        WithdrawalRiskCheck {
+            margin_calculator: MarginCalculator::new(config.clone()),
+            config,
+        }
+    }
+
+    pub fn with_contract(mut self, contract: ContractSpec) -> Self {
+        self.margin_calculator = self.margin_calculator.with_contract(contract);
+        self
+    }
+
+    /// Reject withdrawals that would push the margin ratio below
+    /// maintenance plus the configured buffer
+    /// `positions` are all of the account's legs (`positions_for`), so hedge
+    /// mode longs and shorts both count; funding they owe but haven't settled
+    /// counts against equity
+    pub fn check(
+        &self,
+        account: &Account,
+        positions: &[&Position],
+        amount: Balance,
+        mark_price: Price,
+        funding: Option<&FundingAccrual>,
+    ) -> Result<()> {
+        // Check 1: Free balance
+        if account.available_balance() < amount {
+            return Err(Error::InsufficientAvailableBalance);
+        }
+
+        // Check 2: Post-withdrawal margin ratio (only relevant with open cross
+        // legs; an isolated bucket is reserved and never withdrawable)
+        let (equity, maintenance_margin) = match self.cross_margin(account.balance - amount, positions, mark_price, funding)? {
+            Some(margin) => margin,
+            None => return Ok(()),
+        };
+
+        let margin_ratio = self.margin_calculator.calculate_margin_ratio(
+            equity,
+            Balance::zero(),
+            maintenance_margin,
+        );
+
+        let min_ratio = self.min_margin_ratio();
+        if margin_ratio.to_f64() < min_ratio {
+            return Err(Error::WithdrawalMarginBreach {
+                margin_ratio: margin_ratio.to_f64(),
+                min_ratio,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Largest amount `check()` would accept right now
+    /// Bounded by free balance (resting orders hold reserved margin) and by the
+    /// equity that must stay behind to keep the ratio at min_margin_ratio()
+    /// Fails if a position's value overflows at `mark_price`
+    pub fn max_withdrawable(
+        &self,
+        account: &Account,
+        positions: &[&Position],
+        mark_price: Price,
+        funding: Option<&FundingAccrual>,
+        pending_withdrawals: Balance,
+    ) -> Result<Balance> {
+        let free = account.available_balance() - pending_withdrawals;
+
+        let max_amount = match self.cross_margin(account.balance - pending_withdrawals, positions, mark_price, funding)? {
+            Some((equity, maintenance_margin)) => {
+                // Round the retained equity up so the result never fails check()
+                let required_equity = Balance::from_i64(
+                    (maintenance_margin.to_i64() as f64 * self.min_margin_ratio()).ceil() as i64
+                );
+                let margin_headroom = equity - required_equity;
+
+                free.min(margin_headroom)
+            }
+            None => free,
+        };
+
+        Ok(max_amount.max(Balance::zero()))
+    }
+
+    /// Equity and maintenance margin summed over the open cross legs, or None
+    /// when there are none
+    fn cross_margin(
+        &self,
+        collateral: Balance,
+        positions: &[&Position],
+        mark_price: Price,
+        funding: Option<&FundingAccrual>,
+    ) -> Result<Option<(Balance, Balance)>> {
+        let cross: Vec<&Position> = positions.iter()
+            .copied()
+            .filter(|p| !p.is_flat() && !p.is_isolated())
+            .collect();
+        if cross.is_empty() {
+            return Ok(None);
+        }
+
+        let equity = PnLCalculator::account_equity(
+            self.margin_calculator.contract(),
+            collateral,
+            &cross,
+            mark_price,
+            funding,
+        )?;
+        let maintenance_margin = cross.iter().fold(Balance::zero(), |total, p| {
+            total + self.margin_calculator.calculate_maintenance_margin(p.abs_size(), mark_price)
+        });
+
+        Ok(Some((equity, maintenance_margin)))
+    }
+
+    /// Minimum margin ratio an account must keep after a withdrawal
+    pub fn min_margin_ratio(&self) -> f64 {
+        1.0 + self.config.withdrawal_margin_buffer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::ids::{MarketId, UserId};
+    use crate::types::position::PositionSide;
+
+    #[test]
+    fn hedge_legs_both_hold_back_collateral() {
+        let check = WithdrawalRiskCheck::new(RiskConfig::default());
+        let user_id = UserId::new();
+        let mark = Price::from_f64(50_000.0);
+        let mut account = Account::new(user_id);
+        account.balance = Balance::from_f64(6_000.0);
+
+        let leg = |side: PositionSide, size: i64| Position {
+            size,
+            entry_price: mark,
+            ..Position::new_leg(user_id, MarketId::btc_perp(), side)
+        };
+        let (long, short) = (leg(PositionSide::Long, 1), leg(PositionSide::Short, -1));
+        let legs = [&long, &short];
+
+        // Neither leg is the one-way leg, yet both carry maintenance margin
+        let amount = Balance::from_f64(1_000.0);
+        assert!(check.check(&account, &[], amount, mark, None).is_ok());
+        assert!(matches!(
+            check.check(&account, &legs, amount, mark, None),
+            Err(Error::WithdrawalMarginBreach { .. })
+        ));
+
+        let max = check.max_withdrawable(&account, &legs, mark, None, Balance::zero()).unwrap();
+        assert!(max < amount);
+        assert!(check.check(&account, &legs, max, mark, None).is_ok());
+    }
+}
@@ -2,6 +2,8 @@ use crate::error::{Error, Result};
 use crate::interfaces::balance_provider::BalanceProvider;
 use crate::types::account::Account;
 use crate::settlement::ledger::{EntryType, Ledger, LedgerEntry};
+use crate::settlement::reconciliation::Reconciliation;
+use crate::settlement::sub_accounts;
 use crate::types::balance::Balance;
 use crate::types::ids::{AccountId, UserId};
 use crate::types::timestamp::Timestamp;
@@ -31,6 +33,34 @@ impl BalanceManager {
         Ok(account)
     }
 
+    /// Move `amount` into (or out of) the balance and record it under `entry_type`
+    fn post(
+        &mut self,
+        user_id: UserId,
+        amount: Balance,
+        entry_type: EntryType,
+        reference_id: String,
+        description: String,
+    ) -> Result<()> {
+        let (account_id, balance_after);
+        {
+            let account = self.accounts.get_mut(&user_id)
+                .ok_or(Error::AccountNotFound(AccountId::from_user(user_id)))?;
+
+            account.balance = account.balance + amount;
+            if let EntryType::RealizedPnl = entry_type {
+                account.realized_pnl = account.realized_pnl + amount;
+            }
+            account.updated_at = Timestamp::now();
+
+            account_id = account.account_id;
+            balance_after = account.balance;
+        }
+
+        self.record_ledger_entry(account_id, entry_type, amount, balance_after, reference_id, description);
+        Ok(())
+    }
+
     fn record_ledger_entry(
         &mut self,
         account_id: AccountId,
@@ -61,29 +91,222 @@ impl BalanceProvider for BalanceManager {
             .ok_or(Error::AccountNotFound(AccountId::from_user(user_id)))
     }
 
+    fn get_account_mut(&mut self, user_id: UserId) -> Result<&mut Account> {
+        self.accounts.get_mut(&user_id)
+            .ok_or(Error::AccountNotFound(AccountId::from_user(user_id)))
+    }
+
+    fn create_account(&mut self, user_id: UserId) -> Result<Account> {
+        BalanceManager::create_account(self, user_id)
+    }
+
     fn adjust_balance(&mut self, user_id: UserId, amount: Balance) -> Result<()> {
-        let (account_id, balance_after);
-        {
-            let account = self.accounts.get_mut(&user_id)
-                .ok_or(Error::AccountNotFound(AccountId::from_user(user_id)))?;
+        self.post(
+            user_id,
+            amount,
+            EntryType::Trade,
+            "adjustment".to_string(),
+            "Balance adjustment".to_string(),
+        )
+    }
 
-            account.balance = account.balance + amount;
-            account.updated_at = Timestamp::now();
+    fn settle_realized_pnl(&mut self, user_id: UserId, pnl: Balance, reference_id: &str) -> Result<()> {
+        self.post(
+            user_id,
+            pnl,
+            EntryType::RealizedPnl,
+            reference_id.to_string(),
+            "Realized PnL".to_string(),
+        )
+    }
 
-            account_id = account.account_id;
-            balance_after = account.balance;
+    fn charge_fee(&mut self, user_id: UserId, fee: Balance, reference_id: &str) -> Result<()> {
+        self.post(
+            user_id,
+            -fee,
+            EntryType::Fee,
+            reference_id.to_string(),
+            if fee < Balance::zero() { "Maker rebate" } else { "Trading fee" }.to_string(),
+        )?;
+
+        let revenue = UserId::fee_revenue();
+        self.accounts.entry(revenue).or_insert_with(|| Account::new(revenue));
+        self.post(
+            revenue,
+            fee,
+            EntryType::FeeRevenue,
+            reference_id.to_string(),
+            if fee < Balance::zero() { "Maker rebate paid" } else { "Trading fee collected" }.to_string(),
+        )
+    }
+
+    fn settle_funding(&mut self, user_id: UserId, payment: Balance, reference_id: &str) -> Result<()> {
+        self.post(
+            user_id,
+            payment,
+            EntryType::Funding,
+            reference_id.to_string(),
+            if payment < Balance::zero() { "Funding paid" } else { "Funding received" }.to_string(),
+        )
+    }
+
+    fn settle_funding_residual(&mut self, residual: Balance, reference_id: &str) -> Result<()> {
+        let user_id = UserId::funding_residual();
+        self.accounts.entry(user_id).or_insert_with(|| Account::new(user_id));
+        self.post(
+            user_id,
+            residual,
+            EntryType::FundingResidual,
+            reference_id.to_string(),
+            "Funding rounding residual".to_string(),
+        )
+    }
+
+    fn charge_liquidation_penalty(&mut self, user_id: UserId, penalty: Balance, reference_id: &str) -> Result<()> {
+        self.post(
+            user_id,
+            -penalty,
+            EntryType::LiquidationPenalty,
+            reference_id.to_string(),
+            "Liquidation penalty".to_string(),
+        )?;
+
+        let fund = UserId::insurance_fund();
+        self.accounts.entry(fund).or_insert_with(|| Account::new(fund));
+        self.post(
+            fund,
+            penalty,
+            EntryType::InsuranceFundContribution,
+            reference_id.to_string(),
+            "Liquidation penalty contribution".to_string(),
+        )
+    }
+
+    fn cover_from_insurance_fund(&mut self, user_id: UserId, amount: Balance, reference_id: &str) -> Result<()> {
+        let fund = UserId::insurance_fund();
+        self.accounts.entry(fund).or_insert_with(|| Account::new(fund));
+        self.post(
+            fund,
+            -amount,
+            EntryType::InsuranceFundPayout,
+            reference_id.to_string(),
+            "Bankruptcy deficit covered".to_string(),
+        )?;
+
+        self.post(
+            user_id,
+            amount,
+            EntryType::InsuranceFundPayout,
+            reference_id.to_string(),
+            "Insurance fund payout".to_string(),
+        )
+    }
+
+    fn socialize_loss(&mut self, user_id: UserId, haircuts: &[(UserId, Balance)], reference_id: &str) -> Result<()> {
+        let mut total = Balance::zero();
+        for &(winner, amount) in haircuts {
+            self.post(
+                winner,
+                -amount,
+                EntryType::SocializedLoss,
+                reference_id.to_string(),
+                "Socialized loss haircut".to_string(),
+            )?;
+            total = total + amount;
         }
 
-        self.record_ledger_entry(
-            account_id,
-            EntryType::Trade,
+        self.post(
+            user_id,
+            total,
+            EntryType::SocializedLoss,
+            reference_id.to_string(),
+            "Socialized loss credit".to_string(),
+        )
+    }
+
+    fn repair_balance(&mut self, user_id: UserId, delta: Balance, reference_id: &str) -> Result<()> {
+        self.post(
+            user_id,
+            delta,
+            EntryType::StateRepair,
+            reference_id.to_string(),
+            "State repair".to_string(),
+        )?;
+
+        let suspense = UserId::repair_suspense();
+        self.accounts.entry(suspense).or_insert_with(|| Account::new(suspense));
+        self.post(
+            suspense,
+            -delta,
+            EntryType::StateRepair,
+            reference_id.to_string(),
+            "State repair counter-entry".to_string(),
+        )
+    }
+
+    fn create_sub_account(&mut self, master_id: UserId, sub_account_id: UserId, label: &str) -> Result<Account> {
+        let master = BalanceProvider::get_account(self, master_id)?;
+        sub_accounts::validate_new_sub_account(master, &self.sub_accounts(master_id), label)?;
+        if self.accounts.contains_key(&sub_account_id) {
+            return Err(Error::AccountAlreadyExists(AccountId::from_user(sub_account_id)));
+        }
+
+        let account = Account {
+            master_id: Some(master_id),
+            label: Some(label.to_string()),
+            ..Account::new(sub_account_id)
+        };
+        self.accounts.insert(sub_account_id, account.clone());
+        Ok(account)
+    }
+
+    fn sub_accounts(&self, master_id: UserId) -> Vec<&Account> {
+        let mut subs: Vec<&Account> = self.accounts.values()
+            .filter(|account| account.master_id == Some(master_id))
+            .collect();
+        subs.sort_by_key(|account| (account.created_at, account.user_id.0));
+        subs
+    }
+
+    fn transfer(&mut self, from_user: UserId, to_user: UserId, amount: Balance, reference_id: &str) -> Result<()> {
+        if amount <= Balance::zero() {
+            return Err(Error::InvalidTransferAmount);
+        }
+        BalanceProvider::get_account(self, to_user)?;
+        if BalanceProvider::get_account(self, from_user)?.available_balance() < amount {
+            return Err(Error::InsufficientAvailableBalance);
+        }
+
+        self.post(
+            from_user,
+            -amount,
+            EntryType::Transfer,
+            reference_id.to_string(),
+            format!("Transfer to {}", to_user),
+        )?;
+        self.post(
+            to_user,
             amount,
-            balance_after,
-            "adjustment".to_string(),
-            "Balance adjustment".to_string(),
-        );
+            EntryType::Transfer,
+            reference_id.to_string(),
+            format!("Transfer from {}", from_user),
+        )?;
 
-        Ok(())
+        // Every transfer entry under the reference must pair up
+        let (debits, credits) = self.ledger.get_entries_for_reference(reference_id).iter()
+            .filter(|entry| matches!(entry.entry_type, EntryType::Transfer))
+            .fold((Balance::zero(), Balance::zero()), |(debits, credits), entry| {
+                if entry.amount > Balance::zero() {
+                    (debits + entry.amount, credits)
+                } else {
+                    (debits, credits - entry.amount)
+                }
+            });
+        Reconciliation::verify_double_entry(debits, credits)
+    }
+
+    fn accounts(&self) -> Vec<&Account> {
+        self.accounts.values().collect()
     }
 
     fn reserve_margin(&mut self, user_id: UserId, amount: Balance) -> Result<()> {
@@ -141,4 +364,35 @@ impl BalanceProvider for BalanceManager {
 
         Ok(())
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::fees::FeeConfig;
+
+    fn total(balances: &BalanceManager) -> Balance {
+        balances.accounts.values().fold(Balance::zero(), |sum, account| sum + account.balance)
+    }
+
+    #[test]
+    fn fees_and_rebates_are_zero_sum() {
+        let mut balances = BalanceManager::new();
+        let (maker, taker) = (UserId::new(), UserId::new());
+        BalanceManager::create_account(&mut balances, maker).unwrap();
+        BalanceManager::create_account(&mut balances, taker).unwrap();
+
+        // Notional that does not divide evenly, so the ceil policy rounds
+        let rounding = FeeConfig::default().rounding;
+        let notional = 123_456_789i128;
+        let taker_fee = rounding.div(notional * 5, 10_000).unwrap();
+        let maker_rebate = rounding.div(-notional, 10_000).unwrap();
+
+        balances.charge_fee(taker, taker_fee, "trade-1").unwrap();
+        balances.charge_fee(maker, maker_rebate, "trade-1").unwrap();
+
+        let revenue = balances.get_account(UserId::fee_revenue()).unwrap().balance;
+        assert_eq!(revenue, taker_fee + maker_rebate);
+        assert_eq!(total(&balances), Balance::zero());
+        Reconciliation::verify_conservation_of_value(&balances).unwrap();
+    }
+}
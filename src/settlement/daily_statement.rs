@@ -0,0 +1,210 @@
+use std::collections::HashMap;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+use crate::config::StatementConfig;
+use crate::error::{Error, Result};
+use crate::events::base::{BaseEvent, EventPayload, EventType};
+use crate::events::daily_record::DailyRecord;
+use crate::risk::pnl::PnLCalculator;
+use crate::settlement::balance_manager::BalanceManager;
+use crate::settlement::position_manager::PositionManager;
+use crate::types::balance::Balance;
+use crate::types::ids::{MarketId, UserId};
+use crate::types::price::Price;
+use crate::types::timestamp::Timestamp;
+
+const DAY_MS: u64 = 86_400_000;
+
+/// Daily statements of record
+///
+/// ## Cutoff
+/// - Fires once per day at `cutoff_hour_utc:cutoff_minute_utc`
+/// - Business date is the UTC date of the day ending at the cutoff
+///
+/// ## Records
+/// - One `DailyRecord` per account: balance, position, mark price,
+///   cumulative/daily realized PnL and unrealized PnL (plus its daily change)
+/// - Daily figures are deltas against the previous cutoff's records
+///
+/// ## Storage
+/// - Records are published to the event log and written once to
+///   `statements_dir/{market_id}/{business_date}.json` (never overwritten)
+/// - The latest statement file seeds the baselines after a restart
+pub struct DailyStatementGenerator {
+    config: StatementConfig,
+    market_id: MarketId,
+    last_cutoff: u64,
+    baselines: HashMap<UserId, (Balance, Balance)>,  // (cumulative realized, unrealized) at last cutoff
+}
+
+impl DailyStatementGenerator {
+    pub fn new(config: StatementConfig, market_id: MarketId) -> Result<Self> {
+        let mut generator = DailyStatementGenerator {
+            config,
+            market_id,
+            last_cutoff: 0,
+            baselines: HashMap::new(),
+        };
+
+        generator.last_cutoff = generator.cutoff_at_or_before(Timestamp::now().physical);
+        generator.load_latest_statement()?;
+
+        Ok(generator)
+    }
+
+    fn cutoff_offset_ms(&self) -> u64 {
+        (self.config.cutoff_hour_utc as u64 * 3600 + self.config.cutoff_minute_utc as u64 * 60) * 1000
+    }
+
+    fn cutoff_at_or_before(&self, millis: u64) -> u64 {
+        let offset = self.cutoff_offset_ms();
+        (millis.saturating_sub(offset) / DAY_MS) * DAY_MS + offset
+    }
+
+    /// Next cutoff after the last recorded one
+    pub fn next_cutoff(&self) -> Timestamp {
+        Timestamp::from_millis(self.last_cutoff + DAY_MS)
+    }
+
+    /// Cutoff to record if one has passed since the last statement
+    pub fn due_cutoff(&self, now: Timestamp) -> Option<Timestamp> {
+        let cutoff = self.cutoff_at_or_before(now.physical);
+        (cutoff > self.last_cutoff).then(|| Timestamp::from_millis(cutoff))
+    }
+
+    fn business_date(cutoff: Timestamp) -> String {
+        // Day ending at the cutoff, so a midnight cutoff belongs to the previous date
+        chrono::DateTime::from_timestamp_millis(cutoff.physical as i64 - 1)
+            .map(|dt| dt.format("%Y-%m-%d").to_string())
+            .unwrap_or_default()
+    }
+
+    fn market_dir(&self) -> PathBuf {
+        PathBuf::from(&self.config.statements_dir).join(self.market_id.to_string())
+    }
+
+    /// Record every account at `cutoff` and return the events to publish
+    pub fn generate(
+        &mut self,
+        cutoff: Timestamp,
+        as_of_sequence: u64,
+        balance_manager: &BalanceManager,
+        position_manager: &PositionManager,
+        mark_price: Price,
+    ) -> Result<Vec<BaseEvent>> {
+        let business_date = Self::business_date(cutoff);
+
+        let mut accounts: Vec<_> = balance_manager.accounts.values().collect();
+        accounts.sort_by_key(|a| a.user_id.0);
+
+        let records = accounts.into_iter()
+            .map(|account| {
+                let (size, entry_price, unrealized) = match position_manager.get_position(&account.user_id) {
+                    Some(position) => (
+                        position.size,
+                        position.entry_price,
+                        PnLCalculator::calculate_unrealized_pnl(position, mark_price)?,
+                    ),
+                    None => (0, Price::zero(), Balance::zero()),
+                };
+
+                let (prev_realized, prev_unrealized) = self.baselines.get(&account.user_id)
+                    .copied()
+                    .unwrap_or((Balance::zero(), Balance::zero()));
+
+                Ok(DailyRecord {
+                    base: BaseEvent::new(EventType::DailyRecord, self.market_id),
+                    business_date: business_date.clone(),
+                    cutoff,
+                    as_of_sequence,
+                    user_id: account.user_id,
+                    balance: account.balance,
+                    reserved_margin: account.reserved_margin,
+                    position_size: size,
+                    entry_price,
+                    mark_price,
+                    cumulative_realized_pnl: account.realized_pnl,
+                    daily_realized_pnl: account.realized_pnl - prev_realized,
+                    unrealized_pnl: unrealized,
+                    daily_unrealized_pnl: unrealized - prev_unrealized,
+                })
+            })
+            .collect::<Result<Vec<DailyRecord>>>()?;
+
+        self.write_statement(&business_date, &records)?;
+
+        self.last_cutoff = cutoff.physical;
+        self.baselines = records.iter()
+            .map(|r| (r.user_id, (r.cumulative_realized_pnl, r.unrealized_pnl)))
+            .collect();
+
+        tracing::info!(
+            "Daily statement {} recorded: {} accounts at seq={}",
+            business_date, records.len(), as_of_sequence
+        );
+
+        Ok(records.into_iter()
+            .map(|record| {
+                let base = record.base.clone();
+                BaseEvent {
+                    payload: EventPayload::DailyRecord(Box::new(record)),
+                    ..base
+                }
+            })
+            .collect())
+    }
+
+    fn write_statement(&self, business_date: &str, records: &[DailyRecord]) -> Result<()> {
+        let dir = self.market_dir();
+        std::fs::create_dir_all(&dir).map_err(Error::IoError)?;
+
+        let data = serde_json::to_vec_pretty(records)
+            .map_err(|e| Error::SerializationError(e.to_string()))?;
+
+        // create_new: statements of record are never overwritten
+        let path = dir.join(format!("{}.json", business_date));
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+            .map_err(Error::IoError)?;
+        file.write_all(&data).map_err(Error::IoError)?;
+        file.sync_all().map_err(Error::IoError)?;
+
+        Ok(())
+    }
+
+    /// Seed cutoff and baselines from the most recent statement file
+    fn load_latest_statement(&mut self) -> Result<()> {
+        let entries = match std::fs::read_dir(self.market_dir()) {
+            Ok(entries) => entries,
+            Err(_) => return Ok(()),  // No statements yet
+        };
+
+        // File names are ISO dates, so lexical order is chronological
+        let latest = entries
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|p| p.extension().is_some_and(|ext| ext == "json"))
+            .max();
+
+        let path = match latest {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+
+        let data = std::fs::read(&path).map_err(Error::IoError)?;
+        let records: Vec<DailyRecord> = serde_json::from_slice(&data)
+            .map_err(|e| Error::DeserializationError(e.to_string()))?;
+
+        if let Some(cutoff) = records.iter().map(|r| r.cutoff.physical).max() {
+            self.last_cutoff = cutoff;
+        }
+        self.baselines = records.iter()
+            .map(|r| (r.user_id, (r.cumulative_realized_pnl, r.unrealized_pnl)))
+            .collect();
+
+        tracing::info!("Loaded statement baselines from {:?}", path);
+        Ok(())
+    }
+}
@@ -0,0 +1,94 @@
+use crate::config::market::ExpirySpec;
+use crate::events::base::{BaseEvent, EventType};
+use crate::events::expiry::ExpirySettlement;
+use crate::types::ids::MarketId;
+use crate::types::price::Price;
+use crate::types::timestamp::Timestamp;
+
+/// Expiry settlement job for a dated future
+///
+/// ## Flow
+/// 1. The host feeds every index price it sees to `record_index()`
+/// 2. Samples inside `[expires_at - settlement_window, expires_at]` are kept
+/// 3. Once `is_due()`, `settlement_event()` builds the ExpirySettlement with the
+///    time-weighted index price over the window
+///
+/// Each sample is weighted by the time until the next one (the last until
+/// expiry). Samples taken before a restart are lost, so a restart inside the
+/// window settles on the remaining part of it. Timing runs on the host's
+/// clock; the settlement goes through the event log, so replay does not
+/// depend on it.
+pub struct ExpirySettler {
+    market_id: MarketId,
+    expires_at: Timestamp,
+    window_start: Timestamp,
+    samples: Vec<(Timestamp, Price)>,
+    last_before_window: Option<Price>,
+    latest: Option<Price>,
+}
+
+impl ExpirySettler {
+    pub fn new(spec: &ExpirySpec, market_id: MarketId) -> Self {
+        ExpirySettler {
+            market_id,
+            expires_at: spec.expires_at(),
+            window_start: spec.window_start(),
+            samples: Vec::new(),
+            last_before_window: None,
+            latest: None,
+        }
+    }
+
+    pub fn record_index(&mut self, price: Price, at: Timestamp) {
+        self.latest = Some(price);
+
+        if at < self.window_start {
+            self.last_before_window = Some(price);
+        } else if at <= self.expires_at {
+            self.samples.push((at, price));
+        }
+    }
+
+    pub fn is_due(&self, now: Timestamp) -> bool {
+        now >= self.expires_at
+    }
+
+    /// (TWAP, samples) over the window
+    /// Falls back to the last index price before the window, then to the latest
+    /// one seen at all (restart after expiry), reported as 0 samples
+    pub fn settlement_price(&self) -> Option<(Price, u32)> {
+        if self.samples.is_empty() {
+            return self.last_before_window.or(self.latest).map(|price| (price, 0));
+        }
+
+        let mut weighted: i128 = 0;
+        let mut total_ms: i128 = 0;
+        for (i, (at, price)) in self.samples.iter().enumerate() {
+            let until = self.samples.get(i + 1).map_or(self.expires_at, |(next, _)| *next);
+            let dt = until.physical.saturating_sub(at.physical) as i128;
+            weighted += price.to_i64() as i128 * dt;
+            total_ms += dt;
+        }
+
+        // All samples at the same instant (or at expiry): plain average
+        let twap = if total_ms == 0 {
+            self.samples.iter().map(|(_, p)| p.to_i64() as i128).sum::<i128>() / self.samples.len() as i128
+        } else {
+            weighted / total_ms
+        };
+
+        Some((Price::from_i64(twap as i64), self.samples.len() as u32))
+    }
+
+    /// Build the settlement event; None until an index price is known
+    pub fn settlement_event(&self) -> Option<ExpirySettlement> {
+        let (settlement_price, twap_samples) = self.settlement_price()?;
+
+        Some(ExpirySettlement {
+            base: BaseEvent::new(EventType::ExpirySettlement, self.market_id),
+            expires_at: self.expires_at,
+            settlement_price,
+            twap_samples,
+        })
+    }
+}
@@ -0,0 +1,121 @@
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use crate::events::order::Side;
+use crate::events::trade::TradeEvent;
+use crate::interfaces::position_provider::PositionProvider;
+use crate::types::ids::{TradeId, UserId};
+use crate::types::position::PositionSide;
+
+/// Position change of one side of a matched trade
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InFlightFill {
+    pub user_id: UserId,
+    pub leg: PositionSide,
+    pub delta: i64,  // Signed: positive = bought
+}
+
+/// Fills matched but not yet settled by the EventProcessor
+///
+/// ## Why
+/// - Positions move only when the logged `Trade` comes back through
+///   `process_trade`, so between the match and that point the position
+///   manager lags the book
+/// - Reduce-only caps read positions through `PendingPositions`, which adds
+///   these fills, so two orders matched back to back cannot both close the
+///   same position
+///
+/// ## Lifecycle
+/// - Recorded when the trade is logged, dropped when it settles
+/// - Carried in snapshots: trades logged before the cut settle after it
+#[derive(Clone, Debug, Default)]
+pub struct InFlightFills {
+    fills: HashMap<TradeId, [InFlightFill; 2]>,  // Maker and taker
+    net: HashMap<(UserId, PositionSide), i64>,
+}
+
+impl InFlightFills {
+    pub fn new() -> Self {
+        InFlightFills::default()
+    }
+
+    /// Record a logged trade until it settles
+    pub fn record(&mut self, trade: &TradeEvent) {
+        let signed = |side: Side| match side {
+            Side::Buy => trade.quantity.to_i64(),
+            Side::Sell => -trade.quantity.to_i64(),
+        };
+        let fills = [
+            InFlightFill { user_id: trade.maker_user_id, leg: trade.maker_position_side, delta: signed(trade.maker_side) },
+            InFlightFill { user_id: trade.taker_user_id, leg: trade.taker_position_side, delta: signed(trade.maker_side.opposite()) },
+        ];
+        if self.fills.insert(trade.trade_id, fills).is_none() {
+            for fill in fills {
+                self.add(fill, 1);
+            }
+        }
+    }
+
+    /// Forget a trade once its fills are in the positions
+    pub fn settle(&mut self, trade_id: &TradeId) {
+        if let Some(fills) = self.fills.remove(trade_id) {
+            for fill in fills {
+                self.add(fill, -1);
+            }
+        }
+    }
+
+    fn add(&mut self, fill: InFlightFill, sign: i64) {
+        let key = (fill.user_id, fill.leg);
+        let net = self.net.entry(key).or_insert(0);
+        *net += sign * fill.delta;
+        if *net == 0 {
+            self.net.remove(&key);
+        }
+    }
+
+    /// Unsettled change of one leg
+    pub fn leg_delta(&self, user_id: UserId, leg: PositionSide) -> i64 {
+        self.net.get(&(user_id, leg)).copied().unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fills.is_empty()
+    }
+
+    /// Unsettled trades, by trade id (for snapshots)
+    pub fn fills(&self) -> Vec<(TradeId, [InFlightFill; 2])> {
+        let mut fills: Vec<(TradeId, [InFlightFill; 2])> = self.fills.iter().map(|(trade_id, fills)| (*trade_id, *fills)).collect();
+        fills.sort_by_key(|(trade_id, _)| trade_id.0);
+        fills
+    }
+
+    /// Replace the unsettled trades with a snapshot's
+    pub fn restore(&mut self, fills: &[(TradeId, [InFlightFill; 2])]) {
+        *self = InFlightFills::default();
+        for (trade_id, trade_fills) in fills {
+            self.fills.insert(*trade_id, *trade_fills);
+            for fill in trade_fills {
+                self.add(*fill, 1);
+            }
+        }
+    }
+}
+
+/// Positions as they will be once the in-flight fills settle
+pub struct PendingPositions<'a, P: ?Sized> {
+    pub positions: &'a P,
+    pub in_flight: &'a InFlightFills,
+}
+
+impl<P: PositionProvider + ?Sized> PositionProvider for PendingPositions<'_, P> {
+    fn position_size(&self, user_id: UserId) -> i64 {
+        let pending: i64 = [PositionSide::Both, PositionSide::Long, PositionSide::Short].iter()
+            .map(|leg| self.in_flight.leg_delta(user_id, *leg))
+            .sum();
+        self.positions.position_size(user_id) + pending
+    }
+
+    fn leg_size(&self, user_id: UserId, leg: PositionSide) -> i64 {
+        self.positions.leg_size(user_id, leg) + self.in_flight.leg_delta(user_id, leg)
+    }
+}
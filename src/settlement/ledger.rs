@@ -1,4 +1,5 @@
 use crate::types::*;
+use std::collections::HashMap;
 use serde::{Deserialize, Serialize};
 use crate::types::balance::Balance;
 use crate::types::ids::{AccountId, EntryId};
@@ -21,21 +22,60 @@ pub enum EntryType {
     Deposit,
     Withdrawal,
     Trade,
-    Fee,
+    RealizedPnl,  // Closing part of a fill, credited or debited at the fill price
+    Fee,          // Trading fee (negative amount) or maker rebate (positive)
+    FeeRevenue,   // Counter-entry of a fee or rebate, on the fee revenue account
     Funding,
+    FundingResidual,  // Funding rounding residue, on the funding residual account
     Liquidation,
+    LiquidationPenalty,         // Charged to a liquidated account
+    InsuranceFundContribution,  // Penalty credited to the insurance fund account
+    InsuranceFundPayout,        // Deficit covered by the fund: debits its account, credits the bankrupt one
+    SocializedLoss,             // Haircut on a period winner, or its credit to the bankrupt account
+    Transfer,                   // Internal transfer: debit on the sender, credit on the receiver
+    StateRepair,                // Operator correction, countered on the repair suspense account
     ReserveMargin,
     ReleaseMargin,
 }
 
+/// Sealed run of ledger entries, ready for archival export
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LedgerSegment {
+    pub segment_id: u64,
+    pub opening_balances: HashMap<AccountId, Balance>,  // Sum of all earlier segments
+    pub entries: Vec<LedgerEntry>,
+}
+
+impl LedgerSegment {
+    pub fn first_timestamp(&self) -> Option<Timestamp> {
+        self.entries.first().map(|e| e.timestamp)
+    }
+
+    pub fn last_timestamp(&self) -> Option<Timestamp> {
+        self.entries.last().map(|e| e.timestamp)
+    }
+}
+
+/// Append-only ledger, held in memory as one open segment
+///
+/// ## Segments
+/// - `roll_segment()` seals the open segment and starts the next one
+/// - Sealed entries leave memory; their per-account totals are carried forward
+///   as opening balances, so `verify_balance()` still covers the full history
+/// - Sealed segments are exported by `LedgerArchiver`; the ledger itself never
+///   drops an entry that was not handed out in a segment
 pub struct Ledger {
     entries: Vec<LedgerEntry>,
+    segment_id: u64,
+    carried_forward: HashMap<AccountId, Balance>,
 }
 
 impl Ledger {
     pub fn new() -> Self {
         Ledger {
             entries: Vec::new(),
+            segment_id: 0,
+            carried_forward: HashMap::new(),
         }
     }
 
@@ -43,18 +83,66 @@ impl Ledger {
         self.entries.push(entry);
     }
 
+    /// Entries for the account in the open segment
     pub fn get_entries_for_account(&self, account_id: AccountId) -> Vec<&LedgerEntry> {
         self.entries.iter()
             .filter(|e| e.account_id == account_id)
             .collect()
     }
 
-    pub fn verify_balance(&self, account_id: AccountId, expected: Balance) -> bool {
-        let calculated: i64 = self.entries.iter()
+    /// Entries in the open segment posted under `reference_id`
+    pub fn get_entries_for_reference(&self, reference_id: &str) -> Vec<&LedgerEntry> {
+        self.entries.iter()
+            .filter(|e| e.reference_id == reference_id)
+            .collect()
+    }
+
+    /// Net of every entry ever recorded for the account, sealed segments included
+    pub fn account_total(&self, account_id: AccountId) -> Balance {
+        let open: i64 = self.entries.iter()
             .filter(|e| e.account_id == account_id)
             .map(|e| e.amount.to_i64())
             .sum();
+        let carried = self.carried_forward.get(&account_id).copied().unwrap_or(Balance::zero());
+
+        carried + Balance::from_i64(open)
+    }
 
-        Balance::from_i64(calculated) == expected
+    pub fn verify_balance(&self, account_id: AccountId, expected: Balance) -> bool {
+        self.account_total(account_id) == expected
+    }
+
+    pub fn segment_id(&self) -> u64 {
+        self.segment_id
+    }
+
+    /// Entries in the open segment
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
     }
-}
\ No newline at end of file
+
+    /// Seal the open segment (None when it is empty)
+    pub fn roll_segment(&mut self) -> Option<LedgerSegment> {
+        if self.entries.is_empty() {
+            return None;
+        }
+
+        let segment = LedgerSegment {
+            segment_id: self.segment_id,
+            opening_balances: self.carried_forward.clone(),
+            entries: std::mem::take(&mut self.entries),
+        };
+
+        for entry in &segment.entries {
+            let total = self.carried_forward.entry(entry.account_id).or_insert(Balance::zero());
+            *total = *total + entry.amount;
+        }
+        self.segment_id += 1;
+
+        Some(segment)
+    }
+}
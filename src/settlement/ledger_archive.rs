@@ -0,0 +1,107 @@
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use crate::config::ArchivalConfig;
+use crate::error::{Error, Result};
+use crate::event_log::archive;
+use crate::observability::metrics::LEDGER_ARCHIVED_SEGMENT;
+use crate::settlement::ledger::{Ledger, LedgerSegment};
+use crate::types::ids::MarketId;
+use crate::types::timestamp::Timestamp;
+
+const SEGMENT_KIND: &str = "ledger";
+
+/// Ledger segment rollover and archival export
+///
+/// ## Flow
+/// 1. `roll_if_full()` (under the BalanceManager write lock) seals the open
+///    segment once it reaches `ledger_segment_max_entries`
+/// 2. `export_pending()` (lock released) writes sealed segments to
+///    `archive_dir/{market_id}/ledger_{segment_id}_{last_ms}.json.lz4`;
+///    a failed write keeps the segment queued for the next attempt
+/// 3. `prune()` removes exports older than `retention_days`, never touching
+///    a segment with entries at or after the newest snapshot
+pub struct LedgerArchiver {
+    dir: PathBuf,
+    max_entries: usize,
+    retention: Option<Duration>,  // None = keep forever
+    pending: VecDeque<LedgerSegment>,
+}
+
+impl LedgerArchiver {
+    pub fn new(config: &ArchivalConfig, market_id: MarketId) -> Self {
+        LedgerArchiver {
+            dir: PathBuf::from(&config.archive_dir).join(market_id.to_string()),
+            max_entries: config.ledger_segment_max_entries.max(1),
+            retention: (config.retention_days > 0)
+                .then(|| Duration::from_secs(config.retention_days as u64 * 86400)),
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Seal the open segment if it is full; true when a segment was queued
+    pub fn roll_if_full(&mut self, ledger: &mut Ledger) -> bool {
+        if ledger.len() < self.max_entries {
+            return false;
+        }
+
+        match ledger.roll_segment() {
+            Some(segment) => {
+                tracing::info!(
+                    "Sealed ledger segment {} ({} entries)",
+                    segment.segment_id,
+                    segment.entries.len()
+                );
+                self.pending.push_back(segment);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Sealed segments not yet written to the archive
+    pub fn pending_len(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Write queued segments in order; stops at the first failure
+    pub fn export_pending(&mut self) -> Result<usize> {
+        let mut exported = 0;
+
+        while let Some(segment) = self.pending.front() {
+            let last = segment.last_timestamp().unwrap_or(Timestamp::from_millis(0));
+            let path = self.dir.join(archive::file_name(SEGMENT_KIND, segment.segment_id, last, "json"));
+
+            let data = serde_json::to_vec(segment)
+                .map_err(|e| Error::SerializationError(e.to_string()))?;
+            archive::write_file(&path, &data)?;
+
+            LEDGER_ARCHIVED_SEGMENT.set(segment.segment_id as i64);
+            tracing::info!("Archived ledger segment {} to {:?}", segment.segment_id, path);
+
+            self.pending.pop_front();
+            exported += 1;
+        }
+
+        Ok(exported)
+    }
+
+    /// Remove expired segment exports; None (no snapshot yet) prunes nothing
+    pub fn prune(&self, newest_snapshot: Option<Timestamp>, now: Timestamp) -> Result<usize> {
+        match newest_snapshot {
+            Some(floor) => archive::prune_expired(&self.dir, SEGMENT_KIND, self.retention, floor, now),
+            None => Ok(0),
+        }
+    }
+
+    /// Archived segment exports, oldest first
+    pub fn archived_segments(&self) -> Result<Vec<PathBuf>> {
+        archive::list_files(&self.dir, SEGMENT_KIND)
+    }
+}
+
+/// Read back an archived ledger segment
+pub fn load_segment(path: &Path) -> Result<LedgerSegment> {
+    let data = archive::read_file(path)?;
+    serde_json::from_slice(&data).map_err(|e| Error::DeserializationError(e.to_string()))
+}
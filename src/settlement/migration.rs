@@ -0,0 +1,259 @@
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::sync::oneshot;
+use crate::error::{Error, Result};
+use crate::events::base::{BaseEvent, EventPayload, EventType};
+use crate::events::genesis::{GenesisEvent, GenesisRecord};
+use crate::interfaces::event_producer::EventProducer;
+use crate::matching::order_book::Order;
+use crate::settlement::balance_manager::BalanceManager;
+use crate::settlement::position_manager::PositionManager;
+use crate::types::account::Account;
+use crate::types::ids::MarketId;
+use crate::types::position::Position;
+use crate::types::timestamp::Timestamp;
+
+/// Current account export format version
+/// Bump when the layout of AccountExport changes; readers reject newer versions
+pub const ACCOUNT_EXPORT_VERSION: u32 = 1;
+
+/// Portable dump of all user state for a market
+///
+/// ## Usage
+/// - **Export**: `GET /admin/accounts/export` on the source venue
+///   (`EventProcessor::export_accounts`), or `capture()` then `write_to_file()`
+/// - **Import**: `POST /admin/accounts/import` on the target (or
+///   `read_from_file()`), then `publish_genesis` logs `to_genesis_events()`
+///   to the fresh event log so the new deployment rebuilds state by replay
+///
+/// Serialized as JSON so it can be inspected and diffed during migration drills.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AccountExport {
+    pub format_version: u32,
+    pub exported_at: Timestamp,
+    pub market_id: MarketId,
+    pub sequence: u64,
+    pub accounts: Vec<Account>,
+    pub positions: Vec<Position>,
+    pub open_orders: Vec<Order>,
+    pub checksum: String,
+}
+
+impl AccountExport {
+    /// Capture accounts, non-flat positions and resting orders
+    /// `open_orders` must be in queue priority, so orders re-enter their
+    /// levels with the same priority
+    pub fn capture(
+        market_id: MarketId,
+        sequence: u64,
+        balance_manager: &BalanceManager,
+        position_manager: &PositionManager,
+        open_orders: Vec<Order>,
+    ) -> Self {
+        let mut accounts: Vec<Account> = balance_manager.accounts.values().cloned().collect();
+        accounts.sort_by_key(|a| a.user_id.0);
+
+        let mut positions: Vec<Position> = position_manager.get_all_positions()
+            .into_iter()
+            .filter(|p| !p.is_flat())
+            .cloned()
+            .collect();
+        positions.sort_by_key(|p| p.user_id.0);
+
+
+        let mut export = AccountExport {
+            format_version: ACCOUNT_EXPORT_VERSION,
+            exported_at: Timestamp::now(),
+            market_id,
+            sequence,
+            accounts,
+            positions,
+            open_orders,
+            checksum: String::new(),
+        };
+
+        export.checksum = export.calculate_checksum();
+
+        tracing::info!(
+            "Captured account export: {} accounts, {} positions, {} open orders at seq={}",
+            export.accounts.len(), export.positions.len(), export.open_orders.len(), sequence
+        );
+
+        export
+    }
+
+    fn calculate_checksum(&self) -> String {
+        let mut hasher = Sha256::new();
+
+        hasher.update(self.format_version.to_le_bytes());
+        hasher.update(self.sequence.to_le_bytes());
+
+        for account in &self.accounts {
+            hasher.update(account.user_id.0.as_bytes());
+            hasher.update(account.balance.to_i64().to_le_bytes());
+            hasher.update(account.reserved_margin.to_i64().to_le_bytes());
+        }
+
+        for position in &self.positions {
+            hasher.update(position.user_id.0.as_bytes());
+            hasher.update(position.size.to_le_bytes());
+            hasher.update(position.entry_price.to_i64().to_le_bytes());
+        }
+
+        for order in &self.open_orders {
+            hasher.update(order.order_id.0.as_bytes());
+            hasher.update(order.quantity.to_i64().to_le_bytes());
+            hasher.update(order.filled.to_i64().to_le_bytes());
+        }
+
+        hex::encode(hasher.finalize())
+    }
+
+    pub fn verify_checksum(&self) -> bool {
+        self.checksum == self.calculate_checksum()
+    }
+
+    pub fn write_to_file(&self, path: &Path) -> Result<()> {
+        let data = serde_json::to_vec_pretty(self)
+            .map_err(|e| Error::SerializationError(e.to_string()))?;
+
+        std::fs::write(path, data).map_err(Error::IoError)?;
+
+        tracing::info!("Account export written to {:?}", path);
+        Ok(())
+    }
+
+    /// Read and validate an export (version and checksum)
+    pub fn read_from_file(path: &Path) -> Result<Self> {
+        let data = std::fs::read(path).map_err(Error::IoError)?;
+
+        let export: AccountExport = serde_json::from_slice(&data)
+            .map_err(|e| Error::DeserializationError(e.to_string()))?;
+
+        export.validate().inspect_err(|_| tracing::error!("Invalid account export: {:?}", path))?;
+        Ok(export)
+    }
+
+    /// Check the format version and the checksum
+    pub fn validate(&self) -> Result<()> {
+        if self.format_version > ACCOUNT_EXPORT_VERSION {
+            return Err(Error::UnsupportedExportVersion {
+                version: self.format_version,
+                max_supported: ACCOUNT_EXPORT_VERSION,
+            });
+        }
+
+        if !self.verify_checksum() {
+            return Err(Error::InvalidChecksum);
+        }
+
+        Ok(())
+    }
+
+    /// Genesis events seeding the target deployment
+    /// Ordering: accounts, then positions, then orders (in original queue order)
+    pub fn to_genesis_events(&self) -> Vec<BaseEvent> {
+        let records = self.accounts.iter().cloned().map(GenesisRecord::Account)
+            .chain(self.positions.iter().cloned().map(GenesisRecord::Position))
+            .chain(self.open_orders.iter().cloned().map(GenesisRecord::Order));
+
+        records
+            .map(|record| {
+                let genesis = GenesisEvent {
+                    base: BaseEvent::new(EventType::Genesis, self.market_id),
+                    export_version: self.format_version,
+                    record,
+                };
+
+                let mut event = BaseEvent::with_payload(
+                    EventType::Genesis,
+                    self.market_id,
+                    EventPayload::Genesis(Box::new(genesis)),
+                );
+                event.metadata.source = "migration".to_string();
+                event
+            })
+            .collect()
+    }
+}
+
+/// Export or import request from the admin API, run by the event loop that
+/// owns the processor (so an export is taken between two events)
+pub enum MigrationCommand {
+    Export {
+        reply: oneshot::Sender<Result<AccountExport>>,
+    },
+    Import {
+        export: AccountExport,
+        reply: oneshot::Sender<Result<usize>>,
+    },
+}
+
+/// Log the genesis events of a validated export for `market_id`
+///
+/// Only into a fresh deployment: `last_sequence` is the processor's, and
+/// anything already logged would be mixed with the imported state.
+/// Returns the number of events logged.
+pub async fn publish_genesis<P: EventProducer + ?Sized>(
+    export: &AccountExport,
+    market_id: MarketId,
+    last_sequence: u64,
+    producer: &P,
+) -> Result<usize> {
+    export.validate()?;
+    if export.market_id != market_id {
+        return Err(Error::ExportMarketMismatch { export: export.market_id, market: market_id });
+    }
+    if last_sequence != 0 {
+        return Err(Error::ImportTargetNotEmpty(last_sequence));
+    }
+
+    let events = export.to_genesis_events();
+    let count = events.len();
+    for event in events {
+        producer.produce(event).await?;
+    }
+
+    tracing::warn!(
+        "Imported account export from seq={}: {} genesis events logged",
+        export.sequence, count
+    );
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interfaces::balance_provider::BalanceProvider;
+    use crate::interfaces::memory::InMemoryEventProducer;
+    use crate::types::balance::Balance;
+    use crate::types::ids::UserId;
+
+    fn export(market_id: MarketId) -> AccountExport {
+        let mut balances = BalanceManager::new();
+        let user_id = UserId::new();
+        balances.create_account(user_id).unwrap();
+        balances.adjust_balance(user_id, Balance::from_f64(1_000.0)).unwrap();
+        AccountExport::capture(market_id, 42, &balances, &PositionManager::new_with_market(market_id), Vec::new())
+    }
+
+    #[tokio::test]
+    async fn an_export_is_imported_only_into_an_empty_log_of_its_market() {
+        let market_id = MarketId::new();
+        let producer = InMemoryEventProducer::new();
+
+        let mut tampered = export(market_id);
+        tampered.accounts[0].balance = Balance::from_f64(1_000_000.0);
+        assert!(matches!(publish_genesis(&tampered, market_id, 0, &producer).await, Err(Error::InvalidChecksum)));
+        assert!(matches!(
+            publish_genesis(&export(MarketId::new()), market_id, 0, &producer).await,
+            Err(Error::ExportMarketMismatch { .. })
+        ));
+        assert!(matches!(publish_genesis(&export(market_id), market_id, 7, &producer).await, Err(Error::ImportTargetNotEmpty(7))));
+        assert!(producer.events().is_empty());
+
+        assert_eq!(publish_genesis(&export(market_id), market_id, 0, &producer).await.unwrap(), 1);
+        assert_eq!(producer.events()[0].event_type, EventType::Genesis);
+    }
+}
@@ -1,4 +1,13 @@
 pub mod ledger;
+pub mod ledger_archive;
 pub mod balance_manager;
 pub mod reconciliation;
-pub mod position_manager;
\ No newline at end of file
+pub mod position_manager;
+pub mod migration;
+pub mod daily_statement;
+pub mod repair;
+pub mod settled_trades;
+pub mod in_flight_fills;
+pub mod sub_accounts;
+
+pub mod expiry;
\ No newline at end of file
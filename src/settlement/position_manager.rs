@@ -1,50 +1,245 @@
-use crate::error::Result;
+use crate::config::market::ContractSpec;
+use crate::config::risk::UserLimits;
+use crate::error::{Error, Result};
 use crate::events::order::Side;
+use crate::funding::index::CumulativeFunding;
+use crate::interfaces::position_provider::PositionProvider;
+use crate::interfaces::position_store::PositionStore;
+use crate::types::balance::Balance;
 use crate::types::ids::{MarketId, UserId};
-use crate::types::position::Position;
+use crate::types::position::{MarginMode, Position, PositionMode, PositionSide};
 use crate::types::price::Price;
 use crate::types::quantity::Quantity;
 use std::collections::HashMap;
 
+/// Positions keyed by user and leg
+///
+/// ## Position mode
+/// - One-way (default): a single net position under `PositionSide::Both`
+/// - Hedge: separate `Long` and `Short` legs; buys open / sells close the
+///   long leg, sells open / buys close the short leg
+/// - The mode can only change while the account has no open position
+///
+/// ## Margin mode
+/// - Cross (default): positions share the account balance as collateral
+/// - Isolated: each position carries its own `isolated_margin` bucket
+/// - New positions take the account's margin mode, which likewise can only
+///   change while the account is flat
+///
+/// ## Leverage
+/// Accounts may choose their leverage in this market (up to the risk tier
+/// cap); initial margin is charged at that leverage. Accounts not listed
+/// are margined at the cap.
+///
+/// ## Limits
+/// Operators may override an account's exposure caps (`UserLimits`);
+/// accounts not listed use the caps in `RiskConfig::limits`.
+///
+/// ## Funding
+/// The market's cumulative funding index; new legs start at its current
+/// value, so they owe nothing for intervals settled before they opened.
 pub struct PositionManager {
-    positions: HashMap<UserId, Position>,
+    positions: HashMap<(UserId, PositionSide), Position>,
+    modes: HashMap<UserId, PositionMode>,  // Accounts not listed are one-way
+    margin_modes: HashMap<UserId, MarginMode>,  // Accounts not listed are cross
+    leverage: HashMap<UserId, f64>,
+    limits: HashMap<UserId, UserLimits>,
+    funding: CumulativeFunding,
     market_id: MarketId,
+    contract: ContractSpec,  // Entry averaging and realized PnL follow the contract type
 }
 
 impl PositionManager {
     pub fn new() -> Self {
         PositionManager {
             positions: HashMap::new(),
+            modes: HashMap::new(),
+            margin_modes: HashMap::new(),
+            leverage: HashMap::new(),
+            limits: HashMap::new(),
+            funding: CumulativeFunding::new(),
             market_id: MarketId::from_string("BTC-PERP").expect("REASON"), // Default, should be passed in constructor
+            contract: ContractSpec::default(),
         }
     }
 
     pub fn new_with_market(market_id: MarketId) -> Self {
         PositionManager {
             positions: HashMap::new(),
+            modes: HashMap::new(),
+            margin_modes: HashMap::new(),
+            leverage: HashMap::new(),
+            limits: HashMap::new(),
+            funding: CumulativeFunding::new(),
             market_id,
+            contract: ContractSpec::default(),
         }
     }
 
+    pub fn with_contract(mut self, contract: ContractSpec) -> Self {
+        self.contract = contract;
+        self
+    }
+
+    pub fn position_mode(&self, user_id: &UserId) -> PositionMode {
+        self.modes.get(user_id).copied().unwrap_or_default()
+    }
+
+    /// Switch an account between one-way and hedge mode (flat accounts only)
+    pub fn set_position_mode(&mut self, user_id: UserId, mode: PositionMode) -> Result<()> {
+        if self.position_mode(&user_id) == mode {
+            return Ok(());
+        }
+        if self.positions_for(&user_id).iter().any(|p| !p.is_flat()) {
+            return Err(Error::PositionModeLocked);
+        }
+
+        self.positions.retain(|(owner, _), _| *owner != user_id);
+        match mode {
+            PositionMode::OneWay => self.modes.remove(&user_id),
+            PositionMode::Hedge => self.modes.insert(user_id, mode),
+        };
+        Ok(())
+    }
+
+    pub fn margin_mode(&self, user_id: &UserId) -> MarginMode {
+        self.margin_modes.get(user_id).copied().unwrap_or_default()
+    }
+
+    /// Switch an account between cross and isolated margin (flat accounts only)
+    pub fn set_margin_mode(&mut self, user_id: UserId, mode: MarginMode) -> Result<()> {
+        if self.margin_mode(&user_id) == mode {
+            return Ok(());
+        }
+        if self.positions_for(&user_id).iter().any(|p| !p.is_flat() || p.isolated_margin != Balance::zero()) {
+            return Err(Error::MarginModeLocked);
+        }
+
+        self.positions.retain(|(owner, _), _| *owner != user_id);
+        match mode {
+            MarginMode::Cross => self.margin_modes.remove(&user_id),
+            MarginMode::Isolated => self.margin_modes.insert(user_id, mode),
+        };
+        Ok(())
+    }
+
+    /// Leverage the account chose (None: margined at the tier cap)
+    pub fn leverage(&self, user_id: &UserId) -> Option<f64> {
+        self.leverage.get(user_id).copied()
+    }
+
+    /// Record the account's leverage; the caller checks it against the tier cap
+    pub fn set_leverage(&mut self, user_id: UserId, leverage: Option<f64>) {
+        match leverage {
+            Some(leverage) => self.leverage.insert(user_id, leverage),
+            None => self.leverage.remove(&user_id),
+        };
+    }
+
+    /// Every chosen leverage, in a stable order (for snapshots)
+    pub fn leverage_settings(&self) -> Vec<(UserId, f64)> {
+        let mut settings: Vec<(UserId, f64)> = self.leverage.iter().map(|(user_id, leverage)| (*user_id, *leverage)).collect();
+        settings.sort_by_key(|(user_id, _)| user_id.0);
+        settings
+    }
+
+    /// Operator override of the account's exposure caps
+    pub fn user_limits(&self, user_id: &UserId) -> Option<&UserLimits> {
+        self.limits.get(user_id)
+    }
+
+    /// Set or clear (empty limits) the account's override
+    pub fn set_user_limits(&mut self, user_id: UserId, limits: UserLimits) {
+        if limits.is_empty() {
+            self.limits.remove(&user_id);
+        } else {
+            self.limits.insert(user_id, limits);
+        }
+    }
+
+    /// Every override, in a stable order (for snapshots)
+    pub fn limit_overrides(&self) -> Vec<(UserId, UserLimits)> {
+        let mut overrides: Vec<(UserId, UserLimits)> = self.limits.iter().map(|(user_id, limits)| (*user_id, *limits)).collect();
+        overrides.sort_by_key(|(user_id, _)| user_id.0);
+        overrides
+    }
+
+    pub fn cumulative_funding(&self) -> CumulativeFunding {
+        self.funding
+    }
+
+    pub fn set_cumulative_funding(&mut self, funding: CumulativeFunding) {
+        self.funding = funding;
+    }
+
+    /// Sum of long sizes (equal to the sum of short sizes)
+    pub fn open_interest(&self) -> Quantity {
+        Quantity::from_i64(self.positions.values().filter(|p| p.is_long()).map(|p| p.size).sum())
+    }
+
+    /// One-way (net) position
     pub fn get_position(&self, user_id: &UserId) -> Option<&Position> {
-        self.positions.get(user_id)
+        self.positions.get(&(*user_id, PositionSide::Both))
     }
 
     pub fn get_position_mut(&mut self, user_id: &UserId) -> Option<&mut Position> {
-        self.positions.get_mut(user_id)
+        self.positions.get_mut(&(*user_id, PositionSide::Both))
+    }
+
+    pub fn get_leg(&self, user_id: &UserId, leg: PositionSide) -> Option<&Position> {
+        self.positions.get(&(*user_id, leg))
+    }
+
+    pub fn get_leg_mut(&mut self, user_id: &UserId, leg: PositionSide) -> Option<&mut Position> {
+        self.positions.get_mut(&(*user_id, leg))
+    }
+
+    /// Every leg the user holds (one position in one-way mode)
+    pub fn positions_for(&self, user_id: &UserId) -> Vec<&Position> {
+        [PositionSide::Both, PositionSide::Long, PositionSide::Short].iter()
+            .filter_map(|leg| self.positions.get(&(*user_id, *leg)))
+            .collect()
+    }
+
+    pub fn positions_for_mut(&mut self, user_id: &UserId) -> Vec<&mut Position> {
+        self.positions.iter_mut()
+            .filter(|((owner, _), _)| owner == user_id)
+            .map(|(_, position)| position)
+            .collect()
     }
 
     pub fn get_or_create_position(&mut self, user_id: UserId) -> &mut Position {
+        self.get_or_create_leg(user_id, PositionSide::Both)
+    }
+
+    pub fn get_or_create_leg(&mut self, user_id: UserId, leg: PositionSide) -> &mut Position {
         let market_id = self.market_id;
-        self.positions.entry(user_id).or_insert_with(|| Position::new(user_id, market_id))
+        let margin_mode = self.margin_mode(&user_id);
+        let funding_index = self.funding.index;
+        self.positions.entry((user_id, leg)).or_insert_with(|| Position {
+            margin_mode,
+            funding_index,
+            ..Position::new_leg(user_id, market_id, leg)
+        })
     }
 
+    /// Store a position under its own leg
     pub fn set_position(&mut self, user_id: UserId, position: Position) {
-        self.positions.insert(user_id, position);
+        if position.position_side.is_hedge_leg() {
+            self.modes.insert(user_id, PositionMode::Hedge);
+        }
+        if position.is_isolated() {
+            self.margin_modes.insert(user_id, MarginMode::Isolated);
+        }
+        self.positions.insert((user_id, position.position_side), position);
     }
 
     pub fn remove_position(&mut self, user_id: &UserId) -> Option<Position> {
-        self.positions.remove(user_id)
+        self.positions.remove(&(*user_id, PositionSide::Both))
+    }
+
+    pub fn remove_leg(&mut self, user_id: &UserId, leg: PositionSide) -> Option<Position> {
+        self.positions.remove(&(*user_id, leg))
     }
 
     pub fn update_position(
@@ -53,13 +248,37 @@ impl PositionManager {
         trade_side: Side,
         trade_quantity: Quantity,
         trade_price: Price,
-    ) -> Result<()> {
-        let position = self.get_or_create_position(user_id);
+    ) -> Result<Balance> {
+        self.update_leg(user_id, PositionSide::Both, trade_side, trade_quantity, trade_price)
+    }
 
-        use crate::risk::pnl::PnLCalculator;
-        PnLCalculator::update_position(position, trade_side, trade_quantity, trade_price);
+    /// Apply a fill to one leg; hedge legs may be closed but never flipped
+    /// Returns the PnL realized by the closing part of the fill, which the
+    /// caller settles to the account
+    pub fn update_leg(
+        &mut self,
+        user_id: UserId,
+        leg: PositionSide,
+        trade_side: Side,
+        trade_quantity: Quantity,
+        trade_price: Price,
+    ) -> Result<Balance> {
+        let hedged = self.position_mode(&user_id) == PositionMode::Hedge;
+        if hedged != leg.is_hedge_leg() {
+            return Err(Error::PositionSideMismatch);
+        }
+        if leg.closes(trade_side) {
+            let open = self.get_leg(&user_id, leg).map_or(Quantity::zero(), |p| p.abs_size());
+            if trade_quantity > open {
+                return Err(Error::ReduceOnlyViolation);
+            }
+        }
 
-        Ok(())
+        let contract = self.contract.clone();
+        let position = self.get_or_create_leg(user_id, leg);
+
+        use crate::risk::pnl::PnLCalculator;
+        PnLCalculator::update_position_for(&contract, position, trade_side, trade_quantity, trade_price)
     }
 
     pub fn get_all_positions(&self) -> Vec<&Position> {
@@ -69,4 +288,107 @@ impl PositionManager {
     pub fn get_all_positions_mut(&mut self) -> Vec<&mut Position> {
         self.positions.values_mut().collect()
     }
-}
\ No newline at end of file
+}
+
+impl PositionProvider for PositionManager {
+    fn position_size(&self, user_id: UserId) -> i64 {
+        self.positions_for(&user_id).iter().map(|p| p.size).sum()
+    }
+
+    fn leg_size(&self, user_id: UserId, leg: PositionSide) -> i64 {
+        self.get_leg(&user_id, leg).map_or(0, |p| p.size)
+    }
+}
+
+impl PositionStore for PositionManager {
+    fn position_mode(&self, user_id: &UserId) -> PositionMode {
+        PositionManager::position_mode(self, user_id)
+    }
+
+    fn set_position_mode(&mut self, user_id: UserId, mode: PositionMode) -> Result<()> {
+        PositionManager::set_position_mode(self, user_id, mode)
+    }
+
+    fn margin_mode(&self, user_id: &UserId) -> MarginMode {
+        PositionManager::margin_mode(self, user_id)
+    }
+
+    fn set_margin_mode(&mut self, user_id: UserId, mode: MarginMode) -> Result<()> {
+        PositionManager::set_margin_mode(self, user_id, mode)
+    }
+
+    fn leverage(&self, user_id: &UserId) -> Option<f64> {
+        PositionManager::leverage(self, user_id)
+    }
+
+    fn set_leverage(&mut self, user_id: UserId, leverage: Option<f64>) {
+        PositionManager::set_leverage(self, user_id, leverage)
+    }
+
+    fn user_limits(&self, user_id: &UserId) -> Option<&UserLimits> {
+        PositionManager::user_limits(self, user_id)
+    }
+
+    fn set_user_limits(&mut self, user_id: UserId, limits: UserLimits) {
+        PositionManager::set_user_limits(self, user_id, limits)
+    }
+
+    fn open_interest(&self) -> Quantity {
+        PositionManager::open_interest(self)
+    }
+
+    fn cumulative_funding(&self) -> CumulativeFunding {
+        PositionManager::cumulative_funding(self)
+    }
+
+    fn set_cumulative_funding(&mut self, funding: CumulativeFunding) {
+        PositionManager::set_cumulative_funding(self, funding)
+    }
+
+    fn get_position(&self, user_id: &UserId) -> Option<&Position> {
+        PositionManager::get_position(self, user_id)
+    }
+
+    fn get_leg(&self, user_id: &UserId, leg: PositionSide) -> Option<&Position> {
+        PositionManager::get_leg(self, user_id, leg)
+    }
+
+    fn get_leg_mut(&mut self, user_id: &UserId, leg: PositionSide) -> Option<&mut Position> {
+        PositionManager::get_leg_mut(self, user_id, leg)
+    }
+
+    fn positions_for(&self, user_id: &UserId) -> Vec<&Position> {
+        PositionManager::positions_for(self, user_id)
+    }
+
+    fn positions_for_mut(&mut self, user_id: &UserId) -> Vec<&mut Position> {
+        PositionManager::positions_for_mut(self, user_id)
+    }
+
+    fn get_all_positions(&self) -> Vec<&Position> {
+        PositionManager::get_all_positions(self)
+    }
+
+    fn set_position(&mut self, user_id: UserId, position: Position) {
+        PositionManager::set_position(self, user_id, position)
+    }
+
+    fn remove_position(&mut self, user_id: &UserId) -> Option<Position> {
+        PositionManager::remove_position(self, user_id)
+    }
+
+    fn remove_leg(&mut self, user_id: &UserId, leg: PositionSide) -> Option<Position> {
+        PositionManager::remove_leg(self, user_id, leg)
+    }
+
+    fn update_leg(
+        &mut self,
+        user_id: UserId,
+        leg: PositionSide,
+        trade_side: Side,
+        trade_quantity: Quantity,
+        trade_price: Price,
+    ) -> Result<Balance> {
+        PositionManager::update_leg(self, user_id, leg, trade_side, trade_quantity, trade_price)
+    }
+}
@@ -28,13 +28,8 @@ impl Reconciliation {
     ) -> Result<()> {
         let account = balance_manager.get_account(user_id)?;
 
-        // Calculate balance from ledger
-        let ledger_balance = balance_manager.ledger.get_entries_for_account(account.account_id)
-            .iter()
-            .map(|e| e.amount.to_i64())
-            .sum::<i64>();
-
-        let expected = Balance::from_i64(ledger_balance);
+        // Calculate balance from ledger (archived segments are carried forward)
+        let expected = balance_manager.ledger.account_total(account.account_id);
 
         if account.balance != expected {
             return Err(Error::ReconciliationFailed {
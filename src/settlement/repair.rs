@@ -0,0 +1,109 @@
+use serde::Serialize;
+use crate::error::{Error, Result};
+use crate::event_log::snapshot::Snapshot;
+use crate::events::base::{BaseEvent, EventPayload, EventType};
+use crate::events::repair::StateRepair;
+use crate::types::account::Account;
+use crate::types::balance::Balance;
+use crate::types::ids::{AccountId, OperatorId, UserId};
+use crate::types::position::Position;
+
+/// Which parts of an account to take from the snapshot
+#[derive(Clone, Copy, Debug, Default, serde::Deserialize)]
+pub struct RepairScope {
+    pub balance: bool,
+    pub position: bool,
+}
+
+/// Reviewable diff between current state and the snapshot, plus the event
+/// that would apply it
+#[derive(Clone, Debug, Serialize)]
+pub struct RepairPlan {
+    pub user_id: UserId,
+    pub snapshot_sequence: u64,
+    pub current_balance: Option<Balance>,
+    pub target_balance: Option<Balance>,
+    pub current_position_size: i64,
+    pub target_position_size: Option<i64>,
+    #[serde(skip)]
+    pub repair: StateRepair,
+}
+
+impl RepairPlan {
+    pub fn balance_delta(&self) -> Balance {
+        match (self.target_balance, self.current_balance) {
+            (Some(target), Some(current)) => target - current,
+            (Some(target), None) => target,
+            _ => Balance::zero(),
+        }
+    }
+
+    pub fn into_event(self) -> BaseEvent {
+        let base = self.repair.base.clone();
+        BaseEvent {
+            payload: EventPayload::StateRepair(Box::new(self.repair)),
+            ..base
+        }
+    }
+}
+
+/// Selective state repair - restores one account from a snapshot
+///
+/// ## Usage
+/// - `plan_account_repair()` builds a `RepairPlan` (dry run, nothing applied)
+/// - Publishing `into_event()` sends a `StateRepair` event through the log;
+///   the EventProcessor applies it as a compensating change: the balance
+///   delta is countered on the repair suspense account, the position delta
+///   is taken by the insurance fund's inventory, and reserved margin is
+///   re-netted against the repaired position
+///
+/// Unlike `restore_from_snapshot`, all other accounts, positions and orders
+/// are left as they are.
+pub fn plan_account_repair(
+    snapshot: &Snapshot,
+    user_id: UserId,
+    scope: RepairScope,
+    current_account: Option<&Account>,
+    current_position: Option<&Position>,
+    operator_id: OperatorId,
+    reason: String,
+) -> Result<RepairPlan> {
+    if !crate::utils::helper::is_authorized_operator(operator_id) {
+        return Err(Error::Unauthorized);
+    }
+
+    let snapshot_account = snapshot.accounts.iter().find(|a| a.user_id == user_id);
+    let snapshot_position = snapshot.positions.iter().find(|p| p.user_id == user_id);
+
+    if snapshot_account.is_none() && current_account.is_none() {
+        return Err(Error::AccountNotFound(AccountId::from_user(user_id)));
+    }
+
+    // An account absent from the snapshot did not exist then: repair to zero / flat
+    let target_balance = scope.balance
+        .then(|| snapshot_account.map_or(Balance::zero(), |a| a.balance));
+    let target_position = scope.position.then(|| {
+        snapshot_position.cloned()
+            .unwrap_or_else(|| Position::new(user_id, snapshot.market_id))
+    });
+
+    let repair = StateRepair {
+        base: BaseEvent::new(EventType::StateRepair, snapshot.market_id),
+        user_id,
+        snapshot_sequence: snapshot.sequence,
+        operator_id,
+        reason,
+        target_balance,
+        target_position,
+    };
+
+    Ok(RepairPlan {
+        user_id,
+        snapshot_sequence: snapshot.sequence,
+        current_balance: current_account.map(|a| a.balance),
+        target_balance,
+        current_position_size: current_position.map_or(0, |p| p.size),
+        target_position_size: repair.target_position.as_ref().map(|p| p.size),
+        repair,
+    })
+}
@@ -0,0 +1,76 @@
+use std::collections::{HashSet, VecDeque};
+use crate::types::ids::TradeId;
+
+/// Trade ids already settled by the EventProcessor
+///
+/// ## Single settlement point
+/// - Positions and fees move only when the logged `Trade` event is processed
+/// - The matching path emits the event and never settles itself
+///
+/// ## Idempotency
+/// - A redelivered `Trade` event (consumer retry, overlapping replay) is skipped
+/// - Bounded: the oldest ids are forgotten once `capacity` is reached, which
+///   only has to cover the redelivery window, not the whole log
+/// - Carried in snapshots, so events replayed over a restored snapshot are
+///   recognised too
+pub struct SettledTrades {
+    ids: HashSet<TradeId>,
+    order: VecDeque<TradeId>,
+    capacity: usize,
+}
+
+impl SettledTrades {
+    pub fn new(capacity: usize) -> Self {
+        SettledTrades {
+            ids: HashSet::with_capacity(capacity),
+            order: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    /// Record a trade as settled; false if it already was
+    pub fn insert(&mut self, trade_id: TradeId) -> bool {
+        if !self.ids.insert(trade_id) {
+            return false;
+        }
+
+        self.order.push_back(trade_id);
+        while self.order.len() > self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.ids.remove(&oldest);
+            }
+        }
+
+        true
+    }
+
+    pub fn contains(&self, trade_id: &TradeId) -> bool {
+        self.ids.contains(trade_id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.ids.clear();
+        self.order.clear();
+    }
+
+    /// Remembered ids, oldest first (for snapshots)
+    pub fn ids(&self) -> Vec<TradeId> {
+        self.order.iter().copied().collect()
+    }
+
+    /// Replace the remembered ids with a snapshot's
+    pub fn restore(&mut self, ids: &[TradeId]) {
+        self.clear();
+        for &trade_id in ids {
+            self.insert(trade_id);
+        }
+    }
+}
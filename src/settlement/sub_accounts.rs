@@ -0,0 +1,57 @@
+use crate::error::{Error, Result};
+use crate::types::account::Account;
+use crate::types::ids::UserId;
+
+/// Most sub-accounts one master account can open
+pub const MAX_SUB_ACCOUNTS: usize = 20;
+/// Longest sub-account label, in characters
+pub const MAX_LABEL_LEN: usize = 32;
+
+/// Sub-accounts are ordinary accounts (own balance, positions, margin and
+/// API keys) linked to the master that opened them
+///
+/// ## Rules
+/// - Only a master (an account that is not itself a sub-account) opens
+///   sub-accounts, at most `MAX_SUB_ACCOUNTS`, each with a label unique
+///   among its siblings
+/// - Funds move within a family only: between the master and a sub-account,
+///   or between two sub-accounts of the same master, always on the master's
+///   request
+pub fn validate_new_sub_account(master: &Account, siblings: &[&Account], label: &str) -> Result<()> {
+    if master.is_sub_account() {
+        return Err(Error::InvalidSubAccount("sub-accounts cannot open sub-accounts".to_string()));
+    }
+    if master.user_id.is_system() || master.user_id == *crate::LIQUIDATION_ENGINE_USER_ID {
+        return Err(Error::InvalidSubAccount("system accounts cannot open sub-accounts".to_string()));
+    }
+    validate_label(label)?;
+    if siblings.len() >= MAX_SUB_ACCOUNTS {
+        return Err(Error::InvalidSubAccount(format!("at most {} sub-accounts per master", MAX_SUB_ACCOUNTS)));
+    }
+    if siblings.iter().any(|sibling| sibling.label.as_deref() == Some(label)) {
+        return Err(Error::InvalidSubAccount(format!("label {:?} already used", label)));
+    }
+    Ok(())
+}
+
+/// Non-empty, at most `MAX_LABEL_LEN` characters of letters, digits, `-` and `_`
+pub fn validate_label(label: &str) -> Result<()> {
+    let valid = !label.is_empty()
+        && label.chars().count() <= MAX_LABEL_LEN
+        && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid {
+        return Err(Error::InvalidSubAccount(format!(
+            "label must be 1-{} letters, digits, '-' or '_'", MAX_LABEL_LEN
+        )));
+    }
+    Ok(())
+}
+
+/// `account` is `master` itself or one of its sub-accounts
+pub fn check_family(master_id: UserId, account: &Account) -> Result<()> {
+    if account.user_id == master_id || account.master_id == Some(master_id) {
+        Ok(())
+    } else {
+        Err(Error::NotInSubAccountFamily { master: master_id, account: account.user_id })
+    }
+}
@@ -3,7 +3,8 @@ use crate::types::balance::Balance;
 use crate::types::ids::{AccountId, UserId};
 use crate::types::timestamp::Timestamp;
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
 pub struct Account {
     pub account_id: AccountId,
     pub user_id: UserId,
@@ -13,6 +14,10 @@ pub struct Account {
     pub unrealized_pnl: Balance,
     pub created_at: Timestamp,
     pub updated_at: Timestamp,
+    #[serde(default)]
+    pub master_id: Option<UserId>,  // Set on sub-accounts: the master account that opened it
+    #[serde(default)]
+    pub label: Option<String>,      // Sub-account name, unique per master
 }
 
 impl Account {
@@ -27,9 +32,15 @@ impl Account {
             unrealized_pnl: Balance::zero(),  // FIX IGD-S-001
             created_at: now,
             updated_at: now,
+            master_id: None,
+            label: None,
         }
     }
 
+    pub fn is_sub_account(&self) -> bool {
+        self.master_id.is_some()
+    }
+
     pub fn available_balance(&self) -> Balance {
         self.balance - self.reserved_margin
     }
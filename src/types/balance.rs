@@ -1,8 +1,10 @@
 use serde::{Deserialize, Serialize};
+use crate::error::{Error, Result};
 use std::ops::{Add, Sub, Mul, Div, Neg};
 use std::fmt;
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
 pub struct Balance(i64);  // Signed balance in base units
 
 impl Balance {
@@ -31,6 +33,28 @@ impl Balance {
     pub fn abs(&self) -> Self {
         Balance(self.0.abs())
     }
+
+    /// Narrow an i128 intermediate (e.g. size * price) back to a balance
+    pub fn try_from_i128(raw: i128, operation: &str) -> Result<Self> {
+        i64::try_from(raw).map(Balance).map_err(|_| overflow(operation))
+    }
+
+    /// Narrow an i128 intermediate, clamping at the i64 range
+    pub fn saturating_from_i128(raw: i128) -> Self {
+        Balance(raw.clamp(i64::MIN as i128, i64::MAX as i128) as i64)
+    }
+
+    pub fn checked_add(self, other: Balance) -> Result<Balance> {
+        self.0.checked_add(other.0).map(Balance).ok_or_else(|| overflow("balance addition"))
+    }
+
+    pub fn checked_sub(self, other: Balance) -> Result<Balance> {
+        self.0.checked_sub(other.0).map(Balance).ok_or_else(|| overflow("balance subtraction"))
+    }
+}
+
+fn overflow(operation: &str) -> Error {
+    Error::Overflow { operation: operation.to_string() }
 }
 
 impl Add for Balance {
@@ -47,17 +71,21 @@ impl Sub for Balance {
     }
 }
 
+/// Fixed-point product (e.g. notional * rate), computed in i128 and
+/// saturating at the i64 range
 impl Mul<Balance> for Balance {
     type Output = Balance;
     fn mul(self, other: Balance) -> Balance {
-        Balance(self.0 * other.0)
+        Balance::saturating_from_i128(self.0 as i128 * other.0 as i128 / Self::MULTIPLIER as i128)
     }
 }
 
+/// Fixed-point quotient (e.g. notional / leverage), computed in i128 and
+/// saturating at the i64 range
 impl Div<Balance> for Balance {
     type Output = Balance;
     fn div(self, other: Balance) -> Balance {
-        Balance(self.0 / other.0)
+        Balance::saturating_from_i128(self.0 as i128 * Self::MULTIPLIER as i128 / other.0 as i128)
     }
 }
 
@@ -1,13 +1,14 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
 pub struct FundingRate {
     value: i64,  // Rate * 10^8
 }
 
 impl FundingRate {
     const DECIMALS: u32 = 10;
-    const MULTIPLIER: i64 = 10_000_000_000;
+    pub const MULTIPLIER: i64 = 10_000_000_000;
 
     pub fn from_i64(value: i64) -> Self {
         FundingRate { value }
@@ -5,6 +5,8 @@ use std::fmt;
 macro_rules! define_id_type {
     ($name:ident) => {
         #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+        #[derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+        #[archive(check_bytes)]
         pub struct $name(pub Uuid);
 
         impl $name {
@@ -30,11 +32,47 @@ define_id_type!(EntryId);
 define_id_type!(EventId);
 define_id_type!(OperatorId);
 define_id_type!(AccountId);
+define_id_type!(TenantId);
 
 impl UserId {
     pub fn from_string(s: &str) -> Result<Self, uuid::Error> {
         Ok(UserId(Uuid::parse_str(s)?))
     }
+
+    /// System account absorbing funding rounding residue
+    pub fn funding_residual() -> Self {
+        UserId(Uuid::from_u128(0xF0))
+    }
+
+    /// System account holding liquidation penalties paid into the insurance fund
+    pub fn insurance_fund() -> Self {
+        UserId(Uuid::from_u128(0xF1))
+    }
+
+    /// System account collecting trading fees and paying maker rebates
+    pub fn fee_revenue() -> Self {
+        UserId(Uuid::from_u128(0xF2))
+    }
+
+    /// System account taking the other side of operator balance repairs
+    pub fn repair_suspense() -> Self {
+        UserId(Uuid::from_u128(0xF3))
+    }
+
+    /// Accounts the engine owns rather than a trader
+    pub fn is_system(&self) -> bool {
+        *self == UserId::funding_residual()
+            || *self == UserId::insurance_fund()
+            || *self == UserId::fee_revenue()
+            || *self == UserId::repair_suspense()
+            || *self == *crate::LIQUIDATION_ENGINE_USER_ID
+    }
+}
+
+impl OrderId {
+    pub fn from_string(s: &str) -> Result<Self, uuid::Error> {
+        Ok(OrderId(Uuid::parse_str(s)?))
+    }
 }
 
 impl MarketId {
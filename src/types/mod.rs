@@ -6,4 +6,5 @@ pub mod ratio;
 pub mod ids;
 pub mod position;
 pub mod funding_rate;
-pub mod account;
\ No newline at end of file
+pub mod account;
+pub mod rounding;
\ No newline at end of file
@@ -1,12 +1,64 @@
 use serde::{Deserialize, Serialize};
 use crate::types::*;
+use crate::events::order::Side;
 use crate::types::balance::Balance;
 use crate::types::ids::{MarketId, UserId};
 use crate::types::price::Price;
 use crate::types::quantity::Quantity;
 use crate::types::timestamp::Timestamp;
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+/// How an account holds positions in a market
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
+#[serde(rename_all = "snake_case")]
+pub enum PositionMode {
+    /// One net position; buys and sells offset each other
+    #[default]
+    OneWay,
+    /// Separate long and short legs held at the same time
+    Hedge,
+}
+
+/// How a position is collateralised
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
+#[serde(rename_all = "snake_case")]
+pub enum MarginMode {
+    /// Backed by the whole account balance, shared with every cross position
+    #[default]
+    Cross,
+    /// Backed only by its own `isolated_margin`; a loss beyond it never
+    /// touches the rest of the account
+    Isolated,
+}
+
+/// Position leg an order or position belongs to
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
+#[serde(rename_all = "snake_case")]
+pub enum PositionSide {
+    /// Net position (one-way mode)
+    #[default]
+    Both,
+    /// Long leg (hedge mode): buys open, sells close
+    Long,
+    /// Short leg (hedge mode): sells open, buys close
+    Short,
+}
+
+impl PositionSide {
+    /// True if an order on `side` against this leg can only close it
+    pub fn closes(&self, side: Side) -> bool {
+        matches!((self, side), (PositionSide::Long, Side::Sell) | (PositionSide::Short, Side::Buy))
+    }
+
+    pub fn is_hedge_leg(&self) -> bool {
+        *self != PositionSide::Both
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
 pub struct Position {
     pub user_id: UserId,
     pub market_id: MarketId,
@@ -14,6 +66,14 @@ pub struct Position {
     pub entry_price: Price,
     pub realized_pnl: Balance,
     pub last_funding_timestamp: Timestamp,
+    #[serde(default)]
+    pub position_side: PositionSide,  // Long/Short legs are never negative/positive respectively
+    #[serde(default)]
+    pub margin_mode: MarginMode,
+    #[serde(default = "Balance::zero")]
+    pub isolated_margin: Balance,  // Collateral bucket, moved out of the account's reserved margin (isolated only)
+    #[serde(default = "Balance::zero")]
+    pub funding_index: Balance,  // Market's cumulative funding index when this position last settled funding
 }
 
 impl Position {
@@ -25,9 +85,18 @@ impl Position {
             entry_price: Price::zero(),
             realized_pnl: Balance::zero(),
             last_funding_timestamp: Timestamp::now(),
+            position_side: PositionSide::Both,
+            margin_mode: MarginMode::Cross,
+            isolated_margin: Balance::zero(),
+            funding_index: Balance::zero(),
         }
     }
 
+    /// Empty hedge-mode leg
+    pub fn new_leg(user_id: UserId, market_id: MarketId, position_side: PositionSide) -> Self {
+        Position { position_side, ..Position::new(user_id, market_id) }
+    }
+
     pub fn is_long(&self) -> bool {
         self.size > 0
     }
@@ -40,6 +109,10 @@ impl Position {
         self.size == 0
     }
 
+    pub fn is_isolated(&self) -> bool {
+        self.margin_mode == MarginMode::Isolated
+    }
+
     pub fn abs_size(&self) -> Quantity {
         Quantity::from_i64(self.size.abs())
     }
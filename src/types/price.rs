@@ -1,8 +1,10 @@
 use serde::{Deserialize, Serialize};
+use crate::error::{Error, Result};
 use std::ops::{Add, Sub, Mul, Div};
 use std::fmt;
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
 pub struct Price(i64);  // Fixed-point with 8 decimal places
 
 impl Price {
@@ -35,6 +37,19 @@ impl Price {
     pub fn abs(&self) -> Self {
         Price(self.0.abs())
     }
+
+    /// Narrow an i128 intermediate (e.g. an averaged entry) back to a price
+    pub fn try_from_i128(raw: i128, operation: &str) -> Result<Self> {
+        i64::try_from(raw).map(Price).map_err(|_| Error::Overflow { operation: operation.to_string() })
+    }
+
+    pub fn checked_add(self, other: Price) -> Result<Price> {
+        Self::try_from_i128(self.0 as i128 + other.0 as i128, "price addition")
+    }
+
+    pub fn checked_sub(self, other: Price) -> Result<Price> {
+        Self::try_from_i128(self.0 as i128 - other.0 as i128, "price subtraction")
+    }
 }
 
 impl Add for Price {
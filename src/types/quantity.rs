@@ -1,3 +1,4 @@
+use crate::error::{Error, Result};
 use crate::types::balance::Balance;
 use crate::types::price::Price;
 use serde::{Deserialize, Serialize};
@@ -5,7 +6,8 @@ use std::fmt;
 use std::iter::Sum;
 use std::ops::{Add, Mul, Sub};
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
 pub struct Quantity(i64);  // Base units
 
 impl Quantity {
@@ -42,6 +44,23 @@ impl Quantity {
     pub fn min(self, other: Self) -> Self {
         Quantity(self.0.min(other.0))
     }
+
+    pub fn checked_add(self, other: Quantity) -> Result<Quantity> {
+        self.0.checked_add(other.0).map(Quantity).ok_or_else(|| overflow("quantity addition"))
+    }
+
+    pub fn checked_sub(self, other: Quantity) -> Result<Quantity> {
+        self.0.checked_sub(other.0).map(Quantity).ok_or_else(|| overflow("quantity subtraction"))
+    }
+
+    /// Raw quantity * price, computed in i128
+    pub fn checked_mul(self, price: Price) -> Result<Balance> {
+        Balance::try_from_i128(self.0 as i128 * price.to_i64() as i128, "quantity * price")
+    }
+}
+
+fn overflow(operation: &str) -> Error {
+    Error::Overflow { operation: operation.to_string() }
 }
 
 impl Add for Quantity {
@@ -58,10 +77,12 @@ impl Sub for Quantity {
     }
 }
 
+/// Saturates at the i64 range (display figures such as depth notional);
+/// use `checked_mul` for anything that moves value
 impl Mul<Price> for Quantity {
     type Output = Balance;
     fn mul(self, price: Price) -> Balance {
-        Balance::from_i64(self.0 * price.to_i64())
+        Balance::saturating_from_i128(self.0 as i128 * price.to_i64() as i128)
     }
 }
 
@@ -2,7 +2,8 @@ use serde::{Deserialize, Serialize};
 use std::ops::{Add, Sub, Mul, Div};
 use std::cmp::Ordering;
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
 pub struct Ratio {
     value: i64,  // Ratio * 10^8
 }
@@ -0,0 +1,73 @@
+use serde::{Deserialize, Serialize};
+use crate::error::Result;
+use crate::types::balance::Balance;
+
+/// Decimal places carried by Balance's fixed-point representation
+const BALANCE_DECIMALS: u32 = 8;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RoundingMode {
+    Floor,     // Toward negative infinity
+    Ceil,      // Toward positive infinity
+    HalfEven,  // Banker's rounding
+    Truncate,  // Toward zero
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RoundingPolicy {
+    pub mode: RoundingMode,
+    pub decimals: u32,  // Decimal places kept in the result (max 8)
+}
+
+impl RoundingPolicy {
+    pub fn new(mode: RoundingMode, decimals: u32) -> Self {
+        RoundingPolicy { mode, decimals }
+    }
+
+    /// Divide in fixed point and round the quotient to `decimals` places
+    /// Integer-only: no f64 is involved, so results are deterministic across replays
+    /// Fails with `Error::Overflow` if the rounded quotient does not fit a balance
+    pub fn div(&self, numerator: i128, denominator: i128) -> Result<Balance> {
+        let quantum = 10i128.pow(BALANCE_DECIMALS.saturating_sub(self.decimals));
+        let divisor = denominator * quantum;
+
+        let quotient = numerator / divisor;  // Truncates toward zero
+        let remainder = numerator % divisor;
+
+        let units = if remainder == 0 {
+            quotient
+        } else {
+            let negative = (numerator < 0) != (divisor < 0);
+            let away_from_zero = if negative { quotient - 1 } else { quotient + 1 };
+
+            match self.mode {
+                RoundingMode::Truncate => quotient,
+                RoundingMode::Floor => if negative { away_from_zero } else { quotient },
+                RoundingMode::Ceil => if negative { quotient } else { away_from_zero },
+                RoundingMode::HalfEven => {
+                    match (remainder.abs() * 2).cmp(&divisor.abs()) {
+                        std::cmp::Ordering::Greater => away_from_zero,
+                        std::cmp::Ordering::Less => quotient,
+                        std::cmp::Ordering::Equal => {
+                            if quotient % 2 == 0 { quotient } else { away_from_zero }
+                        }
+                    }
+                }
+            }
+        };
+
+        Balance::try_from_i128(units * quantum, "rounded division")
+    }
+
+    /// Round an existing balance to `decimals` places
+    pub fn round(&self, value: Balance) -> Result<Balance> {
+        self.div(value.to_i64() as i128, 1)
+    }
+}
+
+impl Default for RoundingPolicy {
+    fn default() -> Self {
+        RoundingPolicy::new(RoundingMode::HalfEven, BALANCE_DECIMALS)
+    }
+}
@@ -4,6 +4,8 @@ use std::sync::atomic::{Ordering};
 use std::sync::{RwLock};
 use uuid::Uuid;
 use crate::types::ids::{EntryId, EventId, LiquidationId, OperatorId, OrderId, TradeId};
+use crate::types::quantity::Quantity;
+use sha2::{Digest, Sha256};
 
 // Global state for engine control
 lazy_static::lazy_static! {
@@ -19,9 +21,18 @@ pub fn current_timestamp_ms() -> u64 {
         .as_millis() as u64
 }
 
-/// Generate a new trade ID
-pub fn generate_trade_id() -> TradeId {
-    TradeId(Uuid::new_v4())
+/// Trade ID of a taker's fill, derived from the taker order and how much of
+/// it had traded before the fill
+/// Replaying the same match yields the same id, so a trade that was already
+/// settled is recognised instead of settled again
+pub fn trade_id_for(taker_order_id: OrderId, traded_before: Quantity) -> TradeId {
+    let digest = Sha256::new()
+        .chain_update(taker_order_id.0.as_bytes())
+        .chain_update(traded_before.to_i64().to_le_bytes())
+        .finalize();
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&digest[..16]);
+    TradeId(Uuid::from_bytes(bytes))
 }
 
 /// Generate a new order ID
@@ -1,2 +1,2 @@
 pub mod helper;
-mod task_supervisor;
\ No newline at end of file
+pub mod task_supervisor;
\ No newline at end of file